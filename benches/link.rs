@@ -0,0 +1,349 @@
+//! Benchmarks for the linker phases using synthetic workloads.
+//!
+//! Each workload links a number of objects with several code sections which
+//! call functions defined in the other objects along with a link library
+//! containing a member for each library function. The time for each phase is
+//! taken from the link report so that the phases can be measured separately.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use boflink::{
+    linker::{LinkPhase, LinkerBuilder, LinkerTargetArch},
+    pathed_item::PathedItem,
+};
+use boflink_testutils::archive_searcher::MemoryArchiveSearcher;
+use coffyaml::{
+    archive::{ArchiveYaml, ArchiveYamlMember},
+    coff::{CoffYaml, CoffYamlHeader, CoffYamlSection, CoffYamlSectionRelocation, CoffYamlSymbol},
+};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use object::pe::{
+    IMAGE_FILE_MACHINE_AMD64, IMAGE_REL_AMD64_REL32, IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE,
+    IMAGE_SCN_MEM_READ, IMAGE_SYM_CLASS_EXTERNAL, IMAGE_SYM_CLASS_LABEL, IMAGE_SYM_UNDEFINED,
+};
+
+/// Name of the link library in the workloads.
+const LIBRARY_NAME: &str = "bench";
+
+/// Size of a call instruction with a 32-bit relative displacement.
+const CALL_SIZE: usize = 5;
+
+/// Parameters for generating a synthetic link.
+#[derive(Debug, Clone, Copy)]
+struct Workload {
+    /// Number of input objects.
+    objects: usize,
+
+    /// Number of code sections in each object.
+    sections: usize,
+
+    /// Number of relocations in each section.
+    relocations: usize,
+
+    /// Number of members in the link library.
+    library_members: usize,
+
+    /// Number of static labels in each section. The labels have the same
+    /// names in every object like the `$LN` labels from MSVC.
+    labels: usize,
+}
+
+impl std::fmt::Display for Workload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}x{}x{}+{}",
+            self.objects, self.sections, self.relocations, self.library_members
+        )?;
+
+        if self.labels > 0 {
+            write!(f, "/{}", self.labels)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The built inputs and link library for a workload.
+struct WorkloadFiles {
+    inputs: Vec<PathedItem<PathBuf, Vec<u8>>>,
+    searcher: MemoryArchiveSearcher,
+}
+
+const WORKLOADS: [Workload; 5] = [
+    Workload {
+        objects: 10,
+        sections: 4,
+        relocations: 8,
+        library_members: 50,
+        labels: 0,
+    },
+    Workload {
+        objects: 100,
+        sections: 8,
+        relocations: 16,
+        library_members: 500,
+        labels: 0,
+    },
+    Workload {
+        objects: 400,
+        sections: 8,
+        relocations: 32,
+        library_members: 2000,
+        labels: 0,
+    },
+    Workload {
+        objects: 200,
+        sections: 16,
+        relocations: 8,
+        library_members: 100,
+        labels: 32,
+    },
+    Workload {
+        objects: 100,
+        sections: 8,
+        relocations: 256,
+        library_members: 100,
+        labels: 0,
+    },
+];
+
+/// The phases measured in each benchmark group along with the report phases
+/// making up each one.
+const PHASES: [(&str, &[LinkPhase]); 5] = [
+    ("parse", &[LinkPhase::Parse]),
+    (
+        "resolution",
+        &[LinkPhase::LibrarySearch, LinkPhase::SymbolResolution],
+    ),
+    ("layout", &[LinkPhase::GraphFinish, LinkPhase::Layout]),
+    ("write", &[LinkPhase::Write]),
+    ("total", &[]),
+];
+
+fn function_name(object: usize, section: usize) -> String {
+    format!("func_{object}_{section}")
+}
+
+fn library_function_name(member: usize) -> String {
+    format!("libfunc_{member}")
+}
+
+fn external_symbol(name: String, section_number: i32) -> CoffYamlSymbol {
+    CoffYamlSymbol {
+        name,
+        section_number,
+        storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+        ..Default::default()
+    }
+}
+
+fn static_label(name: String, section_number: i32, value: u32) -> CoffYamlSymbol {
+    CoffYamlSymbol {
+        name,
+        value,
+        section_number,
+        storage_class: IMAGE_SYM_CLASS_LABEL,
+        ..Default::default()
+    }
+}
+
+fn code_section(name: &str, relocations: Vec<CoffYamlSectionRelocation>) -> CoffYamlSection {
+    let mut data = Vec::with_capacity(relocations.len().max(1) * CALL_SIZE + 1);
+    for _ in 0..relocations.len() {
+        data.extend_from_slice(&[0xe8, 0, 0, 0, 0]);
+    }
+    data.push(0xc3);
+
+    CoffYamlSection {
+        name: name.to_string(),
+        characteristics: IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ,
+        alignment: Some(16),
+        section_data: data.into(),
+        relocations,
+        ..Default::default()
+    }
+}
+
+fn amd64_coff(sections: Vec<CoffYamlSection>, symbols: Vec<CoffYamlSymbol>) -> CoffYaml {
+    CoffYaml {
+        header: CoffYamlHeader {
+            machine: IMAGE_FILE_MACHINE_AMD64,
+            characteristics: 0,
+        },
+        sections,
+        symbols,
+    }
+}
+
+impl Workload {
+    /// Builds the input objects and link library.
+    ///
+    /// Each section defines a function and calls the functions in the same
+    /// numbered section of the following objects. Every other call goes to a
+    /// library function so that the library members are pulled into the link.
+    fn build(&self) -> WorkloadFiles {
+        let mut inputs = Vec::with_capacity(self.objects);
+        let mut library_member = 0;
+
+        for object in 0..self.objects {
+            let mut sections = Vec::with_capacity(self.sections);
+            let mut symbols = Vec::new();
+            let mut undefined = Vec::new();
+
+            if object == 0 {
+                symbols.push(external_symbol("go".to_string(), 1));
+            }
+
+            for section in 0..self.sections {
+                let relocations = (0..self.relocations)
+                    .map(|idx| {
+                        let target = if idx % 2 == 1 && self.library_members > 0 {
+                            library_member = (library_member + 1) % self.library_members;
+                            library_function_name(library_member)
+                        } else {
+                            function_name((object + idx / 2 + 1) % self.objects, section)
+                        };
+
+                        if !undefined.contains(&target) {
+                            undefined.push(target.clone());
+                        }
+
+                        CoffYamlSectionRelocation {
+                            virtual_address: (idx * CALL_SIZE + 1) as u32,
+                            symbol_name: target,
+                            typ: IMAGE_REL_AMD64_REL32,
+                        }
+                    })
+                    .collect();
+
+                sections.push(code_section(&format!(".text${section}"), relocations));
+                symbols.push(external_symbol(
+                    function_name(object, section),
+                    section as i32 + 1,
+                ));
+
+                for label in 0..self.labels {
+                    symbols.push(static_label(
+                        format!("$LN{label}"),
+                        section as i32 + 1,
+                        (label % self.relocations.max(1) * CALL_SIZE) as u32,
+                    ));
+                }
+            }
+
+            for name in undefined {
+                if !symbols.iter().any(|symbol| symbol.name == name) {
+                    symbols.push(external_symbol(name, IMAGE_SYM_UNDEFINED));
+                }
+            }
+
+            let coff = amd64_coff(sections, symbols)
+                .build()
+                .expect("could not build workload object");
+            inputs.push(PathedItem::new(
+                PathBuf::from(format!("file{object}")),
+                coff,
+            ));
+        }
+
+        let members = (0..self.library_members)
+            .map(|member| ArchiveYamlMember {
+                name: format!("member{member}.o"),
+                coff: amd64_coff(
+                    vec![code_section(".text", Vec::new())],
+                    vec![external_symbol(library_function_name(member), 1)],
+                ),
+                ec_exports: Vec::new(),
+            })
+            .collect();
+
+        let library = ArchiveYaml {
+            members,
+            ..Default::default()
+        }
+        .build()
+        .expect("could not build workload library");
+
+        let mut searcher = MemoryArchiveSearcher::new();
+        searcher.add_library(LIBRARY_NAME, library);
+
+        WorkloadFiles { inputs, searcher }
+    }
+}
+
+impl WorkloadFiles {
+    /// Links the workload and returns the time spent in the phases.
+    ///
+    /// The total link time is returned if `phases` is empty.
+    fn link(&self, phases: &[LinkPhase], checksums: bool) -> Duration {
+        let mut linker = LinkerBuilder::new()
+            .architecture(LinkerTargetArch::Amd64)
+            .library_searcher(self.searcher.clone())
+            .add_inputs(self.inputs.clone())
+            .add_library(LIBRARY_NAME)
+            .entrypoint("go")
+            .section_checksums(checksums)
+            .build();
+
+        let start = Instant::now();
+        let output = linker.link().expect("could not link workload");
+        let elapsed = start.elapsed();
+        std::hint::black_box(output);
+
+        if phases.is_empty() {
+            return elapsed;
+        }
+
+        linker
+            .report()
+            .phase_times()
+            .filter(|(phase, _)| phases.contains(phase))
+            .map(|(_, time)| time)
+            .sum()
+    }
+}
+
+fn link_phases(c: &mut Criterion) {
+    for workload in WORKLOADS {
+        let files = workload.build();
+
+        let mut group = c.benchmark_group(format!("link/{workload}"));
+        group.sample_size(20);
+
+        for (name, phases) in PHASES {
+            group.bench_function(BenchmarkId::from_parameter(name), |b| {
+                b.iter_custom(|iters| (0..iters).map(|_| files.link(phases, false)).sum())
+            });
+        }
+
+        group.finish();
+    }
+}
+
+/// Measures the write phase with the section checksum table which hashes all
+/// of the output section data. Build with the `parallel` feature to compare
+/// against hashing the sections on the thread pool.
+fn write_checksums(c: &mut Criterion) {
+    let mut group = c.benchmark_group("checksums");
+    group.sample_size(20);
+
+    for workload in WORKLOADS {
+        let files = workload.build();
+        group.bench_function(BenchmarkId::from_parameter(workload), |b| {
+            b.iter_custom(|iters| {
+                (0..iters)
+                    .map(|_| files.link(&[LinkPhase::Write], true))
+                    .sum()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, link_phases, write_checksums);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+#ifndef BOFLINK_H
+#define BOFLINK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Options for a link. */
+typedef struct boflink_options boflink_options;
+
+/* Result of a link. */
+typedef struct boflink_result boflink_result;
+
+/* Creates a new set of link options. */
+boflink_options *boflink_options_new(void);
+
+/* Frees the link options. */
+void boflink_options_free(boflink_options *options);
+
+/* Adds an in-memory input file. The data is copied. Returns 0 on success. */
+int boflink_options_add_input(boflink_options *options, const char *name, const uint8_t *data,
+                              size_t len);
+
+/* Adds a link library. Returns 0 on success. */
+int boflink_options_add_library(boflink_options *options, const char *name);
+
+/* Adds a library search path. Returns 0 on success. */
+int boflink_options_add_library_path(boflink_options *options, const char *path);
+
+/* Sets the name of the entrypoint symbol. Returns 0 on success. */
+int boflink_options_set_entrypoint(boflink_options *options, const char *name);
+
+/* Sets the target machine using the IMAGE_FILE_MACHINE value. Returns 0 on
+ * success. */
+int boflink_options_set_machine(boflink_options *options, uint16_t machine);
+
+/* Links the inputs. The result must be freed with boflink_result_free. A
+ * panic in the linker is returned as the error of the result. */
+boflink_result *boflink_link(const boflink_options *options);
+
+/* Returns the linked COFF and writes the size to len. Returns NULL if the
+ * link failed. */
+const uint8_t *boflink_result_output(const boflink_result *result, size_t *len);
+
+/* Returns the error message if the link failed or NULL. */
+const char *boflink_result_error(const boflink_result *result);
+
+/* Returns the number of diagnostics emitted during the link. */
+size_t boflink_result_diagnostic_count(const boflink_result *result);
+
+/* Returns the diagnostic at the index or NULL if out of range. */
+const char *boflink_result_diagnostic(const boflink_result *result, size_t index);
+
+/* Frees the link result. */
+void boflink_result_free(boflink_result *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BOFLINK_H */
@@ -0,0 +1,463 @@
+//! C bindings for running boflink.
+//!
+//! See `include/boflink.h` for the declarations.
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{CStr, CString, c_char, c_int},
+    panic::AssertUnwindSafe,
+    sync::Once,
+};
+
+use boflink::{
+    link::{LinkInput, LinkOptions, link},
+    linker::LinkerTargetArch,
+};
+
+/// Options for a link.
+#[allow(non_camel_case_types)]
+#[derive(Default)]
+pub struct boflink_options(LinkOptions);
+
+/// Result of a link.
+#[allow(non_camel_case_types)]
+pub struct boflink_result {
+    /// The linked COFF.
+    output: Option<Vec<u8>>,
+
+    /// The error message if the link failed.
+    error: Option<CString>,
+
+    /// Warnings emitted during the link.
+    diagnostics: Vec<CString>,
+}
+
+thread_local! {
+    /// Diagnostics captured for the current link on this thread.
+    static DIAGNOSTICS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Logger for capturing the linker diagnostics.
+struct DiagnosticLogger;
+
+impl log::Log for DiagnosticLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Warn
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        DIAGNOSTICS.with_borrow_mut(|diagnostics| {
+            if let Some(diagnostics) = diagnostics {
+                diagnostics.push(format!("{}: {}", record.level(), record.args()));
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: DiagnosticLogger = DiagnosticLogger;
+static LOGGER_INIT: Once = Once::new();
+
+/// Converts the string into a [`CString`] replacing interior NUL bytes.
+fn to_cstring(s: String) -> CString {
+    CString::new(s.replace('\0', "\\0")).unwrap_or_default()
+}
+
+/// Runs `f` and returns `default` if it panics.
+///
+/// Unwinding across the C ABI aborts the host process so every exported
+/// function catches panics before returning.
+fn catch_panic<T>(default: T, f: impl FnOnce() -> T) -> T {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(default)
+}
+
+/// Returns the message from a caught panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Reads a NUL-terminated string from the pointer.
+///
+/// # Safety
+/// The pointer must be null or point to a valid NUL-terminated string.
+unsafe fn read_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    // SAFETY: The caller guarantees the pointer is a valid NUL-terminated
+    // string.
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+/// Creates a new set of link options.
+#[unsafe(no_mangle)]
+pub extern "C" fn boflink_options_new() -> *mut boflink_options {
+    catch_panic(std::ptr::null_mut(), || Box::into_raw(Box::default()))
+}
+
+/// Frees the link options.
+///
+/// # Safety
+/// The pointer must be null or returned from [`boflink_options_new`] and not
+/// already freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_options_free(options: *mut boflink_options) {
+    if !options.is_null() {
+        // SAFETY: The pointer was allocated by `boflink_options_new`.
+        catch_panic((), || drop(unsafe { Box::from_raw(options) }));
+    }
+}
+
+/// Adds an in-memory input file. Returns 0 on success.
+///
+/// # Safety
+/// `options` must be a valid options pointer, `name` a valid NUL-terminated
+/// string and `data` must point to `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_options_add_input(
+    options: *mut boflink_options,
+    name: *const c_char,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    catch_panic(-1, || {
+        // SAFETY: The caller guarantees the pointers are valid.
+        let (Some(options), Some(name)) = (unsafe { options.as_mut() }, unsafe { read_str(name) })
+        else {
+            return -1;
+        };
+
+        if data.is_null() && len != 0 {
+            return -1;
+        }
+
+        let data = if len == 0 {
+            Vec::new()
+        } else {
+            // SAFETY: The caller guarantees `data` points to `len` bytes.
+            unsafe { std::slice::from_raw_parts(data, len) }.to_vec()
+        };
+
+        options.0.inputs.push(LinkInput::memory(name, data));
+        0
+    })
+}
+
+/// Adds a link library. Returns 0 on success.
+///
+/// # Safety
+/// `options` must be a valid options pointer and `name` a valid
+/// NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_options_add_library(
+    options: *mut boflink_options,
+    name: *const c_char,
+) -> c_int {
+    catch_panic(-1, || {
+        // SAFETY: The caller guarantees the pointers are valid.
+        let (Some(options), Some(name)) = (unsafe { options.as_mut() }, unsafe { read_str(name) })
+        else {
+            return -1;
+        };
+
+        options.0.libraries.push(name.to_string());
+        0
+    })
+}
+
+/// Adds a library search path. Returns 0 on success.
+///
+/// # Safety
+/// `options` must be a valid options pointer and `path` a valid
+/// NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_options_add_library_path(
+    options: *mut boflink_options,
+    path: *const c_char,
+) -> c_int {
+    catch_panic(-1, || {
+        // SAFETY: The caller guarantees the pointers are valid.
+        let (Some(options), Some(path)) = (unsafe { options.as_mut() }, unsafe { read_str(path) })
+        else {
+            return -1;
+        };
+
+        options.0.library_paths.push(path.into());
+        0
+    })
+}
+
+/// Sets the name of the entrypoint symbol. Returns 0 on success.
+///
+/// # Safety
+/// `options` must be a valid options pointer and `name` a valid
+/// NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_options_set_entrypoint(
+    options: *mut boflink_options,
+    name: *const c_char,
+) -> c_int {
+    catch_panic(-1, || {
+        // SAFETY: The caller guarantees the pointers are valid.
+        let (Some(options), Some(name)) = (unsafe { options.as_mut() }, unsafe { read_str(name) })
+        else {
+            return -1;
+        };
+
+        options.0.entrypoint = Some(name.to_string());
+        0
+    })
+}
+
+/// Sets the target machine using the `IMAGE_FILE_MACHINE` value. Returns 0
+/// on success.
+///
+/// # Safety
+/// `options` must be a valid options pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_options_set_machine(
+    options: *mut boflink_options,
+    machine: u16,
+) -> c_int {
+    catch_panic(-1, || {
+        // SAFETY: The caller guarantees the pointer is valid.
+        let Some(options) = (unsafe { options.as_mut() }) else {
+            return -1;
+        };
+
+        let Ok(machine) = LinkerTargetArch::try_from(machine) else {
+            return -1;
+        };
+
+        options.0.machine = Some(machine);
+        0
+    })
+}
+
+/// Links the inputs. The result must be freed with [`boflink_result_free`].
+///
+/// Returns null if `options` is null. A panic in the linker is returned as
+/// the error of the result.
+///
+/// # Safety
+/// `options` must be null or a valid options pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_link(options: *const boflink_options) -> *mut boflink_result {
+    // SAFETY: The caller guarantees the pointer is valid.
+    let Some(options) = (unsafe { options.as_ref() }) else {
+        return std::ptr::null_mut();
+    };
+
+    LOGGER_INIT.call_once(|| {
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(log::LevelFilter::Warn);
+        }
+    });
+
+    DIAGNOSTICS.set(Some(Vec::new()));
+    let linked = std::panic::catch_unwind(AssertUnwindSafe(|| link(options.0.clone())));
+    let diagnostics = DIAGNOSTICS.take().unwrap_or_default();
+
+    let (output, error) = match linked {
+        Ok(Ok(linked)) => (Some(linked.output), None),
+        Ok(Err(e)) => (None, Some(to_cstring(e.to_string()))),
+        Err(payload) => (
+            None,
+            Some(to_cstring(format!(
+                "linker panicked: {}",
+                panic_message(payload.as_ref())
+            ))),
+        ),
+    };
+
+    Box::into_raw(Box::new(boflink_result {
+        output,
+        error,
+        diagnostics: diagnostics.into_iter().map(to_cstring).collect(),
+    }))
+}
+
+/// Returns the linked COFF and writes the size to `len`. Returns null if the
+/// link failed.
+///
+/// # Safety
+/// `result` must be a valid result pointer and `len` must be null or point to
+/// writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_result_output(
+    result: *const boflink_result,
+    len: *mut usize,
+) -> *const u8 {
+    // SAFETY: The caller guarantees the pointer is valid.
+    let Some(output) = (unsafe { result.as_ref() }).and_then(|result| result.output.as_ref())
+    else {
+        return std::ptr::null();
+    };
+
+    if !len.is_null() {
+        // SAFETY: The caller guarantees `len` is writable.
+        unsafe { len.write(output.len()) };
+    }
+
+    output.as_ptr()
+}
+
+/// Returns the error message if the link failed or null.
+///
+/// # Safety
+/// `result` must be a valid result pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_result_error(result: *const boflink_result) -> *const c_char {
+    // SAFETY: The caller guarantees the pointer is valid.
+    unsafe { result.as_ref() }
+        .and_then(|result| result.error.as_ref())
+        .map_or(std::ptr::null(), |error| error.as_ptr())
+}
+
+/// Returns the number of diagnostics emitted during the link.
+///
+/// # Safety
+/// `result` must be a valid result pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_result_diagnostic_count(result: *const boflink_result) -> usize {
+    // SAFETY: The caller guarantees the pointer is valid.
+    unsafe { result.as_ref() }.map_or(0, |result| result.diagnostics.len())
+}
+
+/// Returns the diagnostic at the index or null if it is out of range.
+///
+/// # Safety
+/// `result` must be a valid result pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_result_diagnostic(
+    result: *const boflink_result,
+    index: usize,
+) -> *const c_char {
+    // SAFETY: The caller guarantees the pointer is valid.
+    unsafe { result.as_ref() }
+        .and_then(|result| result.diagnostics.get(index))
+        .map_or(std::ptr::null(), |diagnostic| diagnostic.as_ptr())
+}
+
+/// Frees the link result.
+///
+/// # Safety
+/// The pointer must be null or returned from [`boflink_link`] and not already
+/// freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn boflink_result_free(result: *mut boflink_result) {
+    if !result.is_null() {
+        // SAFETY: The pointer was allocated by `boflink_link`.
+        catch_panic((), || drop(unsafe { Box::from_raw(result) }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use coffyaml::coff::CoffYaml;
+
+    use super::*;
+
+    const GO_COFF: &str = r#"
+header:
+  Machine: IMAGE_FILE_MACHINE_AMD64
+  Characteristics: [  ]
+sections:
+  - Name:            .text
+    Characteristics: [ IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ ]
+    Alignment:       16
+    SectionData:     C3
+symbols:
+  - Name:            go
+    Value:           0
+    SectionNumber:   1
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_FUNCTION
+    StorageClass:    IMAGE_SYM_CLASS_EXTERNAL
+"#;
+
+    #[test]
+    fn link_output() {
+        let coff = serde_yml::from_str::<CoffYaml>(GO_COFF)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // SAFETY: The pointers are created by the bindings and the strings
+        // are NUL-terminated.
+        unsafe {
+            let options = boflink_options_new();
+            assert_eq!(
+                boflink_options_add_input(options, c"go.o".as_ptr(), coff.as_ptr(), coff.len()),
+                0
+            );
+            assert_eq!(boflink_options_set_machine(options, 0x8664), 0);
+
+            let result = boflink_link(options);
+            boflink_options_free(options);
+
+            assert!(boflink_result_error(result).is_null());
+
+            let mut len = 0;
+            let output = boflink_result_output(result, &mut len);
+            assert!(!output.is_null());
+
+            let output = std::slice::from_raw_parts(output, len);
+            assert_eq!(
+                u16::from_le_bytes(output[..2].try_into().unwrap()),
+                0x8664,
+                "output should be an AMD64 COFF"
+            );
+
+            boflink_result_free(result);
+        }
+    }
+
+    #[test]
+    fn caught_panics() {
+        assert_eq!(catch_panic(-1, || panic!("linker bug")), -1);
+
+        let payload = std::panic::catch_unwind(|| panic!("linker {}", "bug")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "linker bug");
+    }
+
+    #[test]
+    fn link_error() {
+        // SAFETY: The pointers are created by the bindings and the strings
+        // are NUL-terminated.
+        unsafe {
+            let options = boflink_options_new();
+            assert_eq!(
+                boflink_options_add_input(options, c"empty.o".as_ptr(), std::ptr::null(), 0),
+                0
+            );
+            assert_eq!(boflink_options_set_machine(options, 0x8664), 0);
+            assert_eq!(boflink_options_set_machine(options, 0xffff), -1);
+
+            let result = boflink_link(options);
+            boflink_options_free(options);
+
+            let mut len = 0;
+            assert!(boflink_result_output(result, &mut len).is_null());
+
+            let error = boflink_result_error(result);
+            assert!(!error.is_null());
+            assert!(CStr::from_ptr(error).to_str().unwrap().contains("empty.o"));
+
+            boflink_result_free(result);
+        }
+    }
+}
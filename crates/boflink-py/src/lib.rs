@@ -0,0 +1,229 @@
+//! Python bindings for boflink.
+
+use boflink::{
+    graph::SymbolErrorKind,
+    link::{LinkInput, LinkOptions},
+    linker::{LinkerTargetArch, error},
+};
+use pyo3::{create_exception, exceptions::PyException, prelude::*, types::PyBytes};
+
+create_exception!(boflink, LinkError, PyException);
+create_exception!(boflink, SetupError, LinkError);
+create_exception!(boflink, SymbolError, LinkError);
+create_exception!(boflink, UndefinedSymbolError, SymbolError);
+create_exception!(boflink, DuplicateSymbolError, SymbolError);
+
+/// Converts the [`error::LinkError`] into the matching Python exception.
+fn link_error(py: Python<'_>, error: error::LinkError) -> PyErr {
+    match &error {
+        error::LinkError::Setup(_) => SetupError::new_err(error.to_string()),
+        error::LinkError::Symbol(symbol_errors) => {
+            let kinds = Vec::from_iter(symbol_errors.symbols().map(|(kind, _)| kind));
+            let symbols = Vec::from_iter(symbol_errors.symbols().map(|(_, name)| name.to_string()));
+
+            let err = if kinds.iter().all(|kind| *kind == SymbolErrorKind::Undefined) {
+                UndefinedSymbolError::new_err(error.to_string())
+            } else if kinds.iter().all(|kind| *kind != SymbolErrorKind::Undefined) {
+                DuplicateSymbolError::new_err(error.to_string())
+            } else {
+                SymbolError::new_err(error.to_string())
+            };
+
+            if let Err(e) = err.value(py).setattr("symbols", symbols) {
+                return e;
+            }
+
+            err
+        }
+        _ => LinkError::new_err(error.to_string()),
+    }
+}
+
+/// Links the objects and returns the linked BOF.
+#[pyfunction]
+#[pyo3(signature = (
+    objects,
+    libraries = Vec::new(),
+    library_paths = Vec::new(),
+    *,
+    library_buffers = Vec::new(),
+    entrypoint = None,
+    machine = None,
+    custom_apis = Vec::new(),
+    merge_bss = false,
+    deterministic = false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn link<'py>(
+    py: Python<'py>,
+    objects: Vec<Vec<u8>>,
+    libraries: Vec<String>,
+    library_paths: Vec<String>,
+    library_buffers: Vec<(String, Vec<u8>)>,
+    entrypoint: Option<String>,
+    machine: Option<&str>,
+    custom_apis: Vec<String>,
+    merge_bss: bool,
+    deterministic: bool,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let mut options = LinkOptions::default();
+
+    options.inputs = objects
+        .into_iter()
+        .enumerate()
+        .map(|(idx, data)| LinkInput::memory(format!("<object {idx}>"), data))
+        .collect();
+
+    options.libraries = libraries;
+    options.library_paths = library_paths.into_iter().map(Into::into).collect();
+    options.library_buffers = library_buffers;
+    options.entrypoint = entrypoint;
+    options.custom_apis = custom_apis;
+    options.merge_bss = merge_bss;
+    options.deterministic = deterministic;
+
+    options.machine = match machine {
+        None => None,
+        Some("amd64" | "x86_64" | "i386pep") => Some(LinkerTargetArch::Amd64),
+        Some("i386" | "x86" | "i386pe") => Some(LinkerTargetArch::I386),
+        Some(other) => {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "unknown machine '{other}'"
+            )));
+        }
+    };
+
+    let linked = py
+        .detach(|| boflink::link::link(options))
+        .map_err(|e| link_error(py, e))?;
+
+    Ok(PyBytes::new(py, &linked.output))
+}
+
+#[pymodule]
+#[pyo3(name = "boflink")]
+fn boflink_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add_function(wrap_pyfunction!(link, m)?)?;
+    m.add("LinkError", py.get_type::<LinkError>())?;
+    m.add("SetupError", py.get_type::<SetupError>())?;
+    m.add("SymbolError", py.get_type::<SymbolError>())?;
+    m.add(
+        "UndefinedSymbolError",
+        py.get_type::<UndefinedSymbolError>(),
+    )?;
+    m.add(
+        "DuplicateSymbolError",
+        py.get_type::<DuplicateSymbolError>(),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use coffyaml::coff::CoffYaml;
+
+    use super::*;
+
+    const GO_COFF: &str = r#"
+header:
+  Machine: IMAGE_FILE_MACHINE_AMD64
+  Characteristics: [  ]
+sections:
+  - Name:            .text
+    Characteristics: [ IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ ]
+    Alignment:       16
+    SectionData:     E800000000C3
+    Relocations:
+      - VirtualAddress:  1
+        SymbolName:      external
+        Type:            IMAGE_REL_AMD64_REL32
+symbols:
+  - Name:            go
+    Value:           0
+    SectionNumber:   1
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_FUNCTION
+    StorageClass:    IMAGE_SYM_CLASS_EXTERNAL
+  - Name:            external
+    Value:           0
+    SectionNumber:   0
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_FUNCTION
+    StorageClass:    IMAGE_SYM_CLASS_EXTERNAL
+"#;
+
+    const EXTERNAL_COFF: &str = r#"
+header:
+  Machine: IMAGE_FILE_MACHINE_AMD64
+  Characteristics: [  ]
+sections:
+  - Name:            .text
+    Characteristics: [ IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ ]
+    Alignment:       16
+    SectionData:     C3
+symbols:
+  - Name:            external
+    Value:           0
+    SectionNumber:   1
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_FUNCTION
+    StorageClass:    IMAGE_SYM_CLASS_EXTERNAL
+"#;
+
+    fn build_coff(yaml: &str) -> Vec<u8> {
+        serde_yml::from_str::<CoffYaml>(yaml)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn call_link<'py>(py: Python<'py>, objects: Vec<Vec<u8>>) -> PyResult<Bound<'py, PyBytes>> {
+        link(
+            py,
+            objects,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            None,
+            Some("amd64"),
+            Vec::new(),
+            false,
+            false,
+        )
+    }
+
+    #[test]
+    fn link_output() {
+        let objects = vec![build_coff(GO_COFF), build_coff(EXTERNAL_COFF)];
+
+        Python::initialize();
+        Python::attach(|py| {
+            let output = call_link(py, objects).expect("link should succeed");
+            let output = output.as_bytes();
+
+            assert_eq!(
+                u16::from_le_bytes(output[..2].try_into().unwrap()),
+                0x8664,
+                "output should be an AMD64 COFF"
+            );
+        });
+    }
+
+    #[test]
+    fn undefined_symbol_error() {
+        let objects = vec![build_coff(GO_COFF)];
+
+        Python::initialize();
+        Python::attach(|py| {
+            let err = call_link(py, objects).expect_err("link should fail");
+            assert!(
+                err.is_instance_of::<UndefinedSymbolError>(py),
+                "error should be an UndefinedSymbolError"
+            );
+
+            let symbols: Vec<String> = err.value(py).getattr("symbols").unwrap().extract().unwrap();
+            assert_eq!(symbols, ["external"]);
+        });
+    }
+}
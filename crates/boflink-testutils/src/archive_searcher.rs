@@ -2,6 +2,8 @@ use std::{collections::HashMap, path::PathBuf};
 
 use boflink::libsearch::{FoundLibrary, LibraryFind, LibsearchError};
 
+/// Library searcher for libraries built in memory.
+#[derive(Clone, Default)]
 pub struct MemoryArchiveSearcher {
     files: HashMap<String, Vec<u8>>,
 }
@@ -22,7 +24,7 @@ impl LibraryFind for MemoryArchiveSearcher {
     fn find_library(&self, name: impl AsRef<str>) -> Result<FoundLibrary, LibsearchError> {
         self.files
             .get(name.as_ref())
-            .map(|data| FoundLibrary::new(PathBuf::from(name.as_ref()), data.clone()))
+            .map(|data| FoundLibrary::new(PathBuf::from(name.as_ref()), data.clone().into()))
             .ok_or(boflink::libsearch::LibsearchError::NotFound(
                 name.as_ref().to_string(),
             ))
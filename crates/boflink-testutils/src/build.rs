@@ -1,6 +1,7 @@
 use coffyaml::{coff::CoffYaml, importlib::ImportlibYaml};
 use serde::Deserialize;
 
+/// A COFF or library document in a test YAML file.
 #[derive(Debug, Deserialize)]
 pub enum YamlInput {
     #[serde(rename = "COFF")]
@@ -8,4 +9,10 @@ pub enum YamlInput {
 
     #[serde(rename = "IMPORTLIB")]
     Importlib(ImportlibYaml),
+
+    #[serde(rename = "LEGACYIMPORTLIB")]
+    LegacyImportlib(ImportlibYaml),
+
+    #[serde(rename = "DLL")]
+    Dll(ImportlibYaml),
 }
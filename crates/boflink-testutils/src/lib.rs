@@ -0,0 +1,10 @@
+//! Utilities for linking YAML described inputs in the boflink tests.
+//!
+//! See [`scenario::Scenario`] for the supported YAML documents.
+
+pub mod archive_searcher;
+pub mod build;
+pub mod scenario;
+pub mod snapshot;
+
+mod macros;
@@ -0,0 +1,48 @@
+/// Links the YAML scenario and returns the linked COFF.
+///
+/// A string literal argument is included as a file path relative to the
+/// calling file.
+#[macro_export]
+macro_rules! link_yaml {
+    ($input:literal, $arch:expr) => {{
+        const __INPUT_DOC: &str = include_str!($input);
+        $crate::link_yaml!(__INPUT_DOC, $arch)
+    }};
+
+    ($input:ident, $arch:expr) => {{
+        $crate::setup_linker!($input, $arch)
+            .build()
+            .link()
+            .expect("Could not link files")
+    }};
+}
+
+/// Compares the linked COFF with the named snapshot in the `tests/snapshots`
+/// directory of the calling package.
+///
+/// See [`snapshot::assert_snapshot`](crate::snapshot::assert_snapshot).
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($name:expr, $coff:expr) => {
+        $crate::snapshot::assert_snapshot(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots"),
+            $name,
+            &$coff,
+        )
+    };
+}
+
+/// Returns a [`LinkerBuilder`](boflink::linker::LinkerBuilder) set up for
+/// the YAML scenario.
+///
+/// A string literal argument is included as a file path relative to the
+/// calling file.
+#[macro_export]
+macro_rules! setup_linker {
+    ($input:literal, $arch:expr) => {{
+        const __INPUT_DOC: &str = include_str!($input);
+        $crate::setup_linker!(__INPUT_DOC, $arch)
+    }};
+
+    ($input:ident, $arch:expr) => {{ $crate::scenario::Scenario::parse($input, $arch).linker() }};
+}
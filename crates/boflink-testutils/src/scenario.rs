@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use boflink::{
+    linker::{LinkerBuilder, LinkerTargetArch},
+    pathed_item::PathedItem,
+};
+use coffyaml::{archive::ArchiveYaml, coff::CoffYaml, importlib::ImportlibYaml};
+use serde::Deserialize;
+
+use crate::archive_searcher::MemoryArchiveSearcher;
+
+/// A document in a scenario file.
+#[derive(Debug, Deserialize)]
+pub enum ScenarioDocument {
+    #[serde(rename = "COFF")]
+    Coff(CoffYaml),
+
+    #[serde(rename = "IMPORTLIB")]
+    Importlib(ImportlibYaml),
+
+    #[serde(rename = "LEGACYIMPORTLIB")]
+    LegacyImportlib(ImportlibYaml),
+
+    #[serde(rename = "DLL")]
+    Dll(ImportlibYaml),
+
+    #[serde(rename = "ARCHIVE")]
+    Archive(ScenarioArchive),
+
+    #[serde(rename = "API")]
+    Api(ImportlibYaml),
+
+    #[serde(rename = "OPTIONS")]
+    Options(ScenarioOptions),
+}
+
+/// A static library in a scenario file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ScenarioArchive {
+    /// The library name. Defaults to `file<N>` after the document number.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Only open the library through `.drectve` `/DEFAULTLIB` directives
+    /// instead of linking it directly.
+    #[serde(default)]
+    pub default_lib: bool,
+
+    #[serde(flatten)]
+    pub archive: ArchiveYaml,
+}
+
+/// Linker options for a scenario.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct ScenarioOptions {
+    #[serde(default)]
+    pub entrypoint: Option<String>,
+
+    /// Libraries linked after the libraries in the scenario documents.
+    #[serde(default)]
+    pub libraries: Vec<String>,
+
+    #[serde(default)]
+    pub merge_bss: bool,
+
+    #[serde(default)]
+    pub tolerant: bool,
+
+    #[serde(default)]
+    pub dedup_inputs: bool,
+
+    #[serde(default)]
+    pub deterministic: bool,
+
+    #[serde(default)]
+    pub positional_libraries: bool,
+
+    /// Whether the libraries in the scenario documents are added as a
+    /// library group.
+    #[serde(default)]
+    pub library_group: bool,
+}
+
+/// The inputs, libraries and options for a link described by a YAML
+/// scenario file.
+///
+/// The scenario file is a multi-document YAML file where each document is a
+/// [`ScenarioDocument`]. COFFs and libraries without a name are named
+/// `file<N>` after their document number.
+pub struct Scenario {
+    arch: LinkerTargetArch,
+    searcher: MemoryArchiveSearcher,
+    inputs: Vec<PathedItem<PathBuf, Vec<u8>>>,
+    libraries: Vec<String>,
+    apis: Vec<String>,
+    options: ScenarioOptions,
+}
+
+impl Scenario {
+    /// Parses the scenario file and builds the inputs for the architecture.
+    ///
+    /// # Panics
+    /// Panics with the document number if a document is invalid.
+    pub fn parse(input: &str, arch: LinkerTargetArch) -> Scenario {
+        let mut scenario = Scenario {
+            arch,
+            searcher: MemoryArchiveSearcher::new(),
+            inputs: Vec::new(),
+            libraries: Vec::new(),
+            apis: Vec::new(),
+            options: ScenarioOptions::default(),
+        };
+
+        for (idx, document) in serde_yml::Deserializer::from_str(input).enumerate() {
+            let name = format!("file{}", idx + 1);
+            let document = ScenarioDocument::deserialize(document)
+                .unwrap_or_else(|e| panic!("document {}: {e}", idx + 1));
+            scenario
+                .add_document(name, document)
+                .unwrap_or_else(|e| panic!("document {}: {e}", idx + 1));
+        }
+
+        scenario
+    }
+
+    fn add_document(
+        &mut self,
+        name: String,
+        document: ScenarioDocument,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match document {
+            ScenarioDocument::Coff(coff) => {
+                self.inputs
+                    .push(PathedItem::new(name.into(), coff.build()?));
+            }
+            ScenarioDocument::Importlib(importlib) => {
+                self.add_library(name, importlib.build(self.arch.into())?);
+            }
+            ScenarioDocument::LegacyImportlib(importlib) => {
+                self.add_library(name, importlib.build_legacy(self.arch.into())?);
+            }
+            ScenarioDocument::Dll(dll) => {
+                self.add_library(name, dll.build_dll(self.arch.into())?);
+            }
+            ScenarioDocument::Archive(archive) => {
+                let name = archive.name.unwrap_or(name);
+                let data = archive.archive.build()?;
+                if archive.default_lib {
+                    self.searcher.add_library(name, data);
+                } else {
+                    self.add_library(name, data);
+                }
+            }
+            ScenarioDocument::Api(api) => {
+                let name = api.library.clone();
+                self.searcher
+                    .add_library(name.clone(), api.build(self.arch.into())?);
+                self.apis.push(name);
+            }
+            ScenarioDocument::Options(options) => {
+                self.options = options;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_library(&mut self, name: String, data: Vec<u8>) {
+        self.searcher.add_library(name.clone(), data);
+        self.libraries.push(name);
+    }
+
+    /// Returns a [`LinkerBuilder`] set up with the scenario inputs and
+    /// options.
+    pub fn linker(self) -> LinkerBuilder<MemoryArchiveSearcher> {
+        let mut linker = LinkerBuilder::new()
+            .architecture(self.arch)
+            .library_searcher(self.searcher)
+            .add_inputs(self.inputs);
+
+        linker = if self.options.library_group {
+            linker
+                .start_library_group()
+                .add_libraries(self.libraries)
+                .end_library_group()
+        } else {
+            linker.add_libraries(self.libraries)
+        };
+
+        linker = linker
+            .add_libraries(self.options.libraries)
+            .positional_libraries(self.options.positional_libraries)
+            .custom_apis(self.apis)
+            .merge_bss(self.options.merge_bss)
+            .tolerant(self.options.tolerant)
+            .dedup_inputs(self.options.dedup_inputs)
+            .deterministic(self.options.deterministic);
+
+        if let Some(entrypoint) = self.options.entrypoint {
+            linker = linker.entrypoint(entrypoint);
+        }
+
+        linker
+    }
+}
@@ -0,0 +1,152 @@
+//! Golden output snapshots of linked COFFs.
+//!
+//! Snapshots are compared against the files in the snapshot directory.
+//! Setting the [`UPDATE_ENV`] environment variable writes the new snapshots
+//! instead. `cargo xtask snapshots` runs the tests with it set.
+
+use std::{fmt::Write, path::Path};
+
+use object::{
+    LittleEndian, Object, ObjectSection, SymbolIndex,
+    coff::{CoffFile, ImageSymbol},
+    pe::ImageSymbolBytes,
+};
+
+/// Environment variable for writing the snapshots instead of comparing them.
+pub const UPDATE_ENV: &str = "BOFLINK_UPDATE_SNAPSHOTS";
+
+/// Returns a textual dump of the COFF for comparing with a snapshot.
+///
+/// The dump leaves out the timestamp and file offsets. Names are written
+/// out instead of string table offsets so that the order of the string
+/// table does not matter.
+///
+/// # Panics
+/// Panics if the COFF fails to parse.
+pub fn dump_coff(data: &[u8]) -> String {
+    try_dump_coff(data).unwrap_or_else(|e| panic!("could not dump COFF: {e}"))
+}
+
+fn try_dump_coff(data: &[u8]) -> object::read::Result<String> {
+    let coff: CoffFile = CoffFile::parse(data)?;
+    let header = coff.coff_header();
+    let symbols = coff.coff_symbol_table();
+    let strings = symbols.strings();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "Machine: {:#06x}", header.machine.get(LittleEndian));
+    let _ = writeln!(
+        out,
+        "Characteristics: {:#06x}",
+        header.characteristics.get(LittleEndian)
+    );
+
+    for section in coff.sections() {
+        let header = section.coff_section();
+        let _ = writeln!(out, "\nSection {}: {}", section.index().0, section.name()?);
+        let _ = writeln!(
+            out,
+            "  Characteristics: {:#010x}",
+            header.characteristics.get(LittleEndian)
+        );
+        let _ = writeln!(
+            out,
+            "  SizeOfRawData: {}",
+            header.size_of_raw_data.get(LittleEndian)
+        );
+
+        for (line, chunk) in section.data()?.chunks(16).enumerate() {
+            let _ = writeln!(out, "  {:04x}: {}", line * 16, hex_bytes(chunk));
+        }
+
+        for reloc in section.coff_relocations()? {
+            let symbol = symbols.symbol(SymbolIndex(
+                reloc.symbol_table_index.get(LittleEndian) as usize
+            ))?;
+            let _ = writeln!(
+                out,
+                "  Relocation {:#x} type {:#06x} -> {}",
+                reloc.virtual_address.get(LittleEndian),
+                reloc.typ.get(LittleEndian),
+                String::from_utf8_lossy(symbol.name(strings)?)
+            );
+        }
+    }
+
+    out.push('\n');
+
+    let mut index = 0;
+    while index < symbols.len() {
+        let symbol = symbols.symbol(SymbolIndex(index))?;
+        let _ = writeln!(
+            out,
+            "Symbol {index}: {} value {:#x} section {} type {:#06x} class {}",
+            String::from_utf8_lossy(symbol.name(strings)?),
+            symbol.value(),
+            symbol.section_number(),
+            symbol.typ(),
+            symbol.storage_class(),
+        );
+
+        for aux in 1..=symbol.number_of_aux_symbols() as usize {
+            let record = symbols.get::<ImageSymbolBytes>(SymbolIndex(index), aux)?;
+            let _ = writeln!(out, "  Aux: {}", hex_bytes(&record.0));
+        }
+
+        index += 1 + symbol.number_of_aux_symbols() as usize;
+    }
+
+    Ok(out)
+}
+
+fn hex_bytes(data: &[u8]) -> String {
+    Vec::from_iter(data.iter().map(|b| format!("{b:02X}"))).join(" ")
+}
+
+/// Compares the dump of the COFF with the snapshot named `name` in the
+/// snapshot directory.
+///
+/// # Panics
+/// Panics if the dump does not match the snapshot or the snapshot does not
+/// exist.
+pub fn assert_snapshot(dir: impl AsRef<Path>, name: &str, coff: &[u8]) {
+    let path = dir.as_ref().join(format!("{name}.snap"));
+    let actual = dump_coff(coff);
+
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        std::fs::create_dir_all(dir.as_ref())
+            .unwrap_or_else(|e| panic!("could not create {}: {e}", dir.as_ref().display()));
+        std::fs::write(&path, actual)
+            .unwrap_or_else(|e| panic!("could not write {}: {e}", path.display()));
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "could not read snapshot {}: {e}\nrun 'cargo xtask snapshots' to create it",
+            path.display()
+        )
+    });
+
+    if expected != actual {
+        let diff = Vec::from_iter(
+            expected
+                .lines()
+                .zip(actual.lines())
+                .enumerate()
+                .filter(|(_, (expected, actual))| expected != actual)
+                .take(10)
+                .map(|(line, (expected, actual))| {
+                    format!("line {}:\n  - {expected}\n  + {actual}", line + 1)
+                }),
+        );
+
+        panic!(
+            "snapshot {} does not match ({} lines expected, {} lines found)\n{}\nrun 'cargo xtask snapshots' to update it",
+            path.display(),
+            expected.lines().count(),
+            actual.lines().count(),
+            diff.join("\n")
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexSet;
+
+use super::{
+    ArchiveMemberIndex, ArchiveMemberMetadata, MemberSize, longnames::ArchiveMemberName,
+    make_archive_member_buffer,
+};
+
+/// Builder for the `/<ECSYMBOLS>/` member in ARM64EC archives.
+///
+/// The layout matches the symbol part of the second linker member. The
+/// member indices refer to the member offsets in the second linker member.
+#[derive(Default)]
+pub struct EcSymbolMapBuilder {
+    string_indicies: HashSet<(String, ArchiveMemberIndex)>,
+    string_table_size: usize,
+}
+
+impl EcSymbolMapBuilder {
+    /// Adds a symbol to the EC symbol map
+    pub fn add_symbol(&mut self, index: ArchiveMemberIndex, symbol: impl AsRef<str>) {
+        let symbol_len = symbol.as_ref().len();
+        if self
+            .string_indicies
+            .insert((symbol.as_ref().to_string(), index))
+        {
+            self.string_table_size += symbol_len + 1;
+        }
+    }
+
+    /// Builds the EC symbol map with the member offsets from the second
+    /// linker member
+    pub fn build(
+        self,
+        archive_map: &HashMap<ArchiveMemberIndex, usize>,
+        member_offsets: &IndexSet<usize>,
+    ) -> Vec<u8> {
+        let mut buffer = make_archive_member_buffer(
+            &ArchiveMemberName::Value("/<ECSYMBOLS>/".to_string()),
+            &ArchiveMemberMetadata {
+                date: Some(0),
+                uid: Some(0),
+                gid: Some(0),
+                mode: Some(0),
+            },
+            &self,
+        );
+
+        // Create the sorted strings
+        let mut string_table = self.string_indicies.into_iter().collect::<Vec<_>>();
+        string_table.sort();
+
+        // Add the number of symbols
+        buffer.extend((string_table.len() as u32).to_le_bytes());
+
+        // Add the symbol indices
+        for (_, archive_idx) in &string_table {
+            let member_offset = *archive_map
+                .get(archive_idx)
+                .unwrap_or_else(|| unreachable!());
+
+            let table_index = member_offsets
+                .get_index_of(&member_offset)
+                .unwrap_or_else(|| unreachable!());
+
+            buffer.extend(u16::try_from(table_index + 1).unwrap().to_le_bytes());
+        }
+
+        // Add the strings
+        for (symbol, _) in string_table {
+            buffer.extend(symbol.as_bytes());
+            buffer.push(0);
+        }
+
+        // Padding
+        if buffer.len() % 2 == 1 {
+            buffer.push(b'\n');
+        }
+
+        buffer
+    }
+}
+
+impl MemberSize for EcSymbolMapBuilder {
+    fn member_data_size(&self) -> usize {
+        4 + 2 * self.string_indicies.len() + self.string_table_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use indexmap::IndexSet;
+
+    use crate::archive::builder::{ArchiveMemberIndex, MemberSize};
+
+    use super::EcSymbolMapBuilder;
+
+    #[test]
+    fn sorted_symbols_indexed_by_offset() {
+        let mut symbol_map = EcSymbolMapBuilder::default();
+        symbol_map.add_symbol(ArchiveMemberIndex(1), "#func");
+        symbol_map.add_symbol(ArchiveMemberIndex(0), "__imp_aux_func");
+        symbol_map.add_symbol(ArchiveMemberIndex(1), "#func");
+
+        let expected_size = symbol_map.member_size();
+
+        let archive_map =
+            HashMap::from([(ArchiveMemberIndex(0), 100), (ArchiveMemberIndex(1), 200)]);
+        let member_offsets = IndexSet::from([100, 200]);
+        let built = symbol_map.build(&archive_map, &member_offsets);
+
+        assert_eq!(built.len(), expected_size);
+        assert_eq!(&built[..13], b"/<ECSYMBOLS>/");
+        assert_eq!(u32::from_le_bytes(built[60..64].try_into().unwrap()), 2);
+        assert_eq!(&built[64..68], &[2, 0, 1, 0]);
+        assert_eq!(&built[68..89], b"#func\0__imp_aux_func\0");
+    }
+}
@@ -35,6 +35,9 @@ impl ArchiveMemberName {
 pub struct ArchiveLongNamesBuilder<const DELIM: u8> {
     offset_map: HashMap<String, usize>,
     longnames: Arena<u8>,
+
+    /// Build the member even if it is empty
+    required: bool,
 }
 
 impl<const DELIM: u8> ArchiveLongNamesBuilder<DELIM> {
@@ -60,13 +63,18 @@ impl<const DELIM: u8> ArchiveLongNamesBuilder<DELIM> {
         }
     }
 
+    /// Sets whether the long names member is built when it is empty.
+    pub fn set_required(&mut self, val: bool) {
+        self.required = val;
+    }
+
     fn is_empty(&self) -> bool {
         self.longnames.len() == 0
     }
 
     pub fn build(self) -> Vec<u8> {
         // Do not build the long names member if it is empty
-        if self.is_empty() {
+        if self.is_empty() && !self.required {
             return Vec::new();
         }
 
@@ -81,7 +89,7 @@ impl<const DELIM: u8> ArchiveLongNamesBuilder<DELIM> {
         buffer.append(&mut self.longnames.into_vec());
 
         // Padding
-        if buffer.len() % 2 != 0 {
+        if buffer.len() % 2 == 1 {
             buffer.push(b'\n');
         }
 
@@ -96,12 +104,12 @@ impl<const DELIM: u8> MemberSize for ArchiveLongNamesBuilder<DELIM> {
 
     fn member_size(&self) -> usize {
         // Return 0 if the longnames are empty
-        if self.member_data_size() == 0 {
+        if self.member_data_size() == 0 && !self.required {
             return 0;
         }
 
         let size = std::mem::size_of::<object::archive::Header>() + self.member_data_size();
-        if size % 2 != 0 { size + 1 } else { size }
+        size.next_multiple_of(2)
     }
 }
 
@@ -228,7 +236,7 @@ mod tests {
 
         // The test name should have an even length
         assert!(
-            name.len() % 2 == 0,
+            name.len().is_multiple_of(2),
             "test string '{name}' length should be even"
         );
 
@@ -251,7 +259,7 @@ mod tests {
 
         // The test name should have an odd length
         assert!(
-            name.len() % 2 != 0,
+            !name.len().is_multiple_of(2),
             "test string '{name}' length should be odd"
         );
 
@@ -4,6 +4,7 @@ use longnames::ArchiveMemberName;
 use typed_arena::Arena;
 
 mod armap;
+mod ec_symbols;
 mod gnu;
 mod longnames;
 mod msvc;
@@ -21,7 +22,7 @@ trait MemberSize {
 
     fn member_size(&self) -> usize {
         let size = std::mem::size_of::<object::archive::Header>() + self.member_data_size();
-        if size % 2 != 0 { size + 1 } else { size }
+        size.next_multiple_of(2)
     }
 }
 
@@ -121,7 +122,7 @@ impl ArchiveMemberBuilder {
     fn build(mut self) -> Vec<u8> {
         let mut buffer = make_archive_member_buffer(&self.name, &self.meta, &self);
         buffer.append(&mut self.data);
-        if buffer.len() % 2 != 0 {
+        if buffer.len() % 2 == 1 {
             buffer.push(b'\n');
         }
 
@@ -227,6 +228,25 @@ impl ArchiveBuilder<MsvcArchiveVariant> {
     pub fn msvc_archive_with_capacity(members: usize) -> ArchiveBuilder<MsvcArchiveVariant> {
         Self::with_capacity(members)
     }
+
+    /// Sets whether the second linker member with the sorted symbol table is
+    /// written. Enabled by default.
+    ///
+    /// Archives without the second linker member are read as GNU style
+    /// archives by some tools.
+    pub fn sorted_linker_member(&mut self, val: bool) -> &mut Self {
+        self.variant.set_sorted_armap(val);
+        self
+    }
+
+    /// Sets whether the `/<ECSYMBOLS>/` member for ARM64EC is written with
+    /// the symbols added through [`ArchiveMemberAccessor::ec_exports`].
+    ///
+    /// The member is only written along with the second linker member.
+    pub fn ec_symbols(&mut self, val: bool) -> &mut Self {
+        self.variant.set_ec_symbols(val);
+        self
+    }
 }
 
 impl ArchiveBuilder<GnuArchiveVariant> {
@@ -285,6 +305,19 @@ impl<V: ArchiveVariant> ArchiveMemberAccessor<'_, V> {
     }
 }
 
+impl ArchiveMemberAccessor<'_, MsvcArchiveVariant> {
+    /// Adds the list of exports to the EC symbol table for this member
+    pub fn ec_exports<I, S>(&mut self, symbols: I)
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+    {
+        for symbol in symbols {
+            self.variant.add_ec_symbol(self.index, symbol);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::make_ascii_base10;
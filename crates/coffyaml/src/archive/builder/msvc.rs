@@ -3,19 +3,71 @@ use std::collections::HashMap;
 use super::{
     ArchiveMemberIndex, ArchiveVariant, ByteSize, MemberSize,
     armap::ArchiveMapBuilder,
+    ec_symbols::EcSymbolMapBuilder,
     longnames::{ArchiveLongNamesBuilder, ArchiveMemberName},
     sorted_armap::SortedArchiveMapBuilder,
 };
 
-#[derive(Default)]
 pub struct MsvcArchiveVariant {
     /// The archive symbol map
     armap: ArchiveMapBuilder,
 
     sorted_armap: SortedArchiveMapBuilder,
 
+    /// The ARM64EC symbol map
+    ec_symbols: EcSymbolMapBuilder,
+
     /// The long names
     longnames: ArchiveLongNamesBuilder<b'\0'>,
+
+    /// Write the second linker member with the sorted symbol table
+    write_sorted_armap: bool,
+
+    /// Write the `/<ECSYMBOLS>/` member
+    write_ec_symbols: bool,
+}
+
+impl Default for MsvcArchiveVariant {
+    fn default() -> Self {
+        Self {
+            armap: Default::default(),
+            sorted_armap: Default::default(),
+            ec_symbols: Default::default(),
+            longnames: Default::default(),
+            write_sorted_armap: true,
+            write_ec_symbols: false,
+        }
+    }
+}
+
+impl MsvcArchiveVariant {
+    /// Sets whether the second linker member is written.
+    pub fn set_sorted_armap(&mut self, val: bool) {
+        self.write_sorted_armap = val;
+        self.longnames.set_required(self.writes_ec_symbols());
+    }
+
+    /// Sets whether the `/<ECSYMBOLS>/` member is written.
+    ///
+    /// The EC symbol map refers to the member offsets in the second linker
+    /// member and is only written along with it.
+    ///
+    /// The long names member is always written before the EC symbol map so
+    /// that readers can find it after the fixed linker members.
+    pub fn set_ec_symbols(&mut self, val: bool) {
+        self.write_ec_symbols = val;
+        self.longnames.set_required(self.writes_ec_symbols());
+    }
+
+    /// Adds a symbol to the EC symbol map for the member.
+    pub fn add_ec_symbol(&mut self, member: ArchiveMemberIndex, symbol: impl AsRef<str>) {
+        self.sorted_armap.add_member(member);
+        self.ec_symbols.add_symbol(member, symbol);
+    }
+
+    fn writes_ec_symbols(&self) -> bool {
+        self.write_sorted_armap && self.write_ec_symbols
+    }
 }
 
 impl ByteSize for MsvcArchiveVariant {
@@ -26,11 +78,18 @@ impl ByteSize for MsvcArchiveVariant {
         build_size += self.armap.member_size();
 
         // Sorted archive map
-        build_size += self.sorted_armap.member_size();
+        if self.write_sorted_armap {
+            build_size += self.sorted_armap.member_size();
+        }
 
         // Long names if it is not empty
         build_size += self.longnames.member_size();
 
+        // EC symbol map
+        if self.writes_ec_symbols() {
+            build_size += self.ec_symbols.member_size();
+        }
+
         build_size
     }
 }
@@ -47,12 +106,23 @@ impl ArchiveVariant for MsvcArchiveVariant {
 
     fn build(self, archive_map: HashMap<ArchiveMemberIndex, usize>) -> Vec<u8> {
         let mut buffer = Vec::with_capacity(self.byte_size());
+        let writes_ec_symbols = self.writes_ec_symbols();
 
         // Each member `.build()` method should add padding
         buffer.append(&mut self.armap.build(&archive_map));
-        buffer.append(&mut self.sorted_armap.build(&archive_map));
+
+        let member_offsets = self.sorted_armap.member_offsets(&archive_map);
+        if self.write_sorted_armap {
+            buffer.append(&mut self.sorted_armap.build(&archive_map));
+        }
+
         buffer.append(&mut self.longnames.build());
 
+        // The EC symbol map comes after the long names
+        if writes_ec_symbols {
+            buffer.append(&mut self.ec_symbols.build(&archive_map, &member_offsets));
+        }
+
         buffer
     }
 }
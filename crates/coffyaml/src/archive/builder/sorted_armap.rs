@@ -28,6 +28,30 @@ impl SortedArchiveMapBuilder {
         self.string_table_size += symbol_len + 1;
     }
 
+    /// Adds a member to the member offsets without any symbols.
+    ///
+    /// Used for members which are only referenced by the EC symbol table.
+    pub fn add_member(&mut self, index: ArchiveMemberIndex) {
+        self.member_indicies.insert(index);
+    }
+
+    /// Returns the sorted member offsets in the order they are written.
+    ///
+    /// The symbol indices are 1-based indices into this set.
+    pub fn member_offsets(
+        &self,
+        archive_map: &HashMap<ArchiveMemberIndex, usize>,
+    ) -> IndexSet<usize> {
+        let mut member_offsets: IndexSet<usize> = IndexSet::from_iter(
+            self.member_indicies
+                .iter()
+                .map(|member_id| *archive_map.get(member_id).unwrap_or_else(|| unreachable!())),
+        );
+
+        member_offsets.sort();
+        member_offsets
+    }
+
     pub fn build(self, archive_map: &HashMap<ArchiveMemberIndex, usize>) -> Vec<u8> {
         let mut buffer = make_archive_member_buffer(
             &ArchiveMemberName::Value("/".to_string()),
@@ -41,14 +65,7 @@ impl SortedArchiveMapBuilder {
         );
 
         // Create the sorted member offsets
-        let mut member_offsets: IndexSet<usize> =
-            IndexSet::from_iter(self.member_indicies.into_iter().map(|member_id| {
-                *archive_map
-                    .get(&member_id)
-                    .unwrap_or_else(|| unreachable!())
-            }));
-
-        member_offsets.sort();
+        let member_offsets = self.member_offsets(archive_map);
 
         // Add the number of members
         buffer.extend((member_offsets.len() as u32).to_le_bytes());
@@ -87,7 +104,7 @@ impl SortedArchiveMapBuilder {
         }
 
         // Padding
-        if buffer.len() % 2 != 0 {
+        if buffer.len() % 2 == 1 {
             buffer.push(b'\n');
         }
 
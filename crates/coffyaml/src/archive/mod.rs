@@ -1 +1,85 @@
+use object::pe::{IMAGE_SYM_CLASS_EXTERNAL, IMAGE_SYM_UNDEFINED};
+use serde::{Deserialize, Serialize};
+
+use crate::coff::{CoffYaml, errors::CoffYamlCoffBuildError};
+
 pub(crate) mod builder;
+
+use builder::{ArchiveBuilder, ArchiveMemberAccessor};
+
+/// A static library containing COFF members.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ArchiveYaml {
+    /// Build an MSVC style archive instead of a GNU style archive.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub msvc: bool,
+
+    /// Omit the second linker member with the sorted symbol table from an
+    /// MSVC style archive.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_sorted_linker_member: bool,
+
+    /// Add the `/<ECSYMBOLS>/` member with the [`ArchiveYamlMember::ec_exports`]
+    /// to an MSVC style archive.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ec_symbols: bool,
+
+    pub members: Vec<ArchiveYamlMember>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ArchiveYamlMember {
+    pub name: String,
+    pub coff: CoffYaml,
+
+    /// Symbols for the member in the ARM64EC symbol table.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ec_exports: Vec<String>,
+}
+
+impl ArchiveYaml {
+    /// Builds the archive with the defined external symbols of each member in
+    /// the archive symbol table.
+    pub fn build(self) -> Result<Vec<u8>, CoffYamlCoffBuildError> {
+        if self.msvc {
+            let mut builder = ArchiveBuilder::msvc_archive_with_capacity(self.members.len());
+            builder
+                .sorted_linker_member(!self.no_sorted_linker_member)
+                .ec_symbols(self.ec_symbols);
+            self.build_with(builder, |member, ec_exports| member.ec_exports(ec_exports))
+        } else {
+            let builder = ArchiveBuilder::gnu_archive_with_capacity(self.members.len());
+            self.build_with(builder, |_, _| {})
+        }
+    }
+
+    fn build_with<V: builder::ArchiveVariant>(
+        self,
+        mut archive_builder: ArchiveBuilder<V>,
+        add_ec_exports: impl Fn(&mut ArchiveMemberAccessor<'_, V>, Vec<String>),
+    ) -> Result<Vec<u8>, CoffYamlCoffBuildError> {
+        for member in self.members {
+            let exports = Vec::from_iter(
+                member
+                    .coff
+                    .symbols
+                    .iter()
+                    .filter(|symbol| {
+                        symbol.storage_class == IMAGE_SYM_CLASS_EXTERNAL
+                            && (symbol.section_number > 0
+                                || (symbol.section_number == IMAGE_SYM_UNDEFINED
+                                    && symbol.value != 0))
+                    })
+                    .map(|symbol| symbol.name.clone()),
+            );
+
+            let mut accessor = archive_builder.add_member(member.name, member.coff.build()?);
+            accessor.exports(exports);
+            add_ec_exports(&mut accessor, member.ec_exports);
+        }
+
+        Ok(archive_builder.build())
+    }
+}
@@ -8,9 +8,102 @@ pub enum CoffYamlCoffBuildError {
     #[error("relocation target symbol {0} does not exist")]
     MissingSymbol(String),
 
-    #[error("alignment value of {align} for section index {index} is not valid")]
-    SectionAlign { index: usize, align: usize },
+    #[error("{0}")]
+    Validation(CoffYamlValidationErrors),
 
     #[error("{0}")]
     ObjectWrite(#[from] object::write::Error),
 }
+
+/// A problem with the COFF YAML found by
+/// [`CoffYaml::validate`](super::CoffYaml::validate).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CoffYamlValidationError {
+    #[error("section {index} ({name}): alignment value of {align} is not valid")]
+    SectionAlign {
+        index: usize,
+        name: String,
+        align: usize,
+    },
+
+    #[error(
+        "section {index} ({name}): SizeOfRawData of {size} is smaller than the section data length of {data_len}"
+    )]
+    SizeOfRawData {
+        index: usize,
+        name: String,
+        size: u32,
+        data_len: usize,
+    },
+
+    #[error("section {index} ({name}): relocation {reloc} references missing symbol {symbol}")]
+    RelocationSymbol {
+        index: usize,
+        name: String,
+        reloc: usize,
+        symbol: String,
+    },
+
+    #[error(
+        "section {index} ({name}): relocation {reloc} address {address:#x} is outside of the section data"
+    )]
+    RelocationAddress {
+        index: usize,
+        name: String,
+        reloc: usize,
+        address: u32,
+    },
+
+    #[error(
+        "symbol {index} ({name}): section number {number} is out of range for {count} sections"
+    )]
+    SectionNumber {
+        index: usize,
+        name: String,
+        number: i32,
+        count: usize,
+    },
+
+    #[error("section {index} ({name}): too many line numbers ({count})")]
+    LineNumberCount {
+        index: usize,
+        name: String,
+        count: usize,
+    },
+
+    #[error("symbol {index} ({name}): aux record {record} is {len} bytes instead of 18")]
+    AuxRecordSize {
+        index: usize,
+        name: String,
+        record: usize,
+        len: usize,
+    },
+
+    #[error("symbol {index} ({name}): too many aux records ({count})")]
+    AuxRecordCount {
+        index: usize,
+        name: String,
+        count: usize,
+    },
+
+    #[error("too many sections ({0})")]
+    SectionCount(usize),
+}
+
+/// The list of problems found when validating the COFF YAML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoffYamlValidationErrors(pub Vec<CoffYamlValidationError>);
+
+impl std::fmt::Display for CoffYamlValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, error) in self.0.iter().enumerate() {
+            if idx != 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{error}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use errors::CoffYamlCoffBuildError;
+use errors::{CoffYamlCoffBuildError, CoffYamlValidationError, CoffYamlValidationErrors};
 use object::{
-    pe::{IMAGE_SYM_ABSOLUTE, IMAGE_SYM_DEBUG, IMAGE_SYM_DTYPE_SHIFT, IMAGE_SYM_UNDEFINED},
+    pe::{
+        IMAGE_SIZEOF_FILE_HEADER, IMAGE_SIZEOF_SECTION_HEADER, IMAGE_SIZEOF_SYMBOL,
+        IMAGE_SYM_ABSOLUTE, IMAGE_SYM_DEBUG, IMAGE_SYM_DTYPE_SHIFT, IMAGE_SYM_UNDEFINED,
+    },
     write::coff::{AuxSymbolSection, FileHeader, Relocation, SectionHeader, Symbol, Writer},
 };
 use serde::{Deserialize, Serialize};
@@ -13,11 +16,21 @@ mod sections;
 mod symbols;
 
 pub use header::CoffYamlHeader;
-pub use sections::{CoffYamlSection, CoffYamlSectionRelocation};
-pub use symbols::{CoffYamlAuxFunctionDefinition, CoffYamlAuxSectionDefinition, CoffYamlSymbol};
+pub use sections::{
+    CoffYamlLineNumber, CoffYamlSection, CoffYamlSectionRelocation, SectionData, SectionDataFormat,
+};
+pub use symbols::{
+    CoffYamlAuxFunctionDefinition, CoffYamlAuxRecord, CoffYamlAuxSectionDefinition, CoffYamlSymbol,
+};
 
 const SECTION_ALIGN_SHIFT: u32 = 20;
 
+/// Size of a COFF line number entry.
+const LINE_NUMBER_SIZE: usize = 6;
+
+/// Offset of the `NumberOfLinenumbers` field in a section header.
+const NUMBER_OF_LINENUMBERS_OFFSET: usize = 34;
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct CoffYaml {
     pub header: CoffYamlHeader,
@@ -26,7 +39,120 @@ pub struct CoffYaml {
 }
 
 impl CoffYaml {
+    /// Returns the problems in the COFF YAML which would prevent it from
+    /// building or produce an invalid COFF.
+    pub fn validate(&self) -> Vec<CoffYamlValidationError> {
+        let mut errors = Vec::new();
+
+        // Section numbers 0xfffe and 0xffff are reserved
+        if self.sections.len() >= 0xfffe {
+            errors.push(CoffYamlValidationError::SectionCount(self.sections.len()));
+        }
+
+        let symbol_names = HashSet::<&str>::from_iter(self.symbols.iter().map(|s| s.name.as_str()));
+
+        for (index, section) in self.sections.iter().enumerate() {
+            if let Some(align) = section
+                .alignment
+                .filter(|align| !align.is_power_of_two() || *align > 8192)
+            {
+                errors.push(CoffYamlValidationError::SectionAlign {
+                    index,
+                    name: section.name.clone(),
+                    align,
+                });
+            }
+
+            if let Some(size) = section
+                .size_of_raw_data
+                .filter(|size| (*size as usize) < section.section_data.len())
+            {
+                errors.push(CoffYamlValidationError::SizeOfRawData {
+                    index,
+                    name: section.name.clone(),
+                    size,
+                    data_len: section.section_data.len(),
+                });
+            }
+
+            if section.line_numbers.len() > u16::MAX as usize {
+                errors.push(CoffYamlValidationError::LineNumberCount {
+                    index,
+                    name: section.name.clone(),
+                    count: section.line_numbers.len(),
+                });
+            }
+
+            for (reloc_index, reloc) in section.relocations.iter().enumerate() {
+                if !symbol_names.contains(reloc.symbol_name.as_str()) {
+                    errors.push(CoffYamlValidationError::RelocationSymbol {
+                        index,
+                        name: section.name.clone(),
+                        reloc: reloc_index,
+                        symbol: reloc.symbol_name.clone(),
+                    });
+                }
+
+                if reloc.virtual_address as usize >= section.section_data.len() {
+                    errors.push(CoffYamlValidationError::RelocationAddress {
+                        index,
+                        name: section.name.clone(),
+                        reloc: reloc_index,
+                        address: reloc.virtual_address,
+                    });
+                }
+            }
+        }
+
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            let special = [IMAGE_SYM_UNDEFINED, IMAGE_SYM_ABSOLUTE, IMAGE_SYM_DEBUG];
+            if !special.contains(&symbol.section_number)
+                && !(1..=self.sections.len()).contains(&(symbol.section_number as usize))
+            {
+                errors.push(CoffYamlValidationError::SectionNumber {
+                    index,
+                    name: symbol.name.clone(),
+                    number: symbol.section_number,
+                    count: self.sections.len(),
+                });
+            }
+
+            for (record, aux) in symbol.aux_records.iter().enumerate() {
+                if aux.0.len() != IMAGE_SIZEOF_SYMBOL {
+                    errors.push(CoffYamlValidationError::AuxRecordSize {
+                        index,
+                        name: symbol.name.clone(),
+                        record,
+                        len: aux.0.len(),
+                    });
+                }
+            }
+
+            let aux_count = usize::from(symbol.file.is_some())
+                + usize::from(symbol.section_definition.is_some())
+                + usize::from(symbol.function_definition.is_some())
+                + symbol.aux_records.len();
+            if aux_count > u8::MAX as usize {
+                errors.push(CoffYamlValidationError::AuxRecordCount {
+                    index,
+                    name: symbol.name.clone(),
+                    count: aux_count,
+                });
+            }
+        }
+
+        errors
+    }
+
+    /// Builds the COFF after [validating](Self::validate) it.
     pub fn build(mut self) -> Result<Vec<u8>, CoffYamlCoffBuildError> {
+        let errors = self.validate();
+        if !errors.is_empty() {
+            return Err(CoffYamlCoffBuildError::Validation(
+                CoffYamlValidationErrors(errors),
+            ));
+        }
+
         let mut buffer = Vec::new();
 
         let mut writer = Writer::new(&mut buffer);
@@ -34,15 +160,8 @@ impl CoffYaml {
         writer.reserve_section_headers(self.sections.len().try_into()?);
 
         let mut section_headers = Vec::with_capacity(self.sections.len());
-        for (idx, section) in self.sections.iter().enumerate() {
+        for section in &self.sections {
             let alignment_flag = if let Some(alignment) = section.alignment {
-                if alignment == 0 || alignment > 8192 || (alignment != 1 && alignment % 2 != 0) {
-                    return Err(CoffYamlCoffBuildError::SectionAlign {
-                        index: idx,
-                        align: alignment,
-                    });
-                }
-
                 ((alignment as u32).ilog2() + 1) << SECTION_ALIGN_SHIFT
             } else {
                 0
@@ -69,6 +188,13 @@ impl CoffYaml {
                 writer.reserve_relocations(section.relocations.len());
         }
 
+        for (section_header, section) in section_headers.iter_mut().zip(self.sections.iter()) {
+            if !section.line_numbers.is_empty() {
+                section_header.pointer_to_linenumbers =
+                    writer.reserve(section.line_numbers.len() * LINE_NUMBER_SIZE, 1);
+            }
+        }
+
         let mut symbol_map = HashMap::with_capacity(self.symbols.len());
         let mut symbol_names = Vec::with_capacity(self.symbols.len());
 
@@ -90,6 +216,12 @@ impl CoffYaml {
             if symbol.section_definition.as_ref().is_some() {
                 writer.reserve_aux_section();
             }
+
+            if symbol.function_definition.is_some() {
+                writer.reserve_symbol_index();
+            }
+
+            writer.reserve_symbol_indices(symbol.aux_records.len().try_into()?);
         }
 
         writer.reserve_symtab_strtab();
@@ -100,8 +232,8 @@ impl CoffYaml {
             characteristics: self.header.characteristics,
         })?;
 
-        for header in section_headers {
-            writer.write_section_header(header);
+        for header in &section_headers {
+            writer.write_section_header(header.clone());
         }
 
         for section in &self.sections {
@@ -124,13 +256,26 @@ impl CoffYaml {
             }
         }
 
+        for section in &self.sections {
+            for line_number in &section.line_numbers {
+                writer.write(&line_number.address.to_le_bytes());
+                writer.write(&line_number.line_number.to_le_bytes());
+            }
+        }
+
         for (symbol_name, symbol) in symbol_names.into_iter().zip(self.symbols.iter()) {
             let aux_count = if symbol.file.is_some() { 1 } else { 0 }
                 + if symbol.section_definition.is_some() {
                     1
                 } else {
                     0
-                };
+                }
+                + if symbol.function_definition.is_some() {
+                    1
+                } else {
+                    0
+                }
+                + u8::try_from(symbol.aux_records.len())?;
 
             writer.write_symbol(Symbol {
                 name: symbol_name,
@@ -160,10 +305,32 @@ impl CoffYaml {
                     selection: aux_section.selection,
                 });
             }
+
+            if let Some(aux_function) = symbol.function_definition.as_ref() {
+                writer.write(&aux_function.tag_index.to_le_bytes());
+                writer.write(&aux_function.total_size.to_le_bytes());
+                writer.write(&aux_function.pointer_to_linenumber.to_le_bytes());
+                writer.write(&aux_function.pointer_to_next_function.to_le_bytes());
+                writer.write(&[0; 2]);
+            }
+
+            for aux in &symbol.aux_records {
+                writer.write(&aux.0);
+            }
         }
 
         writer.write_strtab();
 
+        // The writer always sets the number of line numbers to 0 in the
+        // section headers
+        for (idx, section) in self.sections.iter().enumerate() {
+            let offset = IMAGE_SIZEOF_FILE_HEADER
+                + idx * IMAGE_SIZEOF_SECTION_HEADER
+                + NUMBER_OF_LINENUMBERS_OFFSET;
+            buffer[offset..offset + 2]
+                .copy_from_slice(&(section.line_numbers.len() as u16).to_le_bytes());
+        }
+
         Ok(buffer)
     }
 }
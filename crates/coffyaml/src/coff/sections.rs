@@ -34,17 +34,128 @@ pub struct CoffYamlSection {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub alignment: Option<usize>,
 
-    #[serde(
-        deserialize_with = "hex::serde::deserialize",
-        serialize_with = "hex::serde::serialize_upper"
-    )]
-    pub section_data: Vec<u8>,
+    pub section_data: SectionData,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub size_of_raw_data: Option<u32>,
 
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub relocations: Vec<CoffYamlSectionRelocation>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub line_numbers: Vec<CoffYamlLineNumber>,
+}
+
+/// The representation of the section data in the YAML.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SectionDataFormat {
+    /// Uppercase hex string like LLVM's yaml2obj.
+    #[default]
+    Hex,
+
+    /// Sequence of byte values.
+    Bytes,
+}
+
+/// The contents of a section.
+///
+/// The data deserializes from either a hex string or a sequence of byte
+/// values and serializes back in the same [`SectionDataFormat`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SectionData {
+    pub data: Vec<u8>,
+    pub format: SectionDataFormat,
+}
+
+impl SectionData {
+    /// Returns the section data with the representation used for
+    /// serializing it.
+    pub fn with_format(mut self, format: SectionDataFormat) -> SectionData {
+        self.format = format;
+        self
+    }
+}
+
+impl From<Vec<u8>> for SectionData {
+    fn from(data: Vec<u8>) -> SectionData {
+        Self {
+            data,
+            format: SectionDataFormat::Hex,
+        }
+    }
+}
+
+impl std::ops::Deref for SectionData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl Serialize for SectionData {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.format {
+            SectionDataFormat::Hex => hex::serde::serialize_upper(&self.data, serializer),
+            SectionDataFormat::Bytes => self.data.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SectionData {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SectionDataVisitor;
+
+        impl<'de> Visitor<'de> for SectionDataVisitor {
+            type Value = SectionData;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("hex string or byte list")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(SectionData {
+                    data: hex::decode(v).map_err(serde::de::Error::custom)?,
+                    format: SectionDataFormat::Hex,
+                })
+            }
+
+            // Unquoted hex strings containing only decimal digits are parsed as
+            // integers
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                self.visit_str(&v.to_string())
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut data = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+                while let Some(byte) = seq.next_element::<u8>()? {
+                    data.push(byte);
+                }
+
+                Ok(SectionData {
+                    data,
+                    format: SectionDataFormat::Bytes,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(SectionDataVisitor)
+    }
 }
 
 fn characteristics_deserializer<'de, D>(deserializer: D) -> Result<u32, D::Error>
@@ -170,6 +281,16 @@ pub struct CoffYamlSectionRelocation {
     pub typ: u16,
 }
 
+/// A COFF line number entry.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct CoffYamlLineNumber {
+    /// The symbol table index of the function if the line number is 0.
+    /// Otherwise, the virtual address of the code for the line.
+    pub address: u32,
+    pub line_number: u16,
+}
+
 fn relocation_type_deserializer<'de, D>(deserializer: D) -> Result<u16, D::Error>
 where
     D: Deserializer<'de>,
@@ -237,12 +358,15 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{characteristics_deserializer, relocation_type_deserializer};
+    use super::{
+        SectionData, SectionDataFormat, characteristics_deserializer, relocation_type_deserializer,
+    };
     use crate::testutils;
     use object::pe::{
         IMAGE_REL_AMD64_ADDR32, IMAGE_REL_I386_DIR32, IMAGE_SCN_CNT_CODE,
         IMAGE_SCN_CNT_INITIALIZED_DATA,
     };
+    use serde::Deserialize;
 
     #[test]
     fn characteristic_scalar_deserialize() {
@@ -266,6 +390,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn section_data_deserialize() {
+        testutils::run_deserializer_tests(
+            SectionData::deserialize,
+            [
+                ("C3c3", SectionData::from(vec![0xc3, 0xc3])),
+                ("2020", SectionData::from(vec![0x20, 0x20])),
+                ("''", SectionData::from(Vec::new())),
+                (
+                    "[ 195, 0x90 ]",
+                    SectionData::from(vec![0xc3, 0x90]).with_format(SectionDataFormat::Bytes),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn section_data_serialize() {
+        let data = SectionData::from(vec![0xde, 0xad]);
+        assert_eq!(serde_yml::to_string(&data).unwrap().trim(), "DEAD");
+
+        let data = data.with_format(SectionDataFormat::Bytes);
+        assert_eq!(serde_yml::to_string(&data).unwrap(), "- 222\n- 173\n");
+    }
+
     #[test]
     fn relocation_type_string_deserialize() {
         testutils::run_deserializer_tests(
@@ -66,8 +66,23 @@ pub struct CoffYamlSymbol {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub file: Option<String>,
+
+    /// Raw aux symbol records written after the other aux records.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aux_records: Vec<CoffYamlAuxRecord>,
 }
 
+/// The 18 bytes of an aux symbol record as a hex string.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct CoffYamlAuxRecord(
+    #[serde(
+        deserialize_with = "hex::serde::deserialize",
+        serialize_with = "hex::serde::serialize_upper"
+    )]
+    pub Vec<u8>,
+);
+
 fn section_number_deserializer<'de, D>(deserializer: D) -> Result<i32, D::Error>
 where
     D: Deserializer<'de>,
@@ -648,6 +663,7 @@ mod tests {
                         function_definition: None,
                         section_definition: None,
                         file: None,
+                        aux_records: Vec::new(),
                     },
                 ),
                 (
@@ -669,6 +685,7 @@ mod tests {
                         function_definition: None,
                         section_definition: None,
                         file: None,
+                        aux_records: Vec::new(),
                     },
                 ),
             ],
@@ -712,6 +729,7 @@ mod tests {
                         }),
                         function_definition: None,
                         file: None,
+                        aux_records: Vec::new(),
                     },
                 ),
                 (
@@ -743,6 +761,7 @@ mod tests {
                         }),
                         section_definition: None,
                         file: None,
+                        aux_records: Vec::new(),
                     },
                 ),
                 (
@@ -765,6 +784,7 @@ mod tests {
                         file: Some("test.c".into()),
                         section_definition: None,
                         function_definition: None,
+                        aux_records: Vec::new(),
                     },
                 ),
             ],
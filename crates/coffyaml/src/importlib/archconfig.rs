@@ -1,10 +1,12 @@
-use object::pe::{IMAGE_FILE_MACHINE_AMD64, IMAGE_REL_AMD64_ADDR32NB};
+use object::pe::{IMAGE_FILE_MACHINE_AMD64, IMAGE_REL_AMD64_ADDR32NB, IMAGE_REL_AMD64_REL32};
 
 use super::{Architecture, errors::ImportlibYamlBuildError};
 
 pub(super) struct ArchitectureConfig {
     machine: u16,
     reloc_type: u16,
+    thunk_reloc_type: u16,
+    thunk_size: usize,
 }
 
 impl ArchitectureConfig {
@@ -13,6 +15,8 @@ impl ArchitectureConfig {
             Architecture::X86_64 => Self {
                 machine: IMAGE_FILE_MACHINE_AMD64,
                 reloc_type: IMAGE_REL_AMD64_ADDR32NB,
+                thunk_reloc_type: IMAGE_REL_AMD64_REL32,
+                thunk_size: 8,
             },
             _ => return Err(ImportlibYamlBuildError::UnsupportArchitecture(arch)),
         })
@@ -27,4 +31,16 @@ impl ArchitectureConfig {
     pub fn reloc_type(&self) -> u16 {
         self.reloc_type
     }
+
+    /// Relocation type for the jump in an import thunk.
+    #[inline]
+    pub fn thunk_reloc_type(&self) -> u16 {
+        self.thunk_reloc_type
+    }
+
+    /// Size of an import lookup table entry.
+    #[inline]
+    pub fn thunk_size(&self) -> usize {
+        self.thunk_size
+    }
 }
@@ -1,8 +1,8 @@
 use object::pe::{
     IMAGE_FILE_MACHINE_UNKNOWN, IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_MEM_READ,
     IMAGE_SCN_MEM_WRITE, IMAGE_SYM_CLASS_EXTERNAL, IMAGE_SYM_CLASS_SECTION, IMAGE_SYM_CLASS_STATIC,
-    IMPORT_OBJECT_CODE, IMPORT_OBJECT_NAME, IMPORT_OBJECT_NAME_MASK, IMPORT_OBJECT_NAME_SHIFT,
-    IMPORT_OBJECT_TYPE_MASK,
+    IMPORT_OBJECT_CODE, IMPORT_OBJECT_CONST, IMPORT_OBJECT_DATA, IMPORT_OBJECT_NAME,
+    IMPORT_OBJECT_NAME_MASK, IMPORT_OBJECT_NAME_SHIFT, IMPORT_OBJECT_TYPE_MASK,
 };
 
 use crate::{
@@ -17,7 +17,8 @@ impl ImportlibYaml {
         let cfg = ArchitectureConfig::new(arch)?;
 
         // Import descriptor, NULL import descriptor, NULL thunk data, import members
-        let member_count = 3 + self.exports.len();
+        let member_count =
+            3 + self.exports.len() + self.data_exports.len() + self.const_exports.len();
 
         let mut archive_builder = ArchiveBuilder::msvc_archive_with_capacity(member_count);
 
@@ -51,7 +52,7 @@ impl ImportlibYaml {
                             | IMAGE_SCN_MEM_READ
                             | IMAGE_SCN_MEM_WRITE,
                         alignment: Some(4),
-                        section_data: vec![0u8; 20],
+                        section_data: vec![0u8; 20].into(),
                         size_of_raw_data: None,
                         relocations: vec![
                             CoffYamlSectionRelocation {
@@ -70,6 +71,7 @@ impl ImportlibYaml {
                                 typ: cfg.reloc_type(),
                             },
                         ],
+                        line_numbers: Vec::new(),
                     },
                     CoffYamlSection {
                         name: ".idata$6".to_string(),
@@ -77,7 +79,7 @@ impl ImportlibYaml {
                             | IMAGE_SCN_MEM_READ
                             | IMAGE_SCN_MEM_WRITE,
                         alignment: Some(2),
-                        section_data: format!("{}\0", &self.library).as_bytes().to_vec(),
+                        section_data: format!("{}\0", &self.library).as_bytes().to_vec().into(),
                         ..Default::default()
                     },
                 ],
@@ -145,7 +147,7 @@ impl ImportlibYaml {
                         | IMAGE_SCN_MEM_READ
                         | IMAGE_SCN_MEM_WRITE,
                     alignment: Some(4),
-                    section_data: vec![0u8; 20],
+                    section_data: vec![0u8; 20].into(),
                     ..Default::default()
                 }],
                 symbols: vec![CoffYamlSymbol {
@@ -179,7 +181,7 @@ impl ImportlibYaml {
                             | IMAGE_SCN_MEM_READ
                             | IMAGE_SCN_MEM_WRITE,
                         alignment: Some(8),
-                        section_data: vec![0u8; 8],
+                        section_data: vec![0u8; 8].into(),
                         ..Default::default()
                     },
                     CoffYamlSection {
@@ -188,7 +190,7 @@ impl ImportlibYaml {
                             | IMAGE_SCN_MEM_READ
                             | IMAGE_SCN_MEM_WRITE,
                         alignment: Some(8),
-                        section_data: vec![0u8; 8],
+                        section_data: vec![0u8; 8].into(),
                         ..Default::default()
                     },
                 ],
@@ -209,23 +211,49 @@ impl ImportlibYaml {
         member.export(null_thunk_data_name);
 
         // Add each import COFF
-        for export in self.exports {
+        let exports = self
+            .exports
+            .into_iter()
+            .map(|export| (export, IMPORT_OBJECT_CODE))
+            .chain(
+                self.data_exports
+                    .into_iter()
+                    .map(|export| (export, IMPORT_OBJECT_DATA)),
+            )
+            .chain(
+                self.const_exports
+                    .into_iter()
+                    .map(|export| (export, IMPORT_OBJECT_CONST)),
+            );
+
+        for (export, import_type) in exports {
             let mut member = archive_builder.add_member(
                 &self.library,
-                build_import_coff(cfg.machine(), &export, &self.library),
+                build_import_coff(cfg.machine(), &export, &self.library, import_type),
             );
             member.date(0);
             member.uid(0);
             member.gid(0);
             member.mode(644);
-            member.exports([format!("__imp_{}", &export), export]);
+
+            // Data imports are only referenced through the '__imp_' symbol
+            if import_type == IMPORT_OBJECT_DATA {
+                member.export(format!("__imp_{}", &export));
+            } else {
+                member.exports([format!("__imp_{}", &export), export]);
+            }
         }
 
         Ok(archive_builder.build())
     }
 }
 
-fn build_import_coff(machine: u16, export: impl AsRef<str>, dllname: impl AsRef<str>) -> Vec<u8> {
+fn build_import_coff(
+    machine: u16,
+    export: impl AsRef<str>,
+    dllname: impl AsRef<str>,
+    import_type: u16,
+) -> Vec<u8> {
     let mut buffer =
         Vec::with_capacity(20 + export.as_ref().len() + 1 + dllname.as_ref().len() + 1);
 
@@ -245,8 +273,8 @@ fn build_import_coff(machine: u16, export: impl AsRef<str>, dllname: impl AsRef<
     // Import metadata
     let mut meta: u16 = 0;
 
-    // Code import type
-    meta |= IMPORT_OBJECT_CODE & IMPORT_OBJECT_TYPE_MASK;
+    // Import type
+    meta |= import_type & IMPORT_OBJECT_TYPE_MASK;
 
     // Import by name
     meta |= (IMPORT_OBJECT_NAME & IMPORT_OBJECT_NAME_MASK) << IMPORT_OBJECT_NAME_SHIFT;
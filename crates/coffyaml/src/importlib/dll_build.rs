@@ -0,0 +1,138 @@
+use object::{
+    LittleEndian as LE, U16, U32,
+    pe::{
+        IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE, IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA,
+        IMAGE_DLLCHARACTERISTICS_NX_COMPAT, IMAGE_FILE_DLL, IMAGE_FILE_EXECUTABLE_IMAGE,
+        IMAGE_FILE_LARGE_ADDRESS_AWARE, IMAGE_NUMBEROF_DIRECTORY_ENTRIES,
+        IMAGE_SUBSYSTEM_WINDOWS_CUI, ImageExportDirectory,
+    },
+    pod::bytes_of,
+    write::pe::{NtHeaders, Writer},
+};
+
+use super::{Architecture, ArchitectureConfig, ImportlibYaml, errors::ImportlibYamlBuildError};
+
+impl ImportlibYaml {
+    /// Builds a PE DLL exporting the names from the exports and data exports
+    /// lists.
+    ///
+    /// Every export points to the same `ret` instruction in the `.text`
+    /// section. Data exports point to an 8 byte `.data` section.
+    pub fn build_dll(self, arch: Architecture) -> Result<Vec<u8>, ImportlibYamlBuildError> {
+        let cfg = ArchitectureConfig::new(arch)?;
+
+        // The name pointer table needs to be sorted
+        let mut exports = Vec::from_iter(
+            self.exports
+                .into_iter()
+                .map(|export| (export, false))
+                .chain(self.data_exports.into_iter().map(|export| (export, true))),
+        );
+        exports.sort();
+
+        let export_count = exports.len() as u32;
+        let dll_name_offset = std::mem::size_of::<ImageExportDirectory>() as u32
+            + export_count * 4 // Export address table
+            + export_count * 4 // Name pointer table
+            + export_count * 2; // Ordinal table
+        let edata_size = dll_name_offset
+            + self.library.len() as u32
+            + 1
+            + exports
+                .iter()
+                .map(|(export, _)| export.len() as u32 + 1)
+                .sum::<u32>();
+
+        let mut buffer = Vec::new();
+        let mut writer = Writer::new(true, 0x1000, 0x200, &mut buffer);
+
+        writer.reserve_dos_header_and_stub();
+        writer.reserve_nt_headers(IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+        let has_data = exports.iter().any(|(_, data)| *data);
+        writer.reserve_section_headers(if has_data { 3 } else { 2 });
+
+        let text_range = writer.reserve_text_section(1);
+        let data_range = has_data.then(|| writer.reserve_data_section(8, 8));
+        let edata_range = writer.reserve_edata_section(edata_size);
+
+        let edata_rva = edata_range.virtual_address;
+        let functions_rva = edata_rva + std::mem::size_of::<ImageExportDirectory>() as u32;
+        let names_rva = functions_rva + export_count * 4;
+        let ordinals_rva = names_rva + export_count * 4;
+
+        let mut edata = Vec::with_capacity(edata_size as usize);
+        edata.extend_from_slice(bytes_of(&ImageExportDirectory {
+            characteristics: U32::new(LE, 0),
+            time_date_stamp: U32::new(LE, 0),
+            major_version: U16::new(LE, 0),
+            minor_version: U16::new(LE, 0),
+            name: U32::new(LE, edata_rva + dll_name_offset),
+            base: U32::new(LE, 1),
+            number_of_functions: U32::new(LE, export_count),
+            number_of_names: U32::new(LE, export_count),
+            address_of_functions: U32::new(LE, functions_rva),
+            address_of_names: U32::new(LE, names_rva),
+            address_of_name_ordinals: U32::new(LE, ordinals_rva),
+        }));
+
+        for (_, data) in &exports {
+            let address = match data_range {
+                Some(data_range) if *data => data_range.virtual_address,
+                _ => text_range.virtual_address,
+            };
+            edata.extend_from_slice(&address.to_le_bytes());
+        }
+
+        let mut name_rva = edata_rva + dll_name_offset + self.library.len() as u32 + 1;
+        for (export, _) in &exports {
+            edata.extend_from_slice(&name_rva.to_le_bytes());
+            name_rva += export.len() as u32 + 1;
+        }
+
+        for ordinal in 0..export_count as u16 {
+            edata.extend_from_slice(&ordinal.to_le_bytes());
+        }
+
+        for name in std::iter::once(&self.library).chain(exports.iter().map(|(export, _)| export)) {
+            edata.extend_from_slice(name.as_bytes());
+            edata.push(0);
+        }
+
+        writer.write_dos_header_and_stub()?;
+        writer.write_nt_headers(NtHeaders {
+            machine: cfg.machine(),
+            time_date_stamp: 0,
+            characteristics: IMAGE_FILE_EXECUTABLE_IMAGE
+                | IMAGE_FILE_LARGE_ADDRESS_AWARE
+                | IMAGE_FILE_DLL,
+            major_linker_version: 14,
+            minor_linker_version: 0,
+            address_of_entry_point: 0,
+            image_base: 0x180000000,
+            major_operating_system_version: 6,
+            minor_operating_system_version: 0,
+            major_image_version: 0,
+            minor_image_version: 0,
+            major_subsystem_version: 6,
+            minor_subsystem_version: 0,
+            subsystem: IMAGE_SUBSYSTEM_WINDOWS_CUI,
+            dll_characteristics: IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA
+                | IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE
+                | IMAGE_DLLCHARACTERISTICS_NX_COMPAT,
+            size_of_stack_reserve: 0x100000,
+            size_of_stack_commit: 0x1000,
+            size_of_heap_reserve: 0x100000,
+            size_of_heap_commit: 0x1000,
+        });
+        writer.write_section_headers();
+
+        // ret
+        writer.write_section(text_range.file_offset, &[0xc3]);
+        if let Some(data_range) = data_range {
+            writer.write_section(data_range.file_offset, &[0; 8]);
+        }
+        writer.write_section(edata_range.file_offset, &edata);
+
+        Ok(buffer)
+    }
+}
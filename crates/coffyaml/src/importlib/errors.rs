@@ -4,4 +4,7 @@ use super::Architecture;
 pub enum ImportlibYamlBuildError {
     #[error("architecture {0:?} is not supported")]
     UnsupportArchitecture(Architecture),
+
+    #[error("{0}")]
+    Write(#[from] object::write::Error),
 }
@@ -1,12 +1,392 @@
-use crate::archive::builder::ArchiveBuilder;
+use object::pe::{
+    IMAGE_SCN_CNT_CODE, IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_CNT_UNINITIALIZED_DATA,
+    IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE, IMAGE_SYM_CLASS_EXTERNAL,
+    IMAGE_SYM_CLASS_STATIC,
+};
+
+use crate::{
+    archive::builder::ArchiveBuilder,
+    coff::{CoffYaml, CoffYamlHeader, CoffYamlSection, CoffYamlSectionRelocation, CoffYamlSymbol},
+};
 
 use super::{Architecture, ArchitectureConfig, ImportlibYaml, errors::ImportlibYamlBuildError};
 
+const IDATA_CHARACTERISTICS: u32 =
+    IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE;
+
+/// An export entry for a legacy import library.
+struct LegacyExport<'a> {
+    /// The public symbol name.
+    name: &'a str,
+
+    /// The name imported from the DLL.
+    import: &'a str,
+
+    /// If this is a data import without an import thunk.
+    data: bool,
+}
+
+impl<'a> LegacyExport<'a> {
+    /// Parses an export using the syntax from module-definition files.
+    ///
+    /// `name`, `alias==name` and a trailing `DATA` keyword are supported.
+    fn parse(export: &'a str) -> LegacyExport<'a> {
+        let mut parts = export.split_whitespace();
+        let symbol = parts.next().unwrap_or_default();
+        let data = parts.any(|keyword| keyword == "DATA");
+
+        let (name, import) = symbol.split_once("==").unwrap_or((symbol, symbol));
+
+        LegacyExport { name, import, data }
+    }
+}
+
 impl ImportlibYaml {
+    /// Builds a GNU import library with the member layout used by dlltool.
+    ///
+    /// Exports can be specified as `alias==name` to import `name` under a
+    /// different public symbol and suffixed with `DATA` for data imports.
     pub fn build_legacy(self, arch: Architecture) -> Result<Vec<u8>, ImportlibYamlBuildError> {
-        let _cfg = ArchitectureConfig::new(arch)?;
+        let cfg = ArchitectureConfig::new(arch)?;
+
+        // Head member, tail member, import members
+        let member_count = 2 + self.exports.len();
+
+        let mut archive_builder = ArchiveBuilder::gnu_archive_with_capacity(member_count);
+
+        let library_label = self
+            .library
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+
+        let head_name = format!("_head_{library_label}");
+        let iname = format!("{library_label}_iname");
+
+        // Add the head member
+        let mut member = archive_builder.add_member(
+            format!("{library_label}h.o"),
+            CoffYaml {
+                header: CoffYamlHeader {
+                    machine: cfg.machine(),
+                    characteristics: 0,
+                },
+                sections: [
+                    empty_sections(),
+                    vec![
+                        CoffYamlSection {
+                            name: ".idata$2".to_string(),
+                            characteristics: IDATA_CHARACTERISTICS,
+                            alignment: Some(4),
+                            section_data: vec![0u8; 20].into(),
+                            size_of_raw_data: None,
+                            relocations: vec![
+                                CoffYamlSectionRelocation {
+                                    virtual_address: 0,
+                                    symbol_name: ".idata$4".to_string(),
+                                    typ: cfg.reloc_type(),
+                                },
+                                CoffYamlSectionRelocation {
+                                    virtual_address: 12,
+                                    symbol_name: iname.clone(),
+                                    typ: cfg.reloc_type(),
+                                },
+                                CoffYamlSectionRelocation {
+                                    virtual_address: 16,
+                                    symbol_name: ".idata$5".to_string(),
+                                    typ: cfg.reloc_type(),
+                                },
+                            ],
+                            line_numbers: Vec::new(),
+                        },
+                        CoffYamlSection {
+                            name: ".idata$5".to_string(),
+                            characteristics: IDATA_CHARACTERISTICS,
+                            alignment: Some(4),
+                            ..Default::default()
+                        },
+                        CoffYamlSection {
+                            name: ".idata$4".to_string(),
+                            characteristics: IDATA_CHARACTERISTICS,
+                            alignment: Some(4),
+                            ..Default::default()
+                        },
+                    ],
+                ]
+                .concat(),
+                symbols: [
+                    section_symbols([".text", ".data", ".bss", ".idata$2", ".idata$5", ".idata$4"]),
+                    vec![
+                        CoffYamlSymbol {
+                            name: head_name.clone(),
+                            section_number: 4,
+                            storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+                            ..Default::default()
+                        },
+                        CoffYamlSymbol {
+                            name: iname.clone(),
+                            storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+                            ..Default::default()
+                        },
+                    ],
+                ]
+                .concat(),
+            }
+            .build()
+            .unwrap(),
+        );
+        member.date(0);
+        member.uid(0);
+        member.gid(0);
+        member.mode(644);
+        member.export(&head_name);
+
+        // Add the tail member
+        let mut member = archive_builder.add_member(
+            format!("{library_label}t.o"),
+            CoffYaml {
+                header: CoffYamlHeader {
+                    machine: cfg.machine(),
+                    characteristics: 0,
+                },
+                sections: [
+                    empty_sections(),
+                    vec![
+                        CoffYamlSection {
+                            name: ".idata$4".to_string(),
+                            characteristics: IDATA_CHARACTERISTICS,
+                            alignment: Some(4),
+                            section_data: vec![0u8; cfg.thunk_size()].into(),
+                            ..Default::default()
+                        },
+                        CoffYamlSection {
+                            name: ".idata$5".to_string(),
+                            characteristics: IDATA_CHARACTERISTICS,
+                            alignment: Some(4),
+                            section_data: vec![0u8; cfg.thunk_size()].into(),
+                            ..Default::default()
+                        },
+                        CoffYamlSection {
+                            name: ".idata$7".to_string(),
+                            characteristics: IDATA_CHARACTERISTICS,
+                            alignment: Some(4),
+                            section_data: padded_string(&self.library).into(),
+                            ..Default::default()
+                        },
+                    ],
+                ]
+                .concat(),
+                symbols: [
+                    section_symbols([".text", ".data", ".bss", ".idata$4", ".idata$5", ".idata$7"]),
+                    vec![CoffYamlSymbol {
+                        name: iname.clone(),
+                        section_number: 6,
+                        storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+                        ..Default::default()
+                    }],
+                ]
+                .concat(),
+            }
+            .build()
+            .unwrap(),
+        );
+        member.date(0);
+        member.uid(0);
+        member.gid(0);
+        member.mode(644);
+        member.export(&iname);
+
+        // Add each import COFF
+        for (idx, export) in self.exports.iter().enumerate() {
+            let export = LegacyExport::parse(export);
+
+            let mut member = archive_builder.add_member(
+                format!("{library_label}s{idx:05}.o"),
+                build_legacy_import_coff(&cfg, &export, &head_name),
+            );
+            member.date(0);
+            member.uid(0);
+            member.gid(0);
+            member.mode(644);
+            member.export(format!("__imp_{}", export.name));
+            if !export.data {
+                member.export(export.name);
+            }
+        }
 
-        let _archive_builder = ArchiveBuilder::gnu_archive_with_capacity(0);
-        todo!()
+        Ok(archive_builder.build())
     }
 }
+
+/// Builds the symbol member for an export.
+///
+/// Data imports do not have an import thunk and omit the `.text` section.
+fn build_legacy_import_coff(
+    cfg: &ArchitectureConfig,
+    export: &LegacyExport,
+    head: &str,
+) -> Vec<u8> {
+    let mut sections = Vec::with_capacity(7);
+    let mut symbols = Vec::with_capacity(10);
+
+    if !export.data {
+        sections.push(CoffYamlSection {
+            name: ".text".to_string(),
+            characteristics: IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ,
+            alignment: Some(4),
+            // jmp *__imp_<name>(%rip); nop; nop
+            section_data: vec![0xff, 0x25, 0x00, 0x00, 0x00, 0x00, 0x90, 0x90].into(),
+            size_of_raw_data: None,
+            relocations: vec![CoffYamlSectionRelocation {
+                virtual_address: 2,
+                symbol_name: format!("__imp_{}", export.name),
+                typ: cfg.thunk_reloc_type(),
+            }],
+            line_numbers: Vec::new(),
+        });
+    }
+
+    sections.extend(empty_sections().into_iter().skip(1));
+    sections.extend([
+        CoffYamlSection {
+            name: ".idata$7".to_string(),
+            characteristics: IDATA_CHARACTERISTICS,
+            alignment: Some(4),
+            section_data: vec![0u8; 4].into(),
+            size_of_raw_data: None,
+            relocations: vec![CoffYamlSectionRelocation {
+                virtual_address: 0,
+                symbol_name: head.to_string(),
+                typ: cfg.reloc_type(),
+            }],
+            line_numbers: Vec::new(),
+        },
+        CoffYamlSection {
+            name: ".idata$5".to_string(),
+            characteristics: IDATA_CHARACTERISTICS,
+            alignment: Some(4),
+            section_data: vec![0u8; cfg.thunk_size()].into(),
+            size_of_raw_data: None,
+            relocations: vec![CoffYamlSectionRelocation {
+                virtual_address: 0,
+                symbol_name: ".idata$6".to_string(),
+                typ: cfg.reloc_type(),
+            }],
+            line_numbers: Vec::new(),
+        },
+        CoffYamlSection {
+            name: ".idata$4".to_string(),
+            characteristics: IDATA_CHARACTERISTICS,
+            alignment: Some(4),
+            section_data: vec![0u8; cfg.thunk_size()].into(),
+            size_of_raw_data: None,
+            relocations: vec![CoffYamlSectionRelocation {
+                virtual_address: 0,
+                symbol_name: ".idata$6".to_string(),
+                typ: cfg.reloc_type(),
+            }],
+            line_numbers: Vec::new(),
+        },
+        CoffYamlSection {
+            name: ".idata$6".to_string(),
+            characteristics: IDATA_CHARACTERISTICS,
+            alignment: Some(2),
+            // Hint followed by the import name
+            section_data: [vec![0u8; 2], padded_string(export.import)].concat().into(),
+            ..Default::default()
+        },
+    ]);
+
+    symbols.extend(section_symbols(
+        sections.iter().map(|section| section.name.as_str()),
+    ));
+
+    let section_number = |name: &str| {
+        sections
+            .iter()
+            .position(|section| section.name == name)
+            .map(|idx| idx as i32 + 1)
+            .unwrap_or_default()
+    };
+
+    if !export.data {
+        symbols.push(CoffYamlSymbol {
+            name: export.name.to_string(),
+            section_number: section_number(".text"),
+            storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+            ..Default::default()
+        });
+    }
+
+    symbols.extend([
+        CoffYamlSymbol {
+            name: format!("__imp_{}", export.name),
+            section_number: section_number(".idata$5"),
+            storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+            ..Default::default()
+        },
+        CoffYamlSymbol {
+            name: head.to_string(),
+            storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+            ..Default::default()
+        },
+    ]);
+
+    CoffYaml {
+        header: CoffYamlHeader {
+            machine: cfg.machine(),
+            characteristics: 0,
+        },
+        sections,
+        symbols,
+    }
+    .build()
+    .unwrap()
+}
+
+/// Returns the empty `.text`, `.data` and `.bss` sections.
+fn empty_sections() -> Vec<CoffYamlSection> {
+    vec![
+        CoffYamlSection {
+            name: ".text".to_string(),
+            characteristics: IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ,
+            alignment: Some(4),
+            ..Default::default()
+        },
+        CoffYamlSection {
+            name: ".data".to_string(),
+            characteristics: IDATA_CHARACTERISTICS,
+            alignment: Some(4),
+            ..Default::default()
+        },
+        CoffYamlSection {
+            name: ".bss".to_string(),
+            characteristics: IMAGE_SCN_CNT_UNINITIALIZED_DATA
+                | IMAGE_SCN_MEM_READ
+                | IMAGE_SCN_MEM_WRITE,
+            alignment: Some(4),
+            ..Default::default()
+        },
+    ]
+}
+
+/// Returns the static section symbols for the list of section names.
+fn section_symbols<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<CoffYamlSymbol> {
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(idx, name)| CoffYamlSymbol {
+            name: name.to_string(),
+            section_number: idx as i32 + 1,
+            storage_class: IMAGE_SYM_CLASS_STATIC,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Returns the nul-terminated string padded to an even length.
+fn padded_string(s: &str) -> Vec<u8> {
+    let mut data = format!("{s}\0").into_bytes();
+    data.resize(data.len().next_multiple_of(2), 0);
+    data
+}
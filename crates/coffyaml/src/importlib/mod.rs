@@ -5,6 +5,7 @@ pub use object::Architecture;
 
 mod archconfig;
 mod build;
+mod dll_build;
 pub mod errors;
 mod legacy_build;
 
@@ -13,4 +14,15 @@ mod legacy_build;
 pub struct ImportlibYaml {
     pub library: String,
     pub exports: Vec<String>,
+
+    /// Exports imported as data. Only the `__imp_` symbol is added to the
+    /// symbol table. Only supported by [`ImportlibYaml::build`] and
+    /// [`ImportlibYaml::build_dll`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data_exports: Vec<String>,
+
+    /// Exports imported as constants. Both symbols are added to the symbol
+    /// table. Only supported by [`ImportlibYaml::build`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub const_exports: Vec<String>,
 }
@@ -0,0 +1,164 @@
+use coffyaml::archive::ArchiveYaml;
+use object::{
+    archive::Header,
+    read::archive::{ArchiveFile, ArchiveKind, ArchiveOffset},
+};
+
+const ARCHIVE_YAML: &str = include_str!("archive.yaml");
+
+/// Returns the raw names and data of the members in the archive including the
+/// linker members.
+fn raw_members(archive: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut members = Vec::new();
+    let mut offset = object::archive::MAGIC.len();
+
+    while offset < archive.len() {
+        let header = &archive[offset..offset + std::mem::size_of::<Header>()];
+        let name = header[..16].trim_ascii_end();
+        let size: usize = std::str::from_utf8(&header[std::mem::offset_of!(Header, size)..][..10])
+            .unwrap()
+            .trim_end()
+            .parse()
+            .unwrap();
+
+        let data_start = offset + header.len();
+        members.push((name, &archive[data_start..data_start + size]));
+        offset = (data_start + size).next_multiple_of(2);
+    }
+
+    members
+}
+
+/// Returns the symbols in the EC symbol map with the offsets of the members
+/// from the second linker member.
+fn ec_symbols(archive: &[u8]) -> Vec<(String, u64)> {
+    let members = raw_members(archive);
+
+    let (name, sorted_armap) = members[1];
+    assert_eq!(name, b"/");
+
+    let (_, ec_map) = members
+        .iter()
+        .find(|(name, _)| *name == b"/<ECSYMBOLS>/")
+        .expect("archive should have an EC symbol map");
+
+    let member_offset = |idx: u16| {
+        let start = 4 * idx as usize;
+        u32::from_le_bytes(sorted_armap[start..start + 4].try_into().unwrap()) as u64
+    };
+
+    let symbol_count = u32::from_le_bytes(ec_map[..4].try_into().unwrap()) as usize;
+    let (indices, strings) = ec_map[4..].split_at(2 * symbol_count);
+
+    indices
+        .chunks(2)
+        .map(|idx| u16::from_le_bytes(idx.try_into().unwrap()))
+        .zip(strings.split(|b| *b == 0))
+        .map(|(idx, name)| {
+            (
+                String::from_utf8(name.to_vec()).unwrap(),
+                member_offset(idx),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn msvc_archive_kind() {
+    let parsed_yaml: ArchiveYaml = serde_yml::from_str(ARCHIVE_YAML).unwrap();
+    let built = parsed_yaml.build().unwrap();
+    let parsed_archive = ArchiveFile::parse(built.as_slice()).unwrap();
+
+    assert_eq!(parsed_archive.kind(), ArchiveKind::Coff);
+}
+
+#[test]
+fn msvc_archive_without_sorted_member() {
+    let mut parsed_yaml: ArchiveYaml = serde_yml::from_str(ARCHIVE_YAML).unwrap();
+    parsed_yaml.no_sorted_linker_member = true;
+
+    let built = parsed_yaml.build().unwrap();
+    let parsed_archive = ArchiveFile::parse(built.as_slice()).unwrap();
+
+    // Only the first linker member is left which makes it look like a GNU
+    // archive
+    assert_eq!(parsed_archive.kind(), ArchiveKind::Gnu);
+    assert!(
+        !built
+            .windows(b"/<ECSYMBOLS>/".len())
+            .any(|name| name == b"/<ECSYMBOLS>/"),
+        "EC symbol map should not be written without the second linker member"
+    );
+
+    let symbols = Vec::from_iter(
+        parsed_archive
+            .symbols()
+            .unwrap()
+            .unwrap()
+            .map(|symbol| symbol.unwrap().name().to_vec()),
+    );
+    assert_eq!(symbols, [b"first".to_vec(), b"second".to_vec()]);
+}
+
+#[test]
+fn msvc_archive_members_after_ec_symbols() {
+    let parsed_yaml: ArchiveYaml = serde_yml::from_str(ARCHIVE_YAML).unwrap();
+    let built = parsed_yaml.build().unwrap();
+    let parsed_archive = ArchiveFile::parse(built.as_slice()).unwrap();
+
+    let names = Vec::from_iter(
+        parsed_archive
+            .members()
+            .map(|member| member.unwrap().name().to_vec()),
+    );
+    assert_eq!(names, [b"first.obj".to_vec(), b"second.obj".to_vec()]);
+}
+
+#[test]
+fn msvc_archive_symbols_resolve_members() {
+    let parsed_yaml: ArchiveYaml = serde_yml::from_str(ARCHIVE_YAML).unwrap();
+    let built = parsed_yaml.build().unwrap();
+    let parsed_archive = ArchiveFile::parse(built.as_slice()).unwrap();
+
+    for symbol in parsed_archive.symbols().unwrap().unwrap() {
+        let symbol = symbol.unwrap();
+        let member = parsed_archive.member(symbol.offset()).unwrap();
+
+        let expected: &[u8] = match symbol.name() {
+            b"first" => b"first.obj",
+            b"second" => b"second.obj",
+            name => panic!("unexpected symbol {:?}", String::from_utf8_lossy(name)),
+        };
+        assert_eq!(member.name(), expected);
+    }
+}
+
+#[test]
+fn msvc_archive_ec_symbols() {
+    let parsed_yaml: ArchiveYaml = serde_yml::from_str(ARCHIVE_YAML).unwrap();
+    let built = parsed_yaml.build().unwrap();
+    let parsed_archive = ArchiveFile::parse(built.as_slice()).unwrap();
+
+    let member_name = |offset: u64| {
+        parsed_archive
+            .member(ArchiveOffset(offset))
+            .unwrap()
+            .name()
+            .to_vec()
+    };
+
+    let symbols = Vec::from_iter(
+        ec_symbols(&built)
+            .into_iter()
+            .map(|(name, offset)| (name, member_name(offset))),
+    );
+
+    assert_eq!(
+        symbols,
+        [
+            ("#first".into(), b"first.obj".to_vec()),
+            ("#second".into(), b"second.obj".to_vec()),
+            ("__imp_aux_second".into(), b"second.obj".to_vec()),
+        ]
+    );
+}
@@ -1,5 +1,8 @@
-use coffyaml::coff::CoffYaml;
-use object::{coff::CoffFile, pe};
+use coffyaml::coff::{
+    CoffYaml, SectionDataFormat,
+    errors::{CoffYamlCoffBuildError, CoffYamlValidationError},
+};
+use object::{LittleEndian, Object, ObjectSymbol, ReadRef, SectionIndex, coff::CoffFile, pe};
 
 const COFF_YAML: &str = include_str!("coff.yaml");
 
@@ -20,3 +23,194 @@ fn coff_sanity_object_can_parse() {
     let built = parsed_yaml.build().unwrap();
     assert!(CoffFile::<_, pe::ImageFileHeader>::parse(built.as_slice()).is_ok());
 }
+
+#[test]
+fn coff_section_data_bytes() {
+    let parsed: CoffYaml = serde_yml::from_str(COFF_YAML).unwrap();
+
+    let mut bytes_yaml = parsed.clone();
+    for section in bytes_yaml.sections.iter_mut() {
+        section.section_data.format = SectionDataFormat::Bytes;
+    }
+
+    let serialized = serde_yml::to_string(&bytes_yaml).unwrap();
+    let reparsed: CoffYaml = serde_yml::from_str(&serialized).unwrap();
+    assert_eq!(reparsed.build().unwrap(), parsed.build().unwrap());
+}
+
+#[test]
+fn coff_validation_problems() {
+    let yaml = r#"
+header:
+  Machine:         IMAGE_FILE_MACHINE_AMD64
+  Characteristics: [  ]
+sections:
+  - Name:            .text
+    Characteristics: [ IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ ]
+    Alignment:       6
+    SectionData:     E800000000C3
+    Relocations:
+      - VirtualAddress:  1
+        SymbolName:      missing
+        Type:            IMAGE_REL_AMD64_REL32
+      - VirtualAddress:  8
+        SymbolName:      go
+        Type:            IMAGE_REL_AMD64_REL32
+symbols:
+  - Name:            go
+    Value:           0
+    SectionNumber:   2
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_FUNCTION
+    StorageClass:    IMAGE_SYM_CLASS_EXTERNAL
+"#;
+
+    let parsed: CoffYaml = serde_yml::from_str(yaml).unwrap();
+    assert_eq!(
+        parsed.validate(),
+        [
+            CoffYamlValidationError::SectionAlign {
+                index: 0,
+                name: ".text".to_string(),
+                align: 6,
+            },
+            CoffYamlValidationError::RelocationSymbol {
+                index: 0,
+                name: ".text".to_string(),
+                reloc: 0,
+                symbol: "missing".to_string(),
+            },
+            CoffYamlValidationError::RelocationAddress {
+                index: 0,
+                name: ".text".to_string(),
+                reloc: 1,
+                address: 8,
+            },
+            CoffYamlValidationError::SectionNumber {
+                index: 0,
+                name: "go".to_string(),
+                number: 2,
+                count: 1,
+            },
+        ]
+    );
+
+    let err = parsed.build().unwrap_err();
+    assert!(matches!(err, CoffYamlCoffBuildError::Validation(_)));
+    assert_eq!(
+        err.to_string().lines().next(),
+        Some("section 0 (.text): alignment value of 6 is not valid")
+    );
+}
+
+#[test]
+fn coff_line_numbers_and_aux_records() {
+    let yaml = r#"
+header:
+  Machine:         IMAGE_FILE_MACHINE_AMD64
+  Characteristics: [  ]
+sections:
+  - Name:            .text
+    Characteristics: [ IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ ]
+    Alignment:       16
+    SectionData:     90C3
+    LineNumbers:
+      - Address:         1
+        LineNumber:      0
+      - Address:         1
+        LineNumber:      3
+symbols:
+  - Name:            go
+    Value:           0
+    SectionNumber:   1
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_FUNCTION
+    StorageClass:    IMAGE_SYM_CLASS_EXTERNAL
+  - Name:            weak
+    Value:           0
+    SectionNumber:   0
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_NULL
+    StorageClass:    IMAGE_SYM_CLASS_WEAK_EXTERNAL
+    AuxRecords:
+      - '000000000300000000000000000000000000'
+"#;
+
+    let parsed: CoffYaml = serde_yml::from_str(yaml).unwrap();
+    let built = parsed.build().unwrap();
+    let coff: CoffFile = CoffFile::parse(built.as_slice()).unwrap();
+
+    let text = coff
+        .section_by_index(SectionIndex(1))
+        .unwrap()
+        .coff_section();
+    assert_eq!(text.number_of_linenumbers.get(LittleEndian), 2);
+
+    let line_numbers = built
+        .as_slice()
+        .read_slice_at::<pe::ImageLinenumber>(
+            text.pointer_to_linenumbers.get(LittleEndian).into(),
+            2,
+        )
+        .unwrap();
+    assert_eq!(
+        line_numbers
+            .iter()
+            .map(|line| (
+                line.symbol_table_index_or_virtual_address.get(LittleEndian),
+                line.linenumber.get(LittleEndian)
+            ))
+            .collect::<Vec<_>>(),
+        [(1, 0), (1, 3)]
+    );
+
+    let weak = coff.symbol_by_name("weak").unwrap();
+    assert_eq!(weak.coff_symbol().number_of_aux_symbols, 1);
+
+    let aux = coff
+        .coff_symbol_table()
+        .aux_weak_external(weak.index())
+        .unwrap();
+    assert_eq!(aux.weak_search_type.get(LittleEndian), 3);
+}
+
+#[test]
+fn coff_function_definition() {
+    let yaml = r#"
+header:
+  Machine:         IMAGE_FILE_MACHINE_AMD64
+  Characteristics: [  ]
+sections:
+  - Name:            .text
+    Characteristics: [ IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ ]
+    Alignment:       16
+    SectionData:     90C3
+symbols:
+  - Name:            go
+    Value:           0
+    SectionNumber:   1
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_FUNCTION
+    StorageClass:    IMAGE_SYM_CLASS_EXTERNAL
+    FunctionDefinition:
+      TagIndex:        0
+      TotalSize:       2
+      PointerToLinenumber: 0
+      PointerToNextFunction: 0
+  - Name:            after
+    Value:           1
+    SectionNumber:   1
+    SimpleType:      IMAGE_SYM_TYPE_NULL
+    ComplexType:     IMAGE_SYM_DTYPE_NULL
+    StorageClass:    IMAGE_SYM_CLASS_STATIC
+"#;
+
+    let parsed: CoffYaml = serde_yml::from_str(yaml).unwrap();
+    let built = parsed.build().unwrap();
+    let coff: CoffFile = CoffFile::parse(built.as_slice()).unwrap();
+
+    let go = coff.symbol_by_name("go").unwrap();
+    let aux = coff.coff_symbol_table().aux_function(go.index()).unwrap();
+    assert_eq!(aux.total_size.get(LittleEndian), 2);
+    assert_eq!(coff.symbol_by_name("after").unwrap().index().0, 2);
+}
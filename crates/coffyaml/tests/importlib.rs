@@ -1,6 +1,7 @@
 use coffyaml::importlib::{Architecture, ImportlibYaml};
 use object::{
-    coff::{ImportFile, ImportName},
+    Object, ObjectSection,
+    coff::{CoffFile, ImportFile, ImportName},
     read::archive::ArchiveFile,
 };
 
@@ -73,3 +74,92 @@ fn importlib_extract_member() {
         ImportName::Ordinal(_) => panic!("import value should not be an ordinal"),
     }
 }
+
+const IMPORTLIB_LEGACY_YAML: &str = include_str!("importlib_legacy.yaml");
+
+#[test]
+fn importlib_legacy_symbol_table_exports() {
+    let parsed_yaml: ImportlibYaml = serde_yml::from_str(IMPORTLIB_LEGACY_YAML).unwrap();
+
+    let built = parsed_yaml.build_legacy(Architecture::X86_64).unwrap();
+    let parsed_archive = ArchiveFile::parse(built.as_slice()).unwrap();
+
+    let archive_symbols = parsed_archive
+        .symbols()
+        .unwrap()
+        .unwrap()
+        .map(|symbol| String::from_utf8(symbol.unwrap().name().to_vec()).unwrap())
+        .collect::<Vec<_>>();
+
+    for export in [
+        "_head_KERNEL32_dll",
+        "KERNEL32_dll_iname",
+        "GetLastError",
+        "__imp_GetLastError",
+        "Sleep",
+        "__imp_Sleep",
+        "__imp_DataExport",
+    ] {
+        assert!(
+            archive_symbols.iter().any(|symbol| symbol == export),
+            "could not find '{export}' in symbol table"
+        );
+    }
+
+    assert!(
+        !archive_symbols.iter().any(|symbol| symbol == "DataExport"),
+        "data import should not export a public symbol"
+    );
+}
+
+#[test]
+fn importlib_legacy_members() {
+    let parsed_yaml: ImportlibYaml = serde_yml::from_str(IMPORTLIB_LEGACY_YAML).unwrap();
+
+    let built = parsed_yaml.build_legacy(Architecture::X86_64).unwrap();
+    let parsed_archive = ArchiveFile::parse(built.as_slice()).unwrap();
+
+    let extract_member = |name: &str| {
+        let symbol = parsed_archive
+            .symbols()
+            .unwrap()
+            .unwrap()
+            .map(|symbol| symbol.unwrap())
+            .find(|symbol| symbol.name() == name.as_bytes())
+            .unwrap_or_else(|| panic!("could not find '{name}' in symbol table"));
+
+        let member = parsed_archive.member(symbol.offset()).unwrap();
+        CoffFile::<&[u8]>::parse(member.data(built.as_slice()).unwrap()).unwrap()
+    };
+
+    let code_member = extract_member("__imp_GetLastError");
+    assert_eq!(code_member.coff_section_table().len(), 7);
+
+    let data_member = extract_member("__imp_DataExport");
+    assert_eq!(
+        data_member.coff_section_table().len(),
+        6,
+        "data import member should not have a .text section"
+    );
+    assert!(data_member.section_by_name(".text").is_none());
+
+    let alias_member = extract_member("__imp_Sleep");
+    let name_data = alias_member
+        .section_by_name(".idata$6")
+        .expect("could not find .idata$6 section")
+        .data()
+        .unwrap();
+    assert_eq!(
+        &name_data[2..],
+        b"Sleep@4\0",
+        "alias member should import the aliased name"
+    );
+
+    let tail_member = extract_member("KERNEL32_dll_iname");
+    let dll_data = tail_member
+        .section_by_name(".idata$7")
+        .expect("could not find .idata$7 section")
+        .data()
+        .unwrap();
+    assert!(dll_data.starts_with(b"KERNEL32.dll\0"));
+}
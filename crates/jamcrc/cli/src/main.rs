@@ -1,6 +1,7 @@
 use std::{
-    io::{BufRead, BufReader},
-    path::PathBuf,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
 };
 
 use clap::Parser;
@@ -37,8 +38,16 @@ struct CliArgs {
     /// Print the calculated checksum as hex
     #[arg(long)]
     hex: bool,
+
+    /// Number of threads for hashing large files. Defaults to the available
+    /// parallelism
+    #[arg(long, short = 'j', value_name = "N")]
+    threads: Option<NonZeroUsize>,
 }
 
+/// Minimum number of bytes hashed by each thread.
+const MIN_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+
 #[derive(Clone, Debug)]
 enum StdinOrFilePath {
     Stdin,
@@ -70,6 +79,58 @@ fn calculate_buffered<R: BufRead>(
     }
 }
 
+/// Calculates the checksum for the file by splitting it into ranges hashed
+/// on separate threads and combining the results.
+fn calculate_parallel(
+    mut hasher: jamcrc::Hasher,
+    path: &Path,
+    threads: NonZeroUsize,
+) -> anyhow::Result<u32> {
+    let size = std::fs::metadata(path)?.len();
+    let chunk_count = (size / MIN_CHUNK_SIZE).clamp(1, threads.get() as u64);
+    let chunk_size = size.div_ceil(chunk_count);
+
+    let partials = std::thread::scope(|scope| {
+        let workers = Vec::from_iter((0..chunk_count).map(|chunk| {
+            scope.spawn(move || -> anyhow::Result<jamcrc::Hasher> {
+                let start = chunk * chunk_size;
+                let len = chunk_size.min(size - start);
+
+                let mut f = std::fs::File::open(path)?;
+                f.seek(SeekFrom::Start(start))?;
+
+                let mut hasher = jamcrc::Hasher::new();
+                let mut reader = BufReader::new(f.take(len));
+                loop {
+                    let buffer = reader.fill_buf()?;
+                    if buffer.is_empty() {
+                        return Ok(hasher);
+                    }
+
+                    let consumed = buffer.len();
+                    hasher.update(buffer);
+                    reader.consume(consumed);
+                }
+            })
+        }));
+
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    })?;
+
+    for partial in &partials {
+        hasher.combine(partial);
+    }
+
+    Ok(hasher.finalize())
+}
+
 fn calculate_full(mut hasher: jamcrc::Hasher, data: impl AsRef<[u8]>) -> u32 {
     hasher.update(data.as_ref());
     hasher.finalize()
@@ -93,11 +154,14 @@ fn main() -> anyhow::Result<()> {
                 }
             }
             StdinOrFilePath::FilePath(path) => {
-                let f = std::fs::File::open(path)?;
                 if args.ihex {
+                    let f = std::fs::File::open(path)?;
                     calculate_buffered(hasher, BufReader::new(HexDecodeStream::new(f)))?
                 } else {
-                    calculate_buffered(hasher, BufReader::new(f))?
+                    let threads = args.threads.unwrap_or_else(|| {
+                        std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+                    });
+                    calculate_parallel(hasher, path, threads)?
                 }
             }
         }
@@ -26,6 +26,15 @@ impl Hasher {
         self.inner.update(data);
     }
 
+    /// Combines the hasher with another hasher as if the data passed to
+    /// `other` was appended to the data passed to this hasher.
+    ///
+    /// `other` must be created with [`Hasher::new`].
+    #[inline]
+    pub fn combine(&mut self, other: &Self) {
+        self.inner.combine(&other.inner);
+    }
+
     /// Returns the JamCRC value.
     #[inline]
     pub fn finalize(self) -> u32 {
@@ -1,18 +1,19 @@
 use std::{io::BufWriter, path::PathBuf};
 
 use anyhow::Context;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use coffyaml::{
     coff::{
-        CoffYaml, CoffYamlAuxFunctionDefinition, CoffYamlAuxSectionDefinition, CoffYamlHeader,
-        CoffYamlSection, CoffYamlSectionRelocation, CoffYamlSymbol,
+        CoffYaml, CoffYamlAuxFunctionDefinition, CoffYamlAuxRecord, CoffYamlAuxSectionDefinition,
+        CoffYamlHeader, CoffYamlLineNumber, CoffYamlSection, CoffYamlSectionRelocation,
+        CoffYamlSymbol, SectionData, SectionDataFormat,
     },
     importlib::ImportlibYaml,
 };
 use object::{
-    Object, ObjectSection, ObjectSymbol,
-    coff::{CoffFile, ImageSymbol, ImportFile},
-    pe::{IMAGE_SYM_ABSOLUTE, IMAGE_SYM_DEBUG},
+    Object, ObjectSection, ObjectSymbol, ReadRef,
+    coff::{CoffFile, ImageSymbol, ImportFile, ImportType},
+    pe::{IMAGE_SYM_ABSOLUTE, IMAGE_SYM_DEBUG, ImageLinenumber, ImageSymbolBytes},
     read::archive::ArchiveFile,
 };
 use serde::Serialize;
@@ -27,6 +28,28 @@ struct CliArgs {
     /// Output file. Defaults to stdout.
     #[arg(short, long, value_name = "file", value_hint = clap::ValueHint::FilePath)]
     output: Option<PathBuf>,
+
+    /// Representation of the section data.
+    #[arg(long, value_name = "format", default_value = "hex")]
+    section_data: SectionDataArg,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum SectionDataArg {
+    /// Uppercase hex string.
+    Hex,
+
+    /// List of byte values.
+    Bytes,
+}
+
+impl From<SectionDataArg> for SectionDataFormat {
+    fn from(value: SectionDataArg) -> SectionDataFormat {
+        match value {
+            SectionDataArg::Hex => SectionDataFormat::Hex,
+            SectionDataArg::Bytes => SectionDataFormat::Bytes,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -57,7 +80,8 @@ fn main() -> anyhow::Result<()> {
                 )?));
         } else {
             parsed_inputs.push(ParsedInput::Coff(
-                parse_coff(data).with_context(|| format!("could not parse {}.", file.display()))?,
+                parse_coff(data, args.section_data.into())
+                    .with_context(|| format!("could not parse {}.", file.display()))?,
             ));
         }
     }
@@ -89,23 +113,32 @@ fn parse_importlib(data: Vec<u8>) -> anyhow::Result<ImportlibYaml> {
 
     let library = std::str::from_utf8(import_file.dll())?.to_string();
 
-    let mut symbols = Vec::with_capacity(archive.members().count() - 3);
+    let mut exports = Vec::with_capacity(archive.members().count() - 3);
+    let mut data_exports = Vec::new();
+    let mut const_exports = Vec::new();
 
     for member in archive.members().skip(3) {
         let member = member?;
         let member_data = member.data(data.as_slice())?;
 
         let import_file = ImportFile::parse(member_data)?;
-        symbols.push(std::str::from_utf8(import_file.symbol())?.to_string());
+        let symbol = std::str::from_utf8(import_file.symbol())?.to_string();
+        match import_file.import_type() {
+            ImportType::Code => exports.push(symbol),
+            ImportType::Data => data_exports.push(symbol),
+            ImportType::Const => const_exports.push(symbol),
+        }
     }
 
     Ok(ImportlibYaml {
         library,
-        exports: symbols,
+        exports,
+        data_exports,
+        const_exports,
     })
 }
 
-fn parse_coff(data: Vec<u8>) -> anyhow::Result<CoffYaml> {
+fn parse_coff(data: Vec<u8>, data_format: SectionDataFormat) -> anyhow::Result<CoffYaml> {
     let coff: CoffFile = CoffFile::parse(data.as_slice())?;
 
     let coff_header = coff.coff_header();
@@ -137,13 +170,36 @@ fn parse_coff(data: Vec<u8>) -> anyhow::Result<CoffYaml> {
             });
         }
 
+        let line_numbers = data
+            .as_slice()
+            .read_slice_at::<ImageLinenumber>(
+                coff_section
+                    .pointer_to_linenumbers
+                    .get(object::LittleEndian)
+                    .into(),
+                coff_section
+                    .number_of_linenumbers
+                    .get(object::LittleEndian)
+                    .into(),
+            )
+            .map_err(|()| anyhow::anyhow!("invalid line number table"))?
+            .iter()
+            .map(|line_number| CoffYamlLineNumber {
+                address: line_number
+                    .symbol_table_index_or_virtual_address
+                    .get(object::LittleEndian),
+                line_number: line_number.linenumber.get(object::LittleEndian),
+            })
+            .collect();
+
         sections.push(CoffYamlSection {
             name: section.name()?.to_string(),
             characteristics,
             alignment,
-            section_data: section.data()?.to_vec(),
+            section_data: SectionData::from(section.data()?.to_vec()).with_format(data_format),
             size_of_raw_data: Some(coff_section.size_of_raw_data.get(object::LittleEndian)),
             relocations,
+            line_numbers,
         });
     }
 
@@ -181,6 +237,22 @@ fn parse_coff(data: Vec<u8>) -> anyhow::Result<CoffYaml> {
             None
         };
 
+        // Aux records for other symbols are kept as raw bytes
+        let aux_records = if section_definition.is_none()
+            && function_definition.is_none()
+            && !coff_symbol.has_aux_file_name()
+        {
+            (1..=coff_symbol.number_of_aux_symbols.into())
+                .map(|offset| {
+                    symbol_table
+                        .get::<ImageSymbolBytes>(symbol.index(), offset)
+                        .map(|aux| CoffYamlAuxRecord(aux.0.to_vec()))
+                })
+                .collect::<object::read::Result<_>>()?
+        } else {
+            Vec::new()
+        };
+
         let file = if coff_symbol.has_aux_file_name() {
             Some(symbol.name()?.to_string())
         } else {
@@ -205,6 +277,7 @@ fn parse_coff(data: Vec<u8>) -> anyhow::Result<CoffYaml> {
             section_definition,
             function_definition,
             file,
+            aux_records,
         });
     }
 
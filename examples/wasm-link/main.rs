@@ -0,0 +1,35 @@
+//! Links a COFF read from stdin and writes the BOF to stdout without
+//! accessing the filesystem.
+//!
+//! ```shell
+//! cargo build --example wasm-link --target wasm32-wasip1
+//! wasmtime target/wasm32-wasip1/debug/examples/wasm-link.wasm < bof.o > bof.bof
+//! ```
+
+use std::io::{Read, Write};
+
+use boflink::link::{LinkInput, LinkOptions, link};
+
+fn main() {
+    let mut uploaded = Vec::new();
+    if let Err(e) = std::io::stdin().read_to_end(&mut uploaded) {
+        eprintln!("could not read input: {e}");
+        std::process::exit(1);
+    }
+
+    let mut options = LinkOptions::default();
+    options.inputs.push(LinkInput::memory("<stdin>", uploaded));
+
+    match link(options) {
+        Ok(linked) => {
+            if let Err(e) = std::io::stdout().write_all(&linked.output) {
+                eprintln!("could not write output: {e}");
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
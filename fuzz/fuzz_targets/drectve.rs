@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    boflink::fuzzing::drectve_libraries(data);
+    boflink::fuzzing::coff_drectve_libraries(data);
+});
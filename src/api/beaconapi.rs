@@ -13,7 +13,7 @@ use super::{ApiSymbolError, ApiSymbolSource};
 /// The Beacon API symbol string values.
 ///
 /// Symbols are sorted based on commonality.
-const BEACONAPI_SYMBOLS: [&str; 52] = [
+pub(super) const BEACONAPI_SYMBOLS: [&str; 52] = [
     "BeaconPrintf",
     "BeaconDataParse",
     "BeaconOutput",
@@ -43,7 +43,7 @@ const BEACONAPI_SYMBOLS: [&str; 52] = [
     "BeaconDuplicateHandle",
     "BeaconOpenProcess",
     "BeaconDataStoreGetItem",
-    "BeaconEnableBeaconGate:",
+    "BeaconEnableBeaconGate",
     "BeaconVirtualQuery",
     "BeaconWriteProcessMemory",
     "BeaconSetThreadContext",
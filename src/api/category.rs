@@ -0,0 +1,144 @@
+use crate::linkobject::import::{ImportMember, ImportName};
+
+/// The kind of functionality provided by a Beacon API function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BeaconApiCategory {
+    /// Sending output back to the operator.
+    Output,
+
+    /// Parsing the BOF arguments.
+    DataParsing,
+
+    /// Building formatted output buffers.
+    Format,
+
+    /// Storing values and items across BOF executions.
+    DataStore,
+
+    /// Impersonating tokens and checking privileges.
+    Token,
+
+    /// Spawning and manipulating processes and threads.
+    Process,
+
+    /// Injecting code into processes.
+    ProcessInjection,
+
+    /// Allocating and modifying memory.
+    Memory,
+
+    /// Getting syscall information and toggling BeaconGate.
+    Syscall,
+
+    /// Querying information about the Beacon.
+    Information,
+
+    /// Miscellaneous helper functions.
+    Utility,
+}
+
+impl BeaconApiCategory {
+    /// Returns the category of the Beacon API function with the name.
+    ///
+    /// Returns `None` if the name is not a Beacon API function.
+    pub fn of_name(name: &str) -> Option<BeaconApiCategory> {
+        Some(match name {
+            "BeaconPrintf" | "BeaconOutput" => Self::Output,
+            "BeaconDataParse" | "BeaconDataExtract" | "BeaconDataInt" | "BeaconDataShort"
+            | "BeaconDataLength" | "BeaconDataPtr" => Self::DataParsing,
+            "BeaconFormatAlloc"
+            | "BeaconFormatReset"
+            | "BeaconFormatFree"
+            | "BeaconFormatAppend"
+            | "BeaconFormatPrintf"
+            | "BeaconFormatToString"
+            | "BeaconFormatInt" => Self::Format,
+            "BeaconAddValue"
+            | "BeaconGetValue"
+            | "BeaconRemoveValue"
+            | "BeaconDataStoreGetItem"
+            | "BeaconDataStoreProtectItem"
+            | "BeaconDataStoreUnprotectItem"
+            | "BeaconDataStoreMaxEntries" => Self::DataStore,
+            "BeaconUseToken" | "BeaconRevertToken" | "BeaconIsAdmin" => Self::Token,
+            "BeaconGetSpawnTo"
+            | "BeaconSpawnTemporaryProcess"
+            | "BeaconCleanupProcess"
+            | "BeaconOpenProcess"
+            | "BeaconOpenThread"
+            | "BeaconResumeThread"
+            | "BeaconGetThreadContext"
+            | "BeaconSetThreadContext"
+            | "BeaconCloseHandle"
+            | "BeaconDuplicateHandle" => Self::Process,
+            "BeaconInjectProcess" | "BeaconInjectTemporaryProcess" => Self::ProcessInjection,
+            "BeaconVirtualAlloc"
+            | "BeaconVirtualAllocEx"
+            | "BeaconVirtualProtect"
+            | "BeaconVirtualProtectEx"
+            | "BeaconVirtualFree"
+            | "BeaconVirtualQuery"
+            | "BeaconReadProcessMemory"
+            | "BeaconWriteProcessMemory"
+            | "BeaconUnmapViewOfFile" => Self::Memory,
+            "BeaconGetSyscallInformation"
+            | "BeaconEnableBeaconGate"
+            | "BeaconDisableBeaconGate" => Self::Syscall,
+            "BeaconInformation" | "BeaconGetCustomUserData" => Self::Information,
+            "toWideChar" => Self::Utility,
+            _ => return None,
+        })
+    }
+
+    /// Returns the category of the Beacon API function imported by the
+    /// member along with the function name.
+    ///
+    /// The `__imp_` prefix and the i386 leading underscore are removed from
+    /// the name before classifying it.
+    pub fn of_import<'a>(import: &ImportMember<'a>) -> Option<(BeaconApiCategory, &'a str)> {
+        let name = match import.import {
+            ImportName::Name(name) => name,
+            ImportName::Ordinal(_) => import.symbol,
+        };
+
+        let name = name.strip_prefix("__imp_").unwrap_or(name);
+        [Some(name), name.strip_prefix('_')]
+            .into_iter()
+            .flatten()
+            .find_map(|name| Self::of_name(name).map(|category| (category, name)))
+    }
+}
+
+impl std::fmt::Display for BeaconApiCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Output => write!(f, "output"),
+            Self::DataParsing => write!(f, "data parsing"),
+            Self::Format => write!(f, "format"),
+            Self::DataStore => write!(f, "data store"),
+            Self::Token => write!(f, "token"),
+            Self::Process => write!(f, "process"),
+            Self::ProcessInjection => write!(f, "process injection"),
+            Self::Memory => write!(f, "memory"),
+            Self::Syscall => write!(f, "syscall"),
+            Self::Information => write!(f, "information"),
+            Self::Utility => write!(f, "utility"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BeaconApiCategory;
+    use crate::api::beaconapi::BEACONAPI_SYMBOLS;
+
+    #[test]
+    fn all_symbols_categorized() {
+        for symbol in BEACONAPI_SYMBOLS {
+            assert!(
+                BeaconApiCategory::of_name(symbol).is_some(),
+                "{symbol} does not have a category"
+            );
+        }
+    }
+}
@@ -1,7 +1,9 @@
 mod beaconapi;
+mod category;
 mod error;
 mod traits;
 
 pub use beaconapi::*;
+pub use category::*;
 pub use error::*;
 pub use traits::*;
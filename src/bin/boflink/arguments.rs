@@ -1,12 +1,29 @@
-use std::path::PathBuf;
+use std::{ffi::OsString, path::PathBuf};
 
-use boflink::linker::LinkerTargetArch;
-use clap::{Parser, ValueEnum};
+use boflink::{
+    graph::{
+        CharacteristicsPolicy, IntrinsicGroup, LnkInfoPolicy, SymbolRenameMode,
+        node::SectionNodeCharacteristics,
+    },
+    linker::LinkerTargetArch,
+};
+use clap::{
+    ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+    error::ErrorKind,
+};
 use clap_verbosity_flag::{InfoLevel, Verbosity};
 
 #[derive(Parser, Debug)]
-#[command(version, about)]
+#[command(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    disable_help_subcommand = true
+)]
 pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+
     /// Set the output file name
     #[arg(
         short,
@@ -24,6 +41,24 @@ pub struct CliArgs {
     )]
     pub files: Vec<PathBuf>,
 
+    /// Treat the files until --end-lib as archive members which are only
+    /// linked if they define an undefined symbol
+    #[arg(
+        long,
+        value_name = "file",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub start_lib: Vec<PathBuf>,
+
+    /// Resolve the exports listed in the module-definition file as imports
+    /// from the DLL it names
+    #[arg(
+        long = "def",
+        value_name = "file",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub module_definitions: Vec<PathBuf>,
+
     /// Add the specified library to search for symbols
     #[arg(id = "library", short, long, value_name = "libname")]
     pub libraries: Vec<String>,
@@ -38,7 +73,41 @@ pub struct CliArgs {
     )]
     pub library_paths: Vec<PathBuf>,
 
-    /// Set the sysroot path
+    /// Only search each library for the symbols referenced by the files
+    /// before it. Libraries may be listed more than once
+    #[arg(long, conflicts_with = "machines")]
+    pub positional_libraries: bool,
+
+    /// Search the libraries until --end-group repeatedly with
+    /// --positional-libraries
+    #[arg(
+        long,
+        num_args = 0,
+        default_missing_value = "true",
+        action = ArgAction::Append
+    )]
+    pub start_group: Vec<bool>,
+
+    /// End a library group started with --start-group
+    #[arg(
+        long,
+        num_args = 0,
+        default_missing_value = "true",
+        action = ArgAction::Append
+    )]
+    pub end_group: Vec<bool>,
+
+    /// Search the directories in the LIB environment variable on non-Windows
+    /// hosts. The directories are separated by ';'
+    #[arg(long)]
+    pub lib_env: bool,
+
+    /// Do not cache the library search results between runs
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Set the sysroot path used for library search paths and library file
+    /// names starting with '=' or '$SYSROOT'
     #[arg(
         long,
         value_name = "directory",
@@ -50,32 +119,361 @@ pub struct CliArgs {
     #[arg(short, long, value_name = "emulation")]
     pub machine: Option<TargetEmulation>,
 
+    /// Link once for each machine and write <output>.x64.o and <output>.x86.o
+    #[arg(
+        long,
+        value_name = "machine",
+        value_delimiter = ',',
+        conflicts_with = "machine"
+    )]
+    pub machines: Vec<TargetMachine>,
+
     /// Name of the entrypoint
     #[arg(short, long, value_name = "entry", default_value = "go")]
     pub entry: String,
 
+    /// Place the section defining the entrypoint at the start of .text
+    #[arg(long)]
+    pub entry_first: bool,
+
     /// Dump the link graph to the specified file
     #[arg(long, value_name = "file", value_hint = clap::ValueHint::FilePath)]
     pub dump_link_graph: Option<PathBuf>,
 
-    /// Custom API to use instead of the Beacon API
+    /// Custom API to use instead of the Beacon API. Can be specified multiple
+    /// times to search APIs in priority order
     #[arg(long, value_name = "library", visible_alias = "api")]
-    pub custom_api: Option<String>,
+    pub custom_api: Vec<String>,
+
+    /// File with the mapping of symbol names used for resolving API symbols
+    #[arg(long, value_name = "file", value_hint = clap::ValueHint::FilePath)]
+    pub api_map: Option<PathBuf>,
+
+    /// File with a JSON array or YAML list of the symbols provided by the
+    /// loader. The symbols are left undefined in the output
+    #[arg(long, value_name = "file", value_hint = clap::ValueHint::FilePath)]
+    pub externs: Option<PathBuf>,
 
     /// Initialize the .bss section and merge it with the .data section
     #[arg(long)]
     pub merge_bss: bool,
 
+    /// Rename non-essential symbols in the output
+    #[arg(
+        long,
+        value_name = "mode",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "short"
+    )]
+    pub rename_symbols: Option<RenameSymbolsMode>,
+
+    /// Write the map of renamed symbols to the specified file
+    #[arg(
+        long,
+        value_name = "file",
+        value_hint = clap::ValueHint::FilePath,
+        requires = "rename_symbols"
+    )]
+    pub symbol_map: Option<PathBuf>,
+
+    /// Byte used for padding code sections
+    #[arg(long, value_name = "byte", value_parser = parse_byte)]
+    pub code_fill: Option<u8>,
+
+    /// Byte used for padding data sections
+    #[arg(long, value_name = "byte", value_parser = parse_byte)]
+    pub data_fill: Option<u8>,
+
+    /// Pad the output file to a multiple of the alignment
+    #[arg(long, value_name = "bytes", value_parser = parse_output_alignment)]
+    pub output_align: Option<u32>,
+
+    /// Append the contents of the file after the output COFF
+    #[arg(long, value_name = "file", value_hint = clap::ValueHint::FilePath)]
+    pub trailer: Option<PathBuf>,
+
+    /// Add the contents of the file as a section. Options are
+    /// flags=<r|w|x>, align=<bytes> and symbol=<name> for defining a symbol
+    /// at the start of the section
+    #[arg(
+        long,
+        value_name = "name=file[,option...]",
+        value_parser = parse_add_section
+    )]
+    pub add_section: Vec<AddSectionArg>,
+
+    /// Write the contents of the output section to the file
+    #[arg(long, value_name = "name=file", value_parser = parse_dump_section)]
+    pub dump_section: Vec<(String, PathBuf)>,
+
+    /// Write the sections requested with --dump-section from the input COFF
+    /// instead of linking
+    #[arg(long, requires = "dump_section")]
+    pub dump_only: bool,
+
+    /// Append the JamCRC of the output after the output COFF
+    #[arg(long, conflicts_with = "trailer")]
+    pub trailer_jamcrc: bool,
+
+    /// Add a .rdata$cksum section with the sizes and JamCRC values of the
+    /// output sections
+    #[arg(long)]
+    pub section_checksums: bool,
+
+    /// Keep the auxiliary function records with the function sizes and the
+    /// .bf/.ef symbol chain from the inputs
+    #[arg(long)]
+    pub function_aux: bool,
+
+    /// Replace the loads through the .refptr stubs emitted by GCC with direct
+    /// references when the data is defined in the output
+    #[arg(long)]
+    pub collapse_refptrs: bool,
+
+    /// Rewrite the absolute address loads in code to be RIP-relative so that
+    /// the output has no IMAGE_REL_AMD64_ADDR64 relocations
+    #[arg(long)]
+    pub rip_relative: bool,
+
+    /// Discard the .rsrc resource sections in the inputs
+    #[arg(long)]
+    pub strip_resources: bool,
+
+    /// XOR the contents of the output section with the obfuscation key and
+    /// describe the ranges in a .obfmeta section. Can be specified multiple
+    /// times
+    #[arg(long, value_name = "section")]
+    pub obfuscate_section: Vec<String>,
+
+    /// Hex encoded key for obfuscating sections. A random key is used if not
+    /// specified
+    #[arg(long, value_name = "hex", value_parser = parse_hex_key, requires = "obfuscate_section")]
+    pub obfuscation_key: Option<Box<[u8]>>,
+
+    /// Minimum alignment for output sections
+    #[arg(long, value_name = "bytes", value_parser = parse_section_alignment)]
+    pub min_section_align: Option<u32>,
+
+    /// Do not align the .text and .rdata input sections to 16 bytes for amd64
+    #[arg(long)]
+    pub no_default_section_align: bool,
+
+    /// Write relocations for references to symbols in the same output section
+    /// instead of applying them
+    #[arg(long)]
+    pub no_flatten: bool,
+
+    /// Print the cross-section relocations in the output and warn about
+    /// suspicious relocations
+    #[arg(long)]
+    pub check_relocs: bool,
+
+    /// Order the output symbols independently of the input order
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Skip archive members which fail to parse instead of failing the link
+    #[arg(long)]
+    pub tolerant: bool,
+
+    /// Skip input files and archive members with the same contents as a
+    /// previous input
+    #[arg(long)]
+    pub dedup_inputs: bool,
+
+    /// Keep the absolute symbol in the output. Can be specified multiple
+    /// times
+    #[arg(long, value_name = "symbol")]
+    pub keep_absolute_symbol: Vec<String>,
+
+    /// Keep the symbols matching the pattern from being renamed or
+    /// discarded. Supports '*' and '?' wildcards. Can be specified multiple
+    /// times
+    #[arg(long, value_name = "pattern")]
+    pub keep_symbol: Vec<String>,
+
+    /// Prefer keeping the input sections matching the pattern when
+    /// discarding COMDATs and import thunks. Supports '*' and '?' wildcards.
+    /// Can be specified multiple times
+    #[arg(long, value_name = "pattern")]
+    pub keep_section: Vec<String>,
+
+    /// Redirect the references to a symbol to another symbol after symbol
+    /// resolution. Can be specified multiple times
+    #[arg(long, value_name = "from=to", value_parser = parse_redirect)]
+    pub redirect: Vec<(String, String)>,
+
+    /// Define the undefined compiler intrinsics in the groups for inputs
+    /// linked without a CRT. Can be specified multiple times
+    #[arg(long, value_name = "group", value_delimiter = ',')]
+    pub intrinsics: Vec<IntrinsicsGroup>,
+
+    /// Route the imports from the DLL through the delay import table
+    /// instead of __imp_ symbols. Can be specified multiple times
+    #[arg(long, value_name = "dll")]
+    pub delay_load: Vec<String>,
+
+    /// Require all inputs to be compatible with safe exception handlers
+    /// (i386 only)
+    #[arg(long)]
+    pub safeseh: bool,
+
+    /// How to handle input sections with the same name and conflicting memory
+    /// access flags
+    #[arg(long, value_name = "policy", default_value_t = CharacteristicsMode::Merge)]
+    pub section_flags: CharacteristicsMode,
+
+    /// How to handle input sections with IMAGE_SCN_LNK_INFO other than
+    /// .drectve
+    #[arg(long, value_name = "policy", default_value_t = LnkInfoMode::Keep)]
+    pub lnk_info: LnkInfoMode,
+
+    /// Add a .comment section with the boflink version and the linked inputs
+    #[arg(long)]
+    pub emit_comment: bool,
+
+    /// Add a .ident section with the boflink version and an optional string
+    #[arg(
+        long,
+        value_name = "string",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    pub ident: Option<String>,
+
+    /// Preserve the section and symbol ordering from the layout in the
+    /// specified file and update it with the layout of the output
+    #[arg(long, value_name = "file", value_hint = clap::ValueHint::FilePath)]
+    pub layout: Option<PathBuf>,
+
+    /// Order the code sections using the symbol hit counts in the specified
+    /// profile with the most frequently hit code first
+    #[arg(long, value_name = "file", value_hint = clap::ValueHint::FilePath)]
+    pub profile: Option<PathBuf>,
+
+    /// Move the code without any profile hits to a separate '.text$cold'
+    /// section
+    #[arg(long, requires = "profile")]
+    pub split_cold: bool,
+
+    /// Order the input sections by descending alignment to reduce the
+    /// padding in the output sections
+    #[arg(long)]
+    pub pack_sections: bool,
+
+    /// Write a tar archive with the input files, the link libraries and the
+    /// command line for reproducing the link
+    #[arg(long, value_name = "file", value_hint = clap::ValueHint::FilePath)]
+    pub reproduce: Option<PathBuf>,
+
+    /// Print a table of the external symbols along with the inputs defining
+    /// and referencing them
+    #[arg(long)]
+    pub cref: bool,
+
+    /// Print the number of relocations of each type in the output sections
+    #[arg(long)]
+    pub print_reloc_stats: bool,
+
+    /// Print the resolved Beacon API functions grouped by category
+    #[arg(long)]
+    pub print_api_usage: bool,
+
+    /// Print the opened link libraries as a tree of the libraries whose
+    /// members requested them with .drectve /DEFAULTLIB directives
+    #[arg(long)]
+    pub print_library_deps: bool,
+
+    /// Print the symbols provided by each member of the input archives and
+    /// link libraries instead of linking
+    #[arg(long)]
+    pub list_exports: bool,
+
+    /// Print the printable strings in the output data sections along with
+    /// the inputs contributing them
+    #[arg(long)]
+    pub print_strings: bool,
+
+    /// Print the largest symbols in the output with their estimated sizes
+    /// and defining inputs
+    #[arg(
+        long,
+        value_name = "count",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "20"
+    )]
+    pub print_symbol_sizes: Option<usize>,
+
+    /// Print the inputs searched for the symbol, similar names in the link
+    /// libraries and the inputs referencing it
+    #[arg(long, value_name = "symbol")]
+    pub why_undefined: Vec<String>,
+
+    /// Warn about link libraries which were not used
+    #[arg(long)]
+    pub warn_unused_libraries: bool,
+
     /// Print colored output
     #[arg(long, value_name = "color", default_value_t = ColorOption::Auto)]
     pub color: ColorOption,
 
+    /// Format of the log messages
+    #[arg(long, value_name = "format", default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
     #[command(flatten)]
     pub verbose: Verbosity<InfoLevel>,
 
     /// Print timing information
     #[arg(long)]
     pub print_timing: bool,
+
+    /// Print the time spent in each phase of the link and opening each link
+    /// library
+    #[arg(long)]
+    pub time_report: bool,
+
+    /// The arguments after expanding response files and translating the
+    /// linker flavor options
+    #[arg(skip)]
+    pub command_line: Vec<OsString>,
+
+    /// The files, libraries and library groups in command line order
+    #[arg(skip)]
+    pub link_order: Vec<LinkOrderItem>,
+}
+
+/// Subcommands for generating the documentation from the arguments.
+#[derive(Subcommand, Debug)]
+pub enum CliCommand {
+    /// Print the shell completion script for boflink
+    Completions {
+        /// Shell to generate the completions for
+        #[arg(value_name = "shell")]
+        shell: clap_complete::Shell,
+    },
+
+    /// Print the boflink man page in roff format
+    Man,
+}
+
+/// A positional argument affecting the library search order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkOrderItem {
+    /// The next file in [`CliArgs::files`].
+    File,
+
+    /// The next library in [`CliArgs::libraries`].
+    Library,
+
+    /// `--start-group`.
+    StartGroup,
+
+    /// `--end-group`.
+    EndGroup,
 }
 
 #[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
@@ -96,6 +494,146 @@ impl From<TargetEmulation> for LinkerTargetArch {
     }
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetMachine {
+    #[value(name = "amd64", alias = "x64")]
+    Amd64,
+
+    #[value(name = "i386", alias = "x86")]
+    I386,
+}
+
+impl TargetMachine {
+    /// Returns the suffix added to the output name for the machine.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TargetMachine::Amd64 => "x64",
+            TargetMachine::I386 => "x86",
+        }
+    }
+}
+
+impl From<TargetMachine> for LinkerTargetArch {
+    fn from(value: TargetMachine) -> Self {
+        match value {
+            TargetMachine::Amd64 => LinkerTargetArch::Amd64,
+            TargetMachine::I386 => LinkerTargetArch::I386,
+        }
+    }
+}
+
+impl std::fmt::Display for TargetMachine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetMachine::Amd64 => write!(f, "amd64"),
+            TargetMachine::I386 => write!(f, "i386"),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenameSymbolsMode {
+    #[value(name = "short")]
+    Short,
+
+    #[value(name = "random")]
+    Random,
+}
+
+impl From<RenameSymbolsMode> for SymbolRenameMode {
+    fn from(value: RenameSymbolsMode) -> Self {
+        match value {
+            RenameSymbolsMode::Short => SymbolRenameMode::Short,
+            RenameSymbolsMode::Random => SymbolRenameMode::Random,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntrinsicsGroup {
+    /// Stack probe helpers (__chkstk, ___chkstk_ms, _alloca)
+    #[value(name = "stack-probe")]
+    StackProbe,
+
+    /// 64-bit arithmetic helpers for i386 (__alldiv, __allmul, ___divdi3, etc.)
+    #[value(name = "i386-math")]
+    I386Math,
+
+    /// The _fltused marker for MSVC objects using floating point values
+    #[value(name = "fltused")]
+    Fltused,
+}
+
+impl From<IntrinsicsGroup> for IntrinsicGroup {
+    fn from(value: IntrinsicsGroup) -> Self {
+        match value {
+            IntrinsicsGroup::StackProbe => IntrinsicGroup::StackProbe,
+            IntrinsicsGroup::I386Math => IntrinsicGroup::I386Math,
+            IntrinsicsGroup::Fltused => IntrinsicGroup::Fltused,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharacteristicsMode {
+    #[value(name = "merge")]
+    Merge,
+
+    #[value(name = "strict")]
+    Strict,
+}
+
+impl std::fmt::Display for CharacteristicsMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(v) = self.to_possible_value() {
+            write!(f, "{}", v.get_name())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<CharacteristicsMode> for CharacteristicsPolicy {
+    fn from(value: CharacteristicsMode) -> Self {
+        match value {
+            CharacteristicsMode::Merge => CharacteristicsPolicy::Merge,
+            CharacteristicsMode::Strict => CharacteristicsPolicy::Strict,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LnkInfoMode {
+    #[value(name = "keep")]
+    Keep,
+
+    #[value(name = "drop")]
+    Drop,
+
+    #[value(name = "comment")]
+    Comment,
+}
+
+impl std::fmt::Display for LnkInfoMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(v) = self.to_possible_value() {
+            write!(f, "{}", v.get_name())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<LnkInfoMode> for LnkInfoPolicy {
+    fn from(value: LnkInfoMode) -> Self {
+        match value {
+            LnkInfoMode::Keep => LnkInfoPolicy::Keep,
+            LnkInfoMode::Drop => LnkInfoPolicy::Drop,
+            LnkInfoMode::Comment => LnkInfoPolicy::Comment,
+        }
+    }
+}
+
 #[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DumpGraphState {
     #[value(name = "linked")]
@@ -140,6 +678,25 @@ impl std::fmt::Display for ColorOption {
     }
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    #[value(name = "text")]
+    Text,
+
+    #[value(name = "json")]
+    Json,
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(v) = self.to_possible_value() {
+            write!(f, "{}", v.get_name())?;
+        }
+
+        Ok(())
+    }
+}
+
 impl From<ColorOption> for termcolor::ColorChoice {
     fn from(val: ColorOption) -> Self {
         match val {
@@ -151,15 +708,248 @@ impl From<ColorOption> for termcolor::ColorChoice {
     }
 }
 
+/// Parses a byte value in decimal or `0x` prefixed hexadecimal.
+fn parse_byte(value: &str) -> Result<u8, String> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Parses a `from=to` symbol redirect.
+fn parse_redirect(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            Ok((from.to_string(), to.to_string()))
+        }
+        _ => Err("expected '<from>=<to>'".into()),
+    }
+}
+
+/// Parses a `name=file` section to dump.
+fn parse_dump_section(value: &str) -> Result<(String, PathBuf), String> {
+    match value.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+            Ok((name.to_string(), PathBuf::from(path)))
+        }
+        _ => Err("expected '<name>=<file>'".into()),
+    }
+}
+
+/// A file added as a section with `--add-section`.
+#[derive(Clone, Debug)]
+pub struct AddSectionArg {
+    /// The name of the section.
+    pub name: String,
+
+    /// The file with the section contents.
+    pub path: PathBuf,
+
+    /// The section characteristics from the `flags` option.
+    pub characteristics: Option<SectionNodeCharacteristics>,
+
+    /// The section alignment.
+    pub alignment: Option<u32>,
+
+    /// The symbol defined at the start of the section.
+    pub symbol: Option<String>,
+}
+
+/// Parses a `name=file[,option...]` section to add.
+fn parse_add_section(value: &str) -> Result<AddSectionArg, String> {
+    let (name, rest) = value
+        .split_once('=')
+        .filter(|(name, rest)| !name.is_empty() && !rest.is_empty())
+        .ok_or("expected '<name>=<file>'")?;
+
+    let mut options = rest.split(',');
+    let path = options.next().filter(|path| !path.is_empty());
+
+    let mut section = AddSectionArg {
+        name: name.to_string(),
+        path: PathBuf::from(path.ok_or("expected '<name>=<file>'")?),
+        characteristics: None,
+        alignment: None,
+        symbol: None,
+    };
+
+    for option in options {
+        match option.split_once('=') {
+            Some(("flags", flags)) => {
+                let mut characteristics = SectionNodeCharacteristics::MemRead;
+                for flag in flags.chars() {
+                    characteristics |= match flag {
+                        'r' => SectionNodeCharacteristics::MemRead,
+                        'w' => SectionNodeCharacteristics::MemWrite,
+                        'x' => {
+                            SectionNodeCharacteristics::CntCode
+                                | SectionNodeCharacteristics::MemExecute
+                        }
+                        _ => return Err(format!("unknown section flag '{flag}'")),
+                    };
+                }
+
+                if !characteristics.contains(SectionNodeCharacteristics::CntCode) {
+                    characteristics |= SectionNodeCharacteristics::CntInitializedData;
+                }
+
+                section.characteristics = Some(characteristics);
+            }
+            Some(("align", alignment)) => {
+                section.alignment = Some(parse_section_alignment(alignment)?);
+            }
+            Some(("symbol", symbol)) if !symbol.is_empty() => {
+                section.symbol = Some(symbol.to_string());
+            }
+            _ => return Err(format!("unknown section option '{option}'")),
+        }
+    }
+
+    Ok(section)
+}
+
+/// Parses a hex encoded obfuscation key.
+fn parse_hex_key(value: &str) -> Result<Box<[u8]>, String> {
+    let value = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+
+    if value.is_empty() || value.len() % 2 != 0 || value.len() / 2 > u16::MAX as usize {
+        return Err("key must be an even number of hex digits".into());
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|idx| {
+            value
+                .get(idx..idx + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| format!("invalid hex digits in key '{value}'"))
+        })
+        .collect()
+}
+
+/// Parses a section alignment value.
+fn parse_section_alignment(value: &str) -> Result<u32, String> {
+    let alignment: u32 = value
+        .parse()
+        .map_err(|e: std::num::ParseIntError| e.to_string())?;
+    if !alignment.is_power_of_two() || alignment > 8192 {
+        return Err("alignment must be a power of two no greater than 8192".into());
+    }
+
+    Ok(alignment)
+}
+
+/// Parses a non-zero output file alignment value.
+fn parse_output_alignment(value: &str) -> Result<u32, String> {
+    let alignment: u32 = value
+        .parse()
+        .map_err(|e: std::num::ParseIntError| e.to_string())?;
+    if alignment == 0 {
+        return Err("alignment must be greater than zero".into());
+    }
+
+    Ok(alignment)
+}
+
+/// Splits `-L=<directory>` into separate arguments so that the `=` sysroot
+/// prefix is kept.
+///
+/// The argument parser removes the `=` after short options.
+fn split_sysroot_library_paths(args: Vec<OsString>) -> Vec<OsString> {
+    let mut split = Vec::with_capacity(args.len());
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--") => {
+                split.push(arg);
+                split.extend(args.by_ref());
+            }
+            Some(value) if value.starts_with("-L=") => {
+                split.push("-L".into());
+                split.push(value[2..].into());
+            }
+            _ => split.push(arg),
+        }
+    }
+
+    split
+}
+
+/// Returns the files, libraries and library groups sorted by their position
+/// in the command line.
+fn link_order(matches: &ArgMatches) -> Vec<LinkOrderItem> {
+    let indices = |id: &str| matches.indices_of(id).into_iter().flatten();
+
+    let mut order = Vec::from_iter(
+        indices("files")
+            .map(|position| (position, LinkOrderItem::File))
+            .chain(indices("library").map(|position| (position, LinkOrderItem::Library)))
+            .chain(indices("start_group").map(|position| (position, LinkOrderItem::StartGroup)))
+            .chain(indices("end_group").map(|position| (position, LinkOrderItem::EndGroup))),
+    );
+
+    order.sort_by_key(|(position, _)| *position);
+    Vec::from_iter(order.into_iter().map(|(_, item)| item))
+}
+
 /// Parses the command line arguments into the [`CliArgs`].
+///
+/// MSVC `LINK` style arguments are translated if the link flavor is
+/// selected. Otherwise GNU ld options which do not apply to BOFs are removed
+/// and options unknown to boflink are rejected when run as `ld.boflink`.
 pub fn parse_arguments() -> anyhow::Result<CliArgs> {
-    let args = CliArgs::parse_from(argfile::expand_args_from(
-        std::env::args_os().filter(|arg| arg != "-Bdynamic"),
-        argfile::parse_fromfile,
-        argfile::PREFIX,
-    )?);
+    let mut args = Vec::from_iter(std::env::args_os());
+    let ld_driver = crate::gnu::is_ld_program(args.first());
+    let link_flavor = crate::msvc::take_link_flavor(&mut args)
+        .unwrap_or_else(|e| CliArgs::command().error(ErrorKind::InvalidValue, e).exit());
+
+    let args =
+        argfile::expand_args_from(args.into_iter(), argfile::parse_fromfile, argfile::PREFIX)?;
+
+    let (args, ignored) = if link_flavor {
+        crate::msvc::translate_link_args(args).unwrap_or_else(|e| {
+            CliArgs::command()
+                .error(ErrorKind::UnknownArgument, e)
+                .exit()
+        })
+    } else {
+        crate::gnu::translate_gnu_args(args)
+            .and_then(|(args, mut ignored)| {
+                let args = if ld_driver {
+                    crate::gnu::check_ld_args(args, &mut ignored, &CliArgs::command())?
+                } else {
+                    args
+                };
+                Ok((args, ignored))
+            })
+            .unwrap_or_else(|e| {
+                CliArgs::command()
+                    .error(ErrorKind::ArgumentConflict, e)
+                    .exit()
+            })
+    };
+
+    let args = split_sysroot_library_paths(args);
+
+    let command_line = args.clone();
+    let matches = CliArgs::command().get_matches_from(args);
+    let mut args = CliArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    args.command_line = command_line;
+    args.link_order = link_order(&matches);
 
     crate::logging::setup_logger(&args)?;
 
+    for option in ignored {
+        log::debug!("ignoring option {option}");
+    }
+
     Ok(args)
 }
@@ -0,0 +1,93 @@
+//! Writing the contents of COFF sections for `--dump-section`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail};
+use object::{Object, ObjectSection, SectionKind, coff::CoffFile};
+
+/// Returns the contents of the section named `name` in the COFF.
+///
+/// Returns an error if the COFF does not have exactly one section with the
+/// name or if the section is uninitialized.
+pub fn section_data<'a>(path: &Path, data: &'a [u8], name: &str) -> anyhow::Result<&'a [u8]> {
+    let coff: CoffFile = CoffFile::parse(data)
+        .map_err(|e| anyhow!("{}: could not parse COFF: {e}", path.display()))?;
+
+    let mut sections = coff.sections().filter(|section| {
+        section
+            .name()
+            .is_ok_and(|section_name| section_name == name)
+    });
+
+    let section = sections
+        .next()
+        .ok_or_else(|| anyhow!("{}: no section named '{name}'", path.display()))?;
+
+    if sections.next().is_some() {
+        bail!("{}: multiple sections named '{name}'", path.display());
+    }
+
+    if section.kind() == SectionKind::UninitializedData {
+        bail!("{}: section '{name}' has no contents", path.display());
+    }
+
+    section
+        .data()
+        .map_err(|e| anyhow!("{}: could not read section '{name}': {e}", path.display()))
+}
+
+/// Writes the contents of each requested section of the COFF to its file.
+pub fn dump_sections(
+    path: &Path,
+    data: &[u8],
+    sections: &[(String, PathBuf)],
+) -> anyhow::Result<()> {
+    for (name, output) in sections {
+        let section = section_data(path, data, name)?;
+        std::fs::write(output, section)
+            .map_err(|e| anyhow!("could not write {}: {e}", output.display()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use coffyaml::coff::CoffYaml;
+
+    use super::section_data;
+
+    const COFF: &str = r#"
+header:
+  Machine:         IMAGE_FILE_MACHINE_AMD64
+  Characteristics: [  ]
+sections:
+  - Name:            .text
+    Characteristics: [ IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ ]
+    Alignment:       16
+    SectionData:     'C3'
+  - Name:            .bss
+    Characteristics: [ IMAGE_SCN_CNT_UNINITIALIZED_DATA, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE ]
+    Alignment:       4
+    SectionData:     ''
+    SizeOfRawData:   8
+symbols:
+"#;
+
+    #[test]
+    fn dump_section() {
+        let coff: CoffYaml = serde_yml::from_str(COFF).unwrap();
+        let built = coff.build().unwrap();
+        let path = Path::new("go.o");
+
+        assert_eq!(section_data(path, &built, ".text").unwrap(), [0xc3]);
+
+        let err = section_data(path, &built, ".data").unwrap_err();
+        assert_eq!(err.to_string(), "go.o: no section named '.data'");
+
+        let err = section_data(path, &built, ".bss").unwrap_err();
+        assert_eq!(err.to_string(), "go.o: section '.bss' has no contents");
+    }
+}
@@ -0,0 +1,89 @@
+//! Listing the symbols provided by link libraries for `--list-exports`.
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use boflink::linkobject::{
+    archive::ArchiveMemberKind,
+    library::{LinkLibrary, LinkLibraryParseError},
+};
+
+/// Formats the symbols provided by each member of the archive or the exports
+/// of the DLL.
+pub fn export_listing(path: &Path, data: &[u8]) -> anyhow::Result<String> {
+    use std::fmt::Write;
+
+    if !LinkLibrary::is_library(data) {
+        return Err(anyhow!("{}: file is not an archive or DLL", path.display()));
+    }
+
+    let library = LinkLibrary::parse(data)
+        .map_err(|e: LinkLibraryParseError| anyhow!("{}: {e}", path.display()))?;
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{}:", path.display());
+
+    match library {
+        LinkLibrary::Archive(archive) => {
+            for member in archive.member_exports() {
+                match (member.kind, member.dll) {
+                    (
+                        ArchiveMemberKind::ShortImport | ArchiveMemberKind::LegacyImport,
+                        Some(dll),
+                    ) => {
+                        let _ = writeln!(out, "  {} ({} from {dll})", member.name, member.kind);
+                    }
+                    (kind, _) => {
+                        let _ = writeln!(out, "  {} ({kind})", member.name);
+                    }
+                }
+
+                for symbol in member.symbols {
+                    let _ = writeln!(out, "    {symbol}");
+                }
+            }
+        }
+        LinkLibrary::Dll(dll) => {
+            let _ = writeln!(out, "  {} (DLL)", dll.dll());
+            for symbol in dll.exports() {
+                let _ = writeln!(out, "    {symbol}");
+            }
+        }
+        LinkLibrary::ModuleDefinition(_) => {
+            unreachable!("module-definition files are not parsed as libraries")
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use boflink::linker::LinkerTargetArch;
+    use coffyaml::importlib::ImportlibYaml;
+
+    use super::export_listing;
+
+    #[test]
+    fn import_library() {
+        let importlib: ImportlibYaml =
+            serde_yml::from_str("Library: KERNEL32\nExports:\n  - Sleep\n").unwrap();
+        let built = importlib.build(LinkerTargetArch::Amd64.into()).unwrap();
+
+        let listing = export_listing(Path::new("kernel32.lib"), &built).unwrap();
+        assert!(listing.starts_with("kernel32.lib:\n"), "{listing}");
+        assert!(listing.contains("  KERNEL32 (COFF)\n    __NULL_IMPORT_DESCRIPTOR\n"));
+        assert!(
+            listing
+                .contains("  KERNEL32 (short import from KERNEL32)\n    Sleep\n    __imp_Sleep\n"),
+            "{listing}"
+        );
+    }
+
+    #[test]
+    fn not_library() {
+        assert!(export_listing(Path::new("go.o"), b"\x64\x86").is_err());
+    }
+}
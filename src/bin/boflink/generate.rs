@@ -0,0 +1,53 @@
+use std::io::Write;
+
+use clap::CommandFactory;
+
+use crate::arguments::{CliArgs, CliCommand};
+
+/// Writes the output of the subcommand to stdout.
+pub fn run_command(command: CliCommand) -> anyhow::Result<()> {
+    let mut cmd = CliArgs::command();
+    let mut stdout = std::io::stdout().lock();
+
+    match command {
+        CliCommand::Completions { shell } => {
+            // Generate into a buffer since writing errors make clap_complete
+            // panic
+            let mut completions = Vec::new();
+            let bin_name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, bin_name, &mut completions);
+            stdout.write_all(&completions)?;
+        }
+        CliCommand::Man => {
+            clap_mangen::Man::new(cmd).render(&mut stdout)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use crate::arguments::{CliArgs, CliCommand};
+
+    #[test]
+    fn subcommands() {
+        let args = CliArgs::try_parse_from(["boflink", "completions", "bash"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(CliCommand::Completions {
+                shell: clap_complete::Shell::Bash
+            })
+        ));
+
+        let args = CliArgs::try_parse_from(["boflink", "man"]).unwrap();
+        assert!(matches!(args.command, Some(CliCommand::Man)));
+
+        // Subcommand names after the inputs are files
+        let args = CliArgs::try_parse_from(["boflink", "file.o", "man"]).unwrap();
+        assert!(args.command.is_none());
+        assert_eq!(args.files.len(), 2);
+    }
+}
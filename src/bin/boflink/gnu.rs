@@ -0,0 +1,535 @@
+//! Handling of GNU ld options passed by compiler drivers.
+
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+};
+
+use anyhow::{Result, bail};
+
+/// Program names which enable the GNU ld driver mode. Compiler drivers run
+/// `ld.<name>` for `-fuse-ld=<name>`.
+const LD_PROGRAM_NAMES: [&str; 2] = ["ld.boflink", "ld.boflink.exe"];
+
+/// CRT startup objects which compiler drivers link by default.
+const STARTUP_FILES: [&str; 6] = [
+    "crt1.o",
+    "crt2.o",
+    "dllcrt1.o",
+    "dllcrt2.o",
+    "crtbegin.o",
+    "crtend.o",
+];
+
+/// GNU ld flags which do not affect linking a BOF and are ignored.
+const IGNORED_FLAGS: [&str; 40] = [
+    "Bdynamic",
+    "Bstatic",
+    "call_shared",
+    "dy",
+    "dn",
+    "static",
+    "non_shared",
+    "as-needed",
+    "no-as-needed",
+    "gc-sections",
+    "no-gc-sections",
+    "s",
+    "strip-all",
+    "S",
+    "strip-debug",
+    "x",
+    "discard-all",
+    "X",
+    "discard-locals",
+    "pie",
+    "pic-executable",
+    "no-undefined",
+    "nostdlib",
+    "dynamicbase",
+    "nxcompat",
+    "high-entropy-va",
+    "tsaware",
+    "large-address-aware",
+    "no-seh",
+    "no-insert-timestamp",
+    "insert-timestamp",
+    "enable-auto-image-base",
+    "disable-auto-image-base",
+    "enable-auto-import",
+    "disable-auto-import",
+    "enable-runtime-pseudo-reloc",
+    "disable-runtime-pseudo-reloc",
+    "enable-stdcall-fixup",
+    "disable-stdcall-fixup",
+    "build-id",
+];
+
+/// GNU ld options with a value which do not affect linking a BOF and are
+/// ignored.
+const IGNORED_VALUE_OPTIONS: [&str; 15] = [
+    "z",
+    "plugin",
+    "plugin-opt",
+    "subsystem",
+    "major-os-version",
+    "minor-os-version",
+    "major-subsystem-version",
+    "minor-subsystem-version",
+    "major-image-version",
+    "minor-image-version",
+    "image-base",
+    "file-alignment",
+    "section-alignment",
+    "heap",
+    "stack",
+];
+
+/// GNU ld options which change the output in a way that a BOF can not
+/// represent.
+const INCOMPATIBLE_OPTIONS: [&str; 11] = [
+    "shared",
+    "Bshareable",
+    "dll",
+    "r",
+    "relocatable",
+    "script",
+    "out-implib",
+    "output-def",
+    "export-all-symbols",
+    "whole-archive",
+    "no-whole-archive",
+];
+
+/// Removes the GNU ld options which do not apply to BOFs from the arguments.
+///
+/// Compiler drivers pass these to the linker by default. Options which can
+/// not be supported return an error and unknown options are kept for the
+/// argument parser. Returns the remaining arguments along with the options
+/// which were ignored.
+///
+/// Each file between `--start-lib` and `--end-lib` is passed to the argument
+/// parser as a separate `--start-lib=<file>` option. `-(` and `-)` are passed
+/// as `--start-group` and `--end-group`.
+pub fn translate_gnu_args(
+    args: impl IntoIterator<Item = OsString>,
+) -> Result<(Vec<OsString>, Vec<String>)> {
+    let mut args = args.into_iter();
+    let mut translated = Vec::from_iter(args.next());
+    let mut ignored = Vec::new();
+    let mut lazy = false;
+
+    while let Some(arg) = args.next() {
+        let Some(arg_str) = arg.to_str() else {
+            if lazy {
+                let mut lazy_arg = OsString::from("--start-lib=");
+                lazy_arg.push(arg);
+                translated.push(lazy_arg);
+            } else {
+                translated.push(arg);
+            }
+            continue;
+        };
+
+        if arg_str == "--" {
+            if lazy {
+                bail!("missing --end-lib for --start-lib");
+            }
+
+            translated.push(arg);
+            translated.extend(args.by_ref());
+            break;
+        }
+
+        let Some(option) = arg_str
+            .strip_prefix("--")
+            .or_else(|| arg_str.strip_prefix('-'))
+            .filter(|option| !option.is_empty())
+        else {
+            if lazy {
+                translated.push(format!("--start-lib={arg_str}").into());
+            } else {
+                translated.push(arg);
+            }
+            continue;
+        };
+
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (option, None),
+        };
+
+        // Optimization levels (-O1) and combined -z keywords (-zrelro)
+        let single_dash = !arg_str.starts_with("--");
+        let ignored_combined = single_dash
+            && ((name.len() > 1 && name.starts_with('O') && name[1..].parse::<u32>().is_ok())
+                || (name.len() > 1 && name.starts_with('z')));
+
+        if name == "start-lib" && value.is_none() {
+            if lazy {
+                bail!("nested --start-lib");
+            }
+
+            lazy = true;
+        } else if name == "end-lib" {
+            if !lazy {
+                bail!("stray --end-lib");
+            }
+
+            lazy = false;
+        } else if matches!(name, "start-group" | "(") && value.is_none() {
+            translated.push("--start-group".into());
+        } else if matches!(name, "end-group" | ")") && value.is_none() {
+            translated.push("--end-group".into());
+        } else if INCOMPATIBLE_OPTIONS.contains(&name) || (single_dash && name.starts_with('T')) {
+            bail!("option '{arg_str}' is not supported when linking a BOF");
+        } else if IGNORED_FLAGS.contains(&name) || ignored_combined {
+            ignored.push(arg_str.to_string());
+        } else if IGNORED_VALUE_OPTIONS.contains(&name) {
+            if value.is_some() {
+                ignored.push(arg_str.to_string());
+            } else {
+                let Some(value) = args.next() else {
+                    bail!("missing value for '{arg_str}'");
+                };
+
+                ignored.push(format!("{arg_str} {}", value.to_string_lossy()));
+            }
+        } else {
+            translated.push(arg);
+        }
+    }
+
+    if lazy {
+        bail!("missing --end-lib for --start-lib");
+    }
+
+    Ok((translated, ignored))
+}
+
+/// Returns `true` if the program was run as `ld.boflink` by a compiler
+/// driver using `-fuse-ld=boflink`.
+pub fn is_ld_program(program: Option<&OsString>) -> bool {
+    program
+        .and_then(|program| Path::new(program).file_name())
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| {
+            LD_PROGRAM_NAMES
+                .iter()
+                .any(|ld_name| name.eq_ignore_ascii_case(ld_name))
+        })
+}
+
+/// Checks the arguments from [`translate_gnu_args`] in the GNU ld driver
+/// mode.
+///
+/// The CRT startup objects are removed since BOF loaders do not run the CRT
+/// startup code and are added to the ignored options. Options which are not
+/// known to the argument parser are rejected as unsupported ld options
+/// instead of being reported as unknown boflink options.
+pub fn check_ld_args(
+    args: Vec<OsString>,
+    ignored: &mut Vec<String>,
+    command: &clap::Command,
+) -> Result<Vec<OsString>> {
+    let is_long = |name: &str| {
+        matches!(name, "help" | "version")
+            || command.get_arguments().any(|arg| {
+                arg.get_long() == Some(name)
+                    || arg
+                        .get_all_aliases()
+                        .is_some_and(|aliases| aliases.contains(&name))
+            })
+    };
+
+    let is_short = |name: char| {
+        matches!(name, 'h' | 'V')
+            || command
+                .get_arguments()
+                .any(|arg| arg.get_short() == Some(name))
+    };
+
+    let mut args = args.into_iter();
+    let mut checked = Vec::from_iter(args.next());
+
+    while let Some(arg) = args.next() {
+        let Some(arg_str) = arg.to_str() else {
+            checked.push(arg);
+            continue;
+        };
+
+        if arg_str == "--" {
+            checked.push(arg);
+            checked.extend(args.by_ref());
+            break;
+        }
+
+        let known = if let Some(option) = arg_str.strip_prefix("--") {
+            is_long(option.split_once('=').map_or(option, |(name, _)| name))
+        } else if let Some(option) = arg_str
+            .strip_prefix('-')
+            .filter(|option| !option.is_empty())
+        {
+            option.chars().next().is_some_and(is_short)
+        } else {
+            if Path::new(arg_str)
+                .file_name()
+                .and_then(OsStr::to_str)
+                .is_some_and(|name| STARTUP_FILES.contains(&name))
+            {
+                ignored.push(arg_str.to_string());
+                continue;
+            }
+
+            true
+        };
+
+        if !known {
+            bail!("ld option '{arg_str}' is not supported by boflink");
+        }
+
+        checked.push(arg);
+    }
+
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use clap::CommandFactory;
+
+    use crate::arguments::CliArgs;
+
+    use super::{check_ld_args, is_ld_program, translate_gnu_args};
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn mingw_gcc() {
+        // x86_64-w64-mingw32-gcc -nostartfiles -nostdlib -Wl,--gc-sections
+        let (translated, ignored) = translate_gnu_args(os_args(&[
+            "boflink",
+            "-plugin",
+            "/usr/lib/gcc/x86_64-w64-mingw32/12-win32/liblto_plugin.so",
+            "-plugin-opt=/usr/lib/gcc/x86_64-w64-mingw32/12-win32/lto-wrapper",
+            "-plugin-opt=-fresolution=/tmp/ccQ2n6Vd.res",
+            "-plugin-opt=-pass-through=-lkernel32",
+            "-m",
+            "i386pep",
+            "-Bdynamic",
+            "-o",
+            "bof.o",
+            "-L/usr/lib/gcc/x86_64-w64-mingw32/12-win32",
+            "-L/usr/x86_64-w64-mingw32/lib",
+            "--gc-sections",
+            "/tmp/ccKEhv7D.o",
+            "-lkernel32",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            translated,
+            os_args(&[
+                "boflink",
+                "-m",
+                "i386pep",
+                "-o",
+                "bof.o",
+                "-L/usr/lib/gcc/x86_64-w64-mingw32/12-win32",
+                "-L/usr/x86_64-w64-mingw32/lib",
+                "/tmp/ccKEhv7D.o",
+                "-lkernel32",
+            ])
+        );
+        assert_eq!(ignored.len(), 6);
+    }
+
+    #[test]
+    fn hardening_flags() {
+        // x86_64-w64-mingw32-gcc -Wl,--as-needed,-z,relro,-z,now -Wl,-O1
+        let (translated, ignored) = translate_gnu_args(os_args(&[
+            "boflink",
+            "-m",
+            "i386pep",
+            "--as-needed",
+            "-z",
+            "relro",
+            "-znow",
+            "-O1",
+            "--dynamicbase",
+            "--high-entropy-va",
+            "--subsystem",
+            "console",
+            "--major-os-version=6",
+            "-s",
+            "bof.o",
+        ]))
+        .unwrap();
+
+        assert_eq!(translated, os_args(&["boflink", "-m", "i386pep", "bof.o"]));
+        assert_eq!(
+            ignored,
+            [
+                "--as-needed",
+                "-z relro",
+                "-znow",
+                "-O1",
+                "--dynamicbase",
+                "--high-entropy-va",
+                "--subsystem console",
+                "--major-os-version=6",
+                "-s",
+            ]
+        );
+    }
+
+    #[test]
+    fn incompatible() {
+        assert!(translate_gnu_args(os_args(&["boflink", "-shared", "bof.o"])).is_err());
+        assert!(translate_gnu_args(os_args(&["boflink", "-Tlink.ld", "bof.o"])).is_err());
+        assert!(translate_gnu_args(os_args(&["boflink", "--whole-archive"])).is_err());
+    }
+
+    #[test]
+    fn lazy_objects() {
+        let (translated, _) = translate_gnu_args(os_args(&[
+            "boflink",
+            "a.o",
+            "--start-lib",
+            "b.o",
+            "-lkernel32",
+            "c.o",
+            "--end-lib",
+            "d.o",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            translated,
+            os_args(&[
+                "boflink",
+                "a.o",
+                "--start-lib=b.o",
+                "-lkernel32",
+                "--start-lib=c.o",
+                "d.o",
+            ])
+        );
+
+        assert!(translate_gnu_args(os_args(&["boflink", "--start-lib", "a.o"])).is_err());
+        assert!(translate_gnu_args(os_args(&["boflink", "a.o", "--end-lib"])).is_err());
+    }
+
+    #[test]
+    fn library_groups() {
+        let (translated, ignored) = translate_gnu_args(os_args(&[
+            "boflink",
+            "a.o",
+            "-(",
+            "-lfirst",
+            "-lsecond",
+            "-)",
+            "--start-group",
+            "-lthird",
+            "--end-group",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            translated,
+            os_args(&[
+                "boflink",
+                "a.o",
+                "--start-group",
+                "-lfirst",
+                "-lsecond",
+                "--end-group",
+                "--start-group",
+                "-lthird",
+                "--end-group",
+            ])
+        );
+        assert!(ignored.is_empty());
+    }
+
+    #[test]
+    fn unknown_kept() {
+        let args = os_args(&["boflink", "--merge-bss", "-v", "--", "-s"]);
+        let (translated, ignored) = translate_gnu_args(args.clone()).unwrap();
+        assert_eq!(translated, args);
+        assert!(ignored.is_empty());
+    }
+
+    #[test]
+    fn ld_program() {
+        assert!(is_ld_program(Some(&"/usr/bin/ld.boflink".into())));
+        assert!(is_ld_program(Some(&"LD.BOFLINK.EXE".into())));
+        assert!(!is_ld_program(Some(&"boflink".into())));
+        assert!(!is_ld_program(None));
+    }
+
+    #[test]
+    fn clang_driver() {
+        // clang --target=x86_64-w64-mingw32 -fuse-ld=boflink
+        let (translated, mut ignored) = translate_gnu_args(os_args(&[
+            "ld.boflink",
+            "-m",
+            "i386pep",
+            "-Bdynamic",
+            "-o",
+            "bof.o",
+            "/usr/x86_64-w64-mingw32/lib/crt2.o",
+            "/usr/lib/clang/18/lib/windows/crtbegin.o",
+            "-L/usr/x86_64-w64-mingw32/lib",
+            "bof.c.o",
+            "-lkernel32",
+            "--entry=go",
+            "/usr/lib/clang/18/lib/windows/crtend.o",
+        ]))
+        .unwrap();
+
+        let checked = check_ld_args(translated, &mut ignored, &CliArgs::command()).unwrap();
+        assert_eq!(
+            checked,
+            os_args(&[
+                "ld.boflink",
+                "-m",
+                "i386pep",
+                "-o",
+                "bof.o",
+                "-L/usr/x86_64-w64-mingw32/lib",
+                "bof.c.o",
+                "-lkernel32",
+                "--entry=go",
+            ])
+        );
+        assert_eq!(ignored.len(), 4);
+    }
+
+    #[test]
+    fn ld_unsupported() {
+        let err = check_ld_args(
+            os_args(&["ld.boflink", "--enable-new-dtags", "bof.o"]),
+            &mut Vec::new(),
+            &CliArgs::command(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ld option '--enable-new-dtags' is not supported by boflink"
+        );
+
+        assert!(
+            check_ld_args(
+                os_args(&["ld.boflink", "-Y", "bof.o"]),
+                &mut Vec::new(),
+                &CliArgs::command()
+            )
+            .is_err()
+        );
+    }
+}
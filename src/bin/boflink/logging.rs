@@ -1,13 +1,21 @@
-use std::io::{IsTerminal, Write};
+use std::{
+    fmt::Write as _,
+    io::{IsTerminal, Write},
+    sync::OnceLock,
+};
 
-use log::Level;
+use log::{
+    Level,
+    kv::{self, Key, Value, VisitSource, VisitValue},
+};
 use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
 
-use crate::arguments::{CliArgs, ColorOption};
+use crate::arguments::{CliArgs, ColorOption, LogFormat};
 
 struct CliLogger {
     stdout: BufferWriter,
     stderr: BufferWriter,
+    format: LogFormat,
 }
 
 impl log::Log for CliLogger {
@@ -28,6 +36,13 @@ impl log::Log for CliLogger {
         };
 
         let mut buffer = writer.buffer();
+
+        if self.format == LogFormat::Json {
+            writeln!(buffer, "{}", format_json(record)).unwrap();
+            writer.print(&buffer).unwrap();
+            return;
+        }
+
         write!(buffer, "{}: ", env!("CARGO_BIN_NAME")).unwrap();
 
         match record.level() {
@@ -65,36 +80,264 @@ impl log::Log for CliLogger {
     fn flush(&self) {}
 }
 
+/// Formats the record as a JSON object on a single line.
+///
+/// The structured fields of the record are included after the message.
+fn format_json(record: &log::Record) -> String {
+    let mut out = String::from("{\"level\":");
+    push_json_string(&mut out, &record.level().as_str().to_ascii_lowercase());
+    out.push_str(",\"target\":");
+    push_json_string(&mut out, record.target());
+    out.push_str(",\"message\":");
+    push_json_string(&mut out, &record.args().to_string());
+
+    let _ = record.key_values().visit(&mut JsonFields(&mut out));
+
+    out.push('}');
+    out
+}
+
+/// Appends the string to `out` as a quoted JSON string.
+pub fn push_json_string(out: &mut String, value: &str) {
+    out.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
+
+/// Writes the structured fields of a record as JSON object members.
+struct JsonFields<'a>(&'a mut String);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push(',');
+        push_json_string(self.0, key.as_str());
+        self.0.push(':');
+        value.visit(JsonValue(self.0))
+    }
+}
+
+/// Writes a structured field value as a JSON value.
+struct JsonValue<'a>(&'a mut String);
+
+impl<'v> VisitValue<'v> for JsonValue<'_> {
+    fn visit_any(&mut self, value: Value) -> Result<(), kv::Error> {
+        push_json_string(self.0, &value.to_string());
+        Ok(())
+    }
+
+    fn visit_null(&mut self) -> Result<(), kv::Error> {
+        self.0.push_str("null");
+        Ok(())
+    }
+
+    fn visit_u64(&mut self, value: u64) -> Result<(), kv::Error> {
+        let _ = write!(self.0, "{value}");
+        Ok(())
+    }
+
+    fn visit_i64(&mut self, value: i64) -> Result<(), kv::Error> {
+        let _ = write!(self.0, "{value}");
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Result<(), kv::Error> {
+        let _ = write!(self.0, "{value}");
+        Ok(())
+    }
+
+    fn visit_str(&mut self, value: &str) -> Result<(), kv::Error> {
+        push_json_string(self.0, value);
+        Ok(())
+    }
+}
+
+/// The stderr color choice and log format used for printing diagnostics.
+static DIAGNOSTIC_OUTPUT: OnceLock<(ColorChoice, LogFormat)> = OnceLock::new();
+
+/// Prints an error followed by the notes and help on separate indented
+/// lines.
+///
+/// The output is formatted like the rustc diagnostics. The notes and help
+/// are included as `notes` and `help` fields with JSON logging. If
+/// `separate` is set, the error is separated from the previous error with an
+/// empty line when printing text.
+pub fn print_error(
+    message: &str,
+    notes: &[String],
+    help: Option<&str>,
+    symbol: &str,
+    separate: bool,
+) {
+    if !log::log_enabled!(Level::Error) {
+        return;
+    }
+
+    let Some((color_choice, format)) = DIAGNOSTIC_OUTPUT.get().copied() else {
+        return;
+    };
+
+    if format == LogFormat::Json {
+        match help {
+            Some(help) => log::error!(
+                target: env!("CARGO_BIN_NAME"),
+                symbol = symbol,
+                notes = notes.join("\n"),
+                help = help;
+                "{message}"
+            ),
+            None => log::error!(
+                target: env!("CARGO_BIN_NAME"),
+                symbol = symbol,
+                notes = notes.join("\n");
+                "{message}"
+            ),
+        }
+        return;
+    }
+
+    let writer = BufferWriter::stderr(color_choice);
+    let mut buffer = writer.buffer();
+    if separate {
+        let _ = writeln!(buffer);
+    }
+
+    let _ = write_error(&mut buffer, message, notes, help);
+    let _ = writer.print(&buffer);
+}
+
+/// Writes the rendered error to the buffer.
+fn write_error(
+    buffer: &mut impl WriteColor,
+    message: &str,
+    notes: &[String],
+    help: Option<&str>,
+) -> std::io::Result<()> {
+    write!(buffer, "{}: ", env!("CARGO_BIN_NAME"))?;
+    buffer.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true))?;
+    write!(buffer, "error:")?;
+    buffer.set_color(ColorSpec::new().set_bold(true))?;
+    write!(buffer, " {message}")?;
+    buffer.reset()?;
+    writeln!(buffer)?;
+
+    for note in notes {
+        buffer.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))?;
+        write!(buffer, "  = ")?;
+        buffer.set_color(ColorSpec::new().set_bold(true))?;
+        write!(buffer, "note:")?;
+        buffer.reset()?;
+        writeln!(buffer, " {note}")?;
+    }
+
+    if let Some(help) = help {
+        buffer.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))?;
+        write!(buffer, "  = ")?;
+        buffer.set_color(ColorSpec::new().set_bold(true))?;
+        write!(buffer, "help:")?;
+        buffer.reset()?;
+        writeln!(buffer, " {help}")?;
+    }
+
+    Ok(())
+}
+
 /// Sets up logging for the cli
 pub fn setup_logger(args: &CliArgs) -> anyhow::Result<()> {
-    let color_option = if args.color == ColorOption::Auto
-        && std::env::var("TERM")
-            .ok()
-            .is_none_or(|term| !term.eq_ignore_ascii_case("dumb"))
-        && std::env::var_os("NO_COLOR").is_none()
-    {
-        args.color.into()
-    } else {
-        ColorChoice::Never
+    // Colors are only used for terminals unless forced. termcolor handles
+    // TERM=dumb and NO_COLOR for the auto option
+    let color_choice = |is_terminal: bool| match args.color {
+        ColorOption::Auto if is_terminal => ColorChoice::Auto,
+        ColorOption::Auto => ColorChoice::Never,
+        color => color.into(),
     };
 
+    let stderr_color = color_choice(std::io::stderr().is_terminal());
+    let _ = DIAGNOSTIC_OUTPUT.set((stderr_color, args.log_format));
+
     log::set_boxed_logger(Box::from(CliLogger {
-        stdout: BufferWriter::stdout(
-            if color_option != ColorChoice::Never && std::io::stdout().is_terminal() {
-                color_option
-            } else {
-                ColorChoice::Never
-            },
-        ),
-        stderr: BufferWriter::stderr(
-            if color_option != ColorChoice::Never && std::io::stderr().is_terminal() {
-                color_option
-            } else {
-                ColorChoice::Never
-            },
-        ),
+        stdout: BufferWriter::stdout(color_choice(std::io::stdout().is_terminal())),
+        stderr: BufferWriter::stderr(stderr_color),
+        format: args.log_format,
     }))
     .map(|()| log::set_max_level(args.verbose.log_level_filter()))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use termcolor::{Ansi, NoColor};
+
+    use super::{format_json, write_error};
+
+    #[test]
+    fn json_record() {
+        let fields = [("symbol", "go"), ("section", ".text")];
+        let record = log::Record::builder()
+            .level(log::Level::Warn)
+            .target("boflink")
+            .args(format_args!("\"go\" is\tundefined"))
+            .key_values(&fields)
+            .build();
+
+        assert_eq!(
+            format_json(&record),
+            r#"{"level":"warn","target":"boflink","message":"\"go\" is\tundefined","symbol":"go","section":".text"}"#
+        );
+    }
+
+    #[test]
+    fn rendered_error() {
+        let notes = [
+            "referenced by bof.o:(go)".to_string(),
+            "referenced 2 more times".to_string(),
+        ];
+
+        let mut buffer = NoColor::new(Vec::new());
+        write_error(&mut buffer, "undefined symbol: foo", &notes, None).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).unwrap(),
+            concat!(
+                "boflink: error: undefined symbol: foo\n",
+                "  = note: referenced by bof.o:(go)\n",
+                "  = note: referenced 2 more times\n",
+            )
+        );
+
+        let mut buffer = Ansi::new(Vec::new());
+        write_error(&mut buffer, "undefined symbol: foo", &notes, None).unwrap();
+        let rendered = String::from_utf8(buffer.into_inner()).unwrap();
+        assert!(rendered.contains("\x1b[1m\x1b[31merror:"), "{rendered:?}");
+
+        let mut buffer = NoColor::new(Vec::new());
+        write_error(
+            &mut buffer,
+            "undefined symbol: _fltused",
+            &notes[..1],
+            Some("use --intrinsics=fltused"),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).unwrap(),
+            concat!(
+                "boflink: error: undefined symbol: _fltused\n",
+                "  = note: referenced by bof.o:(go)\n",
+                "  = help: use --intrinsics=fltused\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Splitting the inputs between the links for `--machines`.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::bail;
+use boflink::{
+    inputdata::InputData,
+    linker::{LinkerTargetArch, detect_architecture},
+    pathed_item::PathedItem,
+};
+
+use crate::arguments::TargetMachine;
+
+/// An input file with data shared between the links.
+pub type SharedInput = PathedItem<PathBuf, Arc<InputData>>;
+
+/// Names used for the architectures in input paths.
+const ARCH_NAMES: [&str; 5] = ["x86_64", "amd64", "i386", "x64", "x86"];
+
+/// Returns the output path for the link targeting `machine`.
+///
+/// The extension of `output` is replaced with the machine suffix and `.o`.
+pub fn output_path(output: &Path, machine: TargetMachine) -> PathBuf {
+    let mut name = output.with_extension("").into_os_string();
+    name.push(format!(".{}.o", machine.suffix()));
+    PathBuf::from(name)
+}
+
+/// Splits the inputs between the target machines.
+///
+/// Inputs without a single machine type, like archives with members for
+/// each architecture, are used for every machine. Returns an error if an
+/// input for one machine does not have a matching input for the other
+/// machines. Inputs match if their paths are equal after removing the
+/// architecture names.
+pub fn split_inputs(
+    inputs: Vec<SharedInput>,
+    machines: &[TargetMachine],
+) -> anyhow::Result<Vec<Vec<SharedInput>>> {
+    let mut split = vec![Vec::new(); machines.len()];
+    let mut variants: Vec<(PathBuf, &Path, Vec<bool>)> = Vec::new();
+
+    let inputs = inputs
+        .into_iter()
+        .map(|input| {
+            let arch = detect_architecture(&[PathedItem::new(input.path(), input.as_slice())]).ok();
+            (input, arch)
+        })
+        .collect::<Vec<_>>();
+
+    for (input, arch) in &inputs {
+        let Some(arch) = arch else {
+            continue;
+        };
+
+        let key = arch_neutral_path(input.path());
+        let idx = match variants.iter().position(|(other, _, _)| *other == key) {
+            Some(idx) => idx,
+            None => {
+                variants.push((key, input.path(), vec![false; machines.len()]));
+                variants.len() - 1
+            }
+        };
+
+        if let Some(machine_idx) = machines
+            .iter()
+            .position(|machine| LinkerTargetArch::from(*machine) == *arch)
+        {
+            variants[idx].2[machine_idx] = true;
+        }
+    }
+
+    for (_, path, found) in &variants {
+        if let Some(missing) = found.iter().position(|found| !found) {
+            bail!(
+                "{}: input is not available for {}",
+                path.display(),
+                machines[missing]
+            );
+        }
+    }
+
+    for (input, arch) in inputs {
+        match arch {
+            Some(arch) => {
+                if let Some(idx) = machines
+                    .iter()
+                    .position(|machine| LinkerTargetArch::from(*machine) == arch)
+                {
+                    split[idx].push(input);
+                }
+            }
+            None => {
+                for machine_inputs in &mut split {
+                    machine_inputs.push(input.clone());
+                }
+            }
+        }
+    }
+
+    Ok(split)
+}
+
+/// Returns the path with the architecture names removed from the directory
+/// names and the end of the file stem.
+fn arch_neutral_path(path: &Path) -> PathBuf {
+    let is_arch_name = |name: &str| {
+        ARCH_NAMES
+            .iter()
+            .any(|arch| name.eq_ignore_ascii_case(arch))
+    };
+
+    let mut neutral = PathBuf::new();
+
+    if let Some(parent) = path.parent() {
+        for component in parent.components() {
+            if component.as_os_str().to_str().is_some_and(is_arch_name) {
+                neutral.push("*");
+            } else {
+                neutral.push(component);
+            }
+        }
+    }
+
+    let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return path.to_path_buf();
+    };
+
+    let stem = stem
+        .rfind(['.', '_', '-'])
+        .filter(|&idx| is_arch_name(&stem[idx + 1..]))
+        .map(|idx| &stem[..idx])
+        .unwrap_or(stem);
+
+    neutral.push(stem);
+    if let Some(extension) = path.extension() {
+        neutral.set_extension(extension);
+    }
+
+    neutral
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::arguments::TargetMachine;
+
+    use super::{arch_neutral_path, output_path};
+
+    #[test]
+    fn machine_output_paths() {
+        assert_eq!(
+            output_path(Path::new("out/bof.o"), TargetMachine::Amd64),
+            PathBuf::from("out/bof.x64.o")
+        );
+        assert_eq!(
+            output_path(Path::new("bof"), TargetMachine::I386),
+            PathBuf::from("bof.x86.o")
+        );
+    }
+
+    #[test]
+    fn neutral_paths() {
+        for path in ["bof.x64.o", "bof_x86.o", "bof-AMD64.o"] {
+            assert_eq!(arch_neutral_path(Path::new(path)), PathBuf::from("bof.o"));
+        }
+
+        assert_eq!(
+            arch_neutral_path(Path::new("build/x64/bof.o")),
+            arch_neutral_path(Path::new("build/x86/bof.o"))
+        );
+        assert_eq!(
+            arch_neutral_path(Path::new("x64.o")),
+            PathBuf::from("x64.o")
+        );
+    }
+}
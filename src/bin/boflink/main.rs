@@ -1,15 +1,36 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
 use anyhow::{Result, anyhow, bail};
-use arguments::CliArgs;
+use arguments::{CliArgs, LinkOrderItem, LogFormat};
 use log::{error, info};
 
 use boflink::{
-    libsearch::LibrarySearcher,
-    linker::{LinkerBuilder, error::LinkError},
+    graph::{LinkLayout, LinkProfile},
+    inputdata::InputData,
+    libsearch::{LibraryFind, LibrarySearcher, default_cache_path, expand_sysroot},
+    linker::{
+        AddedSection, ApiSymbolMap, LinkerBuilder, OutputTrailer,
+        error::{LinkError, LinkerSetupError},
+        parse_externs,
+    },
     pathed_item::PathedItem,
 };
 
 mod arguments;
+mod dump;
+mod exports;
+mod generate;
+mod gnu;
 mod logging;
+mod machines;
+mod msvc;
+mod query;
+mod report;
+mod reproduce;
 
 #[derive(Debug)]
 struct EmptyError;
@@ -29,18 +50,26 @@ fn main() {
             match link_error {
                 LinkError::Setup(setup_errors) => {
                     for setup_error in setup_errors.errors() {
-                        error!("{setup_error}");
+                        if let LinkerSetupError::Path(path_error) = setup_error {
+                            error!(
+                                coff:% = path_error.path.display(),
+                                member = path_error.member.as_deref().map(Path::to_string_lossy);
+                                "{setup_error}"
+                            );
+                        } else {
+                            error!("{setup_error}");
+                        }
                     }
                 }
                 LinkError::Symbol(symbol_errors) => {
-                    let error_count = symbol_errors.errors().len();
-                    let mut error_iter = symbol_errors.errors().iter();
-                    for symbol_error in error_iter.by_ref().take(error_count.saturating_sub(1)) {
-                        error!("{symbol_error}\n");
-                    }
-
-                    if let Some(last_error) = error_iter.next() {
-                        error!("{last_error}");
+                    for (idx, diagnostic) in symbol_errors.diagnostics().iter().enumerate() {
+                        logging::print_error(
+                            diagnostic.message(),
+                            diagnostic.notes(),
+                            diagnostic.help(),
+                            diagnostic.symbol(),
+                            idx > 0,
+                        );
                     }
                 }
                 _ => {
@@ -59,6 +88,10 @@ fn main() {
 fn try_main() -> Result<()> {
     let mut args = arguments::parse_arguments()?;
 
+    if let Some(command) = args.command.take() {
+        return generate::run_command(command);
+    }
+
     let it = std::time::Instant::now();
 
     let link_res = run_linker(&mut args);
@@ -72,15 +105,92 @@ fn try_main() -> Result<()> {
 }
 
 fn run_linker(args: &mut CliArgs) -> anyhow::Result<()> {
+    let env_library_paths = if cfg!(windows) {
+        std::env::var_os("LIB")
+            .map(|libenv| Vec::from_iter(std::env::split_paths(&libenv)))
+            .unwrap_or_default()
+    } else if args.lib_env {
+        // LIB uses ';' to separate the directories on all hosts
+        std::env::var("LIB")
+            .map(|libenv| {
+                Vec::from_iter(
+                    libenv
+                        .split(';')
+                        .filter(|path| !path.is_empty())
+                        .map(PathBuf::from),
+                )
+            })
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    // Custom API files read directly instead of found in the search paths
+    // need to be added to the reproduce archive separately
+    let custom_api_files = if args.reproduce.is_some() {
+        Vec::from_iter(
+            args.custom_api
+                .iter()
+                .map(PathBuf::from)
+                .filter(|path| path.is_file()),
+        )
+    } else {
+        Vec::new()
+    };
+
+    // Build the response file before the paths are moved out of the arguments
+    let reproduce_response = args.reproduce.is_some().then(|| {
+        // Search paths relative to the sysroot are left as is and resolve
+        // against the rewritten sysroot
+        let library_paths = args
+            .library_paths
+            .iter()
+            .filter(|path| matches!(expand_sysroot(path, None), Cow::Borrowed(_)));
+
+        let paths = args
+            .files
+            .iter()
+            .chain(&args.start_lib)
+            .chain(&args.module_definitions)
+            .chain(library_paths)
+            .chain(&args.sysroot)
+            .chain(&custom_api_files)
+            .chain(&args.api_map)
+            .chain(&args.externs)
+            .chain(&args.trailer)
+            .chain(&args.layout)
+            .chain(&args.profile)
+            .map(PathBuf::as_path);
+
+        reproduce::response_file(
+            &args.command_line,
+            paths,
+            env_library_paths.iter().map(PathBuf::as_path),
+        )
+    });
+
     let mut library_searcher = LibrarySearcher::new();
+    if let Some(sysroot) = args.sysroot.as_ref() {
+        library_searcher.set_sysroot(sysroot);
+    }
+
     library_searcher.extend_search_paths(std::mem::take(&mut args.library_paths));
+    library_searcher.extend_search_paths(env_library_paths);
 
-    if cfg!(windows) {
-        if let Some(libenv) = std::env::var_os("LIB") {
-            library_searcher.extend_search_paths(std::env::split_paths(&libenv));
+    if !args.no_cache {
+        if let Some(cache_path) = default_cache_path() {
+            library_searcher.set_cache_path(cache_path);
         }
     }
 
+    if args.list_exports {
+        return list_exports(args, &library_searcher);
+    }
+
+    if args.dump_only {
+        return dump_input_sections(args);
+    }
+
     let linker = LinkerBuilder::new().library_searcher(library_searcher);
 
     let linker = if let Some(target_arch) = args.machine.take() {
@@ -95,41 +205,379 @@ fn run_linker(args: &mut CliArgs) -> anyhow::Result<()> {
         linker
     };
 
-    let linker = if let Some(custom_api) = args.custom_api.take() {
-        linker.custom_api(custom_api)
+    let mut linker = linker.custom_apis(std::mem::take(&mut args.custom_api));
+    for custom_api_path in custom_api_files {
+        let data = std::fs::read(&custom_api_path)
+            .map_err(|e| anyhow!("could not open {}: {e}", custom_api_path.display()))?;
+        linker = linker.add_reproduce_file(custom_api_path, data);
+    }
+
+    let linker = match (args.reproduce.take(), reproduce_response) {
+        (Some(reproduce_path), Some(response)) => linker.reproduce(reproduce_path, response),
+        _ => linker,
+    };
+
+    let linker = if let Some(map_path) = args.api_map.take() {
+        let content = std::fs::read_to_string(&map_path)
+            .map_err(|e| anyhow!("could not open {}: {e}", map_path.display()))?;
+        let api_map =
+            ApiSymbolMap::parse(&content).map_err(|e| anyhow!("{}: {e}", map_path.display()))?;
+        linker
+            .api_symbol_map(api_map)
+            .add_reproduce_file(map_path, content)
+    } else {
+        linker
+    };
+
+    let linker = if let Some(externs_path) = args.externs.take() {
+        let content = std::fs::read_to_string(&externs_path)
+            .map_err(|e| anyhow!("could not open {}: {e}", externs_path.display()))?;
+        let externs =
+            parse_externs(&content).map_err(|e| anyhow!("{}: {e}", externs_path.display()))?;
+        linker
+            .loader_externs(externs)
+            .add_reproduce_file(externs_path, content)
     } else {
         linker
     };
 
     let linker = linker.merge_bss(args.merge_bss);
 
-    let mut error_flag = false;
-    let inputs = std::mem::take(&mut args.files)
+    let linker = linker.check_relocations(args.check_relocs);
+
+    let linker = linker.deterministic(args.deterministic);
+
+    let linker = linker.tolerant(args.tolerant);
+
+    let linker = linker.dedup_inputs(args.dedup_inputs);
+
+    let linker = linker.warn_unused_libraries(args.warn_unused_libraries);
+
+    let linker = linker.cross_reference(args.cref);
+
+    let linker = linker.collect_strings(args.print_strings);
+    let linker = linker.collect_symbol_sizes(args.print_symbol_sizes.is_some());
+
+    let linker = std::mem::take(&mut args.why_undefined)
         .into_iter()
-        .filter_map(|file| match std::fs::read(&file) {
-            Ok(buffer) => Some(PathedItem::new(file, buffer)),
-            Err(e) => {
-                error!("could not open {}: {e}", file.display());
-                error_flag = true;
-                None
+        .fold(linker, |linker, symbol| linker.why_undefined(symbol));
+
+    let linker = linker.keep_absolute_symbols(std::mem::take(&mut args.keep_absolute_symbol));
+
+    let linker = linker.keep_symbols(std::mem::take(&mut args.keep_symbol));
+
+    let linker = linker.keep_sections(std::mem::take(&mut args.keep_section));
+
+    let linker = std::mem::take(&mut args.redirect)
+        .into_iter()
+        .fold(linker, |linker, (from, to)| {
+            linker.redirect_symbol(from, to)
+        });
+
+    let linker = linker.intrinsic_groups(args.intrinsics.iter().copied().map(Into::into));
+
+    let linker = linker.delay_load_libraries(std::mem::take(&mut args.delay_load));
+
+    let linker = linker.safeseh(args.safeseh);
+
+    let linker = linker.characteristics_policy(args.section_flags.into());
+    let linker = linker.lnk_info_policy(args.lnk_info.into());
+    let linker = linker.emit_comment(args.emit_comment);
+    let linker = if let Some(ident) = args.ident.take() {
+        linker.ident(ident)
+    } else {
+        linker
+    };
+
+    let linker = linker.entrypoint(std::mem::take(&mut args.entry));
+
+    let linker = linker.entry_first(args.entry_first);
+
+    let linker = if let Some(mode) = args.rename_symbols.take() {
+        linker.rename_symbols(mode.into())
+    } else {
+        linker
+    };
+
+    let linker = if let Some(map_path) = args.symbol_map.take() {
+        linker.symbol_map_path(map_path)
+    } else {
+        linker
+    };
+
+    let linker = if let Some(code_fill) = args.code_fill.take() {
+        linker.code_fill(code_fill)
+    } else {
+        linker
+    };
+
+    let linker = if let Some(data_fill) = args.data_fill.take() {
+        linker.data_fill(data_fill)
+    } else {
+        linker
+    };
+
+    let linker = if let Some(alignment) = args.min_section_align.take() {
+        linker.min_section_alignment(alignment)
+    } else {
+        linker
+    };
+
+    let linker = linker.default_section_alignment(!args.no_default_section_align);
+    let linker = linker.flatten_relocations(!args.no_flatten);
+
+    let linker = if let Some(alignment) = args.output_align.take() {
+        linker.output_alignment(alignment)
+    } else {
+        linker
+    };
+
+    let linker = if let Some(trailer_path) = args.trailer.take() {
+        let trailer = std::fs::read(&trailer_path)
+            .map_err(|e| anyhow!("could not open {}: {e}", trailer_path.display()))?;
+        linker
+            .add_reproduce_file(trailer_path, trailer.clone())
+            .output_trailer(OutputTrailer::Data(trailer))
+    } else if args.trailer_jamcrc {
+        linker.output_trailer(OutputTrailer::Jamcrc)
+    } else {
+        linker
+    };
+
+    let mut linker = linker;
+    for added in std::mem::take(&mut args.add_section) {
+        let data = std::fs::read(&added.path)
+            .map_err(|e| anyhow!("could not open {}: {e}", added.path.display()))?;
+
+        let mut section = AddedSection::new(added.name, data.clone());
+        if let Some(characteristics) = added.characteristics {
+            section = section.characteristics(characteristics);
+        }
+
+        if let Some(alignment) = added.alignment {
+            section = section.alignment(alignment);
+        }
+
+        if let Some(symbol) = added.symbol {
+            section = section.symbol(symbol);
+        }
+
+        linker = linker
+            .add_reproduce_file(added.path, data)
+            .add_section(section);
+    }
+
+    let linker = linker.section_checksums(args.section_checksums);
+
+    let linker = linker.function_aux(args.function_aux);
+    let linker = linker.collapse_refptrs(args.collapse_refptrs);
+    let linker = linker.rip_relative(args.rip_relative);
+    let linker = linker.strip_resources(args.strip_resources);
+
+    let linker = linker.obfuscate_sections(std::mem::take(&mut args.obfuscate_section));
+
+    let linker = if let Some(key) = args.obfuscation_key.take() {
+        linker.obfuscation_key(key)
+    } else {
+        linker
+    };
+
+    let linker = if let Some(layout_path) = args.layout.take() {
+        // A missing layout file is created after linking
+        let linker = match std::fs::read_to_string(&layout_path) {
+            Ok(content) => {
+                let layout = LinkLayout::parse(&content)
+                    .map_err(|e| anyhow!("{}: {e}", layout_path.display()))?;
+                linker
+                    .layout(layout)
+                    .add_reproduce_file(&layout_path, content)
             }
-        })
-        .collect::<Vec<_>>();
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => linker,
+            Err(e) => bail!("could not open {}: {e}", layout_path.display()),
+        };
 
-    let linker = linker.add_inputs(inputs);
+        linker.layout_path(layout_path)
+    } else {
+        linker
+    };
+
+    let linker = if let Some(profile_path) = args.profile.take() {
+        let content = std::fs::read_to_string(&profile_path)
+            .map_err(|e| anyhow!("could not open {}: {e}", profile_path.display()))?;
+        let profile =
+            LinkProfile::parse(&content).map_err(|e| anyhow!("{}: {e}", profile_path.display()))?;
+        linker
+            .profile(profile)
+            .add_reproduce_file(&profile_path, content)
+    } else {
+        linker
+    };
+
+    let linker = linker.split_cold(args.split_cold);
+    let linker = linker.pack_sections(args.pack_sections);
+
+    let mut error_flag = false;
+    let mut read_inputs = |files: Vec<PathBuf>| {
+        files
+            .into_iter()
+            .filter_map(|file| match InputData::read(&file) {
+                Ok(buffer) => Some(PathedItem::new(file, buffer)),
+                Err(e) => {
+                    error!("could not open {}: {e}", file.display());
+                    error_flag = true;
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let inputs = read_inputs(std::mem::take(&mut args.files));
+    let lazy_inputs = read_inputs(std::mem::take(&mut args.start_lib));
+    let module_definitions = read_inputs(std::mem::take(&mut args.module_definitions));
 
     if error_flag {
         bail!(EmptyError);
     }
 
-    let linker = linker.add_libraries(std::mem::take(&mut args.libraries));
+    let linker = linker.add_module_definitions(module_definitions);
+    let libraries = std::mem::take(&mut args.libraries);
+
+    if !args.machines.is_empty() {
+        return link_machines(args, linker.add_libraries(libraries), inputs, lazy_inputs);
+    }
+
+    let linker = linker
+        .positional_libraries(args.positional_libraries)
+        .add_lazy_inputs(lazy_inputs);
+    let linker = add_in_link_order(linker, inputs, libraries, &args.link_order);
+    link_output(args, linker, &args.output)
+}
+
+/// Adds the inputs, link libraries and library groups to the linker in
+/// command line order.
+fn add_in_link_order(
+    mut linker: LinkerBuilder<LibrarySearcher>,
+    inputs: Vec<PathedItem<PathBuf, InputData>>,
+    libraries: Vec<String>,
+    link_order: &[LinkOrderItem],
+) -> LinkerBuilder<LibrarySearcher> {
+    let mut inputs = inputs.into_iter();
+    let mut libraries = libraries.into_iter();
+
+    for item in link_order {
+        linker = match item {
+            LinkOrderItem::File => match inputs.next() {
+                Some(input) => linker.add_input(input),
+                None => linker,
+            },
+            LinkOrderItem::Library => match libraries.next() {
+                Some(library) => linker.add_library(library),
+                None => linker,
+            },
+            LinkOrderItem::StartGroup => linker.start_library_group(),
+            LinkOrderItem::EndGroup => linker.end_library_group(),
+        };
+    }
+
+    linker.add_inputs(inputs).add_libraries(libraries)
+}
+
+/// Links the inputs once for each machine passed to `--machines`.
+///
+/// The links share the input data and the library configuration. Each link
+/// only uses the inputs for its machine along with the inputs without a
+/// single machine type.
+fn link_machines(
+    args: &CliArgs,
+    linker: LinkerBuilder<LibrarySearcher>,
+    inputs: Vec<PathedItem<PathBuf, InputData>>,
+    lazy_inputs: Vec<PathedItem<PathBuf, InputData>>,
+) -> anyhow::Result<()> {
+    let mut target_machines = Vec::new();
+    for machine in &args.machines {
+        if !target_machines.contains(machine) {
+            target_machines.push(*machine);
+        }
+    }
+
+    let share = |inputs: Vec<PathedItem<PathBuf, InputData>>| {
+        Vec::from_iter(inputs.into_iter().map(|input| input.map_item(Arc::new)))
+    };
+
+    let inputs = machines::split_inputs(share(inputs), &target_machines)?;
+    let lazy_inputs = machines::split_inputs(share(lazy_inputs), &target_machines)?;
+
+    for ((machine, inputs), lazy_inputs) in target_machines.into_iter().zip(inputs).zip(lazy_inputs)
+    {
+        let output = machines::output_path(&args.output, machine);
+        let linker = linker
+            .clone()
+            .architecture(machine.into())
+            .add_shared_inputs(inputs)
+            .add_shared_lazy_inputs(lazy_inputs);
 
+        if let Err(e) = link_output(args, linker, &output) {
+            error!("could not link {}", output.display());
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Links the configured inputs, prints the requested reports and writes the
+/// output file.
+fn link_output(
+    args: &CliArgs,
+    linker: LinkerBuilder<LibrarySearcher>,
+    output: &Path,
+) -> anyhow::Result<()> {
     let mut linker = linker.build();
 
-    match linker.link() {
+    let link_res = linker.link();
+
+    if args.cref {
+        print!("{}", report::cross_reference_table(linker.report()));
+    }
+
+    if args.print_reloc_stats {
+        print!("{}", report::relocation_stats(linker.report()));
+    }
+
+    if args.print_strings {
+        print!("{}", report::string_report(linker.report()));
+    }
+
+    if let Some(count) = args.print_symbol_sizes {
+        print!("{}", report::symbol_size_report(linker.report(), count));
+    }
+
+    if args.print_api_usage {
+        print!("{}", report::api_usage(linker.report()));
+    }
+
+    if args.print_library_deps {
+        print!("{}", report::library_dependencies(linker.report()));
+    }
+
+    if !linker.report().symbol_queries().is_empty() {
+        let queries = linker.report().symbol_queries();
+        if args.log_format == LogFormat::Json {
+            print!("{}", query::query_json(queries));
+        } else {
+            print!("{}", query::query_report(queries));
+        }
+    }
+
+    if args.time_report {
+        eprint!("{}", report::time_report(linker.report()));
+    }
+
+    match link_res {
         Ok(built) => {
-            std::fs::write(&args.output, built)
+            std::fs::write(output, &built)
                 .map_err(|e| anyhow!("could not write output file: {e}"))?;
+            dump::dump_sections(output, &built, &args.dump_section)?;
         }
         Err(e) => {
             return Err(anyhow!(e));
@@ -138,3 +586,30 @@ fn run_linker(args: &mut CliArgs) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Writes the sections requested with `--dump-section` from the input COFF.
+fn dump_input_sections(args: &CliArgs) -> anyhow::Result<()> {
+    let [input] = args.files.as_slice() else {
+        bail!("--dump-only requires a single input COFF");
+    };
+
+    let data =
+        InputData::read(input).map_err(|e| anyhow!("could not open {}: {e}", input.display()))?;
+    dump::dump_sections(input, &data, &args.dump_section)
+}
+
+/// Prints the symbols provided by the input files and link libraries.
+fn list_exports(args: &CliArgs, library_searcher: &LibrarySearcher) -> anyhow::Result<()> {
+    for file in &args.files {
+        let data =
+            InputData::read(file).map_err(|e| anyhow!("could not open {}: {e}", file.display()))?;
+        print!("{}", exports::export_listing(file, &data)?);
+    }
+
+    for library in &args.libraries {
+        let found = library_searcher.find_library(library)?;
+        print!("{}", exports::export_listing(found.path(), &found)?);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,227 @@
+//! Translation of MSVC `LINK` style command lines.
+
+use std::{ffi::OsString, path::Path};
+
+use anyhow::{Result, bail};
+
+/// Program names which enable the MSVC command line flavor.
+const LINK_PROGRAM_NAMES: [&str; 3] = ["link", "lld-link", "boflink-link"];
+
+/// Options which do not affect linking a BOF and are ignored.
+const IGNORED_OPTIONS: [&str; 20] = [
+    "NOLOGO",
+    "DEBUG",
+    "INCREMENTAL",
+    "SUBSYSTEM",
+    "NXCOMPAT",
+    "DYNAMICBASE",
+    "HIGHENTROPYVA",
+    "MANIFEST",
+    "MANIFESTFILE",
+    "MANIFESTUAC",
+    "MANIFESTEMBED",
+    "OPT",
+    "ERRORREPORT",
+    "PDB",
+    "PDBALTPATH",
+    "TLBID",
+    "ILK",
+    "NODEFAULTLIB",
+    "RELEASE",
+    "NOIMPLIB",
+];
+
+/// Removes the flavor option from the arguments and returns `true` if the
+/// MSVC command line flavor should be used.
+///
+/// The flavor is selected using `--flavor=link` or `--flavor link` as the
+/// first argument or by running the program as `link`, `lld-link` or
+/// `boflink-link`.
+pub fn take_link_flavor(args: &mut Vec<OsString>) -> Result<bool> {
+    let flavor = match args.get(1).and_then(|arg| arg.to_str()) {
+        Some("--flavor" | "-flavor") => {
+            let Some(flavor) = args.get(2).cloned() else {
+                bail!("missing value for --flavor");
+            };
+            args.drain(1..3);
+            Some(flavor)
+        }
+        Some(arg) if arg.starts_with("--flavor=") => {
+            let flavor = OsString::from(&arg["--flavor=".len()..]);
+            args.remove(1);
+            Some(flavor)
+        }
+        _ => None,
+    };
+
+    if let Some(flavor) = flavor {
+        return match flavor.to_str() {
+            Some("link") => Ok(true),
+            Some("gnu") => Ok(false),
+            _ => bail!("unknown flavor '{}'", flavor.to_string_lossy()),
+        };
+    }
+
+    Ok(args
+        .first()
+        .and_then(|program| Path::new(program).file_stem())
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| {
+            LINK_PROGRAM_NAMES
+                .iter()
+                .any(|name| stem.eq_ignore_ascii_case(name))
+        }))
+}
+
+/// Translates the MSVC `LINK` style arguments into the boflink arguments.
+///
+/// The program name is kept as the first argument. Returns the translated
+/// arguments along with the options which were ignored.
+pub fn translate_link_args(
+    args: impl IntoIterator<Item = OsString>,
+) -> Result<(Vec<OsString>, Vec<String>)> {
+    let mut args = args.into_iter();
+    let mut translated = Vec::from_iter(args.next());
+    let mut ignored = Vec::new();
+
+    for arg in args {
+        let Some(arg_str) = arg.to_str() else {
+            translated.push(arg);
+            continue;
+        };
+
+        let Some(option) = arg_str
+            .strip_prefix('/')
+            .or_else(|| arg_str.strip_prefix('-'))
+        else {
+            translated.push(arg);
+            continue;
+        };
+
+        let (name, value) = match option.split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (option, None),
+        };
+
+        let name = name.to_ascii_uppercase();
+
+        let required_value = || -> Result<&str> {
+            match value {
+                Some(value) if !value.is_empty() => Ok(value),
+                _ => bail!("missing value for /{name}"),
+            }
+        };
+
+        match name.as_str() {
+            "OUT" => translated.extend(["-o".into(), required_value()?.into()]),
+            "LIBPATH" => translated.extend(["-L".into(), required_value()?.into()]),
+            "ENTRY" => translated.extend(["-e".into(), required_value()?.into()]),
+            "DELAYLOAD" => {
+                translated.extend(["--delay-load".into(), required_value()?.into()]);
+            }
+            "REPRODUCE" => {
+                translated.extend(["--reproduce".into(), required_value()?.into()]);
+            }
+            "DEFAULTLIB" => {
+                let library = required_value()?;
+                let library = match library.len().checked_sub(4) {
+                    Some(idx)
+                        if library
+                            .get(idx..)
+                            .is_some_and(|ext| ext.eq_ignore_ascii_case(".lib")) =>
+                    {
+                        &library[..idx]
+                    }
+                    _ => library,
+                };
+
+                translated.extend(["-l".into(), library.into()]);
+            }
+            "MACHINE" => {
+                let emulation = match required_value()?.to_ascii_uppercase().as_str() {
+                    "X64" | "AMD64" => "i386pep",
+                    "X86" | "I386" => "i386pe",
+                    machine => bail!("unsupported machine '{machine}'"),
+                };
+
+                translated.extend(["-m".into(), emulation.into()]);
+            }
+            "VERBOSE" => translated.push("-v".into()),
+            _ if IGNORED_OPTIONS.contains(&name.as_str()) => ignored.push(arg_str.to_string()),
+            // Unix paths are also parsed as options. Treat them as input
+            // files if they are not a known option
+            _ if arg_str.starts_with('/') && option.contains(['/', '\\']) => translated.push(arg),
+            _ => bail!("unknown option '{arg_str}'"),
+        }
+    }
+
+    Ok((translated, ignored))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::{take_link_flavor, translate_link_args};
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn flavor() {
+        let mut args = os_args(&["boflink", "--flavor=link", "a.obj"]);
+        assert!(take_link_flavor(&mut args).unwrap());
+        assert_eq!(args, os_args(&["boflink", "a.obj"]));
+
+        let mut args = os_args(&["/usr/bin/LINK.exe", "a.obj"]);
+        assert!(take_link_flavor(&mut args).unwrap());
+
+        let mut args = os_args(&["boflink", "a.obj"]);
+        assert!(!take_link_flavor(&mut args).unwrap());
+    }
+
+    #[test]
+    fn translate() {
+        let (translated, ignored) = translate_link_args(os_args(&[
+            "link",
+            "/nologo",
+            "/OUT:bof.o",
+            "/machine:x64",
+            "-LIBPATH:C:\\lib",
+            "/DEFAULTLIB:kernel32.LIB",
+            "/entry:go",
+            "/DELAYLOAD:user32.dll",
+            "/home/user/a.obj",
+            "b.obj",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            translated,
+            os_args(&[
+                "link",
+                "-o",
+                "bof.o",
+                "-m",
+                "i386pep",
+                "-L",
+                "C:\\lib",
+                "-l",
+                "kernel32",
+                "-e",
+                "go",
+                "--delay-load",
+                "user32.dll",
+                "/home/user/a.obj",
+                "b.obj",
+            ])
+        );
+        assert_eq!(ignored, ["/nologo"]);
+    }
+
+    #[test]
+    fn unknown_option() {
+        assert!(translate_link_args(os_args(&["link", "/DLL"])).is_err());
+    }
+}
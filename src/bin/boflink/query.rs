@@ -0,0 +1,205 @@
+//! Formatting the symbol resolution steps for `--why-undefined`.
+
+use std::fmt::Write;
+
+use boflink::linker::{ProbeResult, SymbolProbe, SymbolQuery};
+
+use crate::logging::push_json_string;
+
+/// Formats the resolution steps for the queried symbols as text.
+pub fn query_report(queries: &[SymbolQuery]) -> String {
+    let mut out = String::new();
+
+    for query in queries {
+        let _ = writeln!(out, "{}: {}", query.symbol, query.status);
+
+        if let Some(api_symbol) = query.api_symbol.as_deref() {
+            let _ = writeln!(out, "  mapped to API symbol '{api_symbol}'");
+        }
+
+        if !query.probes.is_empty() {
+            out.push_str("  searched:\n");
+        }
+
+        for probe in &query.probes {
+            let _ = write!(out, "    {} {}: ", probe.source, probe.path.display());
+            let _ = match &probe.result {
+                ProbeResult::Found(Some(member)) => writeln!(out, "found in {}", member.display()),
+                ProbeResult::Found(None) => writeln!(out, "found"),
+                ProbeResult::NotFound => writeln!(out, "not found"),
+                ProbeResult::Duplicate(member) => {
+                    writeln!(out, "skipped duplicate {}", member.display())
+                }
+                ProbeResult::Error(e) => writeln!(out, "error: {e}"),
+            };
+        }
+
+        if !query.candidates.is_empty() {
+            out.push_str("  near misses:\n");
+        }
+
+        for (library, candidate) in &query.candidates {
+            let _ = writeln!(out, "    {candidate} in {}", library.display());
+        }
+
+        for (label, files) in [
+            ("defined by", &query.definitions),
+            ("referenced by", &query.references),
+        ] {
+            if !files.is_empty() {
+                let _ = writeln!(out, "  {label}:");
+            }
+
+            for file in files {
+                let _ = writeln!(out, "    {file}");
+            }
+        }
+    }
+
+    out
+}
+
+/// Formats the resolution steps for the queried symbols as one JSON object
+/// per line.
+pub fn query_json(queries: &[SymbolQuery]) -> String {
+    let mut out = String::new();
+
+    for query in queries {
+        out.push_str("{\"symbol\":");
+        push_json_string(&mut out, &query.symbol);
+        out.push_str(",\"status\":");
+        push_json_string(&mut out, &query.status.to_string());
+        out.push_str(",\"api_symbol\":");
+        match query.api_symbol.as_deref() {
+            Some(api_symbol) => push_json_string(&mut out, api_symbol),
+            None => out.push_str("null"),
+        }
+
+        out.push_str(",\"probes\":[");
+        for (idx, probe) in query.probes.iter().enumerate() {
+            if idx > 0 {
+                out.push(',');
+            }
+            push_probe(&mut out, probe);
+        }
+
+        out.push_str("],\"candidates\":[");
+        for (idx, (library, candidate)) in query.candidates.iter().enumerate() {
+            if idx > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"library\":");
+            push_json_string(&mut out, &library.to_string_lossy());
+            out.push_str(",\"symbol\":");
+            push_json_string(&mut out, candidate);
+            out.push('}');
+        }
+
+        for (key, files) in [
+            ("definitions", &query.definitions),
+            ("references", &query.references),
+        ] {
+            out.push_str("],");
+            push_json_string(&mut out, key);
+            out.push_str(":[");
+            for (idx, file) in files.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                push_json_string(&mut out, file);
+            }
+        }
+
+        out.push_str("]}\n");
+    }
+
+    out
+}
+
+/// Appends the probe to `out` as a JSON object.
+fn push_probe(out: &mut String, probe: &SymbolProbe) {
+    out.push_str("{\"source\":");
+    push_json_string(out, &probe.source.to_string());
+    out.push_str(",\"path\":");
+    push_json_string(out, &probe.path.to_string_lossy());
+    out.push_str(",\"result\":");
+
+    let (result, detail) = match &probe.result {
+        ProbeResult::Found(member) => (
+            "found",
+            member.as_ref().map(|member| member.to_string_lossy()),
+        ),
+        ProbeResult::NotFound => ("not found", None),
+        ProbeResult::Duplicate(member) => ("duplicate", Some(member.to_string_lossy())),
+        ProbeResult::Error(e) => ("error", Some(e.as_str().into())),
+    };
+
+    push_json_string(out, result);
+
+    if let Some(detail) = detail {
+        out.push_str(if matches!(probe.result, ProbeResult::Error(_)) {
+            ",\"error\":"
+        } else {
+            ",\"member\":"
+        });
+        push_json_string(out, &detail);
+    }
+
+    out.push('}');
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use boflink::linker::{ProbeResult, ProbeSource, SymbolProbe, SymbolQuery, SymbolStatus};
+
+    use super::{query_json, query_report};
+
+    fn undefined_query() -> SymbolQuery {
+        SymbolQuery {
+            symbol: "__imp_Sleep".into(),
+            api_symbol: None,
+            status: SymbolStatus::Undefined,
+            probes: vec![
+                SymbolProbe {
+                    source: ProbeSource::Api,
+                    path: PathBuf::from("Beacon API"),
+                    result: ProbeResult::NotFound,
+                },
+                SymbolProbe {
+                    source: ProbeSource::Library,
+                    path: PathBuf::from("kernel32.lib"),
+                    result: ProbeResult::NotFound,
+                },
+            ],
+            candidates: vec![(PathBuf::from("kernel32.lib"), "__imp_sleep".into())],
+            definitions: Vec::new(),
+            references: vec!["main.o".into()],
+        }
+    }
+
+    #[test]
+    fn text_report() {
+        let report = query_report(&[undefined_query()]);
+        assert_eq!(
+            report,
+            "__imp_Sleep: undefined\n  searched:\n    api Beacon API: not found\n    \
+             library kernel32.lib: not found\n  near misses:\n    __imp_sleep in kernel32.lib\n  \
+             referenced by:\n    main.o\n"
+        );
+    }
+
+    #[test]
+    fn json_report() {
+        let report = query_json(&[undefined_query()]);
+        assert_eq!(
+            report,
+            "{\"symbol\":\"__imp_Sleep\",\"status\":\"undefined\",\"api_symbol\":null,\
+             \"probes\":[{\"source\":\"api\",\"path\":\"Beacon API\",\"result\":\"not found\"},\
+             {\"source\":\"library\",\"path\":\"kernel32.lib\",\"result\":\"not found\"}],\
+             \"candidates\":[{\"library\":\"kernel32.lib\",\"symbol\":\"__imp_sleep\"}],\
+             \"definitions\":[],\"references\":[\"main.o\"]}\n"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Formatting the link reports printed after linking.
+
+use boflink::{
+    graph::StringEncoding,
+    linker::{LibraryDependency, LinkReport},
+};
+
+/// Formats the phase and library timings from the report.
+pub fn time_report(report: &LinkReport) -> String {
+    use std::fmt::Write;
+
+    const SEPARATOR: &str =
+        "===-------------------------------------------------------------------------===";
+
+    let total = report.total_time().as_secs_f64();
+    let percent = |secs: f64| {
+        if total > 0.0 {
+            secs / total * 100.0
+        } else {
+            0.0
+        }
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{SEPARATOR}");
+    let _ = writeln!(out, "{:>47}", "Link time report");
+    let _ = writeln!(out, "{SEPARATOR}");
+    let _ = writeln!(out, "  Total Execution Time: {total:.4} seconds\n");
+    let _ = writeln!(out, "   ---Wall Time---  --- Name ---");
+
+    for (phase, time) in report.phase_times() {
+        let secs = time.as_secs_f64();
+        let _ = writeln!(out, "   {secs:.4} ({:5.1}%)  {phase}", percent(secs));
+    }
+
+    let _ = writeln!(out, "   {total:.4} (100.0%)  Total\n");
+
+    let mut libraries = Vec::from_iter(report.library_times());
+    if !libraries.is_empty() {
+        libraries.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let _ = writeln!(out, "   ---Wall Time---  --- Library ---");
+        for (library, time) in libraries {
+            let secs = time.as_secs_f64();
+            let _ = writeln!(
+                out,
+                "   {secs:.4} ({:5.1}%)  {}",
+                percent(secs),
+                library.display()
+            );
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Formats the symbol cross-reference table from the report like ld.
+///
+/// The first file listed for each symbol defines it and the remaining files
+/// reference it.
+pub fn cross_reference_table(report: &LinkReport) -> String {
+    use std::fmt::Write;
+
+    const SYMBOL_WIDTH: usize = 50;
+
+    let mut out = String::from("Cross Reference Table\n\n");
+    let _ = writeln!(out, "{:<SYMBOL_WIDTH$}File", "Symbol");
+
+    for cref in report.cross_references() {
+        let mut symbol = cref.symbol.as_str();
+
+        if cref.definitions.is_empty() {
+            let _ = writeln!(out, "{symbol:<SYMBOL_WIDTH$}<undefined>");
+            symbol = "";
+        }
+
+        for file in cref.definitions.iter().chain(&cref.references) {
+            if symbol.len() >= SYMBOL_WIDTH {
+                let _ = writeln!(out, "{symbol}");
+                symbol = "";
+            }
+
+            let _ = writeln!(out, "{symbol:<SYMBOL_WIDTH$}{file}");
+            symbol = "";
+        }
+    }
+
+    out
+}
+
+/// Formats the relocation counts for each output section followed by the
+/// totals for each relocation type.
+pub fn relocation_stats(report: &LinkReport) -> String {
+    use std::fmt::Write;
+
+    const SECTION_WIDTH: usize = 16;
+    const TYPE_WIDTH: usize = 32;
+
+    let mut out = String::from("Relocation Statistics\n\n");
+    let _ = writeln!(
+        out,
+        "{:<SECTION_WIDTH$}{:<TYPE_WIDTH$}Count",
+        "Section", "Type"
+    );
+
+    for count in report.relocation_counts() {
+        let _ = writeln!(
+            out,
+            "{:<SECTION_WIDTH$}{:<TYPE_WIDTH$}{}",
+            count.section,
+            relocation_type_label(count.typ, count.type_name),
+            count.count
+        );
+    }
+
+    out.push('\n');
+
+    for (typ, type_name, count) in report.relocation_totals() {
+        let _ = writeln!(
+            out,
+            "{:<SECTION_WIDTH$}{:<TYPE_WIDTH$}{count}",
+            "<total>",
+            relocation_type_label(typ, type_name),
+        );
+    }
+
+    out
+}
+
+/// Returns the relocation type name with the value for unknown types.
+pub fn relocation_type_label(typ: u16, type_name: &str) -> String {
+    if type_name == "unknown" {
+        format!("unknown ({typ:#06x})")
+    } else {
+        type_name.to_string()
+    }
+}
+
+/// Formats the printable strings in the output data sections from the report.
+///
+/// UTF-16 strings are prefixed with `L`.
+pub fn string_report(report: &LinkReport) -> String {
+    use std::fmt::Write;
+
+    const LOCATION_WIDTH: usize = 24;
+    const INPUT_WIDTH: usize = 40;
+
+    let mut out = String::from("Output Strings\n\n");
+    let _ = writeln!(
+        out,
+        "{:<LOCATION_WIDTH$} {:<INPUT_WIDTH$} String",
+        "Location", "Input"
+    );
+
+    for string in report.strings() {
+        let location = format!("{}+{:#x}", string.section, string.offset);
+        let input = format!("{}({})", string.coff, string.input_section);
+        let prefix = match string.encoding {
+            StringEncoding::Ascii => "",
+            StringEncoding::Utf16 => "L",
+        };
+
+        let _ = writeln!(
+            out,
+            "{location:<LOCATION_WIDTH$} {input:<INPUT_WIDTH$} {prefix}{:?}",
+            string.value
+        );
+    }
+
+    out
+}
+
+/// Formats the `count` largest symbols from the report.
+pub fn symbol_size_report(report: &LinkReport, count: usize) -> String {
+    use std::fmt::Write;
+
+    const LOCATION_WIDTH: usize = 24;
+    const INPUT_WIDTH: usize = 40;
+
+    let mut out = String::from("Symbol Sizes\n\n");
+    let _ = writeln!(
+        out,
+        "{:>10} {:<8} {:<LOCATION_WIDTH$} {:<INPUT_WIDTH$} Symbol",
+        "Size", "Kind", "Location", "Input"
+    );
+
+    for symbol in report.symbol_sizes().iter().take(count) {
+        let location = format!("{}+{:#x}", symbol.section, symbol.address);
+        let kind = if symbol.code { "function" } else { "data" };
+        let _ = writeln!(
+            out,
+            "{:>10} {kind:<8} {location:<LOCATION_WIDTH$} {:<INPUT_WIDTH$} {}",
+            symbol.size, symbol.coff, symbol.name
+        );
+    }
+
+    out
+}
+
+/// Formats the resolved Beacon API functions grouped by category.
+pub fn api_usage(report: &LinkReport) -> String {
+    use std::fmt::Write;
+
+    const CATEGORY_WIDTH: usize = 20;
+
+    let mut out = String::from("Beacon API Usage\n\n");
+    let _ = writeln!(out, "{:<CATEGORY_WIDTH$}Functions", "Category");
+
+    for (category, names) in report.api_categories() {
+        let names = Vec::from_iter(names.iter().map(String::as_str));
+        let _ = writeln!(
+            out,
+            "{:<CATEGORY_WIDTH$}{}",
+            category.to_string(),
+            names.join(", ")
+        );
+    }
+
+    out
+}
+
+/// Formats the opened link libraries as a tree where the children of a
+/// library are the libraries requested by its members.
+///
+/// Libraries requested by input COFFs are at the top level along with the
+/// libraries from the command line.
+pub fn library_dependencies(report: &LinkReport) -> String {
+    use std::fmt::Write;
+
+    fn write_library(
+        out: &mut String,
+        dependencies: &[LibraryDependency],
+        dependency: &LibraryDependency,
+        depth: usize,
+    ) {
+        let indent = depth * 2;
+        let _ = write!(out, "{:indent$}{}", "", dependency.library.display());
+        match &dependency.requested_by {
+            Some((file, Some(member))) => {
+                let _ = write!(out, " (from {}({}))", file.display(), member.display());
+            }
+            Some((file, None)) => {
+                let _ = write!(out, " (from {})", file.display());
+            }
+            None => (),
+        }
+        out.push('\n');
+
+        for child in dependencies.iter().filter(|child| {
+            child
+                .requested_by
+                .as_ref()
+                .is_some_and(|(file, member)| member.is_some() && *file == dependency.library)
+        }) {
+            write_library(out, dependencies, child, depth + 1);
+        }
+    }
+
+    let mut out = String::from("Library Dependencies\n\n");
+
+    let dependencies = report.library_dependencies();
+    for dependency in dependencies.iter().filter(|dependency| {
+        dependency
+            .requested_by
+            .as_ref()
+            .is_none_or(|(file, member)| {
+                member.is_none() || !dependencies.iter().any(|library| library.library == *file)
+            })
+    }) {
+        write_library(&mut out, dependencies, dependency, 0);
+    }
+
+    out
+}
@@ -0,0 +1,134 @@
+//! Building the response file for the `--reproduce` archive.
+
+use std::{ffi::OsString, path::Path};
+
+use boflink::linker::reproduce_path;
+
+/// Returns the response file for replaying the link from the extracted
+/// reproduce archive.
+///
+/// Arguments naming one of the `paths` are rewritten to the paths of the
+/// files in the archive. This includes the values of `--option=<path>` and
+/// `-L<path>` arguments. The `--reproduce` option is removed and the
+/// `extra_library_paths` are appended as `-L` options.
+pub fn response_file<'a>(
+    command_line: &[OsString],
+    paths: impl IntoIterator<Item = &'a Path>,
+    extra_library_paths: impl IntoIterator<Item = &'a Path>,
+) -> String {
+    let paths = Vec::from_iter(paths);
+    let rewrite = |value: &str| {
+        paths
+            .iter()
+            .any(|path| path.as_os_str() == value)
+            .then(|| reproduce_path(value))
+    };
+
+    let mut response = String::new();
+    let mut args = command_line.iter().skip(1).map(|arg| arg.to_string_lossy());
+
+    while let Some(arg) = args.next() {
+        if arg == "--reproduce" {
+            args.next();
+            continue;
+        } else if arg.starts_with("--reproduce=") {
+            continue;
+        }
+
+        let rewritten = if let Some(path) = rewrite(&arg) {
+            path
+        } else if let Some(path) = arg
+            .strip_prefix("-L")
+            .filter(|value| !value.is_empty())
+            .and_then(rewrite)
+        {
+            format!("-L{path}")
+        } else if let Some((option, path)) = arg
+            .split_once('=')
+            .filter(|(option, _)| option.starts_with('-'))
+            .and_then(|(option, value)| Some((option, rewrite(value)?)))
+        {
+            format!("{option}={path}")
+        } else {
+            arg.into_owned()
+        };
+
+        response.push_str(&rewritten);
+        response.push('\n');
+    }
+
+    for library_path in extra_library_paths {
+        response.push_str(&format!("-L{}\n", reproduce_path(library_path)));
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, path::Path};
+
+    use boflink::linker::reproduce_path;
+
+    use super::response_file;
+
+    #[test]
+    fn rewritten_paths() {
+        let command_line = [
+            "boflink",
+            "-o",
+            "bof.o",
+            "--reproduce",
+            "repro.tar",
+            "-Llib",
+            "-L",
+            "lib",
+            "--start-lib=lazy.o",
+            "-e",
+            "go",
+            "go.o",
+            "-lkernel32",
+            "--sysroot=/opt/mingw",
+            "-L",
+            "=/lib",
+            "--custom-api",
+            "api.dll",
+            "--custom-api=api.dll",
+            "--custom-api=libapi",
+        ]
+        .map(OsString::from);
+
+        let paths = ["lib", "lazy.o", "go.o", "/opt/mingw", "api.dll"].map(Path::new);
+        let response = response_file(&command_line, paths, [Path::new("/sdk/lib")]);
+
+        let lib = reproduce_path("lib");
+        let lazy = reproduce_path("lazy.o");
+        let go = reproduce_path("go.o");
+        let sdk = reproduce_path("/sdk/lib");
+        let sysroot = reproduce_path("/opt/mingw");
+        let api = reproduce_path("api.dll");
+        assert_eq!(
+            Vec::from_iter(response.lines()),
+            [
+                "-o",
+                "bof.o",
+                &format!("-L{lib}"),
+                "-L",
+                &lib,
+                &format!("--start-lib={lazy}"),
+                "-e",
+                "go",
+                &go,
+                "-lkernel32",
+                &format!("--sysroot={sysroot}"),
+                "-L",
+                "=/lib",
+                "--custom-api",
+                &api,
+                &format!("--custom-api={api}"),
+                "--custom-api=libapi",
+                &format!("-L{sdk}"),
+            ]
+        );
+    }
+}
@@ -1,47 +1,120 @@
-use object::{Object, ObjectSection, coff::CoffFile, pe::IMAGE_SCN_LNK_INFO};
+use std::borrow::Cow;
+
+use object::{
+    Object, ObjectSection,
+    coff::{CoffFile, CoffHeader},
+    pe::IMAGE_SCN_LNK_INFO,
+};
 
 use parsers::{Parser, many0, many1, not_token, token};
+use typed_arena::Arena;
 
 mod parsers;
 
-pub struct DrectveLibraries<'a> {
+/// Iterator over the `(flag, value)` pairs of the linker directives.
+pub struct DrectveDirectives<'a> {
     section_data: &'a str,
 }
 
-impl<'a> DrectveLibraries<'a> {
-    fn parse(data: &'a str) -> DrectveLibraries<'a> {
+impl<'a> DrectveDirectives<'a> {
+    pub(crate) fn parse(data: &'a str) -> DrectveDirectives<'a> {
         Self { section_data: data }
     }
 }
 
+impl<'a> Iterator for DrectveDirectives<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (directive, remaining) = many0(token(" "))
+            .preceeds(token("-").or(token("/")))
+            .preceeds(
+                many1(not_token(":")).terminated_by(token(":")).then(
+                    many1(not_token("\""))
+                        .surrounded_by(token("\""))
+                        .or(many1(not_token(" ")))
+                        .terminated_by(token(" ")),
+                ),
+            )
+            .parse(self.section_data)
+            .ok()?;
+
+        self.section_data = remaining;
+        Some(directive)
+    }
+}
+
+pub struct DrectveLibraries<'a> {
+    directives: DrectveDirectives<'a>,
+}
+
+impl<'a> DrectveLibraries<'a> {
+    pub(crate) fn parse(data: &'a str) -> DrectveLibraries<'a> {
+        Self {
+            directives: DrectveDirectives::parse(data),
+        }
+    }
+}
+
 impl<'a> Iterator for DrectveLibraries<'a> {
     type Item = &'a str;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let ((flag, value), remaining) = many0(token(" "))
-                .preceeds(token("-").or(token("/")))
-                .preceeds(
-                    many1(not_token(":")).terminated_by(token(":")).then(
-                        many1(not_token("\""))
-                            .surrounded_by(token("\""))
-                            .or(many1(not_token(" ")))
-                            .terminated_by(token(" ")),
-                    ),
-                )
-                .parse(self.section_data)
-                .ok()?;
-
-            self.section_data = remaining;
-
-            if flag.eq_ignore_ascii_case("DEFAULTLIB") {
-                return Some(value);
-            }
-        }
+        self.directives
+            .find(|(flag, _)| flag.eq_ignore_ascii_case("DEFAULTLIB"))
+            .map(|(_, value)| value)
     }
 }
 
-pub fn parse_drectve_libraries<'a>(coff: &CoffFile<'a>) -> Option<DrectveLibraries<'a>> {
+/// Returns the values of the `/KEEP` directives.
+///
+/// Each value is a symbol name pattern for
+/// [`BuiltLinkGraph::set_keep_symbols`](crate::graph::BuiltLinkGraph::set_keep_symbols).
+pub fn drectve_keep_symbols<'a>(
+    directives: DrectveDirectives<'a>,
+) -> impl Iterator<Item = &'a str> {
+    directives
+        .filter(|(flag, _)| flag.eq_ignore_ascii_case("KEEP"))
+        .map(|(_, value)| value)
+}
+
+/// Returns the values of the `/LIBPATH` directives.
+pub fn drectve_library_paths<'a>(
+    directives: DrectveDirectives<'a>,
+) -> impl Iterator<Item = &'a str> {
+    directives
+        .filter(|(flag, _)| flag.eq_ignore_ascii_case("LIBPATH"))
+        .map(|(_, value)| value)
+}
+
+pub fn parse_drectve_directives<'a, C: CoffHeader>(
+    coff: &CoffFile<'a, &'a [u8], C>,
+    strings: &'a Arena<String>,
+) -> Option<DrectveDirectives<'a>> {
+    drectve_str(coff, strings).map(DrectveDirectives::parse)
+}
+
+pub fn parse_drectve_libraries<'a>(
+    coff: &CoffFile<'a>,
+    strings: &'a Arena<String>,
+) -> Option<DrectveLibraries<'a>> {
+    drectve_str(coff, strings).map(DrectveLibraries::parse)
+}
+
+/// Returns the decoded contents of the `.drectve` section with the decoded
+/// copies allocated in `strings`.
+fn drectve_str<'a, C: CoffHeader>(
+    coff: &CoffFile<'a, &'a [u8], C>,
+    strings: &'a Arena<String>,
+) -> Option<&'a str> {
+    match drectve_data(coff)? {
+        Cow::Borrowed(data) => Some(data),
+        Cow::Owned(data) => Some(strings.alloc(data).as_str()),
+    }
+}
+
+/// Returns the decoded contents of the `.drectve` section.
+pub fn drectve_data<'a, C: CoffHeader>(coff: &CoffFile<'a, &'a [u8], C>) -> Option<Cow<'a, str>> {
     let drectve_section = coff.section_by_name(".drectve")?;
     if drectve_section
         .coff_section()
@@ -53,24 +126,67 @@ pub fn parse_drectve_libraries<'a>(coff: &CoffFile<'a>) -> Option<DrectveLibrari
         return None;
     }
 
-    let section_data = drectve_section.data().ok()?;
-    if section_data
-        .get(..3)
-        .is_some_and(|prefix| prefix == [0xef, 0xbb, 0xbf])
-    {
-        Some(DrectveLibraries::parse(
-            std::str::from_utf8(section_data.get(3..)?).ok()?,
-        ))
+    decode_drectve(drectve_section.data().ok()?)
+}
+
+/// Decodes the contents of a `.drectve` section.
+///
+/// The contents are UTF-8 with an optional BOM or UTF-16LE if they start
+/// with the UTF-16LE BOM or the second byte is NUL. Directive strings
+/// separated by NULs are joined with spaces and trailing NUL padding is
+/// removed.
+pub fn decode_drectve(data: &[u8]) -> Option<Cow<'_, str>> {
+    let decoded = if let Some(data) = data.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        Cow::Borrowed(std::str::from_utf8(data).ok()?)
+    } else if let Some(data) = data.strip_prefix(&[0xff, 0xfe]) {
+        Cow::Owned(decode_utf16le(data)?)
+    } else if data.len() >= 2 && data[0] != 0 && data[1] == 0 {
+        Cow::Owned(decode_utf16le(data)?)
     } else {
-        Some(DrectveLibraries::parse(
-            std::str::from_utf8(section_data).ok()?,
-        ))
+        Cow::Borrowed(std::str::from_utf8(data).ok()?)
+    };
+
+    let trimmed_len = decoded.trim_end_matches('\0').len();
+    let decoded = match decoded {
+        Cow::Borrowed(data) => Cow::Borrowed(&data[..trimmed_len]),
+        Cow::Owned(mut data) => {
+            data.truncate(trimmed_len);
+            Cow::Owned(data)
+        }
+    };
+
+    if !decoded.contains('\0') {
+        return Some(decoded);
     }
+
+    // Separate the directive strings with spaces and terminate the last one
+    let mut joined = decoded.replace('\0', " ");
+    joined.push(' ');
+    Some(Cow::Owned(joined))
+}
+
+/// Decodes UTF-16LE data.
+fn decode_utf16le(data: &[u8]) -> Option<String> {
+    // Ignore a single byte of padding after the last character
+    let data = match data.len() % 2 {
+        0 => data,
+        _ => data.strip_suffix(&[0])?,
+    };
+
+    char::decode_utf16(
+        data.chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]])),
+    )
+    .collect::<Result<String, _>>()
+    .ok()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::DrectveLibraries;
+    use super::{
+        DrectveDirectives, DrectveLibraries, decode_drectve, drectve_keep_symbols,
+        drectve_library_paths,
+    };
 
     #[test]
     fn quoted() {
@@ -123,4 +239,66 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn keep() {
+        const INPUT: &str = "  /DEFAULTLIB:uuid.lib /KEEP:resource_table /keep:\"res_*\" ";
+
+        let parsed = drectve_keep_symbols(DrectveDirectives::parse(INPUT)).collect::<Vec<_>>();
+        assert_eq!(parsed, ["resource_table", "res_*"]);
+    }
+
+    #[test]
+    fn library_paths() {
+        const INPUT: &str =
+            "  /LIBPATH:C:\\lib /DEFAULTLIB:uuid.lib /libpath:\"C:\\Program Files\\lib\" ";
+
+        let parsed = drectve_library_paths(DrectveDirectives::parse(INPUT)).collect::<Vec<_>>();
+        assert_eq!(parsed, ["C:\\lib", "C:\\Program Files\\lib"]);
+    }
+
+    #[test]
+    fn nul_separated() {
+        let data = b"/DEFAULTLIB:uuid.lib\0/DEFAULTLIB:\"advapi32.lib\"\0/DEFAULTLIB:OLDNAMES\0\0";
+        let decoded = decode_drectve(data).unwrap();
+
+        let parsed = DrectveLibraries::parse(&decoded).collect::<Vec<_>>();
+        assert_eq!(parsed, ["uuid.lib", "advapi32.lib", "OLDNAMES"]);
+    }
+
+    #[test]
+    fn trailing_padding() {
+        let data = b" /DEFAULTLIB:uuid.lib /DEFAULTLIB:OLDNAMES \0\0\0";
+        let decoded = decode_drectve(data).unwrap();
+        assert!(matches!(decoded, std::borrow::Cow::Borrowed(_)));
+
+        let parsed = DrectveLibraries::parse(&decoded).collect::<Vec<_>>();
+        assert_eq!(parsed, ["uuid.lib", "OLDNAMES"]);
+    }
+
+    #[test]
+    fn utf16() {
+        const INPUT: &str = " /DEFAULTLIB:uuid.lib /LIBPATH:\"C:\\Program Files\\lib\" ";
+        let encoded = Vec::from_iter(INPUT.encode_utf16().flat_map(u16::to_le_bytes));
+
+        let mut with_bom = vec![0xff, 0xfe];
+        with_bom.extend(&encoded);
+
+        for data in [encoded, with_bom] {
+            let decoded = decode_drectve(&data).unwrap();
+            assert_eq!(decoded, INPUT);
+
+            let parsed = DrectveLibraries::parse(&decoded).collect::<Vec<_>>();
+            assert_eq!(parsed, ["uuid.lib"]);
+        }
+    }
+
+    #[test]
+    fn invalid_utf16() {
+        // Odd length
+        assert_eq!(decode_drectve(&[0xff, 0xfe, b'/', 0, b'D']), None);
+
+        // Unpaired surrogate
+        assert_eq!(decode_drectve(&[b'/', 0, 0x00, 0xd8]), None);
+    }
 }
@@ -0,0 +1,66 @@
+//! Entrypoints for the fuzz targets in the `fuzz/` crate.
+//!
+//! These are only used for fuzzing and are not a stable API.
+
+use object::{coff::CoffFile, read::archive::ArchiveFile};
+use typed_arena::Arena;
+
+use crate::{
+    drectve::{self, DrectveLibraries},
+    linkobject::archive::{
+        LinkArchive,
+        legacy_importlib::{
+            LegacyImportHeadMember, LegacyImportSymbolMember, LegacyImportTailMember,
+        },
+    },
+};
+
+/// Parses the data as a link archive and extracts each symbol in the
+/// archive symbol table.
+pub fn link_archive(data: &[u8]) {
+    let Ok(archive) = LinkArchive::parse(data) else {
+        return;
+    };
+
+    let Ok(Some(symbols)) = ArchiveFile::parse(data).and_then(|archive| archive.symbols()) else {
+        return;
+    };
+
+    for symbol in symbols.flatten() {
+        if let Ok(name) = std::str::from_utf8(symbol.name()) {
+            let _ = archive.extract_symbol(name);
+        }
+    }
+}
+
+/// Parses the data as a COFF and parses it as each of the legacy import
+/// library member types.
+pub fn legacy_import_members(data: &[u8]) {
+    let Ok(coff) = CoffFile::<&[u8]>::parse(data) else {
+        return;
+    };
+
+    let _ = LegacyImportSymbolMember::parse(&coff);
+    let _ = LegacyImportHeadMember::parse(&coff);
+    let _ = LegacyImportTailMember::parse(&coff);
+}
+
+/// Decodes the data as the contents of a `.drectve` section and parses the
+/// libraries.
+pub fn drectve_libraries(data: &[u8]) {
+    if let Some(data) = drectve::decode_drectve(data) {
+        DrectveLibraries::parse(&data).for_each(drop);
+    }
+}
+
+/// Parses the data as a COFF and parses the libraries in the `.drectve`
+/// section.
+pub fn coff_drectve_libraries(data: &[u8]) {
+    if let Ok(coff) = CoffFile::<&[u8]>::parse(data) {
+        let strings = Arena::new();
+        drectve::parse_drectve_libraries(&coff, &strings)
+            .into_iter()
+            .flatten()
+            .for_each(drop);
+    }
+}
@@ -1,33 +1,98 @@
 use std::{
     cell::OnceCell,
-    collections::{BTreeMap, LinkedList},
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet, LinkedList},
+    hash::{BuildHasher, RandomState},
+    path::Path,
+    time::{Duration, Instant},
 };
 
-use indexmap::IndexMap;
-use log::debug;
+use indexmap::{IndexMap, IndexSet};
+use log::{debug, info, warn};
 use object::{
+    LittleEndian, U16Bytes, U32Bytes,
     pe::{
-        IMAGE_FILE_LINE_NUMS_STRIPPED, IMAGE_REL_AMD64_REL32, IMAGE_REL_I386_DIR32,
-        IMAGE_SCN_CNT_CODE, IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_CNT_UNINITIALIZED_DATA,
-        IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE, IMAGE_SYM_CLASS_EXTERNAL, IMAGE_SYM_CLASS_STATIC,
-        IMAGE_SYM_TYPE_NULL,
+        IMAGE_FILE_LINE_NUMS_STRIPPED, IMAGE_REL_AMD64_ADDR32, IMAGE_REL_AMD64_ADDR64,
+        IMAGE_REL_AMD64_REL32, IMAGE_REL_I386_DIR32, IMAGE_SCN_CNT_CODE,
+        IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_CNT_UNINITIALIZED_DATA, IMAGE_SCN_MEM_EXECUTE,
+        IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE, IMAGE_SYM_ABSOLUTE, IMAGE_SYM_CLASS_EXTERNAL,
+        IMAGE_SYM_CLASS_STATIC, IMAGE_SYM_TYPE_NULL, ImageAuxSymbolFunction,
+        ImageAuxSymbolFunctionBeginEnd,
     },
     write::coff::{Relocation, SectionHeader, Writer},
 };
 
-use crate::linker::LinkerTargetArch;
+use crate::{linker::LinkerTargetArch, linkobject::import::ImportType};
 
 use super::{
     edge::{ComdatSelection, DefinitionEdgeWeight, Edge, RelocationEdgeWeight},
+    feat::{FEAT_SAFESEH, FEAT_SYMBOL, merge_features},
+    fixup::{Fixup, FixupWorklist, RelocationKind},
+    glob::glob_match,
+    intern::StringPool,
+    layout::{LayoutInput, LinkLayout},
     link::{LinkGraph, LinkGraphArena},
     node::{
-        CoffNode, LibraryNode, SectionNode, SectionNodeCharacteristics, SectionNodeData,
-        SymbolName, SymbolNode, SymbolNodeStorageClass, SymbolNodeType,
+        CoffNode, LibraryName, LibraryNode, SectionNode, SectionNodeCharacteristics,
+        SectionNodeData, SymbolName, SymbolNode, SymbolNodeFunctionAux, SymbolNodeStorageClass,
+        SymbolNodeType,
     },
+    profile::LinkProfile,
+    riprel::{LEA_DISPLACEMENT_OFFSET, RipRelativeError, rewrite_movabs},
+    strings::{OutputString, find_strings},
 };
 
 const SECTION_ALIGN_SHIFT: u32 = 20;
 
+type DefinitionEdge<'arena, 'data> =
+    Edge<'arena, SymbolNode<'arena, 'data>, SectionNode<'arena, 'data>, DefinitionEdgeWeight>;
+
+type RelocationEdge<'arena, 'data> =
+    Edge<'arena, SectionNode<'arena, 'data>, SymbolNode<'arena, 'data>, RelocationEdgeWeight>;
+
+/// The section characteristics flags for memory access permissions.
+const MEM_ACCESS_FLAGS: u32 = IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE | IMAGE_SCN_MEM_EXECUTE;
+
+/// The maximum alignment value which can be set in the section
+/// characteristics.
+const MAX_SECTION_ALIGNMENT: u32 = 8192;
+
+/// Prefix of the symbols for the pointer stubs GCC emits for external data.
+const REFPTR_PREFIX: &str = ".refptr.";
+
+/// Opcode for `lea` used when collapsing the loads through `.refptr` stubs.
+const LEA_OPCODE: u8 = 0x8d;
+
+/// Name of the section with the obfuscation metadata.
+const OBFUSCATION_SECTION: &str = ".obfmeta";
+
+/// Signature at the start of the obfuscation metadata.
+const OBFUSCATION_SIGNATURE: &[u8; 4] = b"OBFM";
+
+/// Size of the obfuscation metadata header.
+const OBFUSCATION_HEADER_SIZE: usize = 12;
+
+/// Size of each range in the obfuscation metadata.
+const OBFUSCATION_RANGE_SIZE: usize = 12;
+
+/// Signature at the start of the section checksum table.
+const CHECKSUM_SIGNATURE: &[u8; 4] = b"CKSM";
+
+/// Size of the section checksum table header.
+const CHECKSUM_HEADER_SIZE: usize = 8;
+
+/// Size of each entry in the section checksum table.
+const CHECKSUM_ENTRY_SIZE: usize = 12;
+
+/// Name of the output section for code without any profile hits.
+const COLD_TEXT_SECTION: &str = ".text$cold";
+
+/// Maximum number of output sections.
+///
+/// Section numbers are 16-bit values and BOF loaders commonly read them as
+/// signed integers.
+const MAX_OUTPUT_SECTIONS: usize = i16::MAX as usize;
+
 #[derive(Debug, thiserror::Error)]
 pub enum LinkGraphLinkError {
     #[error("{coff_name}: {reference} references symbol '{symbol}' defined in discarded section.")]
@@ -53,6 +118,128 @@ pub enum LinkGraphLinkError {
         section: String,
         address: u32,
     },
+
+    #[error(
+        "{coff_name}: cannot rewrite relocation at '{section}+{address:#x}' to be RIP-relative: {reason}."
+    )]
+    RipRelative {
+        coff_name: String,
+        section: String,
+        address: u32,
+        reason: RipRelativeError,
+    },
+
+    #[error("{}", display_data_import_error(.symbol, .library, .references))]
+    DataImport {
+        symbol: String,
+        library: String,
+        references: Vec<String>,
+    },
+
+    #[error("{}", display_safeseh_error(.0))]
+    SafeSeh(Vec<String>),
+
+    #[error(
+        "output has {0} sections which is more than the {MAX_OUTPUT_SECTIONS} supported in a COFF. Inputs compiled with -ffunction-sections or -fdata-sections have a section for each function and variable. Compile without them or use grouped section names ('.text$<name>') so that the sections are merged"
+    )]
+    TooManySections(usize),
+
+    #[error("{0}")]
+    Redirect(#[from] SymbolRedirectError),
+
+    #[error(
+        "section '{section}' has conflicting memory access flags: {first_coff} ({first_flags}) and {conflicting_coff} ({conflicting_flags})"
+    )]
+    SectionCharacteristics {
+        section: String,
+        first_coff: String,
+        first_flags: String,
+        conflicting_coff: String,
+        conflicting_flags: String,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SymbolRedirectError {
+    #[error("cannot redirect symbol '{0}'. symbol is not in the link")]
+    UnknownSymbol(String),
+
+    #[error("cannot redirect symbol '{0}' to itself")]
+    SelfRedirect(String),
+}
+
+/// Formats the memory access flags as `RWX`.
+fn display_access_flags(flags: u32) -> String {
+    [
+        (IMAGE_SCN_MEM_READ, 'R'),
+        (IMAGE_SCN_MEM_WRITE, 'W'),
+        (IMAGE_SCN_MEM_EXECUTE, 'X'),
+    ]
+    .into_iter()
+    .map(|(flag, c)| if flags & flag != 0 { c } else { '-' })
+    .collect()
+}
+
+fn display_data_import_error(symbol: &str, library: &str, references: &[String]) -> String {
+    let mut message = format!(
+        "symbol '{symbol}' is imported as data from '{library}' and cannot be referenced directly. Declare it with __declspec(dllimport) to load the address through '__imp_{symbol}'"
+    );
+
+    for reference in references {
+        message.push_str("\n>>> referenced by ");
+        message.push_str(reference);
+    }
+
+    message
+}
+
+fn display_safeseh_error(coff_names: &[String]) -> String {
+    let mut message = String::from("inputs are not compatible with safe exception handlers:");
+
+    for coff_name in coff_names {
+        message.push_str("\n>>> ");
+        message.push_str(coff_name);
+    }
+
+    message
+}
+
+/// The naming scheme used for renaming symbols in the output COFF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymbolRenameMode {
+    /// Short sequential names.
+    #[default]
+    Short,
+
+    /// Randomized names.
+    Random,
+}
+
+/// How conflicting memory access flags for input sections with the same name
+/// are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CharacteristicsPolicy {
+    /// Use the union of the memory access flags and emit a warning.
+    #[default]
+    Merge,
+
+    /// Return an error.
+    Strict,
+}
+
+/// How input sections with `IMAGE_SCN_LNK_INFO` other than `.drectve` are
+/// handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LnkInfoPolicy {
+    /// Keep the sections in output sections with the same name.
+    #[default]
+    Keep,
+
+    /// Discard the sections.
+    Drop,
+
+    /// Move the sections into the `.comment` output section.
+    Comment,
 }
 
 /// An output section with the header and contained sections.
@@ -79,6 +266,9 @@ pub struct BuiltLinkGraph<'arena, 'data> {
     /// The node for the COMMON section.
     common_section: OnceCell<&'arena SectionNode<'arena, 'data>>,
 
+    /// Whether the COMDAT sections were already handled.
+    comdats_handled: bool,
+
     /// Pseudo-COFF for holding metadata sections.
     root_coff: &'arena CoffNode<'data>,
 
@@ -91,12 +281,128 @@ pub struct BuiltLinkGraph<'arena, 'data> {
     /// The symbol with external storage class.
     external_symbols: IndexMap<&'data str, &'arena SymbolNode<'arena, 'data>>,
 
+    /// Byte used for padding code sections.
+    code_fill: u8,
+
+    /// Byte used for padding data sections.
+    data_fill: u8,
+
+    /// Minimum alignment for output sections.
+    min_section_alignment: u32,
+
+    /// Whether to report cross-section relocations in the output.
+    check_relocations: bool,
+
+    /// Whether to order the output symbols independently of the input order.
+    deterministic: bool,
+
+    /// Local symbols which are not defined in a section.
+    extraneous_symbols: LinkedList<&'arena SymbolNode<'arena, 'data>>,
+
+    /// Names of the absolute symbols to keep in the output.
+    absolute_symbols: HashSet<String>,
+
+    /// The input COFFs.
+    coff_nodes: IndexSet<&'arena CoffNode<'data>>,
+
+    /// The `@feat.00` values for COFFs which have the symbol.
+    coff_features: HashMap<&'arena CoffNode<'data>, u32>,
+
+    /// The input COFFs with `.idata$` sections from import libraries.
+    import_coffs: HashSet<&'arena CoffNode<'data>>,
+
+    /// Whether all inputs need to be compatible with safe exception
+    /// handlers.
+    safeseh: bool,
+
+    /// How conflicting section memory access flags are handled.
+    characteristics_policy: CharacteristicsPolicy,
+
+    /// How the `IMAGE_SCN_LNK_INFO` input sections are handled.
+    lnk_info_policy: LnkInfoPolicy,
+
+    /// Whether to apply the default alignment for well-known sections.
+    default_section_alignment: bool,
+
+    /// Whether to apply the relocations targeting the same output section
+    /// instead of writing them to the output.
+    flatten_relocations: bool,
+
+    /// Layout from a previous link to preserve.
+    layout: Option<LinkLayout>,
+
+    /// Symbol hit counts for ordering the code sections.
+    profile: Option<LinkProfile>,
+
+    /// Whether to move the code sections without any profile hits to the
+    /// `.text$cold` output section.
+    split_cold: bool,
+
+    /// Whether to order the input sections by descending alignment to
+    /// reduce the padding.
+    pack_sections: bool,
+
+    /// Names of the DLLs with imports routed through the delay import table.
+    delay_load_libraries: Vec<String>,
+
+    /// Entrypoint symbol with the defining section placed first in the
+    /// output.
+    entry_first: Option<String>,
+
+    /// Whether to add the table of output section checksums.
+    section_checksums: bool,
+
+    /// Names of the output sections with obfuscated contents.
+    obfuscated_sections: HashSet<String>,
+
+    /// Key for obfuscating the section contents.
+    obfuscation_key: Vec<u8>,
+
+    /// Whether to collect the printable strings in the output data sections.
+    collect_strings: bool,
+
+    /// Whether to estimate the sizes of the defined symbols.
+    collect_symbol_sizes: bool,
+
+    /// Whether to write the auxiliary function records from the inputs.
+    function_aux: bool,
+
+    /// Name patterns for the symbols to keep.
+    keep_symbols: Vec<String>,
+
+    /// Symbol name patterns from the `/KEEP` directives in the inputs.
+    directive_keep_symbols: Vec<String>,
+
+    /// Name patterns for the input sections to keep.
+    keep_sections: Vec<String>,
+
+    /// Whether to replace the loads through `.refptr` stubs with direct
+    /// references.
+    collapse_refptrs: bool,
+
+    /// Whether to rewrite the absolute address loads into RIP-relative
+    /// forms.
+    rip_relative: bool,
+
+    /// Opcode bytes in the input sections to overwrite in the output.
+    opcode_patches: Vec<(&'arena SectionNode<'arena, 'data>, u32, u8)>,
+
+    /// Interned names of the local symbols and the symbols added by the
+    /// linker.
+    symbol_names: StringPool<'arena>,
+
     /// Graph arena allocator.
     arena: &'arena LinkGraphArena,
 }
 
 impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
     pub(super) fn new(link_graph: LinkGraph<'arena, 'data>) -> BuiltLinkGraph<'arena, 'data> {
+        debug!(
+            "interned {} section names and {} symbol names",
+            link_graph.section_names.len(),
+            link_graph.symbol_names.len()
+        );
+
         // Partition the sections by name and discard LnkRemove section
         let mut sections: IndexMap<&str, OutputSection> = link_graph
             .section_nodes
@@ -107,6 +413,9 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                     .contains(SectionNodeCharacteristics::LnkRemove)
                 {
                     debug!(
+                        coff:% = section.coff().file_path().display(),
+                        member = section.coff().member_path().map(Path::to_string_lossy),
+                        section:% = section.name();
                         "{}: discarding 'IMAGE_SCN_LNK_REMOVE' section {}",
                         section.coff(),
                         section.name()
@@ -115,6 +424,9 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                     false
                 } else if section.is_debug() {
                     debug!(
+                        coff:% = section.coff().file_path().display(),
+                        member = section.coff().member_path().map(Path::to_string_lossy),
+                        section:% = section.name();
                         "{}: discarding debug section {}",
                         section.coff(),
                         section.name()
@@ -131,10 +443,12 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                 outputs
             });
 
-        // Sort grouped sections
-        sections
-            .values_mut()
-            .for_each(|section| section.nodes.sort_by_key(|section| section.name().as_str()));
+        // Sort grouped sections by each level of the ordering keys
+        sections.values_mut().for_each(|section| {
+            section
+                .nodes
+                .sort_by(|a, b| a.name().ordering_keys().cmp(b.name().ordering_keys()))
+        });
 
         // Dedup equivalent .rdata$zzz sections
         if let Some(section) = sections.get_mut(".rdata") {
@@ -159,14 +473,339 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
             machine: link_graph.machine,
             sections,
             common_section: link_graph.common_section,
+            comdats_handled: false,
             library_nodes: link_graph.library_nodes,
             root_coff: link_graph.root_coff,
             api_node: link_graph.api_node,
             external_symbols: link_graph.external_symbols,
+            code_fill: 0x90,
+            data_fill: 0x00,
+            min_section_alignment: 0,
+            check_relocations: false,
+            deterministic: false,
+            extraneous_symbols: link_graph.extraneous_symbols,
+            absolute_symbols: HashSet::new(),
+            coff_nodes: link_graph.coff_nodes,
+            coff_features: link_graph.coff_features,
+            import_coffs: link_graph.import_coffs,
+            safeseh: false,
+            characteristics_policy: CharacteristicsPolicy::default(),
+            lnk_info_policy: LnkInfoPolicy::default(),
+            default_section_alignment: true,
+            flatten_relocations: true,
+            layout: None,
+            profile: None,
+            split_cold: false,
+            pack_sections: false,
+            delay_load_libraries: Vec::new(),
+            entry_first: None,
+            section_checksums: false,
+            obfuscated_sections: HashSet::new(),
+            obfuscation_key: Vec::new(),
+            collect_strings: false,
+            collect_symbol_sizes: false,
+            function_aux: false,
+            keep_symbols: Vec::new(),
+            directive_keep_symbols: link_graph.keep_symbols,
+            keep_sections: Vec::new(),
+            collapse_refptrs: false,
+            rip_relative: false,
+            opcode_patches: Vec::new(),
+            symbol_names: link_graph.symbol_names,
             arena: link_graph.arena,
         }
     }
 
+    /// Sets the byte used for padding code sections.
+    ///
+    /// Defaults to `0x90`.
+    pub fn set_code_fill(&mut self, val: u8) {
+        self.code_fill = val;
+    }
+
+    /// Sets the byte used for padding data sections.
+    ///
+    /// Defaults to `0x00`.
+    pub fn set_data_fill(&mut self, val: u8) {
+        self.data_fill = val;
+    }
+
+    /// Sets the minimum alignment for output sections.
+    ///
+    /// The value is rounded up to the next power of two and capped to the
+    /// maximum alignment supported by COFFs (8192 bytes).
+    pub fn set_min_section_alignment(&mut self, val: u32) {
+        self.min_section_alignment = val.min(MAX_SECTION_ALIGNMENT).next_power_of_two();
+    }
+
+    /// Report the cross-section relocations in the output COFF and warn about
+    /// relocations which loaders may not handle.
+    pub fn set_check_relocations(&mut self, val: bool) {
+        self.check_relocations = val;
+    }
+
+    /// Order the symbols in the output symbol table by name instead of by the
+    /// order the inputs were added in.
+    pub fn set_deterministic(&mut self, val: bool) {
+        self.deterministic = val;
+    }
+
+    /// Sets the names of the absolute symbols from the inputs to keep in the
+    /// output COFF.
+    ///
+    /// The values of absolute symbols with the same name are merged with a
+    /// bitwise OR.
+    pub fn set_absolute_symbols<S: Into<String>>(&mut self, names: impl IntoIterator<Item = S>) {
+        self.absolute_symbols = names.into_iter().map(Into::into).collect();
+    }
+
+    /// Require that all inputs are compatible with safe exception handlers
+    /// when linking for i386.
+    ///
+    /// The merged `@feat.00` symbol is written to the output if this is set.
+    pub fn set_safeseh(&mut self, val: bool) {
+        self.safeseh = val;
+    }
+
+    /// Sets how conflicting memory access flags for input sections with the
+    /// same name are handled.
+    pub fn set_characteristics_policy(&mut self, val: CharacteristicsPolicy) {
+        self.characteristics_policy = val;
+    }
+
+    /// Sets how input sections with `IMAGE_SCN_LNK_INFO` other than
+    /// `.drectve` are handled.
+    ///
+    /// Sections added by the linker are not affected.
+    pub fn set_lnk_info_policy(&mut self, val: LnkInfoPolicy) {
+        self.lnk_info_policy = val;
+    }
+
+    /// Apply the default minimum alignment for the input sections in
+    /// well-known output sections.
+    ///
+    /// This is enabled by default and aligns the `.text` and `.rdata` inputs
+    /// to 16 bytes for amd64 like MSVC.
+    pub fn set_default_section_alignment(&mut self, val: bool) {
+        self.default_section_alignment = val;
+    }
+
+    /// Apply the relocations targeting symbols in the same output section
+    /// instead of writing them to the output.
+    ///
+    /// This is enabled by default. Disabling it keeps a relocation for every
+    /// reference in the output.
+    pub fn set_flatten_relocations(&mut self, val: bool) {
+        self.flatten_relocations = val;
+    }
+
+    /// Sets the layout from a previous link.
+    ///
+    /// The output sections, input sections and symbols in the layout are
+    /// kept in the same order to minimize the differences from the previous
+    /// output.
+    pub fn set_layout(&mut self, layout: LinkLayout) {
+        self.layout = Some(layout);
+    }
+
+    /// Sets the symbol hit counts for ordering the code sections.
+    ///
+    /// Input sections with the same name are ordered by the highest hit count
+    /// of the symbols they define. Sections without any hits are placed
+    /// after the ones with hits.
+    pub fn set_profile(&mut self, profile: LinkProfile) {
+        self.profile = Some(profile);
+    }
+
+    /// Move the `.text` input sections without any profile hits to a
+    /// separate `.text$cold` output section placed after `.text`.
+    pub fn set_split_cold(&mut self, val: bool) {
+        self.split_cold = val;
+    }
+
+    /// Order the input sections with the same name by descending alignment
+    /// to reduce the padding between them.
+    ///
+    /// The order is only changed if it results in less padding for the output
+    /// section.
+    pub fn set_pack_sections(&mut self, val: bool) {
+        self.pack_sections = val;
+    }
+
+    /// Sets the names of the DLLs with imports which are resolved through the
+    /// delay import table instead of `__imp_` symbols.
+    ///
+    /// The names are matched case-insensitively with or without the `.dll`
+    /// extension.
+    pub fn set_delay_load_libraries<S: Into<String>>(
+        &mut self,
+        names: impl IntoIterator<Item = S>,
+    ) {
+        self.delay_load_libraries = names.into_iter().map(Into::into).collect();
+    }
+
+    /// Place the section defining the entrypoint symbol first in its output
+    /// section and the output section first in the COFF.
+    ///
+    /// This is for loaders which start executing at the beginning of `.text`
+    /// instead of looking up the entrypoint symbol. A warning is emitted if
+    /// the entrypoint is not at the start of its section.
+    pub fn set_entry_first(&mut self, entrypoint: impl Into<String>) {
+        self.entry_first = Some(entrypoint.into());
+    }
+
+    /// Add a `.rdata$cksum` section with the sizes and JamCRC values of the
+    /// output sections for loaders to verify the BOF.
+    ///
+    /// The table is defined by the `__section_checksums` symbol and holds
+    /// little-endian values. It starts with the `CKSM` signature, a 16-bit
+    /// version (1) and a 16-bit entry count followed by an entry for each
+    /// output section with the 32-bit section number, size and JamCRC.
+    ///
+    /// The checksums are over the section data in the file before applying
+    /// relocations. The table is zeroed while calculating the checksum of the
+    /// `.rdata` section. Uninitialized sections have a checksum of 0.
+    pub fn set_section_checksums(&mut self, val: bool) {
+        self.section_checksums = val;
+    }
+
+    /// XOR the contents of the output sections with the obfuscation key and
+    /// add a `.obfmeta` section describing the obfuscated ranges for loaders.
+    ///
+    /// The bytes patched by the output relocations are left intact so that
+    /// relocations can be applied before or after deobfuscating. The section
+    /// is defined by the `__obfuscation_metadata` symbol and holds
+    /// little-endian values. It starts with the `OBFM` signature, a 16-bit
+    /// version (1), the 16-bit key length and the 32-bit range count. The key
+    /// follows padded to 4 bytes and then each range with the 32-bit section
+    /// number, offset and length. A byte at offset `n` in the section is
+    /// XORed with `key[n % key.len()]`.
+    pub fn set_obfuscated_sections<S: Into<String>>(&mut self, names: impl IntoIterator<Item = S>) {
+        self.obfuscated_sections = names.into_iter().map(Into::into).collect();
+    }
+
+    /// Sets the key for [`BuiltLinkGraph::set_obfuscated_sections`].
+    ///
+    /// A random 16 byte key is used if this is not set.
+    pub fn set_obfuscation_key(&mut self, key: impl Into<Vec<u8>>) {
+        self.obfuscation_key = key.into();
+    }
+
+    /// Sets the name patterns for the symbols to keep.
+    ///
+    /// Patterns may contain `*` and `?` wildcards. Kept symbols are not
+    /// renamed, their imports are always written and input sections defining
+    /// them are not discarded as unused import thunks. Patterns from `/KEEP`
+    /// directives in the inputs are kept in addition to these.
+    pub fn set_keep_symbols<S: Into<String>>(&mut self, patterns: impl IntoIterator<Item = S>) {
+        self.keep_symbols = patterns.into_iter().map(Into::into).collect();
+    }
+
+    /// Sets the name patterns for the input sections to keep.
+    ///
+    /// Patterns may contain `*` and `?` wildcards and are matched against the
+    /// full input section name including the `$` suffix. When choosing which
+    /// copy of a COMDAT to keep, the copy with a kept section or a kept
+    /// associative section is preferred over the first definition. Kept
+    /// sections are not discarded as unused import thunks.
+    pub fn set_keep_sections<S: Into<String>>(&mut self, patterns: impl IntoIterator<Item = S>) {
+        self.keep_sections = patterns.into_iter().map(Into::into).collect();
+    }
+
+    /// Redirects the relocations targeting the `from` symbol to the `to`
+    /// symbol and returns the number of redirected relocations.
+    ///
+    /// Both symbols need to be external symbols in the graph. The `from`
+    /// symbol is left without any references and its imports are removed.
+    /// The definitions of the `from` symbol are kept.
+    pub fn redirect_symbol(&mut self, from: &str, to: &str) -> Result<usize, SymbolRedirectError> {
+        let lookup = |name: &str| {
+            self.external_symbols
+                .get(name)
+                .copied()
+                .ok_or_else(|| SymbolRedirectError::UnknownSymbol(name.to_string()))
+        };
+
+        let from_symbol = lookup(from)?;
+        let to_symbol = lookup(to)?;
+
+        if std::ptr::eq(from_symbol, to_symbol) {
+            return Err(SymbolRedirectError::SelfRedirect(from.to_string()));
+        }
+
+        let references = from_symbol.references().take();
+        for reference in &references {
+            reference.replace_target(to_symbol);
+            to_symbol.references().push_back(reference);
+        }
+
+        for import_edge in from_symbol.imports().take() {
+            import_edge
+                .target()
+                .imports()
+                .retain(|library_import| !std::ptr::eq(library_import, import_edge));
+        }
+
+        debug!(
+            symbol:% = from_symbol.name();
+            "redirecting {} references from '{}' to '{}'",
+            references.len(),
+            from_symbol.name().demangle(),
+            to_symbol.name().demangle()
+        );
+
+        Ok(references.len())
+    }
+
+    /// Collect the printable strings in the initialized data sections of the
+    /// output along with the input sections containing them.
+    pub fn set_collect_strings(&mut self, val: bool) {
+        self.collect_strings = val;
+    }
+
+    /// Estimate the sizes of the symbols defined in the output.
+    pub fn set_collect_symbol_sizes(&mut self, val: bool) {
+        self.collect_symbol_sizes = val;
+    }
+
+    /// Write the auxiliary function records for the function definitions
+    /// and the `.bf`/`.ef` symbols from the inputs.
+    ///
+    /// The symbol table indices in the records are updated for the output
+    /// symbol table. Line number pointers are cleared since line numbers are
+    /// not written to the output.
+    pub fn set_function_aux(&mut self, val: bool) {
+        self.function_aux = val;
+    }
+
+    /// Replace the loads through the `.refptr` stubs emitted by GCC with
+    /// direct references when the target is defined in the output.
+    ///
+    /// GCC loads the address of external data from a `.refptr.<symbol>` stub
+    /// holding a 64-bit pointer to the symbol. When the symbol is defined in
+    /// the output, the `mov reg, [rip + .refptr.<symbol>]` load is rewritten
+    /// to `lea reg, [rip + <symbol>]` and the stub is discarded if nothing
+    /// else references it.
+    pub fn set_collapse_refptrs(&mut self, val: bool) {
+        self.collapse_refptrs = val;
+    }
+
+    /// Rewrite the absolute address loads in code into RIP-relative forms so
+    /// that the output does not have any `IMAGE_REL_AMD64_ADDR64`
+    /// relocations.
+    ///
+    /// Each `movabs r64, imm64` with an `IMAGE_REL_AMD64_ADDR64` relocation
+    /// is rewritten to `lea r64, [rip + disp32]` followed by a 3-byte `nop`
+    /// with an `IMAGE_REL_AMD64_REL32` relocation. The target symbol must be
+    /// defined in the output. Linking fails if an `IMAGE_REL_AMD64_ADDR64`
+    /// relocation cannot be rewritten.
+    ///
+    /// Relocations in discardable sections such as the debug sections are
+    /// left unchanged.
+    pub fn set_rip_relative(&mut self, val: bool) {
+        self.rip_relative = val;
+    }
+
     /// Merge the .bss section with the .data section.
     pub fn merge_bss(&mut self) {
         self.allocate_commons();
@@ -194,6 +833,83 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
         debug!("'.bss' output section merged with '.data' section");
     }
 
+    /// Renames the non-essential symbols defined in the output COFF.
+    ///
+    /// Section symbols, labels and symbols listed in `preserve` keep their
+    /// names. Returns the list of renamed symbols as `(old, new)` pairs.
+    pub fn rename_symbols(
+        &mut self,
+        mode: SymbolRenameMode,
+        preserve: &[&str],
+    ) -> Vec<(&'arena str, &'arena str)> {
+        // Discard COMDATs and allocate COMMON symbols first so that only
+        // symbols which end up in the output are renamed. Both of these only
+        // run once and do nothing when they are called again when linking.
+        self.handle_comdats();
+        self.allocate_commons();
+
+        let random_state = RandomState::new();
+        let mut counter: u64 = 0;
+        let mut renamed = Vec::new();
+
+        // Names of the symbols in the output including the local symbols
+        // which are not in the external symbol table
+        let mut used_names: HashSet<&str> = HashSet::from_iter(
+            self.sections
+                .values()
+                .flat_map(|section| self.symbol_definitions(section))
+                .map(|(_, definition)| definition.source().name().as_str()),
+        );
+
+        for section in self.sections.values() {
+            for (section_node, definition) in self.symbol_definitions(section) {
+                let symbol = definition.source();
+                let name = symbol.name().as_str();
+
+                if symbol.output_rename().get().is_some()
+                    || preserve.contains(&name)
+                    || self.is_kept_symbol(name)
+                {
+                    continue;
+                }
+
+                // Generate a new name which does not collide with any
+                // existing symbols or previously renamed symbols.
+                let new_name = loop {
+                    let candidate = match mode {
+                        SymbolRenameMode::Short => format!("_{counter:x}"),
+                        SymbolRenameMode::Random => {
+                            format!("_{:08x}", random_state.hash_one(counter) as u32)
+                        }
+                    };
+                    counter += 1;
+
+                    if !self.external_symbols.contains_key(candidate.as_str())
+                        && !preserve.contains(&candidate.as_str())
+                        && !used_names.contains(candidate.as_str())
+                    {
+                        break self.symbol_names.intern_copy(self.arena, &candidate);
+                    }
+                };
+
+                debug!(
+                    coff:% = section_node.coff().file_path().display(),
+                    member = section_node.coff().member_path().map(Path::to_string_lossy),
+                    symbol:% = symbol.name();
+                    "{}: renaming symbol '{}' to '{new_name}'",
+                    section_node.coff(),
+                    symbol.name().demangle()
+                );
+
+                used_names.insert(new_name);
+                let _ = symbol.output_rename().set(new_name);
+                renamed.push((name, new_name));
+            }
+        }
+
+        renamed
+    }
+
     /// Allocate space for COMMON symbols at the end of the .bss
     fn allocate_commons(&mut self) {
         // Take the value out of the OnceCell to make the function idempotent.
@@ -243,21 +959,20 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
         for (symbol, symbol_size) in &common_symbols {
             symbol_addr = symbol_addr.next_multiple_of(align);
 
-            // Get the first definition edge from the symbol's edge list.
+            // Keep the first definition edge in the symbol's edge list.
             // This will be re-used as the real definition edge with the symbol
             // address
-            let common_def = symbol.definitions().pop_front().unwrap_or_else(|| {
+            let mut definitions = symbol.definitions().cursor_front();
+            let common_def = definitions.current().unwrap_or_else(|| {
                 unreachable!("COMMON symbol should have at least 1 definition associated to it")
             });
 
             // Set the address of the symbol definition to the new value
             common_def.weight().set_address(symbol_addr);
 
-            // Clear out the remaining definitions connected to the symbol
-            symbol.definitions().clear();
-
-            // Re insert the definition back into the symbol's edge list
-            symbol.definitions().push_back(common_def);
+            // Remove the remaining definitions connected to the symbol
+            definitions.move_next();
+            while definitions.remove_current().is_some() {}
 
             // Increment the address for the next symbol
             symbol_addr += symbol_size;
@@ -299,167 +1014,1028 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
         bss_entry.nodes.push(common_section);
     }
 
-    fn apply_import_thunks(&mut self) {
-        let mut thunk_symbols: LinkedList<(&SymbolNode, SymbolName)> = LinkedList::new();
+    /// Returns `true` if the symbol name matches one of the keep patterns.
+    fn is_kept_symbol(&self, name: &str) -> bool {
+        self.keep_symbols
+            .iter()
+            .map(String::as_str)
+            .chain(self.directive_keep_symbols.iter().map(String::as_str))
+            .any(|pattern| glob_match(pattern, name))
+    }
+
+    /// Returns `true` if the section name matches one of the keep patterns
+    /// or the section defines a kept symbol.
+    fn is_kept_section(&self, section: &SectionNode<'arena, 'data>) -> bool {
+        self.keep_sections
+            .iter()
+            .any(|pattern| glob_match(pattern, section.name().as_str()))
+            || section
+                .definitions()
+                .iter()
+                .any(|definition| self.is_kept_symbol(definition.source().name().as_str()))
+    }
 
+    /// Discards the import thunks defined in the inputs when every caller of
+    /// the thunk has been discarded.
+    ///
+    /// A thunk is still used if a symbol defined in it is referenced or if
+    /// it will be reused for the public symbol of the import. Discarding the
+    /// thunk lets [`Self::remove_discarded_imports`] remove the `__imp_`
+    /// import which the thunk jumps through.
+    fn discard_unused_import_thunks(&self) {
         for library_node in self.api_node.iter().chain(self.library_nodes.values()) {
-            for import_edge in library_node.imports() {
-                let import_name = import_edge.weight().import_name();
+            // Import names with live references to the public symbol
+            let used_imports: HashSet<&str> =
+                HashSet::from_iter(library_node.imports().iter().filter_map(|import_edge| {
+                    let symbol = import_edge.source();
+                    (symbol.name().strip_dllimport().is_none() && !symbol.is_unreferenced())
+                        .then(|| import_edge.weight().import_name().as_str())
+                }));
+
+            for import_edge in library_node.imports().iter() {
                 let symbol = import_edge.source();
-                if symbol
-                    .name()
-                    .strip_dllimport()
-                    .is_none_or(|unprefixed| unprefixed != import_name.as_str())
-                    && !symbol.is_unreferenced()
+                if symbol.name().strip_dllimport().is_none()
+                    || used_imports.contains(import_edge.weight().import_name().as_str())
                 {
-                    thunk_symbols.push_back((symbol, import_name));
+                    continue;
                 }
+
+                let Some(thunk_section) = self.find_import_thunk(symbol).filter(|section| {
+                    !self.is_kept_section(section)
+                        && section
+                            .definitions()
+                            .iter()
+                            .all(|definition| definition.source().is_unreferenced())
+                }) else {
+                    continue;
+                };
+
+                debug!(
+                    coff:% = thunk_section.coff().file_path().display(),
+                    member = thunk_section.coff().member_path().map(Path::to_string_lossy),
+                    section:% = thunk_section.name(),
+                    symbol:% = symbol.name();
+                    "{}: discarding unused import thunk for '{}' in {}",
+                    thunk_section.coff(),
+                    symbol.name().demangle(),
+                    thunk_section.name()
+                );
+                thunk_section.discard();
             }
         }
+    }
 
-        if !thunk_symbols.is_empty() {
-            // jmp [rip + $<symbol>]
-            const CODE_THUNK: [u8; 8] = [0xff, 0x25, 0x00, 0x00, 0x00, 0x00, 0x90, 0x90];
-
-            let code_section_data: &mut [u8] = self
-                .arena
-                .alloc_slice_fill_default(CODE_THUNK.len() * thunk_symbols.len());
+    /// Returns `true` if the relocation in the output section is applied
+    /// instead of being written to the output.
+    ///
+    /// Only relative relocations to symbols in the same output section can
+    /// be applied. Addresses are not known until the output is loaded.
+    fn is_flattened_relocation(
+        &self,
+        section_name: &str,
+        reloc: &RelocationEdgeWeight,
+        target_section: &SectionNode<'arena, 'data>,
+    ) -> bool {
+        self.flatten_relocations
+            && target_section.name().group_name() == section_name
+            && matches!(
+                RelocationKind::new(self.machine, reloc.typ()),
+                RelocationKind::Relative(_)
+            )
+    }
 
-            for data_chunk in code_section_data.chunks_mut(CODE_THUNK.len()) {
-                data_chunk.copy_from_slice(&CODE_THUNK);
+    /// Rewrites the loads through the `.refptr` stubs to reference the
+    /// target symbols directly and discards the unused stubs.
+    fn apply_collapse_refptrs(&mut self) {
+        for (name, refptr_symbol) in &self.external_symbols {
+            if !name.starts_with(REFPTR_PREFIX) {
+                continue;
             }
 
-            let code_section = self.arena.alloc_with(|| {
-                SectionNode::new(
-                    ".text$zzz",
-                    SectionNodeCharacteristics::CntCode
-                        | SectionNodeCharacteristics::MemExecute
-                        | SectionNodeCharacteristics::MemRead
-                        | SectionNodeCharacteristics::Align8Bytes,
-                    SectionNodeData::Initialized(code_section_data),
-                    0,
-                    self.root_coff,
-                )
-            });
-
-            let thunk_reloc = match self.machine {
-                LinkerTargetArch::Amd64 => RelocationEdgeWeight::new(2, IMAGE_REL_AMD64_REL32),
-                LinkerTargetArch::I386 => RelocationEdgeWeight::new(2, IMAGE_REL_I386_DIR32),
+            let Some(definition) = refptr_symbol
+                .definitions()
+                .iter()
+                .find(|definition| !definition.target().is_discarded())
+            else {
+                continue;
             };
 
-            for (symbol_num, (symbol_node, import_name)) in thunk_symbols.iter().enumerate() {
-                let symbol_addr = symbol_num as u32 * 8;
-
-                // Add a definition edge for the existing symbol
-                let definition_edge = self.arena.alloc_with(|| {
-                    Edge::new(
-                        *symbol_node,
-                        code_section,
-                        DefinitionEdgeWeight::new(symbol_addr, None),
-                    )
-                });
+            let stub = definition.target();
+            if self.is_kept_section(stub) {
+                continue;
+            }
 
-                symbol_node.definitions().push_back(definition_edge);
-                code_section.definitions().push_back(definition_edge);
+            // The stub should only hold the pointer to the target symbol
+            let mut stub_relocs = stub.relocations().iter();
+            let Some(target_symbol) = stub_relocs
+                .next()
+                .filter(|reloc| {
+                    definition.weight().address() == 0
+                        && stub.data().len() == 8
+                        && reloc.weight().address() == 0
+                        && reloc.weight().typ() == IMAGE_REL_AMD64_ADDR64
+                })
+                .map(|reloc| reloc.target())
+                .filter(|_| stub_relocs.next().is_none())
+            else {
+                continue;
+            };
 
-                // Add a new thunk import symbol for this symbol
-                let thunk_import_symbol = self.arena.alloc_with(|| {
+            // Loads can only be collapsed if the target is defined in the
+            // output. Imported symbols still need the pointer
+            if !target_symbol
+                .definitions()
+                .iter()
+                .any(|definition| !definition.target().is_discarded())
+            {
+                continue;
+            }
+
+            let mut collapsed = 0usize;
+            refptr_symbol.references().retain(|reference| {
+                let section = reference.source();
+                let address = reference.weight().address() as usize;
+
+                let SectionNodeData::Initialized(data) = section.data() else {
+                    return true;
+                };
+
+                // mov r64, [rip + disp32] with a REX.W prefix
+                let is_load = reference.weight().typ() == IMAGE_REL_AMD64_REL32
+                    && !section.is_discarded()
+                    && address >= 3
+                    && data.get(address - 3..address).is_some_and(|insn| {
+                        insn[0] & 0xf8 == 0x48 && insn[1] == 0x8b && insn[2] & 0xc7 == 0x05
+                    });
+
+                if !is_load {
+                    return true;
+                }
+
+                // Rewrite the load into a lea of the target symbol
+                self.opcode_patches
+                    .push((section, address as u32 - 2, LEA_OPCODE));
+                reference.replace_target(target_symbol);
+                target_symbol.references().push_back(reference);
+                collapsed += 1;
+                false
+            });
+
+            if collapsed == 0 {
+                continue;
+            }
+
+            debug!(
+                symbol:% = refptr_symbol.name();
+                "collapsed {collapsed} loads through '{}' into references to '{}'",
+                refptr_symbol.name().demangle(),
+                target_symbol.name().demangle()
+            );
+
+            // Discard the stubs once nothing loads through them
+            for definition in refptr_symbol.definitions().iter() {
+                let stub = definition.target();
+                if !stub.is_discarded()
+                    && stub
+                        .definitions()
+                        .iter()
+                        .all(|definition| definition.source().is_unreferenced())
+                {
+                    stub.discard();
+                }
+            }
+        }
+    }
+
+    /// Rewrites the `movabs` loads with `IMAGE_REL_AMD64_ADDR64` relocations
+    /// into RIP-relative `lea` instructions.
+    fn apply_rip_relative(&mut self) -> Result<(), LinkGraphLinkError> {
+        for section in self.sections.values() {
+            for &section_node in &section.nodes {
+                if section_node.is_discarded()
+                    || section_node
+                        .characteristics()
+                        .contains(SectionNodeCharacteristics::MemDiscardable)
+                {
+                    continue;
+                }
+
+                for reloc in section_node.relocations().iter() {
+                    let weight = reloc.weight();
+                    if weight.typ() != IMAGE_REL_AMD64_ADDR64 {
+                        continue;
+                    }
+
+                    let error = |reason| LinkGraphLinkError::RipRelative {
+                        coff_name: section_node.coff().to_string(),
+                        section: section_node.name().to_string(),
+                        address: weight.address(),
+                        reason,
+                    };
+
+                    if !section_node
+                        .characteristics()
+                        .contains(SectionNodeCharacteristics::CntCode)
+                    {
+                        return Err(error(RipRelativeError::NotCode));
+                    }
+
+                    // Imported and undefined symbols are resolved by the
+                    // loader and may be out of range of a displacement
+                    let target = reloc.target();
+                    if !target
+                        .definitions()
+                        .iter()
+                        .any(|definition| !definition.target().is_discarded())
+                    {
+                        return Err(error(RipRelativeError::NotDefined(
+                            target.name().demangle().to_string(),
+                        )));
+                    }
+
+                    let SectionNodeData::Initialized(data) = section_node.data() else {
+                        return Err(error(RipRelativeError::UnknownInstruction));
+                    };
+
+                    let rewritten =
+                        rewrite_movabs(data, weight.address() as usize).map_err(error)?;
+
+                    let start = weight.address() - 2;
+                    self.opcode_patches.extend(
+                        rewritten
+                            .into_iter()
+                            .zip(start..)
+                            .map(|(byte, address)| (section_node, address, byte)),
+                    );
+                    weight.rewrite(start + LEA_DISPLACEMENT_OFFSET, IMAGE_REL_AMD64_REL32);
+
+                    debug!(
+                        symbol:% = target.name();
+                        "{}: rewrote load of '{}' at '{}+{start:#x}' to be RIP-relative",
+                        section_node.coff(),
+                        target.name().demangle(),
+                        section_node.name()
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if a data or constant import is referenced directly
+    /// instead of through the `__imp_` symbol.
+    ///
+    /// Direct references would need an import thunk which only works for
+    /// code. The `__imp_` symbol is the address of the pointer to the
+    /// imported data.
+    fn check_data_imports(&self) -> Result<(), LinkGraphLinkError> {
+        for library_node in self.api_node.iter().chain(self.library_nodes.values()) {
+            for import_edge in library_node.imports().iter() {
+                let symbol = import_edge.source();
+                if import_edge.weight().import_type() == ImportType::Code
+                    || symbol.name().strip_dllimport().is_some()
+                {
+                    continue;
+                }
+
+                let references = Vec::from_iter(
+                    symbol
+                        .references()
+                        .iter()
+                        .filter(|reference| !reference.source().is_discarded())
+                        .map(|reference| {
+                            format!(
+                                "{}:({}+{:#x})",
+                                reference.source().coff(),
+                                reference.source().name(),
+                                reference.weight().address()
+                            )
+                        }),
+                );
+
+                if !references.is_empty() {
+                    return Err(LinkGraphLinkError::DataImport {
+                        symbol: symbol.name().demangle().to_string(),
+                        library: library_node.name().to_string(),
+                        references,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the imports for symbols where every reference is from a
+    /// discarded section.
+    ///
+    /// Imports for symbols without any references are kept since they were
+    /// explicitly requested in a symbol table.
+    fn remove_discarded_imports(&mut self) {
+        for library_node in self.api_node.iter().chain(self.library_nodes.values()) {
+            library_node.imports().retain(|import_edge| {
+                let symbol = import_edge.source();
+                if symbol.references().is_empty()
+                    || !symbol.is_unreferenced()
+                    || self.is_kept_symbol(symbol.name().as_str())
+                {
+                    return true;
+                }
+
+                debug!(
+                    library:% = library_node.name(),
+                    symbol:% = symbol.name();
+                    "{}: removing import '{}' only referenced from discarded sections",
+                    library_node.name(),
+                    symbol.name()
+                );
+
+                symbol
+                    .imports()
+                    .retain(|symbol_import| !std::ptr::eq(symbol_import, import_edge));
+                false
+            });
+        }
+    }
+
+    /// Replaces the imports from the delay loaded DLLs with pointer slots in
+    /// the delay import table.
+    ///
+    /// The table is placed in `.data` and is made up of entries with a
+    /// pointer to the DLL name, a pointer to the import name and the pointer
+    /// slot for the imported address. The table ends with a zeroed entry and
+    /// is defined by the `__delay_import_table` symbol. Loaders fill in the
+    /// slots when resolving the imports. `__imp_` symbols are defined at the
+    /// slots and other symbols at thunks which jump through them.
+    fn apply_delay_imports(&mut self) {
+        if self.delay_load_libraries.is_empty() {
+            return;
+        }
+
+        // Imports from the delay loaded DLLs grouped by the import name
+        let mut delay_imports: Vec<(&str, IndexMap<&str, Vec<&SymbolNode>>)> = Vec::new();
+
+        for library_node in self.library_nodes.values() {
+            let library_name = library_node.name();
+            if !self.delay_load_libraries.iter().any(|name| {
+                LibraryName::from(name.as_str())
+                    .trim_dll_suffix()
+                    .eq_ignore_ascii_case(library_name.trim_dll_suffix())
+            }) {
+                continue;
+            }
+
+            let mut imports: IndexMap<&str, Vec<&SymbolNode>> = IndexMap::new();
+            library_node.imports().retain(|import_edge| {
+                let symbol = import_edge.source();
+                imports
+                    .entry(import_edge.weight().import_name().as_str())
+                    .or_default()
+                    .push(symbol);
+
+                symbol
+                    .imports()
+                    .retain(|symbol_import| !std::ptr::eq(symbol_import, import_edge));
+                false
+            });
+
+            if imports.is_empty() {
+                continue;
+            }
+
+            debug!(
+                library:% = library_name;
+                "{library_name}: routing {} imports through the delay import table",
+                imports.len()
+            );
+
+            if self.deterministic {
+                imports.sort_unstable_keys();
+            }
+
+            delay_imports.push((library_name.as_str(), imports));
+        }
+
+        if delay_imports.is_empty() {
+            return;
+        }
+
+        if self.deterministic {
+            delay_imports.sort_by_key(|(library_name, _)| *library_name);
+        }
+
+        let (pointer_size, pointer_reloc, thunk_reloc, data_alignment) = match self.machine {
+            LinkerTargetArch::Amd64 => (
+                8,
+                IMAGE_REL_AMD64_ADDR64,
+                IMAGE_REL_AMD64_REL32,
+                SectionNodeCharacteristics::Align8Bytes,
+            ),
+            LinkerTargetArch::I386 => (
+                4,
+                IMAGE_REL_I386_DIR32,
+                IMAGE_REL_I386_DIR32,
+                SectionNodeCharacteristics::Align4Bytes,
+            ),
+        };
+
+        let entry_size = pointer_size * 3;
+        let entry_count: u32 = delay_imports
+            .iter()
+            .map(|(_, imports)| imports.len() as u32)
+            .sum();
+
+        // DLL and import names
+        let mut names = Vec::new();
+        let mut name_offsets = Vec::with_capacity(delay_imports.len());
+        for (library_name, imports) in &delay_imports {
+            let library_offset = names.len() as u32;
+            names.extend_from_slice(library_name.as_bytes());
+            names.push(0);
+
+            for import_name in imports.keys() {
+                name_offsets.push((library_offset, names.len() as u32));
+                names.extend_from_slice(import_name.as_bytes());
+                names.push(0);
+            }
+        }
+
+        let names_section = self.arena.alloc_with(|| {
+            SectionNode::new(
+                ".rdata$dly",
+                SectionNodeCharacteristics::CntInitializedData
+                    | SectionNodeCharacteristics::MemRead
+                    | SectionNodeCharacteristics::Align1Bytes,
+                SectionNodeData::Initialized(self.arena.alloc_slice_copy(&names)),
+                0,
+                self.root_coff,
+            )
+        });
+
+        let names_symbol = self.arena.alloc_with(|| {
+            SymbolNode::new(
+                ".rdata$dly",
+                SymbolNodeStorageClass::Static,
+                true,
+                SymbolNodeType::Value(0),
+            )
+        });
+
+        let names_definition = self.arena.alloc_with(|| {
+            Edge::new(
+                names_symbol,
+                names_section,
+                DefinitionEdgeWeight::new(0, None),
+            )
+        });
+
+        names_symbol.definitions().push_back(names_definition);
+        names_section.definitions().push_back(names_definition);
+
+        // Write the name offsets as the relocation addends
+        let table_data: &mut [u8] = self
+            .arena
+            .alloc_slice_fill_default(((entry_count + 1) * entry_size) as usize);
+
+        for (entry, (library_offset, import_offset)) in table_data
+            .chunks_mut(entry_size as usize)
+            .zip(&name_offsets)
+        {
+            entry[..4].copy_from_slice(&library_offset.to_le_bytes());
+            entry[pointer_size as usize..pointer_size as usize + 4]
+                .copy_from_slice(&import_offset.to_le_bytes());
+        }
+
+        let table_section = self.arena.alloc_with(|| {
+            SectionNode::new(
+                ".data$dly",
+                SectionNodeCharacteristics::CntInitializedData
+                    | SectionNodeCharacteristics::MemRead
+                    | SectionNodeCharacteristics::MemWrite
+                    | data_alignment,
+                SectionNodeData::Initialized(table_data),
+                0,
+                self.root_coff,
+            )
+        });
+
+        let table_symbol = self.arena.alloc_with(|| {
+            SymbolNode::new(
+                "__delay_import_table",
+                SymbolNodeStorageClass::External,
+                false,
+                SymbolNodeType::Value(0),
+            )
+        });
+
+        let table_definition = self.arena.alloc_with(|| {
+            Edge::new(
+                table_symbol,
+                table_section,
+                DefinitionEdgeWeight::new(0, None),
+            )
+        });
+
+        table_symbol.definitions().push_back(table_definition);
+        table_section.definitions().push_back(table_definition);
+
+        // Symbols which need a thunk jumping through the pointer slot
+        let mut thunk_symbols = Vec::new();
+
+        let entries = delay_imports
+            .iter()
+            .flat_map(|(_, imports)| imports.iter())
+            .enumerate();
+
+        for (entry_num, (import_name, symbols)) in entries {
+            let entry_addr = entry_num as u32 * entry_size;
+            let slot_addr = entry_addr + pointer_size * 2;
+
+            for name_addr in [entry_addr, entry_addr + pointer_size] {
+                let relocation_edge = self.arena.alloc_with(|| {
+                    Edge::new(
+                        table_section,
+                        names_symbol,
+                        RelocationEdgeWeight::new(name_addr, pointer_reloc),
+                    )
+                });
+
+                table_section.relocations().push_back(relocation_edge);
+                names_symbol.references().push_back(relocation_edge);
+            }
+
+            // Define the __imp_ symbols at the pointer slot
+            let mut slot_symbol = None;
+            let mut entry_thunks = Vec::new();
+            for symbol in symbols {
+                if symbol.name().strip_dllimport().is_some() {
+                    let definition_edge = self.arena.alloc_with(|| {
+                        Edge::new(
+                            *symbol,
+                            table_section,
+                            DefinitionEdgeWeight::new(slot_addr, None),
+                        )
+                    });
+
+                    symbol.definitions().push_back(definition_edge);
+                    table_section.definitions().push_back(definition_edge);
+                    slot_symbol.get_or_insert(*symbol);
+                } else {
+                    entry_thunks.push(*symbol);
+                }
+            }
+
+            if entry_thunks.is_empty() {
+                continue;
+            }
+
+            // Add a local symbol for the slot if there is no __imp_ symbol
+            let slot_symbol = slot_symbol.unwrap_or_else(|| {
+                let symbol = self.arena.alloc_with(|| {
                     SymbolNode::new(
-                        &*self
-                            .arena
-                            .alloc_str(&format!("__imp_{}", import_name.as_str())),
-                        SymbolNodeStorageClass::External,
+                        self.symbol_names
+                            .intern_copy(self.arena, &format!("__imp_{import_name}")),
+                        SymbolNodeStorageClass::Static,
                         false,
                         SymbolNodeType::Value(0),
                     )
                 });
 
-                // Add a relocation to the thunk import symbol
-                let relocation_edge = self.arena.alloc_with(|| {
+                let definition_edge = self.arena.alloc_with(|| {
+                    Edge::new(
+                        symbol,
+                        table_section,
+                        DefinitionEdgeWeight::new(slot_addr, None),
+                    )
+                });
+
+                symbol.definitions().push_back(definition_edge);
+                table_section.definitions().push_back(definition_edge);
+                symbol
+            });
+
+            thunk_symbols.extend(entry_thunks.into_iter().map(|symbol| (symbol, slot_symbol)));
+        }
+
+        if !thunk_symbols.is_empty() {
+            // jmp [<slot>]
+            const CODE_THUNK: [u8; 8] = [0xff, 0x25, 0x00, 0x00, 0x00, 0x00, 0x90, 0x90];
+
+            let code_section_data: &mut [u8] = self
+                .arena
+                .alloc_slice_fill_default(CODE_THUNK.len() * thunk_symbols.len());
+
+            for data_chunk in code_section_data.chunks_mut(CODE_THUNK.len()) {
+                data_chunk.copy_from_slice(&CODE_THUNK);
+            }
+
+            let code_section = self.arena.alloc_with(|| {
+                SectionNode::new(
+                    ".text$dly",
+                    SectionNodeCharacteristics::CntCode
+                        | SectionNodeCharacteristics::MemExecute
+                        | SectionNodeCharacteristics::MemRead
+                        | SectionNodeCharacteristics::Align8Bytes,
+                    SectionNodeData::Initialized(code_section_data),
+                    0,
+                    self.root_coff,
+                )
+            });
+
+            for (thunk_num, (symbol, slot_symbol)) in thunk_symbols.into_iter().enumerate() {
+                let thunk_addr = thunk_num as u32 * CODE_THUNK.len() as u32;
+
+                let definition_edge = self.arena.alloc_with(|| {
                     Edge::new(
+                        symbol,
                         code_section,
-                        thunk_import_symbol,
-                        RelocationEdgeWeight::new(
-                            thunk_reloc.address() + symbol_addr,
-                            thunk_reloc.typ(),
-                        ),
+                        DefinitionEdgeWeight::new(thunk_addr, None),
                     )
                 });
 
-                code_section.relocations().push_back(relocation_edge);
-                thunk_import_symbol.references().push_back(relocation_edge);
+                symbol.definitions().push_back(definition_edge);
+                code_section.definitions().push_back(definition_edge);
 
-                // Unlink the import edge from the existing symbol
-                let removed_import_edge = symbol_node.imports().pop_front().unwrap();
-                // Set the source node for the edge to the new thunk import
-                // symbol
-                removed_import_edge.replace_source(thunk_import_symbol);
+                let relocation_edge = self.arena.alloc_with(|| {
+                    Edge::new(
+                        code_section,
+                        slot_symbol,
+                        RelocationEdgeWeight::new(thunk_addr + 2, thunk_reloc),
+                    )
+                });
 
-                // Link the edge to the thunk symbol
-                thunk_import_symbol.imports().push_back(removed_import_edge);
+                code_section.relocations().push_back(relocation_edge);
+                slot_symbol.references().push_back(relocation_edge);
             }
 
-            // Add the new code section to the list of sections
             self.sections
-                .entry(code_section.name().group_name())
+                .entry(".text")
                 .or_default()
                 .nodes
                 .push(code_section);
         }
-    }
 
-    /// Handles discarding/keeping sections for COMDAT symbols
-    fn handle_comdats(&self) {
-        for symbol in self.external_symbols.values() {
-            let mut definition_iter = symbol.definitions().iter().peekable();
+        self.sections
+            .entry(".rdata")
+            .or_default()
+            .nodes
+            .push(names_section);
 
-            let first_definition = match definition_iter.peek() {
-                Some(definition) => definition,
-                None => continue,
-            };
+        self.sections
+            .entry(".data")
+            .or_default()
+            .nodes
+            .push(table_section);
+    }
 
-            let selection = match first_definition.weight().selection {
-                Some(sel) => sel,
-                None => continue,
-            };
+    fn apply_import_thunks(&mut self) {
+        let mut thunk_symbols: Vec<(&SymbolNode, SymbolName)> = Vec::new();
+
+        let library_nodes = self
+            .api_node
+            .iter()
+            .map(|api_node| (*api_node, true))
+            .chain(self.library_nodes.values().map(|node| (*node, false)));
+
+        for (library_node, is_api) in library_nodes {
+            // Thunks defined in the inputs for imported symbols
+            let existing_thunks: HashMap<&str, &SectionNode> =
+                HashMap::from_iter(library_node.imports().iter().filter_map(|import_edge| {
+                    let symbol = import_edge.source();
+                    symbol.name().strip_dllimport()?;
+                    self.find_import_thunk(symbol)
+                        .map(|section| (import_edge.weight().import_name().as_str(), section))
+                }));
+
+            library_node.imports().retain(|import_edge| {
+                let import_name = import_edge.weight().import_name();
+                let symbol = import_edge.source();
+                // API symbols mapped to a different name keep their `__imp_`
+                // prefix
+                let dllimport = symbol
+                    .name()
+                    .strip_dllimport()
+                    .is_some_and(|unprefixed| is_api || unprefixed == import_name.as_str());
+                if dllimport || symbol.is_unreferenced() {
+                    return true;
+                }
 
-            if selection == ComdatSelection::Any
-                || selection == ComdatSelection::SameSize
-                || selection == ComdatSelection::ExactMatch
-            {
-                // Keep the first section but discard the rest.
-                let _ = definition_iter.next();
+                let Some(thunk_section) = existing_thunks.get(import_name.as_str()) else {
+                    thunk_symbols.push((symbol, import_name));
+                    return true;
+                };
 
-                for remaining in definition_iter {
-                    let section = remaining.target();
-                    debug!(
-                        "{}: discarding COMDAT {} ({selection:?})",
-                        section.coff(),
-                        section.name(),
-                    );
-                    section.discard();
-                }
-            } else if selection == ComdatSelection::Largest {
-                // Find the largest size and discard the rest.
-                let mut largest_section: Option<&'arena SectionNode<'arena, 'data>> = None;
+                debug!(
+                    coff:% = thunk_section.coff().file_path().display(),
+                    member = thunk_section.coff().member_path().map(Path::to_string_lossy),
+                    section:% = thunk_section.name(),
+                    symbol:% = symbol.name();
+                    "{}: reusing import thunk for '{}' in {}",
+                    thunk_section.coff(),
+                    symbol.name().demangle(),
+                    thunk_section.name()
+                );
 
-                for definition in definition_iter {
-                    let section = definition.target();
+                // Define the symbol at the existing thunk and remove the import
+                let definition_edge = self.arena.alloc_with(|| {
+                    Edge::new(symbol, *thunk_section, DefinitionEdgeWeight::new(0, None))
+                });
+
+                symbol.definitions().push_back(definition_edge);
+                thunk_section.definitions().push_back(definition_edge);
+
+                symbol
+                    .imports()
+                    .retain(|symbol_import| !std::ptr::eq(symbol_import, import_edge));
+                false
+            });
+        }
+
+        // Sort the thunks by the import name so that the thunk section
+        // layout does not depend on the symbol resolution order
+        if self.deterministic {
+            thunk_symbols.sort_by_key(|(_, import_name)| import_name.as_str());
+        }
+
+        if !thunk_symbols.is_empty() {
+            // jmp [rip + $<symbol>]
+            const CODE_THUNK: [u8; 8] = [0xff, 0x25, 0x00, 0x00, 0x00, 0x00, 0x90, 0x90];
+
+            let code_section_data: &mut [u8] = self
+                .arena
+                .alloc_slice_fill_default(CODE_THUNK.len() * thunk_symbols.len());
+
+            for data_chunk in code_section_data.chunks_mut(CODE_THUNK.len()) {
+                data_chunk.copy_from_slice(&CODE_THUNK);
+            }
+
+            let code_section = self.arena.alloc_with(|| {
+                SectionNode::new(
+                    ".text$zzz",
+                    SectionNodeCharacteristics::CntCode
+                        | SectionNodeCharacteristics::MemExecute
+                        | SectionNodeCharacteristics::MemRead
+                        | SectionNodeCharacteristics::Align8Bytes,
+                    SectionNodeData::Initialized(code_section_data),
+                    0,
+                    self.root_coff,
+                )
+            });
+
+            let thunk_reloc = match self.machine {
+                LinkerTargetArch::Amd64 => RelocationEdgeWeight::new(2, IMAGE_REL_AMD64_REL32),
+                LinkerTargetArch::I386 => RelocationEdgeWeight::new(2, IMAGE_REL_I386_DIR32),
+            };
+
+            for (symbol_num, (symbol_node, import_name)) in thunk_symbols.iter().enumerate() {
+                let symbol_addr = symbol_num as u32 * 8;
+
+                // Add a definition edge for the existing symbol
+                let definition_edge = self.arena.alloc_with(|| {
+                    Edge::new(
+                        *symbol_node,
+                        code_section,
+                        DefinitionEdgeWeight::new(symbol_addr, None),
+                    )
+                });
+
+                symbol_node.definitions().push_back(definition_edge);
+                code_section.definitions().push_back(definition_edge);
+
+                // Add a new thunk import symbol for this symbol
+                let thunk_import_symbol = self.arena.alloc_with(|| {
+                    SymbolNode::new(
+                        self.symbol_names
+                            .intern_copy(self.arena, &format!("__imp_{}", import_name.as_str())),
+                        SymbolNodeStorageClass::External,
+                        false,
+                        SymbolNodeType::Value(0),
+                    )
+                });
+
+                // Add a relocation to the thunk import symbol
+                let relocation_edge = self.arena.alloc_with(|| {
+                    Edge::new(
+                        code_section,
+                        thunk_import_symbol,
+                        RelocationEdgeWeight::new(
+                            thunk_reloc.address() + symbol_addr,
+                            thunk_reloc.typ(),
+                        ),
+                    )
+                });
+
+                code_section.relocations().push_back(relocation_edge);
+                thunk_import_symbol.references().push_back(relocation_edge);
+
+                // Unlink the import edge from the existing symbol
+                let removed_import_edge = symbol_node.imports().pop_front().unwrap();
+                // Set the source node for the edge to the new thunk import
+                // symbol
+                removed_import_edge.replace_source(thunk_import_symbol);
+
+                // Link the edge to the thunk symbol
+                thunk_import_symbol.imports().push_back(removed_import_edge);
+            }
+
+            // Add the new code section to the list of sections
+            self.sections
+                .entry(code_section.name().group_name())
+                .or_default()
+                .nodes
+                .push(code_section);
+        }
+    }
+
+    /// Returns the code section with an import thunk for the `__imp_` symbol
+    /// if one was defined in the inputs.
+    ///
+    /// Import thunks are code sections between 6 and 8 bytes with a
+    /// `jmp [<symbol>]` instruction and a single relocation to the symbol at
+    /// offset 2.
+    fn find_import_thunk(
+        &self,
+        symbol: &'arena SymbolNode<'arena, 'data>,
+    ) -> Option<&'arena SectionNode<'arena, 'data>> {
+        let thunk_reloc_type = match self.machine {
+            LinkerTargetArch::Amd64 => IMAGE_REL_AMD64_REL32,
+            LinkerTargetArch::I386 => IMAGE_REL_I386_DIR32,
+        };
+
+        symbol.references().iter().find_map(|reloc| {
+            let section = reloc.source();
+
+            let SectionNodeData::Initialized(data) = section.data() else {
+                return None;
+            };
+
+            (!section.is_discarded()
+                && section
+                    .characteristics()
+                    .contains(SectionNodeCharacteristics::CntCode)
+                && (6..=8).contains(&data.len())
+                && data.starts_with(&[0xff, 0x25])
+                && section.relocations().len() == 1
+                && reloc.weight().address() == 2
+                && reloc.weight().typ() == thunk_reloc_type)
+                .then_some(section)
+        })
+    }
+
+    /// Discards or moves the `IMAGE_SCN_LNK_INFO` input sections into the
+    /// `.comment` output section.
+    fn apply_lnk_info_policy(&mut self) {
+        let mut comments = Vec::new();
+
+        for (section_name, section) in self.sections.iter_mut() {
+            if *section_name == ".drectve" {
+                continue;
+            }
+
+            section.nodes.retain(|node| {
+                if !node
+                    .characteristics()
+                    .contains(SectionNodeCharacteristics::LnkInfo)
+                    || std::ptr::eq(node.coff(), self.root_coff)
+                    || node.is_discarded()
+                {
+                    return true;
+                }
+
+                match self.lnk_info_policy {
+                    LnkInfoPolicy::Keep => true,
+                    LnkInfoPolicy::Drop => {
+                        debug!(
+                            coff:% = node.coff().file_path().display(),
+                            member = node.coff().member_path().map(Path::to_string_lossy),
+                            section:% = node.name();
+                            "{}: discarding 'IMAGE_SCN_LNK_INFO' section {}",
+                            node.coff(),
+                            node.name()
+                        );
+                        node.discard();
+                        false
+                    }
+                    LnkInfoPolicy::Comment if *section_name == ".comment" => true,
+                    LnkInfoPolicy::Comment => {
+                        comments.push(*node);
+                        false
+                    }
+                }
+            });
+        }
+
+        if !comments.is_empty() {
+            self.sections
+                .entry(".comment")
+                .or_default()
+                .nodes
+                .extend(comments);
+        }
+    }
+
+    /// Handles discarding/keeping sections for COMDAT symbols
+    fn handle_comdats(&mut self) {
+        // The definitions of discarded largest COMDAT sections are redirected
+        // to the kept section so this should only run once but may be called
+        // multiple times
+        if std::mem::replace(&mut self.comdats_handled, true) {
+            return;
+        }
+
+        for symbol in self.external_symbols.values() {
+            let mut definition_iter = symbol.definitions().iter().peekable();
+
+            let first_definition = match definition_iter.peek() {
+                Some(definition) => definition,
+                None => continue,
+            };
+
+            let selection = match first_definition.weight().selection {
+                Some(sel) => sel,
+                None => continue,
+            };
+
+            if selection == ComdatSelection::Any
+                || selection == ComdatSelection::SameSize
+                || selection == ComdatSelection::ExactMatch
+            {
+                // Keep the first section with a kept section in its
+                // associative chain or the first section and discard the
+                // rest.
+                let kept = symbol
+                    .definitions()
+                    .iter()
+                    .position(|definition| {
+                        definition
+                            .target()
+                            .associative_bfs()
+                            .any(|section| self.is_kept_section(section))
+                    })
+                    .unwrap_or(0);
+
+                for (_, remaining) in definition_iter.enumerate().filter(|(idx, _)| *idx != kept) {
+                    let section = remaining.target();
+                    debug!(
+                        coff:% = section.coff().file_path().display(),
+                        member = section.coff().member_path().map(Path::to_string_lossy),
+                        section:% = section.name();
+                        "{}: discarding COMDAT {} ({selection:?})",
+                        section.coff(),
+                        section.name(),
+                    );
+                    section.discard();
+                }
+            } else if selection == ComdatSelection::Largest {
+                // Find the largest size and discard the rest. Sections with
+                // the same size are ordered by alignment.
+                let mut largest_section: Option<&'arena SectionNode<'arena, 'data>> = None;
+
+                for definition in definition_iter {
+                    let section = definition.target();
 
                     if let Some(largest) = &mut largest_section {
-                        if largest.data().len() < section.data().len() {
-                            debug!(
-                                "{}: discarding COMDAT {} ({selection:?})",
-                                largest.coff(),
-                                largest.name()
-                            );
-                            largest.discard();
-                            *largest = section;
-                        }
+                        let (discarded, kept) =
+                            if comdat_sort_key(largest) < comdat_sort_key(section) {
+                                (*largest, section)
+                            } else {
+                                (section, *largest)
+                            };
+
+                        debug!(
+                            coff:% = discarded.coff().file_path().display(),
+                            member = discarded.coff().member_path().map(Path::to_string_lossy),
+                            section:% = discarded.name();
+                            "{}: discarding COMDAT {} ({selection:?})",
+                            discarded.coff(),
+                            discarded.name()
+                        );
+                        discarded.discard();
+                        *largest = kept;
                     } else {
                         largest_section = Some(section);
                     }
                 }
+
+                if let Some(largest) = largest_section {
+                    self.redirect_comdat_definitions(symbol, largest);
+                }
+            } else if selection == ComdatSelection::NoDuplicates {
+                // Definitions from other COFFs were already rejected. The
+                // remaining definitions are repeated in the same COFF so only
+                // the first one is kept to write the symbol once
+                let first_definition = *first_definition;
+                for repeated in definition_iter.skip(1) {
+                    repeated
+                        .target()
+                        .definitions()
+                        .retain(|definition| !std::ptr::eq(definition, repeated));
+                }
+
+                symbol
+                    .definitions()
+                    .retain(|definition| std::ptr::eq(definition, first_definition));
             } else if selection == ComdatSelection::Associative {
                 // Associative COMDAT symbols are handled by traversing the
                 // root of the COMDAT chain.
@@ -476,6 +2052,12 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                 for associative_section in root_section.associative_bfs() {
                     if !associative_section.is_discarded() && root_discarded {
                         debug!(
+                            coff:% = associative_section.coff().file_path().display(),
+                            member = associative_section
+                                .coff()
+                                .member_path()
+                                .map(Path::to_string_lossy),
+                            section:% = associative_section.name();
                             "{}: discarding COMDAT {}. associative to discarded root ({}:{})",
                             associative_section.coff(),
                             associative_section.name(),
@@ -484,16 +2066,1217 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                         );
                     }
 
-                    associative_section.set_discarded(root_discarded);
-                }
+                    associative_section.set_discarded(root_discarded);
+                }
+            }
+        }
+    }
+
+    /// Redirects the local symbols defined in discarded `IMAGE_COMDAT_SELECT_LARGEST`
+    /// sections to the kept section.
+    ///
+    /// Symbols are only redirected if the data in the discarded section is a
+    /// prefix of the data in the kept section starting from the COMDAT symbol.
+    fn redirect_comdat_definitions(
+        &self,
+        symbol: &'arena SymbolNode<'arena, 'data>,
+        kept: &'arena SectionNode<'arena, 'data>,
+    ) {
+        let Some(kept_offset) = symbol
+            .definitions()
+            .iter()
+            .find(|definition| std::ptr::eq(definition.target(), kept))
+            .map(|definition| definition.weight().address())
+        else {
+            return;
+        };
+
+        for comdat_definition in symbol.definitions() {
+            let discarded = comdat_definition.target();
+            if std::ptr::eq(discarded, kept) {
+                continue;
+            }
+
+            let discarded_offset = comdat_definition.weight().address();
+
+            let layout_matches = match (discarded.data(), kept.data()) {
+                (
+                    SectionNodeData::Initialized(discarded_data),
+                    SectionNodeData::Initialized(kept_data),
+                ) => discarded_data
+                    .get(discarded_offset as usize..)
+                    .zip(kept_data.get(kept_offset as usize..))
+                    .is_some_and(|(discarded_data, kept_data)| {
+                        kept_data.starts_with(discarded_data)
+                    }),
+                (SectionNodeData::Uninitialized(_), SectionNodeData::Uninitialized(_)) => true,
+                _ => false,
+            };
+
+            let local_definitions = Vec::from_iter(
+                discarded
+                    .definitions()
+                    .iter()
+                    .filter(|definition| {
+                        definition.source().storage_class() != SymbolNodeStorageClass::External
+                    })
+                    .map(|definition| (definition.source(), definition.weight().address())),
+            );
+
+            for (local_symbol, address) in local_definitions {
+                // Relocations targeting section symbols are not adjusted by
+                // the symbol address so the COMDAT symbol offsets need to match.
+                let redirectable = layout_matches
+                    && address >= discarded_offset
+                    && (!local_symbol.is_section_symbol() || discarded_offset == kept_offset);
+
+                if !redirectable {
+                    if !local_symbol.is_unreferenced() {
+                        warn!(
+                            coff:% = discarded.coff().file_path().display(),
+                            member = discarded.coff().member_path().map(Path::to_string_lossy),
+                            section:% = discarded.name(),
+                            symbol:% = local_symbol.name();
+                            "{}: could not redirect symbol '{}' in discarded COMDAT {} to {}:{}. section layouts do not match",
+                            discarded.coff(),
+                            local_symbol.name().demangle(),
+                            discarded.name(),
+                            kept.coff().short_name(),
+                            kept.name(),
+                        );
+                    }
+
+                    continue;
+                }
+
+                let redirected_address = address - discarded_offset + kept_offset;
+
+                debug!(
+                    coff:% = discarded.coff().file_path().display(),
+                    member = discarded.coff().member_path().map(Path::to_string_lossy),
+                    section:% = discarded.name(),
+                    symbol:% = local_symbol.name();
+                    "{}: redirecting symbol '{}' in discarded COMDAT {} to {}:{}+{redirected_address:#x}",
+                    discarded.coff(),
+                    local_symbol.name().demangle(),
+                    discarded.name(),
+                    kept.coff().short_name(),
+                    kept.name(),
+                );
+
+                let definition_edge = self.arena.alloc_with(|| {
+                    Edge::new(
+                        local_symbol,
+                        kept,
+                        DefinitionEdgeWeight::new(redirected_address, None),
+                    )
+                });
+
+                local_symbol.definitions().push_back(definition_edge);
+                kept.definitions().push_back(definition_edge);
+            }
+        }
+    }
+
+    /// Checks that all of the input COFFs are compatible with safe exception
+    /// handlers.
+    ///
+    /// Import library members do not contain code and are skipped.
+    fn check_safeseh(&self) -> Result<(), LinkGraphLinkError> {
+        let incompatible = Vec::from_iter(
+            self.coff_nodes
+                .iter()
+                .filter(|coff| !self.import_coffs.contains(*coff))
+                .filter(|coff| {
+                    self.coff_features
+                        .get(*coff)
+                        .is_none_or(|features| features & FEAT_SAFESEH == 0)
+                })
+                .map(|coff| coff.to_string()),
+        );
+
+        if incompatible.is_empty() {
+            Ok(())
+        } else {
+            Err(LinkGraphLinkError::SafeSeh(incompatible))
+        }
+    }
+
+    /// Returns the absolute symbols to keep in the output along with their
+    /// merged values.
+    fn absolute_symbols(&self) -> IndexMap<&'arena str, u32> {
+        let mut absolute_symbols: IndexMap<&str, u32> = IndexMap::new();
+
+        for symbol in self.extraneous_symbols.iter() {
+            let SymbolNodeType::Absolute(value) = symbol.typ() else {
+                continue;
+            };
+
+            if symbol.name().as_str() == FEAT_SYMBOL
+                || !self.absolute_symbols.contains(symbol.name().as_str())
+            {
+                continue;
+            }
+
+            *absolute_symbols.entry(symbol.name().as_str()).or_default() |= value;
+        }
+
+        // The feature flags are merged using the flag specific rules
+        if self.absolute_symbols.contains(FEAT_SYMBOL)
+            || (self.safeseh && self.machine == LinkerTargetArch::I386)
+        {
+            let features = merge_features(
+                self.machine,
+                self.coff_nodes
+                    .iter()
+                    .filter(|coff| !self.import_coffs.contains(*coff))
+                    .map(|coff| self.coff_features.get(coff).copied().unwrap_or(0)),
+            );
+
+            absolute_symbols.insert(FEAT_SYMBOL, features);
+        }
+
+        if self.deterministic {
+            absolute_symbols.sort_unstable_keys();
+        }
+
+        if let Some(layout) = self.layout.as_ref() {
+            absolute_symbols
+                .sort_by_cached_key(|name, _| layout_position(layout.symbol_position(name)));
+        }
+
+        absolute_symbols
+    }
+
+    /// Returns the definitions in the output section for symbols which are
+    /// written to the output symbol table.
+    ///
+    /// Section symbols and labels are not included. The definitions are
+    /// ordered by symbol name and then by the COFF path if deterministic
+    /// ordering is enabled.
+    fn symbol_definitions(
+        &self,
+        section: &OutputSection<'arena, 'data>,
+    ) -> Vec<(
+        &'arena SectionNode<'arena, 'data>,
+        &'arena DefinitionEdge<'arena, 'data>,
+    )> {
+        let mut definitions = Vec::from_iter(
+            section
+                .nodes
+                .iter()
+                .filter(|node| !node.is_discarded())
+                .flat_map(|node| {
+                    node.definitions()
+                        .iter()
+                        .map(move |definition| (*node, definition))
+                })
+                .filter(|(_, definition)| {
+                    !definition.source().is_section_symbol() && !definition.source().is_label()
+                }),
+        );
+
+        // Local symbols with the same name are ordered by the COFF defining
+        // them
+        if self.deterministic {
+            definitions.sort_by(|(a_node, a), (b_node, b)| {
+                a.source()
+                    .name()
+                    .as_str()
+                    .cmp(b.source().name().as_str())
+                    .then_with(|| a_node.coff().file_path().cmp(b_node.coff().file_path()))
+                    .then_with(|| {
+                        a_node
+                            .coff()
+                            .member_path()
+                            .cmp(&b_node.coff().member_path())
+                    })
+                    .then(a.weight().address().cmp(&b.weight().address()))
+            });
+        }
+
+        if let Some(layout) = self.layout.as_ref() {
+            definitions.sort_by_cached_key(|(_, definition)| {
+                layout_position(layout.symbol_position(definition.source().name().as_str()))
+            });
+        }
+
+        definitions
+    }
+
+    /// Returns the auxiliary function records for the output symbols keyed
+    /// by the symbol table index.
+    ///
+    /// Function definitions point to the first `.bf` symbol inside the
+    /// function. Function definitions and `.bf` symbols are each chained to
+    /// the next one in the symbol table.
+    fn function_aux_records(&self) -> HashMap<u32, Vec<u8>> {
+        let definitions = Vec::from_iter(
+            self.sections
+                .values()
+                .flat_map(|section| self.symbol_definitions(section))
+                .filter_map(|(section_node, definition)| {
+                    let symbol = definition.source();
+                    Some((
+                        section_node,
+                        definition.weight().address(),
+                        symbol.table_index()?,
+                        symbol.name().as_str(),
+                        symbol.function_aux()?,
+                    ))
+                }),
+        );
+
+        let functions = Vec::from_iter(
+            definitions
+                .iter()
+                .filter(|(.., aux)| matches!(aux, SymbolNodeFunctionAux::Definition { .. })),
+        );
+        let begins = Vec::from_iter(definitions.iter().filter(|(_, _, _, name, aux)| {
+            *name == ".bf" && matches!(aux, SymbolNodeFunctionAux::BeginEnd { .. })
+        }));
+
+        let next_begins: HashMap<u32, u32> =
+            HashMap::from_iter(begins.windows(2).map(|pair| (pair[0].2, pair[1].2)));
+
+        let mut records = HashMap::with_capacity(definitions.len());
+
+        for (position, (section_node, address, index, _, aux)) in functions.iter().enumerate() {
+            let SymbolNodeFunctionAux::Definition { total_size } = *aux else {
+                unreachable!();
+            };
+
+            let end = address.saturating_add(total_size.max(1));
+            let tag_index = begins
+                .iter()
+                .find(|(begin_node, begin_address, ..)| {
+                    std::ptr::eq(*begin_node, *section_node)
+                        && (*address..end).contains(begin_address)
+                })
+                .map(|(_, _, begin_index, ..)| *begin_index)
+                .unwrap_or_default();
+
+            let next_function = functions
+                .get(position + 1)
+                .map(|(_, _, next_index, ..)| *next_index)
+                .unwrap_or_default();
+
+            let record = ImageAuxSymbolFunction {
+                tag_index: U32Bytes::new(LittleEndian, tag_index),
+                total_size: U32Bytes::new(LittleEndian, total_size),
+                pointer_to_linenumber: U32Bytes::new(LittleEndian, 0),
+                pointer_to_next_function: U32Bytes::new(LittleEndian, next_function),
+                unused: [0; 2],
+            };
+            records.insert(*index, object::bytes_of(&record).to_vec());
+        }
+
+        for (_, _, index, name, aux) in &definitions {
+            let SymbolNodeFunctionAux::BeginEnd { line_number } = *aux else {
+                continue;
+            };
+
+            let next_begin = if *name == ".bf" {
+                next_begins.get(index).copied().unwrap_or_default()
+            } else {
+                0
+            };
+
+            let record = ImageAuxSymbolFunctionBeginEnd {
+                unused1: [0; 4],
+                linenumber: U16Bytes::new(LittleEndian, line_number),
+                unused2: [0; 6],
+                pointer_to_next_function: U32Bytes::new(LittleEndian, next_begin),
+                unused3: [0; 2],
+            };
+            records.insert(*index, object::bytes_of(&record).to_vec());
+        }
+
+        records
+    }
+
+    /// Returns the imported symbols along with their names for the output
+    /// COFF.
+    ///
+    /// The symbols are ordered by name if deterministic ordering is enabled.
+    fn import_symbols(&self) -> Vec<(&'arena SymbolNode<'arena, 'data>, &'arena str)> {
+        let mut imports = Vec::new();
+
+        if let Some(api_node) = self.api_node {
+            for import in api_node.imports() {
+                let symbol = import.source();
+                let name = symbol
+                    .output_rename()
+                    .get()
+                    .copied()
+                    .unwrap_or(symbol.name().as_str());
+                imports.push((symbol, name));
+            }
+        }
+
+        for library in self.library_nodes.values() {
+            for import in library.imports() {
+                let name = self.symbol_names.intern_copy(
+                    self.arena,
+                    &format!(
+                        "__imp_{}${}",
+                        library.name().trim_dll_suffix(),
+                        import.weight().import_name()
+                    ),
+                );
+
+                imports.push((import.source(), name));
+            }
+        }
+
+        if self.deterministic {
+            imports.sort_by_key(|(_, name)| *name);
+        }
+
+        if let Some(layout) = self.layout.as_ref() {
+            imports.sort_by_cached_key(|(_, name)| layout_position(layout.symbol_position(name)));
+        }
+
+        imports
+    }
+
+    /// Returns the referenced symbols which are left undefined for the loader.
+    fn loader_extern_symbols(&self) -> Vec<&'arena SymbolNode<'arena, 'data>> {
+        Vec::from_iter(
+            self.external_symbols
+                .values()
+                .copied()
+                .filter(|symbol| symbol.is_loader_extern() && !symbol.is_unreferenced()),
+        )
+    }
+
+    /// Orders the output sections and the input sections with the same name
+    /// using the layout from the previous link.
+    ///
+    /// Sections which are not in the layout are placed after the ones which
+    /// are while keeping their relative order. Returns the layout with the
+    /// resulting order of the sections along with the output sections which
+    /// were packed.
+    fn apply_layout(&mut self) -> (LinkLayout, Vec<PackedSection>) {
+        let mut section_inputs = HashMap::with_capacity(self.sections.len() + 1);
+        let entry_section = self.entry_first_section();
+        let mut cold_nodes = Vec::new();
+        let mut packed_sections = Vec::new();
+
+        for (section_name, section) in self.sections.iter_mut() {
+            // Identify the input sections before they are reordered
+            let mut nodes = Vec::from_iter(
+                section
+                    .nodes
+                    .iter()
+                    .copied()
+                    .zip(layout_inputs(&section.nodes)),
+            );
+
+            // Order the input sections by descending alignment if it reduces
+            // the padding. Sections in the layout or with profile hits are
+            // reordered below
+            if self.pack_sections {
+                let default_alignment = default_section_alignment(self.machine, section_name)
+                    .filter(|_| self.default_section_alignment);
+
+                let padding_before =
+                    padding_size(nodes.iter().map(|(node, _)| *node), default_alignment);
+
+                let mut packed = nodes.clone();
+                for group in packed.chunk_by_mut(|(a, _), (b, _)| a.name() == b.name()) {
+                    group.sort_by_key(|(node, _)| {
+                        Reverse(input_alignment(node, default_alignment).unwrap_or(1))
+                    });
+                }
+
+                let padding_after =
+                    padding_size(packed.iter().map(|(node, _)| *node), default_alignment);
+
+                if padding_after < padding_before {
+                    info!(
+                        section = section_name;
+                        "packed section '{section_name}' saving {} bytes of padding",
+                        padding_before - padding_after
+                    );
+
+                    nodes = packed;
+                    packed_sections.push(PackedSection {
+                        section: section_name.to_string(),
+                        padding_before,
+                        padding_after,
+                    });
+                }
+            }
+
+            // Only reorder input sections with the same name to preserve the
+            // grouped section ordering
+            if let Some(layout) = self.layout.as_ref() {
+                for group in nodes.chunk_by_mut(|(a, _), (b, _)| a.name() == b.name()) {
+                    group.sort_by_cached_key(|(_, input)| {
+                        layout_position(layout.input_position(section_name, input))
+                    });
+                }
+            }
+
+            // Order the code sections by the profile hits
+            if let Some(profile) = self.profile.as_ref() {
+                for group in nodes.chunk_by_mut(|(a, _), (b, _)| a.name() == b.name()) {
+                    group.sort_by_cached_key(|(node, _)| Reverse(profile_hits(profile, node)));
+                }
+            }
+
+            // Move the entrypoint section to the start
+            if let Some(entry_position) = entry_section.and_then(|entry_section| {
+                nodes
+                    .iter()
+                    .position(|(node, _)| std::ptr::eq(*node, entry_section))
+            }) {
+                nodes[..=entry_position].rotate_right(1);
+            }
+
+            // Split off the cold code sections
+            if let Some(profile) = self
+                .profile
+                .as_ref()
+                .filter(|_| self.split_cold && *section_name == ".text")
+            {
+                let (hot, cold): (Vec<_>, Vec<_>) = nodes.into_iter().partition(|(node, _)| {
+                    entry_section.is_some_and(|entry| std::ptr::eq(*node, entry))
+                        || !node
+                            .characteristics()
+                            .contains(SectionNodeCharacteristics::CntCode)
+                        || profile_hits(profile, node) > 0
+                });
+
+                // Keep the sections in .text if none of them are hot
+                nodes = if hot.is_empty() {
+                    cold
+                } else {
+                    cold_nodes = cold;
+                    hot
+                };
+            }
+
+            let (nodes, inputs): (Vec<_>, Vec<_>) = nodes.into_iter().unzip();
+            section.nodes = nodes;
+            section_inputs.insert(*section_name, inputs);
+        }
+
+        if !cold_nodes.is_empty() {
+            let (nodes, inputs): (Vec<_>, Vec<_>) = cold_nodes.into_iter().unzip();
+            debug!(
+                "moving {} code sections without profile hits to '{COLD_TEXT_SECTION}'",
+                nodes.len()
+            );
+
+            let text_index = self.sections.get_index_of(".text").unwrap_or_default();
+            self.sections.shift_insert(
+                text_index + 1,
+                COLD_TEXT_SECTION,
+                OutputSection {
+                    nodes,
+                    ..Default::default()
+                },
+            );
+            section_inputs.insert(COLD_TEXT_SECTION, inputs);
+        }
+
+        if let Some(layout) = self.layout.as_ref() {
+            self.sections
+                .sort_by_cached_key(|name, _| layout_position(layout.section_position(name)));
+        }
+
+        if let Some(entry_section) = entry_section {
+            if let Some(section_index) = self
+                .sections
+                .get_index_of(entry_section.name().group_name())
+            {
+                self.sections.move_index(section_index, 0);
+            }
+        }
+
+        let mut output_layout = LinkLayout::new();
+        for section_name in self.sections.keys() {
+            for input in section_inputs.remove(section_name).into_iter().flatten() {
+                output_layout.add_input(*section_name, input);
+            }
+        }
+
+        (output_layout, packed_sections)
+    }
+
+    /// Returns the section defining the entrypoint symbol if it should be
+    /// placed first.
+    ///
+    /// Entrypoints which are not defined in a code section are not moved.
+    fn entry_first_section(&self) -> Option<&'arena SectionNode<'arena, 'data>> {
+        let entrypoint = self.entry_first.as_deref()?;
+
+        let Some(definition) = self.external_symbols.get(entrypoint).and_then(|symbol| {
+            symbol
+                .definitions()
+                .iter()
+                .find(|definition| !definition.target().is_discarded())
+        }) else {
+            warn!(
+                symbol = entrypoint;
+                "entrypoint '{entrypoint}' is not defined in a section and can not be placed first"
+            );
+            return None;
+        };
+
+        let section = definition.target();
+        if !section
+            .characteristics()
+            .contains(SectionNodeCharacteristics::CntCode)
+        {
+            warn!(
+                coff:% = section.coff().file_path().display(),
+                member = section.coff().member_path().map(Path::to_string_lossy),
+                section:% = section.name(),
+                symbol = entrypoint;
+                "{}: entrypoint '{entrypoint}' is defined in non-code section {} and can not be placed first",
+                section.coff(),
+                section.name()
+            );
+            return None;
+        }
+
+        let address = definition.weight().address();
+        if address != 0 {
+            warn!(
+                coff:% = section.coff().file_path().display(),
+                member = section.coff().member_path().map(Path::to_string_lossy),
+                section:% = section.name(),
+                symbol = entrypoint;
+                "{}: entrypoint '{entrypoint}' is at {}+{address:#x} instead of the start of the section",
+                section.coff(),
+                section.name()
+            );
+        }
+
+        Some(section)
+    }
+
+    /// Returns the printable strings in the initialized data sections of the
+    /// built COFF.
+    ///
+    /// Each input section is searched separately so that every string is
+    /// attributed to a single input.
+    fn output_strings(&self, built_coff: &[u8]) -> Vec<OutputString> {
+        let mut strings = Vec::new();
+
+        for (section_name, section) in &self.sections {
+            if section.header.characteristics & IMAGE_SCN_CNT_INITIALIZED_DATA == 0
+                || section.header.characteristics & IMAGE_SCN_CNT_CODE != 0
+            {
+                continue;
+            }
+
+            let data_start = section.header.pointer_to_raw_data as usize;
+            for node in &section.nodes {
+                let SectionNodeData::Initialized(data) = node.data() else {
+                    continue;
+                };
+
+                let node_start = data_start + node.virtual_address() as usize;
+                let node_data = &built_coff[node_start..node_start + data.len()];
+
+                strings.extend(find_strings(node_data).into_iter().map(
+                    |(offset, encoding, value)| OutputString {
+                        section: section_name.to_string(),
+                        offset: node.virtual_address() + offset as u32,
+                        coff: node.coff().to_string(),
+                        input_section: node.name().to_string(),
+                        encoding,
+                        value,
+                    },
+                ));
+            }
+        }
+
+        strings
+    }
+
+    /// Returns the estimated sizes of the symbols defined in the output.
+    ///
+    /// A symbol extends to the next symbol at a higher address in the same
+    /// input section or to the end of the input section. Section symbols and
+    /// labels are not included.
+    fn symbol_sizes(&self) -> Vec<SymbolSize> {
+        let mut sizes = Vec::new();
+
+        for (section_name, section) in &self.sections {
+            for node in &section.nodes {
+                let node_size = match node.data() {
+                    SectionNodeData::Initialized(data) => data.len() as u32,
+                    SectionNodeData::Uninitialized(size) => size,
+                };
+
+                let mut symbols =
+                    Vec::from_iter(node.definitions().iter().filter_map(|definition| {
+                        let symbol = definition.source();
+                        (!symbol.is_section_symbol() && !symbol.is_label())
+                            .then(|| (definition.weight().address(), symbol))
+                    }));
+                symbols.sort_by_key(|(address, _)| *address);
+
+                for (idx, (address, symbol)) in symbols.iter().enumerate() {
+                    let end = symbols[idx..]
+                        .iter()
+                        .map(|(next_address, _)| *next_address)
+                        .find(|next_address| next_address > address)
+                        .unwrap_or(node_size);
+
+                    sizes.push(SymbolSize {
+                        name: symbol.name().to_string(),
+                        section: section_name.to_string(),
+                        address: node.virtual_address() + address,
+                        size: end.saturating_sub(*address),
+                        code: node
+                            .characteristics()
+                            .contains(SectionNodeCharacteristics::CntCode),
+                        coff: node.coff().to_string(),
+                    });
+                }
+            }
+        }
+
+        sizes.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        sizes
+    }
+
+    /// Adds the zeroed obfuscation metadata section.
+    ///
+    /// The section is sized for the most ranges the obfuscated sections can
+    /// be split into. Every output relocation and the checksum table may
+    /// split a range.
+    fn add_obfuscation_metadata(&mut self) -> &'arena SectionNode<'arena, 'data> {
+        if self.obfuscation_key.is_empty() {
+            let random_state = RandomState::new();
+            self.obfuscation_key = (0..2u64)
+                .flat_map(|idx| random_state.hash_one(idx).to_le_bytes())
+                .collect();
+        }
+
+        let max_ranges: usize = self
+            .sections
+            .iter()
+            .filter(|(section_name, _)| self.obfuscated_sections.contains(**section_name))
+            .map(|(_, section)| {
+                let relocations: usize = section
+                    .nodes
+                    .iter()
+                    .map(|node| node.relocations().len())
+                    .sum();
+                relocations + 2
+            })
+            .sum();
+
+        let metadata_data: &mut [u8] = self.arena.alloc_slice_fill_default(
+            OBFUSCATION_HEADER_SIZE
+                + self.obfuscation_key.len().next_multiple_of(4)
+                + OBFUSCATION_RANGE_SIZE * max_ranges,
+        );
+
+        let metadata_section = self.arena.alloc_with(|| {
+            SectionNode::new(
+                OBFUSCATION_SECTION,
+                SectionNodeCharacteristics::CntInitializedData
+                    | SectionNodeCharacteristics::MemRead
+                    | SectionNodeCharacteristics::Align4Bytes,
+                SectionNodeData::Initialized(metadata_data),
+                0,
+                self.root_coff,
+            )
+        });
+
+        let metadata_symbol = self.arena.alloc_with(|| {
+            SymbolNode::new(
+                "__obfuscation_metadata",
+                SymbolNodeStorageClass::External,
+                false,
+                SymbolNodeType::Value(0),
+            )
+        });
+
+        let metadata_definition = self.arena.alloc_with(|| {
+            Edge::new(
+                metadata_symbol,
+                metadata_section,
+                DefinitionEdgeWeight::new(0, None),
+            )
+        });
+
+        metadata_symbol.definitions().push_back(metadata_definition);
+        metadata_section
+            .definitions()
+            .push_back(metadata_definition);
+
+        self.sections
+            .entry(OBFUSCATION_SECTION)
+            .or_default()
+            .nodes
+            .push(metadata_section);
+
+        metadata_section
+    }
+
+    /// XORs the obfuscated sections in the built COFF and writes the ranges
+    /// to the metadata section.
+    ///
+    /// The bytes for the output relocations and the checksum table are
+    /// skipped.
+    fn write_obfuscation(
+        &self,
+        metadata_section: &SectionNode<'arena, 'data>,
+        checksum_table: Option<&SectionNode<'arena, 'data>>,
+        built_coff: &mut [u8],
+    ) {
+        let key = self.obfuscation_key.as_slice();
+        let mut ranges = Vec::new();
+
+        for (section_index, (section_name, section)) in self.sections.iter().enumerate() {
+            if !self.obfuscated_sections.contains(*section_name) {
+                continue;
+            }
+
+            if *section_name == OBFUSCATION_SECTION
+                || section.header.characteristics & IMAGE_SCN_CNT_UNINITIALIZED_DATA != 0
+            {
+                warn!(
+                    section = section_name;
+                    "section '{section_name}' does not have contents which can be obfuscated"
+                );
+                continue;
+            }
+
+            let mut skipped = Vec::new();
+            for section_node in &section.nodes {
+                if checksum_table.is_some_and(|table| std::ptr::eq(table, *section_node)) {
+                    skipped.push((
+                        section_node.virtual_address(),
+                        section_node.data().len() as u32,
+                    ));
+                    continue;
+                }
+
+                // Only flattened relocations are left out of the output.
+                // Keep the fields of the other relocations intact
+                for reloc in section_node.relocations() {
+                    let flattened = reloc
+                        .target()
+                        .definitions()
+                        .iter()
+                        .find(|definition| !definition.target().is_discarded())
+                        .is_some_and(|definition| {
+                            self.is_flattened_relocation(
+                                section_name,
+                                reloc.weight(),
+                                definition.target(),
+                            )
+                        });
+
+                    if !flattened {
+                        skipped.push((
+                            section_node.virtual_address() + reloc.weight().address(),
+                            relocation_size(self.machine, reloc.weight().typ()),
+                        ));
+                    }
+                }
+            }
+
+            skipped.sort_unstable();
+
+            let section_number = section_index as u32 + 1;
+            let data_start = section.header.pointer_to_raw_data as usize;
+            let section_data =
+                &mut built_coff[data_start..data_start + section.header.size_of_raw_data as usize];
+
+            let mut offset = 0;
+            for (skipped_start, skipped_size) in
+                skipped.into_iter().chain([(section_data.len() as u32, 0)])
+            {
+                if skipped_start > offset {
+                    for (idx, byte) in section_data[offset as usize..skipped_start as usize]
+                        .iter_mut()
+                        .enumerate()
+                    {
+                        *byte ^= key[(offset as usize + idx) % key.len()];
+                    }
+
+                    ranges.push((section_number, offset, skipped_start - offset));
+                }
+
+                offset = offset.max(skipped_start + skipped_size);
+            }
+        }
+
+        let mut metadata = Vec::with_capacity(metadata_section.data().len());
+        metadata.extend_from_slice(OBFUSCATION_SIGNATURE);
+        metadata.extend_from_slice(&1u16.to_le_bytes());
+        metadata.extend_from_slice(&(key.len() as u16).to_le_bytes());
+        metadata.extend_from_slice(&(ranges.len() as u32).to_le_bytes());
+        metadata.extend_from_slice(key);
+        metadata.resize(metadata.len().next_multiple_of(4), 0);
+
+        for (section_number, offset, len) in ranges {
+            metadata.extend_from_slice(&section_number.to_le_bytes());
+            metadata.extend_from_slice(&offset.to_le_bytes());
+            metadata.extend_from_slice(&len.to_le_bytes());
+        }
+
+        let metadata_output = self
+            .sections
+            .get(OBFUSCATION_SECTION)
+            .unwrap_or_else(|| unreachable!("obfuscation metadata section was discarded"));
+        let metadata_start = metadata_output.header.pointer_to_raw_data as usize
+            + metadata_section.virtual_address() as usize;
+        built_coff[metadata_start..metadata_start + metadata.len()].copy_from_slice(&metadata);
+    }
+
+    /// Adds the zeroed section checksum table to the `.rdata` section.
+    fn add_checksum_table(&mut self) -> &'arena SectionNode<'arena, 'data> {
+        // Create the output section first so that it has an entry
+        let section_count = {
+            self.sections.entry(".rdata").or_default();
+            self.sections.len()
+        };
+
+        let table_data: &mut [u8] = self
+            .arena
+            .alloc_slice_fill_default(CHECKSUM_HEADER_SIZE + CHECKSUM_ENTRY_SIZE * section_count);
+
+        let table_section = self.arena.alloc_with(|| {
+            SectionNode::new(
+                ".rdata$cksum",
+                SectionNodeCharacteristics::CntInitializedData
+                    | SectionNodeCharacteristics::MemRead
+                    | SectionNodeCharacteristics::Align4Bytes,
+                SectionNodeData::Initialized(table_data),
+                0,
+                self.root_coff,
+            )
+        });
+
+        let table_symbol = self.arena.alloc_with(|| {
+            SymbolNode::new(
+                "__section_checksums",
+                SymbolNodeStorageClass::External,
+                false,
+                SymbolNodeType::Value(0),
+            )
+        });
+
+        let table_definition = self.arena.alloc_with(|| {
+            Edge::new(
+                table_symbol,
+                table_section,
+                DefinitionEdgeWeight::new(0, None),
+            )
+        });
+
+        table_symbol.definitions().push_back(table_definition);
+        table_section.definitions().push_back(table_definition);
+
+        self.sections
+            .entry(".rdata")
+            .or_default()
+            .nodes
+            .push(table_section);
+
+        table_section
+    }
+
+    /// Calculates the checksums of the output sections in the built COFF and
+    /// writes them to the checksum table.
+    fn write_checksum_table(
+        &self,
+        table_section: &SectionNode<'arena, 'data>,
+        built_coff: &mut [u8],
+    ) {
+        let mut table = Vec::with_capacity(table_section.data().len());
+        table.extend_from_slice(CHECKSUM_SIGNATURE);
+        table.extend_from_slice(&1u16.to_le_bytes());
+        table.extend_from_slice(&(self.sections.len() as u16).to_le_bytes());
+
+        // Hashing the section data only needs the output bytes so the ranges
+        // are collected up front and hashed without touching the graph
+        let ranges = Vec::from_iter(self.sections.values().map(|section| {
+            let size = section.header.size_of_raw_data;
+            let data_start = section.header.pointer_to_raw_data as usize;
+            let initialized =
+                section.header.characteristics & IMAGE_SCN_CNT_UNINITIALIZED_DATA == 0;
+            (
+                size,
+                initialized.then_some(data_start..data_start + size as usize),
+            )
+        }));
+
+        let checksum = |(_, range): &(u32, Option<std::ops::Range<usize>>)| {
+            range.clone().map_or(0, |range| {
+                let mut hasher = jamcrc::Hasher::new();
+                hasher.update(&built_coff[range]);
+                hasher.finalize()
+            })
+        };
+
+        #[cfg(feature = "parallel")]
+        let checksums: Vec<u32> = {
+            use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+            ranges.par_iter().map(checksum).collect()
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let checksums = Vec::from_iter(ranges.iter().map(checksum));
+
+        for (section_index, ((size, _), checksum)) in ranges.iter().zip(checksums).enumerate() {
+            table.extend_from_slice(&(section_index as u32 + 1).to_le_bytes());
+            table.extend_from_slice(&size.to_le_bytes());
+            table.extend_from_slice(&checksum.to_le_bytes());
+        }
+
+        let rdata = self
+            .sections
+            .get(".rdata")
+            .unwrap_or_else(|| unreachable!("checksum table output section was discarded"));
+        let table_start =
+            rdata.header.pointer_to_raw_data as usize + table_section.virtual_address() as usize;
+        built_coff[table_start..table_start + table.len()].copy_from_slice(&table);
+    }
+
+    /// Returns the opcode patches and relocation fixups for the data of the
+    /// output section sorted by their offset in the section.
+    fn fixup_worklist(
+        &self,
+        section_name: &str,
+        section: &OutputSection<'arena, 'data>,
+    ) -> Result<FixupWorklist, LinkGraphLinkError> {
+        let mut worklist = FixupWorklist::default();
+
+        for section_node in section.nodes.iter().copied() {
+            let base = section_node.virtual_address();
+
+            // Overwrite the opcodes of the rewritten instructions
+            for (_, address, opcode) in self
+                .opcode_patches
+                .iter()
+                .filter(|(patched, _, _)| std::ptr::eq(*patched, section_node))
+            {
+                worklist.patch(base + address, *opcode);
+            }
+
+            for reloc_edge in section_node.relocations() {
+                let target_symbol = reloc_edge.target();
+
+                let symbol_definition = match target_symbol
+                    .definitions()
+                    .iter()
+                    .find(|definition| !definition.target().is_discarded())
+                {
+                    Some(definition) => definition,
+                    None => continue,
+                };
+
+                let target_section = symbol_definition.target();
+                let reloc = reloc_edge.weight();
+                let kind = RelocationKind::new(self.machine, reloc.typ());
+
+                // Return an error if the relocation is out of bounds.
+                if reloc.address() as usize + kind.size() > section_node.data().len() {
+                    return Err(LinkGraphLinkError::RelocationBounds {
+                        coff_name: section_node.coff().to_string(),
+                        section: section_node.name().to_string(),
+                        address: reloc.address(),
+                        size: section_node.data().len() as u32,
+                    });
+                }
+
+                let offset = base + reloc.address();
+                let fixup = if self.is_flattened_relocation(section_name, reloc, target_section) {
+                    // Relocation targets a symbol defined in the same section.
+                    // Apply the relocation to the symbol address.
+                    let RelocationKind::Relative(extra) = kind else {
+                        unreachable!("only relative relocations are flattened");
+                    };
+
+                    let symbol_addr =
+                        symbol_definition.weight().address() + target_section.virtual_address();
+                    Fixup::relative(offset, extra, symbol_addr)
+                } else if target_symbol.is_section_symbol() {
+                    // Target symbol is a section symbol. Relocations need to
+                    // be adjusted to account for the section shift.
+                    Fixup {
+                        offset,
+                        kind,
+                        shift: target_section.virtual_address() as i64,
+                    }
+                } else if target_symbol.is_label() {
+                    // Old relocation target symbol is a label. The current
+                    // relocation points to the section symbol and the label
+                    // was discarded.
+                    // Handle this like a section symbol relocation but
+                    // shift it to point to the label's virtual address in
+                    // the section.
+                    let symbol_addr = symbol_definition.weight().address();
+                    Fixup {
+                        offset,
+                        kind,
+                        shift: target_section.virtual_address() as i64 + symbol_addr as i64,
+                    }
+                } else {
+                    // Relocation target is symbolic and does not need
+                    // updating
+                    continue;
+                };
+
+                worklist.push(fixup);
             }
         }
+
+        worklist.sort();
+        Ok(worklist)
+    }
+
+    /// Reports a cross-section relocation in the output COFF.
+    ///
+    /// Absolute 32-bit relocations in 64-bit output and relative relocations
+    /// with addends pointing outside of the target section are flagged with
+    /// a warning.
+    fn check_relocation(
+        &self,
+        section_name: &str,
+        section_node: &SectionNode<'arena, 'data>,
+        reloc: &RelocationEdge<'arena, 'data>,
+    ) -> CheckedRelocation {
+        let target_symbol = reloc.target();
+        let typ = reloc.weight().typ();
+        let address = section_node.virtual_address() + reloc.weight().address();
+
+        info!(
+            section = section_name,
+            symbol:% = target_symbol.name();
+            "{section_name}+{address:#x} -> {} ({})",
+            target_symbol.name().demangle(),
+            relocation_type_name(self.machine, typ),
+        );
+
+        let issue = if self.machine == LinkerTargetArch::Amd64 && typ == IMAGE_REL_AMD64_ADDR32 {
+            warn!(
+                coff:% = section_node.coff().file_path().display(),
+                member = section_node.coff().member_path().map(Path::to_string_lossy),
+                section:% = section_node.name(),
+                symbol:% = target_symbol.name();
+                "{}: {}+{:#x} uses an absolute 32-bit relocation to '{}' in 64-bit output",
+                section_node.coff(),
+                section_node.name(),
+                reloc.weight().address(),
+                target_symbol.name().demangle(),
+            );
+
+            Some(RelocationIssue::Absolute32)
+        } else if let Some(addend) = self.out_of_bounds_addend(section_node, reloc) {
+            warn!(
+                coff:% = section_node.coff().file_path().display(),
+                member = section_node.coff().member_path().map(Path::to_string_lossy),
+                section:% = section_node.name(),
+                symbol:% = target_symbol.name();
+                "{}: {}+{:#x} has addend {addend:#x} to '{}' pointing outside of the target section",
+                section_node.coff(),
+                section_node.name(),
+                reloc.weight().address(),
+                target_symbol.name().demangle(),
+            );
+
+            Some(RelocationIssue::AddendOutOfBounds { addend })
+        } else {
+            None
+        };
+
+        CheckedRelocation {
+            section: section_name.to_string(),
+            address,
+            symbol: target_symbol.name().to_string(),
+            typ,
+            type_name: relocation_type_name(self.machine, typ),
+            issue,
+        }
+    }
+
+    /// Returns the addend of a relative relocation if the address it points
+    /// to is outside of the section defining the target symbol.
+    fn out_of_bounds_addend(
+        &self,
+        section_node: &SectionNode<'arena, 'data>,
+        reloc: &RelocationEdge<'arena, 'data>,
+    ) -> Option<i32> {
+        let RelocationKind::Relative(_) = RelocationKind::new(self.machine, reloc.weight().typ())
+        else {
+            return None;
+        };
+
+        let SectionNodeData::Initialized(data) = section_node.data() else {
+            return None;
+        };
+
+        let field_start = reloc.weight().address() as usize;
+        let field = data.get(field_start..field_start + 4)?;
+        let addend = i32::from_le_bytes(field.try_into().unwrap());
+
+        let definition = reloc
+            .target()
+            .definitions()
+            .iter()
+            .find(|definition| !definition.target().is_discarded())?;
+
+        let target_offset = i64::from(definition.weight().address()) + i64::from(addend);
+        let target_size = definition.target().data().len() as i64;
+        (target_offset < 0 || target_offset > target_size).then_some(addend)
     }
 
     /// Links the graph components together and builds the final COFF.
-    pub fn link(mut self) -> Result<Vec<u8>, LinkGraphLinkError> {
-        self.apply_import_thunks();
+    pub fn link(self) -> Result<Vec<u8>, LinkGraphLinkError> {
+        self.link_with_layout().map(|(linked, _)| linked)
+    }
+
+    /// Links the graph components together and builds the final COFF.
+    ///
+    /// Returns the layout of the output COFF along with the built COFF. The
+    /// layout can be passed to [`BuiltLinkGraph::set_layout`] when relinking
+    /// to preserve the ordering.
+    pub fn link_with_layout(self) -> Result<(Vec<u8>, LinkLayout), LinkGraphLinkError> {
+        self.link_timed().map(|timed| (timed.linked, timed.layout))
+    }
+
+    /// Links the graph and returns the built COFF along with the layout, the
+    /// time spent laying out the output sections and the relocation counts.
+    pub(crate) fn link_timed(mut self) -> Result<TimedLink, LinkGraphLinkError> {
+        let layout_start = Instant::now();
+
+        if self.safeseh && self.machine == LinkerTargetArch::I386 {
+            self.check_safeseh()?;
+        }
+
         self.handle_comdats();
+
+        if self.lnk_info_policy != LnkInfoPolicy::Keep {
+            self.apply_lnk_info_policy();
+        }
+
+        if self.collapse_refptrs && self.machine == LinkerTargetArch::Amd64 {
+            self.apply_collapse_refptrs();
+        }
+
+        if self.rip_relative && self.machine == LinkerTargetArch::Amd64 {
+            self.apply_rip_relative()?;
+        }
+
+        self.discard_unused_import_thunks();
+        self.remove_discarded_imports();
+        self.check_data_imports()?;
+        self.apply_delay_imports();
+        self.apply_import_thunks();
         self.allocate_commons();
 
         // Remove discarded section nodes.
@@ -501,13 +3284,22 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
         self.sections.retain(|section_name, section| {
             section.nodes.retain(|node| !node.is_discarded());
             if section.nodes.is_empty() {
-                debug!("discarding output section '{section_name}'");
+                debug!(
+                    section = section_name;
+                    "discarding output section '{section_name}'"
+                );
                 false
             } else {
                 true
             }
         });
 
+        let obfuscation_metadata =
+            (!self.obfuscated_sections.is_empty()).then(|| self.add_obfuscation_metadata());
+        let checksum_table = self.section_checksums.then(|| self.add_checksum_table());
+
+        let (mut output_layout, packed_sections) = self.apply_layout();
+
         let mut built_coff = Vec::new();
         let mut coff_writer = Writer::new(&mut built_coff);
 
@@ -531,11 +3323,69 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                 }
             }
 
+            // Check for input sections with different memory access flags
+            let first_access = section.header.characteristics & MEM_ACCESS_FLAGS;
+            if let Some(conflicting) = section
+                .nodes
+                .iter()
+                .find(|node| node.characteristics().bits() & MEM_ACCESS_FLAGS != first_access)
+            {
+                let first_coff = section
+                    .nodes
+                    .first()
+                    .map(|node| node.coff().to_string())
+                    .unwrap_or_default();
+
+                let conflicting_access = conflicting.characteristics().bits() & MEM_ACCESS_FLAGS;
+
+                match self.characteristics_policy {
+                    CharacteristicsPolicy::Strict => {
+                        return Err(LinkGraphLinkError::SectionCharacteristics {
+                            section: section_name.to_string(),
+                            first_coff,
+                            first_flags: display_access_flags(first_access),
+                            conflicting_coff: conflicting.coff().to_string(),
+                            conflicting_flags: display_access_flags(conflicting_access),
+                        });
+                    }
+                    CharacteristicsPolicy::Merge => {
+                        let merged = section.nodes.iter().fold(first_access, |flags, node| {
+                            flags | (node.characteristics().bits() & MEM_ACCESS_FLAGS)
+                        });
+
+                        warn!(
+                            section = section_name,
+                            coff:% = conflicting.coff().file_path().display(),
+                            member = conflicting.coff().member_path().map(Path::to_string_lossy);
+                            "section '{section_name}' has conflicting memory access flags: {first_coff} ({}) and {} ({}). using {}",
+                            display_access_flags(first_access),
+                            conflicting.coff(),
+                            display_access_flags(conflicting_access),
+                            display_access_flags(merged),
+                        );
+
+                        section.header.characteristics |= merged;
+                    }
+                }
+            }
+
+            // Apply the minimum alignment override if it was set
+            if self.min_section_alignment > 1 {
+                section_alignment = self.min_section_alignment;
+            }
+
+            // Default alignment for the input sections
+            let default_alignment = if self.default_section_alignment {
+                default_section_alignment(self.machine, section_name)
+            } else {
+                None
+            };
+
             // Assign virtual addresses to each section
             for node in section_nodes_iter {
                 // Include alignment needed to satisfy input section node
                 // alignment
-                if let Some(align) = node.characteristics().alignment() {
+                if let Some(align) = input_alignment(node, default_alignment) {
                     let align = align as u32;
                     section.header.size_of_raw_data =
                         section.header.size_of_raw_data.next_multiple_of(align);
@@ -543,6 +3393,9 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                 }
 
                 debug!(
+                    coff:% = node.coff().file_path().display(),
+                    member = node.coff().member_path().map(Path::to_string_lossy),
+                    section:% = node.name();
                     "{}: mapping section '{}' to '{}' at address {:#x} with size {:#x}",
                     node.coff(),
                     node.name(),
@@ -562,8 +3415,14 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
             }
         }
 
+        let layout_time = layout_start.elapsed();
+
+        if self.sections.len() > MAX_OUTPUT_SECTIONS {
+            return Err(LinkGraphLinkError::TooManySections(self.sections.len()));
+        }
+
         // Reserve section headers
-        coff_writer.reserve_section_headers(self.sections.len().try_into().unwrap());
+        coff_writer.reserve_section_headers(self.sections.len() as u16);
 
         // Reserve section data only if the data is initialized
         for section in self.sections.values_mut() {
@@ -573,8 +3432,9 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
             }
         }
 
-        // Reserve relocations skipping relocations to the same output section
-        for (section_name, section) in self.sections.iter_mut() {
+        // Count relocations skipping relocations to the same output section
+        let mut reloc_counts = Vec::with_capacity(self.sections.len());
+        for (section_name, section) in self.sections.iter() {
             let mut reloc_count = 0usize;
 
             for section_node in &section.nodes {
@@ -586,10 +3446,14 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                         .iter()
                         .find(|definition| !definition.target().is_discarded())
                     {
-                        if definition.target().name().group_name() == *section_name {
+                        if self.is_flattened_relocation(
+                            section_name,
+                            reloc.weight(),
+                            definition.target(),
+                        ) {
                             continue;
                         }
-                    } else if symbol.imports().is_empty() {
+                    } else if symbol.imports().is_empty() && !symbol.is_loader_extern() {
                         // Symbol has no imports and all definitions are in
                         // discarded sections. Return an error.
 
@@ -631,6 +3495,11 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                 }
             }
 
+            reloc_counts.push(reloc_count);
+        }
+
+        // Reserve relocations
+        for (section, reloc_count) in self.sections.values_mut().zip(reloc_counts) {
             section.header.number_of_relocations = reloc_count.try_into().unwrap();
             section.header.pointer_to_relocations = coff_writer.reserve_relocations(reloc_count);
         }
@@ -642,13 +3511,13 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
             let _ = coff_writer.reserve_aux_section();
 
             for section_node in &section.nodes {
-                // Assign table indicies to defined symbols
                 for definition in section_node.definitions() {
                     let symbol = definition.source();
 
-                    // Section symbol already reserved. Set the index to the
-                    // existing one
-                    if symbol.is_section_symbol() {
+                    // Section symbols already reserved and labels are
+                    // associated with the section symbol. Set the index to
+                    // the existing one
+                    if symbol.is_section_symbol() || symbol.is_label() {
                         symbol
                             .assign_table_index(section_symbol_index)
                             .unwrap_or_else(|v| {
@@ -657,44 +3526,25 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                                     symbol.name().demangle()
                                 )
                             });
-                    } else if symbol.is_label() {
-                        // Associate labels with the section symbol
-                        symbol
-                            .assign_table_index(section_symbol_index)
-                            .unwrap_or_else(|v| {
-                                panic!(
-                                    "symbol {} already assigned to symbol table index {v}",
-                                    symbol.name().demangle()
-                                )
-                            });
-                    } else {
-                        let _ = symbol.output_name().get_or_init(|| {
-                            coff_writer.add_name(symbol.name().as_str().as_bytes())
-                        });
-
-                        // Reserve an index for this symbol
-                        symbol
-                            .assign_table_index(coff_writer.reserve_symbol_index())
-                            .unwrap_or_else(|v| {
-                                panic!(
-                                    "symbol {} already assigned to symbol table index {v}",
-                                    symbol.name().demangle()
-                                )
-                            });
                     }
                 }
             }
-        }
-
-        // Reserve API imported symbols
-        if let Some(api_node) = self.api_node {
-            for import in api_node.imports() {
-                let symbol = import.source();
 
-                let _ = symbol
-                    .output_name()
-                    .get_or_init(|| coff_writer.add_name(symbol.name().as_str().as_bytes()));
+            // Assign table indicies to defined symbols
+            for (_, definition) in self.symbol_definitions(section) {
+                let symbol = definition.source();
+                output_layout.add_symbol(symbol.name().as_str());
+
+                let _ = symbol.output_name().get_or_init(|| {
+                    let name = symbol
+                        .output_rename()
+                        .get()
+                        .copied()
+                        .unwrap_or(symbol.name().as_str());
+                    coff_writer.add_name(name.as_bytes())
+                });
 
+                // Reserve an index for this symbol
                 symbol
                     .assign_table_index(coff_writer.reserve_symbol_index())
                     .unwrap_or_else(|v| {
@@ -703,33 +3553,58 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                             symbol.name().demangle()
                         )
                     });
+
+                if self.function_aux && symbol.function_aux().is_some() {
+                    let _ = coff_writer.reserve_symbol_index();
+                }
             }
         }
 
-        // Reserve library imported symbols
-        for library in self.library_nodes.values() {
-            for import in library.imports() {
-                let symbol = import.source();
-
-                let name = self.arena.alloc_str(&format!(
-                    "__imp_{}${}",
-                    library.name().trim_dll_suffix(),
-                    import.weight().import_name()
-                ));
-
-                let _ = symbol
-                    .output_name()
-                    .get_or_init(|| coff_writer.add_name(name.as_bytes()));
+        // Reserve absolute symbols
+        let absolute_symbols = self.absolute_symbols();
+        let absolute_symbols = Vec::from_iter(absolute_symbols.into_iter().map(|(name, value)| {
+            output_layout.add_symbol(name);
+            let _ = coff_writer.reserve_symbol_index();
+            (coff_writer.add_name(name.as_bytes()), value)
+        }));
+
+        // Reserve imported symbols
+        let import_symbols = self.import_symbols();
+        for (symbol, name) in &import_symbols {
+            output_layout.add_symbol(*name);
+
+            let _ = symbol
+                .output_name()
+                .get_or_init(|| coff_writer.add_name(name.as_bytes()));
+
+            symbol
+                .assign_table_index(coff_writer.reserve_symbol_index())
+                .unwrap_or_else(|v| {
+                    panic!(
+                        "symbol {} already assigned to symbol table index {v}",
+                        symbol.name().demangle()
+                    )
+                });
+        }
 
-                symbol
-                    .assign_table_index(coff_writer.reserve_symbol_index())
-                    .unwrap_or_else(|v| {
-                        panic!(
-                            "symbol {} already assigned to symbol table index {v}",
-                            symbol.name().demangle()
-                        )
-                    });
-            }
+        // Reserve the symbols provided by the loader
+        let loader_externs = self.loader_extern_symbols();
+        for symbol in &loader_externs {
+            let name = symbol.name().as_str();
+            output_layout.add_symbol(name);
+
+            let _ = symbol
+                .output_name()
+                .get_or_init(|| coff_writer.add_name(name.as_bytes()));
+
+            symbol
+                .assign_table_index(coff_writer.reserve_symbol_index())
+                .unwrap_or_else(|v| {
+                    panic!(
+                        "symbol {} already assigned to symbol table index {v}",
+                        symbol.name().demangle()
+                    )
+                });
         }
 
         // Finish reserving COFF data
@@ -757,9 +3632,9 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                 coff_writer.write_section_align();
 
                 let alignment_byte = if (section.header.characteristics & IMAGE_SCN_CNT_CODE) != 0 {
-                    0x90u8
+                    self.code_fill
                 } else {
-                    0x00u8
+                    self.data_fill
                 };
 
                 let mut data_written = 0;
@@ -793,6 +3668,8 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
         }
 
         // Write out the relocations skipping relocations to the same section
+        let mut relocation_counts: IndexMap<&str, BTreeMap<u16, usize>> = IndexMap::new();
+        let mut checked_relocations = Vec::new();
         for (section_name, section) in self.sections.iter() {
             for section_node in &section.nodes {
                 for reloc in section_node.relocations() {
@@ -803,11 +3680,29 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                         .iter()
                         .find(|definition| !definition.target().is_discarded())
                     {
-                        if symbol_definition.target().name().group_name() == *section_name {
+                        if self.is_flattened_relocation(
+                            section_name,
+                            reloc.weight(),
+                            symbol_definition.target(),
+                        ) {
                             continue;
                         }
                     }
 
+                    if self.check_relocations {
+                        checked_relocations.push(self.check_relocation(
+                            section_name,
+                            section_node,
+                            reloc,
+                        ));
+                    }
+
+                    *relocation_counts
+                        .entry(section_name)
+                        .or_default()
+                        .entry(reloc.weight().typ())
+                        .or_default() += 1;
+
                     coff_writer.write_relocation(Relocation {
                         virtual_address: section_node.virtual_address() + reloc.weight().address(),
                         symbol: target_symbol.table_index().unwrap_or_else(|| {
@@ -822,6 +3717,12 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
             }
         }
 
+        let mut function_aux_records = if self.function_aux {
+            self.function_aux_records()
+        } else {
+            HashMap::new()
+        };
+
         // Write out symbols defined in sections
         for (section_index, section) in self.sections.values().enumerate() {
             // Write the section symbol
@@ -844,37 +3745,12 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                 selection: 0,
             });
 
-            for section_node in &section.nodes {
-                for definition in section_node.definitions() {
-                    let symbol = definition.source();
-
-                    // Skip labels and section symbols
-                    if !symbol.is_section_symbol() && !symbol.is_label() {
-                        coff_writer.write_symbol(object::write::coff::Symbol {
-                            name: symbol.output_name().get().copied().unwrap_or_else(|| {
-                                panic!(
-                                    "symbol {} never had the name reserved in the output COFF",
-                                    symbol.name().demangle()
-                                )
-                            }),
-                            value: definition.weight().address() + section_node.virtual_address(),
-                            section_number: (section_index + 1).try_into().unwrap(),
-                            typ: match symbol.typ() {
-                                SymbolNodeType::Value(typ) => typ,
-                                _ => unreachable!(),
-                            },
-                            storage_class: symbol.storage_class().into(),
-                            number_of_aux_symbols: 0,
-                        });
-                    }
-                }
-            }
-        }
+            for (section_node, definition) in self.symbol_definitions(section) {
+                let symbol = definition.source();
+                let aux_record = symbol
+                    .table_index()
+                    .and_then(|index| function_aux_records.remove(&index));
 
-        // Write out API imported symbols
-        if let Some(api_node) = self.api_node {
-            for import in api_node.imports() {
-                let symbol = import.source();
                 coff_writer.write_symbol(object::write::coff::Symbol {
                     name: symbol.output_name().get().copied().unwrap_or_else(|| {
                         panic!(
@@ -882,145 +3758,415 @@ impl<'arena, 'data> BuiltLinkGraph<'arena, 'data> {
                             symbol.name().demangle()
                         )
                     }),
-                    value: 0,
-                    section_number: 0,
-                    typ: 0,
-                    storage_class: IMAGE_SYM_CLASS_EXTERNAL,
-                    number_of_aux_symbols: 0,
+                    value: definition.weight().address() + section_node.virtual_address(),
+                    section_number: (section_index + 1).try_into().unwrap(),
+                    typ: match symbol.typ() {
+                        SymbolNodeType::Value(typ) => typ,
+                        _ => unreachable!(),
+                    },
+                    storage_class: symbol.storage_class().into(),
+                    number_of_aux_symbols: aux_record.is_some().into(),
                 });
+
+                if let Some(aux_record) = aux_record {
+                    coff_writer.write(&aux_record);
+                }
             }
         }
 
-        // Write out library imported symbols
-        for library in self.library_nodes.values() {
-            for import in library.imports() {
-                let symbol = import.source();
-                coff_writer.write_symbol(object::write::coff::Symbol {
-                    name: symbol.output_name().get().copied().unwrap_or_else(|| {
-                        panic!(
-                            "symbol {} never had the name reserved in the output COFF",
-                            symbol.name().demangle()
-                        )
-                    }),
-                    value: 0,
-                    section_number: 0,
-                    typ: 0,
-                    storage_class: IMAGE_SYM_CLASS_EXTERNAL,
-                    number_of_aux_symbols: 0,
-                });
-            }
+        // Write out absolute symbols
+        for (name, value) in absolute_symbols {
+            coff_writer.write_symbol(object::write::coff::Symbol {
+                name,
+                value,
+                section_number: IMAGE_SYM_ABSOLUTE as u16,
+                typ: IMAGE_SYM_TYPE_NULL,
+                storage_class: IMAGE_SYM_CLASS_STATIC,
+                number_of_aux_symbols: 0,
+            });
+        }
+
+        // Write out imported symbols
+        for (symbol, _) in import_symbols {
+            coff_writer.write_symbol(object::write::coff::Symbol {
+                name: symbol.output_name().get().copied().unwrap_or_else(|| {
+                    panic!(
+                        "symbol {} never had the name reserved in the output COFF",
+                        symbol.name().demangle()
+                    )
+                }),
+                value: 0,
+                section_number: 0,
+                typ: 0,
+                storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+                number_of_aux_symbols: 0,
+            });
+        }
+
+        // Write out the symbols provided by the loader
+        for symbol in loader_externs {
+            coff_writer.write_symbol(object::write::coff::Symbol {
+                name: symbol.output_name().get().copied().unwrap_or_else(|| {
+                    panic!(
+                        "symbol {} never had the name reserved in the output COFF",
+                        symbol.name().demangle()
+                    )
+                }),
+                value: 0,
+                section_number: 0,
+                typ: 0,
+                storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+                number_of_aux_symbols: 0,
+            });
         }
 
         // Finish writing the COFF
         coff_writer.write_strtab();
 
-        // Fixup relocations
-        for section in self.sections.values() {
-            let section_data_base = section.header.pointer_to_raw_data as usize;
-            for section_node in &section.nodes {
-                let section_data_ptr = section_data_base + section_node.virtual_address() as usize;
+        // Fixup relocations. The fixups for each section are applied in order
+        // of their offset to the section data in the built COFF
+        for (section_name, section) in self.sections.iter() {
+            let worklist = self.fixup_worklist(section_name, section)?;
 
-                let section_data =
-                    &mut built_coff[section_data_ptr..section_data_ptr + section_node.data().len()];
+            if section.header.characteristics & IMAGE_SCN_CNT_UNINITIALIZED_DATA != 0 {
+                continue;
+            }
 
-                for reloc_edge in section_node.relocations() {
-                    let target_symbol = reloc_edge.target();
+            let data_start = section.header.pointer_to_raw_data as usize;
+            let section_data =
+                &mut built_coff[data_start..data_start + section.header.size_of_raw_data as usize];
+
+            worklist.apply(section_data).map_err(|offset| {
+                // Find the input section with the relocation for the error
+                let node_index = section
+                    .nodes
+                    .partition_point(|node| node.virtual_address() <= offset);
+                let section_node = section.nodes[node_index - 1];
+
+                LinkGraphLinkError::RelocationOverflow {
+                    coff_name: section_node.coff().to_string(),
+                    section: section_node.name().to_string(),
+                    address: offset - section_node.virtual_address(),
+                }
+            })?;
+        }
 
-                    let symbol_definition = match target_symbol
-                        .definitions()
-                        .iter()
-                        .find(|definition| !definition.target().is_discarded())
-                    {
-                        Some(definition) => definition,
-                        None => continue,
-                    };
+        // Obfuscate before calculating the checksums so that the checksums
+        // match the file contents
+        if let Some(metadata_section) = obfuscation_metadata {
+            self.write_obfuscation(metadata_section, checksum_table, &mut built_coff);
+        }
 
-                    let target_section = symbol_definition.target();
-                    let reloc = reloc_edge.weight();
+        if let Some(checksum_table) = checksum_table {
+            self.write_checksum_table(checksum_table, &mut built_coff);
+        }
 
-                    // Return an error if the relocation is out of bounds.
-                    if reloc.virtual_address + 4 > section_node.data().len() as u32 {
-                        return Err(LinkGraphLinkError::RelocationBounds {
-                            coff_name: section_node.coff().to_string(),
-                            section: section_node.name().to_string(),
-                            address: reloc.virtual_address,
-                            size: section_node.data().len() as u32,
-                        });
-                    }
+        let strings = if self.collect_strings {
+            self.output_strings(&built_coff)
+        } else {
+            Vec::new()
+        };
 
-                    // The relocation bounds check above checks the relocation
-                    // in the graph. This indexes into the built COFF after
-                    // everything is merged. The slice index should always
-                    // be in bounds but if it is not, there is some logic
-                    // error above. Panic with a verbose error message if that
-                    // is the case.
-
-                    let reloc_data: [u8; 4] = section_data
-                        .get(reloc.address() as usize..reloc.address() as usize + 4)
-                        .map(|data| data.try_into().unwrap_or_else(|_| unreachable!()))
-                        .unwrap_or_else(|| {
-                            unreachable!(
-                                "relocation in section '{}' is out of bounds",
-                                section_node.name()
-                            )
-                        });
+        let symbol_sizes = if self.collect_symbol_sizes {
+            self.symbol_sizes()
+        } else {
+            Vec::new()
+        };
 
-                    // Update relocations
-                    let relocated_val = if target_symbol.is_section_symbol() {
-                        // Target symbol is a section symbol. Relocations need to
-                        // be adjusted to account for the section shift.
-                        let reloc_val = u32::from_le_bytes(reloc_data);
-
-                        reloc_val
-                            .checked_add(target_section.virtual_address())
-                            .ok_or_else(|| LinkGraphLinkError::RelocationOverflow {
-                                coff_name: section_node.coff().to_string(),
-                                section: section_node.name().to_string(),
-                                address: reloc.address(),
-                            })?
-                    } else if section_node.name().group_name() == target_section.name().group_name()
-                    {
-                        // Relocation targets a symbol defined in the same section.
-                        // Apply the relocation to the symbol address.
-
-                        let reloc_addr = reloc.address() + section_node.virtual_address();
-                        let symbol_addr =
-                            symbol_definition.weight().address() + target_section.virtual_address();
-
-                        let reloc_val = u32::from_be_bytes(reloc_data);
-                        let delta = symbol_addr.wrapping_sub(reloc_addr + 4);
-                        reloc_val.wrapping_add(delta)
-                    } else if target_symbol.is_label() {
-                        // Old relocation target symbol is a label. The current
-                        // relocation points to the section symbol and the label
-                        // was discarded.
-                        // Handle this like a section symbol relocation but
-                        // shift it to point to the label's virtual address in
-                        // the section.
-                        let reloc_val = u32::from_le_bytes(reloc_data);
-                        let symbol_addr = symbol_definition.weight().address();
-
-                        reloc_val
-                            .checked_add(target_section.virtual_address())
-                            .and_then(|reloc_val| reloc_val.checked_add(symbol_addr))
-                            .ok_or_else(|| LinkGraphLinkError::RelocationOverflow {
-                                coff_name: section_node.coff().to_string(),
-                                section: section_node.name().to_string(),
-                                address: reloc.address(),
-                            })?
-                    } else {
-                        // Relocation target is symbolic and does not need
-                        // updating
-                        continue;
-                    };
+        let relocation_counts = relocation_counts
+            .into_iter()
+            .flat_map(|(section, counts)| {
+                counts.into_iter().map(move |(typ, count)| RelocationCount {
+                    section: section.to_string(),
+                    typ,
+                    type_name: relocation_type_name(self.machine, typ),
+                    count,
+                })
+            })
+            .collect();
+
+        Ok(TimedLink {
+            linked: built_coff,
+            layout: output_layout,
+            layout_time,
+            relocation_counts,
+            checked_relocations,
+            strings,
+            symbol_sizes,
+            packed_sections,
+        })
+    }
+}
 
-                    // Write the new reloc
-                    section_data[reloc.address() as usize..reloc.address() as usize + 4]
-                        .copy_from_slice(&relocated_val.to_le_bytes());
-                }
-            }
+/// The number of relocations of a type written to an output section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelocationCount {
+    /// The name of the output section.
+    pub section: String,
+
+    /// The relocation type.
+    pub typ: u16,
+
+    /// The name of the relocation type or `unknown`.
+    pub type_name: &'static str,
+
+    /// The number of relocations.
+    pub count: usize,
+}
+
+/// A cross-section relocation written to the output reported by the
+/// relocation check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckedRelocation {
+    /// The name of the output section.
+    pub section: String,
+
+    /// The offset of the relocation in the output section.
+    pub address: u32,
+
+    /// The name of the target symbol.
+    pub symbol: String,
+
+    /// The relocation type.
+    pub typ: u16,
+
+    /// The name of the relocation type or `unknown`.
+    pub type_name: &'static str,
+
+    /// The suspicious pattern found in the relocation.
+    pub issue: Option<RelocationIssue>,
+}
+
+/// A suspicious relocation pattern flagged by the relocation check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationIssue {
+    /// An absolute 32-bit relocation in 64-bit output.
+    Absolute32,
+
+    /// A relative relocation with an addend pointing outside of the section
+    /// defining the target symbol.
+    AddendOutOfBounds { addend: i32 },
+}
+
+/// The estimated size of a symbol defined in the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolSize {
+    /// The name of the symbol.
+    pub name: String,
+
+    /// The name of the output section.
+    pub section: String,
+
+    /// The offset of the symbol in the output section.
+    pub address: u32,
+
+    /// The distance to the next symbol or the end of the input section.
+    pub size: u32,
+
+    /// Whether the symbol is in a code section.
+    pub code: bool,
+
+    /// The input COFF defining the symbol.
+    pub coff: String,
+}
+
+/// An output section with the input sections ordered by alignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedSection {
+    /// The name of the output section.
+    pub section: String,
+
+    /// The number of padding bytes with the original order.
+    pub padding_before: u32,
+
+    /// The number of padding bytes after ordering the input sections.
+    pub padding_after: u32,
+}
+
+impl PackedSection {
+    /// Returns the number of padding bytes saved.
+    pub fn saved(&self) -> u32 {
+        self.padding_before - self.padding_after
+    }
+}
+
+/// The built COFF along with the information collected while linking.
+pub(crate) struct TimedLink {
+    /// The built COFF.
+    pub linked: Vec<u8>,
+
+    /// The layout of the output COFF.
+    pub layout: LinkLayout,
+
+    /// The time spent laying out the output sections.
+    pub layout_time: Duration,
+
+    /// The relocations in the output COFF ordered by output section and
+    /// relocation type.
+    pub relocation_counts: Vec<RelocationCount>,
+
+    /// The cross-section relocations in the output if they were checked.
+    pub checked_relocations: Vec<CheckedRelocation>,
+
+    /// The printable strings in the output data sections if they were
+    /// collected.
+    pub strings: Vec<OutputString>,
+
+    /// The estimated sizes of the defined symbols if they were collected.
+    pub symbol_sizes: Vec<SymbolSize>,
+
+    /// The output sections with the input sections ordered by alignment.
+    pub packed_sections: Vec<PackedSection>,
+}
+
+/// Returns the name of the relocation type for the target architecture.
+fn relocation_type_name(machine: LinkerTargetArch, typ: u16) -> &'static str {
+    use object::pe::*;
+
+    match machine {
+        LinkerTargetArch::Amd64 => match typ {
+            IMAGE_REL_AMD64_ABSOLUTE => "IMAGE_REL_AMD64_ABSOLUTE",
+            IMAGE_REL_AMD64_ADDR64 => "IMAGE_REL_AMD64_ADDR64",
+            IMAGE_REL_AMD64_ADDR32 => "IMAGE_REL_AMD64_ADDR32",
+            IMAGE_REL_AMD64_ADDR32NB => "IMAGE_REL_AMD64_ADDR32NB",
+            IMAGE_REL_AMD64_REL32 => "IMAGE_REL_AMD64_REL32",
+            IMAGE_REL_AMD64_REL32_1 => "IMAGE_REL_AMD64_REL32_1",
+            IMAGE_REL_AMD64_REL32_2 => "IMAGE_REL_AMD64_REL32_2",
+            IMAGE_REL_AMD64_REL32_3 => "IMAGE_REL_AMD64_REL32_3",
+            IMAGE_REL_AMD64_REL32_4 => "IMAGE_REL_AMD64_REL32_4",
+            IMAGE_REL_AMD64_REL32_5 => "IMAGE_REL_AMD64_REL32_5",
+            IMAGE_REL_AMD64_SECTION => "IMAGE_REL_AMD64_SECTION",
+            IMAGE_REL_AMD64_SECREL => "IMAGE_REL_AMD64_SECREL",
+            IMAGE_REL_AMD64_SECREL7 => "IMAGE_REL_AMD64_SECREL7",
+            IMAGE_REL_AMD64_TOKEN => "IMAGE_REL_AMD64_TOKEN",
+            IMAGE_REL_AMD64_SREL32 => "IMAGE_REL_AMD64_SREL32",
+            IMAGE_REL_AMD64_PAIR => "IMAGE_REL_AMD64_PAIR",
+            IMAGE_REL_AMD64_SSPAN32 => "IMAGE_REL_AMD64_SSPAN32",
+            _ => "unknown",
+        },
+        LinkerTargetArch::I386 => match typ {
+            IMAGE_REL_I386_ABSOLUTE => "IMAGE_REL_I386_ABSOLUTE",
+            IMAGE_REL_I386_DIR16 => "IMAGE_REL_I386_DIR16",
+            IMAGE_REL_I386_REL16 => "IMAGE_REL_I386_REL16",
+            IMAGE_REL_I386_DIR32 => "IMAGE_REL_I386_DIR32",
+            IMAGE_REL_I386_DIR32NB => "IMAGE_REL_I386_DIR32NB",
+            IMAGE_REL_I386_SEG12 => "IMAGE_REL_I386_SEG12",
+            IMAGE_REL_I386_SECTION => "IMAGE_REL_I386_SECTION",
+            IMAGE_REL_I386_SECREL => "IMAGE_REL_I386_SECREL",
+            IMAGE_REL_I386_TOKEN => "IMAGE_REL_I386_TOKEN",
+            IMAGE_REL_I386_SECREL7 => "IMAGE_REL_I386_SECREL7",
+            IMAGE_REL_I386_REL32 => "IMAGE_REL_I386_REL32",
+            _ => "unknown",
+        },
+    }
+}
+
+/// Returns the number of bytes patched by the relocation type.
+fn relocation_size(machine: LinkerTargetArch, typ: u16) -> u32 {
+    use object::pe::*;
+
+    match (machine, typ) {
+        (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_ABSOLUTE)
+        | (LinkerTargetArch::I386, IMAGE_REL_I386_ABSOLUTE) => 0,
+        (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_ADDR64) => 8,
+        (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_SECTION)
+        | (
+            LinkerTargetArch::I386,
+            IMAGE_REL_I386_SECTION | IMAGE_REL_I386_DIR16 | IMAGE_REL_I386_REL16,
+        ) => 2,
+        (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_SECREL7)
+        | (LinkerTargetArch::I386, IMAGE_REL_I386_SECREL7) => 1,
+        _ => 4,
+    }
+}
+
+/// Returns the default minimum alignment for input sections in the output
+/// section.
+fn default_section_alignment(machine: LinkerTargetArch, section_name: &str) -> Option<usize> {
+    match (machine, section_name) {
+        // MSVC code may use aligned SSE instructions for constants
+        (LinkerTargetArch::Amd64, ".text" | ".rdata") => Some(16),
+        _ => None,
+    }
+}
+
+/// Returns the alignment for the input section in the output section.
+fn input_alignment(node: &SectionNode<'_, '_>, default_alignment: Option<usize>) -> Option<usize> {
+    match (node.characteristics().alignment(), default_alignment) {
+        (Some(align), Some(default)) => Some(align.max(default)),
+        (align, default) => align.or(default),
+    }
+}
+
+/// Returns the number of padding bytes needed to align the input sections
+/// placed in order.
+fn padding_size<'a, 'arena: 'a, 'data: 'arena>(
+    nodes: impl IntoIterator<Item = &'a SectionNode<'arena, 'data>>,
+    default_alignment: Option<usize>,
+) -> u32 {
+    let mut size = 0u32;
+    let mut padding = 0u32;
+
+    for node in nodes {
+        if let Some(align) = input_alignment(node, default_alignment) {
+            let aligned = size.next_multiple_of(align as u32);
+            padding += aligned - size;
+            size = aligned;
         }
 
-        Ok(built_coff)
+        size += node.data().len() as u32;
+    }
+
+    padding
+}
+
+/// Returns the [`LayoutInput`] identifiers for the input sections.
+fn layout_inputs(nodes: &[&SectionNode<'_, '_>]) -> Vec<LayoutInput> {
+    let mut ordinals: HashMap<(&CoffNode<'_>, &str), usize> = HashMap::new();
+
+    Vec::from_iter(nodes.iter().map(|node| {
+        let ordinal = ordinals
+            .entry((node.coff(), node.name().as_str()))
+            .or_default();
+        let input = LayoutInput {
+            coff: node.coff().to_string(),
+            name: node.name().as_str().to_string(),
+            ordinal: *ordinal,
+        };
+
+        *ordinal += 1;
+        input
+    }))
+}
+
+/// Returns the highest profile hit count of the symbols defined in the code
+/// section.
+fn profile_hits(profile: &LinkProfile, section: &SectionNode<'_, '_>) -> u64 {
+    if !section
+        .characteristics()
+        .contains(SectionNodeCharacteristics::CntCode)
+    {
+        return 0;
     }
+
+    section
+        .definitions()
+        .iter()
+        .map(|definition| profile.hits(definition.source().name().as_str()))
+        .max()
+        .unwrap_or_default()
+}
+
+/// Returns the sort key for a position in the layout placing items which are
+/// not in the layout last.
+fn layout_position(position: Option<usize>) -> usize {
+    position.unwrap_or(usize::MAX)
+}
+
+/// Returns the key used for ordering `IMAGE_COMDAT_SELECT_LARGEST` sections.
+fn comdat_sort_key(section: &SectionNode<'_, '_>) -> (usize, usize) {
+    (
+        section.data().len(),
+        section.characteristics().alignment().unwrap_or(0),
+    )
 }
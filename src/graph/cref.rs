@@ -0,0 +1,63 @@
+use indexmap::IndexSet;
+
+use super::{LinkGraph, node::SymbolNode};
+
+/// An external symbol along with the inputs defining and referencing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossReference {
+    /// The name of the symbol.
+    pub symbol: String,
+
+    /// The COFFs defining the symbol or the DLLs it is imported from.
+    pub definitions: Vec<String>,
+
+    /// The COFFs referencing the symbol.
+    pub references: Vec<String>,
+}
+
+impl CrossReference {
+    fn new(symbol: &SymbolNode) -> CrossReference {
+        let mut definitions = IndexSet::new();
+        for definition in symbol.definitions().iter() {
+            definitions.insert(definition.target().coff().to_string());
+        }
+
+        for import in symbol.imports().iter() {
+            definitions.insert(format!("{} (import)", import.target().name()));
+        }
+
+        let mut references = IndexSet::new();
+        for reference in symbol.references().iter() {
+            references.insert(reference.source().coff().to_string());
+        }
+
+        CrossReference {
+            symbol: symbol.name().to_string(),
+            definitions: definitions.into_iter().collect(),
+            references: references.into_iter().collect(),
+        }
+    }
+}
+
+impl LinkGraph<'_, '_> {
+    /// Returns the cross-reference table of the external symbols sorted by
+    /// name.
+    pub fn cross_references(&self) -> Vec<CrossReference> {
+        let mut cross_references = Vec::from_iter(
+            self.external_symbols
+                .values()
+                .map(|symbol| CrossReference::new(symbol)),
+        );
+
+        cross_references.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        cross_references
+    }
+
+    /// Returns the cross-reference entry for the external symbol `name` if
+    /// it is in the graph.
+    pub fn symbol_cross_reference(&self, name: &str) -> Option<CrossReference> {
+        self.external_symbols
+            .get(name)
+            .map(|symbol| CrossReference::new(symbol))
+    }
+}
@@ -0,0 +1,114 @@
+use super::glob::glob_match;
+
+/// CRT helper symbols which compilers reference implicitly along with the
+/// guidance for linking without the CRT.
+///
+/// The patterns include the extra leading underscore added to the names on
+/// i386.
+const KNOWN_CRT_SYMBOLS: &[(&[&str], &str)] = &[
+    (
+        &["_fltused", "__fltused"],
+        "_fltused is defined by the CRT and is referenced by MSVC objects which use floating point values. \
+         Use --intrinsics=fltused to define it",
+    ),
+    (
+        PSEUDO_RELOC_SYMBOLS,
+        "the MinGW runtime pseudo-relocations are applied by the CRT startup code which BOF loaders do not run. \
+         Declare the imported data with __declspec(dllimport) instead of relying on auto-import",
+    ),
+    (
+        &["__libm_sse2_*", "___libm_sse2_*"],
+        "__libm_sse2_* are CRT math routines used by MSVC for the math functions (sin, pow, etc.) on i386. \
+         Avoid the math functions or provide implementations for them",
+    ),
+    (
+        &["__CI*", "___CI*"],
+        "_CI* are CRT math routines used by MSVC for the x87 math functions (sin, pow, etc.) on i386. \
+         Avoid the math functions or provide implementations for them",
+    ),
+    (
+        &["__ftol", "__ftol2", "__ftol2_sse"],
+        "_ftol is a CRT routine used by MSVC for floating point to integer conversions on i386. \
+         Compile with /arch:SSE2 or /QIfist to use inline conversions",
+    ),
+    (
+        &["___dto*3", "___fto*3", "___*tod3", "___*tof3"],
+        "this is a CRT routine used by MSVC for conversions between floating point values and unsigned or \
+         64-bit integers on i386. Avoid the conversions or provide implementations for them",
+    ),
+    (
+        &[
+            "__chkstk",
+            "___chkstk",
+            "___chkstk_ms",
+            "__alloca",
+            "__alloca_probe",
+        ],
+        "the stack probe helper is referenced by functions with large stack frames. \
+         Use --intrinsics=stack-probe to define it or reduce the size of the stack variables",
+    ),
+    (
+        &[
+            "__allmul",
+            "__alldiv",
+            "__allrem",
+            "__alldvrm",
+            "__allshl",
+            "__allshr",
+            "__aulldiv",
+            "__aullrem",
+            "__aulldvrm",
+            "__aullshr",
+            "___divdi3",
+            "___udivdi3",
+            "___moddi3",
+            "___umoddi3",
+        ],
+        "this is a CRT routine used for 64-bit integer arithmetic on i386. \
+         Use --intrinsics=i386-math to define it",
+    ),
+];
+
+/// Symbols for the MinGW runtime pseudo-relocations which are applied by the
+/// CRT startup code.
+const PSEUDO_RELOC_SYMBOLS: &[&str] = &[
+    "_pei386_runtime_relocator",
+    "__pei386_runtime_relocator",
+    "__RUNTIME_PSEUDO_RELOC_LIST__",
+    "___RUNTIME_PSEUDO_RELOC_LIST__",
+    "__RUNTIME_PSEUDO_RELOC_LIST_END__",
+    "___RUNTIME_PSEUDO_RELOC_LIST_END__",
+];
+
+/// Name of the section with the MinGW runtime pseudo-relocations.
+pub(super) const PSEUDO_RELOC_SECTION: &str = ".rdata_runtime_pseudo_reloc";
+
+/// Returns `true` if the symbol is part of the MinGW runtime pseudo-relocation
+/// support.
+pub(super) fn is_pseudo_reloc_symbol(name: &str) -> bool {
+    PSEUDO_RELOC_SYMBOLS.contains(&name)
+}
+
+/// Returns the guidance for an undefined symbol if it is a known CRT helper.
+pub(super) fn crt_symbol_help(name: &str) -> Option<&'static str> {
+    KNOWN_CRT_SYMBOLS
+        .iter()
+        .find(|(patterns, _)| patterns.iter().any(|pattern| glob_match(pattern, name)))
+        .map(|(_, help)| *help)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::crt_symbol_help;
+
+    #[test]
+    fn known_symbols() {
+        assert!(crt_symbol_help("_fltused").is_some_and(|help| help.contains("fltused")));
+        assert!(crt_symbol_help("___libm_sse2_pow_precise").is_some());
+        assert!(crt_symbol_help("___dtoui3").is_some());
+        assert!(crt_symbol_help("__alldiv").is_some_and(|help| help.contains("i386-math")));
+        assert!(crt_symbol_help("_pei386_runtime_relocator").is_some());
+        assert!(crt_symbol_help("go").is_none());
+        assert!(crt_symbol_help("__imp_MessageBoxA").is_none());
+    }
+}
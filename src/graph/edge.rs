@@ -1,6 +1,7 @@
 use std::{cell::Cell, marker::PhantomData};
 
 use super::node::SymbolName;
+use crate::linkobject::import::ImportType;
 
 use __private::SealedTrait;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
@@ -43,6 +44,10 @@ where
     /// The number of edges in the list.
     size: Cell<usize>,
 
+    /// Whether an [`EdgeListCursor`] for this list exists.
+    #[cfg(debug_assertions)]
+    cursor_active: Cell<bool>,
+
     /// The traversal type for this edge list.
     _traversal: PhantomData<Tr>,
 }
@@ -58,10 +63,22 @@ where
             head: Cell::new(None),
             tail: Cell::new(None),
             size: Cell::new(0),
+            #[cfg(debug_assertions)]
+            cursor_active: Cell::new(false),
             _traversal: PhantomData,
         }
     }
 
+    /// Checks that the list is not being modified while a cursor exists.
+    #[inline]
+    fn debug_assert_no_cursor(&self) {
+        #[cfg(debug_assertions)]
+        assert!(
+            !self.cursor_active.get(),
+            "edge list modified while a cursor is active"
+        );
+    }
+
     /// Returns the number of entries in this [`EdgeList`].
     #[inline]
     pub fn len(&self) -> usize {
@@ -92,9 +109,39 @@ where
         EdgeListIter((self.head.get(), PhantomData))
     }
 
+    /// Returns an [`EdgeListCursor`] starting at the first edge for removing
+    /// edges while traversing the list.
+    ///
+    /// # Panics
+    /// Panics in debug builds if another cursor for the list exists.
+    pub(super) fn cursor_front(&self) -> EdgeListCursor<'_, 'arena, Source, Target, Weight, Tr> {
+        self.debug_assert_no_cursor();
+
+        #[cfg(debug_assertions)]
+        self.cursor_active.set(true);
+
+        EdgeListCursor {
+            list: self,
+            prev: None,
+            current: self.head.get(),
+        }
+    }
+
     /// Adds an edge to this list with the specified weight and linked to the
     /// target node.
+    ///
+    /// The edge must not be linked into another list of the same traversal
+    /// type. Edges are unlinked when they are removed with
+    /// [`EdgeList::pop_front`], [`EdgeList::take`], [`EdgeList::clear`] or
+    /// [`EdgeListCursor::remove_current`].
     pub(super) fn push_back(&self, edge: &'arena Edge<'arena, Source, Target, Weight>) {
+        self.debug_assert_no_cursor();
+        debug_assert!(
+            edge.next_node().get().is_none()
+                && !self.tail.get().is_some_and(|tail| std::ptr::eq(tail, edge)),
+            "edge is already linked into a list"
+        );
+
         if let Some(tail_node) = self.tail.get() {
             tail_node.next_node().set(Some(edge));
             self.tail.set(Some(edge));
@@ -111,6 +158,7 @@ where
     /// # Note
     /// This will leak the removed edge.
     pub(super) fn pop_front(&self) -> Option<&'arena Edge<'arena, Source, Target, Weight>> {
+        self.debug_assert_no_cursor();
         let removed_edge = self.head.get()?;
         let size = self.size.get().saturating_sub(1);
 
@@ -123,14 +171,125 @@ where
         Some(removed_edge)
     }
 
-    /// Removes all of the nodes from the edge list.
+    /// Retains only the edges in the list where the predicate returns `true`.
+    ///
+    /// The edges are unlinked before they are passed to the predicate so
+    /// that the removed edges can be added to another list.
     ///
     /// # Note
-    /// This does not deallocate the edges since they are handled by the arena.
-    pub(super) fn clear(&self) {
+    /// This will leak the removed edges.
+    pub(super) fn retain(
+        &self,
+        mut f: impl FnMut(&'arena Edge<'arena, Source, Target, Weight>) -> bool,
+    ) {
+        for edge in self.take() {
+            if f(edge) {
+                self.push_back(edge);
+            }
+        }
+    }
+
+    /// Removes all of the edges from the list and returns them.
+    ///
+    /// The returned edges are unlinked so that they can be added to another
+    /// list.
+    pub(super) fn take(&self) -> Vec<&'arena Edge<'arena, Source, Target, Weight>> {
+        self.debug_assert_no_cursor();
+        let edges = Vec::from_iter(self.iter());
+
+        for edge in &edges {
+            edge.next_node().set(None);
+        }
+
         self.head.set(None);
         self.tail.set(None);
         self.size.set(0);
+        edges
+    }
+
+    /// Removes all of the edges from the edge list.
+    ///
+    /// The removed edges are unlinked so that they can be added to another
+    /// list.
+    ///
+    /// # Note
+    /// This does not deallocate the edges since they are handled by the arena.
+    pub(super) fn clear(&self) {
+        self.take();
+    }
+}
+
+/// A cursor over an [`EdgeList`] for removing edges while traversing it.
+///
+/// The list cannot be modified through other means while the cursor exists.
+/// This is checked with assertions in debug builds.
+pub(super) struct EdgeListCursor<'list, 'arena, Source, Target, Weight, Tr: EdgeListTraversal>
+where
+    Edge<'arena, Source, Target, Weight>: EdgeListEntry<'arena, Source, Target, Weight, Tr>,
+{
+    /// The list being traversed.
+    list: &'list EdgeList<'arena, Source, Target, Weight, Tr>,
+
+    /// The edge before the current edge.
+    prev: Option<&'arena Edge<'arena, Source, Target, Weight>>,
+
+    /// The current edge or `None` if the cursor is past the end of the list.
+    current: Option<&'arena Edge<'arena, Source, Target, Weight>>,
+}
+
+impl<'arena, Source, Target, Weight, Tr: EdgeListTraversal>
+    EdgeListCursor<'_, 'arena, Source, Target, Weight, Tr>
+where
+    Edge<'arena, Source, Target, Weight>: EdgeListEntry<'arena, Source, Target, Weight, Tr>,
+{
+    /// Returns the edge at the cursor or `None` if the cursor is past the end
+    /// of the list.
+    #[inline]
+    pub fn current(&self) -> Option<&'arena Edge<'arena, Source, Target, Weight>> {
+        self.current
+    }
+
+    /// Moves the cursor to the next edge.
+    #[inline]
+    pub fn move_next(&mut self) {
+        if let Some(current) = self.current {
+            self.prev = Some(current);
+            self.current = current.next_node().get();
+        }
+    }
+
+    /// Unlinks the edge at the cursor from the list and moves the cursor to
+    /// the next edge.
+    ///
+    /// # Note
+    /// This will leak the removed edge.
+    pub fn remove_current(&mut self) -> Option<&'arena Edge<'arena, Source, Target, Weight>> {
+        let removed = self.current?;
+        let next = removed.next_node().take();
+
+        match self.prev {
+            Some(prev) => prev.next_node().set(next),
+            None => self.list.head.set(next),
+        }
+
+        if next.is_none() {
+            self.list.tail.set(self.prev);
+        }
+
+        self.list.size.set(self.list.size.get() - 1);
+        self.current = next;
+        Some(removed)
+    }
+}
+
+impl<'arena, Source, Target, Weight, Tr: EdgeListTraversal> Drop
+    for EdgeListCursor<'_, 'arena, Source, Target, Weight, Tr>
+where
+    Edge<'arena, Source, Target, Weight>: EdgeListEntry<'arena, Source, Target, Weight, Tr>,
+{
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        self.list.cursor_active.set(false);
     }
 }
 
@@ -241,6 +400,14 @@ impl<'arena, S, T, W> Edge<'arena, S, T, W> {
         self.source_node.replace(source_node);
     }
 
+    /// Replaces the target node joined to this edge. The edge must be removed
+    /// from the target node before it can be replaced.
+    #[inline]
+    pub(super) fn replace_target(&self, target_node: &'arena T) {
+        debug_assert!(self.next_incoming.get().is_none());
+        self.target_node.replace(target_node);
+    }
+
     /// Returns a reference to the source node joined to this edge.
     #[inline]
     pub fn source(&self) -> &'arena S {
@@ -261,7 +428,7 @@ impl<'arena, S, T, W> Edge<'arena, S, T, W> {
 
     /// Returns a mutable reference to the edge weight
     #[inline]
-    pub fn weight_mut(&mut self) -> &mut W {
+    pub(crate) fn weight_mut(&mut self) -> &mut W {
         &mut self.weight
     }
 }
@@ -287,6 +454,7 @@ impl<'arena, Source, Target, Weight> EdgeListEntry<'arena, Source, Target, Weigh
 }
 
 /// The weight for a definition edge.
+#[derive(Debug)]
 pub struct DefinitionEdgeWeight {
     /// The virtual address for the definition.
     virtual_address: Cell<u32>,
@@ -317,7 +485,7 @@ impl DefinitionEdgeWeight {
     ///
     /// Used for assigning addresses to COMMON symbols.
     #[inline]
-    pub fn set_address(&self, val: u32) {
+    pub(crate) fn set_address(&self, val: u32) {
         self.virtual_address.set(val);
     }
 
@@ -346,45 +514,63 @@ pub enum ComdatSelection {
 }
 
 /// The weight for a relocation edge.
+#[derive(Debug)]
 pub struct RelocationEdgeWeight {
     /// The virtual address of the relocation.
-    pub(super) virtual_address: u32,
+    virtual_address: Cell<u32>,
 
     /// The relocation type.
-    typ: u16,
+    typ: Cell<u16>,
 }
 
 impl RelocationEdgeWeight {
     #[inline]
     pub(super) fn new(virtual_address: u32, typ: u16) -> RelocationEdgeWeight {
         Self {
-            virtual_address,
-            typ,
+            virtual_address: Cell::new(virtual_address),
+            typ: Cell::new(typ),
         }
     }
 
     #[inline]
     pub fn address(&self) -> u32 {
-        self.virtual_address
+        self.virtual_address.get()
     }
 
     #[inline]
     pub fn typ(&self) -> u16 {
-        self.typ
+        self.typ.get()
+    }
+
+    /// Replaces the address and type of the relocation.
+    ///
+    /// Used when the instruction with the relocation is rewritten.
+    #[inline]
+    pub(crate) fn rewrite(&self, virtual_address: u32, typ: u16) {
+        self.virtual_address.set(virtual_address);
+        self.typ.set(typ);
     }
 }
 
 /// The weight for an import edge.
+#[derive(Debug)]
 pub struct ImportEdgeWeight<'data> {
     /// The name to import the symbol as.
     import_name: SymbolName<'data>,
+
+    /// The type of import.
+    import_type: ImportType,
 }
 
 impl<'data> ImportEdgeWeight<'data> {
     #[inline]
-    pub(super) fn new(import_name: impl Into<SymbolName<'data>>) -> ImportEdgeWeight<'data> {
+    pub(super) fn new(
+        import_name: impl Into<SymbolName<'data>>,
+        import_type: ImportType,
+    ) -> ImportEdgeWeight<'data> {
         Self {
             import_name: import_name.into(),
+            import_type,
         }
     }
 
@@ -392,6 +578,12 @@ impl<'data> ImportEdgeWeight<'data> {
     pub fn import_name(&self) -> SymbolName<'data> {
         self.import_name
     }
+
+    /// Returns the type of import from the import library.
+    #[inline]
+    pub fn import_type(&self) -> ImportType {
+        self.import_type
+    }
 }
 
 /// The weight for a COMDAT associative section edge.
@@ -400,3 +592,102 @@ pub struct AssociativeSectionEdgeWeight;
 mod __private {
     pub trait SealedTrait {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Edge, EdgeList, OutgoingEdges};
+
+    type TestEdge<'arena> = Edge<'arena, u32, u32, u32>;
+    type TestEdgeList<'arena> = EdgeList<'arena, u32, u32, u32, OutgoingEdges>;
+
+    fn weights(list: &TestEdgeList<'_>) -> Vec<u32> {
+        Vec::from_iter(list.iter().map(|edge| *edge.weight()))
+    }
+
+    #[test]
+    fn cursor_remove() {
+        let arena = bumpalo::Bump::new();
+        let list = TestEdgeList::new();
+        for weight in 0..5 {
+            list.push_back(arena.alloc(TestEdge::new(&0, &0, weight)));
+        }
+
+        // Remove the head, a middle edge and the tail
+        let mut cursor = list.cursor_front();
+        while let Some(edge) = cursor.current() {
+            if *edge.weight() % 2 == 0 {
+                let removed = cursor.remove_current().unwrap();
+                assert!(std::ptr::eq(removed, edge));
+            } else {
+                cursor.move_next();
+            }
+        }
+        drop(cursor);
+
+        assert_eq!(weights(&list), [1, 3]);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.back().map(|edge| *edge.weight()), Some(3));
+
+        // The tail is updated for the next edge
+        list.push_back(arena.alloc(TestEdge::new(&0, &0, 5)));
+        assert_eq!(weights(&list), [1, 3, 5]);
+    }
+
+    #[test]
+    fn cursor_remove_all() {
+        let arena = bumpalo::Bump::new();
+        let list = TestEdgeList::new();
+        for weight in 0..3 {
+            list.push_back(arena.alloc(TestEdge::new(&0, &0, weight)));
+        }
+
+        let mut cursor = list.cursor_front();
+        while cursor.remove_current().is_some() {}
+        drop(cursor);
+
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+        assert!(list.back().is_none());
+    }
+
+    #[test]
+    fn cleared_edges_are_unlinked() {
+        let arena = bumpalo::Bump::new();
+        let first = TestEdgeList::new();
+        let edges =
+            Vec::from_iter((0..3).map(|weight| &*arena.alloc(TestEdge::new(&0, &0, weight))));
+        for edge in &edges {
+            first.push_back(edge);
+        }
+
+        first.clear();
+
+        // Relinking the first edge only adds that edge
+        let second = TestEdgeList::new();
+        second.push_back(edges[0]);
+        assert_eq!(weights(&second), [0]);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "edge list modified while a cursor is active")]
+    fn modified_with_active_cursor() {
+        let arena = bumpalo::Bump::new();
+        let list = TestEdgeList::new();
+        list.push_back(arena.alloc(TestEdge::new(&0, &0, 0)));
+
+        let _cursor = list.cursor_front();
+        list.pop_front();
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "edge is already linked into a list")]
+    fn push_linked_edge() {
+        let arena = bumpalo::Bump::new();
+        let list = TestEdgeList::new();
+        let edge = &*arena.alloc(TestEdge::new(&0, &0, 0));
+        list.push_back(edge);
+        list.push_back(edge);
+    }
+}
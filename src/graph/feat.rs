@@ -0,0 +1,73 @@
+//! Merging of the `@feat.00` feature flags.
+
+use crate::linker::LinkerTargetArch;
+
+/// Name of the absolute symbol holding the feature flags for a COFF.
+pub const FEAT_SYMBOL: &str = "@feat.00";
+
+/// The COFF is compatible with safe exception handlers (`/SAFESEH`).
+pub const FEAT_SAFESEH: u32 = 0x1;
+
+/// The COFF was compiled with control flow guard (`/guard:cf`).
+pub const FEAT_GUARD_CF: u32 = 0x800;
+
+/// The COFF was compiled with EH continuation metadata (`/guard:ehcont`).
+pub const FEAT_GUARD_EHCONT: u32 = 0x4000;
+
+/// Returns the feature flags which are only set in the output if every input
+/// COFF has the feature.
+fn required_features(machine: LinkerTargetArch) -> u32 {
+    match machine {
+        // Safe exception handler tables are only used on i386
+        LinkerTargetArch::I386 => FEAT_SAFESEH | FEAT_GUARD_CF | FEAT_GUARD_EHCONT,
+        LinkerTargetArch::Amd64 => FEAT_GUARD_CF | FEAT_GUARD_EHCONT,
+    }
+}
+
+/// Merges the feature flags from each input COFF.
+///
+/// Features describing compatibility with the whole image are only kept if
+/// all inputs have them. The remaining features are kept if any input has
+/// them. COFFs without a `@feat.00` symbol should be passed in as `0`.
+pub fn merge_features(machine: LinkerTargetArch, features: impl IntoIterator<Item = u32>) -> u32 {
+    let required = required_features(machine);
+
+    let (all, any) = features
+        .into_iter()
+        .fold((u32::MAX, 0), |(all, any), flags| {
+            (all & flags, any | flags)
+        });
+
+    if all == u32::MAX && any == 0 {
+        return 0;
+    }
+
+    (all & required) | (any & !required)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::linker::LinkerTargetArch;
+
+    use super::{FEAT_GUARD_CF, FEAT_SAFESEH, merge_features};
+
+    #[test]
+    fn safeseh_requires_all() {
+        let merged = merge_features(LinkerTargetArch::I386, [FEAT_SAFESEH, 0x10]);
+        assert_eq!(merged, 0x10);
+
+        let merged = merge_features(LinkerTargetArch::I386, [FEAT_SAFESEH, FEAT_SAFESEH | 0x10]);
+        assert_eq!(merged, FEAT_SAFESEH | 0x10);
+    }
+
+    #[test]
+    fn amd64_safeseh_any() {
+        let merged = merge_features(LinkerTargetArch::Amd64, [FEAT_SAFESEH | FEAT_GUARD_CF, 0]);
+        assert_eq!(merged, FEAT_SAFESEH);
+    }
+
+    #[test]
+    fn no_inputs() {
+        assert_eq!(merge_features(LinkerTargetArch::I386, []), 0);
+    }
+}
@@ -0,0 +1,432 @@
+use object::pe::{
+    IMAGE_REL_AMD64_ADDR64, IMAGE_REL_AMD64_REL32, IMAGE_REL_AMD64_REL32_1,
+    IMAGE_REL_AMD64_REL32_2, IMAGE_REL_AMD64_REL32_3, IMAGE_REL_AMD64_REL32_4,
+    IMAGE_REL_AMD64_REL32_5, IMAGE_REL_AMD64_SECREL, IMAGE_REL_AMD64_SECREL7,
+    IMAGE_REL_AMD64_SECTION, IMAGE_REL_I386_REL32, IMAGE_REL_I386_SECREL, IMAGE_REL_I386_SECREL7,
+    IMAGE_REL_I386_SECTION,
+};
+
+use crate::linker::LinkerTargetArch;
+
+/// How the value for a relocation is stored in the section data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RelocationKind {
+    /// A signed 32-bit displacement relative to the end of the instruction.
+    ///
+    /// The value is the number of bytes between the end of the displacement
+    /// and the end of the instruction (`IMAGE_REL_AMD64_REL32_1` through
+    /// `IMAGE_REL_AMD64_REL32_5`).
+    Relative(u32),
+
+    /// A 64-bit address.
+    Address64,
+
+    /// A 32-bit offset from the start of the section with the symbol.
+    SectionRelative,
+
+    /// A 7-bit offset from the start of the section with the symbol.
+    SectionRelative7,
+
+    /// A 16-bit section index of the section with the symbol.
+    ///
+    /// The value is filled in with the output section number when the
+    /// relocation is applied and does not depend on the symbol address.
+    SectionIndex,
+
+    /// A 32-bit address or offset.
+    Value32,
+}
+
+impl RelocationKind {
+    /// Returns the kind of the relocation type for the architecture.
+    pub fn new(machine: LinkerTargetArch, typ: u16) -> RelocationKind {
+        match (machine, typ) {
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_REL32) => Self::Relative(0),
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_REL32_1) => Self::Relative(1),
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_REL32_2) => Self::Relative(2),
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_REL32_3) => Self::Relative(3),
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_REL32_4) => Self::Relative(4),
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_REL32_5) => Self::Relative(5),
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_ADDR64) => Self::Address64,
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_SECREL)
+            | (LinkerTargetArch::I386, IMAGE_REL_I386_SECREL) => Self::SectionRelative,
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_SECREL7)
+            | (LinkerTargetArch::I386, IMAGE_REL_I386_SECREL7) => Self::SectionRelative7,
+            (LinkerTargetArch::Amd64, IMAGE_REL_AMD64_SECTION)
+            | (LinkerTargetArch::I386, IMAGE_REL_I386_SECTION) => Self::SectionIndex,
+            (LinkerTargetArch::I386, IMAGE_REL_I386_REL32) => Self::Relative(0),
+            _ => Self::Value32,
+        }
+    }
+
+    /// Returns the number of bytes holding the relocation value.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Address64 => 8,
+            Self::Relative(_) | Self::SectionRelative | Self::Value32 => 4,
+            Self::SectionIndex => 2,
+            Self::SectionRelative7 => 1,
+        }
+    }
+}
+
+/// The adjusted relocation value does not fit in the relocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct FixupOverflow;
+
+/// Adds `shift` to the addend stored in `field`.
+///
+/// Relative displacements are signed. 32-bit values read the addend as a
+/// signed offset but may hold an unsigned address after the shift so both
+/// ranges are accepted and the result wraps to 32 bits. Section offsets
+/// cannot be negative and section indices are left unchanged.
+pub(super) fn shift_addend(
+    kind: RelocationKind,
+    field: &mut [u8],
+    shift: i64,
+) -> Result<(), FixupOverflow> {
+    match kind {
+        RelocationKind::Relative(_) => {
+            let addend = i32::from_le_bytes(field[..4].try_into().unwrap());
+            let value = i32::try_from(addend as i64 + shift).map_err(|_| FixupOverflow)?;
+            field[..4].copy_from_slice(&value.to_le_bytes());
+        }
+        RelocationKind::Address64 => {
+            let addend = u64::from_le_bytes(field[..8].try_into().unwrap());
+            field[..8].copy_from_slice(&addend.wrapping_add_signed(shift).to_le_bytes());
+        }
+        RelocationKind::Value32 => {
+            let addend = i32::from_le_bytes(field[..4].try_into().unwrap());
+            let value = addend as i64 + shift;
+            if value < i32::MIN as i64 || value > u32::MAX as i64 {
+                return Err(FixupOverflow);
+            }
+
+            field[..4].copy_from_slice(&(value as u32).to_le_bytes());
+        }
+        RelocationKind::SectionRelative => {
+            let addend = u32::from_le_bytes(field[..4].try_into().unwrap());
+            let value = u32::try_from(addend as i64 + shift).map_err(|_| FixupOverflow)?;
+            field[..4].copy_from_slice(&value.to_le_bytes());
+        }
+        RelocationKind::SectionRelative7 => {
+            let value = (field[0] & 0x7f) as i64 + shift;
+            if !(0..=0x7f).contains(&value) {
+                return Err(FixupOverflow);
+            }
+
+            field[0] = (field[0] & 0x80) | value as u8;
+        }
+        RelocationKind::SectionIndex => (),
+    }
+
+    Ok(())
+}
+
+/// A relocation fixup at an offset in the data of an output section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Fixup {
+    /// Offset of the relocation value in the output section.
+    pub offset: u32,
+
+    /// How the relocation value is stored.
+    pub kind: RelocationKind,
+
+    /// The shift applied to the addend. See [`shift_addend`].
+    pub shift: i64,
+}
+
+impl Fixup {
+    /// Returns the fixup resolving a relative relocation at `offset` to the
+    /// symbol at `symbol_addr` in the same output section.
+    ///
+    /// `extra` is the number of bytes between the end of the displacement and
+    /// the end of the instruction.
+    pub fn relative(offset: u32, extra: u32, symbol_addr: u32) -> Fixup {
+        let next_instruction = offset as i64 + 4 + extra as i64;
+        Fixup {
+            offset,
+            kind: RelocationKind::Relative(extra),
+            shift: symbol_addr as i64 - next_instruction,
+        }
+    }
+
+    /// Applies the fixup to the relocation value in the section `data`.
+    pub fn apply(&self, data: &mut [u8]) -> Result<(), FixupOverflow> {
+        let start = self.offset as usize;
+        shift_addend(
+            self.kind,
+            &mut data[start..start + self.kind.size()],
+            self.shift,
+        )
+    }
+}
+
+/// The byte patches and relocation fixups for an output section.
+///
+/// The worklist is built from the graph after the section data is written
+/// and applied to the data of the output section in a single pass. Patches
+/// are applied before the fixups since rewritten instructions may replace
+/// the addend of a relocation.
+#[derive(Debug, Default)]
+pub(super) struct FixupWorklist {
+    patches: Vec<(u32, u8)>,
+    fixups: Vec<Fixup>,
+}
+
+impl FixupWorklist {
+    /// Adds a patch replacing the byte at `offset`.
+    pub fn patch(&mut self, offset: u32, byte: u8) {
+        self.patches.push((offset, byte));
+    }
+
+    /// Adds a relocation fixup.
+    pub fn push(&mut self, fixup: Fixup) {
+        self.fixups.push(fixup);
+    }
+
+    /// Sorts the fixups by their offset in the output section.
+    ///
+    /// Fixups at the same offset keep the order they were added in.
+    pub fn sort(&mut self) {
+        if !self.fixups.is_sorted_by_key(|fixup| fixup.offset) {
+            self.fixups.sort_by_key(|fixup| fixup.offset);
+        }
+    }
+
+    /// Applies the patches and the fixups to the section `data`.
+    ///
+    /// Returns the offset of the relocation if the adjusted value does not
+    /// fit in it.
+    pub fn apply(&self, data: &mut [u8]) -> Result<(), u32> {
+        for &(offset, byte) in &self.patches {
+            data[offset as usize] = byte;
+        }
+
+        for fixup in &self.fixups {
+            fixup.apply(data).map_err(|_| fixup.offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object::pe::{
+        IMAGE_REL_AMD64_ADDR32NB, IMAGE_REL_AMD64_ADDR64, IMAGE_REL_AMD64_REL32,
+        IMAGE_REL_AMD64_REL32_4, IMAGE_REL_AMD64_SECREL, IMAGE_REL_AMD64_SECTION,
+        IMAGE_REL_I386_DIR32, IMAGE_REL_I386_REL32, IMAGE_REL_I386_SECREL7,
+    };
+
+    use super::{Fixup, FixupOverflow, FixupWorklist, RelocationKind, shift_addend};
+    use crate::linker::LinkerTargetArch;
+
+    fn resolve_relative(
+        field: &mut [u8],
+        extra: u32,
+        reloc_addr: u32,
+        symbol_addr: u32,
+    ) -> Result<(), FixupOverflow> {
+        // Apply the fixup to the start of `field`
+        Fixup {
+            offset: 0,
+            ..Fixup::relative(reloc_addr, extra, symbol_addr)
+        }
+        .apply(field)
+    }
+
+    fn resolved(addend: i32, extra: u32, reloc_addr: u32, symbol_addr: u32) -> i32 {
+        let mut field = addend.to_le_bytes();
+        resolve_relative(&mut field, extra, reloc_addr, symbol_addr).unwrap();
+        i32::from_le_bytes(field)
+    }
+
+    #[test]
+    fn kinds() {
+        let amd64 = |typ| RelocationKind::new(LinkerTargetArch::Amd64, typ);
+        let i386 = |typ| RelocationKind::new(LinkerTargetArch::I386, typ);
+
+        assert_eq!(amd64(IMAGE_REL_AMD64_REL32), RelocationKind::Relative(0));
+        assert_eq!(amd64(IMAGE_REL_AMD64_REL32_4), RelocationKind::Relative(4));
+        assert_eq!(amd64(IMAGE_REL_AMD64_ADDR64), RelocationKind::Address64);
+        assert_eq!(amd64(IMAGE_REL_AMD64_ADDR32NB), RelocationKind::Value32);
+        assert_eq!(i386(IMAGE_REL_I386_REL32), RelocationKind::Relative(0));
+        assert_eq!(i386(IMAGE_REL_I386_DIR32), RelocationKind::Value32);
+        assert_eq!(
+            amd64(IMAGE_REL_AMD64_SECREL),
+            RelocationKind::SectionRelative
+        );
+        assert_eq!(amd64(IMAGE_REL_AMD64_SECTION), RelocationKind::SectionIndex);
+        assert_eq!(
+            i386(IMAGE_REL_I386_SECREL7),
+            RelocationKind::SectionRelative7
+        );
+        assert_eq!(RelocationKind::SectionIndex.size(), 2);
+
+        // IMAGE_REL_I386_REL32 is IMAGE_REL_AMD64_SSPAN32 on amd64
+        assert_eq!(amd64(IMAGE_REL_I386_REL32), RelocationKind::Value32);
+    }
+
+    #[test]
+    fn relative_forward() {
+        // call at 0x10 to a symbol at 0x40
+        assert_eq!(resolved(0, 0, 0x11, 0x40), 0x40 - 0x15);
+
+        // The addend is added to the displacement
+        assert_eq!(resolved(8, 0, 0x11, 0x40), 0x40 - 0x15 + 8);
+
+        // cmp byte ptr [rip + disp], imm8 ends one byte after the
+        // displacement
+        assert_eq!(resolved(0, 1, 0x2, 0x20), 0x20 - 0x7);
+    }
+
+    #[test]
+    fn relative_backward() {
+        // call at 0x40 to a symbol at 0x10
+        assert_eq!(resolved(0, 0, 0x41, 0x10), 0x10 - 0x45);
+
+        // Negative addends are kept
+        assert_eq!(resolved(-4, 0, 0x41, 0x10), 0x10 - 0x45 - 4);
+
+        // Call to the start of the instruction itself
+        assert_eq!(resolved(0, 0, 0x1, 0x0), -5);
+        assert_eq!(resolved(0, 4, 0x3, 0x0), -11);
+    }
+
+    #[test]
+    fn relative_endianness() {
+        let mut field = [0x10, 0x00, 0x00, 0x00];
+        resolve_relative(&mut field, 0, 0, 0x100).unwrap();
+        assert_eq!(field, [0x0c, 0x01, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn relative_overflow() {
+        let mut field = i32::MAX.to_le_bytes();
+        assert_eq!(resolve_relative(&mut field, 0, 0, 0x10), Err(FixupOverflow));
+        assert_eq!(field, i32::MAX.to_le_bytes(), "field should be unchanged");
+
+        let mut field = i32::MIN.to_le_bytes();
+        assert_eq!(resolve_relative(&mut field, 0, 0x10, 0), Err(FixupOverflow));
+    }
+
+    #[test]
+    fn shift_wraps_negative_addends() {
+        // Section relative values with negative addends
+        let mut field = (-4i32).to_le_bytes();
+        shift_addend(RelocationKind::Value32, &mut field, 0x100).unwrap();
+        assert_eq!(u32::from_le_bytes(field), 0xfc);
+
+        let mut field = (-4i32).to_le_bytes();
+        shift_addend(RelocationKind::Relative(0), &mut field, 0x100).unwrap();
+        assert_eq!(i32::from_le_bytes(field), 0xfc);
+
+        let mut field = (-4i64).to_le_bytes();
+        shift_addend(RelocationKind::Address64, &mut field, 0x100).unwrap();
+        assert_eq!(u64::from_le_bytes(field), 0xfc);
+
+        // Large unsigned 64-bit addends carry into the upper half
+        let mut field = 0xffff_fff0u64.to_le_bytes();
+        shift_addend(RelocationKind::Address64, &mut field, 0x20).unwrap();
+        assert_eq!(u64::from_le_bytes(field), 0x1_0000_0010);
+    }
+
+    #[test]
+    fn shift_overflow() {
+        let mut field = i32::MAX.to_le_bytes();
+        assert_eq!(
+            shift_addend(RelocationKind::Value32, &mut field, 0x8000_0001),
+            Err(FixupOverflow)
+        );
+
+        let mut field = i32::MAX.to_le_bytes();
+        assert_eq!(
+            shift_addend(RelocationKind::Relative(0), &mut field, 1),
+            Err(FixupOverflow)
+        );
+    }
+
+    #[test]
+    fn section_relative() {
+        let mut field = 0x8u32.to_le_bytes();
+        shift_addend(RelocationKind::SectionRelative, &mut field, 0x20).unwrap();
+        assert_eq!(u32::from_le_bytes(field), 0x28);
+
+        let mut field = 0x8u32.to_le_bytes();
+        assert_eq!(
+            shift_addend(RelocationKind::SectionRelative, &mut field, -0x10),
+            Err(FixupOverflow)
+        );
+
+        let mut field = [0x84];
+        shift_addend(RelocationKind::SectionRelative7, &mut field, 0x10).unwrap();
+        assert_eq!(field, [0x94], "high bit should be preserved");
+
+        let mut field = [0x70];
+        assert_eq!(
+            shift_addend(RelocationKind::SectionRelative7, &mut field, 0x10),
+            Err(FixupOverflow)
+        );
+    }
+
+    #[test]
+    fn section_index_unchanged() {
+        let mut field = [0x00, 0x00];
+        shift_addend(RelocationKind::SectionIndex, &mut field, 0x40).unwrap();
+        assert_eq!(field, [0x00, 0x00]);
+    }
+
+    #[test]
+    fn worklist_sorted_by_offset() {
+        let mut worklist = FixupWorklist::default();
+        worklist.push(Fixup {
+            offset: 8,
+            kind: RelocationKind::Value32,
+            shift: 0x10,
+        });
+        worklist.push(Fixup::relative(1, 0, 0x20));
+        worklist.sort();
+
+        assert_eq!(
+            Vec::from_iter(worklist.fixups.iter().map(|fixup| fixup.offset)),
+            [1, 8]
+        );
+
+        let mut data = [0u8; 12];
+        data[8..].copy_from_slice(&4u32.to_le_bytes());
+        worklist.apply(&mut data).unwrap();
+
+        assert_eq!(i32::from_le_bytes(data[1..5].try_into().unwrap()), 0x20 - 5);
+        assert_eq!(u32::from_le_bytes(data[8..].try_into().unwrap()), 0x14);
+    }
+
+    #[test]
+    fn worklist_patches_before_fixups() {
+        // The patch replaces the addend before the fixup is applied
+        let mut worklist = FixupWorklist::default();
+        worklist.push(Fixup {
+            offset: 0,
+            kind: RelocationKind::SectionRelative,
+            shift: 0x100,
+        });
+        worklist.patch(0, 0x10);
+
+        let mut data = [0u8; 4];
+        worklist.apply(&mut data).unwrap();
+        assert_eq!(u32::from_le_bytes(data), 0x110);
+    }
+
+    #[test]
+    fn worklist_overflow() {
+        let mut worklist = FixupWorklist::default();
+        worklist.push(Fixup {
+            offset: 1,
+            kind: RelocationKind::SectionRelative7,
+            shift: 0x80,
+        });
+
+        let mut data = [0u8; 2];
+        assert_eq!(worklist.apply(&mut data), Err(1));
+    }
+}
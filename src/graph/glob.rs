@@ -0,0 +1,60 @@
+/// Returns `true` if the name matches the glob pattern.
+///
+/// `*` matches any sequence of characters and `?` matches a single
+/// character. All other characters match themselves.
+pub(super) fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let name = name.as_bytes();
+
+    let mut pattern_idx = 0;
+    let mut name_idx = 0;
+
+    // Position of the last `*` in the pattern and the name position it
+    // started matching from
+    let mut backtrack = None;
+
+    while name_idx < name.len() {
+        match pattern.get(pattern_idx) {
+            Some(b'*') => {
+                pattern_idx += 1;
+                backtrack = Some((pattern_idx, name_idx));
+            }
+            Some(c) if *c == b'?' || *c == name[name_idx] => {
+                pattern_idx += 1;
+                name_idx += 1;
+            }
+            _ => match backtrack {
+                Some((star_pattern, star_name)) => {
+                    // Let the last `*` match one more character
+                    pattern_idx = star_pattern;
+                    name_idx = star_name + 1;
+                    backtrack = Some((star_pattern, name_idx));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[pattern_idx..].iter().all(|c| *c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn wildcards() {
+        assert!(glob_match("resource_table", "resource_table"));
+        assert!(glob_match("resource_*", "resource_table"));
+        assert!(glob_match(".rdata$*", ".rdata$res"));
+        assert!(glob_match("*_table", "resource_table"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("r?s*e", "resource_table"));
+        assert!(glob_match("*a*b*", "xxaxxbxx"));
+
+        assert!(!glob_match("resource_*", "resources"));
+        assert!(!glob_match(".rdata$?", ".rdata$res"));
+        assert!(!glob_match("*a*b", "xxaxxbxx"));
+        assert!(!glob_match("", "go"));
+    }
+}
@@ -0,0 +1,183 @@
+use indexmap::IndexSet;
+
+use crate::linker::LinkerTargetArch;
+
+use super::{
+    LinkGraph,
+    edge::{DefinitionEdgeWeight, ImportEdgeWeight, RelocationEdgeWeight},
+    node::{CoffNode, LibraryNode, SectionNode, SymbolNode},
+};
+
+/// A node in the link graph.
+#[derive(Debug, Clone, Copy)]
+pub enum GraphNode<'arena, 'data> {
+    /// A section from an input COFF.
+    Section(&'arena SectionNode<'arena, 'data>),
+
+    /// A symbol defined or referenced by the inputs.
+    Symbol(&'arena SymbolNode<'arena, 'data>),
+
+    /// A DLL with imported symbols or the API.
+    Library(&'arena LibraryNode<'arena, 'data>),
+}
+
+/// An edge in the link graph.
+#[derive(Debug, Clone, Copy)]
+pub enum GraphEdge<'arena, 'data> {
+    /// A relocation from a section to the target symbol.
+    Relocation(&'arena RelocationEdgeWeight),
+
+    /// A definition of a symbol in a section.
+    Definition(&'arena DefinitionEdgeWeight),
+
+    /// An import of a symbol from a library.
+    Import(&'arena ImportEdgeWeight<'data>),
+
+    /// A COMDAT association from a section to the section it is associated
+    /// with.
+    Associative,
+}
+
+impl<'arena, 'data> LinkGraph<'arena, 'data> {
+    /// Returns the target architecture of the graph.
+    pub fn machine(&self) -> LinkerTargetArch {
+        self.machine
+    }
+
+    /// Returns the COFF nodes in the order they were added.
+    pub fn coffs(&self) -> impl Iterator<Item = &'arena CoffNode<'data>> + '_ {
+        self.coff_nodes.iter().copied()
+    }
+
+    /// Returns the section nodes in the order they were added followed by the
+    /// section for the COMMON symbols.
+    pub fn sections(&self) -> impl Iterator<Item = &'arena SectionNode<'arena, 'data>> + '_ {
+        self.section_nodes
+            .iter()
+            .chain(self.common_section.get())
+            .copied()
+    }
+
+    /// Returns the external symbol nodes in the order they were added.
+    pub fn external_symbols(&self) -> impl Iterator<Item = &'arena SymbolNode<'arena, 'data>> + '_ {
+        self.external_symbols.values().copied()
+    }
+
+    /// Returns the external symbol node with the name.
+    pub fn external_symbol(&self, name: &str) -> Option<&'arena SymbolNode<'arena, 'data>> {
+        self.external_symbols.get(name).copied()
+    }
+
+    /// Returns the library nodes for the DLLs with imported symbols.
+    pub fn libraries(&self) -> impl Iterator<Item = &'arena LibraryNode<'arena, 'data>> + '_ {
+        self.library_nodes.values().copied()
+    }
+
+    /// Returns the library node for the API if an API symbol was imported.
+    pub fn api_library(&self) -> Option<&'arena LibraryNode<'arena, 'data>> {
+        self.api_node
+    }
+
+    /// Returns all of the nodes in the graph.
+    ///
+    /// The order only depends on the order the inputs were added. Sections
+    /// come first, followed by the external symbols, the local symbols, the
+    /// libraries and the API.
+    pub fn nodes(&self) -> Vec<GraphNode<'arena, 'data>> {
+        let mut symbols: IndexSet<*const SymbolNode<'arena, 'data>> = IndexSet::new();
+        let mut nodes = Vec::from_iter(self.sections().map(GraphNode::Section));
+
+        let local_symbols = self
+            .sections()
+            .flat_map(|section| {
+                section
+                    .definitions()
+                    .iter()
+                    .map(|definition| definition.source())
+                    .chain(section.relocations().iter().map(|reloc| reloc.target()))
+            })
+            .chain(self.extraneous_symbols.iter().copied());
+
+        for symbol in self.external_symbols().chain(local_symbols) {
+            if symbols.insert(symbol as *const _) {
+                nodes.push(GraphNode::Symbol(symbol));
+            }
+        }
+
+        nodes.extend(
+            self.libraries()
+                .chain(self.api_node)
+                .map(GraphNode::Library),
+        );
+        nodes
+    }
+
+    /// Exports the graph as a [`petgraph::Graph`].
+    ///
+    /// The node indices follow the order of [`LinkGraph::nodes`]. Relocation
+    /// and definition edges point from the section and symbol to the symbol
+    /// and section. Import edges point from the symbol to the library.
+    #[cfg(feature = "petgraph")]
+    pub fn to_petgraph(
+        &self,
+    ) -> petgraph::Graph<GraphNode<'arena, 'data>, GraphEdge<'arena, 'data>> {
+        use std::collections::HashMap;
+
+        let nodes = self.nodes();
+        let mut graph = petgraph::Graph::with_capacity(nodes.len(), 0);
+        let mut indices: HashMap<*const (), petgraph::graph::NodeIndex> =
+            HashMap::with_capacity(nodes.len());
+
+        for node in nodes {
+            let ptr = match node {
+                GraphNode::Section(section) => section as *const _ as *const (),
+                GraphNode::Symbol(symbol) => symbol as *const _ as *const (),
+                GraphNode::Library(library) => library as *const _ as *const (),
+            };
+            indices.insert(ptr, graph.add_node(node));
+        }
+
+        let index = |ptr: *const ()| indices[&ptr];
+
+        for section in self.sections() {
+            let section_idx = index(section as *const _ as *const ());
+
+            for reloc in section.relocations().iter() {
+                graph.add_edge(
+                    section_idx,
+                    index(reloc.target() as *const _ as *const ()),
+                    GraphEdge::Relocation(reloc.weight()),
+                );
+            }
+
+            for definition in section.definitions().iter() {
+                graph.add_edge(
+                    index(definition.source() as *const _ as *const ()),
+                    section_idx,
+                    GraphEdge::Definition(definition.weight()),
+                );
+            }
+
+            for association in section.associative_edges().iter() {
+                graph.add_edge(
+                    section_idx,
+                    index(association.target() as *const _ as *const ()),
+                    GraphEdge::Associative,
+                );
+            }
+        }
+
+        for library in self.libraries().chain(self.api_node) {
+            let library_idx = index(library as *const _ as *const ());
+            for import in library.imports().iter() {
+                graph.add_edge(
+                    index(import.source() as *const _ as *const ()),
+                    library_idx,
+                    GraphEdge::Import(import.weight()),
+                );
+            }
+        }
+
+        graph
+    }
+}
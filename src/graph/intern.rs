@@ -0,0 +1,86 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use super::link::LinkGraphArena;
+
+/// Pool of interned names for the nodes in the link graph.
+///
+/// Each distinct name is only stored once. Names from the inputs are
+/// borrowed from the input data and names created by the linker are copied
+/// into the graph arena the first time they are interned. Interned names with
+/// the same contents have the same address which lets the name types compare
+/// them by pointer before comparing the contents.
+#[derive(Debug, Default)]
+pub(super) struct StringPool<'a> {
+    names: RefCell<HashSet<&'a str>>,
+}
+
+impl<'a> StringPool<'a> {
+    /// Returns the interned copy of `name`.
+    ///
+    /// `name` becomes the interned copy if the pool does not contain it.
+    pub fn intern(&self, name: &'a str) -> &'a str {
+        let mut names = self.names.borrow_mut();
+        if let Some(interned) = names.get(name) {
+            return interned;
+        }
+
+        names.insert(name);
+        name
+    }
+
+    /// Returns the interned copy of `name` and copies it into the `arena` if
+    /// the pool does not contain it.
+    pub fn intern_copy(&self, arena: &'a LinkGraphArena, name: &str) -> &'a str {
+        let mut names = self.names.borrow_mut();
+        if let Some(interned) = names.get(name) {
+            return interned;
+        }
+
+        let interned = &*arena.alloc_str(name);
+        names.insert(interned);
+        interned
+    }
+
+    /// Returns the number of names in the pool.
+    pub fn len(&self) -> usize {
+        self.names.borrow().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::link::LinkGraphArena;
+
+    use super::StringPool;
+
+    #[test]
+    fn borrowed_names() {
+        let first = String::from("__imp_func");
+        let second = String::from("__imp_func");
+
+        let pool = StringPool::default();
+        let interned = pool.intern(&first);
+        assert!(std::ptr::eq(interned, first.as_str()));
+        assert!(std::ptr::eq(pool.intern(&second), interned));
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn copied_names() {
+        let arena = LinkGraphArena::new();
+        let pool = StringPool::default();
+
+        let interned = pool.intern_copy(&arena, &format!("__imp_{}", "func"));
+        assert_eq!(interned, "__imp_func");
+        assert!(std::ptr::eq(
+            pool.intern_copy(&arena, &format!("__imp_{}", "func")),
+            interned
+        ));
+        assert!(std::ptr::eq(pool.intern("__imp_func"), interned));
+
+        let allocated = arena.allocated_bytes();
+        pool.intern_copy(&arena, "__imp_func");
+        assert_eq!(arena.allocated_bytes(), allocated);
+        assert_eq!(pool.len(), 1);
+    }
+}
@@ -0,0 +1,385 @@
+use crate::linker::LinkerTargetArch;
+
+/// The kind of section holding a synthesized intrinsic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum IntrinsicSection {
+    /// Executable code placed in `.text$intr`.
+    Code,
+
+    /// Read-only data placed in `.rdata$intr`.
+    ReadOnlyData,
+}
+
+impl IntrinsicSection {
+    /// Returns the name of the section.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Code => ".text$intr",
+            Self::ReadOnlyData => ".rdata$intr",
+        }
+    }
+}
+
+/// A group of compiler intrinsics which can be synthesized for inputs linked
+/// without a CRT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntrinsicGroup {
+    /// The stack probe helpers (`__chkstk`, `___chkstk_ms`, `__alloca`)
+    /// referenced by functions with large stack frames.
+    StackProbe,
+
+    /// The 64-bit arithmetic helpers (`__alldiv`, `__allmul`, `___divdi3`,
+    /// etc.) referenced by i386 code.
+    I386Math,
+
+    /// The `_fltused` marker referenced by MSVC objects which use floating
+    /// point values.
+    Fltused,
+}
+
+/// The implementation of one or more compiler intrinsics.
+pub(super) struct IntrinsicTemplate {
+    /// The group the intrinsics belong to.
+    pub group: IntrinsicGroup,
+
+    /// The architecture of the implementation.
+    pub machine: LinkerTargetArch,
+
+    /// The kind of section for the implementation.
+    pub section: IntrinsicSection,
+
+    /// The position independent code for the implementation.
+    pub code: &'static [u8],
+
+    /// The symbol names of the intrinsics along with their offsets in the
+    /// code.
+    pub symbols: &'static [(&'static str, u32)],
+}
+
+/// Probes the stack pages for the allocation size in `rax` without
+/// adjusting `rsp`. All registers are preserved.
+///
+/// ```text
+/// push rcx
+/// push rax
+/// lea  rcx, [rsp + 24]
+/// cmp  rax, 0x1000
+/// jb   2f
+/// 1:
+/// sub  rcx, 0x1000
+/// test [rcx], rcx
+/// sub  rax, 0x1000
+/// cmp  rax, 0x1000
+/// ja   1b
+/// 2:
+/// sub  rcx, rax
+/// test [rcx], rcx
+/// pop  rax
+/// pop  rcx
+/// ret
+/// ```
+const AMD64_STACK_PROBE: &[u8] = &[
+    0x51, 0x50, 0x48, 0x8d, 0x4c, 0x24, 0x18, 0x48, 0x3d, 0x00, 0x10, 0x00, 0x00, 0x72, 0x18, 0x48,
+    0x81, 0xe9, 0x00, 0x10, 0x00, 0x00, 0x48, 0x85, 0x09, 0x48, 0x2d, 0x00, 0x10, 0x00, 0x00, 0x48,
+    0x3d, 0x00, 0x10, 0x00, 0x00, 0x77, 0xe8, 0x48, 0x29, 0xc1, 0x48, 0x85, 0x09, 0x58, 0x59, 0xc3,
+];
+
+/// Probes the stack pages for the allocation size in `rax` and subtracts
+/// the size from `rsp` before returning. `r10` and `r11` are clobbered.
+///
+/// ```text
+/// pop  r11
+/// mov  r10, rsp
+/// cmp  rax, 0x1000
+/// jb   2f
+/// 1:
+/// sub  r10, 0x1000
+/// test [r10], r10
+/// sub  rax, 0x1000
+/// cmp  rax, 0x1000
+/// ja   1b
+/// 2:
+/// sub  r10, rax
+/// test [r10], r10
+/// mov  rsp, r10
+/// push r11
+/// ret
+/// ```
+const AMD64_STACK_ALLOC: &[u8] = &[
+    0x41, 0x5b, 0x49, 0x89, 0xe2, 0x48, 0x3d, 0x00, 0x10, 0x00, 0x00, 0x72, 0x18, 0x49, 0x81, 0xea,
+    0x00, 0x10, 0x00, 0x00, 0x4d, 0x85, 0x12, 0x48, 0x2d, 0x00, 0x10, 0x00, 0x00, 0x48, 0x3d, 0x00,
+    0x10, 0x00, 0x00, 0x77, 0xe8, 0x49, 0x29, 0xc2, 0x4d, 0x85, 0x12, 0x4c, 0x89, 0xd4, 0x41, 0x53,
+    0xc3,
+];
+
+/// Probes the stack pages for the allocation size in `eax` without
+/// adjusting `esp`. All registers are preserved.
+///
+/// ```text
+/// push ecx
+/// push eax
+/// lea  ecx, [esp + 12]
+/// cmp  eax, 0x1000
+/// jb   2f
+/// 1:
+/// sub  ecx, 0x1000
+/// test [ecx], ecx
+/// sub  eax, 0x1000
+/// cmp  eax, 0x1000
+/// ja   1b
+/// 2:
+/// sub  ecx, eax
+/// test [ecx], ecx
+/// pop  eax
+/// pop  ecx
+/// ret
+/// ```
+const I386_STACK_PROBE: &[u8] = &[
+    0x51, 0x50, 0x8d, 0x4c, 0x24, 0x0c, 0x3d, 0x00, 0x10, 0x00, 0x00, 0x72, 0x14, 0x81, 0xe9, 0x00,
+    0x10, 0x00, 0x00, 0x85, 0x09, 0x2d, 0x00, 0x10, 0x00, 0x00, 0x3d, 0x00, 0x10, 0x00, 0x00, 0x77,
+    0xec, 0x29, 0xc1, 0x85, 0x09, 0x58, 0x59, 0xc3,
+];
+
+/// Probes the stack pages for the allocation size in `eax` and subtracts
+/// the size from `esp` before returning. `eax` is clobbered.
+///
+/// ```text
+/// push ecx
+/// lea  ecx, [esp + 8]
+/// cmp  eax, 0x1000
+/// jb   2f
+/// 1:
+/// sub  ecx, 0x1000
+/// test [ecx], ecx
+/// sub  eax, 0x1000
+/// cmp  eax, 0x1000
+/// ja   1b
+/// 2:
+/// sub  ecx, eax
+/// test [ecx], ecx
+/// mov  eax, esp
+/// mov  esp, ecx
+/// mov  ecx, [eax]
+/// mov  eax, [eax + 4]
+/// push eax
+/// ret
+/// ```
+const I386_STACK_ALLOC: &[u8] = &[
+    0x51, 0x8d, 0x4c, 0x24, 0x08, 0x3d, 0x00, 0x10, 0x00, 0x00, 0x72, 0x14, 0x81, 0xe9, 0x00, 0x10,
+    0x00, 0x00, 0x85, 0x09, 0x2d, 0x00, 0x10, 0x00, 0x00, 0x3d, 0x00, 0x10, 0x00, 0x00, 0x77, 0xec,
+    0x29, 0xc1, 0x85, 0x09, 0x89, 0xe0, 0x89, 0xcc, 0x8b, 0x08, 0x8b, 0x40, 0x04, 0x50, 0xc3,
+];
+
+/// The 64-bit arithmetic helpers for i386 assembled from
+/// `intrinsics/i386_math.s`.
+///
+/// The MSVC helpers (`__allmul`, `__alldiv`, ...) pop their arguments and
+/// the libgcc helpers (`___divdi3`, ...) are wrappers which leave the
+/// arguments for the caller. The helpers only reference each other with
+/// relative calls which are resolved when assembling so the code needs no
+/// relocations.
+const I386_MATH: &[u8] = &[
+    0x56, 0x57, 0x55, 0x31, 0xf6, 0x31, 0xff, 0xbd, 0x40, 0x00, 0x00, 0x00, 0xd1, 0xe0, 0xd1, 0xd2,
+    0xd1, 0xd6, 0xd1, 0xd7, 0x72, 0x0a, 0x39, 0xcf, 0x72, 0x0d, 0x77, 0x04, 0x39, 0xde, 0x72, 0x07,
+    0x29, 0xde, 0x19, 0xcf, 0x83, 0xc8, 0x01, 0x4d, 0x75, 0xe2, 0x89, 0xf3, 0x89, 0xf9, 0x5d, 0x5f,
+    0x5e, 0xc3, 0x53, 0x8b, 0x44, 0x24, 0x0c, 0xf7, 0x64, 0x24, 0x10, 0x89, 0xc3, 0x8b, 0x44, 0x24,
+    0x08, 0xf7, 0x64, 0x24, 0x14, 0x01, 0xc3, 0x8b, 0x44, 0x24, 0x08, 0xf7, 0x64, 0x24, 0x10, 0x01,
+    0xda, 0x5b, 0xc2, 0x10, 0x00, 0x53, 0x8b, 0x44, 0x24, 0x08, 0x8b, 0x54, 0x24, 0x0c, 0x8b, 0x5c,
+    0x24, 0x10, 0x8b, 0x4c, 0x24, 0x14, 0xe8, 0x95, 0xff, 0xff, 0xff, 0x5b, 0xc2, 0x10, 0x00, 0x53,
+    0x8b, 0x44, 0x24, 0x08, 0x8b, 0x54, 0x24, 0x0c, 0x8b, 0x5c, 0x24, 0x10, 0x8b, 0x4c, 0x24, 0x14,
+    0xe8, 0x7b, 0xff, 0xff, 0xff, 0x89, 0xd8, 0x89, 0xca, 0x5b, 0xc2, 0x10, 0x00, 0x8b, 0x44, 0x24,
+    0x04, 0x8b, 0x54, 0x24, 0x08, 0x8b, 0x5c, 0x24, 0x0c, 0x8b, 0x4c, 0x24, 0x10, 0xe8, 0x5e, 0xff,
+    0xff, 0xff, 0x87, 0xcb, 0xc2, 0x10, 0x00, 0x8b, 0x44, 0x24, 0x14, 0x8b, 0x54, 0x24, 0x18, 0x8b,
+    0x5c, 0x24, 0x1c, 0x8b, 0x4c, 0x24, 0x20, 0x89, 0xd6, 0x89, 0xd7, 0x31, 0xcf, 0x85, 0xd2, 0x79,
+    0x07, 0xf7, 0xda, 0xf7, 0xd8, 0x83, 0xda, 0x00, 0x85, 0xc9, 0x79, 0x07, 0xf7, 0xd9, 0xf7, 0xdb,
+    0x83, 0xd9, 0x00, 0xe8, 0x28, 0xff, 0xff, 0xff, 0x85, 0xff, 0x79, 0x07, 0xf7, 0xda, 0xf7, 0xd8,
+    0x83, 0xda, 0x00, 0x85, 0xf6, 0x79, 0x07, 0xf7, 0xd9, 0xf7, 0xdb, 0x83, 0xd9, 0x00, 0xc3, 0x53,
+    0x56, 0x57, 0xe8, 0xb0, 0xff, 0xff, 0xff, 0x5f, 0x5e, 0x5b, 0xc2, 0x10, 0x00, 0x53, 0x56, 0x57,
+    0xe8, 0xa2, 0xff, 0xff, 0xff, 0x89, 0xd8, 0x89, 0xca, 0x5f, 0x5e, 0x5b, 0xc2, 0x10, 0x00, 0x56,
+    0x57, 0x83, 0xec, 0x04, 0xe8, 0x8e, 0xff, 0xff, 0xff, 0x87, 0xcb, 0x83, 0xc4, 0x04, 0x5f, 0x5e,
+    0xc2, 0x10, 0x00, 0x80, 0xf9, 0x40, 0x73, 0x12, 0x80, 0xf9, 0x20, 0x73, 0x06, 0x0f, 0xa5, 0xc2,
+    0xd3, 0xe0, 0xc3, 0x89, 0xc2, 0x31, 0xc0, 0xd3, 0xe2, 0xc3, 0x31, 0xc0, 0x31, 0xd2, 0xc3, 0x80,
+    0xf9, 0x40, 0x73, 0x12, 0x80, 0xf9, 0x20, 0x73, 0x06, 0x0f, 0xad, 0xd0, 0xd3, 0xea, 0xc3, 0x89,
+    0xd0, 0x31, 0xd2, 0xd3, 0xe8, 0xc3, 0x31, 0xc0, 0x31, 0xd2, 0xc3, 0x80, 0xf9, 0x40, 0x73, 0x13,
+    0x80, 0xf9, 0x20, 0x73, 0x06, 0x0f, 0xad, 0xd0, 0xd3, 0xfa, 0xc3, 0x89, 0xd0, 0xc1, 0xfa, 0x1f,
+    0xd3, 0xf8, 0xc3, 0xc1, 0xfa, 0x1f, 0x89, 0xd0, 0xc3, 0xff, 0x74, 0x24, 0x10, 0xff, 0x74, 0x24,
+    0x10, 0xff, 0x74, 0x24, 0x10, 0xff, 0x74, 0x24, 0x10, 0xe8, 0x61, 0xff, 0xff, 0xff, 0xc3, 0xff,
+    0x74, 0x24, 0x10, 0xff, 0x74, 0x24, 0x10, 0xff, 0x74, 0x24, 0x10, 0xff, 0x74, 0x24, 0x10, 0xe8,
+    0xb1, 0xfe, 0xff, 0xff, 0xc3, 0xff, 0x74, 0x24, 0x10, 0xff, 0x74, 0x24, 0x10, 0xff, 0x74, 0x24,
+    0x10, 0xff, 0x74, 0x24, 0x10, 0xe8, 0x43, 0xff, 0xff, 0xff, 0xc3, 0xff, 0x74, 0x24, 0x10, 0xff,
+    0x74, 0x24, 0x10, 0xff, 0x74, 0x24, 0x10, 0xff, 0x74, 0x24, 0x10, 0xe8, 0x9f, 0xfe, 0xff, 0xff,
+    0xc3,
+];
+
+/// The value the CRT uses for `_fltused`. Nothing reads the value since the
+/// symbol is only referenced to pull in the floating point support.
+const FLTUSED: &[u8] = &[0x75, 0x98, 0x00, 0x00];
+
+/// The intrinsics which can be synthesized.
+const TEMPLATES: &[IntrinsicTemplate] = &[
+    IntrinsicTemplate {
+        group: IntrinsicGroup::StackProbe,
+        machine: LinkerTargetArch::Amd64,
+        section: IntrinsicSection::Code,
+        code: AMD64_STACK_PROBE,
+        symbols: &[("__chkstk", 0), ("___chkstk_ms", 0)],
+    },
+    IntrinsicTemplate {
+        group: IntrinsicGroup::StackProbe,
+        machine: LinkerTargetArch::Amd64,
+        section: IntrinsicSection::Code,
+        code: AMD64_STACK_ALLOC,
+        symbols: &[("___chkstk", 0)],
+    },
+    IntrinsicTemplate {
+        group: IntrinsicGroup::StackProbe,
+        machine: LinkerTargetArch::I386,
+        section: IntrinsicSection::Code,
+        code: I386_STACK_PROBE,
+        symbols: &[("___chkstk_ms", 0)],
+    },
+    IntrinsicTemplate {
+        group: IntrinsicGroup::StackProbe,
+        machine: LinkerTargetArch::I386,
+        section: IntrinsicSection::Code,
+        code: I386_STACK_ALLOC,
+        symbols: &[
+            ("__chkstk", 0),
+            ("__alloca_probe", 0),
+            ("___chkstk", 0),
+            ("__alloca", 0),
+        ],
+    },
+    IntrinsicTemplate {
+        group: IntrinsicGroup::I386Math,
+        machine: LinkerTargetArch::I386,
+        section: IntrinsicSection::Code,
+        code: I386_MATH,
+        symbols: &[
+            ("__allmul", 0x32),
+            ("__aulldiv", 0x55),
+            ("__aullrem", 0x6f),
+            ("__aulldvrm", 0x8d),
+            ("__alldiv", 0xef),
+            ("__allrem", 0xfd),
+            ("__alldvrm", 0x10f),
+            ("__allshl", 0x123),
+            ("__aullshr", 0x13f),
+            ("__allshr", 0x15b),
+            ("___divdi3", 0x179),
+            ("___udivdi3", 0x18f),
+            ("___moddi3", 0x1a5),
+            ("___umoddi3", 0x1bb),
+        ],
+    },
+    IntrinsicTemplate {
+        group: IntrinsicGroup::Fltused,
+        machine: LinkerTargetArch::Amd64,
+        section: IntrinsicSection::ReadOnlyData,
+        code: FLTUSED,
+        symbols: &[("_fltused", 0)],
+    },
+    IntrinsicTemplate {
+        group: IntrinsicGroup::Fltused,
+        machine: LinkerTargetArch::I386,
+        section: IntrinsicSection::ReadOnlyData,
+        code: FLTUSED,
+        symbols: &[("__fltused", 0)],
+    },
+];
+
+/// Returns the intrinsic templates in the groups for the architecture.
+pub(super) fn intrinsic_templates(
+    machine: LinkerTargetArch,
+    groups: &[IntrinsicGroup],
+) -> impl Iterator<Item = &'static IntrinsicTemplate> {
+    TEMPLATES
+        .iter()
+        .filter(move |template| template.machine == machine && groups.contains(&template.group))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntrinsicGroup, IntrinsicSection, TEMPLATES, intrinsic_templates};
+    use crate::linker::LinkerTargetArch;
+
+    fn defines(machine: LinkerTargetArch, group: IntrinsicGroup, name: &str) -> bool {
+        intrinsic_templates(machine, &[group])
+            .any(|template| template.symbols.iter().any(|(symbol, _)| *symbol == name))
+    }
+
+    #[test]
+    fn lookup_by_arch() {
+        assert!(defines(
+            LinkerTargetArch::I386,
+            IntrinsicGroup::StackProbe,
+            "__alloca"
+        ));
+        assert!(!defines(
+            LinkerTargetArch::Amd64,
+            IntrinsicGroup::StackProbe,
+            "__alloca"
+        ));
+        assert!(defines(
+            LinkerTargetArch::I386,
+            IntrinsicGroup::I386Math,
+            "__alldiv"
+        ));
+        assert!(!defines(
+            LinkerTargetArch::I386,
+            IntrinsicGroup::StackProbe,
+            "__alldiv"
+        ));
+        assert!(!defines(
+            LinkerTargetArch::Amd64,
+            IntrinsicGroup::I386Math,
+            "__alldiv"
+        ));
+
+        assert!(defines(
+            LinkerTargetArch::Amd64,
+            IntrinsicGroup::Fltused,
+            "_fltused"
+        ));
+        assert!(defines(
+            LinkerTargetArch::I386,
+            IntrinsicGroup::Fltused,
+            "__fltused"
+        ));
+
+        for template in TEMPLATES {
+            for (name, offset) in template.symbols {
+                assert!(
+                    (*offset as usize) < template.code.len(),
+                    "{name} should be within the code"
+                );
+            }
+
+            if template.section == IntrinsicSection::ReadOnlyData {
+                continue;
+            }
+
+            assert_eq!(
+                template.code.last(),
+                Some(&0xc3),
+                "{:?} template should end with a ret",
+                template.symbols
+            );
+        }
+    }
+
+    #[test]
+    fn calls_resolved() {
+        // An unresolved call from the assembler would target itself
+        for template in TEMPLATES {
+            assert!(
+                !template
+                    .code
+                    .windows(5)
+                    .any(|insn| insn == [0xe8, 0xfc, 0xff, 0xff, 0xff]),
+                "{:?} template has an unresolved call",
+                template.symbols
+            );
+        }
+    }
+}
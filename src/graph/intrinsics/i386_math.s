@@ -0,0 +1,240 @@
+/* 64-bit arithmetic helpers for i386 which are used by the
+   `IntrinsicGroup::I386Math` template in intrinsics.rs.
+
+   Assembled with:
+     as --32 -o i386_math.o i386_math.s
+     objcopy -O binary -j .text i386_math.o i386_math.bin
+
+   The MSVC helpers pop their arguments and the `*dvrm` helpers return the
+   remainder in ebx:ecx. The shift helpers take the value in edx:eax and the
+   shift count in cl.
+
+   The symbols are left local so that the assembler resolves the calls
+   between the helpers instead of emitting relocations for them. The output
+   must not have any relocations (check with `objdump -r i386_math.o`). */
+
+.intel_syntax noprefix
+.text
+
+/* Unsigned divide of edx:eax by ecx:ebx. Returns the quotient in edx:eax
+   and the remainder in ecx:ebx. */
+udivmod:
+    push esi
+    push edi
+    push ebp
+    xor esi, esi
+    xor edi, edi
+    mov ebp, 64
+1:  shl eax, 1
+    rcl edx, 1
+    rcl esi, 1
+    rcl edi, 1
+    jc 3f
+    cmp edi, ecx
+    jb 4f
+    ja 3f
+    cmp esi, ebx
+    jb 4f
+3:  sub esi, ebx
+    sbb edi, ecx
+    or eax, 1
+4:  dec ebp
+    jnz 1b
+    mov ebx, esi
+    mov ecx, edi
+    pop ebp
+    pop edi
+    pop esi
+    ret
+
+_allmul:
+    push ebx
+    mov eax, [esp + 12]
+    mul dword ptr [esp + 16]
+    mov ebx, eax
+    mov eax, [esp + 8]
+    mul dword ptr [esp + 20]
+    add ebx, eax
+    mov eax, [esp + 8]
+    mul dword ptr [esp + 16]
+    add edx, ebx
+    pop ebx
+    ret 16
+
+_aulldiv:
+    push ebx
+    mov eax, [esp + 8]
+    mov edx, [esp + 12]
+    mov ebx, [esp + 16]
+    mov ecx, [esp + 20]
+    call udivmod
+    pop ebx
+    ret 16
+
+_aullrem:
+    push ebx
+    mov eax, [esp + 8]
+    mov edx, [esp + 12]
+    mov ebx, [esp + 16]
+    mov ecx, [esp + 20]
+    call udivmod
+    mov eax, ebx
+    mov edx, ecx
+    pop ebx
+    ret 16
+
+_aulldvrm:
+    mov eax, [esp + 4]
+    mov edx, [esp + 8]
+    mov ebx, [esp + 12]
+    mov ecx, [esp + 16]
+    call udivmod
+    xchg ebx, ecx
+    ret 16
+
+/* Signed divide of the stack arguments. The dividend sign is returned in
+   esi and the quotient sign in edi. */
+sdivmod:
+    mov eax, [esp + 20]
+    mov edx, [esp + 24]
+    mov ebx, [esp + 28]
+    mov ecx, [esp + 32]
+    mov esi, edx
+    mov edi, edx
+    xor edi, ecx
+    test edx, edx
+    jns 1f
+    neg edx
+    neg eax
+    sbb edx, 0
+1:  test ecx, ecx
+    jns 2f
+    neg ecx
+    neg ebx
+    sbb ecx, 0
+2:  call udivmod
+    test edi, edi
+    jns 3f
+    neg edx
+    neg eax
+    sbb edx, 0
+3:  test esi, esi
+    jns 4f
+    neg ecx
+    neg ebx
+    sbb ecx, 0
+4:  ret
+
+_alldiv:
+    push ebx
+    push esi
+    push edi
+    call sdivmod
+    pop edi
+    pop esi
+    pop ebx
+    ret 16
+
+_allrem:
+    push ebx
+    push esi
+    push edi
+    call sdivmod
+    mov eax, ebx
+    mov edx, ecx
+    pop edi
+    pop esi
+    pop ebx
+    ret 16
+
+_alldvrm:
+    push esi
+    push edi
+    sub esp, 4
+    call sdivmod
+    xchg ebx, ecx
+    add esp, 4
+    pop edi
+    pop esi
+    ret 16
+
+_allshl:
+    cmp cl, 64
+    jae 2f
+    cmp cl, 32
+    jae 1f
+    shld edx, eax, cl
+    shl eax, cl
+    ret
+1:  mov edx, eax
+    xor eax, eax
+    shl edx, cl
+    ret
+2:  xor eax, eax
+    xor edx, edx
+    ret
+
+_aullshr:
+    cmp cl, 64
+    jae 2f
+    cmp cl, 32
+    jae 1f
+    shrd eax, edx, cl
+    shr edx, cl
+    ret
+1:  mov eax, edx
+    xor edx, edx
+    shr eax, cl
+    ret
+2:  xor eax, eax
+    xor edx, edx
+    ret
+
+_allshr:
+    cmp cl, 64
+    jae 2f
+    cmp cl, 32
+    jae 1f
+    shrd eax, edx, cl
+    sar edx, cl
+    ret
+1:  mov eax, edx
+    sar edx, 31
+    sar eax, cl
+    ret
+2:  sar edx, 31
+    mov eax, edx
+    ret
+
+/* The libgcc helpers take the same arguments but the caller pops them. */
+divdi3:
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    call _alldiv
+    ret
+
+udivdi3:
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    call _aulldiv
+    ret
+
+moddi3:
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    call _allrem
+    ret
+
+umoddi3:
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    push dword ptr [esp + 16]
+    call _aullrem
+    ret
@@ -0,0 +1,199 @@
+use indexmap::{IndexMap, IndexSet};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LinkLayoutParseError {
+    #[error("line {0}: expected 'section', 'input' or 'symbol'")]
+    Syntax(usize),
+
+    #[error("line {0}: input section is not part of an output section")]
+    MissingSection(usize),
+
+    #[error("line {0}: invalid input section ordinal")]
+    Ordinal(usize),
+}
+
+/// Identifies an input section in a [`LinkLayout`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LayoutInput {
+    /// The name of the COFF containing the section.
+    pub coff: String,
+
+    /// The name of the section.
+    pub name: String,
+
+    /// Index of the section among the sections in the COFF with the same
+    /// name.
+    pub ordinal: usize,
+}
+
+/// The section and symbol ordering from a previous link.
+///
+/// Relinking with the layout keeps the output sections, input sections and
+/// symbols in the same order as the previous link. Inputs which are not part
+/// of the layout are placed after the ones which are.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinkLayout {
+    /// The output sections with the ordered input sections.
+    sections: IndexMap<String, IndexSet<LayoutInput>>,
+
+    /// The ordered output symbol names.
+    symbols: IndexSet<String>,
+}
+
+impl LinkLayout {
+    /// Creates a new empty [`LinkLayout`].
+    pub fn new() -> LinkLayout {
+        Self::default()
+    }
+
+    /// Parses a layout file.
+    ///
+    /// Each `section <name>` line starts an output section and is followed by
+    /// the `input <name> <ordinal> <coff>` lines for the input sections in
+    /// it. Symbols are listed with `symbol <name>` lines. Lines starting with
+    /// `#` are ignored.
+    pub fn parse(content: &str) -> Result<LinkLayout, LinkLayoutParseError> {
+        let mut layout = LinkLayout::new();
+        let mut current_section = None;
+
+        for (line_num, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((directive, value)) = line.split_once(char::is_whitespace) else {
+                return Err(LinkLayoutParseError::Syntax(line_num + 1));
+            };
+
+            let value = value.trim_start();
+
+            match directive {
+                "section" => {
+                    layout.sections.entry(value.to_string()).or_default();
+                    current_section = Some(value.to_string());
+                }
+                "input" => {
+                    let inputs = current_section
+                        .as_ref()
+                        .and_then(|section| layout.sections.get_mut(section))
+                        .ok_or(LinkLayoutParseError::MissingSection(line_num + 1))?;
+
+                    let mut parts = value.splitn(3, char::is_whitespace);
+                    let (Some(name), Some(ordinal), Some(coff)) =
+                        (parts.next(), parts.next(), parts.next())
+                    else {
+                        return Err(LinkLayoutParseError::Syntax(line_num + 1));
+                    };
+
+                    let ordinal = ordinal
+                        .parse()
+                        .map_err(|_| LinkLayoutParseError::Ordinal(line_num + 1))?;
+
+                    inputs.insert(LayoutInput {
+                        coff: coff.trim_start().to_string(),
+                        name: name.to_string(),
+                        ordinal,
+                    });
+                }
+                "symbol" => {
+                    layout.symbols.insert(value.to_string());
+                }
+                _ => return Err(LinkLayoutParseError::Syntax(line_num + 1)),
+            }
+        }
+
+        Ok(layout)
+    }
+
+    /// Adds an input section to the end of the output section.
+    pub fn add_input(&mut self, section: impl Into<String>, input: LayoutInput) {
+        self.sections
+            .entry(section.into())
+            .or_default()
+            .insert(input);
+    }
+
+    /// Adds a symbol to the end of the symbol order.
+    pub fn add_symbol(&mut self, name: impl Into<String>) {
+        self.symbols.insert(name.into());
+    }
+
+    /// Returns the output section names in order.
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        self.sections.keys().map(String::as_str)
+    }
+
+    /// Returns the input sections in the output section in order.
+    pub fn inputs(&self, section: &str) -> impl Iterator<Item = &LayoutInput> {
+        self.sections.get(section).into_iter().flatten()
+    }
+
+    /// Returns the symbol names in order.
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.symbols.iter().map(String::as_str)
+    }
+
+    /// Returns the position of the output section in the layout.
+    pub(super) fn section_position(&self, section: &str) -> Option<usize> {
+        self.sections.get_index_of(section)
+    }
+
+    /// Returns the position of the input section in the output section.
+    pub(super) fn input_position(&self, section: &str, input: &LayoutInput) -> Option<usize> {
+        self.sections
+            .get(section)
+            .and_then(|inputs| inputs.get_index_of(input))
+    }
+
+    /// Returns the position of the symbol in the layout.
+    pub(super) fn symbol_position(&self, name: &str) -> Option<usize> {
+        self.symbols.get_index_of(name)
+    }
+}
+
+impl std::fmt::Display for LinkLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (section, inputs) in &self.sections {
+            writeln!(f, "section {section}")?;
+
+            for input in inputs {
+                writeln!(f, "input {} {} {}", input.name, input.ordinal, input.coff)?;
+            }
+        }
+
+        for symbol in &self.symbols {
+            writeln!(f, "symbol {symbol}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LayoutInput, LinkLayout, LinkLayoutParseError};
+
+    #[test]
+    fn roundtrip() {
+        let mut layout = LinkLayout::new();
+        layout.add_input(
+            ".text",
+            LayoutInput {
+                coff: "path with spaces/a.o".into(),
+                name: ".text$mn".into(),
+                ordinal: 1,
+            },
+        );
+        layout.add_symbol("go");
+
+        let parsed = LinkLayout::parse(&layout.to_string()).unwrap();
+        assert_eq!(parsed, layout);
+    }
+
+    #[test]
+    fn input_without_section() {
+        let err = LinkLayout::parse("input .text 0 a.o").unwrap_err();
+        assert!(matches!(err, LinkLayoutParseError::MissingSection(1)));
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     cell::OnceCell,
-    collections::{BTreeMap, HashMap, LinkedList, hash_map},
+    collections::{BTreeMap, HashMap, HashSet, LinkedList, hash_map},
     hash::{DefaultHasher, Hasher},
     path::Path,
     sync::LazyLock,
@@ -11,9 +11,14 @@ use log::warn;
 use object::{
     Architecture, Object, ObjectSection, ObjectSymbol, SectionIndex, SymbolIndex,
     coff::{CoffFile, CoffHeader, ImageSymbol},
+    pe::{
+        IMAGE_FILE_MACHINE_UNKNOWN, IMAGE_SYM_CLASS_CLR_TOKEN, IMAGE_SYM_CLASS_FUNCTION,
+        ImageAuxSymbolFunctionBeginEnd,
+    },
 };
 
 use crate::{
+    drectve,
     linker::LinkerTargetArch,
     linkobject::import::{ImportMember, ImportName},
 };
@@ -21,13 +26,18 @@ use crate::{
 use super::{
     BuiltLinkGraph, SpecLinkGraph,
     cache::LinkGraphCache,
+    crt::{PSEUDO_RELOC_SECTION, crt_symbol_help, is_pseudo_reloc_symbol},
     edge::{
         AssociativeSectionEdgeWeight, ComdatSelection, DefinitionEdgeWeight, Edge,
         ImportEdgeWeight, RelocationEdgeWeight, TryFromComdatSelectionError,
     },
+    feat::FEAT_SYMBOL,
+    intern::StringPool,
+    intrinsics::{IntrinsicGroup, IntrinsicSection, intrinsic_templates},
     node::{
-        CoffNode, LibraryNode, LibraryNodeWeight, SectionNode, SectionNodeCharacteristics,
-        SectionNodeData, SymbolNode, SymbolNodeStorageClass, SymbolNodeType, TryFromSymbolError,
+        CoffNode, LibraryNode, LibraryNodeWeight, SectionName, SectionNode,
+        SectionNodeCharacteristics, SectionNodeData, SymbolNode, SymbolNodeFunctionAux,
+        SymbolNodeStorageClass, SymbolNodeType, TryFromSymbolError,
     },
 };
 
@@ -85,6 +95,16 @@ pub enum LinkGraphAddError {
         associative_index: SectionIndex,
     },
 
+    #[error("COMDAT section '{section}' ({index}) has a cyclic associative section chain: {chain}")]
+    ComdatAssociativeCycle {
+        section: String,
+        index: SectionIndex,
+        chain: String,
+    },
+
+    #[error("COFF contains managed code ({reason}). Objects compiled with /clr cannot be linked")]
+    ManagedCode { reason: String },
+
     #[error("{0}")]
     Object(#[from] object::read::Error),
 }
@@ -101,38 +121,128 @@ pub enum SymbolError<'arena, 'data> {
     MultiplyDefined(MultiplyDefinedSymbolError<'arena, 'data>),
 }
 
-#[derive(Debug, thiserror::Error)]
-pub struct DuplicateSymbolError<'arena, 'data>(&'arena SymbolNode<'arena, 'data>);
+/// The kind of a [`SymbolError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolErrorKind {
+    /// The symbol is defined more than once.
+    Duplicate,
 
-impl std::fmt::Display for DuplicateSymbolError<'_, '_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "duplicate symbol: {}", self.0.name().demangle())?;
+    /// The symbol is not defined.
+    Undefined,
 
-        let mut definition_iter = self.0.definitions().iter();
+    /// The symbol has conflicting COMDAT definitions.
+    MultiplyDefined,
+}
 
-        for definition in definition_iter.by_ref().take(5) {
-            write!(f, "\n>>> defined at {}", definition.target().coff())?;
+impl SymbolError<'_, '_> {
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> SymbolErrorKind {
+        match self {
+            Self::Duplicate(_) => SymbolErrorKind::Duplicate,
+            Self::Undefined(_) => SymbolErrorKind::Undefined,
+            Self::MultiplyDefined(_) => SymbolErrorKind::MultiplyDefined,
         }
+    }
 
-        let remaining = definition_iter.count();
-        if remaining > 0 {
-            write!(f, "\n>>> defined {remaining} more times")?;
+    /// Returns the name of the symbol with the error.
+    pub fn symbol_name(&self) -> &str {
+        match self {
+            Self::Duplicate(e) => e.0.name().as_str(),
+            Self::Undefined(e) => e.0.name().as_str(),
+            Self::MultiplyDefined(e) => e.0.name().as_str(),
         }
+    }
 
-        Ok(())
+    /// Returns the error message without the notes.
+    pub fn message(&self) -> String {
+        match self {
+            Self::Duplicate(e) => format!("duplicate symbol: {}", e.0.name().demangle()),
+            Self::Undefined(e) => format!("undefined symbol: {}", e.0.name().demangle()),
+            Self::MultiplyDefined(e) => {
+                format!("multiply defined symbol: {}", e.0.name().demangle())
+            }
+        }
+    }
+
+    /// Returns the notes with the locations where the symbol is defined or
+    /// referenced.
+    pub fn notes(&self) -> Vec<String> {
+        match self {
+            Self::Duplicate(e) => definition_notes(e.0),
+            Self::Undefined(e) => e.notes(),
+            Self::MultiplyDefined(e) => definition_notes(e.0),
+        }
+    }
+
+    /// Returns the guidance for fixing the error if the symbol is a known
+    /// CRT helper.
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            Self::Undefined(e) => crt_symbol_help(e.0.name().as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Number of definitions or references listed in the symbol error notes.
+const SYMBOL_ERROR_NOTES: usize = 5;
+
+/// Writes the symbol error message followed by the notes.
+fn display_symbol_error(
+    f: &mut std::fmt::Formatter<'_>,
+    error: &SymbolError<'_, '_>,
+) -> std::fmt::Result {
+    write!(f, "{}", error.message())?;
+
+    for note in error.notes() {
+        write!(f, "\n>>> {note}")?;
+    }
+
+    if let Some(help) = error.help() {
+        write!(f, "\nhelp: {help}")?;
     }
+
+    Ok(())
+}
+
+/// Returns the notes listing the COFFs which define the symbol.
+fn definition_notes(symbol: &SymbolNode<'_, '_>) -> Vec<String> {
+    let mut definition_iter = symbol.definitions().iter();
+
+    let mut notes = Vec::from_iter(
+        definition_iter
+            .by_ref()
+            .take(SYMBOL_ERROR_NOTES)
+            .map(|definition| format!("defined at {}", definition.target().coff())),
+    );
+
+    let remaining = definition_iter.count();
+    if remaining > 0 {
+        notes.push(format!("defined {remaining} more times"));
+    }
+
+    notes
 }
 
 #[derive(Debug, thiserror::Error)]
-pub struct UndefinedSymbolError<'arena, 'data>(&'arena SymbolNode<'arena, 'data>);
+pub struct DuplicateSymbolError<'arena, 'data>(&'arena SymbolNode<'arena, 'data>);
 
-impl std::fmt::Display for UndefinedSymbolError<'_, '_> {
+impl std::fmt::Display for DuplicateSymbolError<'_, '_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "undefined symbol: {}", self.0.name().demangle())?;
+        display_symbol_error(f, &SymbolError::Duplicate(DuplicateSymbolError(self.0)))
+    }
+}
 
+#[derive(Debug, thiserror::Error)]
+pub struct UndefinedSymbolError<'arena, 'data>(&'arena SymbolNode<'arena, 'data>);
+
+impl UndefinedSymbolError<'_, '_> {
+    /// Returns the notes listing the locations referencing the symbol.
+    fn notes(&self) -> Vec<String> {
         let mut reference_iter = self.0.references().iter();
+        let mut notes = Vec::new();
 
-        for reference in reference_iter.by_ref().take(5) {
+        for reference in reference_iter.by_ref().take(SYMBOL_ERROR_NOTES) {
             let section = reference.source();
             let coff = section.coff();
 
@@ -150,49 +260,71 @@ impl std::fmt::Display for UndefinedSymbolError<'_, '_> {
                 .range(0..=reference.weight().address())
                 .next_back()
             {
-                write!(
-                    f,
-                    "\n>>> referenced by {coff}:({})",
+                notes.push(format!(
+                    "referenced by {coff}:({})",
                     reference_symbol.1.demangle()
-                )?;
+                ));
             } else {
-                write!(
-                    f,
-                    "\n>>> referenced by {coff}:({}+{:#x})",
+                notes.push(format!(
+                    "referenced by {coff}:({}+{:#x})",
                     section.name(),
                     reference.weight().address()
-                )?;
+                ));
             }
         }
 
         let remaining = reference_iter.count();
         if remaining > 0 {
-            write!(f, "\n>>> referenced {remaining} more times")?;
+            notes.push(format!("referenced {remaining} more times"));
         }
 
-        Ok(())
+        notes
     }
 }
 
-#[derive(Debug, thiserror::Error)]
-pub struct MultiplyDefinedSymbolError<'arena, 'data>(&'arena SymbolNode<'arena, 'data>);
-
-impl std::fmt::Display for MultiplyDefinedSymbolError<'_, '_> {
+impl std::fmt::Display for UndefinedSymbolError<'_, '_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "multiply defined symbol: {}", self.0.name().demangle())?;
+        display_symbol_error(f, &SymbolError::Undefined(UndefinedSymbolError(self.0)))
+    }
+}
 
-        let mut definition_iter = self.0.definitions().iter();
+/// Returns an error if the COFF contains managed code.
+fn check_managed_code<'data, C: CoffHeader>(
+    coff: &CoffFile<'data, &'data [u8], C>,
+) -> Result<(), LinkGraphAddError> {
+    if coff
+        .sections()
+        .any(|section| section.name().is_ok_and(|name| name == ".cormeta"))
+    {
+        return Err(LinkGraphAddError::ManagedCode {
+            reason: "found .cormeta section".into(),
+        });
+    }
 
-        for definition in definition_iter.by_ref().take(5) {
-            write!(f, "\n>>> defined at {}", definition.target().coff())?;
-        }
+    if let Some(symbol) = coff
+        .symbols()
+        .find(|symbol| symbol.coff_symbol().storage_class() == IMAGE_SYM_CLASS_CLR_TOKEN)
+    {
+        return Err(LinkGraphAddError::ManagedCode {
+            reason: format!(
+                "found CLR token symbol '{}'",
+                symbol.name().unwrap_or("<unknown>")
+            ),
+        });
+    }
 
-        let remaining = definition_iter.count();
-        if remaining > 0 {
-            write!(f, "\n>>> defined {remaining} more times")?;
-        }
+    Ok(())
+}
 
-        Ok(())
+#[derive(Debug, thiserror::Error)]
+pub struct MultiplyDefinedSymbolError<'arena, 'data>(&'arena SymbolNode<'arena, 'data>);
+
+impl std::fmt::Display for MultiplyDefinedSymbolError<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        display_symbol_error(
+            f,
+            &SymbolError::MultiplyDefined(MultiplyDefinedSymbolError(self.0)),
+        )
     }
 }
 
@@ -225,9 +357,28 @@ pub struct LinkGraph<'arena, 'data> {
     /// Local symbols without any definition (absolute/debug symbols)
     pub(super) extraneous_symbols: LinkedList<&'arena SymbolNode<'arena, 'data>>,
 
+    /// The `@feat.00` values for COFFs which have the symbol.
+    pub(super) coff_features: HashMap<&'arena CoffNode<'data>, u32>,
+
+    /// The input COFFs with `.idata$` sections from import libraries.
+    pub(super) import_coffs: HashSet<&'arena CoffNode<'data>>,
+
+    /// Symbol name patterns from the `/KEEP` directives in the inputs.
+    pub(super) keep_symbols: Vec<String>,
+
+    /// Whether to discard the `.rsrc` resource sections in the inputs.
+    pub(super) strip_resources: bool,
+
     /// Number of nodes in the graph.
     pub(super) node_count: usize,
 
+    /// Interned names of the local symbols and the symbols added by the
+    /// linker.
+    pub(super) symbol_names: StringPool<'arena>,
+
+    /// Interned names of the sections.
+    pub(super) section_names: StringPool<'data>,
+
     /// COFF insertion cache.
     pub(super) cache: LinkGraphCache<'arena, 'data>,
 
@@ -252,7 +403,13 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
             api_node: None,
             external_symbols: IndexMap::new(),
             extraneous_symbols: LinkedList::new(),
+            coff_features: HashMap::new(),
+            import_coffs: HashSet::new(),
+            keep_symbols: Vec::new(),
+            strip_resources: false,
             node_count: 0,
+            symbol_names: StringPool::default(),
+            section_names: StringPool::default(),
             cache: LinkGraphCache::new(),
             arena,
         }
@@ -272,14 +429,30 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
         self.arena.allocated_bytes()
     }
 
+    /// Discard the `.rsrc` resource sections in the COFFs added after this
+    /// is set.
+    ///
+    /// BOF loaders do not load resources so the sections only take up space
+    /// in the output.
+    pub fn set_strip_resources(&mut self, val: bool) {
+        self.strip_resources = val;
+    }
+
     /// Adds a COFF to the graph.
+    ///
+    /// COFFs without a machine type or without any sections are accepted
+    /// for any target architecture. Compilers emit these for empty
+    /// translation units.
     pub fn add_coff<C: CoffHeader>(
         &mut self,
         file_path: &'data Path,
         member_path: Option<&'data Path>,
         coff: &CoffFile<'data, &'data [u8], C>,
     ) -> Result<(), LinkGraphAddError> {
-        if Architecture::from(self.machine) != coff.architecture() {
+        if Architecture::from(self.machine) != coff.architecture()
+            && coff.coff_header().machine() != IMAGE_FILE_MACHINE_UNKNOWN
+            && !coff.coff_section_table().is_empty()
+        {
             return Err(LinkGraphAddError::ArchitectureMismatch {
                 expected: self.machine.into(),
                 found: coff.architecture(),
@@ -292,10 +465,19 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
             return Ok(());
         }
 
+        check_managed_code(coff)?;
+
         let coff_node = self.arena.alloc(coff_node);
         self.node_count += 1;
         self.coff_nodes.insert(coff_node);
 
+        if let Some(directives) = drectve::drectve_data(coff) {
+            self.keep_symbols.extend(
+                drectve::drectve_keep_symbols(drectve::DrectveDirectives::parse(&directives))
+                    .map(str::to_string),
+            );
+        }
+
         let symbol_table = coff.coff_symbol_table();
 
         self.cache.clear();
@@ -305,13 +487,37 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
         let mut comdat_count = 0;
 
         for section in coff.sections() {
-            let section_name = section.name()?;
+            let section_name = self.section_names.intern(section.name()?);
             let coff_section = section.coff_section();
 
-            let characteristics = SectionNodeCharacteristics::from_bits_truncate(
+            if section_name.starts_with(".idata$") {
+                self.import_coffs.insert(coff_node);
+            }
+
+            let mut characteristics = SectionNodeCharacteristics::from_bits_truncate(
                 coff_section.characteristics.get(object::LittleEndian),
             );
 
+            if SectionName::from(section_name).group_name() == ".rsrc" {
+                if self.strip_resources {
+                    characteristics.insert(SectionNodeCharacteristics::LnkRemove);
+                } else {
+                    warn!(
+                        coff:% = file_path.display(),
+                        member = member_path.map(Path::to_string_lossy),
+                        section = section_name;
+                        "{coff_node}: resource section '{section_name}' will not be loaded by BOF loaders. use --strip-resources to discard it",
+                    );
+                }
+            } else if section_name == PSEUDO_RELOC_SECTION {
+                warn!(
+                    coff:% = file_path.display(),
+                    member = member_path.map(Path::to_string_lossy),
+                    section = section_name;
+                    "{coff_node}: section '{section_name}' contains MinGW runtime pseudo-relocations which are not applied by BOF loaders",
+                );
+            }
+
             let section_data =
                 if characteristics.contains(SectionNodeCharacteristics::CntUninitializedData) {
                     SectionNodeData::Uninitialized(
@@ -337,12 +543,24 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
 
         self.cache.reserve_comdat_selections(comdat_count);
 
+        // Map of associative COMDAT sections to the sections they are
+        // associated with
+        let mut associations: IndexMap<SectionIndex, SectionIndex> = IndexMap::new();
+
         for symbol in coff.symbols() {
             let symbol_name = symbol.name()?;
             let coff_symbol = symbol.coff_symbol();
 
+            // External symbols use the name of the first occurrence from the
+            // symbol map
+            let node_name = if symbol.is_global() {
+                symbol_name
+            } else {
+                self.symbol_names.intern(symbol_name)
+            };
+
             let graph_symbol =
-                SymbolNode::try_from_symbol::<C>(symbol_name, coff_symbol).map_err(|e| {
+                SymbolNode::try_from_symbol::<C>(node_name, coff_symbol).map_err(|e| {
                     LinkGraphAddError::Symbol {
                         name: symbol_name.to_string(),
                         index: symbol.index(),
@@ -413,6 +631,12 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
                         graph_symbol.definitions().push_back(definition_edge);
                         common_section.definitions().push_back(definition_edge);
                     } else if symbol.is_local() {
+                        if let SymbolNodeType::Absolute(features) = graph_symbol.typ() {
+                            if symbol_name == FEAT_SYMBOL {
+                                self.coff_features.insert(coff_node, features);
+                            }
+                        }
+
                         self.extraneous_symbols.push_back(graph_symbol);
                     }
 
@@ -434,6 +658,23 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
                 DefinitionEdgeWeight::new(coff_symbol.value(), None),
             );
 
+            // Keep the first function record for symbols defined in
+            // multiple COFFs
+            if coff_symbol.has_aux_function() {
+                let aux_function = symbol_table.aux_function(symbol.index())?;
+                let _ = graph_symbol.set_function_aux(SymbolNodeFunctionAux::Definition {
+                    total_size: aux_function.total_size.get(object::LittleEndian),
+                });
+            } else if coff_symbol.storage_class() == IMAGE_SYM_CLASS_FUNCTION
+                && coff_symbol.number_of_aux_symbols() > 0
+            {
+                let aux_function: &ImageAuxSymbolFunctionBeginEnd =
+                    symbol_table.get(symbol.index(), 1)?;
+                let _ = graph_symbol.set_function_aux(SymbolNodeFunctionAux::BeginEnd {
+                    line_number: aux_function.linenumber.get(object::LittleEndian),
+                });
+            }
+
             if coff_symbol.has_aux_section() {
                 let aux_section = symbol_table.aux_section(symbol.index())?;
                 let mut checksum = aux_section.check_sum.get(object::LittleEndian);
@@ -463,6 +704,19 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
                                 associative_index: associative_section_index,
                             })?;
 
+                        if associative_section_index.0 > section_idx.0 {
+                            warn!(
+                                coff:% = file_path.display(),
+                                member = member_path.map(Path::to_string_lossy),
+                                section:% = graph_section.name();
+                                "{coff_node}: COMDAT section '{}' ({section_idx}) is associated with later section '{}' ({associative_section_index})",
+                                graph_section.name(),
+                                associative_section.name(),
+                            );
+                        }
+
+                        associations.insert(section_idx, associative_section_index);
+
                         associative_section
                             .associative_edges()
                             .push_back(self.arena.alloc_with(|| {
@@ -511,6 +765,8 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
             graph_section.definitions().push_back(definition_edge);
         }
 
+        self.check_associations(&associations)?;
+
         for section in coff.sections() {
             let graph_section = self
                 .cache
@@ -545,6 +801,51 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
         Ok(())
     }
 
+    /// Checks the associative COMDAT sections in the COFF for cycles.
+    fn check_associations(
+        &self,
+        associations: &IndexMap<SectionIndex, SectionIndex>,
+    ) -> Result<(), LinkGraphAddError> {
+        let section_name = |idx: SectionIndex| {
+            self.cache
+                .get_section(idx)
+                .map(|section| section.name().to_string())
+                .unwrap_or_default()
+        };
+
+        for &section_idx in associations.keys() {
+            let mut chain = vec![section_idx];
+            let mut current = section_idx;
+
+            while let Some(&parent) = associations.get(&current) {
+                if parent == section_idx {
+                    chain.push(parent);
+
+                    return Err(LinkGraphAddError::ComdatAssociativeCycle {
+                        section: section_name(section_idx),
+                        index: section_idx,
+                        chain: chain
+                            .into_iter()
+                            .map(|idx| format!("'{}' ({idx})", section_name(idx)))
+                            .collect::<Vec<_>>()
+                            .join(" -> "),
+                    });
+                }
+
+                // The cycle does not include this section. It will be
+                // reported when checking a section in the cycle.
+                if chain.contains(&parent) {
+                    break;
+                }
+
+                chain.push(parent);
+                current = parent;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Adds an external symbol to the graph if it does not exist.
     ///
     /// The newly added symbol will be undefined.
@@ -561,11 +862,125 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
         });
     }
 
-    /// Returns an iterator over the names of the undefined symbols
+    /// Adds an external symbol provided by the loader to the graph.
+    ///
+    /// The symbol is not searched for in the link libraries and is written
+    /// to the output as an undefined external symbol if no input defines it.
+    pub fn add_loader_extern(&mut self, name: &'data str) {
+        self.add_external_symbol(name);
+        self.external_symbols[name].set_loader_extern();
+    }
+
+    /// Adds a section with a copy of `data` to the graph.
+    ///
+    /// The optional `symbol` is defined as an external symbol at the start
+    /// of the section. Defining a symbol which an input already defines
+    /// results in a duplicate symbol error.
+    pub fn add_section(
+        &mut self,
+        name: &'data str,
+        characteristics: SectionNodeCharacteristics,
+        data: &[u8],
+        symbol: Option<&'data str>,
+    ) {
+        let name = self.section_names.intern(name);
+        let section = self.arena.alloc_with(|| {
+            SectionNode::new(
+                name,
+                characteristics,
+                SectionNodeData::Initialized(self.arena.alloc_slice_copy(data)),
+                0,
+                self.root_coff,
+            )
+        });
+
+        if let Some(symbol_name) = symbol {
+            self.add_external_symbol(symbol_name);
+            let symbol = self.external_symbols[symbol_name];
+
+            let definition_edge = self
+                .arena
+                .alloc_with(|| Edge::new(symbol, section, DefinitionEdgeWeight::new(0, None)));
+
+            symbol.definitions().push_back(definition_edge);
+            section.definitions().push_back(definition_edge);
+        }
+
+        self.section_nodes.push(section);
+        self.node_count += 1;
+    }
+
+    /// Defines the undefined compiler intrinsics in the groups which have a
+    /// known implementation for the target architecture and returns the
+    /// names of the synthesized intrinsics.
+    ///
+    /// Intrinsics sharing an implementation are defined in the same
+    /// `.text$intr` or `.rdata$intr` section so the code is only added once.
+    pub fn synthesize_intrinsics(&mut self, groups: &[IntrinsicGroup]) -> Vec<&'data str> {
+        let mut synthesized = Vec::new();
+
+        for template in intrinsic_templates(self.machine, groups) {
+            let undefined = Vec::from_iter(template.symbols.iter().filter_map(|(name, offset)| {
+                self.external_symbols
+                    .get_key_value(name)
+                    .filter(|(_, symbol)| symbol.is_undefined())
+                    .map(|(name, symbol)| (*name, *symbol, *offset))
+            }));
+
+            if undefined.is_empty() {
+                continue;
+            }
+
+            let characteristics = match template.section {
+                IntrinsicSection::Code => {
+                    SectionNodeCharacteristics::CntCode
+                        | SectionNodeCharacteristics::MemExecute
+                        | SectionNodeCharacteristics::MemRead
+                        | match self.machine {
+                            LinkerTargetArch::Amd64 => SectionNodeCharacteristics::Align16Bytes,
+                            LinkerTargetArch::I386 => SectionNodeCharacteristics::Align4Bytes,
+                        }
+                }
+                IntrinsicSection::ReadOnlyData => {
+                    SectionNodeCharacteristics::CntInitializedData
+                        | SectionNodeCharacteristics::MemRead
+                        | SectionNodeCharacteristics::Align4Bytes
+                }
+            };
+
+            let section = self.arena.alloc_with(|| {
+                SectionNode::new(
+                    template.section.name(),
+                    characteristics,
+                    SectionNodeData::Initialized(template.code),
+                    0,
+                    self.root_coff,
+                )
+            });
+
+            for (name, symbol, offset) in undefined {
+                let definition_edge = self.arena.alloc_with(|| {
+                    Edge::new(symbol, section, DefinitionEdgeWeight::new(offset, None))
+                });
+
+                symbol.definitions().push_back(definition_edge);
+                section.definitions().push_back(definition_edge);
+                synthesized.push(name);
+            }
+
+            self.section_nodes.push(section);
+            self.node_count += 1;
+        }
+
+        synthesized
+    }
+
+    /// Returns an iterator over the names of the undefined symbols which are
+    /// not provided by the loader.
     pub fn undefined_symbols(&self) -> impl Iterator<Item = &'data str> + use<'_, 'data, 'arena> {
-        self.external_symbols
-            .iter()
-            .filter_map(|(name, symbol)| symbol.is_undefined().then_some(*name))
+        self.external_symbols.iter().filter_map(|(name, symbol)| {
+            (symbol.is_undefined() && !symbol.is_loader_extern()).then_some(*name)
+        })
     }
 
     /// Associates `symbol` as an API imported symbol with metadata from the
@@ -587,6 +1002,22 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
         self.add_import_edge(symbol, api_node, import)
     }
 
+    /// Sets the name used for `symbol` in the output COFF.
+    ///
+    /// # Panics
+    /// Panics if `symbol` does not exist.
+    pub fn set_output_name(&mut self, symbol: &str, name: &str) {
+        let symbol_node = self
+            .external_symbols
+            .get(symbol)
+            .copied()
+            .unwrap_or_else(|| panic!("symbol {symbol} does not exist"));
+
+        let _ = symbol_node
+            .output_rename()
+            .set(self.symbol_names.intern_copy(self.arena, name));
+    }
+
     /// Associates `symbol` with the specified [`ImportMember`].
     ///
     /// # Panics
@@ -628,15 +1059,20 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
             ImportName::Name(name) => name,
             ImportName::Ordinal(o) => {
                 warn!(
+                    symbol = symbol;
                     "found ordinal import value '{o}' for symbol \"{symbol}\". Linking public symbol name."
                 );
                 import.symbol
             }
         };
 
-        let import_edge = self
-            .arena
-            .alloc_with(|| Edge::new(symbol_node, library, ImportEdgeWeight::new(import_name)));
+        let import_edge = self.arena.alloc_with(|| {
+            Edge::new(
+                symbol_node,
+                library,
+                ImportEdgeWeight::new(import_name, import.typ),
+            )
+        });
 
         symbol_node.imports().push_back(import_edge);
         library.imports().push_back(import_edge);
@@ -649,7 +1085,9 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
         let mut symbol_errors = Vec::new();
 
         for symbol in self.external_symbols.values().copied() {
-            if symbol.is_undefined() {
+            if symbol.is_loader_extern() {
+                continue;
+            } else if symbol.is_undefined() {
                 symbol_errors.push(SymbolError::Undefined(UndefinedSymbolError(symbol)));
             } else if symbol.is_duplicate() {
                 symbol_errors.push(SymbolError::Duplicate(DuplicateSymbolError(symbol)));
@@ -664,6 +1102,23 @@ impl<'arena, 'data> LinkGraph<'arena, 'data> {
             return Err(symbol_errors);
         }
 
+        // The pseudo-relocator is normally run by the CRT startup code
+        for (name, symbol) in &self.external_symbols {
+            if !is_pseudo_reloc_symbol(name) || symbol.is_unreferenced() {
+                continue;
+            }
+
+            if let Some(reference) = symbol.references().front() {
+                let coff = reference.source().coff();
+                warn!(
+                    coff:% = coff.file_path().display(),
+                    member = coff.member_path().map(Path::to_string_lossy),
+                    symbol = name;
+                    "{coff}: references the MinGW runtime pseudo-relocation symbol '{name}'. BOF loaders do not run the pseudo-relocator so auto-imported data will not be relocated",
+                );
+            }
+        }
+
         Ok(BuiltLinkGraph::new(self))
     }
 
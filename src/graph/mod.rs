@@ -1,10 +1,29 @@
 mod built;
 mod cache;
+mod cref;
+mod crt;
 pub mod edge;
+pub mod feat;
+mod fixup;
+mod glob;
+mod inspect;
+mod intern;
+mod intrinsics;
+mod layout;
 mod link;
 pub mod node;
+mod profile;
+mod riprel;
 mod spec;
+mod strings;
 
 pub use built::*;
+pub use cref::*;
+pub use inspect::*;
+pub use intrinsics::IntrinsicGroup;
+pub use layout::*;
 pub use link::*;
+pub use profile::*;
+pub use riprel::RipRelativeError;
 pub use spec::*;
+pub use strings::{OutputString, StringEncoding};
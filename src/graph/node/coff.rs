@@ -19,6 +19,18 @@ impl<'data> CoffNode<'data> {
         }
     }
 
+    /// Returns the path of the COFF or the archive containing it.
+    #[inline]
+    pub fn file_path(&self) -> &'data Path {
+        self.file_path
+    }
+
+    /// Returns the archive member path if the COFF is an archive member.
+    #[inline]
+    pub fn member_path(&self) -> Option<&'data Path> {
+        self.member_path
+    }
+
     /// Returns a [`CoffNodeShortName`] for displaying a shortened version of
     /// the COFF name.
     #[inline]
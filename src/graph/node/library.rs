@@ -35,6 +35,14 @@ impl<'arena, 'data> LibraryNode<'arena, 'data> {
     }
 }
 
+impl std::fmt::Debug for LibraryNode<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LibraryNode")
+            .field("name", &self.weight.name)
+            .finish_non_exhaustive()
+    }
+}
+
 /// The weight for a library node.
 pub struct LibraryNodeWeight<'data> {
     /// The name of the library.
@@ -52,7 +60,12 @@ impl<'data> LibraryNodeWeight<'data> {
 #[derive(Debug, Clone, Copy)]
 pub struct LibraryName<'data>(&'data str);
 
-impl LibraryName<'_> {
+impl<'data> LibraryName<'data> {
+    #[inline]
+    pub fn as_str(&self) -> &'data str {
+        self.0
+    }
+
     pub fn trim_dll_suffix(&self) -> &str {
         self.0
             .rsplit_once('.')
@@ -23,7 +23,7 @@ use crate::graph::edge::{
     RelocationEdgeWeight,
 };
 
-use super::{CoffNode, SymbolNode};
+use super::{CoffNode, SymbolNode, SymbolNodeStorageClass};
 
 /// A section node in the graph.
 pub struct SectionNode<'arena, 'data> {
@@ -142,13 +142,13 @@ impl<'arena, 'data> SectionNode<'arena, 'data> {
 
     /// Marks this section as being discarded.
     #[inline]
-    pub fn discard(&self) {
+    pub(crate) fn discard(&self) {
         self.discarded.set(true);
     }
 
     /// Sets the discarded value for the section.
     #[inline]
-    pub fn set_discarded(&self, val: bool) {
+    pub(crate) fn set_discarded(&self, val: bool) {
         self.discarded.set(val);
     }
 
@@ -171,7 +171,8 @@ impl<'arena, 'data> SectionNode<'arena, 'data> {
         self.name().group_name() == ".debug"
             && self
                 .name()
-                .group_ordering()
+                .ordering_keys()
+                .next()
                 .is_some_and(|val| val == "S" || val == "T" || val == "P" || val == "F")
     }
 
@@ -202,7 +203,7 @@ impl<'arena, 'data> SectionNode<'arena, 'data> {
 
     /// Sets the size value if this section contains uninitialized data.
     #[inline]
-    pub fn set_uninitialized_size(&self, val: u32) {
+    pub(crate) fn set_uninitialized_size(&self, val: u32) {
         if matches!(self.data(), SectionNodeData::Uninitialized(_)) {
             self.data.set(SectionNodeData::Uninitialized(val));
         }
@@ -216,10 +217,71 @@ impl<'arena, 'data> SectionNode<'arena, 'data> {
 
     /// Replaces the checksum value for the section data.
     #[inline]
-    pub fn replace_checksum(&self, val: u32) {
+    pub(crate) fn replace_checksum(&self, val: u32) {
         self.checksum.set(val);
     }
 
+    /// Returns `true` if this section is an exact match of the other section.
+    ///
+    /// The section data, relocations and the offsets of the external symbols
+    /// defined in the sections must match.
+    pub fn is_exact_match(&self, other: &SectionNode<'arena, 'data>) -> bool {
+        if self.checksum() != other.checksum() || self.data() != other.data() {
+            return false;
+        }
+
+        if self.relocations().len() != other.relocations().len() {
+            return false;
+        }
+
+        let relocations_match = self
+            .relocations()
+            .iter()
+            .zip(other.relocations().iter())
+            .all(|(reloc, other_reloc)| {
+                let target = reloc.target();
+                let other_target = other_reloc.target();
+
+                // External symbols share the same node in the graph. Other
+                // symbols are local to the COFF and are compared by name.
+                let targets_match = if target.storage_class() == SymbolNodeStorageClass::External
+                    || other_target.storage_class() == SymbolNodeStorageClass::External
+                {
+                    std::ptr::eq(target, other_target)
+                } else {
+                    target.name() == other_target.name()
+                };
+
+                reloc.weight().address() == other_reloc.weight().address()
+                    && reloc.weight().typ() == other_reloc.weight().typ()
+                    && targets_match
+            });
+
+        if !relocations_match {
+            return false;
+        }
+
+        let external_definitions = |section: &SectionNode<'arena, 'data>| {
+            let mut definitions = section
+                .definitions()
+                .iter()
+                .filter(|definition| {
+                    definition.source().storage_class() == SymbolNodeStorageClass::External
+                })
+                .map(|definition| {
+                    (
+                        definition.source().name().as_str(),
+                        definition.weight().address(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            definitions.sort_unstable();
+            definitions
+        };
+
+        external_definitions(self) == external_definitions(other)
+    }
+
     /// Returns the assigned virtual address of the section.
     #[inline]
     pub fn virtual_address(&self) -> u32 {
@@ -228,11 +290,21 @@ impl<'arena, 'data> SectionNode<'arena, 'data> {
 
     /// Assigns a virtual address for the section.
     #[inline]
-    pub fn assign_virtual_address(&self, val: u32) {
+    pub(crate) fn assign_virtual_address(&self, val: u32) {
         self.virtual_address.set(val);
     }
 }
 
+impl std::fmt::Debug for SectionNode<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SectionNode")
+            .field("name", &self.name)
+            .field("coff", &self.coff)
+            .field("characteristics", &self.characteristics)
+            .finish_non_exhaustive()
+    }
+}
+
 /// A section name.
 #[derive(Debug, Clone, Copy)]
 pub struct SectionName<'data>(&'data str);
@@ -255,14 +327,37 @@ impl<'data> SectionName<'data> {
 
     /// Returns the `group ordering` value (`<group name>$<group ordering>`)
     /// from the section name if this is a grouped section.
+    ///
+    /// The value includes any further `$` separated ordering keys.
     #[inline]
     pub fn group_ordering(&self) -> Option<&str> {
         self.0
             .split_once('$')
             .map(|(_, group_ordering)| group_ordering)
     }
+
+    /// Returns the `$` separated keys in the `group ordering` value.
+    ///
+    /// Sections with names such as `.CRT$XCU$B` have multiple levels of
+    /// ordering keys. Grouped sections are ordered by comparing the keys
+    /// level by level so `.CRT$XCU$B` is placed after `.CRT$XCU` and before
+    /// `.CRT$XCV`.
+    #[inline]
+    pub fn ordering_keys(&self) -> impl Iterator<Item = &'data str> + use<'data> {
+        self.0.split('$').skip(1)
+    }
 }
 
+/// Interned names are compared by pointer before comparing the contents.
+impl PartialEq for SectionName<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for SectionName<'_> {}
+
 impl<'data> From<&'data str> for SectionName<'data> {
     fn from(value: &'data str) -> Self {
         Self(value)
@@ -57,9 +57,15 @@ pub struct SymbolNode<'arena, 'data> {
     /// The symbol name for the output COFF.
     output_name: OnceCell<object::write::coff::Name>,
 
+    /// Replacement name for the symbol in the output COFF.
+    output_rename: OnceCell<&'arena str>,
+
     /// Cached flag for checking if this is an MSVC label symbol.
     msvc_label: OnceCell<bool>,
 
+    /// The auxiliary function record from the defining input symbol.
+    function_aux: OnceCell<SymbolNodeFunctionAux>,
+
     /// The name of the symbol.
     name: SymbolName<'arena>,
 
@@ -71,6 +77,9 @@ pub struct SymbolNode<'arena, 'data> {
 
     /// The type of symbol.
     typ: Cell<SymbolNodeType>,
+
+    /// Whether the symbol is provided by the loader when it is undefined.
+    loader_extern: Cell<bool>,
 }
 
 impl<'arena, 'data> SymbolNode<'arena, 'data> {
@@ -87,11 +96,14 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
             relocation_edges: EdgeList::new(),
             table_index: OnceCell::new(),
             output_name: OnceCell::new(),
+            output_rename: OnceCell::new(),
             msvc_label: OnceCell::new(),
+            function_aux: OnceCell::new(),
             name: name.into(),
             storage_class,
             section,
             typ: Cell::new(typ),
+            loader_extern: Cell::new(false),
         }
     }
 
@@ -105,7 +117,9 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
             relocation_edges: EdgeList::new(),
             table_index: OnceCell::new(),
             output_name: OnceCell::new(),
+            output_rename: OnceCell::new(),
             msvc_label: OnceCell::new(),
+            function_aux: OnceCell::new(),
             name: name.into(),
             storage_class: coff_symbol.storage_class().try_into()?,
             section: coff_symbol.has_aux_section(),
@@ -114,6 +128,7 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
                 IMAGE_SYM_DEBUG => SymbolNodeType::Debug,
                 _ => SymbolNodeType::Value(coff_symbol.typ()),
             }),
+            loader_extern: Cell::new(false),
         })
     }
 
@@ -205,6 +220,18 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
         self.imports().is_empty() && self.definitions().is_empty()
     }
 
+    /// Marks the symbol as provided by the loader when it is undefined.
+    #[inline]
+    pub(crate) fn set_loader_extern(&self) {
+        self.loader_extern.set(true);
+    }
+
+    /// Returns `true` if the symbol is undefined and provided by the loader.
+    #[inline]
+    pub fn is_loader_extern(&self) -> bool {
+        self.loader_extern.get() && self.is_undefined()
+    }
+
     /// Returns `true` if this symbol has multiple non-COMDAT definitions.
     pub fn is_duplicate(&self) -> bool {
         self.definitions()
@@ -215,13 +242,19 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
     }
 
     /// Returns `true` if this symbol is multiply defined.
+    ///
+    /// `IMAGE_COMDAT_SELECT_NODUPLICATES` symbols are only multiply defined
+    /// if the definitions are from different COFFs. Repeated definitions in
+    /// the same COFF, such as a symbol defined again in a section associated
+    /// with the COMDAT, are not duplicates.
     pub fn is_multiply_defined(&self) -> bool {
         let mut noduplicates = false;
         let mut samesize = false;
         let mut exact_match = false;
 
         let mut sizes = HashSet::with_capacity(self.definitions().len());
-        let mut checksums = HashSet::with_capacity(self.definitions().len());
+        let mut exact_match_section: Option<&SectionNode<'arena, 'data>> = None;
+        let mut mismatched = false;
 
         for definition in self.definitions().iter() {
             let selection = match definition.weight().selection() {
@@ -238,19 +271,34 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
                     samesize = true;
                 }
                 ComdatSelection::ExactMatch => {
-                    // TODO: This will just check if the section data matches.
-                    // Also need to check that the relocations and definitions
-                    // match.
-                    checksums.insert(definition.target().checksum());
+                    let section = definition.target();
+                    match exact_match_section {
+                        Some(first) => {
+                            mismatched |= !first.is_exact_match(section);
+                        }
+                        None => exact_match_section = Some(section),
+                    }
                     exact_match = true;
                 }
                 _ => (),
             }
         }
 
-        (noduplicates && self.definitions().len() > 1)
+        (noduplicates && self.has_cross_coff_definitions())
             || (samesize && sizes.len() > 1)
-            || (exact_match && checksums.len() > 1)
+            || (exact_match && mismatched)
+    }
+
+    /// Returns `true` if the definitions of this symbol are from more than
+    /// one COFF.
+    fn has_cross_coff_definitions(&self) -> bool {
+        let mut definitions = self.definitions().iter();
+        let Some(first) = definitions.next() else {
+            return false;
+        };
+
+        let first_coff = first.target().coff();
+        definitions.any(|definition| !std::ptr::eq(definition.target().coff(), first_coff))
     }
 
     /// Returns the type associated with this symbol.
@@ -261,7 +309,7 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
 
     /// Sets the type to the specified value for this symbol.
     #[inline]
-    pub fn set_type(&self, val: u16) {
+    pub(crate) fn set_type(&self, val: u16) {
         self.typ.set(SymbolNodeType::Value(val));
     }
 
@@ -269,7 +317,7 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
     ///
     /// This can only be set once.
     #[inline]
-    pub fn assign_table_index(&self, value: u32) -> Result<(), u32> {
+    pub(crate) fn assign_table_index(&self, value: u32) -> Result<(), u32> {
         self.table_index.set(value)
     }
 
@@ -285,9 +333,34 @@ impl<'arena, 'data> SymbolNode<'arena, 'data> {
     ///
     /// Returns `None` if the name of the symbol was never added to the COFF.
     #[inline]
-    pub fn output_name(&self) -> &OnceCell<object::write::coff::Name> {
+    pub(crate) fn output_name(&self) -> &OnceCell<object::write::coff::Name> {
         &self.output_name
     }
+
+    /// Gets the replacement name for the symbol in the output COFF.
+    ///
+    /// The original symbol name is used if this is not set.
+    #[inline]
+    pub(crate) fn output_rename(&self) -> &OnceCell<&'arena str> {
+        &self.output_rename
+    }
+
+    /// Sets the auxiliary function record from the defining input symbol.
+    ///
+    /// This can only be set once.
+    #[inline]
+    pub(crate) fn set_function_aux(
+        &self,
+        aux: SymbolNodeFunctionAux,
+    ) -> Result<(), SymbolNodeFunctionAux> {
+        self.function_aux.set(aux)
+    }
+
+    /// Gets the auxiliary function record from the defining input symbol.
+    #[inline]
+    pub fn function_aux(&self) -> Option<SymbolNodeFunctionAux> {
+        self.function_aux.get().copied()
+    }
 }
 
 impl std::fmt::Debug for SymbolNode<'_, '_> {
@@ -325,6 +398,16 @@ impl<'data> SymbolName<'data> {
     }
 }
 
+/// Interned names are compared by pointer before comparing the contents.
+impl PartialEq for SymbolName<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for SymbolName<'_> {}
+
 impl<'data> From<&'data str> for SymbolName<'data> {
     fn from(value: &'data str) -> Self {
         Self(value)
@@ -385,6 +468,19 @@ pub enum SymbolNodeStorageClass {
     ClrToken = IMAGE_SYM_CLASS_CLR_TOKEN,
 }
 
+/// The values from an auxiliary function record.
+///
+/// The symbol table indices in the records are not kept since they are
+/// recalculated for the output symbol table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SymbolNodeFunctionAux {
+    /// Function definition record with the size of the function code.
+    Definition { total_size: u32 },
+
+    /// Record for a `.bf` or `.ef` symbol with the source line number.
+    BeginEnd { line_number: u16 },
+}
+
 /// The type of symbol.
 #[derive(Debug, Copy, Clone)]
 pub enum SymbolNodeType {
@@ -392,7 +488,7 @@ pub enum SymbolNodeType {
     Debug,
 
     /// An absolute symbol.
-    Absolute(#[allow(unused)] u32),
+    Absolute(u32),
 
     /// A defined symbol type value.
     Value(u16),
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LinkProfileParseError {
+    #[error("line {0}: expected '<hit count> <symbol>'")]
+    Syntax(usize),
+
+    #[error("line {0}: invalid hit count")]
+    Count(usize),
+}
+
+/// Symbol hit counts collected from running a previous build.
+///
+/// Code sections are ordered by the hit counts of the symbols they define
+/// with the most frequently hit sections first. Sections without any hits are
+/// considered cold and placed last.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinkProfile {
+    /// The hit counts for the symbol names.
+    hits: HashMap<String, u64>,
+}
+
+impl LinkProfile {
+    /// Creates a new empty [`LinkProfile`].
+    pub fn new() -> LinkProfile {
+        Self::default()
+    }
+
+    /// Parses a profile file.
+    ///
+    /// Each line contains a hit count followed by the symbol name. Counts for
+    /// the same symbol are added together so profiles from multiple runs can
+    /// be concatenated. Lines starting with `#` are ignored.
+    pub fn parse(content: &str) -> Result<LinkProfile, LinkProfileParseError> {
+        let mut profile = LinkProfile::new();
+
+        for (line_num, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((count, name)) = line.split_once(char::is_whitespace) else {
+                return Err(LinkProfileParseError::Syntax(line_num + 1));
+            };
+
+            let count = count
+                .parse()
+                .map_err(|_| LinkProfileParseError::Count(line_num + 1))?;
+
+            profile.add_hits(name.trim_start(), count);
+        }
+
+        Ok(profile)
+    }
+
+    /// Adds hits for the symbol.
+    pub fn add_hits(&mut self, name: impl Into<String>, count: u64) {
+        let hits = self.hits.entry(name.into()).or_default();
+        *hits = hits.saturating_add(count);
+    }
+
+    /// Returns the number of hits for the symbol.
+    pub fn hits(&self, name: &str) -> u64 {
+        self.hits.get(name).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LinkProfile, LinkProfileParseError};
+
+    #[test]
+    fn parse_counts() {
+        let profile =
+            LinkProfile::parse("# run 1\n120 go\n3 helper\n\n# run 2\n80 go\n0 unused\n").unwrap();
+
+        assert_eq!(profile.hits("go"), 200);
+        assert_eq!(profile.hits("helper"), 3);
+        assert_eq!(profile.hits("unused"), 0);
+        assert_eq!(profile.hits("missing"), 0);
+    }
+
+    #[test]
+    fn invalid_count() {
+        let err = LinkProfile::parse("go 12").unwrap_err();
+        assert!(matches!(err, LinkProfileParseError::Count(1)));
+    }
+}
@@ -0,0 +1,124 @@
+/// Size of the `movabs r64, imm64` instruction.
+pub(super) const MOVABS_SIZE: usize = 10;
+
+/// Offset of the 32-bit displacement in the rewritten `lea`.
+pub(super) const LEA_DISPLACEMENT_OFFSET: u32 = 3;
+
+/// The reason an absolute address load cannot be rewritten to a RIP-relative
+/// form.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RipRelativeError {
+    #[error("instruction is not a 'movabs r64, imm64'")]
+    UnknownInstruction,
+
+    #[error("addend {0:#x} does not fit in a 32-bit displacement")]
+    DisplacementOverflow(i64),
+
+    #[error("relocation is not in a code section")]
+    NotCode,
+
+    #[error("symbol '{0}' is not defined in the output")]
+    NotDefined(String),
+}
+
+/// Rewrites the `movabs r64, imm64` with the 64-bit address at `address` in
+/// `data` into `lea r64, [rip + disp32]` followed by a 3-byte `nop`.
+///
+/// The addend stored in the immediate becomes the displacement. The `lea`
+/// starts at the same address as the `movabs` and the displacement is at
+/// [`LEA_DISPLACEMENT_OFFSET`] from the start of the instruction.
+pub(super) fn rewrite_movabs(
+    data: &[u8],
+    address: usize,
+) -> Result<[u8; MOVABS_SIZE], RipRelativeError> {
+    let insn = address
+        .checked_sub(2)
+        .and_then(|start| data.get(start..start + MOVABS_SIZE))
+        .ok_or(RipRelativeError::UnknownInstruction)?;
+
+    // REX.W with an optional REX.B for r8-r15 followed by B8+rd
+    let (rex, opcode) = (insn[0], insn[1]);
+    if rex & 0xfe != 0x48 || opcode & 0xf8 != 0xb8 {
+        return Err(RipRelativeError::UnknownInstruction);
+    }
+
+    let addend = i64::from_le_bytes(insn[2..].try_into().unwrap());
+    let displacement =
+        i32::try_from(addend).map_err(|_| RipRelativeError::DisplacementOverflow(addend))?;
+
+    // The register moves from the opcode to ModRM.reg so REX.B becomes REX.R
+    let reg = opcode & 0x07;
+    let rex = 0x48 | ((rex & 0x01) << 2);
+
+    let mut rewritten = [0u8; MOVABS_SIZE];
+    rewritten[..3].copy_from_slice(&[rex, 0x8d, 0x05 | (reg << 3)]);
+    rewritten[3..7].copy_from_slice(&displacement.to_le_bytes());
+    rewritten[7..].copy_from_slice(&[0x0f, 0x1f, 0x00]);
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RipRelativeError, rewrite_movabs};
+
+    #[test]
+    fn rewrite_rax() {
+        // movabs rax, 0x10
+        let data = [0x48, 0xb8, 0x10, 0, 0, 0, 0, 0, 0, 0, 0xc3];
+        assert_eq!(
+            rewrite_movabs(&data, 2),
+            Ok([0x48, 0x8d, 0x05, 0x10, 0, 0, 0, 0x0f, 0x1f, 0x00])
+        );
+    }
+
+    #[test]
+    fn rewrite_extended_registers() {
+        // movabs rdi, 0
+        let data = [0x48, 0xbf, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            rewrite_movabs(&data, 2),
+            Ok([0x48, 0x8d, 0x3d, 0, 0, 0, 0, 0x0f, 0x1f, 0x00])
+        );
+
+        // movabs r11, -8
+        let mut data = vec![0x90, 0x49, 0xbb];
+        data.extend((-8i64).to_le_bytes());
+        assert_eq!(
+            rewrite_movabs(&data, 3),
+            Ok([0x4c, 0x8d, 0x1d, 0xf8, 0xff, 0xff, 0xff, 0x0f, 0x1f, 0x00])
+        );
+    }
+
+    #[test]
+    fn unknown_instructions() {
+        // mov eax, imm32 without REX.W
+        let data = [0x90, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            rewrite_movabs(&data, 2),
+            Err(RipRelativeError::UnknownInstruction)
+        );
+
+        // movabs rax, [moffs64]
+        let data = [0x48, 0xa1, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            rewrite_movabs(&data, 2),
+            Err(RipRelativeError::UnknownInstruction)
+        );
+
+        // Address in the first bytes of the section
+        assert_eq!(
+            rewrite_movabs(&[0; 8], 0),
+            Err(RipRelativeError::UnknownInstruction)
+        );
+    }
+
+    #[test]
+    fn displacement_overflow() {
+        let mut data = vec![0x48, 0xb8];
+        data.extend(0x1_0000_0000i64.to_le_bytes());
+        assert_eq!(
+            rewrite_movabs(&data, 2),
+            Err(RipRelativeError::DisplacementOverflow(0x1_0000_0000))
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use std::{cell::OnceCell, collections::LinkedList};
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet, LinkedList},
+};
 
 use indexmap::{IndexMap, IndexSet};
 use object::{
@@ -12,6 +15,7 @@ use super::{
     LinkGraph, ROOT_COFF,
     cache::LinkGraphCache,
     edge::{DefinitionEdgeWeight, Edge, RelocationEdgeWeight},
+    intern::StringPool,
     link::LinkGraphArena,
     node::{CoffNode, SectionNode, SymbolNode},
 };
@@ -116,6 +120,12 @@ impl SpecLinkGraph {
             api_node: None,
             external_symbols: IndexMap::with_capacity(self.externals),
             extraneous_symbols: LinkedList::new(),
+            coff_features: HashMap::new(),
+            import_coffs: HashSet::new(),
+            keep_symbols: Vec::new(),
+            strip_resources: false,
+            symbol_names: StringPool::default(),
+            section_names: StringPool::default(),
             cache: LinkGraphCache::with_capacity(self.max_symbols, self.max_sections),
             node_count: 0,
             arena,
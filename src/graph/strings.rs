@@ -0,0 +1,121 @@
+/// Minimum number of characters for a printable string.
+pub(super) const MIN_STRING_LEN: usize = 4;
+
+/// The encoding of a string found in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringEncoding {
+    /// Single byte ASCII characters.
+    Ascii,
+
+    /// UTF-16LE characters in the ASCII range.
+    Utf16,
+}
+
+/// A printable string in an initialized data section of the output along
+/// with the input section which contributed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputString {
+    /// The name of the output section.
+    pub section: String,
+
+    /// The offset of the string in the output section.
+    pub offset: u32,
+
+    /// The input COFF contributing the string.
+    pub coff: String,
+
+    /// The name of the input section.
+    pub input_section: String,
+
+    /// The encoding of the string.
+    pub encoding: StringEncoding,
+
+    /// The string contents.
+    pub value: String,
+}
+
+/// Returns the offsets, encodings and contents of the printable strings in
+/// the data with at least [`MIN_STRING_LEN`] characters.
+pub(super) fn find_strings(data: &[u8]) -> Vec<(usize, StringEncoding, String)> {
+    let mut strings = Vec::new();
+
+    let ascii_chars = data.iter().map(|c| Some(*c).filter(|c| is_printable(*c)));
+    collect_strings(ascii_chars, 1, 0, StringEncoding::Ascii, &mut strings);
+
+    for alignment in 0..2 {
+        let utf16_chars = data
+            .get(alignment..)
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .map(|pair| Some(pair[0]).filter(|c| pair[1] == 0 && is_printable(*c)));
+        collect_strings(
+            utf16_chars,
+            2,
+            alignment,
+            StringEncoding::Utf16,
+            &mut strings,
+        );
+    }
+
+    strings.sort_by_key(|(offset, _, _)| *offset);
+    strings
+}
+
+/// Adds the runs of printable characters to the found strings.
+///
+/// Non-printable characters are [`None`].
+fn collect_strings(
+    chars: impl Iterator<Item = Option<u8>>,
+    char_size: usize,
+    base: usize,
+    encoding: StringEncoding,
+    strings: &mut Vec<(usize, StringEncoding, String)>,
+) {
+    let mut current = String::new();
+    let mut start = 0;
+
+    for (idx, c) in chars.chain([None]).enumerate() {
+        match c {
+            Some(c) => {
+                if current.is_empty() {
+                    start = idx;
+                }
+
+                current.push(c as char);
+            }
+            None if current.len() >= MIN_STRING_LEN => {
+                strings.push((
+                    base + start * char_size,
+                    encoding,
+                    std::mem::take(&mut current),
+                ));
+            }
+            None => current.clear(),
+        }
+    }
+}
+
+/// Returns `true` if the byte is a printable ASCII character or a tab.
+fn is_printable(c: u8) -> bool {
+    c == b'\t' || (0x20..0x7f).contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StringEncoding, find_strings};
+
+    #[test]
+    fn ascii_and_utf16() {
+        let mut data = b"\x01abc\0hello world\0\x02".to_vec();
+        data.extend("C:\\x".encode_utf16().flat_map(u16::to_le_bytes));
+        data.extend_from_slice(&[0, 0]);
+
+        assert_eq!(
+            find_strings(&data),
+            [
+                (5, StringEncoding::Ascii, "hello world".to_string()),
+                (18, StringEncoding::Utf16, "C:\\x".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+
+/// Contents of a linker input file.
+///
+/// With the `mmap` feature enabled, files opened with [`InputData::read`] are
+/// memory mapped instead of being read into a buffer.
+pub enum InputData {
+    /// Data read into a buffer.
+    Buffer(Vec<u8>),
+
+    /// A memory mapped file.
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl InputData {
+    /// Opens the file at the specified path.
+    pub fn read(path: impl AsRef<Path>) -> std::io::Result<InputData> {
+        #[cfg(feature = "mmap")]
+        {
+            let file = std::fs::File::open(path)?;
+
+            // SAFETY: The mapping is read-only and input files are not
+            // expected to be modified while the linker is running. This is the
+            // same assumption other linkers make when mapping inputs.
+            #[allow(unsafe_code)]
+            let mapped = unsafe { memmap2::Mmap::map(&file)? };
+
+            Ok(InputData::Mapped(mapped))
+        }
+
+        #[cfg(not(feature = "mmap"))]
+        {
+            std::fs::read(path).map(InputData::Buffer)
+        }
+    }
+
+    /// Returns the data as a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            InputData::Buffer(buffer) => buffer.as_slice(),
+            #[cfg(feature = "mmap")]
+            InputData::Mapped(mapped) => mapped,
+        }
+    }
+}
+
+impl From<Vec<u8>> for InputData {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Buffer(value)
+    }
+}
+
+impl std::ops::Deref for InputData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for InputData {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputData;
+
+    #[test]
+    fn read_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+
+        let expected = std::fs::read(path).unwrap();
+        let data = InputData::read(path).unwrap();
+
+        assert_eq!(data.as_slice(), expected.as_slice());
+    }
+}
@@ -1,7 +1,12 @@
 mod api;
 mod drectve;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing;
 pub mod graph;
+pub mod inputdata;
 pub mod libsearch;
+pub mod link;
 pub mod linker;
 pub mod linkobject;
 pub mod pathed_item;
@@ -1,12 +1,26 @@
-use std::{borrow::Cow, io::ErrorKind, path::PathBuf};
+use std::{
+    borrow::Cow,
+    cell::{OnceCell, RefCell},
+    collections::{BTreeMap, HashMap},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::UNIX_EPOCH,
+};
 
 use indexmap::IndexSet;
-use log::debug;
+use log::{debug, warn};
 
-use crate::pathed_item::PathedItem;
+use crate::{inputdata::InputData, pathed_item::PathedItem};
 
 pub trait LibraryFind {
     fn find_library(&self, name: impl AsRef<str>) -> Result<FoundLibrary, LibsearchError>;
+
+    /// Adds a directory from a `/LIBPATH` directive to the search paths.
+    ///
+    /// Searchers without search paths ignore the directory.
+    fn add_search_path(&mut self, _path: &Path) {}
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -47,8 +61,27 @@ impl std::fmt::Display for SearchLibraryName<'_> {
     }
 }
 
+/// Returns the path with the `=` or `$SYSROOT` prefix replaced by the
+/// sysroot.
+///
+/// The prefix is removed if there is no sysroot like GNU ld. Paths without
+/// the prefix are returned unchanged.
+pub fn expand_sysroot<'a>(path: &'a Path, sysroot: Option<&Path>) -> Cow<'a, Path> {
+    let Some(relative) = path.to_str().and_then(|path| {
+        path.strip_prefix('=')
+            .or_else(|| path.strip_prefix("$SYSROOT"))
+    }) else {
+        return Cow::Borrowed(path);
+    };
+
+    match sysroot {
+        Some(sysroot) => Cow::Owned(sysroot.join(relative.trim_start_matches(['/', '\\']))),
+        None => Cow::Owned(PathBuf::from(relative)),
+    }
+}
+
 /// A read in link library found from the [`LibrarySearcher`].
-pub type FoundLibrary = PathedItem<PathBuf, Vec<u8>>;
+pub type FoundLibrary = PathedItem<PathBuf, InputData>;
 
 impl std::hash::Hash for FoundLibrary {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
@@ -64,10 +97,238 @@ impl std::cmp::PartialEq for FoundLibrary {
 
 impl std::cmp::Eq for FoundLibrary {}
 
+/// A cached library search result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LibraryCacheEntry {
+    /// Fingerprint of the search paths when the library was found.
+    fingerprint: u64,
+
+    /// Size of the library file.
+    size: u64,
+
+    /// Modification time of the library file in nanoseconds since the Unix
+    /// epoch.
+    mtime: u128,
+
+    /// Path to the library.
+    path: PathBuf,
+}
+
+impl LibraryCacheEntry {
+    /// Creates a new entry for the library at `path` returning `None` if the
+    /// metadata for the file could not be read.
+    fn new(fingerprint: u64, path: PathBuf) -> Option<LibraryCacheEntry> {
+        let (size, mtime) = file_stamp(&path)?;
+        Some(Self {
+            fingerprint,
+            size,
+            mtime,
+            path,
+        })
+    }
+
+    /// Returns `true` if the library file has not changed since it was
+    /// cached.
+    fn is_fresh(&self, fingerprint: u64) -> bool {
+        self.fingerprint == fingerprint
+            && file_stamp(&self.path).is_some_and(|stamp| stamp == (self.size, self.mtime))
+    }
+}
+
+/// On-disk cache of library search results.
+///
+/// Each line in the cache file holds the library name, search path
+/// fingerprint, file size, modification time and path separated by tabs.
+///
+/// Only the resolved library paths are cached. The archive symbol tables
+/// are parsed lazily from the library data when symbols are looked up so
+/// they are not stored in the cache.
+#[derive(Default, Clone)]
+struct LibraryCache {
+    /// Path to the cache file.
+    path: PathBuf,
+
+    /// The cached search results.
+    entries: HashMap<String, LibraryCacheEntry>,
+
+    /// Whether the entries were modified since the cache was loaded.
+    dirty: bool,
+}
+
+impl LibraryCache {
+    /// Loads the cache file at the specified path.
+    ///
+    /// Missing cache files and malformed lines are ignored.
+    fn load(path: PathBuf) -> LibraryCache {
+        let mut cache = LibraryCache {
+            path,
+            ..Default::default()
+        };
+
+        let content = match std::fs::read_to_string(&cache.path) {
+            Ok(content) => content,
+            Err(e) => {
+                if e.kind() != ErrorKind::NotFound {
+                    warn!("could not read library cache {}: {e}", cache.path.display());
+                }
+                return cache;
+            }
+        };
+
+        for line in content.lines() {
+            let mut parts = line.splitn(5, '\t');
+            let (Some(name), Some(fingerprint), Some(size), Some(mtime), Some(path)) = (
+                parts.next(),
+                parts.next(),
+                parts.next(),
+                parts.next(),
+                parts.next(),
+            ) else {
+                continue;
+            };
+
+            let (Ok(fingerprint), Ok(size), Ok(mtime)) = (
+                u64::from_str_radix(fingerprint, 16),
+                size.parse(),
+                mtime.parse(),
+            ) else {
+                continue;
+            };
+
+            cache.entries.insert(
+                name.to_string(),
+                LibraryCacheEntry {
+                    fingerprint,
+                    size,
+                    mtime,
+                    path: PathBuf::from(path),
+                },
+            );
+        }
+
+        cache
+    }
+
+    /// Writes the cache out to the cache file if it was modified.
+    ///
+    /// Entries written by other links since the cache was loaded are kept.
+    /// The cache is written to a temporary file which replaces the cache file
+    /// so that concurrent links never read a partially written cache.
+    fn save(&self) -> std::io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut entries = BTreeMap::from_iter(LibraryCache::load(self.path.clone()).entries);
+        entries.extend(
+            self.entries
+                .iter()
+                .map(|(name, entry)| (name.clone(), entry.clone())),
+        );
+
+        let mut content = String::new();
+        for (name, entry) in &entries {
+            content.push_str(&format!(
+                "{name}\t{:016x}\t{}\t{}\t{}\n",
+                entry.fingerprint,
+                entry.size,
+                entry.mtime,
+                entry.path.display()
+            ));
+        }
+
+        static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let mut temp_name = self.path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = self.path.with_file_name(temp_name);
+
+        std::fs::write(&temp_path, content)?;
+        std::fs::rename(&temp_path, &self.path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp_path);
+        })
+    }
+}
+
+/// Returns the size and modification time of the file.
+fn file_stamp(path: &Path) -> Option<(u64, u128)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let mtime = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+
+    Some((metadata.len(), mtime))
+}
+
+/// Returns the default path for the library search cache.
+///
+/// This is `boflink/libsearch` in the platform cache directory.
+pub fn default_cache_path() -> Option<PathBuf> {
+    let cache_dir = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+    }?;
+
+    Some(cache_dir.join("boflink").join("libsearch"))
+}
+
+/// Finds link libraries from buffers in memory.
+///
+/// This does not access the filesystem and can be used for linking when the
+/// libraries are provided by the caller.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryLibrarySearcher {
+    libraries: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryLibrarySearcher {
+    pub fn new() -> MemoryLibrarySearcher {
+        Default::default()
+    }
+
+    /// Adds a library which is found using the specified name.
+    pub fn add_library(&mut self, name: impl Into<String>, data: impl Into<Vec<u8>>) {
+        self.libraries.insert(name.into(), data.into());
+    }
+}
+
+impl LibraryFind for MemoryLibrarySearcher {
+    fn find_library(&self, name: impl AsRef<str>) -> Result<FoundLibrary, LibsearchError> {
+        self.libraries
+            .get(name.as_ref())
+            .map(|data| FoundLibrary::new(PathBuf::from(name.as_ref()), data.clone().into()))
+            .ok_or_else(|| LibsearchError::NotFound(name.as_ref().to_string()))
+    }
+}
+
 /// Used for finding link libraries.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct LibrarySearcher {
     search_paths: IndexSet<PathBuf>,
+
+    /// Sysroot for the search paths and library file names starting with
+    /// `=` or `$SYSROOT`.
+    sysroot: Option<PathBuf>,
+
+    /// Cache of library search results.
+    cache: Option<RefCell<LibraryCache>>,
+
+    /// Fingerprint of the search paths used for validating cache entries.
+    fingerprint: OnceCell<u64>,
 }
 
 impl LibrarySearcher {
@@ -75,6 +336,92 @@ impl LibrarySearcher {
         Default::default()
     }
 
+    /// Caches the library search results in the specified file.
+    ///
+    /// Cached results are used for libraries which have not changed since
+    /// they were found instead of searching the library search paths. The
+    /// cache file is updated when the searcher is dropped.
+    pub fn set_cache_path(&mut self, path: impl Into<PathBuf>) {
+        self.cache = Some(RefCell::new(LibraryCache::load(path.into())));
+    }
+
+    /// Returns the fingerprint of the search paths.
+    ///
+    /// This includes the modification times of the search directories so
+    /// that cached results are invalidated when libraries are added or
+    /// removed.
+    fn fingerprint(&self) -> u64 {
+        *self.fingerprint.get_or_init(|| {
+            let mut h = DefaultHasher::new();
+            for search_path in self.expanded_search_paths() {
+                search_path.hash(&mut h);
+                file_stamp(&search_path).hash(&mut h);
+            }
+            h.finish()
+        })
+    }
+
+    /// Returns the cached library if it has not changed.
+    fn find_cached(&self, name: &str) -> Option<FoundLibrary> {
+        let cache = self.cache.as_ref()?.borrow();
+        let entry = cache.entries.get(name)?;
+
+        if !entry.is_fresh(self.fingerprint()) {
+            debug!(library = name; "library cache entry for {name} is stale");
+            return None;
+        }
+
+        match InputData::read(&entry.path) {
+            Ok(data) => {
+                debug!(
+                    library = name,
+                    path:% = entry.path.display();
+                    "using cached library {} for {name}",
+                    entry.path.display()
+                );
+                Some(FoundLibrary::new(entry.path.clone(), data))
+            }
+            Err(e) => {
+                debug!(
+                    "could not open cached library {}: {e}",
+                    entry.path.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// Adds the library search result to the cache.
+    fn cache_result(&self, name: &str, path: &Path) {
+        let Some(cache) = self.cache.as_ref() else {
+            return;
+        };
+
+        let Some(entry) = LibraryCacheEntry::new(self.fingerprint(), path.to_path_buf()) else {
+            return;
+        };
+
+        let mut cache = cache.borrow_mut();
+        if cache.entries.get(name) != Some(&entry) {
+            cache.entries.insert(name.to_string(), entry);
+            cache.dirty = true;
+        }
+    }
+
+    /// Sets the sysroot for the search paths and library file names starting
+    /// with `=` or `$SYSROOT`.
+    pub fn set_sysroot(&mut self, sysroot: impl Into<PathBuf>) {
+        self.sysroot = Some(sysroot.into());
+        self.fingerprint.take();
+    }
+
+    /// Returns the search paths with the sysroot prefixes expanded.
+    fn expanded_search_paths(&self) -> impl Iterator<Item = Cow<'_, Path>> {
+        self.search_paths
+            .iter()
+            .map(|search_path| expand_sysroot(search_path, self.sysroot.as_deref()))
+    }
+
     pub fn extend_search_paths<I, P>(&mut self, search_paths: I)
     where
         I: IntoIterator<Item = P>,
@@ -82,16 +429,42 @@ impl LibrarySearcher {
     {
         self.search_paths
             .extend(search_paths.into_iter().map(|v| v.into()));
+        self.fingerprint.take();
     }
 }
 
 impl LibraryFind for LibrarySearcher {
+    fn add_search_path(&mut self, path: &Path) {
+        self.extend_search_paths([path]);
+    }
+
     fn find_library(&self, name: impl AsRef<str>) -> Result<FoundLibrary, LibsearchError> {
+        let library = SearchLibraryName::from(name.as_ref());
+
+        // Library file names relative to the sysroot are opened directly
+        if library.is_filename() {
+            let library_path = Path::new(library.value());
+            if let Cow::Owned(full_path) = expand_sysroot(library_path, self.sysroot.as_deref()) {
+                return match InputData::read(&full_path) {
+                    Ok(data) => Ok(FoundLibrary::new(full_path, data)),
+                    Err(e) if e.kind() == ErrorKind::NotFound => {
+                        Err(LibsearchError::NotFound(name.as_ref().to_string()))
+                    }
+                    Err(e) => Err(LibsearchError::Io {
+                        path: full_path,
+                        error: e,
+                    }),
+                };
+            }
+        }
+
         if self.search_paths.is_empty() {
             return Err(LibsearchError::NotFound(name.as_ref().to_string()));
         }
 
-        let library = SearchLibraryName::from(name.as_ref());
+        if let Some(found) = self.find_cached(name.as_ref()) {
+            return Ok(found);
+        }
 
         let library_filenames: Vec<Cow<'_, str>> = if !library.is_filename() {
             let name = library.value();
@@ -102,17 +475,20 @@ impl LibraryFind for LibrarySearcher {
                 format!("lib{name}.a").into(),
                 format!("{name}.lib").into(),
                 format!("lib{name}.lib").into(),
+                format!("lib{name}.dll").into(),
+                format!("{name}.dll").into(),
                 format!("{name}.a").into(),
             ]
         } else {
             vec![Cow::Borrowed(library.value())]
         };
 
-        for search_path in &self.search_paths {
+        for search_path in self.expanded_search_paths() {
             for filename in &library_filenames {
                 let full_path = search_path.join(filename.as_ref());
-                match std::fs::read(&full_path) {
+                match InputData::read(&full_path) {
                     Ok(data) => {
+                        self.cache_result(name.as_ref(), &full_path);
                         return Ok(FoundLibrary::new(full_path, data));
                     }
                     Err(e) if e.kind() != ErrorKind::NotFound => {
@@ -122,7 +498,13 @@ impl LibraryFind for LibrarySearcher {
                         });
                     }
                     Err(e) => {
-                        debug!("attempt to open {} failed ({})", full_path.display(), e);
+                        debug!(
+                            library = name.as_ref(),
+                            path:% = full_path.display();
+                            "attempt to open {} failed ({})",
+                            full_path.display(),
+                            e
+                        );
                     }
                 };
             }
@@ -131,3 +513,153 @@ impl LibraryFind for LibrarySearcher {
         Err(LibsearchError::NotFound(name.as_ref().to_string()))
     }
 }
+
+impl Drop for LibrarySearcher {
+    fn drop(&mut self) {
+        if let Some(cache) = self.cache.as_ref() {
+            let cache = cache.borrow();
+            if let Err(e) = cache.save() {
+                warn!(
+                    "could not write library cache {}: {e}",
+                    cache.path.display()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{LibraryFind, LibrarySearcher, expand_sysroot};
+
+    /// Creates an empty temporary directory for the test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("boflink-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn cached_search() {
+        let dir = test_dir("libsearch-cache");
+        let library_dir = dir.join("lib");
+        let cache_path = dir.join("cache");
+        std::fs::create_dir(&library_dir).unwrap();
+        std::fs::write(library_dir.join("libfoo.a"), b"first").unwrap();
+
+        let searcher = || {
+            let mut searcher = LibrarySearcher::new();
+            searcher.extend_search_paths([&library_dir]);
+            searcher.set_cache_path(&cache_path);
+            searcher
+        };
+
+        let found = searcher().find_library("foo").unwrap();
+        assert_eq!(found.path(), &library_dir.join("libfoo.a"));
+
+        let cache = std::fs::read_to_string(&cache_path).unwrap();
+        assert!(
+            cache.starts_with("foo\t"),
+            "cache should have the library entry"
+        );
+
+        // Adding a library with a higher priority invalidates the cache
+        std::fs::write(library_dir.join("libfoo.dll.a"), b"second").unwrap();
+
+        let found = searcher().find_library("foo").unwrap();
+        assert_eq!(found.path(), &library_dir.join("libfoo.dll.a"));
+        assert_eq!(found.as_slice(), b"second");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cache_merged_on_save() {
+        let dir = test_dir("libsearch-cache-merge");
+        let cache_path = dir.join("cache");
+        for name in ["foo", "bar"] {
+            let library_dir = dir.join(name);
+            std::fs::create_dir(&library_dir).unwrap();
+            std::fs::write(library_dir.join(format!("lib{name}.a")), name).unwrap();
+        }
+
+        let searcher = |name: &str| {
+            let mut searcher = LibrarySearcher::new();
+            searcher.extend_search_paths([dir.join(name)]);
+            searcher.set_cache_path(&cache_path);
+            searcher
+        };
+
+        // Both searchers load the cache before either one saves it
+        let foo_searcher = searcher("foo");
+        let bar_searcher = searcher("bar");
+        foo_searcher.find_library("foo").unwrap();
+        bar_searcher.find_library("bar").unwrap();
+        drop(foo_searcher);
+        drop(bar_searcher);
+
+        let cache = std::fs::read_to_string(&cache_path).unwrap();
+        let names = Vec::from_iter(cache.lines().filter_map(|line| line.split('\t').next()));
+        assert_eq!(names, ["bar", "foo"], "cache should keep both entries");
+
+        let files = Vec::from_iter(
+            std::fs::read_dir(&dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name()),
+        );
+        assert!(
+            !files
+                .iter()
+                .any(|name| name.to_string_lossy().ends_with(".tmp")),
+            "temporary cache files should be renamed: {files:?}"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sysroot_prefix() {
+        let sysroot = Some(Path::new("/opt/mingw"));
+        assert_eq!(
+            expand_sysroot(Path::new("=/lib"), sysroot),
+            Path::new("/opt/mingw/lib")
+        );
+        assert_eq!(
+            expand_sysroot(Path::new("$SYSROOT/lib"), sysroot),
+            Path::new("/opt/mingw/lib")
+        );
+        assert_eq!(expand_sysroot(Path::new("=/lib"), None), Path::new("/lib"));
+        assert_eq!(
+            expand_sysroot(Path::new("/usr/lib"), sysroot),
+            Path::new("/usr/lib")
+        );
+    }
+
+    #[test]
+    fn sysroot_search() {
+        let dir = test_dir("libsearch-sysroot");
+        let library_dir = dir.join("lib");
+        std::fs::create_dir(&library_dir).unwrap();
+        std::fs::write(library_dir.join("libfoo.a"), b"foo").unwrap();
+
+        let mut searcher = LibrarySearcher::new();
+        searcher.extend_search_paths(["=/lib"]);
+        searcher.set_sysroot(&dir);
+
+        let found = searcher.find_library("foo").unwrap();
+        assert_eq!(found.path(), &library_dir.join("libfoo.a"));
+
+        // File names relative to the sysroot do not need a search path
+        let mut searcher = LibrarySearcher::new();
+        searcher.set_sysroot(&dir);
+
+        let found = searcher.find_library(":=/lib/libfoo.a").unwrap();
+        assert_eq!(found.path(), &library_dir.join("libfoo.a"));
+        assert!(searcher.find_library(":=/lib/libbar.a").is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,212 @@
+//! High-level interface for running a link.
+//!
+//! This wraps the [`LinkerBuilder`] with a plain options struct for callers
+//! which do not need to configure the linker internals.
+//!
+//! ```no_run
+//! use boflink::link::{LinkInput, LinkOptions, link};
+//!
+//! let mut options = LinkOptions::default();
+//! options.inputs.push(LinkInput::file("bof.o"));
+//! options.libraries.push("kernel32".into());
+//! options.library_paths.push("/usr/x86_64-w64-mingw32/lib".into());
+//!
+//! let output = link(options).expect("could not link BOF");
+//! std::fs::write("bof.bof", output.output).unwrap();
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    inputdata::InputData,
+    libsearch::{
+        FoundLibrary, LibraryFind, LibrarySearcher, LibsearchError, MemoryLibrarySearcher,
+    },
+    linker::{LinkReport, LinkerBuilder, LinkerTargetArch, error::LinkError},
+    pathed_item::PathedItem,
+};
+
+/// An input file for the link.
+#[derive(Debug, Clone)]
+pub enum LinkInput {
+    /// A file read from the path.
+    File(PathBuf),
+
+    /// A file in memory with the name used in diagnostics.
+    Memory { name: PathBuf, data: Vec<u8> },
+}
+
+impl LinkInput {
+    /// Creates an input for the file at the path.
+    pub fn file(path: impl Into<PathBuf>) -> LinkInput {
+        Self::File(path.into())
+    }
+
+    /// Creates an input for the file contents in memory.
+    pub fn memory(name: impl Into<PathBuf>, data: impl Into<Vec<u8>>) -> LinkInput {
+        Self::Memory {
+            name: name.into(),
+            data: data.into(),
+        }
+    }
+}
+
+/// Options for [`link`].
+///
+/// New options may be added in later versions. Create the options using
+/// [`LinkOptions::default`] and set the needed fields.
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct LinkOptions {
+    /// The input files to link.
+    pub inputs: Vec<LinkInput>,
+
+    /// Names of the link libraries.
+    pub libraries: Vec<String>,
+
+    /// Directories to search for link libraries.
+    pub library_paths: Vec<PathBuf>,
+
+    /// Link libraries in memory along with the names used for finding them.
+    ///
+    /// These are searched before the library paths.
+    pub library_buffers: Vec<(String, Vec<u8>)>,
+
+    /// The target architecture. Detected from the inputs if not set.
+    pub machine: Option<LinkerTargetArch>,
+
+    /// Name of the entrypoint symbol.
+    pub entrypoint: Option<String>,
+
+    /// Custom APIs to use instead of the Beacon API in priority order.
+    pub custom_apis: Vec<String>,
+
+    /// Initialize the .bss section and merge it with the .data section.
+    pub merge_bss: bool,
+
+    /// Order the output symbols independently of the input order.
+    pub deterministic: bool,
+
+    /// Skip archive members which fail to parse.
+    pub tolerant: bool,
+
+    /// Require all inputs to be compatible with safe exception handlers.
+    pub safeseh: bool,
+
+    /// Minimum alignment for output sections.
+    pub min_section_alignment: Option<u32>,
+
+    /// Apply the default alignment for well-known sections.
+    pub default_section_alignment: bool,
+}
+
+impl Default for LinkOptions {
+    fn default() -> Self {
+        Self {
+            inputs: Vec::new(),
+            libraries: Vec::new(),
+            library_paths: Vec::new(),
+            library_buffers: Vec::new(),
+            machine: None,
+            entrypoint: None,
+            custom_apis: Vec::new(),
+            merge_bss: false,
+            deterministic: false,
+            tolerant: false,
+            safeseh: false,
+            min_section_alignment: None,
+            default_section_alignment: true,
+        }
+    }
+}
+
+/// The result of a successful [`link`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LinkOutput {
+    /// The linked COFF.
+    pub output: Vec<u8>,
+
+    /// Information collected during the link.
+    pub report: LinkReport,
+}
+
+/// Searches the in-memory libraries before the library paths.
+struct OptionsLibrarySearcher {
+    memory: MemoryLibrarySearcher,
+    paths: LibrarySearcher,
+}
+
+impl LibraryFind for OptionsLibrarySearcher {
+    fn find_library(&self, name: impl AsRef<str>) -> Result<FoundLibrary, LibsearchError> {
+        match self.memory.find_library(name.as_ref()) {
+            Err(LibsearchError::NotFound(_)) => self.paths.find_library(name),
+            res => res,
+        }
+    }
+
+    fn add_search_path(&mut self, path: &Path) {
+        self.paths.add_search_path(path);
+    }
+}
+
+/// Links the inputs using the options.
+///
+/// The filesystem is only accessed for [`LinkInput::File`] inputs and the
+/// library paths.
+pub fn link(options: LinkOptions) -> Result<LinkOutput, LinkError> {
+    let mut library_searcher = OptionsLibrarySearcher {
+        memory: MemoryLibrarySearcher::new(),
+        paths: LibrarySearcher::new(),
+    };
+
+    library_searcher
+        .paths
+        .extend_search_paths(options.library_paths);
+
+    for (name, data) in options.library_buffers {
+        library_searcher.memory.add_library(name, data);
+    }
+
+    let mut inputs = Vec::with_capacity(options.inputs.len());
+    for input in options.inputs {
+        inputs.push(match input {
+            LinkInput::File(path) => match InputData::read(&path) {
+                Ok(data) => PathedItem::new(path, data),
+                Err(error) => return Err(LinkError::Io { path, error }),
+            },
+            LinkInput::Memory { name, data } => PathedItem::new(name, InputData::from(data)),
+        });
+    }
+
+    let mut linker = LinkerBuilder::new()
+        .library_searcher(library_searcher)
+        .add_inputs(inputs)
+        .add_libraries(options.libraries)
+        .custom_apis(options.custom_apis)
+        .merge_bss(options.merge_bss)
+        .deterministic(options.deterministic)
+        .tolerant(options.tolerant)
+        .safeseh(options.safeseh)
+        .default_section_alignment(options.default_section_alignment);
+
+    if let Some(machine) = options.machine {
+        linker = linker.architecture(machine);
+    }
+
+    if let Some(entrypoint) = options.entrypoint {
+        linker = linker.entrypoint(entrypoint);
+    }
+
+    if let Some(alignment) = options.min_section_alignment {
+        linker = linker.min_section_alignment(alignment);
+    }
+
+    let mut linker = linker.build();
+    let output = linker.link()?;
+
+    Ok(LinkOutput {
+        output,
+        report: linker.report().clone(),
+    })
+}
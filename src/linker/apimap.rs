@@ -0,0 +1,175 @@
+use super::error::{ApiSymbolMapError, ApiSymbolMapParseError};
+
+/// A rule for mapping an API symbol name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ApiSymbolMapRule {
+    /// Replaces the exact symbol name.
+    Exact { from: String, to: String },
+
+    /// Replaces the prefix of the symbol name.
+    Prefix { from: String, to: String },
+}
+
+impl ApiSymbolMapRule {
+    /// Applies the rule to the symbol name returning the mapped name if it
+    /// matches.
+    fn apply(&self, name: &str) -> Option<String> {
+        match self {
+            Self::Exact { from, to } => (name == from).then(|| to.clone()),
+            Self::Prefix { from, to } => name
+                .strip_prefix(from.as_str())
+                .map(|suffix| format!("{to}{suffix}")),
+        }
+    }
+}
+
+/// Mapping of symbol names used for resolving API symbols.
+///
+/// Objects referencing a mapped symbol name are resolved against the API
+/// using the replacement name.
+#[derive(Debug, Default, Clone)]
+pub struct ApiSymbolMap {
+    rules: Vec<ApiSymbolMapRule>,
+}
+
+impl ApiSymbolMap {
+    /// Creates a new empty [`ApiSymbolMap`].
+    pub fn new() -> ApiSymbolMap {
+        Self::default()
+    }
+
+    /// Parses a mapping file.
+    ///
+    /// Each non-empty line contains the symbol name to map followed by the
+    /// replacement name separated by whitespace. Names ending with a `*` map
+    /// the prefix of the symbol. Lines starting with `#` are ignored.
+    pub fn parse(content: &str) -> Result<ApiSymbolMap, ApiSymbolMapParseError> {
+        let mut map = ApiSymbolMap::new();
+
+        for (line_num, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let (Some(from), Some(to), None) = (parts.next(), parts.next(), parts.next()) else {
+                return Err(ApiSymbolMapParseError::Syntax(line_num + 1));
+            };
+
+            map.add(from, to)
+                .map_err(|_| ApiSymbolMapParseError::Prefix(line_num + 1))?;
+        }
+
+        Ok(map)
+    }
+
+    /// Adds a mapping from the symbol name `from` to `to`.
+    ///
+    /// Names ending with a `*` map the prefix of the symbol. Returns an error
+    /// if only one of the names is a prefix.
+    pub fn add(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Result<(), ApiSymbolMapError> {
+        let (from, to) = (from.into(), to.into());
+        let rule = match (from.strip_suffix('*'), to.strip_suffix('*')) {
+            (Some(from), Some(to)) => ApiSymbolMapRule::Prefix {
+                from: from.to_string(),
+                to: to.to_string(),
+            },
+            (None, None) => ApiSymbolMapRule::Exact {
+                from: from.to_string(),
+                to: to.to_string(),
+            },
+            _ => return Err(ApiSymbolMapError { from, to }),
+        };
+
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    /// Returns `true` if the map does not contain any mappings.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Maps the symbol name returning the replacement name.
+    ///
+    /// The `__imp_` prefix and the leading underscore on i386 symbols are
+    /// kept. Returns `None` if the symbol is not mapped.
+    pub fn map_symbol(&self, symbol: &str) -> Option<String> {
+        let (dllimport, name) = match symbol.strip_prefix("__imp_") {
+            Some(name) => ("__imp_", name),
+            None => ("", symbol),
+        };
+
+        let mut candidates = vec![("", name)];
+        if let Some(undecorated) = name.strip_prefix('_') {
+            candidates.push(("_", undecorated));
+        }
+
+        candidates.into_iter().find_map(|(decoration, name)| {
+            self.rules
+                .iter()
+                .find_map(|rule| rule.apply(name))
+                .map(|mapped| format!("{dllimport}{decoration}{mapped}"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiSymbolMap;
+
+    #[test]
+    fn prefix_mapping() {
+        let map = ApiSymbolMap::parse("# Beacon API\nBeacon* BofApi*\n").unwrap();
+
+        assert_eq!(
+            map.map_symbol("__imp_BeaconPrintf").as_deref(),
+            Some("__imp_BofApiPrintf")
+        );
+        assert_eq!(
+            map.map_symbol("__imp__BeaconPrintf").as_deref(),
+            Some("__imp__BofApiPrintf")
+        );
+        assert_eq!(
+            map.map_symbol("BeaconOutput").as_deref(),
+            Some("BofApiOutput")
+        );
+        assert_eq!(map.map_symbol("toWideChar"), None);
+    }
+
+    #[test]
+    fn exact_mapping() {
+        let map = ApiSymbolMap::parse("toWideChar BofApiToWideChar").unwrap();
+
+        assert_eq!(
+            map.map_symbol("__imp_toWideChar").as_deref(),
+            Some("__imp_BofApiToWideChar")
+        );
+        assert_eq!(map.map_symbol("toWideCharA"), None);
+    }
+
+    #[test]
+    fn invalid_lines() {
+        assert!(ApiSymbolMap::parse("BeaconPrintf").is_err());
+        assert!(ApiSymbolMap::parse("a b c").is_err());
+        assert!(ApiSymbolMap::parse("Beacon* BofApiPrintf").is_err());
+    }
+
+    #[test]
+    fn mixed_prefix_add() {
+        let mut map = ApiSymbolMap::new();
+
+        let error = map.add("Beacon*", "BofApiPrintf").unwrap_err();
+        assert_eq!(error.from, "Beacon*");
+        assert_eq!(error.to, "BofApiPrintf");
+        assert!(map.is_empty(), "invalid mapping should not be added");
+
+        map.add("Beacon*", "BofApi*").unwrap();
+        assert!(!map.is_empty());
+    }
+}
@@ -1,32 +1,89 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use indexmap::IndexSet;
 
 use crate::{
     api::BeaconApiInit,
+    graph::{
+        CharacteristicsPolicy, IntrinsicGroup, LinkGraph, LinkLayout, LinkProfile, LnkInfoPolicy,
+        SymbolRenameMode,
+    },
+    inputdata::InputData,
     libsearch::{LibraryFind, LibrarySearcher},
     pathed_item::PathedItem,
 };
 
-use super::{ConfiguredLinker, CustomApiInit, LinkImpl, LinkerTargetArch};
+use super::{
+    AddedSection, ApiSymbolMap, ConfiguredLinker, CustomApiInit, LinkImpl, LinkerTargetArch,
+    OutputTrailer, error::ApiSymbolMapError,
+};
+
+/// Callback for inspecting the link graph.
+pub type GraphInspector = Arc<dyn Fn(&LinkGraph<'_, '_>) + Send + Sync>;
+
+/// A link library added to the builder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct LibraryOccurrence {
+    /// The name of the link library.
+    pub name: String,
+
+    /// The number of inputs added before the library.
+    pub inputs_before: usize,
+
+    /// The library group containing the library.
+    pub group: Option<usize>,
+}
 
 /// Sets up inputs and configures a [`super::Linker`].
-#[derive(Default)]
+///
+/// Options may be set in any order. A builder can be cloned as a template for
+/// running multiple links with different options. The input data is shared
+/// between the clones.
+#[derive(Clone)]
 pub struct LinkerBuilder<L: LibraryFind + 'static> {
     /// The target architecture.
     pub(super) target_arch: Option<LinkerTargetArch>,
 
     /// The input files to link.
-    pub(super) inputs: Vec<PathedItem<PathBuf, Vec<u8>>>,
+    pub(super) inputs: Vec<PathedItem<PathBuf, Arc<InputData>>>,
+
+    /// The input files which are only linked if they define an undefined
+    /// symbol.
+    pub(super) lazy_inputs: Vec<PathedItem<PathBuf, Arc<InputData>>>,
+
+    /// Module-definition files with exports which are resolved as imports.
+    pub(super) module_definitions: Vec<PathedItem<PathBuf, Arc<InputData>>>,
 
     /// Link libraries.
     pub(super) libraries: IndexSet<String>,
 
+    /// Each time a link library was added in order.
+    pub(super) library_occurrences: Vec<LibraryOccurrence>,
+
+    /// The number of library groups started.
+    pub(super) library_groups: usize,
+
+    /// The library group which libraries are currently added to.
+    pub(super) open_library_group: Option<usize>,
+
+    /// Whether to only search link libraries for the symbols referenced
+    /// before them.
+    pub(super) positional_libraries: bool,
+
     /// The name of the entrypoint symbol.
     pub(super) entrypoint: Option<String>,
 
-    /// Custom BOF API to use.
-    pub(super) custom_api: Option<String>,
+    /// Whether to place the section defining the entrypoint first.
+    pub(super) entry_first: bool,
+
+    /// Custom BOF APIs to use in priority order.
+    pub(super) custom_api: Vec<String>,
+
+    /// Mapping of symbol names used for resolving API symbols.
+    pub(super) api_symbol_map: ApiSymbolMap,
+
+    /// Invalid API symbol mappings reported when linking.
+    pub(super) api_symbol_map_errors: Vec<ApiSymbolMapError>,
 
     /// Whether to merge the .bss section with the .data section.
     pub(super) merge_bss: bool,
@@ -36,6 +93,156 @@ pub struct LinkerBuilder<L: LibraryFind + 'static> {
 
     /// Output path for dumping the link graph.
     pub(super) link_graph_output: Option<PathBuf>,
+
+    /// Callback for inspecting the link graph after resolving symbols.
+    pub(super) graph_inspector: Option<GraphInspector>,
+
+    /// Naming scheme for renaming symbols in the output COFF.
+    pub(super) rename_symbols: Option<SymbolRenameMode>,
+
+    /// Output path for the renamed symbol map.
+    pub(super) symbol_map_output: Option<PathBuf>,
+
+    /// Byte used for padding code sections.
+    pub(super) code_fill: Option<u8>,
+
+    /// Byte used for padding data sections.
+    pub(super) data_fill: Option<u8>,
+
+    /// Minimum alignment for output sections.
+    pub(super) min_section_alignment: Option<u32>,
+
+    /// Whether to apply the default alignment for well-known sections.
+    pub(super) default_section_alignment: bool,
+
+    /// Whether to apply the relocations targeting the same output section.
+    pub(super) flatten_relocations: bool,
+
+    /// Whether to report cross-section relocations in the output.
+    pub(super) check_relocations: bool,
+
+    /// Whether to order the output symbols independently of the input order.
+    pub(super) deterministic: bool,
+
+    /// Whether to skip archive members which fail to parse.
+    pub(super) tolerant: bool,
+
+    /// Whether to skip inputs with the same contents as a previous input.
+    pub(super) dedup_inputs: bool,
+
+    /// Whether to warn about link libraries which were not used.
+    pub(super) warn_unused_libraries: bool,
+
+    /// Names of the absolute symbols to keep in the output.
+    pub(super) absolute_symbols: IndexSet<String>,
+
+    /// Name patterns for the symbols to keep.
+    pub(super) keep_symbols: IndexSet<String>,
+
+    /// Name patterns for the input sections to keep.
+    pub(super) keep_sections: IndexSet<String>,
+
+    /// Symbols provided by the loader which are left undefined.
+    pub(super) loader_externs: IndexSet<String>,
+
+    /// Files added to the output as sections.
+    pub(super) added_sections: Vec<AddedSection>,
+
+    /// Symbols with the references redirected to another symbol.
+    pub(super) redirected_symbols: Vec<(String, String)>,
+
+    /// Groups of the undefined compiler intrinsics to define.
+    pub(super) intrinsic_groups: IndexSet<IntrinsicGroup>,
+
+    /// Names of the DLLs with imports routed through the delay import table.
+    pub(super) delay_load_libraries: IndexSet<String>,
+
+    /// Whether all inputs need to be compatible with safe exception handlers.
+    pub(super) safeseh: bool,
+
+    /// How conflicting section memory access flags are handled.
+    pub(super) characteristics_policy: CharacteristicsPolicy,
+
+    /// How the `IMAGE_SCN_LNK_INFO` input sections are handled.
+    pub(super) lnk_info_policy: LnkInfoPolicy,
+
+    /// Whether to add a `.comment` section with the linker version and
+    /// inputs.
+    pub(super) emit_comment: bool,
+
+    /// User string for the `.ident` section with the linker version.
+    pub(super) ident: Option<String>,
+
+    /// Layout from a previous link to preserve.
+    pub(super) layout: Option<LinkLayout>,
+
+    /// Output path for the layout of the linked output.
+    pub(super) layout_output: Option<PathBuf>,
+
+    /// Symbol hit counts for ordering the code sections.
+    pub(super) profile: Option<LinkProfile>,
+
+    /// Whether to split the code without profile hits into `.text$cold`.
+    pub(super) split_cold: bool,
+
+    /// Whether to order the input sections by alignment to reduce padding.
+    pub(super) pack_sections: bool,
+
+    /// Alignment for the total size of the output file.
+    pub(super) output_alignment: Option<u32>,
+
+    /// Data appended after the output COFF.
+    pub(super) output_trailer: Option<OutputTrailer>,
+
+    /// Whether to add the table of output section checksums.
+    pub(super) section_checksums: bool,
+
+    /// Whether to write the auxiliary function records from the inputs.
+    pub(super) function_aux: bool,
+
+    /// Whether to replace the loads through `.refptr` stubs with direct
+    /// references.
+    pub(super) collapse_refptrs: bool,
+
+    /// Whether to rewrite the absolute address loads into RIP-relative
+    /// forms.
+    pub(super) rip_relative: bool,
+
+    /// Whether to discard the `.rsrc` resource sections in the inputs.
+    pub(super) strip_resources: bool,
+
+    /// Names of the output sections with obfuscated contents.
+    pub(super) obfuscated_sections: IndexSet<String>,
+
+    /// Key for obfuscating the section contents.
+    pub(super) obfuscation_key: Option<Vec<u8>>,
+
+    /// Whether to collect the symbol cross-reference table.
+    pub(super) cross_reference: bool,
+
+    /// Whether to collect the printable strings in the output data sections.
+    pub(super) collect_strings: bool,
+
+    /// Whether to estimate the sizes of the symbols in the output.
+    pub(super) collect_symbol_sizes: bool,
+
+    /// Symbols to record the resolution steps for.
+    pub(super) why_undefined: IndexSet<String>,
+
+    /// Output path for the archive of the link inputs.
+    pub(super) reproduce_output: Option<PathBuf>,
+
+    /// Response file for replaying the link from the archive.
+    pub(super) reproduce_response: String,
+
+    /// Files read by the caller to include in the archive.
+    pub(super) reproduce_files: Vec<PathedItem<PathBuf, Vec<u8>>>,
+}
+
+impl<L: LibraryFind + 'static> Default for LinkerBuilder<L> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<L: LibraryFind + 'static> LinkerBuilder<L> {
@@ -44,12 +251,68 @@ impl<L: LibraryFind + 'static> LinkerBuilder<L> {
         Self {
             target_arch: Default::default(),
             inputs: Default::default(),
+            lazy_inputs: Default::default(),
+            module_definitions: Default::default(),
             libraries: Default::default(),
+            library_occurrences: Vec::new(),
+            library_groups: 0,
+            open_library_group: None,
+            positional_libraries: false,
             entrypoint: Default::default(),
+            entry_first: false,
             custom_api: Default::default(),
+            api_symbol_map: Default::default(),
+            api_symbol_map_errors: Vec::new(),
             merge_bss: false,
             library_searcher: None,
             link_graph_output: None,
+            graph_inspector: None,
+            rename_symbols: None,
+            symbol_map_output: None,
+            code_fill: None,
+            data_fill: None,
+            min_section_alignment: None,
+            default_section_alignment: true,
+            flatten_relocations: true,
+            check_relocations: false,
+            deterministic: false,
+            tolerant: false,
+            dedup_inputs: false,
+            warn_unused_libraries: false,
+            absolute_symbols: Default::default(),
+            keep_symbols: Default::default(),
+            keep_sections: Default::default(),
+            loader_externs: Default::default(),
+            added_sections: Vec::new(),
+            redirected_symbols: Vec::new(),
+            intrinsic_groups: Default::default(),
+            delay_load_libraries: Default::default(),
+            safeseh: false,
+            characteristics_policy: CharacteristicsPolicy::default(),
+            lnk_info_policy: LnkInfoPolicy::default(),
+            emit_comment: false,
+            ident: None,
+            layout: None,
+            layout_output: None,
+            profile: None,
+            split_cold: false,
+            pack_sections: false,
+            output_alignment: None,
+            output_trailer: None,
+            section_checksums: false,
+            function_aux: false,
+            collapse_refptrs: false,
+            rip_relative: false,
+            strip_resources: false,
+            obfuscated_sections: Default::default(),
+            obfuscation_key: None,
+            cross_reference: false,
+            collect_strings: false,
+            collect_symbol_sizes: false,
+            why_undefined: Default::default(),
+            reproduce_output: None,
+            reproduce_response: String::new(),
+            reproduce_files: Vec::new(),
         }
     }
 
@@ -68,15 +331,520 @@ impl<L: LibraryFind + 'static> LinkerBuilder<L> {
         self
     }
 
+    /// Set a callback for inspecting the link graph after the symbols are
+    /// resolved.
+    ///
+    /// The callback is run before the graph is checked for undefined
+    /// symbols. See [`LinkGraph::nodes`] for walking the graph.
+    pub fn inspect_graph(
+        mut self,
+        inspector: impl Fn(&LinkGraph<'_, '_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.graph_inspector = Some(Arc::new(inspector));
+        self
+    }
+
+    /// Set the name of the entrypoint symbol.
+    pub fn entrypoint(mut self, name: impl Into<String>) -> Self {
+        self.entrypoint = Some(name.into());
+        self
+    }
+
+    /// Place the section defining the entrypoint symbol at the start of
+    /// `.text` for loaders which begin executing there.
+    ///
+    /// The entrypoint defaults to `go` if it is not set.
+    pub fn entry_first(mut self, val: bool) -> Self {
+        self.entry_first = val;
+        self
+    }
+
+    /// Rename non-essential symbols in the output COFF using the specified
+    /// naming scheme.
+    pub fn rename_symbols(mut self, mode: SymbolRenameMode) -> Self {
+        self.rename_symbols = Some(mode);
+        self
+    }
+
+    /// Set the output path for writing the map of renamed symbols.
+    pub fn symbol_map_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.symbol_map_output = Some(path.into());
+        self
+    }
+
+    /// Set the byte used for padding code sections.
+    pub fn code_fill(mut self, val: u8) -> Self {
+        self.code_fill = Some(val);
+        self
+    }
+
+    /// Set the byte used for padding data sections.
+    pub fn data_fill(mut self, val: u8) -> Self {
+        self.data_fill = Some(val);
+        self
+    }
+
+    /// Set the minimum alignment for output sections.
+    pub fn min_section_alignment(mut self, val: u32) -> Self {
+        self.min_section_alignment = Some(val);
+        self
+    }
+
+    /// Apply the default minimum alignment for input sections in well-known
+    /// output sections.
+    ///
+    /// This is enabled by default and aligns the `.text` and `.rdata` input
+    /// sections to 16 bytes for amd64 like MSVC does.
+    pub fn default_section_alignment(mut self, val: bool) -> Self {
+        self.default_section_alignment = val;
+        self
+    }
+
+    /// Apply the relocations targeting symbols in the same output section
+    /// instead of writing them to the output.
+    ///
+    /// This is enabled by default. Disabling it writes a relocation for every
+    /// reference.
+    pub fn flatten_relocations(mut self, val: bool) -> Self {
+        self.flatten_relocations = val;
+        self
+    }
+
+    /// Report cross-section relocations in the output.
+    pub fn check_relocations(mut self, val: bool) -> Self {
+        self.check_relocations = val;
+        self
+    }
+
+    /// Order the output symbols independently of the order of the inputs.
+    pub fn deterministic(mut self, val: bool) -> Self {
+        self.deterministic = val;
+        self
+    }
+
+    /// Skip archive members which fail to parse with a warning instead of
+    /// failing the link.
+    pub fn tolerant(mut self, val: bool) -> Self {
+        self.tolerant = val;
+        self
+    }
+
+    /// Skip input COFFs and archive members with the same contents as a
+    /// previously added input.
+    ///
+    /// Inputs are otherwise only deduplicated by their paths. The same object
+    /// passed under different paths results in duplicate symbol errors.
+    pub fn dedup_inputs(mut self, val: bool) -> Self {
+        self.dedup_inputs = val;
+        self
+    }
+
+    /// Warn about link libraries which did not contribute any members to the
+    /// link.
+    pub fn warn_unused_libraries(mut self, val: bool) -> Self {
+        self.warn_unused_libraries = val;
+        self
+    }
+
+    /// Collect the table of external symbols along with the inputs defining
+    /// and referencing them in the [`LinkReport`](super::LinkReport).
+    pub fn cross_reference(mut self, val: bool) -> Self {
+        self.cross_reference = val;
+        self
+    }
+
+    /// Collect the printable strings in the initialized data sections of the
+    /// output along with the inputs contributing them in the
+    /// [`LinkReport`](super::LinkReport).
+    pub fn collect_strings(mut self, val: bool) -> Self {
+        self.collect_strings = val;
+        self
+    }
+
+    /// Estimate the sizes of the symbols defined in the output for the
+    /// [`LinkReport`](super::LinkReport).
+    ///
+    /// The size of a symbol is the distance to the next symbol in the same
+    /// input section or to the end of the section.
+    pub fn collect_symbol_sizes(mut self, val: bool) -> Self {
+        self.collect_symbol_sizes = val;
+        self
+    }
+
+    /// Keep the absolute symbol with the specified name in the output.
+    ///
+    /// The values of absolute symbols with the same name from different
+    /// inputs are merged with a bitwise OR. The `@feat.00` feature flags are
+    /// merged using the rules for each flag.
+    pub fn keep_absolute_symbol(mut self, name: impl Into<String>) -> Self {
+        self.absolute_symbols.insert(name.into());
+        self
+    }
+
+    /// Keep a set of absolute symbols in the output.
+    pub fn keep_absolute_symbols<S: Into<String>, I: IntoIterator<Item = S>>(
+        mut self,
+        names: I,
+    ) -> Self {
+        self.absolute_symbols
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Record the inputs searched while resolving the symbol in the
+    /// [`LinkReport`](super::LinkReport).
+    ///
+    /// The report includes symbols in the link libraries which only differ
+    /// in case or i386 name decorations if the symbol is left undefined.
+    pub fn why_undefined(mut self, symbol: impl Into<String>) -> Self {
+        self.why_undefined.insert(symbol.into());
+        self
+    }
+
+    /// Keep the symbols matching the name pattern.
+    ///
+    /// The pattern may contain `*` and `?` wildcards. See
+    /// [`BuiltLinkGraph::set_keep_symbols`](crate::graph::BuiltLinkGraph::set_keep_symbols)
+    /// for what is kept.
+    pub fn keep_symbol(mut self, pattern: impl Into<String>) -> Self {
+        self.keep_symbols.insert(pattern.into());
+        self
+    }
+
+    /// Keep the symbols matching a set of name patterns.
+    pub fn keep_symbols<S: Into<String>, I: IntoIterator<Item = S>>(mut self, patterns: I) -> Self {
+        self.keep_symbols
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keep the input sections matching the name pattern.
+    ///
+    /// The pattern may contain `*` and `?` wildcards. See
+    /// [`BuiltLinkGraph::set_keep_sections`](crate::graph::BuiltLinkGraph::set_keep_sections)
+    /// for what is kept.
+    pub fn keep_section(mut self, pattern: impl Into<String>) -> Self {
+        self.keep_sections.insert(pattern.into());
+        self
+    }
+
+    /// Keep the input sections matching a set of name patterns.
+    pub fn keep_sections<S: Into<String>, I: IntoIterator<Item = S>>(
+        mut self,
+        patterns: I,
+    ) -> Self {
+        self.keep_sections
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Delay load the imports from the DLL.
+    ///
+    /// The imports are emitted as pointer slots in a delay import table
+    /// defined by the `__delay_import_table` symbol instead of `__imp_`
+    /// symbols for loaders which resolve imports when they are first used.
+    /// Each table entry holds a pointer to the DLL name, a pointer to the
+    /// import name and the pointer slot. The table ends with a zeroed entry.
+    pub fn delay_load(mut self, dll: impl Into<String>) -> Self {
+        self.delay_load_libraries.insert(dll.into());
+        self
+    }
+
+    /// Delay load the imports from a set of DLLs.
+    pub fn delay_load_libraries<S: Into<String>, I: IntoIterator<Item = S>>(
+        mut self,
+        dlls: I,
+    ) -> Self {
+        self.delay_load_libraries
+            .extend(dlls.into_iter().map(Into::into));
+        self
+    }
+
+    /// Require that all inputs are compatible with safe exception handlers
+    /// and write the merged `@feat.00` symbol to the output.
+    ///
+    /// This only applies to i386 targets.
+    pub fn safeseh(mut self, val: bool) -> Self {
+        self.safeseh = val;
+        self
+    }
+
+    /// Set how conflicting memory access flags for input sections with the
+    /// same name are handled.
+    pub fn characteristics_policy(mut self, policy: CharacteristicsPolicy) -> Self {
+        self.characteristics_policy = policy;
+        self
+    }
+
+    /// Set how input sections with `IMAGE_SCN_LNK_INFO` other than
+    /// `.drectve` are handled.
+    pub fn lnk_info_policy(mut self, policy: LnkInfoPolicy) -> Self {
+        self.lnk_info_policy = policy;
+        self
+    }
+
+    /// Add a `.comment` section with the boflink version and the names of
+    /// the linked inputs.
+    pub fn emit_comment(mut self, val: bool) -> Self {
+        self.emit_comment = val;
+        self
+    }
+
+    /// Add a `.ident` section with the boflink version and the `ident`
+    /// string.
+    ///
+    /// An empty string only adds the boflink version. The section is not
+    /// added by default and is kept when input sections are stripped.
+    pub fn ident(mut self, ident: impl Into<String>) -> Self {
+        self.ident = Some(ident.into());
+        self
+    }
+
     /// Merge the .bss section with the .data section.
     pub fn merge_bss(mut self, val: bool) -> Self {
         self.merge_bss = val;
         self
     }
 
-    /// Custom BOF API to use instead of the Beacon API.
+    /// Add a custom BOF API to use instead of the Beacon API.
+    ///
+    /// Symbols are resolved against the custom APIs in the order they are
+    /// added.
     pub fn custom_api(mut self, api: impl Into<String>) -> Self {
-        self.custom_api = Some(api.into());
+        self.custom_api.push(api.into());
+        self
+    }
+
+    /// Set the layout from a previous link to preserve the section and symbol
+    /// ordering.
+    pub fn layout(mut self, layout: LinkLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Set the symbol hit counts for placing the frequently executed code
+    /// sections first.
+    pub fn profile(mut self, profile: LinkProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Move the `.text` sections without any profile hits to a separate
+    /// `.text$cold` output section.
+    pub fn split_cold(mut self, val: bool) -> Self {
+        self.split_cold = val;
+        self
+    }
+
+    /// Order the input sections with the same name by descending alignment
+    /// if it reduces the padding in the output section.
+    pub fn pack_sections(mut self, val: bool) -> Self {
+        self.pack_sections = val;
+        self
+    }
+
+    /// Set the output path for writing the layout of the linked output.
+    pub fn layout_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.layout_output = Some(path.into());
+        self
+    }
+
+    /// Pad the output file so that the total size including the trailer is
+    /// a multiple of `alignment` bytes.
+    pub fn output_alignment(mut self, alignment: u32) -> Self {
+        self.output_alignment = Some(alignment);
+        self
+    }
+
+    /// Append the trailer after the output COFF.
+    ///
+    /// Any padding from [`LinkerBuilder::output_alignment`] is inserted
+    /// before the trailer.
+    pub fn output_trailer(mut self, trailer: OutputTrailer) -> Self {
+        self.output_trailer = Some(trailer);
+        self
+    }
+
+    /// Write a tar archive with the inputs, the opened link libraries and the
+    /// response file for replaying the link.
+    ///
+    /// The files are stored under a directory named after the archive using
+    /// the path from [`reproduce_path`](super::reproduce_path). The response
+    /// file holds one argument per line and should use the same paths so that
+    /// the link can be replayed from the extracted directory.
+    pub fn reproduce(mut self, path: impl Into<PathBuf>, response: impl Into<String>) -> Self {
+        self.reproduce_output = Some(path.into());
+        self.reproduce_response = response.into();
+        self
+    }
+
+    /// Add a file read outside of the linker to the reproduce archive.
+    ///
+    /// The file is ignored unless [`LinkerBuilder::reproduce`] is set.
+    pub fn add_reproduce_file(
+        mut self,
+        path: impl Into<PathBuf>,
+        data: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.reproduce_files
+            .push(PathedItem::new(path.into(), data.into()));
+        self
+    }
+
+    /// Add a `.rdata$cksum` section with the sizes and JamCRC values of the
+    /// output sections.
+    ///
+    /// See [`BuiltLinkGraph::set_section_checksums`](crate::graph::BuiltLinkGraph::set_section_checksums)
+    /// for the table format.
+    pub fn section_checksums(mut self, val: bool) -> Self {
+        self.section_checksums = val;
+        self
+    }
+
+    /// Write the auxiliary function records for the function symbols in the
+    /// inputs along with the `.bf`/`.ef` records.
+    ///
+    /// See [`BuiltLinkGraph::set_function_aux`](crate::graph::BuiltLinkGraph::set_function_aux)
+    /// for how the records are updated.
+    pub fn function_aux(mut self, val: bool) -> Self {
+        self.function_aux = val;
+        self
+    }
+
+    /// Replace the loads through the `.refptr` stubs emitted by GCC with
+    /// direct references to the data symbols defined in the output.
+    ///
+    /// See [`BuiltLinkGraph::set_collapse_refptrs`](crate::graph::BuiltLinkGraph::set_collapse_refptrs)
+    /// for how the loads are rewritten.
+    pub fn collapse_refptrs(mut self, val: bool) -> Self {
+        self.collapse_refptrs = val;
+        self
+    }
+
+    /// Rewrite the absolute address loads in code into RIP-relative forms
+    /// for loaders which do not support `IMAGE_REL_AMD64_ADDR64`
+    /// relocations.
+    ///
+    /// See [`BuiltLinkGraph::set_rip_relative`](crate::graph::BuiltLinkGraph::set_rip_relative)
+    /// for which loads can be rewritten.
+    pub fn rip_relative(mut self, val: bool) -> Self {
+        self.rip_relative = val;
+        self
+    }
+
+    /// Discard the `.rsrc` resource sections in the inputs instead of
+    /// warning about them.
+    pub fn strip_resources(mut self, val: bool) -> Self {
+        self.strip_resources = val;
+        self
+    }
+
+    /// XOR the contents of the output section with the obfuscation key.
+    ///
+    /// The key and the obfuscated ranges are written to a `.obfmeta` section
+    /// for the loader to deobfuscate the section. See
+    /// [`BuiltLinkGraph::set_obfuscated_sections`](crate::graph::BuiltLinkGraph::set_obfuscated_sections)
+    /// for the metadata format.
+    pub fn obfuscate_section(mut self, name: impl Into<String>) -> Self {
+        self.obfuscated_sections.insert(name.into());
+        self
+    }
+
+    /// XOR the contents of a set of output sections with the obfuscation key.
+    pub fn obfuscate_sections<S: Into<String>, I: IntoIterator<Item = S>>(
+        mut self,
+        names: I,
+    ) -> Self {
+        self.obfuscated_sections
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the key for obfuscating the section contents.
+    ///
+    /// A random key is used if this is not set.
+    pub fn obfuscation_key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.obfuscation_key = Some(key.into());
+        self
+    }
+
+    /// Add a set of custom BOF APIs to use instead of the Beacon API.
+    pub fn custom_apis<S: Into<String>, I: IntoIterator<Item = S>>(mut self, apis: I) -> Self {
+        self.custom_api.extend(apis.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the mapping of symbol names used for resolving API symbols.
+    pub fn api_symbol_map(mut self, map: ApiSymbolMap) -> Self {
+        self.api_symbol_map = map;
+        self
+    }
+
+    /// Map the API symbol name `from` to `to` when resolving API symbols.
+    ///
+    /// Names ending with a `*` map the prefix of the symbol. Mappings where
+    /// only one of the names is a prefix fail the link with a setup error.
+    pub fn map_api_symbol(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        if let Err(e) = self.api_symbol_map.add(from, to) {
+            self.api_symbol_map_errors.push(e);
+        }
+        self
+    }
+
+    /// Redirect the references to the symbol `from` to the symbol `to`.
+    ///
+    /// This is done after symbol resolution so `to` is linked in from the
+    /// link libraries or the API if it is not defined in the inputs. See
+    /// [`BuiltLinkGraph::redirect_symbol`](crate::graph::BuiltLinkGraph::redirect_symbol).
+    pub fn redirect_symbol(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.redirected_symbols.push((from.into(), to.into()));
+        self
+    }
+
+    /// Treat the symbol as provided by the loader.
+    ///
+    /// The symbol is not searched for in the link libraries or the API and
+    /// is written to the output as an undefined external symbol if no input
+    /// defines it. See
+    /// [`LinkGraph::add_loader_extern`](crate::graph::LinkGraph::add_loader_extern).
+    pub fn loader_extern(mut self, symbol: impl Into<String>) -> Self {
+        self.loader_externs.insert(symbol.into());
+        self
+    }
+
+    /// Treat a set of symbols as provided by the loader.
+    pub fn loader_externs<S: Into<String>, I: IntoIterator<Item = S>>(
+        mut self,
+        symbols: I,
+    ) -> Self {
+        self.loader_externs
+            .extend(symbols.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add a file to the output as a section.
+    ///
+    /// The section is added to the graph after the inputs and is placed in
+    /// the output section for its name like the input sections.
+    pub fn add_section(mut self, section: AddedSection) -> Self {
+        self.added_sections.push(section);
+        self
+    }
+
+    /// Define the compiler intrinsics in the group which are still undefined
+    /// after searching the link libraries.
+    ///
+    /// A warning lists the intrinsics which were defined. See
+    /// [`LinkGraph::synthesize_intrinsics`](crate::graph::LinkGraph::synthesize_intrinsics).
+    pub fn intrinsic_group(mut self, group: IntrinsicGroup) -> Self {
+        self.intrinsic_groups.insert(group);
+        self
+    }
+
+    /// Define the compiler intrinsics in a set of groups which are still
+    /// undefined after searching the link libraries.
+    pub fn intrinsic_groups(mut self, groups: impl IntoIterator<Item = IntrinsicGroup>) -> Self {
+        self.intrinsic_groups.extend(groups);
         self
     }
 
@@ -87,59 +855,176 @@ impl<L: LibraryFind + 'static> LinkerBuilder<L> {
     }
 
     /// Add an input file to the linker.
-    pub fn add_input(mut self, input: PathedItem<PathBuf, Vec<u8>>) -> Self {
-        self.inputs.push(input);
+    pub fn add_input(mut self, input: PathedItem<PathBuf, impl Into<InputData>>) -> Self {
+        self.inputs
+            .push(input.map_item(|data| Arc::new(data.into())));
         self
     }
 
     /// Add a set of input files to the linker.
-    pub fn add_inputs(
+    pub fn add_inputs<D: Into<InputData>>(
         mut self,
-        inputs: impl IntoIterator<Item = PathedItem<PathBuf, Vec<u8>>>,
+        inputs: impl IntoIterator<Item = PathedItem<PathBuf, D>>,
+    ) -> Self {
+        self.inputs.extend(
+            inputs
+                .into_iter()
+                .map(|input| input.map_item(|data| Arc::new(data.into()))),
+        );
+        self
+    }
+
+    /// Add a set of input files whose data is shared with other linkers.
+    pub fn add_shared_inputs(
+        mut self,
+        inputs: impl IntoIterator<Item = PathedItem<PathBuf, Arc<InputData>>>,
     ) -> Self {
         self.inputs.extend(inputs);
         self
     }
 
+    /// Add a lazy input file to the linker.
+    ///
+    /// Lazy inputs are treated like archive members. They are only linked if
+    /// they define a symbol which is undefined in the other inputs.
+    pub fn add_lazy_input(mut self, input: PathedItem<PathBuf, impl Into<InputData>>) -> Self {
+        self.lazy_inputs
+            .push(input.map_item(|data| Arc::new(data.into())));
+        self
+    }
+
+    /// Add a set of lazy input files to the linker.
+    pub fn add_lazy_inputs<D: Into<InputData>>(
+        mut self,
+        inputs: impl IntoIterator<Item = PathedItem<PathBuf, D>>,
+    ) -> Self {
+        self.lazy_inputs.extend(
+            inputs
+                .into_iter()
+                .map(|input| input.map_item(|data| Arc::new(data.into()))),
+        );
+        self
+    }
+
+    /// Add a set of lazy input files whose data is shared with other linkers.
+    pub fn add_shared_lazy_inputs(
+        mut self,
+        inputs: impl IntoIterator<Item = PathedItem<PathBuf, Arc<InputData>>>,
+    ) -> Self {
+        self.lazy_inputs.extend(inputs);
+        self
+    }
+
+    /// Add a module-definition (.def) file to the linker.
+    ///
+    /// The exports listed in the file are resolved as imports from the DLL
+    /// named by the LIBRARY statement without needing an import library.
+    pub fn add_module_definition(
+        mut self,
+        input: PathedItem<PathBuf, impl Into<InputData>>,
+    ) -> Self {
+        self.module_definitions
+            .push(input.map_item(|data| Arc::new(data.into())));
+        self
+    }
+
+    /// Add a set of module-definition files to the linker.
+    pub fn add_module_definitions<D: Into<InputData>>(
+        mut self,
+        inputs: impl IntoIterator<Item = PathedItem<PathBuf, D>>,
+    ) -> Self {
+        self.module_definitions.extend(
+            inputs
+                .into_iter()
+                .map(|input| input.map_item(|data| Arc::new(data.into()))),
+        );
+        self
+    }
+
     /// Add a link library to the linker.
+    ///
+    /// The position of the library after the inputs added so far is used
+    /// with [`LinkerBuilder::positional_libraries`].
     pub fn add_library(mut self, name: impl Into<String>) -> Self {
-        self.libraries.insert(name.into());
+        let name = name.into();
+        self.library_occurrences.push(LibraryOccurrence {
+            name: name.clone(),
+            inputs_before: self.inputs.len(),
+            group: self.open_library_group,
+        });
+        self.libraries.insert(name);
         self
     }
 
     /// Add a set of link libraries to the linker.
-    pub fn add_libraries<S: Into<String>, I: IntoIterator<Item = S>>(mut self, names: I) -> Self {
-        self.libraries.extend(names.into_iter().map(Into::into));
+    pub fn add_libraries<S: Into<String>, I: IntoIterator<Item = S>>(self, names: I) -> Self {
+        names
+            .into_iter()
+            .fold(self, |builder, name| builder.add_library(name))
+    }
+
+    /// Only search each link library for the symbols referenced by the
+    /// inputs added before it, like GNU ld.
+    ///
+    /// A library added more than once is searched again at each position.
+    /// Symbols referenced by the members extracted from a library are only
+    /// searched for in the same library, libraries in the same group and
+    /// the libraries added after it. The APIs, lazy inputs, `.drectve`
+    /// libraries and archives passed as inputs are searched for every
+    /// symbol.
+    pub fn positional_libraries(mut self, val: bool) -> Self {
+        self.positional_libraries = val;
+        self
+    }
+
+    /// Start a group of link libraries which are searched repeatedly for
+    /// [`LinkerBuilder::positional_libraries`] like `--start-group` in
+    /// GNU ld.
+    pub fn start_library_group(mut self) -> Self {
+        if self.open_library_group.is_none() {
+            self.open_library_group = Some(self.library_groups);
+            self.library_groups += 1;
+        }
+        self
+    }
+
+    /// End the current group of link libraries.
+    pub fn end_library_group(mut self) -> Self {
+        self.open_library_group = None;
         self
     }
 
     /// Finishes configuring the linker.
+    ///
+    /// Clone the builder before building it for reusing the configuration.
     pub fn build(mut self) -> Box<dyn LinkImpl> {
+        let custom_apis = std::mem::take(&mut self.custom_api);
+
         if let Some(library_searcher) = self.library_searcher.take() {
-            if let Some(custom_api) = self.custom_api.take() {
+            if !custom_apis.is_empty() {
                 Box::new(ConfiguredLinker::with_opts(
                     self,
                     library_searcher,
-                    CustomApiInit::from(custom_api),
+                    custom_apis.into_iter().map(CustomApiInit::from).collect(),
                 ))
             } else {
                 Box::new(ConfiguredLinker::with_opts(
                     self,
                     library_searcher,
-                    BeaconApiInit,
+                    vec![BeaconApiInit],
                 ))
             }
-        } else if let Some(custom_api) = self.custom_api.take() {
+        } else if !custom_apis.is_empty() {
             Box::new(ConfiguredLinker::with_opts(
                 self,
                 LibrarySearcher::new(),
-                CustomApiInit::from(custom_api),
+                custom_apis.into_iter().map(CustomApiInit::from).collect(),
             ))
         } else {
             Box::new(ConfiguredLinker::with_opts(
                 self,
                 LibrarySearcher::new(),
-                BeaconApiInit,
+                vec![BeaconApiInit],
             ))
         }
     }
@@ -1,27 +1,48 @@
 use std::{
+    cell::RefCell,
     collections::VecDeque,
-    io::BufWriter,
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
 };
 
 use indexmap::{IndexMap, IndexSet};
-use log::warn;
-use object::{Object, coff::CoffFile};
+use log::{debug, warn};
+use object::{Object, ObjectSymbol, coff::CoffFile};
 use typed_arena::Arena;
 
 use crate::{
     api::{ApiSymbolError, ApiSymbolSource},
     drectve,
-    graph::LinkGraph,
+    graph::{
+        CharacteristicsPolicy, IntrinsicGroup, LinkGraph, LinkGraphLinkError, LinkLayout,
+        LinkProfile, LnkInfoPolicy, SymbolRenameMode,
+        node::{CoffNode, SectionNodeCharacteristics},
+    },
+    inputdata::InputData,
     libsearch::LibraryFind,
     linker::error::{DrectveLibsearchError, LinkerSymbolErrors},
-    linkobject::archive::{ExtractMemberError, ExtractedMemberContents, LinkArchive},
+    linkobject::{
+        archive::{ExtractMemberError, ExtractedMemberContents},
+        library::LinkLibrary,
+        moddef::ModuleDefinition,
+    },
     pathed_item::PathedItem,
 };
 
 use super::{
-    ApiInit, ApiInitCtx, LinkImpl, LinkerBuilder, LinkerTargetArch,
-    error::{LinkError, LinkerSetupError, LinkerSetupErrors, LinkerSetupPathError},
+    AddedSection, ApiInit, ApiInitCtx, ApiSymbolMap, BeaconApiCategory, LinkImpl, LinkPhase,
+    LinkReport, LinkerBuilder, LinkerTargetArch, OutputTrailer, ProbeResult, ProbeSource,
+    SymbolQuery, SymbolStatus,
+    builder::{GraphInspector, LibraryOccurrence},
+    detect::detect_input_architecture,
+    error::{
+        ApiSymbolMapError, LinkError, LinkerSetupError, LinkerSetupErrors, LinkerSetupPathError,
+    },
+    positional::LibraryPositions,
+    reproduce::{RecordedLibraries, RecordingSearcher, TarWriter, reproduce_path},
+    trailer::finish_output,
 };
 
 /// A configured linker.
@@ -30,13 +51,32 @@ pub struct ConfiguredLinker<L: LibraryFind, Api: ApiInit> {
     target_arch: Option<LinkerTargetArch>,
 
     /// The unparsed linker inputs
-    inputs: Vec<PathedItem<PathBuf, Vec<u8>>>,
+    inputs: Vec<PathedItem<PathBuf, Arc<InputData>>>,
+
+    /// The unparsed lazy linker inputs
+    lazy_inputs: Vec<PathedItem<PathBuf, Arc<InputData>>>,
+
+    /// The unparsed module-definition files
+    module_definitions: Vec<PathedItem<PathBuf, Arc<InputData>>>,
 
     /// The names of the link libraries.
     library_names: IndexSet<String>,
 
-    /// The custom API.
-    custom_api: Api,
+    /// Each time a link library was added in order.
+    library_occurrences: Vec<LibraryOccurrence>,
+
+    /// Whether to only search link libraries for the symbols referenced
+    /// before them.
+    positional_libraries: bool,
+
+    /// The custom APIs in priority order.
+    custom_api: Vec<Api>,
+
+    /// Mapping of symbol names used for resolving API symbols.
+    api_symbol_map: ApiSymbolMap,
+
+    /// Invalid API symbol mappings added to the builder.
+    api_symbol_map_errors: Vec<ApiSymbolMapError>,
 
     /// The link library searcher.
     library_searcher: L,
@@ -44,11 +84,161 @@ pub struct ConfiguredLinker<L: LibraryFind, Api: ApiInit> {
     /// The name of the entrypoint symbol.
     entrypoint: Option<String>,
 
+    /// Whether to place the section defining the entrypoint first.
+    entry_first: bool,
+
     /// Whether to merge the .bss section with the .data section.
     merge_bss: bool,
 
     /// Output path for dumping the link graph.
     link_graph_output: Option<PathBuf>,
+
+    /// Callback for inspecting the link graph after resolving symbols.
+    graph_inspector: Option<GraphInspector>,
+
+    /// Naming scheme for renaming symbols in the output COFF.
+    rename_symbols: Option<SymbolRenameMode>,
+
+    /// Output path for the renamed symbol map.
+    symbol_map_output: Option<PathBuf>,
+
+    /// Byte used for padding code sections.
+    code_fill: Option<u8>,
+
+    /// Byte used for padding data sections.
+    data_fill: Option<u8>,
+
+    /// Minimum alignment for output sections.
+    min_section_alignment: Option<u32>,
+
+    /// Whether to apply the default alignment for well-known sections.
+    default_section_alignment: bool,
+
+    /// Whether to apply the relocations targeting the same output section.
+    flatten_relocations: bool,
+
+    /// Whether to report cross-section relocations in the output.
+    check_relocations: bool,
+
+    /// Whether to order the output symbols independently of the input order.
+    deterministic: bool,
+
+    /// Whether to skip archive members which fail to parse.
+    tolerant: bool,
+
+    /// Whether to skip inputs with the same contents as a previous input.
+    dedup_inputs: bool,
+
+    /// Whether to warn about link libraries which were not used.
+    warn_unused_libraries: bool,
+
+    /// Names of the absolute symbols to keep in the output.
+    absolute_symbols: IndexSet<String>,
+
+    /// Name patterns for the symbols to keep.
+    keep_symbols: IndexSet<String>,
+
+    /// Name patterns for the input sections to keep.
+    keep_sections: IndexSet<String>,
+
+    /// Symbols provided by the loader which are left undefined.
+    loader_externs: IndexSet<String>,
+
+    /// Files added to the output as sections.
+    added_sections: Vec<AddedSection>,
+
+    /// Symbols with the references redirected to another symbol.
+    redirected_symbols: Vec<(String, String)>,
+
+    /// Groups of the undefined compiler intrinsics to define.
+    intrinsic_groups: Vec<IntrinsicGroup>,
+
+    /// Names of the DLLs with imports routed through the delay import table.
+    delay_load_libraries: IndexSet<String>,
+
+    /// Whether all inputs need to be compatible with safe exception handlers.
+    safeseh: bool,
+
+    /// How conflicting section memory access flags are handled.
+    characteristics_policy: CharacteristicsPolicy,
+
+    /// How the `IMAGE_SCN_LNK_INFO` input sections are handled.
+    lnk_info_policy: LnkInfoPolicy,
+
+    /// Whether to add a `.comment` section with the linker version and
+    /// inputs.
+    emit_comment: bool,
+
+    /// User string for the `.ident` section with the linker version.
+    ident: Option<String>,
+
+    /// Layout from a previous link to preserve.
+    layout: Option<LinkLayout>,
+
+    /// Output path for the layout of the linked output.
+    layout_output: Option<PathBuf>,
+
+    /// Symbol hit counts for ordering the code sections.
+    profile: Option<LinkProfile>,
+
+    /// Whether to split the code without profile hits into `.text$cold`.
+    split_cold: bool,
+
+    /// Whether to order the input sections by alignment to reduce padding.
+    pack_sections: bool,
+
+    /// Alignment for the total size of the output file.
+    output_alignment: Option<u32>,
+
+    /// Data appended after the output COFF.
+    output_trailer: Option<OutputTrailer>,
+
+    /// Whether to add the table of output section checksums.
+    section_checksums: bool,
+
+    /// Whether to write the auxiliary function records from the inputs.
+    function_aux: bool,
+
+    /// Whether to replace the loads through `.refptr` stubs with direct
+    /// references.
+    collapse_refptrs: bool,
+
+    /// Whether to rewrite the absolute address loads into RIP-relative
+    /// forms.
+    rip_relative: bool,
+
+    /// Whether to discard the `.rsrc` resource sections in the inputs.
+    strip_resources: bool,
+
+    /// Names of the output sections with obfuscated contents.
+    obfuscated_sections: IndexSet<String>,
+
+    /// Key for obfuscating the section contents.
+    obfuscation_key: Option<Vec<u8>>,
+
+    /// Whether to collect the symbol cross-reference table.
+    cross_reference: bool,
+
+    /// Whether to collect the printable strings in the output data sections.
+    collect_strings: bool,
+
+    /// Whether to estimate the sizes of the symbols in the output.
+    collect_symbol_sizes: bool,
+
+    /// Symbols to record the resolution steps for.
+    why_undefined: IndexSet<String>,
+
+    /// Output path for the archive of the link inputs.
+    reproduce_output: Option<PathBuf>,
+
+    /// Response file for replaying the link from the archive.
+    reproduce_response: String,
+
+    /// Files read by the caller to include in the archive.
+    reproduce_files: Vec<PathedItem<PathBuf, Vec<u8>>>,
+
+    /// The report for the last link.
+    report: LinkReport,
 }
 
 impl<L: LibraryFind, Api: ApiInit> ConfiguredLinker<L, Api> {
@@ -60,33 +250,170 @@ impl<L: LibraryFind, Api: ApiInit> ConfiguredLinker<L, Api> {
     pub(super) fn with_opts<T: LibraryFind>(
         builder: LinkerBuilder<T>,
         library_searcher: L,
-        custom_api: Api,
+        custom_api: Vec<Api>,
     ) -> ConfiguredLinker<L, Api> {
         Self {
             target_arch: builder.target_arch,
             inputs: builder.inputs,
+            lazy_inputs: builder.lazy_inputs,
+            module_definitions: builder.module_definitions,
             library_names: builder.libraries,
+            library_occurrences: builder.library_occurrences,
+            positional_libraries: builder.positional_libraries,
             custom_api,
+            api_symbol_map: builder.api_symbol_map,
+            api_symbol_map_errors: builder.api_symbol_map_errors,
             library_searcher,
             entrypoint: builder.entrypoint,
+            entry_first: builder.entry_first,
             merge_bss: builder.merge_bss,
             link_graph_output: builder.link_graph_output,
+            graph_inspector: builder.graph_inspector,
+            rename_symbols: builder.rename_symbols,
+            symbol_map_output: builder.symbol_map_output,
+            code_fill: builder.code_fill,
+            data_fill: builder.data_fill,
+            min_section_alignment: builder.min_section_alignment,
+            default_section_alignment: builder.default_section_alignment,
+            flatten_relocations: builder.flatten_relocations,
+            check_relocations: builder.check_relocations,
+            deterministic: builder.deterministic,
+            tolerant: builder.tolerant,
+            dedup_inputs: builder.dedup_inputs,
+            warn_unused_libraries: builder.warn_unused_libraries,
+            absolute_symbols: builder.absolute_symbols,
+            keep_symbols: builder.keep_symbols,
+            keep_sections: builder.keep_sections,
+            loader_externs: builder.loader_externs,
+            added_sections: builder.added_sections,
+            redirected_symbols: builder.redirected_symbols,
+            intrinsic_groups: builder.intrinsic_groups.into_iter().collect(),
+            delay_load_libraries: builder.delay_load_libraries,
+            safeseh: builder.safeseh,
+            characteristics_policy: builder.characteristics_policy,
+            lnk_info_policy: builder.lnk_info_policy,
+            emit_comment: builder.emit_comment,
+            ident: builder.ident,
+            layout: builder.layout,
+            layout_output: builder.layout_output,
+            profile: builder.profile,
+            split_cold: builder.split_cold,
+            pack_sections: builder.pack_sections,
+            output_alignment: builder.output_alignment,
+            output_trailer: builder.output_trailer,
+            section_checksums: builder.section_checksums,
+            function_aux: builder.function_aux,
+            collapse_refptrs: builder.collapse_refptrs,
+            rip_relative: builder.rip_relative,
+            strip_resources: builder.strip_resources,
+            obfuscated_sections: builder.obfuscated_sections,
+            obfuscation_key: builder.obfuscation_key,
+            cross_reference: builder.cross_reference,
+            collect_strings: builder.collect_strings,
+            collect_symbol_sizes: builder.collect_symbol_sizes,
+            why_undefined: builder.why_undefined,
+            reproduce_output: builder.reproduce_output,
+            reproduce_response: builder.reproduce_response,
+            reproduce_files: builder.reproduce_files,
+            report: LinkReport::default(),
+        }
+    }
+
+    /// Writes the inputs, the found link libraries and the response file to
+    /// the reproduce archive.
+    fn write_reproduce(
+        &self,
+        path: &Path,
+        libraries: &[PathedItem<PathBuf, Vec<u8>>],
+    ) -> std::io::Result<()> {
+        let mut files: IndexMap<String, &[u8]> = IndexMap::new();
+        for (file_path, data) in self
+            .inputs
+            .iter()
+            .chain(&self.lazy_inputs)
+            .chain(&self.module_definitions)
+            .map(|input| (input.path(), input.as_slice()))
+            .chain(
+                self.reproduce_files
+                    .iter()
+                    .map(|file| (file.path(), file.as_slice())),
+            )
+            .chain(
+                libraries
+                    .iter()
+                    .map(|library| (library.path(), library.as_slice())),
+            )
+        {
+            files.entry(reproduce_path(file_path)).or_insert(data);
+        }
+
+        let base = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "repro".into());
+
+        let mut tar = TarWriter::new(BufWriter::new(std::fs::File::create(path)?), base);
+        tar.append("response.txt", self.reproduce_response.as_bytes())?;
+        tar.append(
+            "version.txt",
+            concat!("boflink ", env!("CARGO_PKG_VERSION"), "\n").as_bytes(),
+        )?;
+
+        for (name, data) in files {
+            tar.append(&name, data)?;
         }
+
+        tar.finish()?;
+        Ok(())
     }
 }
 
 impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
+    fn report(&self) -> &LinkReport {
+        &self.report
+    }
+
     fn link(&mut self) -> Result<Vec<u8>, LinkError> {
+        let libraries = RefCell::new(Vec::new());
+        let linked = self.link_inputs(self.reproduce_output.is_some().then_some(&libraries));
+
+        // The archive is also written for failed links so that errors can be
+        // reproduced
+        if let Some(archive_path) = self.reproduce_output.as_deref() {
+            if let Err(e) = self.write_reproduce(archive_path, &libraries.into_inner()) {
+                warn!("could not write {}: {e}", archive_path.display());
+            }
+        }
+
+        linked
+    }
+}
+
+impl<L: LibraryFind, A: ApiInit> ConfiguredLinker<L, A> {
+    /// Links the inputs and records the found link libraries if `recorded`
+    /// is set.
+    fn link_inputs(&mut self, recorded: Option<&RecordedLibraries>) -> Result<Vec<u8>, LinkError> {
+        self.report = LinkReport::default();
+        let mut phase_start = Instant::now();
+
         // Parsed input COFFs
         let mut parsed_inputs = Vec::with_capacity(self.inputs.len());
 
         // Errors during setup
-        let mut setup_errors = Vec::new();
+        let mut setup_errors = Vec::from_iter(
+            self.api_symbol_map_errors
+                .iter()
+                .cloned()
+                .map(LinkerSetupError::ApiSymbolMap),
+        );
 
         // Parsed link libraries
         let mut link_libraries =
             IndexMap::with_capacity(self.inputs.len() + self.library_names.len());
 
+        // Decoded copies of the .drectve sections which are not UTF-8
+        let drectve_strings: Arena<String> = Arena::new();
+
         // The opened link library names including .drectve libraries
         let mut library_names: IndexSet<&str> =
             IndexSet::from_iter(self.library_names.iter().map(|v| v.as_str()));
@@ -98,17 +425,28 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
         // Queue of .drectve libraries to open
         let mut drectve_queue = VecDeque::with_capacity(self.inputs.len());
 
+        // Contents of the added COFFs for skipping duplicate inputs
+        let mut input_contents: IndexMap<&[u8], CoffNode> = IndexMap::new();
+
+        // Positions of the symbols for searching the link libraries
+        // positionally
+        let mut positions = self
+            .positional_libraries
+            .then(|| LibraryPositions::new(&self.library_occurrences));
+
+        // Symbols which could not be found in any of the link libraries
+        let mut undefined_symbols: IndexSet<&str> = IndexSet::new();
+
         // Parse the command line input files
-        for input in &self.inputs {
-            // Check if this is an archive file passed in the command line
-            if input
-                .get(..object::archive::MAGIC.len())
-                .is_some_and(|magic| magic == object::archive::MAGIC)
-            {
-                match LinkArchive::parse(input.as_slice())
+        for (input_idx, input) in self.inputs.iter().enumerate() {
+            // Check if this is an archive file or DLL passed in the command
+            // line
+            if LinkLibrary::is_library(input) {
+                match LinkLibrary::parse(input.as_slice())
                     .map_err(|e| LinkerSetupPathError::nomember(input.path(), e))
                 {
                     Ok(parsed) => {
+                        self.report.open_library(input.path(), None);
                         link_libraries.insert(input.path().as_path(), parsed);
                     }
                     Err(e) => {
@@ -116,14 +454,25 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
                     }
                 };
             } else {
+                if self.dedup_inputs {
+                    let coff_node = CoffNode::new(input.path(), None);
+                    if let Some(original) = input_contents.get(input.as_slice()) {
+                        self.report.skip_duplicate(&coff_node, original);
+                        continue;
+                    }
+
+                    input_contents.insert(input.as_slice(), coff_node);
+                }
+
                 match CoffFile::<_>::parse(input.as_slice())
                     .map_err(|e| LinkerSetupPathError::nomember(input.path(), e))
                 {
                     Ok(parsed) => {
                         // Add .drectve libraries to the drectve_queue.
-                        for library_name in drectve::parse_drectve_libraries(&parsed)
-                            .into_iter()
-                            .flatten()
+                        for library_name in
+                            drectve::parse_drectve_libraries(&parsed, &drectve_strings)
+                                .into_iter()
+                                .flatten()
                         {
                             let library_name = library_name.trim_end_matches(".lib");
                             if library_names.insert(library_name) {
@@ -131,8 +480,27 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
                             }
                         }
 
+                        // Add the /LIBPATH directories to the search paths
+                        for library_path in
+                            drectve::parse_drectve_directives(&parsed, &drectve_strings)
+                                .into_iter()
+                                .flat_map(drectve::drectve_library_paths)
+                        {
+                            debug!(
+                                "{}: adding library search path {library_path}",
+                                input.path().display()
+                            );
+                            self.library_searcher
+                                .add_search_path(Path::new(library_path));
+                        }
+
                         spec.add_coff(&parsed);
 
+                        if let Some(positions) = positions.as_mut() {
+                            let position = positions.input_position(input_idx);
+                            positions.reference_symbols(&parsed, position, &mut undefined_symbols);
+                        }
+
                         // Add the COFF to the list of parsed inputs.
                         parsed_inputs.push(PathedItem::new(input.path().as_path(), parsed));
                     }
@@ -143,13 +511,59 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
             }
         }
 
+        // Parsed lazy inputs which have not been added to the graph
+        let mut lazy_inputs = Vec::with_capacity(self.lazy_inputs.len());
+
+        // Map of symbols defined in the lazy inputs to the defining input
+        let mut lazy_symbols: IndexMap<&str, usize> = IndexMap::new();
+
+        // Parse the lazy input files
+        for input in &self.lazy_inputs {
+            match CoffFile::<_>::parse(input.as_slice())
+                .map_err(|e| LinkerSetupPathError::nomember(input.path(), e))
+            {
+                Ok(parsed) => {
+                    for symbol in parsed.symbols() {
+                        if symbol.is_global() && !symbol.is_undefined() {
+                            if let Ok(symbol_name) = symbol.name() {
+                                lazy_symbols.entry(symbol_name).or_insert(lazy_inputs.len());
+                            }
+                        }
+                    }
+
+                    lazy_inputs.push(Some(PathedItem::new(input.path().as_path(), parsed)));
+                }
+                Err(e) => {
+                    setup_errors.push(LinkerSetupError::Path(e));
+                }
+            }
+        }
+
+        self.report.finish_phase(LinkPhase::Parse, &mut phase_start);
+
+        let library_searcher = RecordingSearcher::new(&self.library_searcher, recorded);
+
         let library_arena = Arena::with_capacity(library_names.len() + 1);
 
+        // Mapped API symbol names
+        let mapped_api_symbols: Arena<String> = Arena::new();
+
+        // Paths of the opened link libraries
+        let mut library_paths: IndexMap<&str, &Path> = IndexMap::new();
+
         // Open link libraries
         for link_library in &self.library_names {
-            let found = match self.library_searcher.find_library(link_library) {
+            let library_start = Instant::now();
+            let found = match library_searcher.find_library(link_library) {
                 Ok(found) => {
-                    if link_libraries.contains_key(found.path().as_path()) {
+                    // Libraries found under more than one name are searched
+                    // at the positions of each name
+                    if let Some((opened, _)) = link_libraries.get_key_value(found.path().as_path())
+                    {
+                        let opened: &Path = opened;
+                        if library_paths.values().any(|path| *path == opened) {
+                            library_paths.insert(link_library, opened);
+                        }
                         continue;
                     }
 
@@ -161,7 +575,7 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
                 }
             };
 
-            let parsed = match LinkArchive::parse(found.as_slice()) {
+            let parsed = match LinkLibrary::parse(found.as_slice()) {
                 Ok(parsed) => parsed,
                 Err(e) => {
                     setup_errors.push(LinkerSetupError::Path(LinkerSetupPathError::nomember(
@@ -172,12 +586,22 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
                 }
             };
 
+            self.report
+                .library_times
+                .insert(found.path().to_path_buf(), library_start.elapsed());
+            self.report.open_library(found.path(), None);
+            library_paths.insert(link_library, found.path().as_path());
             link_libraries.insert(found.path().as_path(), parsed);
         }
 
+        if let Some(positions) = positions.as_mut() {
+            positions.set_library_paths(&library_paths);
+        }
+
         // Open drectve link libraries
         while let Some((coff_path, drectve_library)) = drectve_queue.pop_front() {
-            let found = match self.library_searcher.find_library(drectve_library) {
+            let library_start = Instant::now();
+            let found = match library_searcher.find_library(drectve_library) {
                 Ok(found) => {
                     if link_libraries.contains_key(found.path().as_path()) {
                         continue;
@@ -194,7 +618,7 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
                 }
             };
 
-            let parsed = match LinkArchive::parse(found.as_slice()) {
+            let parsed = match LinkLibrary::parse(found.as_slice()) {
                 Ok(parsed) => parsed,
                 Err(e) => {
                     setup_errors.push(LinkerSetupError::Path(LinkerSetupPathError::nomember(
@@ -205,55 +629,83 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
                 }
             };
 
+            self.report
+                .library_times
+                .insert(found.path().to_path_buf(), library_start.elapsed());
+            self.report
+                .open_library(found.path(), Some((coff_path, None)));
             link_libraries.insert(found.path().as_path(), parsed);
         }
 
-        let target_arch = self.target_arch.take().or_else(|| {
-            parsed_inputs
-                .iter()
-                .find_map(|coff| LinkerTargetArch::try_from(coff.architecture()).ok())
-        });
-
-        let target_arch = match target_arch {
+        let target_arch = match self.target_arch.take() {
             Some(target_arch) => target_arch,
-            None => {
-                if !setup_errors.is_empty() {
-                    return Err(LinkError::Setup(LinkerSetupErrors(setup_errors)));
-                }
+            None => match detect_input_architecture(
+                self.inputs
+                    .iter()
+                    .chain(&self.lazy_inputs)
+                    .map(|input| (input.path().as_path(), input.as_slice())),
+            ) {
+                Ok(target_arch) => target_arch,
+                Err(e) => {
+                    if !setup_errors.is_empty() {
+                        return Err(LinkError::Setup(LinkerSetupErrors(setup_errors)));
+                    }
 
-                if self.inputs.is_empty() {
-                    return Err(LinkError::NoInput);
+                    return Err(e);
                 }
+            },
+        };
 
-                return Err(LinkError::ArchitectureDetect);
+        // Parse the module-definition files now that the architecture of the
+        // imports is known
+        for def in &self.module_definitions {
+            match ModuleDefinition::parse(def.as_slice(), target_arch.into()) {
+                Ok(parsed) => {
+                    link_libraries
+                        .insert(def.path().as_path(), LinkLibrary::ModuleDefinition(parsed));
+                }
+                Err(e) => {
+                    setup_errors.push(LinkerSetupError::Path(LinkerSetupPathError::nomember(
+                        def.path(),
+                        e,
+                    )));
+                }
             }
-        };
+        }
 
-        // Initialize the custom API
-        let api_resolver = match self.custom_api.initialize_api(&ApiInitCtx {
+        // Initialize the custom APIs
+        let api_ctx = ApiInitCtx {
             target_arch,
-            library_searcher: &self.library_searcher,
+            library_searcher: &library_searcher,
             arena: &library_arena,
-        }) {
-            Ok(resolver) => resolver,
-            Err(e) => {
-                setup_errors.push(LinkerSetupError::ApiInit(e));
-                return Err(LinkError::Setup(LinkerSetupErrors(setup_errors)));
-            }
         };
 
+        let mut api_resolvers = Vec::with_capacity(self.custom_api.len());
+        for custom_api in &self.custom_api {
+            match custom_api.initialize_api(&api_ctx) {
+                Ok(resolver) => api_resolvers.push(resolver),
+                Err(e) => {
+                    setup_errors.push(LinkerSetupError::ApiInit(e));
+                }
+            }
+        }
+
+        self.report
+            .finish_phase(LinkPhase::LibrarySearch, &mut phase_start);
+
         // Check errors
         if !setup_errors.is_empty() {
             return Err(LinkError::Setup(LinkerSetupErrors(setup_errors)));
         }
 
-        if self.inputs.is_empty() {
+        if self.inputs.is_empty() && self.lazy_inputs.is_empty() {
             return Err(LinkError::NoInput);
         }
 
         // Build the graph
         let graph_arena = spec.alloc_arena();
         let mut graph = spec.alloc_graph(&graph_arena, target_arch);
+        graph.set_strip_resources(self.strip_resources);
 
         // Add COFFs
         for coff in parsed_inputs {
@@ -270,20 +722,58 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
             return Err(LinkError::Setup(LinkerSetupErrors(setup_errors)));
         }
 
+        if self.entry_first && self.entrypoint.is_none() {
+            self.entrypoint = Some("go".into());
+        }
+
         // Add the entrypoint symbol so that it can be linked in from archives
         if let Some(entrypoint) = &mut self.entrypoint {
             if target_arch == LinkerTargetArch::I386 {
                 *entrypoint = format!("_{entrypoint}");
             }
+        }
 
+        if let Some(entrypoint) = self.entrypoint.as_deref() {
             graph.add_external_symbol(entrypoint);
         }
 
-        let mut drectve_queue: VecDeque<((&Path, &Path), &str)> = VecDeque::new();
+        // Add the redirect targets so that they can be linked in
+        for (_, to) in &self.redirected_symbols {
+            graph.add_external_symbol(to);
+        }
+
+        for symbol in &self.loader_externs {
+            graph.add_loader_extern(symbol);
+        }
+
+        for section in &self.added_sections {
+            graph.add_section(
+                &section.name,
+                section.characteristics,
+                &section.data,
+                section.symbol.as_deref(),
+            );
+        }
+
+        let mut drectve_queue: VecDeque<((&Path, Option<&Path>), &str)> = VecDeque::new();
 
         let undefined_count = graph.undefined_symbols().count();
         let mut symbol_search_buffer = VecDeque::with_capacity(undefined_count);
-        let mut undefined_symbols: IndexSet<&str> = IndexSet::with_capacity(undefined_count);
+        undefined_symbols.reserve(undefined_count);
+
+        // Link libraries to search for the current symbol in order along
+        // with the positional library occurrence
+        let mut library_search_order: Vec<(&Path, Option<usize>)> = Vec::new();
+
+        // Number of members extracted from each link library
+        let mut library_members: IndexMap<&Path, usize> = IndexMap::new();
+
+        // Resolution steps for the queried symbols
+        let mut symbol_queries: IndexMap<String, SymbolQuery> = self
+            .why_undefined
+            .iter()
+            .map(|symbol| (symbol.clone(), SymbolQuery::new(symbol.as_str())))
+            .collect();
 
         // Resolve symbols
         loop {
@@ -301,102 +791,318 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
 
             // Attempt to resolve each symbol in the search list
             'symbol: while let Some(symbol_name) = symbol_search_buffer.pop_front() {
-                // Try resolving it as an API import first
-                match api_resolver.extract_api_symbol(symbol_name) {
-                    Ok(api_import) => {
-                        if let Err(e) = graph.add_api_import(symbol_name, &api_import) {
+                // Try resolving it as an API import first using the APIs in
+                // priority order
+                let api_symbol_name = match self.api_symbol_map.map_symbol(symbol_name) {
+                    Some(mapped) => {
+                        debug!(
+                            symbol = symbol_name;
+                            "mapping API symbol '{symbol_name}' to '{mapped}'"
+                        );
+                        Some(mapped_api_symbols.alloc(mapped).as_str())
+                    }
+                    None => None,
+                };
+
+                let mut query = symbol_queries.get_mut(symbol_name);
+                if let Some(query) = query.as_mut() {
+                    query.api_symbol = api_symbol_name.map(str::to_string);
+                }
+
+                for api_resolver in &api_resolvers {
+                    match api_resolver.extract_api_symbol(api_symbol_name.unwrap_or(symbol_name)) {
+                        Ok(api_import) => {
+                            if let Err(e) = graph.add_api_import(symbol_name, &api_import) {
+                                if let Some(query) = query.as_mut() {
+                                    query.probe(
+                                        ProbeSource::Api,
+                                        api_resolver.api_path(),
+                                        ProbeResult::Error(e.to_string()),
+                                    );
+                                }
+
+                                setup_errors.push(LinkerSetupError::Path(
+                                    LinkerSetupPathError::nomember(api_resolver.api_path(), e),
+                                ));
+                                break;
+                            }
+
+                            if let Some(query) = query.as_mut() {
+                                query.probe(
+                                    ProbeSource::Api,
+                                    api_resolver.api_path(),
+                                    ProbeResult::Found(None),
+                                );
+                            }
+
+                            if let Some(api_symbol_name) = api_symbol_name {
+                                graph.set_output_name(symbol_name, api_symbol_name);
+                            }
+
+                            debug!(
+                                library:% = api_resolver.api_path().display(),
+                                symbol = symbol_name;
+                                "{}: resolved API symbol '{symbol_name}'",
+                                api_resolver.api_path().display()
+                            );
+
+                            self.report.api_symbols.insert(
+                                symbol_name.to_string(),
+                                api_resolver.api_path().to_path_buf(),
+                            );
+
+                            if let Some((category, name)) =
+                                BeaconApiCategory::of_import(&api_import)
+                            {
+                                self.report
+                                    .api_categories
+                                    .entry(category)
+                                    .or_default()
+                                    .insert(name.to_string());
+                            }
+
+                            continue 'symbol;
+                        }
+                        Err(ApiSymbolError::NotFound) => {
+                            if let Some(query) = query.as_mut() {
+                                query.probe(
+                                    ProbeSource::Api,
+                                    api_resolver.api_path(),
+                                    ProbeResult::NotFound,
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            if let Some(query) = query.as_mut() {
+                                query.probe(
+                                    ProbeSource::Api,
+                                    api_resolver.api_path(),
+                                    ProbeResult::Error(e.to_string()),
+                                );
+                            }
+
                             setup_errors.push(LinkerSetupError::Path(
                                 LinkerSetupPathError::nomember(api_resolver.api_path(), e),
                             ));
-                        } else {
-                            continue;
                         }
                     }
-                    Err(ApiSymbolError::NotFound) => (),
-                    Err(e) => {
-                        setup_errors.push(LinkerSetupError::Path(LinkerSetupPathError::nomember(
-                            api_resolver.api_path(),
-                            e,
-                        )));
-                    }
                 }
 
                 // Open any pending libraries in the .drectve queue
-                while let Some(((library_path, coff_path), drectve_library)) =
+                while let Some(((file_path, member_path), drectve_library)) =
                     drectve_queue.pop_front()
                 {
-                    match self.library_searcher.find_library(drectve_library) {
-                        Ok(found) => {
-                            if library_names.insert(drectve_library) {
-                                let found = library_arena.alloc(found);
+                    if !library_names.insert(drectve_library) {
+                        continue;
+                    }
 
-                                match LinkArchive::parse(found.as_slice()) {
-                                    Ok(parsed) => {
-                                        link_libraries.insert(found.path().as_path(), parsed);
-                                    }
-                                    Err(e) => {
-                                        setup_errors.push(LinkerSetupError::Path(
-                                            LinkerSetupPathError::new(
-                                                library_path,
-                                                Some(coff_path),
-                                                e,
-                                            ),
-                                        ));
-                                    }
-                                }
+                    let library_start = Instant::now();
+                    let found = match library_searcher.find_library(drectve_library) {
+                        Ok(found) => {
+                            if link_libraries.contains_key(found.path().as_path()) {
+                                continue;
                             }
+
+                            library_arena.alloc(found)
                         }
                         Err(e) => {
                             setup_errors.push(LinkerSetupError::Path(LinkerSetupPathError::new(
-                                library_path,
-                                Some(coff_path),
+                                file_path,
+                                member_path,
                                 DrectveLibsearchError::from(e),
                             )));
+                            continue;
+                        }
+                    };
+
+                    match LinkLibrary::parse(found.as_slice()) {
+                        Ok(parsed) => {
+                            self.report
+                                .library_times
+                                .insert(found.path().to_path_buf(), library_start.elapsed());
+                            self.report
+                                .open_library(found.path(), Some((file_path, member_path)));
+                            link_libraries.insert(found.path().as_path(), parsed);
+                        }
+                        Err(e) => {
+                            setup_errors.push(LinkerSetupError::Path(
+                                LinkerSetupPathError::nomember(found.path(), e),
+                            ));
+                        }
+                    }
+                }
+
+                // Add the lazy input defining the symbol
+                if let Some(lazy_input) = lazy_symbols
+                    .get(symbol_name)
+                    .and_then(|idx| lazy_inputs[*idx].take())
+                {
+                    for drectve_library in
+                        drectve::parse_drectve_libraries(&lazy_input, &drectve_strings)
+                            .into_iter()
+                            .flatten()
+                    {
+                        let drectve_library = drectve_library.trim_end_matches(".lib");
+                        if !library_names.contains(drectve_library) {
+                            drectve_queue.push_back(((lazy_input.path(), None), drectve_library));
                         }
                     }
+
+                    if let Some(positions) = positions.as_mut() {
+                        positions.reference_symbols(&lazy_input, 0, &mut undefined_symbols);
+                    }
+
+                    let result = graph.add_coff(lazy_input.path(), None, &lazy_input);
+                    if let Some(query) = query.as_mut() {
+                        query.probe(
+                            ProbeSource::LazyInput,
+                            lazy_input.path(),
+                            match &result {
+                                Ok(_) => ProbeResult::Found(None),
+                                Err(e) => ProbeResult::Error(e.to_string()),
+                            },
+                        );
+                    }
+
+                    if let Err(e) = result {
+                        setup_errors.push(LinkerSetupError::Path(LinkerSetupPathError::nomember(
+                            lazy_input.path(),
+                            e,
+                        )));
+                    }
+
+                    continue 'symbol;
                 }
 
                 // Attempt to resolve the symbol using the opened link libraries
-                for (library_path, library) in &link_libraries {
-                    let extracted =
-                        match library.extract_symbol(symbol_name) {
-                            Ok(extracted) => extracted,
-                            Err(ExtractMemberError::NotFound) => {
-                                continue;
+                library_search_order.clear();
+                match positions.as_ref() {
+                    Some(positions) => {
+                        library_search_order.extend(
+                            positions
+                                .search_order(symbol_name)
+                                .iter()
+                                .map(|(occurrence, path)| (*path, Some(*occurrence))),
+                        );
+                        library_search_order.extend(
+                            link_libraries
+                                .keys()
+                                .filter(|path| !positions.contains(path))
+                                .map(|path| (*path, None)),
+                        );
+                    }
+                    None => {
+                        library_search_order
+                            .extend(link_libraries.keys().map(|path| (*path, None)));
+                    }
+                }
+
+                for &(library_path, occurrence) in &library_search_order {
+                    let library = &link_libraries[&library_path];
+                    let extracted = match library.extract_symbol(symbol_name, target_arch.into()) {
+                        Ok(extracted) => extracted,
+                        Err(ExtractMemberError::NotFound) => {
+                            if let Some(query) = query.as_mut() {
+                                query.probe(
+                                    ProbeSource::Library,
+                                    library_path,
+                                    ProbeResult::NotFound,
+                                );
                             }
-                            Err(ExtractMemberError::ArchiveParse(e)) => {
-                                setup_errors.push(LinkerSetupError::Path(
-                                    LinkerSetupPathError::nomember(library_path, e),
-                                ));
-                                continue;
+                            continue;
+                        }
+                        Err(ExtractMemberError::ArchiveParse(e)) => {
+                            let e = LinkerSetupPathError::nomember(library_path, e);
+                            if let Some(query) = query.as_mut() {
+                                query.probe(
+                                    ProbeSource::Library,
+                                    library_path,
+                                    ProbeResult::Error(e.to_string()),
+                                );
                             }
-                            Err(ExtractMemberError::MemberParse(e)) => {
-                                setup_errors.push(LinkerSetupError::Path(
-                                    LinkerSetupPathError::new(library_path, Some(e.path), e.kind),
-                                ));
-                                continue;
+                            if self.tolerant {
+                                self.report.skip_member(e);
+                            } else {
+                                setup_errors.push(LinkerSetupError::Path(e));
+                            }
+                            continue;
+                        }
+                        Err(ExtractMemberError::MemberParse(e)) => {
+                            let e = LinkerSetupPathError::new(library_path, Some(e.path), e.kind);
+                            if let Some(query) = query.as_mut() {
+                                query.probe(
+                                    ProbeSource::Library,
+                                    library_path,
+                                    ProbeResult::Error(e.to_string()),
+                                );
+                            }
+                            if self.tolerant {
+                                self.report.skip_member(e);
+                            } else {
+                                setup_errors.push(LinkerSetupError::Path(e));
                             }
-                        };
+                            continue;
+                        }
+                    };
 
                     match extracted.contents() {
                         ExtractedMemberContents::Coff(coff) => {
+                            // Search the remaining libraries if the member is
+                            // a duplicate of a previous input
+                            if self.dedup_inputs {
+                                let coff_node = CoffNode::new(library_path, Some(extracted.path()));
+                                if let Some(original) = input_contents.get(extracted.data()) {
+                                    if *original != coff_node {
+                                        if let Some(query) = query.as_mut() {
+                                            query.probe(
+                                                ProbeSource::Library,
+                                                library_path,
+                                                ProbeResult::Duplicate(
+                                                    extracted.path().to_path_buf(),
+                                                ),
+                                            );
+                                        }
+
+                                        self.report.skip_duplicate(&coff_node, original);
+                                        continue;
+                                    }
+                                } else {
+                                    input_contents.insert(extracted.data(), coff_node);
+                                }
+                            }
+
                             // Add any .drectve link libraries from linked in COFFs
                             // to the drectve queue
                             for drectve_library in
-                                drectve::parse_drectve_libraries(coff).into_iter().flatten()
+                                drectve::parse_drectve_libraries(coff, &drectve_strings)
+                                    .into_iter()
+                                    .flatten()
                             {
                                 let drectve_library_name = drectve_library.trim_end_matches(".lib");
-                                if library_names.contains(drectve_library) {
+                                if !library_names.contains(drectve_library_name) {
                                     drectve_queue.push_back((
-                                        (library_path, extracted.path()),
+                                        (library_path, Some(extracted.path())),
                                         drectve_library_name,
                                     ));
                                 }
                             }
 
-                            if let Err(e) =
-                                graph.add_coff(library_path, Some(extracted.path()), coff)
-                            {
+                            if let Some(positions) = positions.as_mut() {
+                                let position =
+                                    occurrence.map_or(0, |idx| positions.member_position(idx));
+                                positions.reference_symbols(coff, position, &mut undefined_symbols);
+                            }
+
+                            let result = graph.add_coff(library_path, Some(extracted.path()), coff);
+                            if let Some(query) = query.as_mut() {
+                                query.probe(
+                                    ProbeSource::Library,
+                                    library_path,
+                                    member_probe_result(extracted.path(), &result),
+                                );
+                            }
+
+                            if let Err(e) = result {
                                 setup_errors.push(LinkerSetupError::Path(
                                     LinkerSetupPathError::new(
                                         library_path,
@@ -407,10 +1113,20 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
                                 continue;
                             }
 
+                            *library_members.entry(library_path).or_default() += 1;
                             continue 'symbol;
                         }
                         ExtractedMemberContents::Import(import_member) => {
-                            if let Err(e) = graph.add_library_import(symbol_name, import_member) {
+                            let result = graph.add_library_import(symbol_name, import_member);
+                            if let Some(query) = query.as_mut() {
+                                query.probe(
+                                    ProbeSource::Library,
+                                    library_path,
+                                    member_probe_result(extracted.path(), &result),
+                                );
+                            }
+
+                            if let Err(e) = result {
                                 setup_errors.push(LinkerSetupError::Path(
                                     LinkerSetupPathError::new(
                                         library_path,
@@ -421,6 +1137,7 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
                                 continue;
                             }
 
+                            *library_members.entry(library_path).or_default() += 1;
                             continue 'symbol;
                         }
                     }
@@ -431,6 +1148,116 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
             }
         }
 
+        if !self.intrinsic_groups.is_empty() {
+            let synthesized = graph.synthesize_intrinsics(&self.intrinsic_groups);
+            if !synthesized.is_empty() {
+                warn!(
+                    "synthesized compiler intrinsics: {}",
+                    synthesized.join(", ")
+                );
+            }
+        }
+
+        for (library_path, library) in &link_libraries {
+            if let Some(stats) = library.lookup_stats() {
+                debug!(
+                    library:% = library_path.display();
+                    "{}: {} symbol lookups, {} cached, {} missing, {} symbols scanned{}",
+                    library_path.display(),
+                    stats.lookups,
+                    stats.cache_hits,
+                    stats.misses,
+                    stats.scanned,
+                    if stats.eager { " (indexed up front)" } else { "" },
+                );
+            }
+
+            let members = library_members.get(library_path).copied().unwrap_or(0);
+            if members == 0 && self.warn_unused_libraries {
+                warn!(
+                    library:% = library_path.display();
+                    "{}: library was not used",
+                    library_path.display()
+                );
+            }
+
+            self.report
+                .library_members
+                .insert(library_path.to_path_buf(), members);
+        }
+
+        if self.cross_reference {
+            self.report.cross_references = graph.cross_references();
+        }
+
+        for (symbol_name, mut query) in symbol_queries {
+            if let Some(cross_reference) = graph.symbol_cross_reference(&symbol_name) {
+                query.status = if undefined_symbols.contains(symbol_name.as_str()) {
+                    SymbolStatus::Undefined
+                } else if cross_reference.definitions.is_empty()
+                    && self.loader_externs.contains(&symbol_name)
+                {
+                    SymbolStatus::LoaderExtern
+                } else if query
+                    .probes
+                    .iter()
+                    .any(|probe| matches!(probe.result, ProbeResult::Found(_)))
+                {
+                    SymbolStatus::Resolved
+                } else {
+                    SymbolStatus::Defined
+                };
+
+                query.definitions = cross_reference.definitions;
+                query.references = cross_reference.references;
+            }
+
+            if query.status == SymbolStatus::Undefined {
+                for (library_path, library) in &link_libraries {
+                    query.candidates.extend(
+                        library
+                            .find_folded(&symbol_name)
+                            .into_iter()
+                            .filter(|candidate| *candidate != symbol_name)
+                            .map(|candidate| (library_path.to_path_buf(), candidate.to_string())),
+                    );
+                }
+            }
+
+            self.report.symbol_queries.push(query);
+        }
+
+        // Add the .comment section with the linker version and the inputs
+        if self.emit_comment {
+            let mut comment = format!("boflink {}\0", env!("CARGO_PKG_VERSION"));
+            for coff in graph.coffs() {
+                comment.push_str(&format!("{}\0", coff.short_name()));
+            }
+
+            graph.add_section(
+                ".comment",
+                SectionNodeCharacteristics::LnkInfo | SectionNodeCharacteristics::Align1Bytes,
+                comment.as_bytes(),
+                None,
+            );
+        }
+
+        // Add the .ident section with the linker version and the user string
+        if let Some(ident) = self.ident.as_deref() {
+            let mut data = format!("boflink {}\0", env!("CARGO_PKG_VERSION"));
+            if !ident.is_empty() {
+                data.push_str(ident);
+                data.push('\0');
+            }
+
+            graph.add_section(
+                ".ident",
+                SectionNodeCharacteristics::LnkInfo | SectionNodeCharacteristics::Align1Bytes,
+                data.as_bytes(),
+                None,
+            );
+        }
+
         // Write out the link graph
         if let Some(graph_path) = self.link_graph_output.as_ref() {
             match std::fs::File::create(graph_path) {
@@ -445,6 +1272,13 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
             }
         }
 
+        if let Some(inspector) = self.graph_inspector.as_ref() {
+            inspector(&graph);
+        }
+
+        self.report
+            .finish_phase(LinkPhase::SymbolResolution, &mut phase_start);
+
         // Return errors
         if !setup_errors.is_empty() {
             return Err(LinkError::Setup(LinkerSetupErrors(setup_errors)));
@@ -454,16 +1288,140 @@ impl<L: LibraryFind, A: ApiInit> LinkImpl for ConfiguredLinker<L, A> {
         let mut graph = match graph.finish() {
             Ok(graph) => graph,
             Err(e) => {
-                return Err(LinkError::Symbol(LinkerSymbolErrors(
-                    e.into_iter().map(|v| v.to_string()).collect(),
-                )));
+                return Err(LinkError::Symbol(LinkerSymbolErrors::new(e)));
             }
         };
 
+        if let Some(code_fill) = self.code_fill {
+            graph.set_code_fill(code_fill);
+        }
+
+        if let Some(data_fill) = self.data_fill {
+            graph.set_data_fill(data_fill);
+        }
+
+        if let Some(alignment) = self.min_section_alignment {
+            graph.set_min_section_alignment(alignment);
+        }
+
+        graph.set_default_section_alignment(self.default_section_alignment);
+        graph.set_flatten_relocations(self.flatten_relocations);
+        graph.set_check_relocations(self.check_relocations);
+        graph.set_deterministic(self.deterministic);
+        graph.set_absolute_symbols(self.absolute_symbols.iter().cloned());
+        graph.set_keep_symbols(self.keep_symbols.iter().cloned());
+        graph.set_keep_sections(self.keep_sections.iter().cloned());
+        graph.set_delay_load_libraries(self.delay_load_libraries.iter().cloned());
+        graph.set_section_checksums(self.section_checksums);
+        graph.set_function_aux(self.function_aux);
+        graph.set_collapse_refptrs(self.collapse_refptrs);
+        graph.set_rip_relative(self.rip_relative);
+        graph.set_collect_strings(self.collect_strings);
+        graph.set_collect_symbol_sizes(self.collect_symbol_sizes);
+        graph.set_obfuscated_sections(self.obfuscated_sections.iter().cloned());
+
+        if let Some(key) = self.obfuscation_key.as_ref() {
+            graph.set_obfuscation_key(key.clone());
+        }
+
+        if let Some(entrypoint) = self.entrypoint.as_deref().filter(|_| self.entry_first) {
+            graph.set_entry_first(entrypoint);
+        }
+
+        if self.safeseh && target_arch != LinkerTargetArch::I386 {
+            warn!("safe exception handlers are only checked for i386 targets");
+        }
+
+        graph.set_safeseh(self.safeseh);
+        graph.set_characteristics_policy(self.characteristics_policy);
+        graph.set_lnk_info_policy(self.lnk_info_policy);
+
+        if let Some(layout) = self.layout.as_ref() {
+            graph.set_layout(layout.clone());
+        }
+
+        if let Some(profile) = self.profile.as_ref() {
+            graph.set_profile(profile.clone());
+        } else if self.split_cold {
+            warn!("splitting cold code requires a profile");
+        }
+
+        graph.set_split_cold(self.split_cold);
+        graph.set_pack_sections(self.pack_sections);
+
         if self.merge_bss {
             graph.merge_bss();
         }
 
-        Ok(graph.link()?)
+        for (from, to) in &self.redirected_symbols {
+            graph
+                .redirect_symbol(from, to)
+                .map_err(LinkGraphLinkError::from)?;
+        }
+
+        // Rename symbols
+        if let Some(mode) = self.rename_symbols {
+            let preserve = Vec::from_iter(self.entrypoint.as_deref());
+            let renamed = graph.rename_symbols(mode, &preserve);
+
+            if let Some(map_path) = self.symbol_map_output.as_ref() {
+                std::fs::File::create(map_path)
+                    .and_then(|f| {
+                        let mut w = BufWriter::new(f);
+                        renamed
+                            .iter()
+                            .try_for_each(|(old, new)| writeln!(w, "{old}\t{new}"))?;
+                        w.flush()
+                    })
+                    .map_err(|error| LinkError::Write {
+                        path: map_path.clone(),
+                        error,
+                    })?;
+            }
+        }
+
+        self.report
+            .finish_phase(LinkPhase::GraphFinish, &mut phase_start);
+
+        let timed = graph.link_timed()?;
+        let mut linked = timed.linked;
+        self.report.relocation_counts = timed.relocation_counts;
+        self.report.checked_relocations = timed.checked_relocations;
+        self.report.strings = timed.strings;
+        self.report.symbol_sizes = timed.symbol_sizes;
+        self.report.packed_sections = timed.packed_sections;
+
+        finish_output(
+            &mut linked,
+            self.output_alignment,
+            self.output_trailer.as_ref(),
+        );
+
+        // Write out the layout for the next link
+        if let Some(layout_path) = self.layout_output.as_ref() {
+            if let Err(e) = std::fs::write(layout_path, timed.layout.to_string()) {
+                warn!("could not write layout {}: {e}", layout_path.display());
+            }
+        }
+
+        let write_time = phase_start.elapsed().saturating_sub(timed.layout_time);
+        self.report.phase_times.extend([
+            (LinkPhase::Layout, timed.layout_time),
+            (LinkPhase::Write, write_time),
+        ]);
+
+        Ok(linked)
+    }
+}
+
+/// Returns the [`ProbeResult`] for adding the archive member at `member` to
+/// the link graph.
+fn member_probe_result<T, E: std::fmt::Display>(
+    member: &Path,
+    result: &Result<T, E>,
+) -> ProbeResult {
+    match result {
+        Ok(_) => ProbeResult::Found(Some(member.to_path_buf())),
+        Err(e) => ProbeResult::Error(e.to_string()),
     }
 }
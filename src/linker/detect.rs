@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use object::{
+    LittleEndian,
+    pe::{IMAGE_FILE_MACHINE_UNKNOWN, ImageFileHeader},
+    read::{archive::ArchiveFile, coff::CoffHeader},
+};
+
+use crate::{linkobject::dll::DllImports, pathed_item::PathedItem};
+
+use super::{
+    LinkerTargetArch,
+    error::{ArchitectureConflictError, LinkError},
+};
+
+/// The machine type of an input file.
+struct InputMachine {
+    /// The machine value from the file header.
+    machine: u16,
+
+    /// Whether the input has any sections.
+    has_sections: bool,
+}
+
+/// Detects the target architecture from the input files.
+///
+/// COFFs and the members of archives are considered. Inputs without a machine
+/// type are ignored and COFFs without any sections are only used if no other
+/// input specifies a machine type. Archives with members for different
+/// machines are also ignored.
+///
+/// Returns an error listing the machine type of each input if the inputs do
+/// not agree on the architecture.
+pub fn detect_architecture<P: AsRef<Path>, D: std::ops::Deref<Target = [u8]>>(
+    inputs: &[PathedItem<P, D>],
+) -> Result<LinkerTargetArch, LinkError> {
+    detect_input_architecture(
+        inputs
+            .iter()
+            .map(|input| (input.path().as_ref(), &***input)),
+    )
+}
+
+/// Detects the target architecture from the paths and data of the input
+/// files.
+pub(super) fn detect_input_architecture<'a>(
+    inputs: impl IntoIterator<Item = (&'a Path, &'a [u8])>,
+) -> Result<LinkerTargetArch, LinkError> {
+    let mut found_inputs = false;
+    let mut machines = Vec::new();
+    let mut empty_machines = Vec::new();
+
+    for (path, data) in inputs {
+        found_inputs = true;
+
+        let Some(input) = input_machine(data) else {
+            continue;
+        };
+
+        if input.has_sections {
+            machines.push((path, input.machine));
+        } else {
+            empty_machines.push((path, input.machine));
+        }
+    }
+
+    if !found_inputs {
+        return Err(LinkError::NoInput);
+    }
+
+    let machines = if machines.is_empty() {
+        empty_machines
+    } else {
+        machines
+    };
+
+    let Some((_, machine)) = machines.first().copied() else {
+        return Err(LinkError::ArchitectureDetect);
+    };
+
+    if machines.iter().all(|(_, other)| *other == machine) {
+        if let Ok(target_arch) = LinkerTargetArch::try_from(machine) {
+            return Ok(target_arch);
+        }
+    }
+
+    Err(LinkError::ArchitectureConflict(
+        ArchitectureConflictError::new(
+            machines
+                .into_iter()
+                .map(|(path, machine)| (path.to_path_buf(), machine))
+                .collect(),
+        ),
+    ))
+}
+
+/// Returns the machine type of the input data.
+///
+/// Returns [`None`] if the input does not specify a single machine type.
+fn input_machine(data: &[u8]) -> Option<InputMachine> {
+    if data
+        .get(..object::archive::MAGIC.len())
+        .is_some_and(|magic| magic == object::archive::MAGIC)
+    {
+        let archive = ArchiveFile::parse(data).ok()?;
+
+        let mut archive_machine = None;
+        for member in archive.members() {
+            let member_data = member.ok()?.data(data).ok()?;
+            let Some(member) = object_machine(member_data) else {
+                continue;
+            };
+
+            match archive_machine {
+                Some(machine) if machine != member.machine => return None,
+                _ => archive_machine = Some(member.machine),
+            }
+        }
+
+        return archive_machine.map(|machine| InputMachine {
+            machine,
+            has_sections: true,
+        });
+    }
+
+    // DLLs are only used for resolving imports
+    if DllImports::is_dll(data) {
+        return None;
+    }
+
+    object_machine(data)
+}
+
+/// Returns the machine type of the COFF or import member data.
+fn object_machine(data: &[u8]) -> Option<InputMachine> {
+    // Import members and anonymous objects have the machine after the
+    // signatures and version
+    let input = if data.get(..4).is_some_and(|sig| sig == [0, 0, 0xff, 0xff]) {
+        InputMachine {
+            machine: u16::from_le_bytes(data.get(6..8)?.try_into().ok()?),
+            has_sections: true,
+        }
+    } else {
+        let header = ImageFileHeader::parse(data, &mut 0).ok()?;
+        InputMachine {
+            machine: header.machine.get(LittleEndian),
+            has_sections: header.number_of_sections() > 0,
+        }
+    };
+
+    (input.machine != IMAGE_FILE_MACHINE_UNKNOWN).then_some(input)
+}
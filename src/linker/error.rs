@@ -1,10 +1,19 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use object::pe::{
+    IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64, IMAGE_FILE_MACHINE_ARMNT,
+    IMAGE_FILE_MACHINE_I386,
+};
 
 use crate::{
     api::ApiSymbolError,
-    graph::{LinkGraphAddError, LinkGraphLinkError},
+    graph::{LinkGraphAddError, LinkGraphLinkError, SymbolError, SymbolErrorKind},
     libsearch::LibsearchError,
-    linkobject::archive::{ArchiveParseError, LinkArchiveParseError, MemberParseErrorKind},
+    linkobject::{
+        archive::{ArchiveParseError, LinkArchiveParseError, MemberParseErrorKind},
+        library::LinkLibraryParseError,
+        moddef::ModuleDefinitionParseError,
+    },
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -23,6 +32,46 @@ pub enum LinkError {
 
     #[error("could not detect architecture")]
     ArchitectureDetect,
+
+    #[error("{0}")]
+    ArchitectureConflict(ArchitectureConflictError),
+
+    #[error("could not open {}: {error}", .path.display())]
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    #[error("could not write {}: {error}", .path.display())]
+    Write {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+/// The inputs used for detecting the architecture did not agree on a
+/// supported machine type.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "could not detect architecture, inputs have conflicting or unsupported machine types{}",
+    display_machines(.machines)
+)]
+pub struct ArchitectureConflictError {
+    machines: Vec<(PathBuf, u16)>,
+}
+
+impl ArchitectureConflictError {
+    pub(super) fn new(machines: Vec<(PathBuf, u16)>) -> ArchitectureConflictError {
+        Self { machines }
+    }
+
+    /// Returns the paths of the inputs along with the machine value of each
+    /// input.
+    pub fn machines(&self) -> impl Iterator<Item = (&Path, u16)> {
+        self.machines
+            .iter()
+            .map(|(path, machine)| (path.as_path(), *machine))
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -45,6 +94,9 @@ pub enum LinkerSetupError {
 
     #[error("{0}")]
     ApiInit(ApiInitError),
+
+    #[error("{0}")]
+    ApiSymbolMap(ApiSymbolMapError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -119,6 +171,12 @@ pub enum LinkerPathErrorKind {
     #[error("{0}")]
     ArchiveParse(#[from] LinkArchiveParseError),
 
+    #[error("{0}")]
+    LibraryParse(#[from] LinkLibraryParseError),
+
+    #[error("{0}")]
+    ModuleDefinitionParse(#[from] ModuleDefinitionParseError),
+
     #[error("{0}")]
     ArchiveExtract(#[from] ArchiveParseError),
 
@@ -135,6 +193,31 @@ pub enum LinkerPathErrorKind {
     Object(#[from] object::Error),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum ApiSymbolMapParseError {
+    #[error("line {0}: expected a symbol name followed by the replacement name")]
+    Syntax(usize),
+
+    #[error("line {0}: prefix mappings must end with '*' on both names")]
+    Prefix(usize),
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("API symbol mapping '{from}' -> '{to}' mixes a prefix with a symbol name")]
+pub struct ApiSymbolMapError {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExternsParseError {
+    #[error("expected a JSON array of symbol names")]
+    Json,
+
+    #[error("line {0}: expected a '- name' sequence item")]
+    Syntax(usize),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DrectveLibsearchError {
     #[error("unable to find library {0}")]
@@ -157,12 +240,82 @@ impl From<LibsearchError> for DrectveLibsearchError {
 }
 
 #[derive(Debug, thiserror::Error)]
-#[error("{}", display_vec(.0))]
-pub struct LinkerSymbolErrors(pub(super) Vec<String>);
+#[error("{}", display_vec(.errors))]
+pub struct LinkerSymbolErrors {
+    errors: Vec<String>,
+    diagnostics: Vec<SymbolDiagnostic>,
+}
 
 impl LinkerSymbolErrors {
+    pub(super) fn new(errors: Vec<SymbolError<'_, '_>>) -> LinkerSymbolErrors {
+        Self {
+            errors: errors.iter().map(ToString::to_string).collect(),
+            diagnostics: errors
+                .iter()
+                .map(|error| SymbolDiagnostic {
+                    kind: error.kind(),
+                    symbol: error.symbol_name().to_string(),
+                    message: error.message(),
+                    notes: error.notes(),
+                    help: error.help(),
+                })
+                .collect(),
+        }
+    }
+
     pub fn errors(&self) -> &[String] {
-        &self.0
+        &self.errors
+    }
+
+    /// Returns an iterator over the kind of each error along with the name
+    /// of the symbol.
+    pub fn symbols(&self) -> impl Iterator<Item = (SymbolErrorKind, &str)> {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.kind, diagnostic.symbol.as_str()))
+    }
+
+    /// Returns the errors split into the message and notes for rendering.
+    pub fn diagnostics(&self) -> &[SymbolDiagnostic] {
+        &self.diagnostics
+    }
+}
+
+/// A symbol error split into the primary message and the notes listing
+/// where the symbol is defined or referenced.
+#[derive(Debug, Clone)]
+pub struct SymbolDiagnostic {
+    kind: SymbolErrorKind,
+    symbol: String,
+    message: String,
+    notes: Vec<String>,
+    help: Option<&'static str>,
+}
+
+impl SymbolDiagnostic {
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> SymbolErrorKind {
+        self.kind
+    }
+
+    /// Returns the name of the symbol with the error.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Returns the error message without the notes.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the notes for the error.
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    /// Returns the guidance for fixing the error.
+    pub fn help(&self) -> Option<&str> {
+        self.help
     }
 }
 
@@ -190,3 +343,30 @@ impl<'a, T: std::fmt::Display> std::fmt::Display for DisplayVec<'a, T> {
 fn display_vec<T: std::fmt::Display>(errors: &Vec<T>) -> DisplayVec<'_, T> {
     DisplayVec(errors)
 }
+
+struct DisplayMachines<'a>(&'a [(PathBuf, u16)]);
+
+impl std::fmt::Display for DisplayMachines<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (path, machine) in self.0 {
+            let name = match *machine {
+                IMAGE_FILE_MACHINE_AMD64 => "amd64",
+                IMAGE_FILE_MACHINE_I386 => "i386",
+                IMAGE_FILE_MACHINE_ARM64 => "arm64",
+                IMAGE_FILE_MACHINE_ARMNT => "arm",
+                _ => {
+                    write!(f, "\n>>> {}: machine {machine:#06x}", path.display())?;
+                    continue;
+                }
+            };
+
+            write!(f, "\n>>> {}: {name}", path.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn display_machines(machines: &[(PathBuf, u16)]) -> DisplayMachines<'_> {
+    DisplayMachines(machines)
+}
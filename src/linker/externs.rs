@@ -0,0 +1,149 @@
+use super::error::ExternsParseError;
+
+/// Parses a file listing the symbols provided by the loader.
+///
+/// The file is either a JSON array of symbol names or a YAML block sequence
+/// with one `- name` item per line. Names in the YAML sequence may be
+/// quoted. Lines starting with `#` are ignored in the YAML sequence.
+pub fn parse_externs(content: &str) -> Result<Vec<String>, ExternsParseError> {
+    if content.trim_start().starts_with('[') {
+        parse_json_externs(content)
+    } else {
+        parse_yaml_externs(content)
+    }
+}
+
+/// Parses a JSON array of strings.
+fn parse_json_externs(content: &str) -> Result<Vec<String>, ExternsParseError> {
+    let mut chars = content.trim().chars().peekable();
+    let mut symbols = Vec::new();
+
+    let skip_whitespace = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    };
+
+    if chars.next() != Some('[') {
+        return Err(ExternsParseError::Json);
+    }
+
+    skip_whitespace(&mut chars);
+    if chars.next_if_eq(&']').is_some() {
+        skip_whitespace(&mut chars);
+        return match chars.next() {
+            None => Ok(symbols),
+            Some(_) => Err(ExternsParseError::Json),
+        };
+    }
+
+    loop {
+        skip_whitespace(&mut chars);
+        if chars.next() != Some('"') {
+            return Err(ExternsParseError::Json);
+        }
+
+        let mut symbol = String::new();
+        loop {
+            match chars.next().ok_or(ExternsParseError::Json)? {
+                '"' => break,
+                '\\' => symbol.push(match chars.next().ok_or(ExternsParseError::Json)? {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'u' => {
+                        let code: String = chars.by_ref().take(4).collect();
+                        u32::from_str_radix(&code, 16)
+                            .ok()
+                            .filter(|_| code.len() == 4)
+                            .and_then(char::from_u32)
+                            .ok_or(ExternsParseError::Json)?
+                    }
+                    _ => return Err(ExternsParseError::Json),
+                }),
+                c => symbol.push(c),
+            }
+        }
+
+        symbols.push(symbol);
+
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some(']') => break,
+            _ => return Err(ExternsParseError::Json),
+        }
+    }
+
+    skip_whitespace(&mut chars);
+    match chars.next() {
+        None => Ok(symbols),
+        Some(_) => Err(ExternsParseError::Json),
+    }
+}
+
+/// Parses a YAML block sequence of names.
+fn parse_yaml_externs(content: &str) -> Result<Vec<String>, ExternsParseError> {
+    let mut symbols = Vec::new();
+
+    for (line_num, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line == "---" {
+            continue;
+        }
+
+        let name = line
+            .strip_prefix("- ")
+            .map(str::trim)
+            .map(|name| {
+                name.strip_prefix('"')
+                    .and_then(|name| name.strip_suffix('"'))
+                    .or_else(|| {
+                        name.strip_prefix('\'')
+                            .and_then(|name| name.strip_suffix('\''))
+                    })
+                    .unwrap_or(name)
+            })
+            .filter(|name| !name.is_empty())
+            .ok_or(ExternsParseError::Syntax(line_num + 1))?;
+
+        symbols.push(name.to_string());
+    }
+
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_externs;
+
+    #[test]
+    fn json_array() {
+        let symbols = parse_externs("[\"g_state\", \"__imp_Helper\" ,\"\\u0041\"]\n").unwrap();
+        assert_eq!(symbols, ["g_state", "__imp_Helper", "A"]);
+        assert!(parse_externs("[ ]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn json_errors() {
+        assert!(parse_externs("[\"g_state\"").is_err());
+        assert!(parse_externs("[\"g_state\",]").is_err());
+        assert!(parse_externs("[1]").is_err());
+        assert!(parse_externs("[] trailing").is_err());
+    }
+
+    #[test]
+    fn yaml_sequence() {
+        let symbols =
+            parse_externs("# loader globals\n---\n- g_state\n- \"g_config\"\n\n- 'g_key'\n")
+                .unwrap();
+        assert_eq!(symbols, ["g_state", "g_config", "g_key"]);
+    }
+
+    #[test]
+    fn yaml_errors() {
+        let err = parse_externs("- g_state\ng_config\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: expected a '- name' sequence item");
+    }
+}
@@ -5,20 +5,41 @@ use object::pe::{IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_I386};
 use typed_arena::Arena;
 
 use crate::{
-    api::ApiSymbolSource, libsearch::LibraryFind, linkobject::archive::LinkArchive,
-    pathed_item::PathedItem,
+    api::ApiSymbolSource, inputdata::InputData, libsearch::LibraryFind,
+    linkobject::archive::LinkArchive, pathed_item::PathedItem,
 };
 use error::{ApiInitError, LinkError};
 
+mod apimap;
 mod builder;
 mod configured;
+mod detect;
 pub mod error;
+mod externs;
+mod positional;
+mod query;
+mod report;
+mod reproduce;
+mod section;
+mod trailer;
 
 pub use self::configured::*;
+pub use crate::api::BeaconApiCategory;
+pub use apimap::*;
 pub use builder::*;
+pub use detect::detect_architecture;
+pub use externs::parse_externs;
+pub use query::*;
+pub use report::*;
+pub use reproduce::reproduce_path;
+pub use section::AddedSection;
+pub use trailer::OutputTrailer;
 
 pub trait LinkImpl {
     fn link(&mut self) -> Result<Vec<u8>, LinkError>;
+
+    /// Returns the report for the last link.
+    fn report(&self) -> &LinkReport;
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
@@ -52,7 +73,7 @@ impl TryFrom<object::Architecture> for LinkerTargetArch {
 pub struct ApiInitCtx<'b, 'a, L: LibraryFind> {
     pub(super) target_arch: LinkerTargetArch,
     pub(super) library_searcher: &'b L,
-    pub(super) arena: &'a Arena<PathedItem<PathBuf, Vec<u8>>>,
+    pub(super) arena: &'a Arena<PathedItem<PathBuf, InputData>>,
 }
 
 pub trait ApiInit {
@@ -79,11 +100,13 @@ impl ApiInit for CustomApiInit {
         &self,
         ctx: &ApiInitCtx<'_, 'a, L>,
     ) -> Result<Self::Output<'a>, ApiInitError> {
-        let custom_api = match std::fs::read(&self.0) {
+        let custom_api = match InputData::read(&self.0) {
             Ok(buffer) => ctx
                 .arena
                 .alloc(PathedItem::new(PathBuf::from(&self.0), buffer)),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            // WASM runtimes may not provide filesystem access. Use the
+            // library searcher for finding the API instead of failing
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound || cfg!(target_family = "wasm") => {
                 match ctx.library_searcher.find_library(&self.0) {
                     Ok(found) => ctx.arena.alloc(found),
                     Err(e) => {
@@ -0,0 +1,191 @@
+use std::path::Path;
+
+use indexmap::{IndexMap, IndexSet};
+use object::{Object, ObjectSymbol, coff::CoffFile};
+
+use super::builder::LibraryOccurrence;
+
+/// Tracks which link libraries can resolve each symbol when the libraries
+/// are searched positionally.
+///
+/// Each occurrence of a link library is a position in the search order.
+/// A symbol can only be resolved by the occurrences at or after its
+/// position. Occurrences in a library group share the position of the first
+/// library in the group.
+pub(super) struct LibraryPositions<'a> {
+    /// The link library occurrences in order.
+    occurrences: &'a [LibraryOccurrence],
+
+    /// The position for the symbols referenced by members of each
+    /// occurrence.
+    member_positions: Vec<usize>,
+
+    /// The libraries to search for each position along with the occurrence
+    /// of each library.
+    search_orders: Vec<Vec<(usize, &'a Path)>>,
+
+    /// The paths of the positional link libraries.
+    paths: IndexSet<&'a Path>,
+
+    /// The lowest position of each referenced symbol.
+    symbols: IndexMap<&'a str, usize>,
+}
+
+impl<'a> LibraryPositions<'a> {
+    pub fn new(occurrences: &'a [LibraryOccurrence]) -> LibraryPositions<'a> {
+        let member_positions =
+            Vec::from_iter(occurrences.iter().enumerate().map(|(idx, occurrence)| {
+                match occurrence.group {
+                    Some(group) => occurrences
+                        .iter()
+                        .position(|other| other.group == Some(group))
+                        .unwrap_or(idx),
+                    None => idx,
+                }
+            }));
+
+        Self {
+            occurrences,
+            member_positions,
+            search_orders: Vec::new(),
+            paths: IndexSet::new(),
+            symbols: IndexMap::new(),
+        }
+    }
+
+    /// Sets the paths of the opened link libraries from the library names.
+    ///
+    /// Occurrences of libraries which were not opened are not searched.
+    pub fn set_library_paths(&mut self, library_paths: &IndexMap<&str, &'a Path>) {
+        self.paths = IndexSet::from_iter(library_paths.values().copied());
+        self.search_orders = Vec::from_iter((0..=self.occurrences.len()).map(|position| {
+            let mut search_order: Vec<(usize, &Path)> = Vec::new();
+            for (idx, occurrence) in self.occurrences.iter().enumerate().skip(position) {
+                let Some(path) = library_paths.get(occurrence.name.as_str()) else {
+                    continue;
+                };
+
+                if !search_order.iter().any(|(_, searched)| searched == path) {
+                    search_order.push((idx, *path));
+                }
+            }
+            search_order
+        }));
+    }
+
+    /// Returns `true` if the library is searched positionally.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    /// Returns the position for the symbols referenced by the input.
+    pub fn input_position(&self, input: usize) -> usize {
+        self.occurrences
+            .iter()
+            .position(|occurrence| occurrence.inputs_before > input)
+            .map_or(self.occurrences.len(), |idx| self.member_positions[idx])
+    }
+
+    /// Returns the position for the symbols referenced by a member of the
+    /// library occurrence.
+    pub fn member_position(&self, occurrence: usize) -> usize {
+        self.member_positions[occurrence]
+    }
+
+    /// Returns the positional libraries to search for the symbol in order.
+    ///
+    /// Symbols without a recorded position are searched in every library.
+    pub fn search_order(&self, symbol: &str) -> &[(usize, &'a Path)] {
+        let position = self.symbols.get(symbol).copied().unwrap_or(0);
+        self.search_orders
+            .get(position)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Records the undefined symbols referenced by the COFF at the position.
+    ///
+    /// Symbols which were not found at their previous position are removed
+    /// from `undefined` if the new position is lower so that they are
+    /// searched for again.
+    pub fn reference_symbols(
+        &mut self,
+        coff: &CoffFile<'a>,
+        position: usize,
+        undefined: &mut IndexSet<&'a str>,
+    ) {
+        for symbol in coff.symbols() {
+            if !symbol.is_global() || !symbol.is_undefined() {
+                continue;
+            }
+
+            let Ok(symbol_name) = symbol.name() else {
+                continue;
+            };
+
+            let previous = self.symbols.entry(symbol_name).or_insert(position);
+            if position < *previous {
+                *previous = position;
+                undefined.swap_remove(symbol_name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use indexmap::IndexMap;
+
+    use super::{LibraryOccurrence, LibraryPositions};
+
+    fn occurrence(name: &str, inputs_before: usize, group: Option<usize>) -> LibraryOccurrence {
+        LibraryOccurrence {
+            name: name.into(),
+            inputs_before,
+            group,
+        }
+    }
+
+    #[test]
+    fn input_positions() {
+        // a.o -la b.o --start-group -lb c.o -lc --end-group -la
+        let occurrences = [
+            occurrence("a", 1, None),
+            occurrence("b", 2, Some(0)),
+            occurrence("c", 3, Some(0)),
+            occurrence("a", 3, None),
+        ];
+        let positions = LibraryPositions::new(&occurrences);
+
+        assert_eq!(positions.input_position(0), 0);
+        assert_eq!(positions.input_position(1), 1);
+        assert_eq!(positions.input_position(2), 1);
+        assert_eq!(positions.input_position(3), 4);
+        assert_eq!(positions.member_position(2), 1);
+        assert_eq!(positions.member_position(3), 3);
+    }
+
+    #[test]
+    fn repeated_search_order() {
+        let occurrences = [
+            occurrence("a", 1, None),
+            occurrence("b", 1, None),
+            occurrence("a", 1, None),
+            occurrence("missing", 1, None),
+        ];
+        let library_paths =
+            IndexMap::from([("a", Path::new("liba.a")), ("b", Path::new("libb.a"))]);
+
+        let mut positions = LibraryPositions::new(&occurrences);
+        positions.set_library_paths(&library_paths);
+
+        assert_eq!(
+            positions.search_orders[1],
+            [(1, Path::new("libb.a")), (2, Path::new("liba.a"))]
+        );
+        assert_eq!(positions.search_orders[3], []);
+        assert_eq!(positions.search_order("unreferenced").len(), 2);
+    }
+}
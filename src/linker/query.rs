@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+/// The kind of input searched for a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeSource {
+    /// A custom API or the Beacon API.
+    Api,
+
+    /// A lazy input defining the symbol.
+    LazyInput,
+
+    /// A link library.
+    Library,
+}
+
+impl std::fmt::Display for ProbeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Api => write!(f, "api"),
+            Self::LazyInput => write!(f, "lazy input"),
+            Self::Library => write!(f, "library"),
+        }
+    }
+}
+
+/// The result of searching an input for a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeResult {
+    /// The input defines the symbol. Includes the archive member which was
+    /// linked in.
+    Found(Option<PathBuf>),
+
+    /// The input does not define the symbol.
+    NotFound,
+
+    /// The archive member defining the symbol was skipped because it has the
+    /// same contents as a previous input.
+    Duplicate(PathBuf),
+
+    /// The input could not be searched or the member could not be added.
+    Error(String),
+}
+
+/// An input searched while resolving a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolProbe {
+    /// The kind of input.
+    pub source: ProbeSource,
+
+    /// The path of the input.
+    pub path: PathBuf,
+
+    /// The result of the search.
+    pub result: ProbeResult,
+}
+
+/// The final state of a queried symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolStatus {
+    /// The symbol was not referenced or defined by any of the inputs.
+    Unreferenced,
+
+    /// The symbol was defined by the inputs without searching for it.
+    Defined,
+
+    /// The symbol was resolved from an API, lazy input or link library.
+    Resolved,
+
+    /// The symbol could not be resolved.
+    Undefined,
+
+    /// The symbol is left undefined for the loader to provide.
+    LoaderExtern,
+}
+
+impl std::fmt::Display for SymbolStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unreferenced => write!(f, "unreferenced"),
+            Self::Defined => write!(f, "defined"),
+            Self::Resolved => write!(f, "resolved"),
+            Self::Undefined => write!(f, "undefined"),
+            Self::LoaderExtern => write!(f, "loader extern"),
+        }
+    }
+}
+
+/// The steps taken to resolve a symbol requested with
+/// [`LinkerBuilder::why_undefined`](super::LinkerBuilder::why_undefined).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolQuery {
+    /// The name of the symbol.
+    pub symbol: String,
+
+    /// The name the symbol was mapped to for the API lookups.
+    pub api_symbol: Option<String>,
+
+    /// The final state of the symbol.
+    pub status: SymbolStatus,
+
+    /// The inputs searched for the symbol in search order.
+    pub probes: Vec<SymbolProbe>,
+
+    /// Symbols in the link libraries which only differ in case or i386 name
+    /// decorations along with the library path.
+    pub candidates: Vec<(PathBuf, String)>,
+
+    /// The COFFs defining the symbol or the DLLs it is imported from.
+    pub definitions: Vec<String>,
+
+    /// The COFFs referencing the symbol.
+    pub references: Vec<String>,
+}
+
+impl SymbolQuery {
+    pub(super) fn new(symbol: impl Into<String>) -> SymbolQuery {
+        SymbolQuery {
+            symbol: symbol.into(),
+            api_symbol: None,
+            status: SymbolStatus::Unreferenced,
+            probes: Vec::new(),
+            candidates: Vec::new(),
+            definitions: Vec::new(),
+            references: Vec::new(),
+        }
+    }
+
+    /// Records a search of the input at `path` for the symbol.
+    pub(super) fn probe(
+        &mut self,
+        source: ProbeSource,
+        path: impl Into<PathBuf>,
+        result: ProbeResult,
+    ) {
+        self.probes.push(SymbolProbe {
+            source,
+            path: path.into(),
+            result,
+        });
+    }
+}
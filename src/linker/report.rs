@@ -0,0 +1,338 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+use log::{info, warn};
+
+use crate::graph::{
+    CheckedRelocation, CrossReference, OutputString, PackedSection, RelocationCount, SymbolSize,
+    node::CoffNode,
+};
+
+use super::{BeaconApiCategory, SymbolQuery, error::LinkerSetupPathError};
+
+/// A phase of the link measured in the [`LinkReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkPhase {
+    /// Parsing the input files.
+    Parse,
+
+    /// Finding and opening the link libraries and custom APIs.
+    LibrarySearch,
+
+    /// Building the link graph and resolving symbols.
+    SymbolResolution,
+
+    /// Finishing the link graph and applying the output options.
+    GraphFinish,
+
+    /// Merging and laying out the output sections.
+    Layout,
+
+    /// Writing the output COFF.
+    Write,
+}
+
+impl std::fmt::Display for LinkPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse => write!(f, "parse inputs"),
+            Self::LibrarySearch => write!(f, "library search"),
+            Self::SymbolResolution => write!(f, "symbol resolution"),
+            Self::GraphFinish => write!(f, "graph finish"),
+            Self::Layout => write!(f, "layout"),
+            Self::Write => write!(f, "write output"),
+        }
+    }
+}
+
+/// An opened link library along with the input which requested it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryDependency {
+    /// The path of the link library.
+    pub library: PathBuf,
+
+    /// The file path and archive member path of the COFF with the `.drectve`
+    /// `/DEFAULTLIB` directive which requested the library.
+    ///
+    /// [`None`] for libraries from the command line.
+    pub requested_by: Option<(PathBuf, Option<PathBuf>)>,
+}
+
+/// Information collected during a link.
+#[derive(Debug, Default, Clone)]
+pub struct LinkReport {
+    /// The resolved API symbols and the path of the API which provided them.
+    pub(super) api_symbols: IndexMap<String, PathBuf>,
+
+    /// The names of the resolved Beacon API functions in each category.
+    pub(super) api_categories: BTreeMap<BeaconApiCategory, BTreeSet<String>>,
+
+    /// The archive members skipped because they failed to parse.
+    pub(super) skipped_members: Vec<(PathBuf, Option<PathBuf>)>,
+
+    /// The inputs skipped because they had the same contents as a previous
+    /// input.
+    pub(super) duplicate_inputs: Vec<(PathBuf, Option<PathBuf>)>,
+
+    /// The opened link libraries and the number of members extracted from
+    /// each library.
+    pub(super) library_members: IndexMap<PathBuf, usize>,
+
+    /// The opened link libraries in the order they were opened along with
+    /// the inputs which requested them.
+    pub(super) library_dependencies: Vec<LibraryDependency>,
+
+    /// The time spent in each completed phase of the link.
+    pub(super) phase_times: Vec<(LinkPhase, Duration)>,
+
+    /// The time spent finding and parsing each link library.
+    pub(super) library_times: IndexMap<PathBuf, Duration>,
+
+    /// The symbol cross-reference table if it was requested.
+    pub(super) cross_references: Vec<CrossReference>,
+
+    /// The number of relocations of each type in the output sections.
+    pub(super) relocation_counts: Vec<RelocationCount>,
+
+    /// The cross-section relocations in the output if they were checked.
+    pub(super) checked_relocations: Vec<CheckedRelocation>,
+
+    /// The printable strings in the output data sections if they were
+    /// requested.
+    pub(super) strings: Vec<OutputString>,
+
+    /// The estimated sizes of the output symbols if they were requested.
+    pub(super) symbol_sizes: Vec<SymbolSize>,
+
+    /// The output sections with the input sections ordered by alignment.
+    pub(super) packed_sections: Vec<PackedSection>,
+
+    /// The resolution steps for the queried symbols.
+    pub(super) symbol_queries: Vec<SymbolQuery>,
+}
+
+impl LinkReport {
+    /// Returns an iterator over the resolved API symbols along with the path
+    /// of the API which provided each symbol.
+    pub fn api_symbols(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.api_symbols
+            .iter()
+            .map(|(symbol, path)| (symbol.as_str(), path.as_path()))
+    }
+
+    /// Returns an iterator over the categories of the resolved Beacon API
+    /// functions along with the function names in each category.
+    ///
+    /// API symbols which are not Beacon API functions are not included.
+    pub fn api_categories(&self) -> impl Iterator<Item = (BeaconApiCategory, &BTreeSet<String>)> {
+        self.api_categories
+            .iter()
+            .map(|(category, names)| (*category, names))
+    }
+
+    /// Returns an iterator over the library paths and member paths of the
+    /// archive members skipped in tolerant mode.
+    ///
+    /// The member path is [`None`] if the member header could not be parsed.
+    pub fn skipped_members(&self) -> impl Iterator<Item = (&Path, Option<&Path>)> {
+        self.skipped_members
+            .iter()
+            .map(|(library, member)| (library.as_path(), member.as_deref()))
+    }
+
+    /// Returns an iterator over the file paths and member paths of the inputs
+    /// skipped because they had the same contents as a previous input.
+    ///
+    /// The member path is [`None`] for input files which are not archive
+    /// members.
+    pub fn duplicate_inputs(&self) -> impl Iterator<Item = (&Path, Option<&Path>)> {
+        self.duplicate_inputs
+            .iter()
+            .map(|(path, member)| (path.as_path(), member.as_deref()))
+    }
+
+    /// Returns an iterator over the opened link libraries along with the
+    /// number of members extracted from each library.
+    pub fn library_members(&self) -> impl Iterator<Item = (&Path, usize)> {
+        self.library_members
+            .iter()
+            .map(|(library, members)| (library.as_path(), *members))
+    }
+
+    /// Returns an iterator over the opened link libraries which did not
+    /// contribute any members to the link.
+    pub fn unused_libraries(&self) -> impl Iterator<Item = &Path> {
+        self.library_members()
+            .filter(|(_, members)| *members == 0)
+            .map(|(library, _)| library)
+    }
+
+    /// Returns the opened link libraries in the order they were opened along
+    /// with the inputs which requested them.
+    ///
+    /// Libraries requested by a member of another link library come after
+    /// that library.
+    pub fn library_dependencies(&self) -> &[LibraryDependency] {
+        &self.library_dependencies
+    }
+
+    /// Returns an iterator over the completed link phases along with the
+    /// time spent in each phase.
+    pub fn phase_times(&self) -> impl Iterator<Item = (LinkPhase, Duration)> {
+        self.phase_times.iter().copied()
+    }
+
+    /// Returns an iterator over the opened link libraries along with the time
+    /// spent finding and parsing each library.
+    pub fn library_times(&self) -> impl Iterator<Item = (&Path, Duration)> {
+        self.library_times
+            .iter()
+            .map(|(library, time)| (library.as_path(), *time))
+    }
+
+    /// Returns the symbol cross-reference table sorted by symbol name.
+    ///
+    /// The table is empty unless it was requested with
+    /// [`LinkerBuilder::cross_reference`](super::LinkerBuilder::cross_reference).
+    pub fn cross_references(&self) -> &[CrossReference] {
+        &self.cross_references
+    }
+
+    /// Returns the number of relocations of each type written to the output
+    /// sections ordered by output section and relocation type.
+    ///
+    /// Relocations which were applied by the linker and do not appear in the
+    /// output are not counted.
+    pub fn relocation_counts(&self) -> &[RelocationCount] {
+        &self.relocation_counts
+    }
+
+    /// Returns the cross-section relocations written to the output along
+    /// with the suspicious patterns found in them.
+    ///
+    /// The relocations are empty unless they were checked with
+    /// [`LinkerBuilder::check_relocations`](super::LinkerBuilder::check_relocations).
+    pub fn checked_relocations(&self) -> &[CheckedRelocation] {
+        &self.checked_relocations
+    }
+
+    /// Returns the total number of relocations of each type written to the
+    /// output ordered by relocation type.
+    pub fn relocation_totals(&self) -> impl Iterator<Item = (u16, &'static str, usize)> {
+        let mut totals: BTreeMap<u16, (&'static str, usize)> = BTreeMap::new();
+        for count in &self.relocation_counts {
+            totals.entry(count.typ).or_insert((count.type_name, 0)).1 += count.count;
+        }
+
+        totals
+            .into_iter()
+            .map(|(typ, (type_name, count))| (typ, type_name, count))
+    }
+
+    /// Returns the printable strings in the initialized data sections of the
+    /// output ordered by output section and offset.
+    ///
+    /// The strings are empty unless they were requested with
+    /// [`LinkerBuilder::collect_strings`](super::LinkerBuilder::collect_strings).
+    pub fn strings(&self) -> &[OutputString] {
+        &self.strings
+    }
+
+    /// Returns the estimated sizes of the symbols defined in the output
+    /// ordered from the largest.
+    ///
+    /// The sizes are empty unless they were requested with
+    /// [`LinkerBuilder::collect_symbol_sizes`](super::LinkerBuilder::collect_symbol_sizes).
+    pub fn symbol_sizes(&self) -> &[SymbolSize] {
+        &self.symbol_sizes
+    }
+
+    /// Returns the output sections with the input sections ordered by
+    /// alignment along with the padding before and after.
+    ///
+    /// The sections are empty unless packing was enabled with
+    /// [`LinkerBuilder::pack_sections`](super::LinkerBuilder::pack_sections).
+    pub fn packed_sections(&self) -> &[PackedSection] {
+        &self.packed_sections
+    }
+
+    /// Returns the total number of padding bytes saved by packing the
+    /// output sections.
+    pub fn packing_savings(&self) -> u32 {
+        self.packed_sections.iter().map(PackedSection::saved).sum()
+    }
+
+    /// Returns the resolution steps for the symbols requested with
+    /// [`LinkerBuilder::why_undefined`](super::LinkerBuilder::why_undefined)
+    /// in request order.
+    pub fn symbol_queries(&self) -> &[SymbolQuery] {
+        &self.symbol_queries
+    }
+
+    /// Returns the total time spent in the completed link phases.
+    pub fn total_time(&self) -> Duration {
+        self.phase_times.iter().map(|(_, time)| *time).sum()
+    }
+
+    /// Records the skipped duplicate of the original input.
+    pub(super) fn skip_duplicate(&mut self, duplicate: &CoffNode, original: &CoffNode) {
+        info!(
+            coff:% = duplicate.file_path().display(),
+            member = duplicate.member_path().map(Path::to_string_lossy);
+            "{duplicate}: skipping duplicate of {original}"
+        );
+
+        self.duplicate_inputs.push((
+            duplicate.file_path().to_path_buf(),
+            duplicate.member_path().map(Path::to_path_buf),
+        ));
+    }
+
+    /// Records the archive member skipped in tolerant mode.
+    ///
+    /// Members which were already skipped by a previous symbol lookup are
+    /// not recorded or reported again.
+    pub(super) fn skip_member(&mut self, error: LinkerSetupPathError) {
+        if self
+            .skipped_members
+            .iter()
+            .any(|(library, member)| *library == error.path && *member == error.member)
+        {
+            return;
+        }
+
+        warn!(
+            library:% = error.path.display(),
+            member = error.member.as_deref().map(Path::to_string_lossy);
+            "{error}. Skipping archive member"
+        );
+
+        self.skipped_members.push((error.path, error.member));
+    }
+
+    /// Records the opened link library along with the COFF which requested
+    /// it.
+    pub(super) fn open_library(
+        &mut self,
+        library: &Path,
+        requested_by: Option<(&Path, Option<&Path>)>,
+    ) {
+        self.library_dependencies.push(LibraryDependency {
+            library: library.to_path_buf(),
+            requested_by: requested_by
+                .map(|(file, member)| (file.to_path_buf(), member.map(Path::to_path_buf))),
+        });
+    }
+
+    /// Records the time since `start` for the phase and resets `start` for
+    /// the next phase.
+    pub(super) fn finish_phase(&mut self, phase: LinkPhase, start: &mut Instant) {
+        let now = Instant::now();
+        self.phase_times.push((phase, now - *start));
+        *start = now;
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    cell::RefCell,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    libsearch::{FoundLibrary, LibraryFind, LibsearchError},
+    pathed_item::PathedItem,
+};
+
+/// Size of the tar header and data blocks.
+const BLOCK_SIZE: usize = 512;
+
+/// Maximum length of a name in the ustar header excluding the terminator.
+const MAX_NAME_LEN: usize = 99;
+
+/// The paths and contents of the link libraries found while recording.
+pub(super) type RecordedLibraries = RefCell<Vec<PathedItem<PathBuf, Vec<u8>>>>;
+
+/// Link library searcher which records the contents of the found libraries.
+pub(super) struct RecordingSearcher<'a, L: LibraryFind> {
+    /// The wrapped searcher.
+    searcher: &'a L,
+
+    /// The paths and contents of the found libraries if recording.
+    recorded: Option<&'a RecordedLibraries>,
+}
+
+impl<'a, L: LibraryFind> RecordingSearcher<'a, L> {
+    pub fn new(
+        searcher: &'a L,
+        recorded: Option<&'a RecordedLibraries>,
+    ) -> RecordingSearcher<'a, L> {
+        Self { searcher, recorded }
+    }
+}
+
+impl<L: LibraryFind> LibraryFind for RecordingSearcher<'_, L> {
+    fn find_library(&self, name: impl AsRef<str>) -> Result<FoundLibrary, LibsearchError> {
+        let found = self.searcher.find_library(name)?;
+
+        if let Some(recorded) = self.recorded {
+            recorded.borrow_mut().push(PathedItem::new(
+                found.path().clone(),
+                found.as_slice().to_vec(),
+            ));
+        }
+
+        Ok(found)
+    }
+}
+
+/// Returns the path of the file relative to the base directory of the
+/// reproduce archive.
+///
+/// The path is made absolute so that files with the same relative path from
+/// different working directories do not collide. Windows drive letters are
+/// kept as the first component.
+pub fn reproduce_path(path: impl AsRef<Path>) -> String {
+    let path = path.as_ref();
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => components.push(
+                prefix
+                    .as_os_str()
+                    .to_string_lossy()
+                    .replace([':', '\\', '?'], ""),
+            ),
+            Component::Normal(name) => components.push(name.to_string_lossy().into_owned()),
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::RootDir | Component::CurDir => (),
+        }
+    }
+
+    components.join("/")
+}
+
+/// Writer for a ustar archive.
+///
+/// Every file is placed under the base directory. Names which do not fit in
+/// the ustar header are stored in a pax extended header.
+pub(super) struct TarWriter<W: Write> {
+    /// The output for the archive.
+    inner: W,
+
+    /// Name of the directory containing the files.
+    base: String,
+}
+
+impl<W: Write> TarWriter<W> {
+    pub fn new(inner: W, base: impl Into<String>) -> TarWriter<W> {
+        Self {
+            inner,
+            base: base.into(),
+        }
+    }
+
+    /// Appends the file to the archive.
+    pub fn append(&mut self, name: &str, data: &[u8]) -> std::io::Result<()> {
+        let name = format!("{}/{name}", self.base);
+
+        if name.len() > MAX_NAME_LEN {
+            let record = pax_record("path", &name);
+            self.write_header(b"././@PaxHeader", record.len(), b'x')?;
+            self.write_data(record.as_bytes())?;
+        }
+
+        let truncated = &name.as_bytes()[..name.len().min(MAX_NAME_LEN)];
+        self.write_header(truncated, data.len(), b'0')?;
+        self.write_data(data)
+    }
+
+    /// Writes the end of archive marker and returns the output.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.inner.write_all(&[0u8; BLOCK_SIZE * 2])?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_header(&mut self, name: &[u8], size: usize, typeflag: u8) -> std::io::Result<()> {
+        let mut header = [0u8; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name);
+        write_octal(&mut header[100..108], 0o644);
+        write_octal(&mut header[108..116], 0);
+        write_octal(&mut header[116..124], 0);
+        write_octal(&mut header[124..136], size as u64);
+        write_octal(&mut header[136..148], 0);
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        // The checksum is calculated with the checksum field set to spaces
+        header[148..156].fill(b' ');
+        let checksum = header.iter().map(|b| u64::from(*b)).sum();
+        write_octal(&mut header[148..155], checksum);
+
+        self.inner.write_all(&header)
+    }
+
+    fn write_data(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.inner.write_all(data)?;
+
+        let padding = data.len().next_multiple_of(BLOCK_SIZE) - data.len();
+        self.inner.write_all(&[0u8; BLOCK_SIZE][..padding])
+    }
+}
+
+/// Writes the zero-padded and NUL-terminated octal value into the field.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let formatted = format!("{value:0digits$o}");
+    let formatted = &formatted.as_bytes()[formatted.len().saturating_sub(digits)..];
+    field[..digits].copy_from_slice(formatted);
+    field[digits] = 0;
+}
+
+/// Returns the pax extended header record for the key and value.
+///
+/// The record starts with its own length in decimal including the length
+/// field.
+fn pax_record(key: &str, value: &str) -> String {
+    let body_len = key.len() + value.len() + 3;
+    let mut len = body_len + 1;
+    while len != body_len + len.to_string().len() {
+        len = body_len + len.to_string().len();
+    }
+
+    format!("{len} {key}={value}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BLOCK_SIZE, TarWriter, pax_record};
+
+    /// Returns the names and contents of the files in the archive.
+    fn read_tar(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut files = Vec::new();
+        let mut long_name = None;
+
+        let mut header_offset = 0;
+        loop {
+            let header = &data[header_offset..header_offset + BLOCK_SIZE];
+            if header.iter().all(|b| *b == 0) {
+                break;
+            }
+
+            let checksum = header
+                .iter()
+                .enumerate()
+                .map(|(idx, b)| {
+                    if (148..156).contains(&idx) {
+                        32
+                    } else {
+                        *b as u64
+                    }
+                })
+                .sum::<u64>();
+            let field = std::str::from_utf8(&header[148..154]).unwrap();
+            assert_eq!(u64::from_str_radix(field, 8).unwrap(), checksum);
+
+            let size = std::str::from_utf8(&header[124..135]).unwrap();
+            let size = usize::from_str_radix(size, 8).unwrap();
+            let name_len = header[..100].iter().position(|b| *b == 0).unwrap_or(100);
+            let name = String::from_utf8(header[..name_len].to_vec()).unwrap();
+            let contents = data[header_offset + BLOCK_SIZE..][..size].to_vec();
+
+            if header[156] == b'x' {
+                let record = String::from_utf8(contents).unwrap();
+                let (_, path) = record.trim_end().split_once(" path=").unwrap();
+                long_name = Some(path.to_string());
+            } else {
+                files.push((long_name.take().unwrap_or(name), contents));
+            }
+
+            header_offset += BLOCK_SIZE + size.next_multiple_of(BLOCK_SIZE);
+        }
+
+        files
+    }
+
+    #[test]
+    fn tar_files() {
+        let mut writer = TarWriter::new(Vec::new(), "repro");
+        writer.append("response.txt", b"go.o\n").unwrap();
+        writer.append(&"a/".repeat(60), &[0xcc; 600]).unwrap();
+        let archive = writer.finish().unwrap();
+
+        assert_eq!(archive.len() % BLOCK_SIZE, 0);
+        assert_eq!(
+            read_tar(&archive),
+            [
+                ("repro/response.txt".to_string(), b"go.o\n".to_vec()),
+                (format!("repro/{}", "a/".repeat(60)), vec![0xcc; 600]),
+            ]
+        );
+    }
+
+    #[test]
+    fn pax_record_length() {
+        assert_eq!(pax_record("path", "a"), "9 path=a\n");
+        assert_eq!(pax_record("path", "abcdefgh"), "17 path=abcdefgh\n");
+
+        // Adding the length pushes the record to three digits
+        let record = pax_record("path", &"a".repeat(91));
+        assert_eq!(record, format!("101 path={}\n", "a".repeat(91)));
+        assert_eq!(record.len(), 101);
+    }
+}
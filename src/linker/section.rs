@@ -0,0 +1,134 @@
+use crate::graph::node::SectionNodeCharacteristics;
+
+/// Bit offset of the alignment in the section characteristics.
+const ALIGN_SHIFT: u32 = 20;
+
+/// Mask for the alignment in the section characteristics.
+const ALIGN_MASK: u32 = 0xf << ALIGN_SHIFT;
+
+/// A file added to the output as a section.
+#[derive(Debug, Clone)]
+pub struct AddedSection {
+    /// The name of the section.
+    pub(super) name: String,
+
+    /// The section contents.
+    pub(super) data: Vec<u8>,
+
+    /// The section characteristics including the alignment.
+    pub(super) characteristics: SectionNodeCharacteristics,
+
+    /// The symbol defined at the start of the section.
+    pub(super) symbol: Option<String>,
+}
+
+impl AddedSection {
+    /// Creates a new section with the contents of `data`.
+    ///
+    /// Sections named `.text` or `.text$<suffix>` are executable code,
+    /// `.data` sections are writable data and other sections are read-only
+    /// data. The section is 4 byte aligned.
+    pub fn new(name: impl Into<String>, data: impl Into<Vec<u8>>) -> AddedSection {
+        let name = name.into();
+        let group = name
+            .split_once('$')
+            .map(|(group, _)| group)
+            .unwrap_or(&name);
+
+        let characteristics = match group {
+            ".text" => {
+                SectionNodeCharacteristics::CntCode
+                    | SectionNodeCharacteristics::MemExecute
+                    | SectionNodeCharacteristics::MemRead
+            }
+            ".data" => {
+                SectionNodeCharacteristics::CntInitializedData
+                    | SectionNodeCharacteristics::MemRead
+                    | SectionNodeCharacteristics::MemWrite
+            }
+            _ => {
+                SectionNodeCharacteristics::CntInitializedData | SectionNodeCharacteristics::MemRead
+            }
+        };
+
+        AddedSection {
+            name,
+            data: data.into(),
+            characteristics: characteristics | SectionNodeCharacteristics::Align4Bytes,
+            symbol: None,
+        }
+    }
+
+    /// Set the section characteristics.
+    ///
+    /// The alignment is only replaced if `characteristics` includes one.
+    pub fn characteristics(mut self, characteristics: SectionNodeCharacteristics) -> Self {
+        let alignment = if characteristics.bits() & ALIGN_MASK != 0 {
+            characteristics.bits() & ALIGN_MASK
+        } else {
+            self.characteristics.bits() & ALIGN_MASK
+        };
+
+        self.characteristics = SectionNodeCharacteristics::from_bits_retain(
+            characteristics.zero_align().bits() | alignment,
+        );
+        self
+    }
+
+    /// Set the section alignment.
+    ///
+    /// The alignment must be a power of two no greater than 8192.
+    pub fn alignment(mut self, alignment: u32) -> Self {
+        debug_assert!(alignment.is_power_of_two() && alignment <= 8192);
+        self.characteristics = SectionNodeCharacteristics::from_bits_retain(
+            self.characteristics.zero_align().bits() | ((alignment.ilog2() + 1) << ALIGN_SHIFT),
+        );
+        self
+    }
+
+    /// Define an external symbol at the start of the section.
+    pub fn symbol(mut self, name: impl Into<String>) -> Self {
+        self.symbol = Some(name.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::node::SectionNodeCharacteristics;
+
+    use super::AddedSection;
+
+    #[test]
+    fn default_characteristics() {
+        let section = AddedSection::new(".text$payload", [0xc3]);
+        assert!(
+            section
+                .characteristics
+                .contains(SectionNodeCharacteristics::MemExecute)
+        );
+        assert_eq!(section.characteristics.alignment(), Some(4));
+
+        let section = AddedSection::new(".rdata$cfg", []);
+        assert!(!section.characteristics.intersects(
+            SectionNodeCharacteristics::MemWrite | SectionNodeCharacteristics::MemExecute
+        ));
+    }
+
+    #[test]
+    fn alignment_kept() {
+        let section = AddedSection::new(".rdata$cfg", [])
+            .alignment(16)
+            .characteristics(
+                SectionNodeCharacteristics::CntInitializedData
+                    | SectionNodeCharacteristics::MemRead
+                    | SectionNodeCharacteristics::MemWrite,
+            );
+        assert_eq!(section.characteristics.alignment(), Some(16));
+        assert!(
+            section
+                .characteristics
+                .contains(SectionNodeCharacteristics::MemWrite)
+        );
+    }
+}
@@ -0,0 +1,79 @@
+/// Data appended after the output COFF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputTrailer {
+    /// Raw bytes such as a manifest.
+    Data(Vec<u8>),
+
+    /// The little-endian JamCRC of the preceding output including any
+    /// padding.
+    Jamcrc,
+}
+
+impl OutputTrailer {
+    /// Returns the size of the trailer in bytes.
+    fn len(&self) -> usize {
+        match self {
+            Self::Data(data) => data.len(),
+            Self::Jamcrc => 4,
+        }
+    }
+}
+
+/// Pads the output COFF and appends the trailer.
+///
+/// The padding is inserted between the COFF and the trailer so that the
+/// trailer ends the file and the total size is a multiple of `alignment`.
+pub(super) fn finish_output(
+    linked: &mut Vec<u8>,
+    alignment: Option<u32>,
+    trailer: Option<&OutputTrailer>,
+) {
+    let trailer_len = trailer.map(OutputTrailer::len).unwrap_or_default();
+
+    if let Some(alignment) = alignment.filter(|alignment| *alignment > 1) {
+        let total_len = linked.len() + trailer_len;
+        linked.resize(
+            total_len.next_multiple_of(alignment as usize) - trailer_len,
+            0,
+        );
+    }
+
+    match trailer {
+        Some(OutputTrailer::Data(data)) => linked.extend_from_slice(data),
+        Some(OutputTrailer::Jamcrc) => {
+            let mut hasher = jamcrc::Hasher::new();
+            hasher.update(linked);
+            linked.extend_from_slice(&hasher.finalize().to_le_bytes());
+        }
+        None => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutputTrailer, finish_output};
+
+    #[test]
+    fn padded_trailer() {
+        let mut linked = vec![1u8; 10];
+        finish_output(
+            &mut linked,
+            Some(16),
+            Some(&OutputTrailer::Data(b"abc".to_vec())),
+        );
+
+        assert_eq!(linked.len(), 16);
+        assert_eq!(&linked[10..13], &[0, 0, 0]);
+        assert_eq!(&linked[13..], b"abc");
+    }
+
+    #[test]
+    fn jamcrc_trailer() {
+        let mut linked = vec![1u8; 10];
+        finish_output(&mut linked, None, Some(&OutputTrailer::Jamcrc));
+
+        let mut hasher = jamcrc::Hasher::new();
+        hasher.update(&[1u8; 10]);
+        assert_eq!(linked[10..], hasher.finalize().to_le_bytes());
+    }
+}
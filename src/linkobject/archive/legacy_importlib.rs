@@ -2,7 +2,7 @@ use std::ffi::CStr;
 
 use object::{
     Object, ObjectSection, ObjectSymbol,
-    coff::{CoffFile, ImageSymbol},
+    coff::{CoffFile, CoffSymbol, ImageSymbol},
     pe::{IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE, IMAGE_SYM_CLASS_EXTERNAL},
 };
 
@@ -37,7 +37,10 @@ impl<'a> LegacyImportSymbolMember<'a> {
     pub fn parse(
         coff: &CoffFile<'a>,
     ) -> Result<LegacyImportSymbolMember<'a>, LegacyImportSymbolMemberParseError> {
-        if coff.coff_section_table().len() != 7 {
+        // Symbol members have 7 sections. Data imports without an import
+        // thunk and members built with dlltool's '--no-idata4' or
+        // '--no-idata5' options will have fewer sections.
+        if !(5..=7).contains(&coff.coff_section_table().len()) {
             return Err(LegacyImportSymbolMemberParseError::Invalid);
         }
 
@@ -47,60 +50,87 @@ impl<'a> LegacyImportSymbolMember<'a> {
             return Err(LegacyImportSymbolMemberParseError::Invalid);
         }
 
-        let public_symbol = coff
-            .symbols()
-            .filter(|symbol| symbol.coff_symbol().storage_class() == IMAGE_SYM_CLASS_EXTERNAL)
-            .find_map(|symbol| {
-                symbol
-                    .section_index()
-                    .and_then(|section_idx| coff.section_by_index(section_idx).ok())
-                    .and_then(|section| section.name().ok())
-                    .and_then(|section_name| {
-                        (!section_name.starts_with(".idata$")).then_some(symbol)
-                    })
-            })
-            .ok_or(LegacyImportSymbolMemberParseError::MissingPublicSymbol)?;
-
-        let public_section = coff.section_by_index(public_symbol.section_index().unwrap())?;
+        let section_name = |symbol: &CoffSymbol<'a, '_>| {
+            symbol
+                .section_index()
+                .and_then(|section_idx| coff.section_by_index(section_idx).ok())
+                .and_then(|section| section.name().ok())
+        };
 
-        let characteristics = public_section
-            .coff_section()
-            .characteristics
-            .get(object::LittleEndian);
+        let external_symbols = || {
+            coff.symbols()
+                .filter(|symbol| symbol.coff_symbol().storage_class() == IMAGE_SYM_CLASS_EXTERNAL)
+        };
 
-        let typ = if characteristics & IMAGE_SCN_CNT_CODE != 0 {
-            ImportType::Code
-        } else if characteristics & IMAGE_SCN_MEM_READ != 0
-            && characteristics & IMAGE_SCN_MEM_WRITE == 0
+        // The public symbol is defined in the import thunk section. Alias
+        // members define additional public symbols in the same section with
+        // the first one being the canonical name.
+        let (public_symbol, typ) = match external_symbols()
+            .find(|symbol| section_name(symbol).is_some_and(|name| !name.starts_with(".idata$")))
         {
-            ImportType::Const
-        } else {
-            ImportType::Data
+            Some(public_symbol) => {
+                let public_section =
+                    coff.section_by_index(public_symbol.section_index().unwrap())?;
+
+                let characteristics = public_section
+                    .coff_section()
+                    .characteristics
+                    .get(object::LittleEndian);
+
+                let typ = if characteristics & IMAGE_SCN_CNT_CODE != 0 {
+                    ImportType::Code
+                } else if characteristics & IMAGE_SCN_MEM_READ != 0
+                    && characteristics & IMAGE_SCN_MEM_WRITE == 0
+                {
+                    ImportType::Const
+                } else {
+                    ImportType::Data
+                };
+
+                (public_symbol.name()?, typ)
+            }
+            None => {
+                // Data imports only define the '__imp_' symbol in the IAT.
+                let imp_name = external_symbols()
+                    .find_map(|symbol| {
+                        section_name(&symbol)
+                            .is_some_and(|name| name == ".idata$5")
+                            .then(|| symbol.name().ok())
+                            .flatten()
+                            .and_then(|name| name.strip_prefix("__imp_"))
+                    })
+                    .ok_or(LegacyImportSymbolMemberParseError::MissingPublicSymbol)?;
+
+                (imp_name, ImportType::Data)
+            }
         };
 
-        let head_symbol = coff
-            .symbols()
-            .filter(|symbol| symbol.coff_symbol().storage_class() == IMAGE_SYM_CLASS_EXTERNAL)
+        let head_symbol = external_symbols()
             .find_map(|symbol| symbol.is_undefined().then(|| symbol.name().ok()).flatten())
             .ok_or(LegacyImportSymbolMemberParseError::MissingHeadSymbol)?;
 
+        // The IAT has the same contents as the ILT if the member does not
+        // have an ILT section.
         let ilt_section = coff
             .section_by_name(".idata$4")
+            .or_else(|| coff.section_by_name(".idata$5"))
             .ok_or(LegacyImportSymbolMemberParseError::IltMissing)?;
 
         let ilt_data = ilt_section.data()?;
 
         let ilt = if coff.is_64() {
             u64::from_le_bytes(
-                ilt_data[..8]
-                    .try_into()
-                    .map_err(|_| LegacyImportSymbolMemberParseError::IltMalformed)?,
+                ilt_data
+                    .get(..8)
+                    .and_then(|ilt| ilt.try_into().ok())
+                    .ok_or(LegacyImportSymbolMemberParseError::IltMalformed)?,
             )
         } else {
             u32::from_le_bytes(
-                ilt_data[..4]
-                    .try_into()
-                    .map_err(|_| LegacyImportSymbolMemberParseError::IltMalformed)?,
+                ilt_data
+                    .get(..4)
+                    .and_then(|ilt| ilt.try_into().ok())
+                    .ok_or(LegacyImportSymbolMemberParseError::IltMalformed)?,
             )
             .into()
         };
@@ -128,7 +158,7 @@ impl<'a> LegacyImportSymbolMember<'a> {
         };
 
         Ok(LegacyImportSymbolMember {
-            public_symbol: public_symbol.name()?,
+            public_symbol,
             typ,
             import_name,
             head_symbol,
@@ -146,7 +176,9 @@ impl<'a> LegacyImportHeadMember<'a> {
     pub fn parse(
         coff: &CoffFile<'a>,
     ) -> Result<LegacyImportHeadMember<'a>, LegacyImportHeadMemberParseError> {
-        if coff.coff_section_table().len() != 6 {
+        // Head members have 6 sections or fewer if the '.idata$4' or
+        // '.idata$5' sections were omitted.
+        if !(4..=6).contains(&coff.coff_section_table().len()) {
             return Err(LegacyImportHeadMemberParseError::Invalid);
         }
 
@@ -182,13 +214,15 @@ impl<'a> LegacyImportTailMember<'a> {
     pub fn parse(
         coff: &CoffFile<'a>,
     ) -> Result<LegacyImportTailMember<'a>, LegacyImportTailMemberParseError> {
-        if coff.coff_section_table().len() != 6 {
+        // Tail members have 6 sections or fewer if the '.idata$4' or
+        // '.idata$5' sections were omitted.
+        if !(4..=6).contains(&coff.coff_section_table().len()) {
             return Err(LegacyImportTailMemberParseError::Invalid);
         }
 
-        // This is the first '.idata' section after the .text, .data and .bss
-        // sections. Use it as a smoke test to check if the COFF is valid.
-        if coff.section_by_name(".idata$4").is_none() {
+        // The '.idata$7' section holds the DLL name. Use it as a smoke test
+        // to check if the COFF is valid.
+        if coff.section_by_name(".idata$7").is_none() {
             return Err(LegacyImportTailMemberParseError::Invalid);
         }
 
@@ -1,12 +1,13 @@
 use std::{
-    cell::RefCell,
+    cell::{OnceCell, RefCell},
     collections::{BTreeMap, HashMap},
     ops::Deref,
     path::{Path, PathBuf},
 };
 
+use log::debug;
 use object::{
-    Object,
+    Architecture, LittleEndian, Object,
     coff::{CoffFile, ImportFile},
     pe::IMAGE_FILE_MACHINE_UNKNOWN,
     read::archive::{ArchiveFile, ArchiveMember, ArchiveOffset, ArchiveSymbolIterator},
@@ -17,27 +18,30 @@ use crate::{
     pathed_item::PathedItem,
 };
 
-use super::import::ImportMember;
+use super::{dll::undecorate_symbol, import::ImportMember};
 
 pub use error::*;
 
 use legacy_importlib::{LegacyImportHeadMember, LegacyImportSymbolMember, LegacyImportTailMember};
 
 pub mod error;
-mod legacy_importlib;
+pub(crate) mod legacy_importlib;
 
 pub struct ExtractedMember<'a> {
     path: &'a Path,
+    data: &'a [u8],
     contents: ExtractedMemberContents<'a>,
 }
 
 impl<'a> ExtractedMember<'a> {
     pub fn new(
         path: &'a Path,
+        data: &'a [u8],
         contents: impl Into<ExtractedMemberContents<'a>>,
     ) -> ExtractedMember<'a> {
         Self {
             path,
+            data,
             contents: contents.into(),
         }
     }
@@ -46,9 +50,29 @@ impl<'a> ExtractedMember<'a> {
         self.path
     }
 
+    /// Returns the raw data of the member.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
     pub fn contents(&self) -> &ExtractedMemberContents<'a> {
         &self.contents
     }
+
+    /// Returns `true` if the member can be linked for the architecture.
+    ///
+    /// COFFs without a machine type or without any sections are accepted
+    /// for any architecture.
+    pub fn is_for_architecture(&self, architecture: Architecture) -> bool {
+        match &self.contents {
+            ExtractedMemberContents::Coff(coff) => {
+                coff.architecture() == architecture
+                    || coff.coff_header().machine.get(LittleEndian) == IMAGE_FILE_MACHINE_UNKNOWN
+                    || coff.coff_section_table().is_empty()
+            }
+            ExtractedMemberContents::Import(import) => import.architecture == architecture,
+        }
+    }
 }
 
 pub enum ExtractedMemberContents<'a> {
@@ -68,32 +92,162 @@ impl<'a> From<ImportMember<'a>> for ExtractedMemberContents<'a> {
     }
 }
 
+/// The kind of an archive member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveMemberKind {
+    /// A COFF.
+    Coff,
+
+    /// A short import library member.
+    ShortImport,
+
+    /// A legacy import library member with the import in a COFF.
+    LegacyImport,
+
+    /// A member which could not be parsed.
+    Invalid,
+}
+
+impl std::fmt::Display for ArchiveMemberKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Coff => write!(f, "COFF"),
+            Self::ShortImport => write!(f, "short import"),
+            Self::LegacyImport => write!(f, "legacy import"),
+            Self::Invalid => write!(f, "invalid"),
+        }
+    }
+}
+
+/// The symbols provided by an archive member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberExports<'a> {
+    /// The name of the member.
+    pub name: &'a str,
+
+    /// The kind of member.
+    pub kind: ArchiveMemberKind,
+
+    /// The name of the DLL for import members.
+    pub dll: Option<&'a str>,
+
+    /// The symbols in the archive symbol table for the member.
+    pub symbols: Vec<&'a str>,
+}
+
+/// Archives with at least this many symbols have the symbol table indexed
+/// when they are parsed instead of on demand.
+pub const EAGER_INDEX_THRESHOLD: usize = 4096;
+
+/// Statistics for the symbol lookups in a [`LinkArchive`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolLookupStats {
+    /// The number of symbols looked up.
+    pub lookups: u32,
+
+    /// The number of lookups found in the indexed symbols.
+    pub cache_hits: u32,
+
+    /// The number of lookups for symbols not in the archive.
+    pub misses: u32,
+
+    /// The number of symbols read from the archive symbol table.
+    pub scanned: u32,
+
+    /// Whether the symbol table was fully indexed before the first lookup.
+    pub eager: bool,
+}
+
 struct CachedSymbolMap<'a> {
     cache: HashMap<&'a str, ArchiveOffset>,
+
+    /// Members after the first one defining the same symbol.
+    duplicates: HashMap<&'a str, Vec<ArchiveOffset>>,
+
     iter: Option<ArchiveSymbolIterator<'a>>,
+    stats: SymbolLookupStats,
 }
 
 impl CachedSymbolMap<'_> {
     fn find_symbol(&mut self, symbol: &str) -> Option<ArchiveOffset> {
+        self.stats.lookups += 1;
+
         if let Some(found) = self.cache.get(symbol).copied() {
+            self.stats.cache_hits += 1;
             return Some(found);
         }
 
         for archive_symbol in self.iter.iter_mut().flatten().flatten() {
+            self.stats.scanned += 1;
+
             let archive_symbol_name = match std::str::from_utf8(archive_symbol.name()) {
                 Ok(name) => name,
                 Err(_) => continue,
             };
 
-            self.cache
-                .insert(archive_symbol_name, archive_symbol.offset());
+            insert_symbol(
+                &mut self.cache,
+                &mut self.duplicates,
+                archive_symbol_name,
+                archive_symbol.offset(),
+            );
             if archive_symbol_name == symbol {
                 return Some(archive_symbol.offset());
             }
         }
 
+        // Symbol table was exhausted
+        self.iter = None;
+        self.stats.misses += 1;
         None
     }
+
+    /// Indexes the rest of the symbol table.
+    fn index_all(&mut self) {
+        for archive_symbol in self.iter.take().into_iter().flatten().flatten() {
+            self.stats.scanned += 1;
+
+            if let Ok(name) = std::str::from_utf8(archive_symbol.name()) {
+                insert_symbol(
+                    &mut self.cache,
+                    &mut self.duplicates,
+                    name,
+                    archive_symbol.offset(),
+                );
+            }
+        }
+    }
+
+    /// Returns the offsets of all members defining the symbol.
+    fn find_all_symbols(&mut self, symbol: &str) -> Vec<ArchiveOffset> {
+        self.index_all();
+
+        self.cache
+            .get(symbol)
+            .into_iter()
+            .chain(self.duplicates.get(symbol).into_iter().flatten())
+            .copied()
+            .collect()
+    }
+}
+
+/// Adds the symbol to the cache keeping the first member defining it.
+fn insert_symbol<'a>(
+    cache: &mut HashMap<&'a str, ArchiveOffset>,
+    duplicates: &mut HashMap<&'a str, Vec<ArchiveOffset>>,
+    name: &'a str,
+    offset: ArchiveOffset,
+) {
+    match cache.entry(name) {
+        std::collections::hash_map::Entry::Occupied(entry) => {
+            if entry.get().0 != offset.0 {
+                duplicates.entry(name).or_default().push(offset);
+            }
+        }
+        std::collections::hash_map::Entry::Vacant(entry) => {
+            entry.insert(offset);
+        }
+    }
 }
 
 /// A parsed archive file for linking.
@@ -108,6 +262,10 @@ pub struct LinkArchive<'a> {
     /// library names.
     legacy_imports: RefCell<BTreeMap<&'a str, &'a str>>,
 
+    /// Archive symbol names keyed by the folded name. Built on the first
+    /// folded lookup.
+    folded_index: OnceCell<HashMap<String, Vec<&'a str>>>,
+
     /// The archive file data.
     archive_data: &'a [u8],
 }
@@ -129,15 +287,134 @@ impl<'a> LinkArchive<'a> {
             .1
             .unwrap_or_else(|| symbols.clone().count());
 
-        Ok(Self {
+        let archive = Self {
             archive_file,
             symbol_cache: RefCell::new(CachedSymbolMap {
                 cache: HashMap::with_capacity(symbol_count),
+                duplicates: HashMap::new(),
                 iter: Some(symbols),
+                stats: SymbolLookupStats::default(),
             }),
             legacy_imports: RefCell::new(BTreeMap::new()),
+            folded_index: OnceCell::new(),
             archive_data: data,
-        })
+        };
+
+        if symbol_count >= EAGER_INDEX_THRESHOLD {
+            archive.index_symbols();
+        }
+
+        Ok(archive)
+    }
+
+    /// Indexes the full archive symbol table.
+    ///
+    /// Lookups index the symbol table on demand by default. Archives with at
+    /// least [`EAGER_INDEX_THRESHOLD`] symbols are indexed when parsed.
+    pub fn index_symbols(&self) {
+        let mut symbol_map = self.symbol_cache.borrow_mut();
+        if symbol_map.stats.lookups == 0 {
+            symbol_map.stats.eager = true;
+        }
+
+        symbol_map.index_all();
+    }
+
+    /// Returns the archive symbols with the same folded name as the symbol in
+    /// symbol table order.
+    ///
+    /// See [`fold_symbol_name`] for how the names are folded. The index is
+    /// built from the archive symbol table on the first call.
+    pub fn find_folded(&self, symbol: &str) -> &[&'a str] {
+        let index = self.folded_index.get_or_init(|| {
+            let mut index: HashMap<String, Vec<&'a str>> = HashMap::new();
+
+            for archive_symbol in self
+                .archive_file
+                .symbols()
+                .ok()
+                .flatten()
+                .into_iter()
+                .flatten()
+            {
+                let Some(name) = archive_symbol
+                    .ok()
+                    .and_then(|archive_symbol| std::str::from_utf8(archive_symbol.name()).ok())
+                else {
+                    continue;
+                };
+
+                index.entry(fold_symbol_name(name)).or_default().push(name);
+            }
+
+            index
+        });
+
+        index
+            .get(&fold_symbol_name(symbol))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the symbols provided by each member in the archive symbol
+    /// table ordered by the member position in the archive.
+    pub fn member_exports(&self) -> Vec<MemberExports<'a>> {
+        let mut member_symbols: BTreeMap<u64, Vec<&'a str>> = BTreeMap::new();
+        for archive_symbol in self
+            .archive_file
+            .symbols()
+            .ok()
+            .flatten()
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            if let Ok(name) = std::str::from_utf8(archive_symbol.name()) {
+                member_symbols
+                    .entry(archive_symbol.offset().0)
+                    .or_default()
+                    .push(name);
+            }
+        }
+
+        member_symbols
+            .into_iter()
+            .filter_map(|(offset, symbols)| {
+                let member = self.archive_file.member(ArchiveOffset(offset)).ok()?;
+                let name = std::str::from_utf8(member.name()).unwrap_or("<invalid name>");
+
+                let short_import = member
+                    .data(self.archive_data)
+                    .ok()
+                    .and_then(|data| data.get(..2))
+                    .is_some_and(|magic| magic == IMAGE_FILE_MACHINE_UNKNOWN.to_le_bytes());
+
+                let (kind, dll) = match self.parse_member(&member, name) {
+                    Ok(extracted) => match extracted.contents() {
+                        ExtractedMemberContents::Coff(_) => (ArchiveMemberKind::Coff, None),
+                        ExtractedMemberContents::Import(import) if short_import => {
+                            (ArchiveMemberKind::ShortImport, Some(import.dll))
+                        }
+                        ExtractedMemberContents::Import(import) => {
+                            (ArchiveMemberKind::LegacyImport, Some(import.dll))
+                        }
+                    },
+                    Err(_) => (ArchiveMemberKind::Invalid, None),
+                };
+
+                Some(MemberExports {
+                    name,
+                    kind,
+                    dll,
+                    symbols,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the statistics for the symbol lookups.
+    pub fn lookup_stats(&self) -> SymbolLookupStats {
+        self.symbol_cache.borrow().stats
     }
 
     pub fn extract_symbol(
@@ -152,6 +429,47 @@ impl<'a> LinkArchive<'a> {
             .map_err(ExtractMemberError::MemberParse)
     }
 
+    /// Extracts the member defining the symbol for the target architecture.
+    ///
+    /// Archives may contain members for multiple architectures defining the
+    /// same symbol. The first member is returned if none of the members
+    /// defining the symbol are for the architecture.
+    pub fn extract_symbol_for(
+        &self,
+        symbol: &'a str,
+        architecture: Architecture,
+    ) -> Result<ExtractedMember<'a>, ExtractMemberError> {
+        let first = self.extract_symbol(symbol)?;
+        if first.is_for_architecture(architecture) {
+            return Ok(first);
+        }
+
+        let offsets = self.symbol_cache.borrow_mut().find_all_symbols(symbol);
+        for offset in offsets.into_iter().skip(1) {
+            let Ok(member) = self.archive_file.member(offset) else {
+                continue;
+            };
+
+            let Ok(member_name) = std::str::from_utf8(member.name()) else {
+                continue;
+            };
+
+            if let Some(extracted) = self
+                .parse_member(&member, member_name)
+                .ok()
+                .filter(|extracted| extracted.is_for_architecture(architecture))
+            {
+                debug!(
+                    "{}: using '{member_name}' for '{symbol}' instead of the member for a different architecture",
+                    first.path().display()
+                );
+                return Ok(extracted);
+            }
+        }
+
+        Ok(first)
+    }
+
     fn parse_member(
         &self,
         member: &ArchiveMember<'a>,
@@ -169,6 +487,7 @@ impl<'a> LinkArchive<'a> {
         {
             Ok(ExtractedMember {
                 path: member_path,
+                data: member_data,
                 contents: ExtractedMemberContents::Import(
                     ImportFile::parse(member_data)
                         .map_err(|e| MemberParseError::new(member_path, e))?
@@ -181,7 +500,7 @@ impl<'a> LinkArchive<'a> {
                 .map_err(|e| MemberParseError::new(member_path, e))?;
 
             match self.parse_legacy_import_member(member_name, &coff) {
-                Ok(import) => Ok(ExtractedMember::new(member_path, import)),
+                Ok(import) => Ok(ExtractedMember::new(member_path, member_data, import)),
                 Err(e)
                     if matches!(
                         e.kind,
@@ -190,7 +509,7 @@ impl<'a> LinkArchive<'a> {
                         )
                     ) =>
                 {
-                    Ok(ExtractedMember::new(member_path, coff))
+                    Ok(ExtractedMember::new(member_path, member_data, coff))
                 }
                 Err(e) => Err(e),
             }
@@ -311,6 +630,20 @@ impl<'a> LinkArchive<'a> {
     }
 }
 
+/// Returns the symbol name with the i386 decorations removed and ASCII
+/// characters in lowercase.
+///
+/// The `__imp_` prefix is kept so that imports do not match the public
+/// symbols.
+pub fn fold_symbol_name(name: &str) -> String {
+    let (prefix, name) = match name.strip_prefix("__imp_") {
+        Some(unprefixed) => ("__imp_", unprefixed),
+        None => ("", name),
+    };
+
+    format!("{prefix}{}", undecorate_symbol(name).to_ascii_lowercase())
+}
+
 impl<'a> ApiSymbolSource<'a> for PathedItem<&Path, LinkArchive<'a>> {
     fn api_path(&self) -> &Path {
         self.path()
@@ -323,10 +656,7 @@ impl<'a> ApiSymbolSource<'a> for PathedItem<&Path, LinkArchive<'a>> {
 
 impl<'a> ApiSymbolSource<'a> for LinkArchive<'a> {
     fn extract_api_symbol(&self, symbol: &'a str) -> Result<ImportMember<'a>, ApiSymbolError> {
-        let member = match self.extract_archive_member(symbol) {
-            Ok(member) => member,
-            Err(e) => return Err(e.into()),
-        };
+        let member = self.extract_archive_member(symbol)?;
 
         let member_name = std::str::from_utf8(member.name())
             .map_err(|e| ApiSymbolError::ArchiveParse(ArchiveParseError::MemberName(e)))?;
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use object::{
+    Architecture, FileKind, LittleEndian, Object,
+    pe::IMAGE_SCN_MEM_EXECUTE,
+    read::pe::{ExportTable, ExportTarget, ImageNtHeaders, PeFile, SectionTable},
+};
+
+use super::import::{ImportMember, ImportName, ImportType};
+
+#[derive(Debug, thiserror::Error)]
+pub enum DllParseError {
+    #[error("file is not a PE DLL")]
+    NotDll,
+
+    #[error("DLL does not have an export table")]
+    NoExportTable,
+
+    #[error("DLL name is invalid: {0}")]
+    DllName(std::str::Utf8Error),
+
+    #[error("{0}")]
+    Object(#[from] object::read::Error),
+}
+
+/// The named exports of a PE DLL used for resolving symbols as imports.
+pub struct DllImports<'a> {
+    /// The architecture of the DLL.
+    architecture: Architecture,
+
+    /// The name of the DLL from the export directory.
+    dll: &'a str,
+
+    /// The exported names with the type of symbol they point to.
+    exports: HashMap<&'a str, ImportType>,
+}
+
+impl<'a> DllImports<'a> {
+    /// Returns `true` if the data starts with the MS-DOS header magic.
+    pub fn is_dll(data: &[u8]) -> bool {
+        data.get(..2).is_some_and(|magic| magic == b"MZ")
+    }
+
+    /// Parses the export table of the DLL.
+    pub fn parse(data: &'a [u8]) -> Result<DllImports<'a>, DllParseError> {
+        match FileKind::parse(data)? {
+            FileKind::Pe32 => Self::parse_pe::<object::pe::ImageNtHeaders32>(data),
+            FileKind::Pe64 => Self::parse_pe::<object::pe::ImageNtHeaders64>(data),
+            _ => Err(DllParseError::NotDll),
+        }
+    }
+
+    fn parse_pe<Pe: ImageNtHeaders>(data: &'a [u8]) -> Result<DllImports<'a>, DllParseError> {
+        let pe_file = PeFile::<Pe>::parse(data)?;
+        let export_table = pe_file
+            .export_table()?
+            .ok_or(DllParseError::NoExportTable)?;
+
+        let dll =
+            export_table.name_from_pointer(export_table.directory().name.get(LittleEndian))?;
+        let dll = std::str::from_utf8(dll).map_err(DllParseError::DllName)?;
+
+        let sections = pe_file.section_table();
+
+        let mut exports = HashMap::with_capacity(export_table.name_pointers().len());
+        for (name_pointer, index) in export_table.name_iter() {
+            // Skip names which are not valid symbol names
+            if let Ok(Ok(name)) = export_table
+                .name_from_pointer(name_pointer)
+                .map(std::str::from_utf8)
+            {
+                exports.insert(name, export_type(&export_table, &sections, index));
+            }
+        }
+
+        Ok(Self {
+            architecture: pe_file.architecture(),
+            dll,
+            exports,
+        })
+    }
+
+    /// Returns the name of the DLL.
+    pub fn dll(&self) -> &'a str {
+        self.dll
+    }
+
+    /// Returns the exported names sorted by name.
+    pub fn exports(&self) -> Vec<&'a str> {
+        let mut exports = Vec::from_iter(self.exports.keys().copied());
+        exports.sort_unstable();
+        exports
+    }
+
+    /// Returns an [`ImportMember`] for the symbol if the DLL exports it.
+    ///
+    /// The `__imp_` prefix is removed from the symbol name along with the
+    /// i386 name decorations before looking up the export.
+    pub fn extract_symbol(&self, symbol: &'a str) -> Option<ImportMember<'a>> {
+        let public_symbol = symbol.strip_prefix("__imp_").unwrap_or(symbol);
+
+        let export_name = if self.architecture == Architecture::I386 {
+            undecorate_symbol(public_symbol)
+        } else {
+            public_symbol
+        };
+
+        let (export_name, typ) = self.exports.get_key_value(export_name)?;
+
+        Some(ImportMember {
+            architecture: self.architecture,
+            symbol: public_symbol,
+            dll: self.dll,
+            import: ImportName::Name(export_name),
+            typ: *typ,
+        })
+    }
+}
+
+/// Returns the [`ImportType`] for the export at the index in the export
+/// address table.
+///
+/// Exports pointing into sections which are not executable are imported as
+/// data. Forwarded exports and exports which cannot be resolved are imported
+/// as code.
+fn export_type(export_table: &ExportTable, sections: &SectionTable, index: u16) -> ImportType {
+    match export_table.target_by_index(index.into()) {
+        Ok(ExportTarget::Address(address)) => match sections.section_containing(address) {
+            Some(section)
+                if section.characteristics.get(LittleEndian) & IMAGE_SCN_MEM_EXECUTE == 0 =>
+            {
+                ImportType::Data
+            }
+            _ => ImportType::Code,
+        },
+        _ => ImportType::Code,
+    }
+}
+
+/// Removes the cdecl, stdcall and fastcall decorations from the i386 symbol
+/// name.
+pub(super) fn undecorate_symbol(symbol: &str) -> &str {
+    let symbol = symbol
+        .strip_prefix('_')
+        .or_else(|| symbol.strip_prefix('@'))
+        .unwrap_or(symbol);
+
+    match symbol.rsplit_once('@') {
+        Some((name, suffix))
+            if !suffix.is_empty() && suffix.bytes().all(|c| c.is_ascii_digit()) =>
+        {
+            name
+        }
+        _ => symbol,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::undecorate_symbol;
+
+    #[test]
+    fn undecorate() {
+        assert_eq!(undecorate_symbol("_MessageBoxA@16"), "MessageBoxA");
+        assert_eq!(undecorate_symbol("@FastFunc@8"), "FastFunc");
+        assert_eq!(undecorate_symbol("_strlen"), "strlen");
+        assert_eq!(undecorate_symbol("name@v"), "name@v");
+    }
+}
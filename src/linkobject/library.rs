@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use object::Architecture;
+
+use super::{
+    archive::{
+        ExtractMemberError, ExtractedMember, LinkArchive, LinkArchiveParseError, SymbolLookupStats,
+        fold_symbol_name,
+    },
+    dll::{DllImports, DllParseError},
+    moddef::ModuleDefinition,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LinkLibraryParseError {
+    #[error("{0}")]
+    Archive(#[from] LinkArchiveParseError),
+
+    #[error("{0}")]
+    Dll(#[from] DllParseError),
+}
+
+/// A parsed link library used for resolving symbols.
+pub enum LinkLibrary<'a> {
+    /// An archive file or import library.
+    Archive(Box<LinkArchive<'a>>),
+
+    /// A DLL with the exports used as imports.
+    Dll(DllImports<'a>),
+
+    /// A module-definition file with the exports used as imports.
+    ModuleDefinition(ModuleDefinition<'a>),
+}
+
+impl<'a> LinkLibrary<'a> {
+    /// Returns `true` if the data is an archive file or a DLL.
+    pub fn is_library(data: &[u8]) -> bool {
+        data.get(..object::archive::MAGIC.len())
+            .is_some_and(|magic| magic == object::archive::MAGIC)
+            || DllImports::is_dll(data)
+    }
+
+    /// Parses the data as an archive file or DLL.
+    pub fn parse(data: &'a [u8]) -> Result<LinkLibrary<'a>, LinkLibraryParseError> {
+        if DllImports::is_dll(data) {
+            Ok(Self::Dll(DllImports::parse(data)?))
+        } else {
+            Ok(Self::Archive(Box::new(LinkArchive::parse(data)?)))
+        }
+    }
+
+    /// Returns the symbol lookup statistics for archives.
+    pub fn lookup_stats(&self) -> Option<SymbolLookupStats> {
+        match self {
+            Self::Archive(archive) => Some(archive.lookup_stats()),
+            Self::Dll(_) | Self::ModuleDefinition(_) => None,
+        }
+    }
+
+    /// Returns the symbols which match `symbol` after case folding and
+    /// removing the i386 name decorations.
+    ///
+    /// Module-definition files do not return any symbols.
+    pub fn find_folded(&self, symbol: &str) -> Vec<&'a str> {
+        match self {
+            Self::Archive(archive) => archive.find_folded(symbol).to_vec(),
+            Self::Dll(dll) => {
+                // DLL exports do not have the `__imp_` prefix
+                let folded = fold_symbol_name(symbol.strip_prefix("__imp_").unwrap_or(symbol));
+                dll.exports()
+                    .into_iter()
+                    .filter(|export| fold_symbol_name(export) == folded)
+                    .collect()
+            }
+            Self::ModuleDefinition(_) => Vec::new(),
+        }
+    }
+
+    /// Extracts the member defining the symbol for the architecture.
+    ///
+    /// DLLs and module-definition files return an import member for the
+    /// exported symbol.
+    pub fn extract_symbol(
+        &self,
+        symbol: &'a str,
+        architecture: Architecture,
+    ) -> Result<ExtractedMember<'a>, ExtractMemberError> {
+        match self {
+            Self::Archive(archive) => archive.extract_symbol_for(symbol, architecture),
+            Self::Dll(dll) => dll
+                .extract_symbol(symbol)
+                .map(|import| ExtractedMember::new(Path::new(dll.dll()), &[], import))
+                .ok_or(ExtractMemberError::NotFound),
+            Self::ModuleDefinition(def) => def
+                .extract_symbol(symbol)
+                .map(|import| ExtractedMember::new(Path::new(def.dll()), &[], import))
+                .ok_or(ExtractMemberError::NotFound),
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod archive;
+pub mod dll;
 pub mod import;
+pub mod library;
+pub mod moddef;
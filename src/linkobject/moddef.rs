@@ -0,0 +1,316 @@
+use std::{collections::HashMap, str::Utf8Error};
+
+use object::Architecture;
+
+use super::{
+    dll::undecorate_symbol,
+    import::{ImportMember, ImportName, ImportType},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModuleDefinitionParseError {
+    #[error("module-definition file is not valid UTF-8: {0}")]
+    Utf8(#[from] Utf8Error),
+
+    #[error("missing LIBRARY statement")]
+    MissingLibrary,
+
+    #[error("line {0}: expected a library name")]
+    LibraryName(usize),
+
+    #[error("line {0}: invalid export ordinal '{1}'")]
+    Ordinal(usize, String),
+
+    #[error("line {0}: unexpected '{1}' in export")]
+    Export(usize, String),
+}
+
+/// Statements in module-definition files which do not affect the imports.
+const IGNORED_STATEMENTS: [&str; 7] = [
+    "DESCRIPTION",
+    "VERSION",
+    "HEAPSIZE",
+    "STACKSIZE",
+    "SECTIONS",
+    "SEGMENTS",
+    "STUB",
+];
+
+/// An export from a module-definition file.
+struct DefExport<'a> {
+    /// The name imported from the DLL.
+    import: ImportName<'a>,
+
+    /// The type of import.
+    typ: ImportType,
+}
+
+/// The exports of a DLL from a module-definition (.def) file used for
+/// resolving symbols as imports.
+pub struct ModuleDefinition<'a> {
+    /// The architecture of the imports.
+    architecture: Architecture,
+
+    /// The name of the DLL from the LIBRARY statement.
+    dll: &'a str,
+
+    /// The exported names mapped to the imports.
+    exports: HashMap<&'a str, DefExport<'a>>,
+}
+
+impl<'a> ModuleDefinition<'a> {
+    /// Parses the LIBRARY and EXPORTS statements of the module-definition
+    /// file.
+    ///
+    /// Exports support the `name[=internal]`, `name==import`, `@ordinal`,
+    /// `NONAME`, `DATA`, `CONSTANT` and `PRIVATE` syntax. Private exports are
+    /// skipped.
+    pub fn parse(
+        data: &'a [u8],
+        architecture: Architecture,
+    ) -> Result<ModuleDefinition<'a>, ModuleDefinitionParseError> {
+        let content = std::str::from_utf8(data)?;
+
+        let mut dll = None;
+        let mut exports = HashMap::new();
+        let mut in_exports = false;
+
+        for (idx, line) in content.lines().enumerate() {
+            let line_number = idx + 1;
+            let line = line.split_once(';').map_or(line, |(line, _)| line).trim();
+
+            let mut tokens = line.split_whitespace().peekable();
+            let Some(first) = tokens.peek().copied() else {
+                continue;
+            };
+
+            match first {
+                "LIBRARY" | "NAME" => {
+                    tokens.next();
+                    in_exports = false;
+
+                    dll = Some(
+                        tokens
+                            .next()
+                            .map(|name| name.trim_matches('"'))
+                            .filter(|name| !name.is_empty())
+                            .ok_or(ModuleDefinitionParseError::LibraryName(line_number))?,
+                    );
+                    continue;
+                }
+                "EXPORTS" => {
+                    tokens.next();
+                    in_exports = true;
+
+                    // The first export can be on the same line
+                    if tokens.peek().is_none() {
+                        continue;
+                    }
+                }
+                _ if IGNORED_STATEMENTS.contains(&first) => {
+                    in_exports = false;
+                    continue;
+                }
+                _ if !in_exports => continue,
+                _ => (),
+            }
+
+            if let Some((name, export)) = parse_export(tokens, line_number)? {
+                exports.insert(name, export);
+            }
+        }
+
+        Ok(Self {
+            architecture,
+            dll: dll.ok_or(ModuleDefinitionParseError::MissingLibrary)?,
+            exports,
+        })
+    }
+
+    /// Returns the name of the DLL.
+    pub fn dll(&self) -> &'a str {
+        self.dll
+    }
+
+    /// Returns an [`ImportMember`] for the symbol if it is exported.
+    ///
+    /// Data and constant exports are only resolved for the `__imp_` prefixed
+    /// symbol. i386 symbols have the name decorations removed if the
+    /// decorated name is not exported.
+    pub fn extract_symbol(&self, symbol: &'a str) -> Option<ImportMember<'a>> {
+        let (public_symbol, import_symbol) = match symbol.strip_prefix("__imp_") {
+            Some(public_symbol) => (public_symbol, true),
+            None => (symbol, false),
+        };
+
+        let export = if self.architecture == Architecture::I386 {
+            let name = public_symbol.strip_prefix('_').unwrap_or(public_symbol);
+            self.exports
+                .get(name)
+                .or_else(|| self.exports.get(undecorate_symbol(public_symbol)))?
+        } else {
+            self.exports.get(public_symbol)?
+        };
+
+        if export.typ != ImportType::Code && !import_symbol {
+            return None;
+        }
+
+        Some(ImportMember {
+            architecture: self.architecture,
+            symbol: public_symbol,
+            dll: self.dll,
+            import: export.import,
+            typ: export.typ,
+        })
+    }
+}
+
+/// Parses an export definition from the tokens of the line.
+///
+/// Returns [`None`] for private exports.
+fn parse_export<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<Option<(&'a str, DefExport<'a>)>, ModuleDefinitionParseError> {
+    let Some(definition) = tokens.next() else {
+        return Ok(None);
+    };
+
+    // name==import imports a different name from the DLL. The internal name
+    // from name=internal does not affect the import.
+    let (name, import) = match definition.split_once("==") {
+        Some((name, import)) => (name, import),
+        None => {
+            let name = definition
+                .split_once('=')
+                .map_or(definition, |(name, _)| name);
+            (name, name)
+        }
+    };
+
+    if name.is_empty() || import.is_empty() {
+        return Err(ModuleDefinitionParseError::Export(
+            line_number,
+            definition.to_string(),
+        ));
+    }
+
+    let mut ordinal = None;
+    let mut noname = false;
+    let mut private = false;
+    let mut typ = ImportType::Code;
+
+    let mut tokens = tokens.peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            "NONAME" => noname = true,
+            "PRIVATE" => private = true,
+            "DATA" => typ = ImportType::Data,
+            "CONSTANT" => typ = ImportType::Const,
+            _ if token.starts_with('@') => {
+                // The ordinal can be separated from the '@' by whitespace
+                let value = match &token[1..] {
+                    "" => tokens.next().unwrap_or_default(),
+                    value => value,
+                };
+
+                ordinal = Some(value.parse::<u16>().map_err(|_| {
+                    ModuleDefinitionParseError::Ordinal(line_number, value.to_string())
+                })?);
+            }
+            _ => {
+                return Err(ModuleDefinitionParseError::Export(
+                    line_number,
+                    token.to_string(),
+                ));
+            }
+        }
+    }
+
+    if private {
+        return Ok(None);
+    }
+
+    let import = match ordinal {
+        Some(ordinal) if noname => ImportName::Ordinal(ordinal),
+        _ => ImportName::Name(import),
+    };
+
+    Ok(Some((name, DefExport { import, typ })))
+}
+
+#[cfg(test)]
+mod tests {
+    use object::Architecture;
+
+    use super::ModuleDefinition;
+    use crate::linkobject::import::{ImportName, ImportType};
+
+    const DEF: &str = r#"; mylib.def
+LIBRARY "mylib"
+DESCRIPTION "private library"
+EXPORTS
+    Function
+    Renamed=InternalName @2
+    Alias==Target
+    ByOrdinal @ 7 NONAME
+    Variable DATA
+    Hidden PRIVATE ; not in the import library
+"#;
+
+    #[test]
+    fn exports() {
+        let def = ModuleDefinition::parse(DEF.as_bytes(), Architecture::X86_64).unwrap();
+        assert_eq!(def.dll(), "mylib");
+
+        let import = def.extract_symbol("Function").unwrap();
+        assert_eq!(import.symbol, "Function");
+        assert_eq!(import.import, ImportName::Name("Function"));
+
+        let import = def.extract_symbol("__imp_Renamed").unwrap();
+        assert_eq!(import.symbol, "Renamed");
+        assert_eq!(import.import, ImportName::Name("Renamed"));
+
+        let import = def.extract_symbol("Alias").unwrap();
+        assert_eq!(import.import, ImportName::Name("Target"));
+
+        let import = def.extract_symbol("ByOrdinal").unwrap();
+        assert_eq!(import.import, ImportName::Ordinal(7));
+
+        assert!(def.extract_symbol("Variable").is_none());
+        let import = def.extract_symbol("__imp_Variable").unwrap();
+        assert_eq!(import.typ, ImportType::Data);
+
+        assert!(def.extract_symbol("Hidden").is_none());
+        assert!(def.extract_symbol("InternalName").is_none());
+    }
+
+    #[test]
+    fn i386_decorations() {
+        let def = ModuleDefinition::parse(
+            b"LIBRARY user32.dll\nEXPORTS MessageBoxA@16\n    GetLastError\n",
+            Architecture::I386,
+        )
+        .unwrap();
+
+        let import = def.extract_symbol("__imp__MessageBoxA@16").unwrap();
+        assert_eq!(import.import, ImportName::Name("MessageBoxA@16"));
+
+        let import = def.extract_symbol("_GetLastError@0").unwrap();
+        assert_eq!(import.import, ImportName::Name("GetLastError"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(ModuleDefinition::parse(b"EXPORTS\n    Function\n", Architecture::X86_64).is_err());
+        assert!(
+            ModuleDefinition::parse(b"LIBRARY a\nEXPORTS\n    f @x\n", Architecture::X86_64)
+                .is_err()
+        );
+        assert!(
+            ModuleDefinition::parse(b"LIBRARY a\nEXPORTS\n    f BOGUS\n", Architecture::X86_64)
+                .is_err()
+        );
+    }
+}
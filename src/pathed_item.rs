@@ -1,6 +1,7 @@
 use std::path::Path;
 
 /// An item with an associated path.
+#[derive(Clone)]
 pub struct PathedItem<P: AsRef<Path>, T> {
     path: P,
     item: T,
@@ -22,6 +23,14 @@ impl<P: AsRef<Path>, T> PathedItem<P, T> {
         &mut self.path
     }
 
+    /// Maps the item to a different value while keeping the path.
+    pub fn map_item<U>(self, f: impl FnOnce(T) -> U) -> PathedItem<P, U> {
+        PathedItem {
+            path: self.path,
+            item: f(self.item),
+        }
+    }
+
     /// Converts the item into a `Box<T>`.
     pub fn into_boxed_item(self) -> PathedItem<P, Box<T>> {
         PathedItem {
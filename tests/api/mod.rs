@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use boflink::{
+    linker::{
+        BeaconApiCategory, LinkerBuilder, LinkerTargetArch,
+        error::{LinkError, LinkerSetupError},
+    },
+    pathed_item::PathedItem,
+};
+use object::{Object, ObjectSymbol, coff::CoffFile};
+use serde::Deserialize;
+
+use boflink_testutils::{archive_searcher::MemoryArchiveSearcher, build::YamlInput};
+
+/// Sets up a linker for the input YAML using the import libraries as custom
+/// APIs.
+fn setup_api_linker(input: &str) -> LinkerBuilder<MemoryArchiveSearcher> {
+    let mut searcher = MemoryArchiveSearcher::new();
+    let mut coffs = Vec::new();
+
+    for document in serde_yml::Deserializer::from_str(input) {
+        match YamlInput::deserialize(document).unwrap() {
+            YamlInput::Coff(coff) => {
+                coffs.push(PathedItem::new("file1".into(), coff.build().unwrap()));
+            }
+            YamlInput::Importlib(importlib) => {
+                let name = importlib.library.clone();
+                searcher.add_library(
+                    name,
+                    importlib.build(LinkerTargetArch::Amd64.into()).unwrap(),
+                );
+            }
+            YamlInput::LegacyImportlib(importlib) => {
+                let name = importlib.library.clone();
+                searcher.add_library(
+                    name,
+                    importlib
+                        .build_legacy(LinkerTargetArch::Amd64.into())
+                        .unwrap(),
+                );
+            }
+            YamlInput::Dll(dll) => {
+                let name = dll.library.clone();
+                searcher.add_library(name, dll.build_dll(LinkerTargetArch::Amd64.into()).unwrap());
+            }
+        }
+    }
+
+    LinkerBuilder::new()
+        .architecture(LinkerTargetArch::Amd64)
+        .library_searcher(searcher)
+        .add_inputs(coffs)
+}
+
+#[test]
+fn api_priority() {
+    let mut linker = setup_api_linker(include_str!("priority.yaml"))
+        .custom_api("api1")
+        .custom_api("api2")
+        .build();
+
+    linker.link().expect("Could not link files");
+
+    let api_symbols = linker.report().api_symbols().collect::<Vec<_>>();
+
+    for (symbol, api) in [
+        ("__imp_ApiOne", "api1"),
+        ("__imp_ApiTwo", "api2"),
+        ("__imp_Shared", "api1"),
+    ] {
+        assert!(
+            api_symbols.contains(&(symbol, Path::new(api))),
+            "API symbol '{symbol}' should have been resolved from '{api}'"
+        );
+    }
+}
+
+#[test]
+fn api_prefix_mapping() {
+    let linked = setup_api_linker(include_str!("mapping.yaml"))
+        .custom_api("bofapi")
+        .map_api_symbol("Beacon*", "BofApi*")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    for name in ["__imp_BofApiPrintf", "__imp_BofApiOutput"] {
+        let symbol = coff
+            .symbol_by_name(name)
+            .unwrap_or_else(|| panic!("Could not find mapped API symbol '{name}'"));
+
+        assert!(
+            symbol.is_undefined(),
+            "API symbol '{name}' should be undefined"
+        );
+    }
+
+    assert!(
+        coff.symbol_by_name("__imp_BeaconPrintf").is_none(),
+        "unmapped API symbol '__imp_BeaconPrintf' should not be in the output"
+    );
+}
+
+#[test]
+fn api_mixed_prefix_mapping() {
+    let err = setup_api_linker(include_str!("mapping.yaml"))
+        .custom_api("bofapi")
+        .map_api_symbol("Beacon*", "BofApiPrintf")
+        .build()
+        .link()
+        .expect_err("Linking with a mixed prefix mapping should fail");
+
+    let LinkError::Setup(setup_errors) = err else {
+        panic!("Expected a setup error for the mapping, got: {err}");
+    };
+
+    assert!(
+        matches!(
+            setup_errors.errors(),
+            [LinkerSetupError::ApiSymbolMap(e)] if e.from == "Beacon*" && e.to == "BofApiPrintf"
+        ),
+        "Unexpected errors: {setup_errors}"
+    );
+}
+
+#[test]
+fn api_categories() {
+    let mut linker = setup_api_linker(include_str!("mapping.yaml")).build();
+    linker.link().expect("Could not link files");
+
+    let categories = linker
+        .report()
+        .api_categories()
+        .map(|(category, names)| (category, Vec::from_iter(names.iter().map(String::as_str))))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        categories,
+        [(
+            BeaconApiCategory::Output,
+            vec!["BeaconOutput", "BeaconPrintf"]
+        )]
+    );
+
+    // Mapped symbols from other APIs are not Beacon API functions
+    let mut linker = setup_api_linker(include_str!("mapping.yaml"))
+        .custom_api("bofapi")
+        .map_api_symbol("Beacon*", "BofApi*")
+        .build();
+    linker.link().expect("Could not link files");
+    assert_eq!(linker.report().api_categories().count(), 0);
+}
@@ -1,5 +1,5 @@
 use crate::{link_yaml, setup_linker};
-use boflink::linker::LinkerTargetArch;
+use boflink::{graph::SymbolRenameMode, linker::LinkerTargetArch};
 use object::{Object, ObjectSection, ObjectSymbol, coff::CoffFile};
 
 #[test]
@@ -54,6 +54,29 @@ fn common_symbols() {
     }
 }
 
+#[test]
+fn renamed_common_symbols() {
+    let bss_size = |linked: &[u8]| {
+        let coff: CoffFile = CoffFile::parse(linked).expect("Could not parse linked COFF");
+        coff.section_by_name(".bss")
+            .expect("Could not find .bss section")
+            .size()
+    };
+
+    let linked = link_yaml!("commons.yaml", LinkerTargetArch::Amd64);
+    let renamed = setup_linker!("commons.yaml", LinkerTargetArch::Amd64)
+        .rename_symbols(SymbolRenameMode::Short)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    assert_eq!(
+        bss_size(&renamed),
+        bss_size(&linked),
+        "COMMON symbols should only be allocated once when renaming symbols"
+    );
+}
+
 #[test]
 fn merged_bss_data() {
     let linked = setup_linker!("merged.yaml", LinkerTargetArch::Amd64)
@@ -94,3 +117,31 @@ fn merged_bss_data() {
         ".data section should have 32 bytes of initialized data"
     );
 }
+
+#[test]
+fn cloned_builder() {
+    let template = setup_linker!("merged.yaml", LinkerTargetArch::Amd64);
+
+    let linked = template
+        .clone()
+        .merge_bss(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        parsed.section_by_name(".bss").is_none_or(|section| section
+            .coff_section()
+            .size_of_raw_data
+            .get(object::LittleEndian)
+            == 0),
+        "Output COFF should have an empty .bss section or none at all"
+    );
+
+    let linked = template.build().link().expect("Could not link files");
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        parsed.section_by_name(".bss").is_some(),
+        "Template builder should not merge the .bss section"
+    );
+}
@@ -1,7 +1,13 @@
-use boflink::linker::LinkerTargetArch;
-use object::{Object, ObjectSection, coff::CoffFile, pe::IMAGE_SCN_LNK_COMDAT};
+use boflink::{
+    graph::{LinkGraphAddError, SymbolErrorKind, SymbolRenameMode},
+    linker::{
+        LinkerTargetArch,
+        error::{LinkError, LinkerPathErrorKind, LinkerSetupError},
+    },
+};
+use object::{Object, ObjectSection, ObjectSymbol, coff::CoffFile, pe::IMAGE_SCN_LNK_COMDAT};
 
-use crate::link_yaml;
+use crate::{link_yaml, setup_linker};
 
 #[test]
 fn any() {
@@ -74,3 +80,245 @@ fn associative() {
         ".assoc section data should be all zeros"
     );
 }
+
+#[test]
+fn associative_cycle() {
+    let mut linker = setup_linker!("associative_cycle.yaml", LinkerTargetArch::Amd64).build();
+
+    let err = linker
+        .link()
+        .expect_err("Linking COMDAT sections with cyclic associations should fail");
+
+    let LinkError::Setup(setup_errors) = err else {
+        panic!("Expected a setup error, got: {err}");
+    };
+
+    assert!(
+        setup_errors.errors().iter().any(|error| matches!(
+            error,
+            LinkerSetupError::Path(path_error)
+                if matches!(
+                    &path_error.error,
+                    LinkerPathErrorKind::GraphAdd(LinkGraphAddError::ComdatAssociativeCycle { .. })
+                )
+        )),
+        "Expected a COMDAT associative cycle error"
+    );
+}
+
+#[test]
+fn exact_match_relocations() {
+    let mut linker = setup_linker!("exact_match_relocations.yaml", LinkerTargetArch::Amd64).build();
+
+    let err = linker
+        .link()
+        .expect_err("Linking exact match COMDATs with different relocations should fail");
+
+    let LinkError::Symbol(symbol_errors) = err else {
+        panic!("Expected a symbol error, got: {err}");
+    };
+
+    assert!(
+        symbol_errors
+            .errors()
+            .iter()
+            .any(|error| error.contains("multiply defined symbol: inline_func")),
+        "Expected inline_func to be multiply defined"
+    );
+
+    assert!(
+        symbol_errors
+            .symbols()
+            .any(|symbol| symbol == (SymbolErrorKind::MultiplyDefined, "inline_func")),
+        "Expected the multiply defined error kind for inline_func"
+    );
+
+    let diagnostic = symbol_errors
+        .diagnostics()
+        .iter()
+        .find(|diagnostic| diagnostic.symbol() == "inline_func")
+        .expect("Expected a diagnostic for inline_func");
+
+    assert_eq!(diagnostic.message(), "multiply defined symbol: inline_func");
+    assert_eq!(diagnostic.notes(), ["defined at file1", "defined at file2"]);
+}
+
+#[test]
+fn largest_redirect() {
+    let linked = link_yaml!("largest_redirect.yaml", LinkerTargetArch::Amd64);
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let rdata_section = coff
+        .section_by_name(".rdata")
+        .expect("Could not find .rdata section");
+
+    assert_eq!(
+        rdata_section.size(),
+        16,
+        "The largest COMDAT section should have been kept"
+    );
+
+    let data_section = coff
+        .section_by_name(".data")
+        .expect("Could not find .data section");
+
+    let (_, reloc) = data_section
+        .relocations()
+        .next()
+        .expect("Could not find .data relocation");
+
+    let object::RelocationTarget::Symbol(target_index) = reloc.target() else {
+        panic!(".data relocation should target a symbol");
+    };
+
+    let target_symbol = coff
+        .symbol_by_index(target_index)
+        .expect("Could not find .data relocation target symbol");
+
+    assert_eq!(target_symbol.name(), Ok("local_data"));
+
+    assert_eq!(
+        target_symbol.section_index(),
+        Some(rdata_section.index()),
+        "local_data should be defined in the .rdata section"
+    );
+
+    assert_eq!(
+        target_symbol.address(),
+        4,
+        "local_data should be redirected to the same offset in the kept COMDAT section"
+    );
+}
+
+#[test]
+fn largest_redirect_renamed() {
+    let linked = setup_linker!("largest_redirect.yaml", LinkerTargetArch::Amd64)
+        .rename_symbols(SymbolRenameMode::Short)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let rdata_section = coff
+        .section_by_name(".rdata")
+        .expect("Could not find .rdata section");
+
+    assert_eq!(
+        rdata_section.size(),
+        16,
+        "The largest COMDAT section should have been kept"
+    );
+
+    let data_section = coff
+        .section_by_name(".data")
+        .expect("Could not find .data section");
+
+    let (_, reloc) = data_section
+        .relocations()
+        .next()
+        .expect("Could not find .data relocation");
+
+    let object::RelocationTarget::Symbol(target_index) = reloc.target() else {
+        panic!(".data relocation should target a symbol");
+    };
+
+    let target_symbol = coff
+        .symbol_by_index(target_index)
+        .expect("Could not find .data relocation target symbol");
+
+    assert_eq!(
+        target_symbol.section_index(),
+        Some(rdata_section.index()),
+        "The relocation target should be redirected to the kept COMDAT section"
+    );
+}
+
+#[test]
+fn keep_section() {
+    let linked = link_yaml!("keep_section.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        coff.section_by_name(".res").is_none(),
+        "The associative section of the discarded COMDAT should not be in the output"
+    );
+
+    let linked = setup_linker!("keep_section.yaml", LinkerTargetArch::Amd64)
+        .keep_section(".res$*")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let res_section = coff
+        .section_by_name(".res")
+        .expect("Could not find .res section");
+
+    assert_eq!(
+        res_section.data().expect("Could not get .res section data"),
+        b"res\0\0\0\0\0",
+        "The COMDAT copy with the kept associative section should have been kept"
+    );
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+
+    let shared = coff
+        .symbol_by_name("shared")
+        .expect("Could not find shared symbol");
+
+    assert_eq!(shared.section_index(), Some(text_section.index()));
+    assert_eq!(
+        text_section.size(),
+        17,
+        "Only one copy of the COMDAT should be in the output"
+    );
+}
+
+#[test]
+fn noduplicates_same_coff() {
+    let linked = link_yaml!("noduplicates_same_coff.yaml", LinkerTargetArch::Amd64);
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let guard_symbols = coff
+        .symbols()
+        .filter(|symbol| symbol.name() == Ok("guard_table"))
+        .count();
+    assert_eq!(guard_symbols, 1, "guard_table should be defined once");
+
+    let rdata_section = coff
+        .section_by_name(".rdata")
+        .expect("Could not find .rdata section");
+
+    assert_eq!(
+        rdata_section.size(),
+        8,
+        ".rdata should only contain the COMDAT section"
+    );
+}
+
+#[test]
+fn noduplicates_cross_coff() {
+    let mut linker = setup_linker!("noduplicates_cross.yaml", LinkerTargetArch::Amd64).build();
+
+    let err = linker
+        .link()
+        .expect_err("Linking NODUPLICATES COMDATs from different COFFs should fail");
+
+    let LinkError::Symbol(symbol_errors) = err else {
+        panic!("Expected a symbol error, got: {err}");
+    };
+
+    let diagnostic = symbol_errors
+        .diagnostics()
+        .iter()
+        .find(|diagnostic| diagnostic.symbol() == "guard_table")
+        .expect("Expected a diagnostic for guard_table");
+
+    assert_eq!(diagnostic.kind(), SymbolErrorKind::MultiplyDefined);
+    assert_eq!(diagnostic.notes(), ["defined at file1", "defined at file2"]);
+}
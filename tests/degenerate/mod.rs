@@ -0,0 +1,203 @@
+use boflink::{
+    linker::{LinkerBuilder, LinkerTargetArch, detect_architecture, error::LinkError},
+    pathed_item::PathedItem,
+};
+use object::{Object, coff::CoffFile};
+
+use boflink_testutils::{archive_searcher::MemoryArchiveSearcher, build::YamlInput};
+
+use crate::{link_yaml, setup_linker};
+
+/// Links the COFF YAML documents while detecting the target architecture
+/// from the inputs.
+fn link_detected(input: &str) -> Result<Vec<u8>, LinkError> {
+    let coffs = serde_yml::Deserializer::from_str(input)
+        .enumerate()
+        .map(|(idx, document)| {
+            let YamlInput::Coff(coff) = serde::Deserialize::deserialize(document).unwrap() else {
+                panic!("input should be a COFF");
+            };
+
+            PathedItem::new(format!("file{}", idx + 1).into(), coff.build().unwrap())
+        });
+
+    LinkerBuilder::new()
+        .library_searcher(MemoryArchiveSearcher::new())
+        .add_inputs(coffs)
+        .build()
+        .link()
+}
+
+#[test]
+fn no_sections() {
+    let linked = link_yaml!("no_sections.yaml", LinkerTargetArch::Amd64);
+
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        parsed.symbol_by_name("go").is_some(),
+        "Could not find go symbol in linked COFF"
+    );
+}
+
+#[test]
+fn drectve_only() {
+    let linked = link_yaml!("drectve_only.yaml", LinkerTargetArch::Amd64);
+
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        parsed.section_by_name(".drectve").is_none(),
+        ".drectve section should be discarded"
+    );
+}
+
+#[test]
+fn unknown_machine() {
+    let linked = link_yaml!("unknown_machine.yaml", LinkerTargetArch::Amd64);
+
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        parsed.symbol_by_name("go").is_some(),
+        "Could not find go symbol in linked COFF"
+    );
+}
+
+#[test]
+fn unsupported_machine() {
+    // Machines which are not supported are not treated as machine-less COFFs
+    let err = setup_linker!("unsupported_machine.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("Linking a COFF for an unsupported machine should fail");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("invalid architecture"),
+        "unexpected error: {message}"
+    );
+}
+
+#[test]
+fn unknown_machine_detected() {
+    const INPUT: &str = include_str!("unknown_machine.yaml");
+    let linked = link_detected(INPUT).expect("Could not link files");
+
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert_eq!(parsed.architecture(), object::Architecture::X86_64);
+}
+
+#[test]
+fn empty_first_detected() {
+    const INPUT: &str = include_str!("i386_empty_first.yaml");
+    let linked = link_detected(INPUT).expect("Could not link files");
+
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert_eq!(parsed.architecture(), object::Architecture::X86_64);
+}
+
+#[test]
+fn only_empty() {
+    const INPUT: &str = include_str!("only_empty.yaml");
+    let err = link_detected(INPUT).expect_err("Link should fail");
+    assert!(
+        matches!(err, LinkError::ArchitectureDetect),
+        "Expected an architecture detection error, got {err}"
+    );
+}
+
+#[test]
+fn only_empty_with_architecture() {
+    let linked = link_yaml!("only_empty.yaml", LinkerTargetArch::I386);
+
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert_eq!(parsed.architecture(), object::Architecture::I386);
+}
+
+#[test]
+fn conflicting_machines() {
+    const INPUT: &str = include_str!("conflicting_machines.yaml");
+    let err = link_detected(INPUT).expect_err("Link should fail");
+
+    let LinkError::ArchitectureConflict(conflict) = err else {
+        panic!("Expected an architecture conflict error, got {err}");
+    };
+
+    assert_eq!(
+        Vec::from_iter(conflict.machines()),
+        [
+            (
+                std::path::Path::new("file1"),
+                object::pe::IMAGE_FILE_MACHINE_AMD64
+            ),
+            (
+                std::path::Path::new("file2"),
+                object::pe::IMAGE_FILE_MACHINE_I386
+            ),
+        ]
+    );
+}
+
+#[test]
+fn detect_public() {
+    const INPUT: &str = include_str!("i386_empty_first.yaml");
+    let coffs = Vec::from_iter(serde_yml::Deserializer::from_str(INPUT).enumerate().map(
+        |(idx, document)| {
+            let YamlInput::Coff(coff) = serde::Deserialize::deserialize(document).unwrap() else {
+                panic!("input should be a COFF");
+            };
+
+            PathedItem::new(format!("file{}", idx + 1), coff.build().unwrap())
+        },
+    ));
+
+    assert!(matches!(
+        detect_architecture(&coffs),
+        Ok(LinkerTargetArch::Amd64)
+    ));
+}
+
+/// Builds a COFF with the number of distinct data sections.
+fn distinct_sections(count: usize) -> Vec<u8> {
+    use coffyaml::coff::{CoffYaml, CoffYamlHeader, CoffYamlSection};
+    use object::pe::{
+        IMAGE_FILE_MACHINE_AMD64, IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_MEM_READ,
+    };
+
+    CoffYaml {
+        header: CoffYamlHeader {
+            machine: IMAGE_FILE_MACHINE_AMD64,
+            characteristics: 0,
+        },
+        sections: Vec::from_iter((0..count).map(|idx| CoffYamlSection {
+            name: format!(".data.{idx}"),
+            characteristics: IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ,
+            alignment: Some(1),
+            section_data: vec![idx as u8].into(),
+            ..Default::default()
+        })),
+        symbols: Vec::new(),
+    }
+    .build()
+    .unwrap()
+}
+
+#[test]
+fn too_many_sections() {
+    let link = |count: usize| {
+        LinkerBuilder::new()
+            .architecture(LinkerTargetArch::Amd64)
+            .library_searcher(MemoryArchiveSearcher::new())
+            .add_input(PathedItem::new("file1".into(), distinct_sections(count)))
+            .build()
+            .link()
+    };
+
+    let linked = link(i16::MAX as usize).expect("Could not link files");
+    let parsed: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert_eq!(parsed.sections().count(), i16::MAX as usize);
+
+    let err = link(i16::MAX as usize + 1).expect_err("Link should fail");
+    assert!(
+        err.to_string().contains("32768 sections"),
+        "unexpected error: {err}"
+    );
+}
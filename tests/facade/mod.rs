@@ -0,0 +1,112 @@
+use boflink::{
+    link::{LinkInput, LinkOptions, link},
+    linker::{LinkerTargetArch, error::LinkError},
+};
+use object::{Object, coff::CoffFile};
+use serde::Deserialize;
+
+use boflink_testutils::build::YamlInput;
+
+/// Builds the input YAML into link options with in-memory inputs and
+/// libraries.
+fn yaml_options(input: &str) -> LinkOptions {
+    let mut options = LinkOptions::default();
+    options.machine = Some(LinkerTargetArch::Amd64);
+
+    for (idx, document) in serde_yml::Deserializer::from_str(input).enumerate() {
+        let name = format!("file{}", idx + 1);
+        match YamlInput::deserialize(document).unwrap() {
+            YamlInput::Coff(coff) => {
+                options
+                    .inputs
+                    .push(LinkInput::memory(name, coff.build().unwrap()));
+            }
+            YamlInput::Importlib(importlib) => {
+                let data = importlib.build(LinkerTargetArch::Amd64.into()).unwrap();
+                options.libraries.push(name.clone());
+                options.library_buffers.push((name, data));
+            }
+            YamlInput::LegacyImportlib(importlib) => {
+                let data = importlib
+                    .build_legacy(LinkerTargetArch::Amd64.into())
+                    .unwrap();
+                options.libraries.push(name.clone());
+                options.library_buffers.push((name, data));
+            }
+            YamlInput::Dll(dll) => {
+                let data = dll.build_dll(LinkerTargetArch::Amd64.into()).unwrap();
+                options.libraries.push(name.clone());
+                options.library_buffers.push((name, data));
+            }
+        }
+    }
+
+    options
+}
+
+#[test]
+fn link_memory_inputs() {
+    let options = yaml_options(include_str!("link.yaml"));
+    let linked = link(options).expect("Could not link files");
+    let coff: CoffFile =
+        CoffFile::parse(linked.output.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(coff.symbol_by_name("go").is_some());
+    assert_eq!(linked.report.library_members().count(), 0);
+}
+
+#[test]
+fn library_buffers() {
+    let options = yaml_options(include_str!("library_buffers.yaml"));
+    let linked = link(options).expect("Could not link files");
+    let coff: CoffFile =
+        CoffFile::parse(linked.output.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(
+        coff.symbol_by_name("__imp_LIBRARY$imported_symbol")
+            .is_some(),
+        "Import from the in-memory library should be in the output"
+    );
+    assert_eq!(linked.report.unused_libraries().count(), 0);
+}
+
+#[test]
+fn missing_input() {
+    let mut options = LinkOptions::default();
+    options.inputs.push(LinkInput::file("does-not-exist.o"));
+
+    let err = link(options).expect_err("Missing input should fail the link");
+    assert!(
+        matches!(err, LinkError::Io { .. }),
+        "Unexpected error: {err}"
+    );
+}
+
+#[test]
+fn drectve_library_path() {
+    let dir = std::env::temp_dir().join(format!("boflink-drectve-libpath-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let drectve = format!("/LIBPATH:\"{}\" /DEFAULTLIB:libpath.lib ", dir.display());
+    let hex = String::from_iter(drectve.bytes().map(|b| format!("{b:02X}")));
+    let input = include_str!("drectve_libpath.yaml").replace("{DRECTVE}", &hex);
+
+    let mut options = yaml_options(&input);
+
+    // Move the import library to the directory in the /LIBPATH directive
+    let (_, data) = options.library_buffers.pop().unwrap();
+    options.libraries.clear();
+    std::fs::write(dir.join("libpath.lib"), data).unwrap();
+
+    let linked = link(options);
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let linked = linked.expect("Could not link files");
+    let coff: CoffFile =
+        CoffFile::parse(linked.output.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        coff.symbol_by_name("__imp_LIBPATH$imported_symbol")
+            .is_some(),
+        "Import from the /LIBPATH library should be in the output"
+    );
+}
@@ -1,6 +1,17 @@
-use crate::link_yaml;
-use boflink::linker::LinkerTargetArch;
-use object::{Object, ObjectSymbol, coff::CoffFile};
+use std::path::Path;
+
+use crate::{link_yaml, setup_linker};
+use boflink::{
+    linker::{
+        LinkPhase, LinkerBuilder, LinkerTargetArch, ProbeResult, ProbeSource, SymbolStatus,
+        reproduce_path,
+    },
+    linkobject::archive::{LinkArchive, SymbolLookupStats, fold_symbol_name},
+    pathed_item::PathedItem,
+};
+use boflink_testutils::{archive_searcher::MemoryArchiveSearcher, build::YamlInput};
+use object::{Object, ObjectSection, ObjectSymbol, coff::CoffFile, read::archive::ArchiveFile};
+use serde::Deserialize;
 
 #[test]
 fn library_prefix() {
@@ -64,3 +75,561 @@ fn import_thunks() {
         "Thunk relocation target does not point to import symbol"
     );
 }
+
+#[test]
+fn data_imports() {
+    let linked = link_yaml!("data_imports.yaml", LinkerTargetArch::Amd64);
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    assert!(
+        parsed.symbol_by_name("__imp_LIBRARY$counter").is_some(),
+        "Could not find symbol '__imp_LIBRARY$counter' in linked output"
+    );
+
+    assert!(
+        parsed.symbol_by_name("counter").is_none(),
+        "data import should not have a thunk"
+    );
+}
+
+#[test]
+fn direct_data_import() {
+    let err = setup_linker!("direct_data_import.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("Referencing imported data directly should fail");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("symbol 'limit' is imported as data from 'LIBRARY'")
+            && message.contains("'__imp_limit'")
+            && message.contains(">>> referenced by file1:(.text+0x3)"),
+        "unexpected error: {message}"
+    );
+}
+
+#[test]
+fn legacy_imports() {
+    let linked = link_yaml!("legacy_imports.yaml", LinkerTargetArch::Amd64);
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    for symbol in [
+        "__imp_KERNEL32$GetLastError",
+        "__imp_KERNEL32$Sleep@4",
+        "__imp_KERNEL32$DataExport",
+    ] {
+        assert!(
+            parsed.symbol_by_name(symbol).is_some(),
+            "Could not find symbol '{symbol}' in linked output"
+        );
+    }
+}
+
+#[test]
+fn dll_imports() {
+    let linked = link_yaml!("dll_imports.yaml", LinkerTargetArch::Amd64);
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    let thunk_symbol = parsed
+        .symbol_by_name("import")
+        .expect("Could not find symbol 'import'");
+
+    assert!(
+        thunk_symbol.is_definition(),
+        "thunk symbol should be defined"
+    );
+
+    assert!(
+        parsed.symbol_by_name("__imp_LIBRARY$import").is_some(),
+        "Could not find symbol '__imp_LIBRARY$import' in linked output"
+    );
+}
+
+#[test]
+fn dll_data_import() {
+    let err = setup_linker!("dll_data_import.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("Referencing data exported from a DLL directly should fail");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("symbol 'counter' is imported as data from 'LIBRARY.dll'"),
+        "unexpected error: {message}"
+    );
+}
+
+#[test]
+fn module_definition() {
+    const DEF: &str = "LIBRARY LIBRARY.dll\nEXPORTS\n    import @1\n";
+
+    let linked = setup_linker!("module_definition.yaml", LinkerTargetArch::Amd64)
+        .add_module_definition(PathedItem::new(
+            "library.def".into(),
+            DEF.as_bytes().to_vec(),
+        ))
+        .build()
+        .link()
+        .expect("Could not link files");
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    assert!(
+        parsed
+            .symbol_by_name("import")
+            .is_some_and(|symbol| symbol.is_definition()),
+        "thunk symbol should be defined"
+    );
+
+    assert!(
+        parsed.symbol_by_name("__imp_LIBRARY$import").is_some(),
+        "Could not find symbol '__imp_LIBRARY$import' in linked output"
+    );
+}
+
+#[test]
+fn delay_load() {
+    let linked = setup_linker!("delay_load.yaml", LinkerTargetArch::Amd64)
+        .delay_load("library")
+        .build()
+        .link()
+        .expect("Could not link files");
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    assert!(
+        parsed.symbol_by_name("__imp_LIBRARY$import").is_none()
+            && parsed.symbol_by_name("__imp_LIBRARY$other").is_none(),
+        "delay loaded imports should not have __imp_ symbols"
+    );
+
+    assert!(
+        parsed.symbol_by_name("__imp_KEPT$kept").is_some(),
+        "Could not find symbol '__imp_KEPT$kept' in linked output"
+    );
+
+    let data_section = parsed
+        .section_by_name(".data")
+        .expect("Could not find .data section");
+
+    let table_symbol = parsed
+        .symbol_by_name("__delay_import_table")
+        .expect("Could not find symbol '__delay_import_table'");
+    assert_eq!(table_symbol.section_index(), Some(data_section.index()));
+
+    // The slot for the __imp_ symbol is in the second entry
+    let slot_symbol = parsed
+        .symbol_by_name("__imp_other")
+        .expect("Could not find symbol '__imp_other'");
+    assert_eq!(slot_symbol.section_index(), Some(data_section.index()));
+    assert_eq!(slot_symbol.address(), table_symbol.address() + 24 + 16);
+
+    let thunk_symbol = parsed
+        .symbol_by_name("import")
+        .expect("Could not find symbol 'import'");
+    assert!(
+        thunk_symbol.is_definition(),
+        "thunk symbol should be defined"
+    );
+
+    // Two entries with a relocation for the DLL name and import name each
+    assert_eq!(
+        data_section.relocations().count(),
+        4,
+        "delay import table should have a relocation for each name"
+    );
+
+    let rdata_section = parsed
+        .section_by_name(".rdata")
+        .expect("Could not find .rdata section");
+    assert_eq!(
+        rdata_section.data().unwrap(),
+        b"LIBRARY.dll\0import\0other\0"
+    );
+}
+
+/// Sets up a linker where the import member for 'Broken' in the first import
+/// library is corrupted.
+fn setup_corrupt_linker() -> LinkerBuilder<MemoryArchiveSearcher> {
+    let mut searcher = MemoryArchiveSearcher::new();
+    let mut coffs = Vec::new();
+    let mut libraries = Vec::new();
+
+    for document in serde_yml::Deserializer::from_str(include_str!("tolerant.yaml")) {
+        match YamlInput::deserialize(document).unwrap() {
+            YamlInput::Coff(coff) => {
+                coffs.push(PathedItem::new("file1".into(), coff.build().unwrap()));
+            }
+            YamlInput::Importlib(importlib) => {
+                let name = importlib.library.clone();
+                let mut built = importlib.build(LinkerTargetArch::Amd64.into()).unwrap();
+
+                if libraries.is_empty() {
+                    let archive = ArchiveFile::parse(built.as_slice()).unwrap();
+                    let symbol = archive
+                        .symbols()
+                        .unwrap()
+                        .unwrap()
+                        .map(|symbol| symbol.unwrap())
+                        .find(|symbol| symbol.name() == b"__imp_Broken")
+                        .unwrap();
+                    let (offset, _) = archive.member(symbol.offset()).unwrap().file_range();
+
+                    // Set the size of the import data past the end of the member
+                    let size_offset = offset as usize + 12;
+                    built[size_offset..size_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+                }
+
+                searcher.add_library(name.clone(), built);
+                libraries.push(name);
+            }
+            YamlInput::LegacyImportlib(_) | YamlInput::Dll(_) => unreachable!(),
+        }
+    }
+
+    LinkerBuilder::new()
+        .architecture(LinkerTargetArch::Amd64)
+        .library_searcher(searcher)
+        .add_inputs(coffs)
+        .add_libraries(libraries)
+}
+
+#[test]
+fn corrupt_member_error() {
+    let mut linker = setup_corrupt_linker().build();
+    assert!(
+        linker.link().is_err(),
+        "Linking with a corrupt archive member should fail"
+    );
+}
+
+#[test]
+fn tolerant_corrupt_member() {
+    let mut linker = setup_corrupt_linker().tolerant(true).build();
+
+    let linked = linker.link().expect("Could not link files");
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    assert!(
+        parsed.symbol_by_name("__imp_VALID$Broken").is_some(),
+        "Symbol should have been resolved from the valid import library"
+    );
+
+    let skipped = linker.report().skipped_members().collect::<Vec<_>>();
+    assert_eq!(skipped.len(), 1, "Corrupt member should have been skipped");
+    assert_eq!(skipped[0].0, Path::new("CORRUPT.dll"));
+}
+
+#[test]
+fn discarded_imports() {
+    let linked = link_yaml!("discarded_import.yaml", LinkerTargetArch::Amd64);
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    for symbol in [
+        "__imp_LIBRARY$DiscardedImport",
+        "__imp_BeaconPrintf",
+        "BeaconPrintf",
+    ] {
+        assert!(
+            parsed.symbol_by_name(symbol).is_none(),
+            "Import '{symbol}' only referenced from a discarded section should be removed"
+        );
+    }
+}
+
+#[test]
+fn discarded_thunks() {
+    let linked = link_yaml!("discarded_thunks.yaml", LinkerTargetArch::Amd64);
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    let text_section = parsed
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+
+    // Only the go section and the kept COMDAT section at the default 16 byte
+    // .text alignment
+    assert_eq!(
+        text_section.size(),
+        17,
+        "Import thunks only called from a discarded section should be removed"
+    );
+
+    for symbol in [
+        "ThunkedImport",
+        "__imp_LIBRARY$ThunkedImport",
+        "ExistingThunkImport",
+        "__imp_LIBRARY$ExistingThunkImport",
+    ] {
+        assert!(
+            parsed.symbol_by_name(symbol).is_none(),
+            "Import '{symbol}' only called from a discarded section should be removed"
+        );
+    }
+}
+
+#[test]
+fn existing_thunk() {
+    let linked = link_yaml!("existing_thunk.yaml", LinkerTargetArch::Amd64);
+    let parsed: CoffFile =
+        CoffFile::parse(linked.as_slice()).expect("Could not parse linked output");
+
+    let text_section = parsed
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+
+    // The existing thunk is placed at the default 16 byte .text alignment
+    assert_eq!(
+        text_section.size(),
+        24,
+        "An import thunk should not be added for a symbol with an existing thunk"
+    );
+
+    let thunk_symbol = parsed
+        .symbol_by_name("import")
+        .expect("Could not find symbol 'import'");
+
+    assert!(
+        thunk_symbol.is_definition(),
+        "thunk symbol should be defined"
+    );
+
+    assert_eq!(
+        thunk_symbol.address(),
+        16,
+        "thunk symbol should be defined at the existing thunk"
+    );
+
+    let thunk_relocs = text_section
+        .coff_relocations()
+        .expect("Could not get .text relocations");
+
+    assert_eq!(
+        thunk_relocs.len(),
+        1,
+        ".text section should only have the existing thunk relocation"
+    );
+}
+
+#[test]
+fn unused_library() {
+    let mut linker = setup_linker!("unused_library.yaml", LinkerTargetArch::Amd64).build();
+    linker.link().expect("Could not link files");
+
+    let library_members = linker.report().library_members().collect::<Vec<_>>();
+    assert_eq!(
+        library_members,
+        [(Path::new("file2"), 1), (Path::new("file3"), 0)],
+        "Unexpected number of members extracted from the link libraries"
+    );
+
+    let unused = linker.report().unused_libraries().collect::<Vec<_>>();
+    assert_eq!(unused, [Path::new("file3")]);
+}
+
+#[test]
+fn phase_times() {
+    let mut linker = setup_linker!("unused_library.yaml", LinkerTargetArch::Amd64).build();
+    linker.link().expect("Could not link files");
+
+    let phases = linker
+        .report()
+        .phase_times()
+        .map(|(phase, _)| phase)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        phases,
+        [
+            LinkPhase::Parse,
+            LinkPhase::LibrarySearch,
+            LinkPhase::SymbolResolution,
+            LinkPhase::GraphFinish,
+            LinkPhase::Layout,
+            LinkPhase::Write,
+        ]
+    );
+
+    let total = linker.report().phase_times().map(|(_, time)| time).sum();
+    assert_eq!(linker.report().total_time(), total);
+
+    let libraries = linker
+        .report()
+        .library_times()
+        .map(|(library, _)| library)
+        .collect::<Vec<_>>();
+    assert_eq!(libraries, [Path::new("file2"), Path::new("file3")]);
+}
+
+#[test]
+fn cross_references() {
+    let mut linker = setup_linker!("unused_library.yaml", LinkerTargetArch::Amd64)
+        .cross_reference(true)
+        .build();
+    linker.link().expect("Could not link files");
+
+    let cref = linker
+        .report()
+        .cross_references()
+        .iter()
+        .find(|cref| cref.symbol == "import")
+        .expect("Could not find cross reference for 'import'");
+
+    assert_eq!(cref.definitions, ["LIBRARY (import)"]);
+    assert_eq!(cref.references, ["file1"]);
+}
+
+#[test]
+fn reproduce() {
+    let archive_path =
+        std::env::temp_dir().join(format!("boflink-repro-{}.tar", std::process::id()));
+
+    let mut linker = setup_linker!("unused_library.yaml", LinkerTargetArch::Amd64)
+        .reproduce(&archive_path, "file1\n-lfile2\n-lfile3\n")
+        .add_reproduce_file("symbols.map", "import\n")
+        .build();
+    linker.link().expect("Could not link files");
+
+    let archive = std::fs::read(&archive_path).expect("Could not read reproduce archive");
+    let _ = std::fs::remove_file(&archive_path);
+
+    assert_eq!(archive.len() % 512, 0);
+
+    let base = archive_path.file_stem().unwrap().to_string_lossy();
+    for name in ["response.txt", "version.txt"]
+        .map(String::from)
+        .into_iter()
+        .chain(["file1", "file2", "file3", "symbols.map"].map(reproduce_path))
+    {
+        let name = format!("{base}/{name}");
+        assert!(
+            archive
+                .windows(name.len())
+                .any(|window| window == name.as_bytes()),
+            "reproduce archive should contain {name}"
+        );
+    }
+}
+
+#[test]
+fn archive_lookup_stats() {
+    let mut documents = serde_yml::Deserializer::from_str(include_str!("unused_library.yaml"));
+    let YamlInput::Importlib(importlib) =
+        YamlInput::deserialize(documents.nth(1).unwrap()).unwrap()
+    else {
+        panic!("second document should be an import library");
+    };
+
+    let built = importlib.build(LinkerTargetArch::Amd64.into()).unwrap();
+    let symbol_count = ArchiveFile::parse(built.as_slice())
+        .unwrap()
+        .symbols()
+        .unwrap()
+        .unwrap()
+        .count() as u32;
+
+    let archive = LinkArchive::parse(built.as_slice()).expect("Could not parse archive");
+    assert!(archive.extract_symbol("missing").is_err());
+    assert!(archive.extract_symbol("missing").is_err());
+    assert!(archive.extract_symbol("__imp_import").is_ok());
+
+    // The first miss scans the symbol table and later lookups use the index
+    assert_eq!(
+        archive.lookup_stats(),
+        SymbolLookupStats {
+            lookups: 3,
+            cache_hits: 1,
+            misses: 2,
+            scanned: symbol_count,
+            eager: false,
+        }
+    );
+
+    let archive = LinkArchive::parse(built.as_slice()).expect("Could not parse archive");
+    archive.index_symbols();
+    assert!(archive.extract_symbol("__imp_import").is_ok());
+
+    assert_eq!(
+        archive.lookup_stats(),
+        SymbolLookupStats {
+            lookups: 1,
+            cache_hits: 1,
+            misses: 0,
+            scanned: symbol_count,
+            eager: true,
+        }
+    );
+}
+
+#[test]
+fn archive_folded_lookup() {
+    assert_eq!(fold_symbol_name("_MessageBoxA@16"), "messageboxa");
+    assert_eq!(
+        fold_symbol_name("__imp__MessageBoxA@16"),
+        "__imp_messageboxa"
+    );
+
+    let mut documents = serde_yml::Deserializer::from_str(include_str!("unused_library.yaml"));
+    let YamlInput::Importlib(importlib) =
+        YamlInput::deserialize(documents.nth(1).unwrap()).unwrap()
+    else {
+        panic!("second document should be an import library");
+    };
+
+    let built = importlib.build(LinkerTargetArch::Amd64.into()).unwrap();
+    let archive = LinkArchive::parse(built.as_slice()).expect("Could not parse archive");
+
+    assert_eq!(archive.find_folded("_IMPORT@8"), ["import"]);
+    assert_eq!(archive.find_folded("__imp_Import"), ["__imp_import"]);
+    assert!(archive.find_folded("missing").is_empty());
+
+    // Folded lookups do not scan the symbol table for exact lookups
+    assert_eq!(archive.lookup_stats(), SymbolLookupStats::default());
+}
+
+#[test]
+fn why_undefined() {
+    let mut linker = setup_linker!("why_undefined.yaml", LinkerTargetArch::Amd64)
+        .why_undefined("IMPORT")
+        .why_undefined("import")
+        .why_undefined("missing")
+        .build();
+    linker
+        .link()
+        .expect_err("Linking should fail with an undefined symbol");
+
+    let [undefined, resolved, missing] = linker.report().symbol_queries() else {
+        panic!("expected a query for each symbol");
+    };
+
+    assert_eq!(undefined.status, SymbolStatus::Undefined);
+    let library_probe = undefined
+        .probes
+        .iter()
+        .find(|probe| probe.source == ProbeSource::Library)
+        .expect("link library should be searched");
+    assert_eq!(library_probe.result, ProbeResult::NotFound);
+    assert_eq!(
+        undefined.candidates,
+        [(library_probe.path.clone(), "import".to_string())]
+    );
+    assert_eq!(undefined.references, ["file1"]);
+
+    assert_eq!(resolved.status, SymbolStatus::Resolved);
+    assert!(
+        resolved
+            .probes
+            .last()
+            .is_some_and(|probe| matches!(probe.result, ProbeResult::Found(Some(_)))),
+        "import should be found in an archive member: {:?}",
+        resolved.probes
+    );
+    assert!(resolved.candidates.is_empty());
+
+    assert_eq!(missing.status, SymbolStatus::Unreferenced);
+    assert!(missing.probes.is_empty());
+}
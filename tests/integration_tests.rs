@@ -1,6 +1,14 @@
+use boflink_testutils::{link_yaml, setup_linker};
+
+mod api;
 mod bss;
 mod comdats;
 mod compilers;
+mod degenerate;
+mod facade;
 mod imports;
 mod relocations;
-mod utils;
+mod scenarios;
+mod sections;
+mod snapshots;
+mod symbols;
@@ -1,7 +1,14 @@
-use boflink::linker::LinkerTargetArch;
-use object::{Object, ObjectSection, ObjectSymbol, coff::CoffFile};
+use boflink::{graph::RelocationIssue, linker::LinkerTargetArch};
+use object::{
+    Object, ObjectSection, ObjectSymbol, RelocationTarget,
+    coff::CoffFile,
+    pe::{
+        IMAGE_REL_AMD64_ADDR32NB, IMAGE_REL_AMD64_ADDR64, IMAGE_REL_AMD64_REL32,
+        IMAGE_REL_AMD64_SECREL, IMAGE_REL_AMD64_SECTION,
+    },
+};
 
-use crate::link_yaml;
+use crate::{link_yaml, setup_linker};
 
 #[test]
 fn same_section_flattened() {
@@ -48,6 +55,84 @@ fn same_section_flattened() {
     );
 }
 
+#[test]
+fn same_section_not_flattened() {
+    let linked = setup_linker!("same_section_flattened.yaml", LinkerTargetArch::Amd64)
+        .flatten_relocations(false)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section in linked COFF");
+
+    let target_symbol = coff
+        .symbol_by_name("external_function")
+        .expect("Could not get external_function symbol");
+
+    let relocs = Vec::from_iter(text_section.relocations());
+    assert_eq!(relocs.len(), 1, ".text section should keep the relocation");
+
+    let (address, reloc) = &relocs[0];
+    assert_eq!(*address, 2);
+    assert_eq!(
+        reloc.target(),
+        RelocationTarget::Symbol(target_symbol.index())
+    );
+
+    let section_data = text_section
+        .data()
+        .expect("Could not get .text section data");
+
+    assert_eq!(
+        section_data[2..6],
+        [0, 0, 0, 0],
+        "Relocation addend should be left unchanged"
+    );
+}
+
+#[test]
+fn same_section_backward() {
+    let linked = link_yaml!("same_section_backward.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section in linked COFF");
+
+    let target_symbol = coff
+        .symbol_by_name("backward_function")
+        .expect("Could not get backward_function symbol");
+
+    let relocs = Vec::from_iter(text_section.relocations());
+    assert_eq!(
+        relocs.len(),
+        1,
+        ".text section should only keep the ADDR64 relocation"
+    );
+
+    let (address, reloc) = &relocs[0];
+    assert_eq!(*address, 0x18);
+    assert_eq!(
+        reloc.target(),
+        RelocationTarget::Symbol(target_symbol.index())
+    );
+
+    let section_data = text_section
+        .data()
+        .expect("Could not get .text section data");
+
+    let found_reloc_val = i32::from_le_bytes(section_data[0x11..0x15].try_into().unwrap());
+    assert_eq!(
+        found_reloc_val,
+        4 - 0x15,
+        "Backward relocation should be applied with the little endian addend"
+    );
+}
+
 #[test]
 fn section_target_shifted() {
     let linked = link_yaml!("section_target_shifted.yaml", LinkerTargetArch::Amd64);
@@ -82,6 +167,72 @@ fn section_target_shifted() {
     );
 }
 
+#[test]
+fn section_relative_targets() {
+    let linked = link_yaml!("secrel.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let data_section = coff
+        .section_by_name(".data")
+        .expect("Could not find .data section in linked COFF");
+
+    let section_symbol = coff
+        .symbol_by_name(".data")
+        .expect("Could not get .data section symbol");
+
+    let relocs = Vec::from_iter(
+        data_section
+            .coff_relocations()
+            .expect("Could not get .data section relocations")
+            .iter()
+            .map(|reloc| {
+                (
+                    reloc.virtual_address.get(object::LittleEndian),
+                    reloc.typ.get(object::LittleEndian),
+                    reloc.symbol(),
+                )
+            }),
+    );
+
+    assert_eq!(
+        relocs,
+        [
+            (16, IMAGE_REL_AMD64_SECREL, section_symbol.index()),
+            (30, IMAGE_REL_AMD64_SECTION, section_symbol.index()),
+        ]
+    );
+
+    let section_data = data_section
+        .data()
+        .expect("Could not get .data section data");
+
+    assert_eq!(
+        u32::from_le_bytes(section_data[16..20].try_into().unwrap()),
+        0x14,
+        "Section offset should be shifted with the section"
+    );
+
+    assert_eq!(
+        section_data[28..32],
+        [0xaa, 0xaa, 0x00, 0x00],
+        "Section index should not be shifted"
+    );
+}
+
+#[test]
+fn section_relative_overflow() {
+    let err = setup_linker!("secrel_overflow.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("Linking an overflowing relocation should fail");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("file2: relocation adjustment at '.data+0x4' overflowed"),
+        "unexpected error: {message}"
+    );
+}
+
 #[test]
 fn defined_symbol_target_no_shift() {
     let linked = link_yaml!(
@@ -131,3 +282,330 @@ fn defined_symbol_target_no_shift() {
         "Relocation value should not have shifted"
     );
 }
+
+#[test]
+fn relocation_counts() {
+    let mut linker = setup_linker!("reloc_stats.yaml", LinkerTargetArch::Amd64).build();
+    linker.link().expect("Could not link files");
+
+    let counts = linker
+        .report()
+        .relocation_counts()
+        .iter()
+        .map(|count| (count.section.as_str(), count.type_name, count.count))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        counts,
+        [
+            (".text", "IMAGE_REL_AMD64_REL32", 2),
+            (".rdata", "IMAGE_REL_AMD64_ADDR64", 1),
+            (".rdata", "IMAGE_REL_AMD64_ADDR32NB", 1),
+        ]
+    );
+
+    let totals = linker.report().relocation_totals().collect::<Vec<_>>();
+    assert_eq!(
+        totals,
+        [
+            (IMAGE_REL_AMD64_ADDR64, "IMAGE_REL_AMD64_ADDR64", 1),
+            (IMAGE_REL_AMD64_ADDR32NB, "IMAGE_REL_AMD64_ADDR32NB", 1),
+            (IMAGE_REL_AMD64_REL32, "IMAGE_REL_AMD64_REL32", 2),
+        ]
+    );
+}
+
+#[test]
+fn checked_relocations() {
+    let mut linker = setup_linker!("check_relocs.yaml", LinkerTargetArch::Amd64)
+        .check_relocations(true)
+        .build();
+    linker.link().expect("Could not link files");
+
+    let checked = linker
+        .report()
+        .checked_relocations()
+        .iter()
+        .map(|reloc| {
+            (
+                reloc.section.as_str(),
+                reloc.address,
+                reloc.symbol.as_str(),
+                reloc.type_name,
+                reloc.issue,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        checked,
+        [
+            (".text", 3, "counter", "IMAGE_REL_AMD64_REL32", None),
+            (
+                ".text",
+                10,
+                "counter",
+                "IMAGE_REL_AMD64_REL32",
+                Some(RelocationIssue::AddendOutOfBounds { addend: 0x100 })
+            ),
+            (
+                ".text",
+                17,
+                "counter",
+                "IMAGE_REL_AMD64_ADDR32",
+                Some(RelocationIssue::Absolute32)
+            ),
+        ]
+    );
+
+    // Relocations are only reported when checked
+    let mut linker = setup_linker!("check_relocs.yaml", LinkerTargetArch::Amd64).build();
+    linker.link().expect("Could not link files");
+    assert!(linker.report().checked_relocations().is_empty());
+}
+
+/// Links `input` with the `sections` obfuscated and checks that the sections
+/// match the unobfuscated output after deobfuscating them.
+fn check_obfuscated(input: &str, sections: &[&str], flatten: bool) {
+    let plain = setup_linker!(input, LinkerTargetArch::Amd64)
+        .flatten_relocations(flatten)
+        .build()
+        .link()
+        .expect("Could not link files");
+    let plain: CoffFile = CoffFile::parse(plain.as_slice()).expect("Could not parse linked COFF");
+
+    let key = [0x5a, 0xa5, 0x3c];
+    let linked = setup_linker!(input, LinkerTargetArch::Amd64)
+        .flatten_relocations(flatten)
+        .obfuscate_sections(sections.iter().copied())
+        .obfuscation_key(key)
+        .build()
+        .link()
+        .expect("Could not link files");
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let metadata_symbol = coff
+        .symbol_by_name("__obfuscation_metadata")
+        .expect("Could not find __obfuscation_metadata symbol");
+    let metadata_section = coff
+        .section_by_name(".obfmeta")
+        .expect("Could not find .obfmeta section");
+    assert_eq!(
+        metadata_symbol.section_index(),
+        Some(metadata_section.index())
+    );
+
+    let metadata = metadata_section
+        .data()
+        .expect("Could not get .obfmeta section data");
+    let value =
+        |offset: usize| u32::from_le_bytes(metadata[offset..offset + 4].try_into().unwrap());
+
+    assert_eq!(&metadata[..4], b"OBFM");
+    assert_eq!(u16::from_le_bytes(metadata[4..6].try_into().unwrap()), 1);
+    assert_eq!(
+        u16::from_le_bytes(metadata[6..8].try_into().unwrap()) as usize,
+        key.len()
+    );
+    assert_eq!(&metadata[12..12 + key.len()], key);
+
+    let mut deobfuscated = Vec::from_iter(
+        coff.sections()
+            .map(|section| section.data().expect("Could not get section data").to_vec()),
+    );
+
+    let range_count = value(8) as usize;
+    for range in 0..range_count {
+        let range_offset = 16 + range * 12;
+        let section_data = &mut deobfuscated[value(range_offset) as usize - 1];
+        let start = value(range_offset + 4) as usize;
+        let len = value(range_offset + 8) as usize;
+
+        for (idx, byte) in section_data[start..start + len].iter_mut().enumerate() {
+            *byte ^= key[(start + idx) % key.len()];
+        }
+    }
+
+    for name in sections.iter().copied() {
+        let section = coff.section_by_name(name).unwrap();
+        let plain_section = plain.section_by_name(name).unwrap();
+        let plain_data = plain_section.data().unwrap();
+
+        assert_ne!(section.data().unwrap(), plain_data);
+        assert_eq!(
+            deobfuscated[section.index().0 - 1],
+            plain_data,
+            "{name} should match the unobfuscated output after deobfuscating"
+        );
+
+        // The bytes patched by the relocations are not obfuscated
+        let relocations = section.coff_relocations().unwrap();
+        assert!(
+            !relocations.is_empty(),
+            "{name} should have relocations in the output"
+        );
+
+        for reloc in relocations {
+            let address = reloc.virtual_address.get(object::LittleEndian) as usize;
+            let size = if reloc.typ.get(object::LittleEndian) == IMAGE_REL_AMD64_ADDR64 {
+                8
+            } else {
+                4
+            };
+
+            assert_eq!(
+                section.data().unwrap()[address..address + size],
+                plain_data[address..address + size],
+                "{name} relocation at {address:#x} should not be obfuscated"
+            );
+        }
+    }
+}
+
+#[test]
+fn obfuscated_sections() {
+    check_obfuscated(include_str!("reloc_stats.yaml"), &[".text", ".rdata"], true);
+}
+
+#[test]
+fn obfuscated_same_section_address() {
+    // Absolute addresses to the same section are written out and need to be
+    // left intact
+    check_obfuscated(include_str!("obfuscated_rdata.yaml"), &[".rdata"], true);
+}
+
+#[test]
+fn obfuscated_not_flattened() {
+    check_obfuscated(include_str!("reloc_stats.yaml"), &[".text"], false);
+}
+
+#[test]
+fn collapse_refptrs() {
+    let linked = link_yaml!("refptr.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(
+        coff.symbol_by_name(".refptr.counter").is_some(),
+        ".refptr stub should be kept without collapsing"
+    );
+
+    let linked = setup_linker!("refptr.yaml", LinkerTargetArch::Amd64)
+        .collapse_refptrs(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(
+        coff.symbol_by_name(".refptr.counter").is_none(),
+        "unused .refptr stub should be discarded"
+    );
+    assert!(
+        coff.section_by_name(".rdata").is_none(),
+        ".rdata should only have contained the .refptr stub"
+    );
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+
+    let text_data = text_section
+        .data()
+        .expect("Could not get .text section data");
+
+    assert_eq!(
+        text_data[..3],
+        [0x48, 0x8d, 0x05],
+        "load through .refptr should be rewritten to a lea"
+    );
+
+    let counter = coff
+        .symbol_by_name("counter")
+        .expect("Could not find counter symbol");
+
+    let relocs = Vec::from_iter(text_section.relocations());
+    assert_eq!(relocs.len(), 1, ".text should have a single relocation");
+
+    let (address, reloc) = &relocs[0];
+    assert_eq!(*address, 3);
+    assert_eq!(
+        reloc.target(),
+        RelocationTarget::Symbol(counter.index()),
+        "relocation should target counter directly"
+    );
+}
+
+#[test]
+fn rip_relative() {
+    let linked = setup_linker!("rip_relative.yaml", LinkerTargetArch::Amd64)
+        .rip_relative(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+
+    let text_data = text_section
+        .data()
+        .expect("Could not get .text section data");
+
+    // The counter addend is kept and the .data section symbol addend is
+    // shifted to the second .data section
+    assert_eq!(
+        text_data,
+        [
+            0x48, 0x8d, 0x05, 0x04, 0x00, 0x00, 0x00, 0x0f, 0x1f, 0x00, 0x4c, 0x8d, 0x1d, 0x04,
+            0x00, 0x00, 0x00, 0x0f, 0x1f, 0x00, 0xc3
+        ],
+        "movabs instructions should be rewritten to lea"
+    );
+
+    let relocs = Vec::from_iter(
+        text_section
+            .coff_relocations()
+            .expect("Could not get .text relocations")
+            .iter()
+            .map(|reloc| {
+                (
+                    reloc.virtual_address.get(object::LittleEndian),
+                    reloc.typ.get(object::LittleEndian),
+                )
+            }),
+    );
+
+    assert_eq!(
+        relocs,
+        [(3, IMAGE_REL_AMD64_REL32), (13, IMAGE_REL_AMD64_REL32)],
+        "relocations should be RIP-relative"
+    );
+}
+
+#[test]
+fn rip_relative_unrewritable() {
+    for (input, expected) in [
+        (
+            include_str!("rip_relative_unknown.yaml"),
+            "file1: cannot rewrite relocation at '.text+0x1' to be RIP-relative: instruction is not a 'movabs r64, imm64'",
+        ),
+        (
+            include_str!("rip_relative_data.yaml"),
+            "file1: cannot rewrite relocation at '.data+0x0' to be RIP-relative: relocation is not in a code section",
+        ),
+        (
+            include_str!("rip_relative_import.yaml"),
+            "symbol '__declspec(dllimport) GetTickCount' is not defined in the output",
+        ),
+    ] {
+        let err = setup_linker!(input, LinkerTargetArch::Amd64)
+            .rip_relative(true)
+            .build()
+            .link()
+            .expect_err("Linking unrewritable relocations should fail");
+
+        let message = err.to_string();
+        assert!(message.contains(expected), "unexpected error: {message}");
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use boflink::{
+    graph::GraphNode,
+    linker::{LinkerTargetArch, error::LinkError},
+};
+use object::{Object, ObjectSection, ObjectSymbol, coff::CoffFile};
+
+use crate::setup_linker;
+
+#[test]
+fn first_library_definition() {
+    let mut linker = setup_linker!("resolution_order.yaml", LinkerTargetArch::Amd64).build();
+    let linked = linker.link().expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section")
+        .data()
+        .expect("Could not get .text section data");
+
+    let helper = coff
+        .symbol_by_name("helper")
+        .expect("Could not find helper symbol");
+    let helper_start = helper.address() as usize;
+    assert_eq!(
+        &text[helper_start..helper_start + 6],
+        [0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3],
+        "helper should come from the first library which defines it"
+    );
+
+    let api_symbols = Vec::from_iter(linker.report().api_symbols());
+    assert_eq!(api_symbols, [("__imp_ApiCall", Path::new("api"))]);
+}
+
+/// Returns the code of the `helper` function in the linked COFF.
+fn helper_code(linked: &[u8]) -> Vec<u8> {
+    let coff: CoffFile = CoffFile::parse(linked).expect("Could not parse linked COFF");
+
+    let text = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section")
+        .data()
+        .expect("Could not get .text section data");
+
+    let helper = coff
+        .symbol_by_name("helper")
+        .expect("Could not find helper symbol");
+    let helper_start = helper.address() as usize;
+    text[helper_start..helper_start + 6].to_vec()
+}
+
+#[test]
+fn fat_library_member() {
+    let linked = setup_linker!("fat_library.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    assert_eq!(
+        helper_code(&linked),
+        [0xb8, 0x04, 0x00, 0x00, 0x00, 0xc3],
+        "helper should come from the member for the target architecture"
+    );
+}
+
+#[test]
+fn fat_library_unsupported_member() {
+    // Members for machines which are not supported should not be picked over
+    // the member for the target architecture
+    let linked = setup_linker!("fat_library_unsupported.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    assert_eq!(
+        helper_code(&linked),
+        [0xb8, 0x04, 0x00, 0x00, 0x00, 0xc3],
+        "helper should come from the member for the target architecture"
+    );
+}
+
+#[test]
+fn positional_library_order() {
+    let err = setup_linker!("positional_libraries.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("Library before its reference should not resolve the symbol");
+    assert!(
+        matches!(err, LinkError::Symbol(_)),
+        "second_func should be undefined"
+    );
+
+    let linked = setup_linker!("positional_libraries.yaml", LinkerTargetArch::Amd64)
+        .add_library("second")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        coff.symbol_by_name("second_func").is_some(),
+        "second_func should come from the repeated library"
+    );
+}
+
+#[test]
+fn positional_library_group() {
+    let linked = setup_linker!("positional_group.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(
+        coff.symbol_by_name("second_func").is_some(),
+        "second_func should be resolved from the library group"
+    );
+}
+
+#[test]
+fn drectve_library_dependencies() {
+    let mut linker = setup_linker!("library_deps.yaml", LinkerTargetArch::Amd64).build();
+    linker.link().expect("Could not link files");
+
+    let dependencies = Vec::from_iter(linker.report().library_dependencies().iter().map(
+        |dependency| {
+            (
+                dependency.library.as_path(),
+                dependency
+                    .requested_by
+                    .as_ref()
+                    .map(|(file, member)| (file.as_path(), member.as_deref())),
+            )
+        },
+    ));
+
+    assert_eq!(
+        dependencies,
+        [
+            (Path::new("unused"), None),
+            (Path::new("first"), Some((Path::new("file1"), None))),
+            (
+                Path::new("second"),
+                Some((Path::new("first"), Some(Path::new("first.o"))))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn drectve_utf16_libraries() {
+    let mut linker = setup_linker!("drectve_utf16.yaml", LinkerTargetArch::Amd64).build();
+    let linked = linker.link().expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(coff.symbol_by_name("first_func").is_some());
+    assert!(coff.symbol_by_name("second_func").is_some());
+
+    let libraries = Vec::from_iter(
+        linker
+            .report()
+            .library_dependencies()
+            .iter()
+            .map(|dependency| dependency.library.as_path()),
+    );
+    assert_eq!(libraries, [Path::new("first"), Path::new("second")]);
+}
+
+#[test]
+fn inspect_link_graph() {
+    let inspected = Arc::new(Mutex::new(Vec::new()));
+    let recorded = inspected.clone();
+
+    setup_linker!("resolution_order.yaml", LinkerTargetArch::Amd64)
+        .inspect_graph(move |graph| {
+            let mut recorded = recorded.lock().unwrap();
+            for node in graph.nodes() {
+                match node {
+                    GraphNode::Section(section) => {
+                        for reloc in section.relocations().iter() {
+                            recorded.push(format!(
+                                "{}({}) -> {}",
+                                section.name(),
+                                section.coff().short_name(),
+                                reloc.target().name()
+                            ));
+                        }
+                    }
+                    GraphNode::Symbol(_) => (),
+                    GraphNode::Library(library) => {
+                        for import in library.imports().iter() {
+                            recorded.push(format!(
+                                "{} -> {}",
+                                import.source().name(),
+                                library.name()
+                            ));
+                        }
+                    }
+                }
+            }
+        })
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let inspected = inspected.lock().unwrap();
+    assert_eq!(
+        *inspected,
+        [
+            ".text(file1) -> helper",
+            ".text(file1) -> __imp_ApiCall",
+            "__imp_ApiCall -> api",
+        ]
+    );
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+fn petgraph_export() {
+    use boflink::graph::GraphEdge;
+
+    let counts = Arc::new(Mutex::new((0, 0)));
+    let recorded = counts.clone();
+
+    setup_linker!("resolution_order.yaml", LinkerTargetArch::Amd64)
+        .inspect_graph(move |graph| {
+            let exported = graph.to_petgraph();
+            assert_eq!(exported.node_count(), graph.nodes().len());
+
+            let imports = exported
+                .edge_weights()
+                .filter(|edge| matches!(edge, GraphEdge::Import(_)))
+                .count();
+            *recorded.lock().unwrap() = (exported.edge_count(), imports);
+        })
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let (edges, imports) = *counts.lock().unwrap();
+    assert!(
+        edges > imports,
+        "relocations and definitions should be exported"
+    );
+    assert_eq!(imports, 1);
+}
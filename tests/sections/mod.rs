@@ -0,0 +1,731 @@
+use std::sync::{Arc, Mutex};
+
+use boflink::{
+    graph::{CharacteristicsPolicy, LinkLayout, LinkProfile, LnkInfoPolicy, StringEncoding},
+    linker::{AddedSection, LinkerTargetArch, OutputTrailer, error::LinkError},
+};
+use object::{
+    Object, ObjectSection, ObjectSymbol,
+    coff::CoffFile,
+    pe::{IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE},
+};
+
+use crate::{link_yaml, setup_linker};
+
+#[test]
+fn default_padding() {
+    let linked = link_yaml!("padding.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_data = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section")
+        .data()
+        .expect("Could not get .text section data");
+
+    assert!(
+        text_data[3..16].iter().all(|b| *b == 0x90),
+        ".text section should be padded with 0x90"
+    );
+
+    let data_data = coff
+        .section_by_name(".data")
+        .expect("Could not find .data section")
+        .data()
+        .expect("Could not get .data section data");
+
+    assert!(
+        data_data[3..8].iter().all(|b| *b == 0x00),
+        ".data section should be padded with 0x00"
+    );
+}
+
+#[test]
+fn custom_padding() {
+    let linked = setup_linker!("padding.yaml", LinkerTargetArch::Amd64)
+        .code_fill(0xcc)
+        .data_fill(0xaa)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_data = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section")
+        .data()
+        .expect("Could not get .text section data");
+
+    assert!(
+        text_data[3..16].iter().all(|b| *b == 0xcc),
+        ".text section should be padded with 0xcc"
+    );
+
+    let data_data = coff
+        .section_by_name(".data")
+        .expect("Could not find .data section")
+        .data()
+        .expect("Could not get .data section data");
+
+    assert!(
+        data_data[3..8].iter().all(|b| *b == 0xaa),
+        ".data section should be padded with 0xaa"
+    );
+}
+
+#[test]
+fn min_alignment() {
+    let linked = setup_linker!("padding.yaml", LinkerTargetArch::Amd64)
+        .min_section_alignment(64)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    for name in [".text", ".data"] {
+        let section = coff
+            .section_by_name(name)
+            .unwrap_or_else(|| panic!("Could not find {name} section"));
+
+        assert_eq!(
+            section.align(),
+            64,
+            "{name} section should have the minimum alignment applied"
+        );
+    }
+}
+
+#[test]
+fn output_alignment() {
+    let unpadded = link_yaml!("padding.yaml", LinkerTargetArch::Amd64);
+
+    let linked = setup_linker!("padding.yaml", LinkerTargetArch::Amd64)
+        .output_alignment(512)
+        .output_trailer(OutputTrailer::Data(b"manifest".to_vec()))
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    assert_eq!(
+        linked.len() % 512,
+        0,
+        "Output should be padded to 512 bytes"
+    );
+    assert!(
+        linked.ends_with(b"manifest"),
+        "Output should end with the trailer"
+    );
+    assert_eq!(
+        &linked[..unpadded.len()],
+        unpadded.as_slice(),
+        "COFF should not change"
+    );
+    assert!(
+        linked[unpadded.len()..linked.len() - 8]
+            .iter()
+            .all(|b| *b == 0),
+        "Padding should be before the trailer"
+    );
+
+    CoffFile::<_>::parse(linked.as_slice()).expect("Could not parse linked COFF");
+}
+
+#[test]
+fn section_checksums() {
+    let linked = setup_linker!("padding.yaml", LinkerTargetArch::Amd64)
+        .section_checksums(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let table_symbol = coff
+        .symbol_by_name("__section_checksums")
+        .expect("Could not find __section_checksums symbol");
+    let rdata = coff
+        .section_by_name(".rdata")
+        .expect("Could not find .rdata section");
+    assert_eq!(table_symbol.section_index(), Some(rdata.index()));
+
+    let mut rdata_data = rdata
+        .data()
+        .expect("Could not get .rdata section data")
+        .to_vec();
+    let table_start = table_symbol.address() as usize;
+    let table = rdata_data[table_start..].to_vec();
+
+    assert_eq!(&table[..4], b"CKSM");
+    assert_eq!(u16::from_le_bytes(table[4..6].try_into().unwrap()), 1);
+
+    let count = u16::from_le_bytes(table[6..8].try_into().unwrap()) as usize;
+    assert_eq!(count, coff.sections().count());
+
+    // The .rdata checksum is calculated with the table zeroed
+    let table_len = 8 + count * 12;
+    rdata_data[table_start..table_start + table_len].fill(0);
+
+    for entry in table[8..table_len].chunks(12) {
+        let value = |idx: usize| u32::from_le_bytes(entry[idx..idx + 4].try_into().unwrap());
+
+        let section = coff
+            .section_by_index(object::SectionIndex(value(0) as usize))
+            .expect("Could not get checksummed section");
+        let data = if section.index() == rdata.index() {
+            rdata_data.clone()
+        } else {
+            section.data().expect("Could not get section data").to_vec()
+        };
+
+        let mut hasher = jamcrc::Hasher::new();
+        hasher.update(&data);
+
+        assert_eq!(value(4) as usize, data.len());
+        assert_eq!(
+            value(8),
+            hasher.finalize(),
+            "{} checksum should match the section data",
+            section.name().unwrap()
+        );
+    }
+}
+
+#[test]
+fn default_alignment() {
+    let linked = link_yaml!("default_alignment.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    for (name, size) in [(".text", 17), (".rdata", 20)] {
+        let section = coff
+            .section_by_name(name)
+            .unwrap_or_else(|| panic!("Could not find {name} section"));
+
+        assert_eq!(
+            section.size(),
+            size,
+            "{name} input sections should be aligned to 16 bytes"
+        );
+        assert_eq!(section.align(), 16);
+    }
+}
+
+#[test]
+fn no_default_alignment() {
+    let linked = setup_linker!("default_alignment.yaml", LinkerTargetArch::Amd64)
+        .default_section_alignment(false)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    for (name, size) in [(".text", 5), (".rdata", 8)] {
+        let section = coff
+            .section_by_name(name)
+            .unwrap_or_else(|| panic!("Could not find {name} section"));
+
+        assert_eq!(
+            section.size(),
+            size,
+            "{name} input sections should keep the requested alignment"
+        );
+    }
+}
+
+#[test]
+fn pack_sections() {
+    let unpacked = link_yaml!("packing.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(unpacked.as_slice()).expect("Could not parse linked COFF");
+    let section = coff
+        .section_by_name(".data")
+        .expect("Could not find .data section");
+    assert_eq!(section.size(), 33);
+
+    let mut linker = setup_linker!("packing.yaml", LinkerTargetArch::Amd64)
+        .pack_sections(true)
+        .build();
+    let linked = linker.link().expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    let section = coff
+        .section_by_name(".data")
+        .expect("Could not find .data section");
+
+    let data = section.data().expect("Could not get .data section data");
+    assert_eq!(data.len(), 18, "input sections should not need padding");
+    assert_eq!(data[..16], [0x02; 16]);
+    assert_eq!(
+        data[16..],
+        [0x01, 0x03],
+        "input sections with the same alignment should keep their order"
+    );
+
+    let packed = linker.report().packed_sections();
+    assert_eq!(packed.len(), 1);
+    assert_eq!(packed[0].section, ".data");
+    assert_eq!(packed[0].padding_before, 15);
+    assert_eq!(packed[0].padding_after, 0);
+    assert_eq!(linker.report().packing_savings(), 15);
+}
+
+#[test]
+fn layout() {
+    let layout = LinkLayout::parse(
+        "section .data\n\
+         section .text\n\
+         input .text$mn 1 file1\n\
+         input .text$mn 0 file1\n\
+         symbol helper\n\
+         symbol go\n",
+    )
+    .expect("Could not parse layout");
+
+    let linked = setup_linker!("layout.yaml", LinkerTargetArch::Amd64)
+        .layout(layout)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let section_names = Vec::from_iter(coff.sections().map(|section| section.name().unwrap()));
+    assert_eq!(
+        section_names,
+        [".data", ".text"],
+        "Output sections should be ordered using the layout"
+    );
+
+    let text_data = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section")
+        .data()
+        .expect("Could not get .text section data");
+
+    assert_eq!(
+        &text_data[..2],
+        &[0xcc, 0xc3],
+        "Input sections should be ordered using the layout"
+    );
+    assert_eq!(text_data[16], 0xc3);
+
+    let symbol_names = Vec::from_iter(
+        coff.symbols()
+            .filter(|symbol| symbol.is_global())
+            .map(|symbol| symbol.name().unwrap()),
+    );
+    assert_eq!(
+        symbol_names,
+        ["helper", "go"],
+        "Symbols should be ordered using the layout"
+    );
+}
+
+#[test]
+fn profile_order() {
+    let profile = LinkProfile::parse("10 go\n500 hot\n").expect("Could not parse profile");
+
+    let linked = setup_linker!("profile.yaml", LinkerTargetArch::Amd64)
+        .profile(profile)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_data = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section")
+        .data()
+        .expect("Could not get .text section data");
+
+    assert_eq!(
+        [&text_data[..2], &text_data[16..17], &text_data[32..34]],
+        [&[0xcc, 0xc3][..], &[0xc3], &[0x90, 0xc3]],
+        "Code sections should be ordered by the profile hits"
+    );
+}
+
+#[test]
+fn split_cold() {
+    let profile = LinkProfile::parse("10 go\n500 hot\n").expect("Could not parse profile");
+
+    let linked = setup_linker!("profile.yaml", LinkerTargetArch::Amd64)
+        .profile(profile)
+        .split_cold(true)
+        .entrypoint("go")
+        .entry_first(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let section_names = Vec::from_iter(coff.sections().map(|section| section.name().unwrap()));
+    assert_eq!(section_names, [".text", ".text$cold"]);
+
+    let text_data = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section")
+        .data()
+        .expect("Could not get .text section data");
+
+    assert_eq!(
+        [&text_data[..1], &text_data[16..18]],
+        [&[0xc3][..], &[0xcc, 0xc3]],
+        "The entrypoint section should stay first"
+    );
+
+    let cold_data = coff
+        .section_by_name(".text$cold")
+        .expect("Could not find .text$cold section")
+        .data()
+        .expect("Could not get .text$cold section data");
+
+    assert_eq!(cold_data, [0x90, 0xc3]);
+
+    let cold = coff
+        .symbol_by_name("cold")
+        .expect("Could not find symbol 'cold'");
+    assert_eq!(
+        coff.section_by_index(cold.section_index().unwrap())
+            .unwrap()
+            .name()
+            .unwrap(),
+        ".text$cold"
+    );
+}
+
+#[test]
+fn entry_first() {
+    // The layout places .data first and the entrypoint section last
+    let layout = LinkLayout::parse(
+        "section .data\n\
+         section .text\n\
+         input .text$mn 0 file1\n\
+         input .text$mn 1 file1\n",
+    )
+    .expect("Could not parse layout");
+
+    let linked = setup_linker!("layout.yaml", LinkerTargetArch::Amd64)
+        .layout(layout)
+        .entrypoint("helper")
+        .entry_first(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let section_names = Vec::from_iter(coff.sections().map(|section| section.name().unwrap()));
+    assert_eq!(
+        section_names,
+        [".text", ".data"],
+        "Output section with the entrypoint should be first"
+    );
+
+    let text_data = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section")
+        .data()
+        .expect("Could not get .text section data");
+
+    assert_eq!(
+        &text_data[..2],
+        &[0xcc, 0xc3],
+        "Entrypoint section should be first in .text"
+    );
+
+    let helper = coff
+        .symbol_by_name("helper")
+        .expect("Could not find symbol 'helper'");
+    assert_eq!(helper.address(), 0, "Entrypoint should be at the start");
+}
+
+#[test]
+fn managed_code() {
+    let err = setup_linker!("managed.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("Linking managed code should fail");
+
+    let LinkError::Setup(setup_errors) = err else {
+        panic!("Expected a setup error for managed code, got: {err}");
+    };
+
+    let errors = setup_errors
+        .errors()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        errors.len(),
+        1,
+        "Only the managed code error should be reported"
+    );
+    assert!(
+        errors[0].contains("managed code"),
+        "Unexpected error: {}",
+        errors[0]
+    );
+}
+
+#[test]
+fn grouped_section_ordering() {
+    let linked = link_yaml!("grouped_ordering.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let crt_data = coff
+        .section_by_name(".CRT")
+        .expect("Could not find .CRT section")
+        .data()
+        .expect("Could not get .CRT section data");
+
+    // .CRT$XCU, .CRT$XCU$A, .CRT$XCU$B, .CRT$XCU#, .CRT$XCV
+    assert_eq!(crt_data, [0x01, 0x02, 0x03, 0x05, 0x04]);
+}
+
+#[test]
+fn merged_characteristics() {
+    let linked = link_yaml!("conflicting_flags.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let flags = coff
+        .section_by_name(".shared")
+        .expect("Could not find .shared section")
+        .coff_section()
+        .characteristics
+        .get(object::LittleEndian);
+
+    assert_eq!(
+        flags & (IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE),
+        IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE,
+        "Memory access flags should have been merged"
+    );
+}
+
+#[test]
+fn strict_characteristics() {
+    let err = setup_linker!("conflicting_flags.yaml", LinkerTargetArch::Amd64)
+        .characteristics_policy(CharacteristicsPolicy::Strict)
+        .build()
+        .link()
+        .expect_err("Linking sections with conflicting flags should fail");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("file1 (R--)") && message.contains("file2 (RW-)"),
+        "Error should name the conflicting COFFs: {message}"
+    );
+}
+
+/// Returns the names of the output sections and the `.comment` contents.
+fn lnk_info_output(policy: LnkInfoPolicy, emit_comment: bool) -> (Vec<String>, Vec<u8>) {
+    let linked = setup_linker!("lnk_info.yaml", LinkerTargetArch::Amd64)
+        .lnk_info_policy(policy)
+        .emit_comment(emit_comment)
+        .build()
+        .link()
+        .expect("Could not link files");
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let names = Vec::from_iter(
+        coff.sections()
+            .map(|section| section.name().unwrap().to_string()),
+    );
+    let comment = coff
+        .section_by_name(".comment")
+        .map(|section| section.data().unwrap().to_vec())
+        .unwrap_or_default();
+
+    (names, comment)
+}
+
+#[test]
+fn lnk_info_keep() {
+    let (names, comment) = lnk_info_output(LnkInfoPolicy::Keep, false);
+    assert_eq!(names, [".text", ".note", ".comment"]);
+    assert_eq!(comment, b"GCC\0");
+}
+
+#[test]
+fn lnk_info_drop() {
+    let (names, _) = lnk_info_output(LnkInfoPolicy::Drop, false);
+    assert_eq!(names, [".text"]);
+}
+
+#[test]
+fn lnk_info_comment() {
+    let (names, comment) = lnk_info_output(LnkInfoPolicy::Comment, false);
+    assert_eq!(names, [".text", ".comment"]);
+    assert_eq!(comment, b"GCC\0note\0");
+}
+
+#[test]
+fn emit_comment() {
+    let expected = format!("boflink {}\0file1\0file2\0", env!("CARGO_PKG_VERSION"));
+
+    // The linker comment is kept with the drop policy
+    let (names, comment) = lnk_info_output(LnkInfoPolicy::Drop, true);
+    assert_eq!(names, [".text", ".comment"]);
+    assert_eq!(comment, expected.as_bytes());
+}
+
+#[test]
+fn interned_section_names() {
+    let names = Arc::new(Mutex::new(Vec::new()));
+    let recorded = names.clone();
+
+    setup_linker!("conflicting_flags.yaml", LinkerTargetArch::Amd64)
+        .inspect_graph(move |graph| {
+            recorded.lock().unwrap().extend(
+                graph
+                    .sections()
+                    .map(|section| section.name().as_str().as_ptr() as usize),
+            );
+        })
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    // Both .shared sections use the name from the first COFF
+    let names = names.lock().unwrap();
+    assert_eq!(names.len(), 2);
+    assert_eq!(names[0], names[1]);
+}
+
+#[test]
+fn output_strings() {
+    let mut linker = setup_linker!("strings.yaml", LinkerTargetArch::Amd64)
+        .collect_strings(true)
+        .build();
+    linker.link().expect("Could not link files");
+
+    let strings = Vec::from_iter(linker.report().strings().iter().map(|string| {
+        (
+            string.section.as_str(),
+            string.offset,
+            string.coff.as_str(),
+            string.input_section.as_str(),
+            string.encoding,
+            string.value.as_str(),
+        )
+    }));
+
+    // Strings in code sections and shorter than 4 characters are not reported
+    assert_eq!(
+        strings,
+        [
+            (
+                ".rdata",
+                0,
+                "file1",
+                ".rdata",
+                StringEncoding::Ascii,
+                "beacon.dll"
+            ),
+            (
+                ".rdata",
+                16,
+                "file2",
+                ".rdata",
+                StringEncoding::Utf16,
+                "pipe"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn strip_resources() {
+    let linked = link_yaml!("resources.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(
+        coff.section_by_name(".rsrc").is_some(),
+        ".rsrc section should be kept by default"
+    );
+
+    let linked = setup_linker!("resources.yaml", LinkerTargetArch::Amd64)
+        .strip_resources(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(
+        coff.section_by_name(".rsrc").is_none(),
+        ".rsrc section should be discarded"
+    );
+    assert!(coff.section_by_name(".text").is_some());
+}
+
+#[test]
+fn ident() {
+    let linked = link_yaml!("resources.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(
+        coff.section_by_name(".ident").is_none(),
+        ".ident section should not be added by default"
+    );
+
+    // The .ident section is kept when the input sections are stripped
+    let linked = setup_linker!("resources.yaml", LinkerTargetArch::Amd64)
+        .ident("build 42")
+        .strip_resources(true)
+        .lnk_info_policy(LnkInfoPolicy::Drop)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    assert!(coff.section_by_name(".rsrc").is_none());
+
+    let ident = coff
+        .section_by_name(".ident")
+        .expect("Could not find .ident section")
+        .data()
+        .expect("Could not get .ident section data");
+
+    assert_eq!(
+        ident,
+        format!("boflink {}\0build 42\0", env!("CARGO_PKG_VERSION")).as_bytes()
+    );
+}
+
+#[test]
+fn added_section() {
+    let linked = setup_linker!("added_section.yaml", LinkerTargetArch::Amd64)
+        .add_section(
+            AddedSection::new(".rdata$cfg", b"config".as_slice())
+                .alignment(16)
+                .symbol("g_cfg"),
+        )
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let rdata = coff
+        .section_by_name(".rdata")
+        .expect("Could not find .rdata section");
+    assert_eq!(rdata.data().unwrap(), b"config");
+    assert_eq!(rdata.align(), 16);
+
+    let symbol = coff
+        .symbol_by_name("g_cfg")
+        .expect("Could not find g_cfg symbol");
+    assert_eq!(symbol.section_index(), Some(rdata.index()));
+
+    let err = setup_linker!("added_section.yaml", LinkerTargetArch::Amd64)
+        .add_section(AddedSection::new(".rdata$cfg", b"config".as_slice()).symbol("go"))
+        .build()
+        .link()
+        .expect_err("Defining an existing symbol should fail");
+    assert!(matches!(err, LinkError::Symbol(_)));
+}
@@ -0,0 +1,31 @@
+use boflink::linker::LinkerTargetArch;
+use boflink_testutils::assert_snapshot;
+
+use crate::link_yaml;
+
+#[test]
+fn msvc_amd64_empty() {
+    let linked = link_yaml!(
+        "../compilers/msvc/amd64_empty.yaml",
+        LinkerTargetArch::Amd64
+    );
+    assert_snapshot!("msvc_amd64_empty", linked);
+}
+
+#[test]
+fn mingw_i386_empty() {
+    let linked = link_yaml!("../compilers/mingw/i386_empty.yaml", LinkerTargetArch::I386);
+    assert_snapshot!("mingw_i386_empty", linked);
+}
+
+#[test]
+fn section_padding() {
+    let linked = link_yaml!("../sections/padding.yaml", LinkerTargetArch::Amd64);
+    assert_snapshot!("section_padding", linked);
+}
+
+#[test]
+fn import_thunks() {
+    let linked = link_yaml!("../imports/import_thunks.yaml", LinkerTargetArch::Amd64);
+    assert_snapshot!("import_thunks", linked);
+}
@@ -0,0 +1,680 @@
+use boflink::{
+    graph::{IntrinsicGroup, SymbolRenameMode},
+    linker::{LinkerBuilder, LinkerTargetArch, error::LinkError},
+    pathed_item::PathedItem,
+};
+use object::{
+    LittleEndian, Object, ObjectSection, ObjectSymbol,
+    coff::{CoffFile, ImageSymbol},
+    pe::ImageAuxSymbolFunctionBeginEnd,
+};
+
+use boflink_testutils::{archive_searcher::MemoryArchiveSearcher, build::YamlInput};
+
+use crate::{link_yaml, setup_linker};
+
+/// Links the COFF YAML inputs in the specified order with deterministic
+/// ordering enabled.
+fn link_deterministic(inputs: &[(&str, &str)]) -> Vec<u8> {
+    let coffs = inputs.iter().map(|(name, doc)| {
+        let YamlInput::Coff(coff) = serde_yml::from_str(doc).unwrap() else {
+            panic!("input should be a COFF");
+        };
+
+        PathedItem::new(name.into(), coff.build().unwrap())
+    });
+
+    LinkerBuilder::new()
+        .architecture(LinkerTargetArch::Amd64)
+        .library_searcher(MemoryArchiveSearcher::new())
+        .deterministic(true)
+        .add_inputs(coffs)
+        .build()
+        .link()
+        .expect("Could not link files")
+}
+
+#[test]
+fn deterministic_local_order() {
+    // Local symbols with the same name are ordered by the COFF path
+    let input = include_str!("deterministic_local.yaml");
+    let other = input.replace("'C3C3'", "'C390'");
+    let linked = link_deterministic(&[("file2", input), ("file1", &other)]);
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    let helpers = coff
+        .symbols()
+        .filter(|symbol| symbol.name() == Ok("helper"))
+        .map(|symbol| symbol.address())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        helpers,
+        [0x11, 0x1],
+        "'helper' from file1 should be listed before 'helper' from file2"
+    );
+}
+
+#[test]
+fn renamed() {
+    let linked = setup_linker!("renamed.yaml", LinkerTargetArch::Amd64)
+        .entrypoint("go")
+        .rename_symbols(SymbolRenameMode::Short)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    for name in ["go", "__imp_BeaconPrintf"] {
+        assert!(
+            coff.symbol_by_name(name).is_some(),
+            "symbol '{name}' should not have been renamed"
+        );
+    }
+
+    for name in ["internal_function", "static_function"] {
+        assert!(
+            coff.symbol_by_name(name).is_none(),
+            "symbol '{name}' should have been renamed"
+        );
+    }
+
+    let renamed_count = coff
+        .symbols()
+        .filter(|symbol| symbol.name().is_ok_and(|name| name.starts_with('_')))
+        .filter(|symbol| symbol.is_definition())
+        .count();
+
+    assert_eq!(
+        renamed_count, 2,
+        "output COFF should contain 2 renamed symbol definitions"
+    );
+}
+
+#[test]
+fn renamed_local_collision() {
+    // The kept local symbol '_0' uses the first generated name
+    let linked = setup_linker!("renamed_local.yaml", LinkerTargetArch::Amd64)
+        .entrypoint("go")
+        .rename_symbols(SymbolRenameMode::Short)
+        .keep_symbol("_0")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let names = coff
+        .symbols()
+        .filter(|symbol| symbol.is_definition())
+        .filter_map(|symbol| symbol.name().ok())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        names.iter().filter(|name| **name == "_0").count(),
+        1,
+        "renamed symbol should not reuse the local symbol name '_0': {names:?}"
+    );
+    assert!(
+        !names.contains(&"internal_function"),
+        "symbol 'internal_function' should have been renamed"
+    );
+}
+
+#[test]
+fn symbol_map_write_error() {
+    let map_path = std::env::temp_dir()
+        .join("boflink-missing-directory")
+        .join("symbols.map");
+
+    let err = setup_linker!("renamed.yaml", LinkerTargetArch::Amd64)
+        .entrypoint("go")
+        .rename_symbols(SymbolRenameMode::Short)
+        .symbol_map_path(&map_path)
+        .build()
+        .link()
+        .expect_err("Linking should fail if the symbol map cannot be written");
+
+    assert!(
+        matches!(&err, LinkError::Write { path, .. } if *path == map_path),
+        "Unexpected error: {err}"
+    );
+}
+
+#[test]
+fn keep_symbols() {
+    let linked = setup_linker!("keep.yaml", LinkerTargetArch::Amd64)
+        .entrypoint("go")
+        .rename_symbols(SymbolRenameMode::Short)
+        .keep_symbol("resource_*")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    // static_resources is kept by the /KEEP directive in the input
+    for name in ["go", "resource_table", "static_resources"] {
+        assert!(
+            coff.symbol_by_name(name).is_some(),
+            "symbol '{name}' should have been kept"
+        );
+    }
+
+    assert!(
+        coff.symbol_by_name("internal_function").is_none(),
+        "symbol 'internal_function' should have been renamed"
+    );
+}
+
+#[test]
+fn redirect() {
+    let linked = setup_linker!("redirect.yaml", LinkerTargetArch::Amd64)
+        .redirect_symbol("printf", "BeaconPrintf")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+
+    let text_data = text_section
+        .data()
+        .expect("Could not get .text section data");
+
+    let thunk = coff
+        .symbol_by_name("BeaconPrintf")
+        .expect("Could not find BeaconPrintf symbol");
+
+    // The calls are within .text so the relocations are applied directly
+    for call in [1usize, 6] {
+        let displacement = i32::from_le_bytes(text_data[call..call + 4].try_into().unwrap());
+        assert_eq!(
+            (call as i64 + 4 + displacement as i64) as u64,
+            thunk.address(),
+            "call at {call:#x} should have been redirected to BeaconPrintf"
+        );
+    }
+
+    assert!(
+        coff.symbol_by_name("__imp_BeaconPrintf").is_some(),
+        "BeaconPrintf should have been imported from the API"
+    );
+
+    let err = setup_linker!("redirect.yaml", LinkerTargetArch::Amd64)
+        .redirect_symbol("missing", "go")
+        .build()
+        .link()
+        .expect_err("Redirecting a symbol which is not in the link should fail");
+
+    assert_eq!(
+        err.to_string(),
+        "cannot redirect symbol 'missing'. symbol is not in the link"
+    );
+}
+
+#[test]
+fn synthesized_intrinsics() {
+    setup_linker!("intrinsics.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("___chkstk_ms should be undefined without synthesized intrinsics");
+
+    let linked = setup_linker!("intrinsics.yaml", LinkerTargetArch::Amd64)
+        .intrinsic_group(IntrinsicGroup::StackProbe)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+
+    let chkstk = coff
+        .symbol_by_name("___chkstk_ms")
+        .expect("Could not find ___chkstk_ms symbol");
+
+    assert_eq!(chkstk.section_index(), Some(text_section.index()));
+    assert_eq!(chkstk.address(), 16, "___chkstk_ms should be after go");
+
+    let text_data = text_section
+        .data()
+        .expect("Could not get .text section data");
+
+    assert_eq!(
+        text_data[16..18],
+        [0x51, 0x50],
+        "___chkstk_ms should start by saving rcx and rax"
+    );
+
+    let displacement = i32::from_le_bytes(text_data[6..10].try_into().unwrap());
+    assert_eq!(10 + displacement, 16, "call should target ___chkstk_ms");
+}
+
+#[test]
+fn fltused() {
+    let err = setup_linker!("fltused.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("_fltused should be undefined without synthesized intrinsics");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("undefined symbol: _fltused") && message.contains("--intrinsics=fltused"),
+        "_fltused error should include the guidance: {message}"
+    );
+
+    let linked = setup_linker!("fltused.yaml", LinkerTargetArch::Amd64)
+        .intrinsic_group(IntrinsicGroup::Fltused)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let rdata_section = coff
+        .section_by_name(".rdata")
+        .expect("Could not find .rdata section");
+
+    let fltused = coff
+        .symbol_by_name("_fltused")
+        .expect("Could not find _fltused symbol");
+
+    assert_eq!(fltused.section_index(), Some(rdata_section.index()));
+}
+
+#[test]
+fn synthesized_i386_math_intrinsics() {
+    setup_linker!("intrinsics_i386.yaml", LinkerTargetArch::I386)
+        .intrinsic_group(IntrinsicGroup::StackProbe)
+        .build()
+        .link()
+        .expect_err("__alldiv should not be a stack probe intrinsic");
+
+    let linked = setup_linker!("intrinsics_i386.yaml", LinkerTargetArch::I386)
+        .intrinsic_group(IntrinsicGroup::I386Math)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+
+    let text_data = text_section
+        .data()
+        .expect("Could not get .text section data");
+
+    // Both helpers should be defined in the single copy of the math helpers
+    // placed after go
+    assert_eq!(text_data.len(), 12 + 465);
+    assert!(
+        coff.symbol_by_name("__allmul").is_none(),
+        "unreferenced helpers should not be defined"
+    );
+
+    for (name, offset, call_site) in [("__alldiv", 0xef, 1), ("___udivdi3", 0x18f, 6)] {
+        let symbol = coff
+            .symbol_by_name(name)
+            .unwrap_or_else(|| panic!("Could not find {name} symbol"));
+
+        assert_eq!(symbol.section_index(), Some(text_section.index()));
+        assert_eq!(symbol.address(), 12 + offset, "{name} address");
+
+        let displacement =
+            i32::from_le_bytes(text_data[call_site..call_site + 4].try_into().unwrap());
+        assert_eq!(
+            call_site as i64 + 4 + displacement as i64,
+            symbol.address() as i64,
+            "call should target {name}"
+        );
+    }
+
+    // The libgcc wrapper pushes the 4 arguments and calls __aulldiv
+    let wrapper_call = 12 + 0x18f + 16;
+    assert_eq!(text_data[wrapper_call], 0xe8, "___udivdi3 should call");
+    let displacement = i32::from_le_bytes(
+        text_data[wrapper_call + 1..wrapper_call + 5]
+            .try_into()
+            .unwrap(),
+    );
+    assert_eq!(
+        wrapper_call as i64 + 5 + displacement as i64,
+        12 + 0x55,
+        "___udivdi3 should call __aulldiv"
+    );
+}
+
+#[test]
+fn deterministic_order() {
+    const INPUT_A: (&str, &str) = ("file1", include_str!("deterministic_a.yaml"));
+    const INPUT_B: (&str, &str) = ("file2", include_str!("deterministic_b.yaml"));
+
+    let linked = link_deterministic(&[INPUT_A, INPUT_B]);
+    let shuffled = link_deterministic(&[INPUT_B, INPUT_A]);
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    let symbol_names = coff
+        .symbols()
+        .filter_map(|symbol| symbol.name().ok())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        symbol_names,
+        [
+            ".text",
+            "BeaconOutput",
+            "BeaconPrintf",
+            "alpha",
+            "beta",
+            "go",
+            "zeta",
+            "__imp_BeaconOutput",
+            "__imp_BeaconPrintf"
+        ],
+        "output symbols should be ordered by name"
+    );
+
+    assert!(
+        linked == shuffled,
+        "linking the inputs in a different order should produce the same output"
+    );
+}
+
+#[test]
+fn absolute_symbols() {
+    let linked = setup_linker!("absolute.yaml", LinkerTargetArch::Amd64)
+        .keep_absolute_symbol("@feat.00")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let feat_symbols = coff
+        .symbols()
+        .filter(|symbol| symbol.name() == Ok("@feat.00"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        feat_symbols.len(),
+        1,
+        "@feat.00 symbols should have been merged into one symbol"
+    );
+
+    let feat_symbol = &feat_symbols[0];
+    assert_eq!(feat_symbol.section(), object::SymbolSection::Absolute);
+    assert_eq!(
+        feat_symbol.coff_symbol().value.get(object::LittleEndian),
+        0x110,
+        "@feat.00 values should have been merged"
+    );
+
+    assert!(
+        coff.symbol_by_name("@comp.id").is_none(),
+        "@comp.id should not be kept in the output"
+    );
+}
+
+#[test]
+fn safeseh_incompatible() {
+    let err = setup_linker!("safeseh.yaml", LinkerTargetArch::I386)
+        .safeseh(true)
+        .build()
+        .link()
+        .expect_err("Linking inputs without safe exception handlers should fail");
+
+    let message = err.to_string();
+    assert!(
+        message.contains(">>> file2") && !message.contains(">>> file1"),
+        "Only the incompatible input should be reported: {message}"
+    );
+}
+
+#[test]
+fn safeseh_compatible() {
+    let linked = setup_linker!("safeseh_compatible.yaml", LinkerTargetArch::I386)
+        .safeseh(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let feat_symbol = coff
+        .symbol_by_name("@feat.00")
+        .expect("@feat.00 should be written to the output");
+
+    assert_eq!(
+        feat_symbol.coff_symbol().value.get(object::LittleEndian),
+        0x11,
+        "@feat.00 values should have been merged"
+    );
+}
+
+#[test]
+fn safeseh_import_member() {
+    // Import library members do not have '@feat.00' symbols
+    setup_linker!("safeseh_import_descriptor.yaml", LinkerTargetArch::I386)
+        .safeseh(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+}
+
+#[test]
+fn feat_safeseh_merge() {
+    let linked = setup_linker!("safeseh.yaml", LinkerTargetArch::I386)
+        .keep_absolute_symbol("@feat.00")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let feat_symbol = coff
+        .symbol_by_name("@feat.00")
+        .expect("@feat.00 should be written to the output");
+
+    assert_eq!(
+        feat_symbol.coff_symbol().value.get(object::LittleEndian),
+        0x10,
+        "SAFESEH flag should only be set if all inputs have it"
+    );
+}
+
+#[test]
+fn duplicate_input() {
+    let mut linker = setup_linker!("duplicate_input.yaml", LinkerTargetArch::Amd64).build();
+    linker
+        .link()
+        .expect_err("Duplicate inputs should have duplicate symbols");
+
+    let mut linker = setup_linker!("duplicate_input.yaml", LinkerTargetArch::Amd64)
+        .dedup_inputs(true)
+        .build();
+    linker.link().expect("Could not link files");
+
+    assert_eq!(
+        Vec::from_iter(linker.report().duplicate_inputs()),
+        [(std::path::Path::new("file2"), None)]
+    );
+}
+
+#[test]
+fn lazy_inputs() {
+    const INPUT: &str = include_str!("lazy_inputs.yaml");
+
+    let mut coffs = serde_yml::Deserializer::from_str(INPUT)
+        .enumerate()
+        .map(|(idx, document)| {
+            let YamlInput::Coff(coff) = serde::Deserialize::deserialize(document).unwrap() else {
+                panic!("input should be a COFF");
+            };
+
+            PathedItem::new(format!("file{}", idx + 1).into(), coff.build().unwrap())
+        });
+
+    let input = coffs.next().unwrap();
+
+    let linked = LinkerBuilder::new()
+        .architecture(LinkerTargetArch::Amd64)
+        .library_searcher(MemoryArchiveSearcher::new())
+        .add_input(input)
+        .add_lazy_inputs(coffs)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(
+        coff.symbol_by_name("resolved")
+            .is_some_and(|symbol| symbol.is_definition()),
+        "Lazy input defining 'resolved' should be linked"
+    );
+    assert!(
+        coff.symbol_by_name("unused").is_none(),
+        "Lazy input defining 'unused' should not be linked"
+    );
+}
+
+#[test]
+fn function_aux() {
+    let linked = setup_linker!("function_aux.yaml", LinkerTargetArch::Amd64)
+        .function_aux(true)
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+    let symbol_table = coff.coff_symbol_table();
+
+    let symbol_index = |name: &str, address: u32| {
+        coff.symbols()
+            .find(|symbol| symbol.name() == Ok(name) && symbol.coff_symbol().value() == address)
+            .unwrap_or_else(|| panic!("Could not find symbol '{name}' at {address:#x}"))
+            .index()
+    };
+
+    let go = symbol_index("go", 0);
+    let helper = symbol_index("helper", 16);
+    let go_begin = symbol_index(".bf", 0);
+    let helper_begin = symbol_index(".bf", 16);
+    let helper_end = symbol_index(".ef", 17);
+
+    let functions = [go, helper].map(|index| {
+        let aux = symbol_table.aux_function(index).unwrap();
+        (
+            aux.tag_index.get(LittleEndian),
+            aux.total_size.get(LittleEndian),
+            aux.pointer_to_next_function.get(LittleEndian),
+        )
+    });
+    assert_eq!(
+        functions,
+        [
+            (go_begin.0 as u32, 6, helper.0 as u32),
+            (helper_begin.0 as u32, 2, 0)
+        ],
+        "Function definition records should point to the output symbols"
+    );
+
+    let begin_end = [go_begin, helper_begin, helper_end].map(|index| {
+        let aux: &ImageAuxSymbolFunctionBeginEnd = symbol_table.get(index, 1).unwrap();
+        (
+            aux.linenumber.get(LittleEndian),
+            aux.pointer_to_next_function.get(LittleEndian),
+        )
+    });
+    assert_eq!(
+        begin_end,
+        [(3, helper_begin.0 as u32), (10, 0), (11, 0)],
+        ".bf symbols should be chained to the next .bf symbol"
+    );
+}
+
+#[test]
+fn function_aux_disabled() {
+    let linked = link_yaml!("function_aux.yaml", LinkerTargetArch::Amd64);
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    assert!(
+        coff.symbols()
+            .filter(|symbol| symbol.name() != Ok(".text"))
+            .all(|symbol| symbol.coff_symbol().number_of_aux_symbols() == 0),
+        "Auxiliary function records should not be written by default"
+    );
+}
+
+#[test]
+fn loader_externs() {
+    setup_linker!("loader_externs.yaml", LinkerTargetArch::Amd64)
+        .build()
+        .link()
+        .expect_err("g_state should be undefined without the loader extern");
+
+    let linked = setup_linker!("loader_externs.yaml", LinkerTargetArch::Amd64)
+        .loader_extern("g_state")
+        .loader_extern("g_unused")
+        .build()
+        .link()
+        .expect("Could not link files");
+
+    let coff: CoffFile = CoffFile::parse(linked.as_slice()).expect("Could not parse linked COFF");
+
+    let g_state = coff
+        .symbol_by_name("g_state")
+        .expect("Could not find g_state symbol");
+    assert!(g_state.is_undefined() && g_state.is_global());
+    assert!(
+        coff.symbol_by_name("g_unused").is_none(),
+        "unreferenced loader externs should not be in the output"
+    );
+
+    let text_section = coff
+        .section_by_name(".text")
+        .expect("Could not find .text section");
+    let relocations = Vec::from_iter(text_section.relocations());
+    assert_eq!(relocations.len(), 1);
+    assert_eq!(
+        relocations[0].1.target(),
+        object::RelocationTarget::Symbol(g_state.index())
+    );
+}
+
+#[test]
+fn symbol_sizes() {
+    let mut linker = setup_linker!("symbol_sizes.yaml", LinkerTargetArch::Amd64)
+        .collect_symbol_sizes(true)
+        .build();
+    linker.link().expect("Could not link files");
+
+    let sizes = Vec::from_iter(linker.report().symbol_sizes().iter().map(|symbol| {
+        (
+            symbol.name.as_str(),
+            symbol.section.as_str(),
+            symbol.address,
+            symbol.size,
+            symbol.code,
+        )
+    }));
+
+    // Section symbols are not reported
+    assert_eq!(
+        sizes,
+        [
+            ("go", ".text", 0, 16, true),
+            ("helper", ".text", 16, 8, true),
+            ("g_value", ".data", 4, 4, false),
+        ]
+    );
+}
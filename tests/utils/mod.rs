@@ -1,3 +0,0 @@
-pub mod archive_searcher;
-pub mod build;
-pub mod macros;
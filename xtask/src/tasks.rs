@@ -29,11 +29,21 @@ pub const TASKLIST: &[Task] = &[
         help: "Run tests",
         run: test,
     },
+    Task {
+        name: "snapshots",
+        help: "Regenerate the golden COFF snapshots",
+        run: snapshots,
+    },
     Task {
         name: "checkfmt",
         help: "Check formatting",
         run: checkfmt,
     },
+    Task {
+        name: "checkwasm",
+        help: "Check the library and wasm-link example for wasm32-wasip1",
+        run: checkwasm,
+    },
     Task {
         name: "ci",
         help: "Run ci workflow",
@@ -123,15 +133,46 @@ pub fn test() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+pub fn snapshots() -> Result<(), Box<dyn Error>> {
+    utils::shell::run_cargo_env(
+        "BOFLINK_UPDATE_SNAPSHOTS",
+        "1",
+        [
+            "test",
+            "-p",
+            "boflink",
+            "--test",
+            "integration_tests",
+            "snapshots::",
+        ],
+    )?;
+    Ok(())
+}
+
 pub fn checkfmt() -> Result<(), Box<dyn Error>> {
     utils::shell::run_cargo(["fmt", "--all", "--check"])?;
     Ok(())
 }
 
+pub fn checkwasm() -> Result<(), Box<dyn Error>> {
+    utils::shell::run_cargo([
+        "check",
+        "-p",
+        "boflink",
+        "--lib",
+        "--example",
+        "wasm-link",
+        "--target",
+        "wasm32-wasip1",
+    ])?;
+    Ok(())
+}
+
 pub fn ci() -> Result<(), Box<dyn Error>> {
     checkfmt()?;
     lint()?;
     test()?;
+    checkwasm()?;
     Ok(())
 }
 
@@ -82,3 +82,30 @@ pub fn run_echo_projdir<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
 pub fn run_cargo<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(args: I) -> std::io::Result<()> {
     run_echo_projdir(cargo(), args)
 }
+
+/// Runs cargo in the project directory with the environment variable set.
+pub fn run_cargo_env<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
+    key: &str,
+    value: &str,
+    args: I,
+) -> std::io::Result<()> {
+    let args = args.into_iter().collect::<Vec<_>>();
+
+    print!("{key}={value} cargo");
+    for arg in &args {
+        print!(" {}", arg.as_ref().to_string_lossy());
+    }
+    println!();
+
+    let status = Command::new(cargo())
+        .current_dir(project_root()?)
+        .env(key, value)
+        .args(args)
+        .status()?;
+
+    if !status.success() {
+        Err(std::io::Error::other("command returned non-zero exit code"))
+    } else {
+        Ok(())
+    }
+}